@@ -0,0 +1,57 @@
+//! GitHub Actions annotations
+//!
+//! A workflow command such as `::warning file=src/lib.rs,line=12::message`
+//! (or `::error`, `::notice`) printed on stdout during a GitHub Actions job shows
+//! up as an inline annotation on the pull request. `remove-debug` and `validate-docs` emit them with
+//! `--format github`, the default when `GITHUB_ACTIONS=true`.
+//!
+//! Per the workflow-command spec, `%`, `\r`, and `\n` are percent-encoded in the
+//! message, and additionally `:` and `,` in property values like the file path.
+
+use std::path::Path;
+
+/// A `::warning` command for `line` (1-based) of `file`
+pub fn warning(file: &Path, line: usize, message: &str) -> String {
+    command("warning", file, line, message)
+}
+
+/// An `::error` command for `line` (1-based) of `file`
+pub fn error(file: &Path, line: usize, message: &str) -> String {
+    command("error", file, line, message)
+}
+
+/// A `::notice` command for `line` (1-based) of `file`
+pub fn notice(file: &Path, line: usize, message: &str) -> String {
+    command("notice", file, line, message)
+}
+
+fn command(name: &str, file: &Path, line: usize, message: &str) -> String {
+    format!(
+        "::{} file={},line={}::{}",
+        name,
+        escape_property(&file.to_string_lossy().replace('\\', "/")),
+        line,
+        escape_data(message)
+    )
+}
+
+fn escape_data(value: &str) -> String {
+    value.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values_are_escaped() {
+        assert_eq!(
+            warning(Path::new("src/a,b:c.rs"), 3, "100% done\r\nnext: line"),
+            "::warning file=src/a%2Cb%3Ac.rs,line=3::100%25 done%0D%0Anext: line"
+        );
+    }
+}
@@ -0,0 +1,265 @@
+//! File backups for commands that rewrite source files
+//!
+//! Before `remove-debug` modifies a file, the original is copied to
+//! `.autodebugger/backups/<run>/<relative-path>`, where `<run>` is a UTC timestamp
+//! shared by everything modified in one invocation. Each run directory holds a
+//! `manifest.json` recording, per file, the original location and checksums of
+//! both the original and the rewritten content.
+//!
+//! Restoring a run checks that each backup still matches its recorded checksum,
+//! and refuses to overwrite a file that was edited again after the rewrite (its
+//! content matches neither checksum). Only the newest runs are kept; older ones
+//! are pruned after each run.
+//!
+//! Checksums are 64-bit FNV-1a, which is stable across Rust versions and enough to
+//! detect accidental changes.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::monitor::cache::CACHE_DIR;
+
+const MANIFEST: &str = "manifest.json";
+
+/// One backed-up file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupEntry {
+    /// Absolute path of the file that was modified
+    pub original: PathBuf,
+    /// Location of the copy, relative to the run directory
+    pub backup: PathBuf,
+    pub original_checksum: String,
+    /// Checksum of the content written in place of the original
+    pub modified_checksum: String,
+}
+
+/// Files backed up in one run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub run: String,
+    pub files: Vec<BackupEntry>,
+}
+
+/// Outcome of restoring a run
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    pub run: String,
+    pub restored: Vec<PathBuf>,
+    /// Files left alone, with the reason
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+/// Backup runs under `<base>/.autodebugger/backups`
+#[derive(Debug, Clone)]
+pub struct BackupStore {
+    base: PathBuf,
+    root: PathBuf,
+}
+
+impl BackupStore {
+    /// Store for a workspace; paths of backed-up files are recorded relative to `base`
+    pub fn new(base: &Path) -> Self {
+        let base = base.canonicalize().unwrap_or_else(|_| base.to_path_buf());
+        let root = base.join(CACHE_DIR).join("backups");
+        Self { base, root }
+    }
+
+    /// A fresh run id: the current UTC time, sortable as a string
+    pub fn new_run_id() -> String {
+        chrono::Utc::now().format("%Y%m%dT%H%M%S%3fZ").to_string()
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Copy `original` content of `file` into the run and record it in the manifest
+    pub fn backup(&self, run: &str, file: &Path, original: &str, modified: &str) -> Result<()> {
+        let file = file.canonicalize()
+            .with_context(|| format!("Failed to resolve {}", file.display()))?;
+        let relative = match file.strip_prefix(&self.base) {
+            Ok(relative) => relative.to_path_buf(),
+            // Outside the workspace: keep the full path below the run directory
+            Err(_) => file.components()
+                .filter(|c| matches!(c, Component::Normal(_)))
+                .collect(),
+        };
+
+        let run_dir = self.root.join(run);
+        let target = run_dir.join(&relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&target, original)
+            .with_context(|| format!("Failed to write backup {}", target.display()))?;
+
+        let mut manifest = self.manifest(run).unwrap_or_else(|_| BackupManifest {
+            run: run.to_string(),
+            files: Vec::new(),
+        });
+        manifest.files.retain(|entry| entry.original != file);
+        manifest.files.push(BackupEntry {
+            original: file,
+            backup: relative,
+            original_checksum: checksum(original.as_bytes()),
+            modified_checksum: checksum(modified.as_bytes()),
+        });
+        self.ensure_gitignore()?;
+        fs::write(run_dir.join(MANIFEST), serde_json::to_string_pretty(&manifest)?)
+            .with_context(|| format!("Failed to write manifest for backup run {}", run))?;
+        Ok(())
+    }
+
+    /// Run ids, oldest first
+    pub fn runs(&self) -> Result<Vec<String>> {
+        if !self.root.exists() {
+            return Ok(Vec::new());
+        }
+        let mut runs: Vec<String> = fs::read_dir(&self.root)
+            .with_context(|| format!("Failed to list {}", self.root.display()))?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().join(MANIFEST).is_file())
+            .filter_map(|e| e.file_name().to_str().map(str::to_string))
+            .collect();
+        runs.sort();
+        Ok(runs)
+    }
+
+    pub fn manifest(&self, run: &str) -> Result<BackupManifest> {
+        let path = self.root.join(run).join(MANIFEST);
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("No backup run {}", run))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid manifest {}", path.display()))
+    }
+
+    /// Put the files of a run (the latest if None) back in place
+    pub fn restore(&self, run: Option<&str>) -> Result<RestoreReport> {
+        let runs = self.runs()?;
+        let run = match run {
+            Some(run) if runs.iter().any(|r| r == run) => run.to_string(),
+            Some(run) => anyhow::bail!("No backup run {} (available: {})", run, available(&runs)),
+            None => runs.last().cloned().context("No backups to restore")?,
+        };
+
+        let manifest = self.manifest(&run)?;
+        let mut report = RestoreReport { run: run.clone(), ..RestoreReport::default() };
+        for entry in manifest.files {
+            let backup_path = self.root.join(&run).join(&entry.backup);
+            let Ok(backup) = fs::read(&backup_path) else {
+                report.skipped.push((entry.original, "backup file is missing".to_string()));
+                continue;
+            };
+            if checksum(&backup) != entry.original_checksum {
+                report.skipped.push((entry.original, "backup checksum does not match the manifest".to_string()));
+                continue;
+            }
+            if let Ok(current) = fs::read(&entry.original) {
+                let current = checksum(&current);
+                if current != entry.modified_checksum && current != entry.original_checksum {
+                    report.skipped.push((entry.original, "file changed after the backup was taken".to_string()));
+                    continue;
+                }
+            }
+            fs::write(&entry.original, &backup)
+                .with_context(|| format!("Failed to restore {}", entry.original.display()))?;
+            report.restored.push(entry.original);
+        }
+        Ok(report)
+    }
+
+    /// Delete all but the newest `keep` runs; returns how many were removed
+    pub fn prune(&self, keep: usize) -> Result<usize> {
+        let runs = self.runs()?;
+        let excess = runs.len().saturating_sub(keep);
+        for run in &runs[..excess] {
+            let dir = self.root.join(run);
+            fs::remove_dir_all(&dir)
+                .with_context(|| format!("Failed to remove {}", dir.display()))?;
+        }
+        Ok(excess)
+    }
+
+    /// Keep backups out of `git status`
+    fn ensure_gitignore(&self) -> Result<()> {
+        let dir = self.base.join(CACHE_DIR);
+        let gitignore = dir.join(".gitignore");
+        if !gitignore.exists() {
+            fs::create_dir_all(&dir)?;
+            fs::write(&gitignore, "*\n")
+                .with_context(|| format!("Failed to write {}", gitignore.display()))?;
+        }
+        Ok(())
+    }
+}
+
+fn available(runs: &[String]) -> String {
+    if runs.is_empty() { "none".to_string() } else { runs.join(", ") }
+}
+
+/// 64-bit FNV-1a of the content, as hex
+pub fn checksum(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf29ce484222325u64, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_restore_round_trip() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("src/lib.rs");
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, "modified").unwrap();
+
+        let store = BackupStore::new(temp.path());
+        store.backup("20240601T100000000Z", &file, "original", "modified").unwrap();
+        assert!(store.root().join("20240601T100000000Z/src/lib.rs").is_file());
+        assert!(temp.path().join(CACHE_DIR).join(".gitignore").is_file());
+
+        let report = store.restore(None).unwrap();
+        assert_eq!(report.restored.len(), 1);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "original");
+    }
+
+    #[test]
+    fn test_restore_refuses_tampered_or_edited_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let edited = temp.path().join("edited.rs");
+        let tampered = temp.path().join("tampered.rs");
+        fs::write(&edited, "edited again").unwrap();
+        fs::write(&tampered, "modified").unwrap();
+
+        let store = BackupStore::new(temp.path());
+        store.backup("run", &edited, "original", "modified").unwrap();
+        store.backup("run", &tampered, "original", "modified").unwrap();
+        fs::write(store.root().join("run/tampered.rs"), "corrupt").unwrap();
+
+        let report = store.restore(Some("run")).unwrap();
+        assert!(report.restored.is_empty());
+        assert_eq!(report.skipped.len(), 2);
+        assert_eq!(fs::read_to_string(&edited).unwrap(), "edited again");
+        assert!(store.restore(Some("missing")).unwrap_err().to_string().contains("available: run"));
+    }
+
+    #[test]
+    fn test_prune_keeps_newest_runs() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("a.rs");
+        fs::write(&file, "x").unwrap();
+        let store = BackupStore::new(temp.path());
+        for run in ["20240101T000000000Z", "20240102T000000000Z", "20240103T000000000Z"] {
+            store.backup(run, &file, "x", "y").unwrap();
+        }
+
+        assert_eq!(store.prune(2).unwrap(), 1);
+        assert_eq!(store.runs().unwrap(), vec!["20240102T000000000Z", "20240103T000000000Z"]);
+    }
+}
@@ -0,0 +1,169 @@
+//! Parsing of `cargo audit --json` output for the audit check
+//!
+//! Only the fields the check reports are read: each vulnerability's advisory
+//! ID, the affected package, and its CVSS vector. cargo-audit does not print a
+//! severity, so it is derived from the CVSS 3.x base score (low below 4.0,
+//! medium below 7.0, high below 9.0, critical above). Advisories without a
+//! vector, or with a CVSS 4.0 vector, have no severity. Warnings such as
+//! unmaintained crates are not vulnerabilities and are ignored.
+
+use serde::Deserialize;
+
+/// One vulnerable dependency
+#[derive(Debug, Clone, PartialEq)]
+pub struct Advisory {
+    /// e.g. "RUSTSEC-2020-0071"
+    pub id: String,
+    pub package: String,
+    /// "low", "medium", "high", or "critical" with the base score
+    pub severity: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AuditOutput {
+    vulnerabilities: Vulnerabilities,
+}
+
+#[derive(Deserialize)]
+struct Vulnerabilities {
+    #[serde(default)]
+    list: Vec<Vulnerability>,
+}
+
+#[derive(Deserialize)]
+struct Vulnerability {
+    advisory: AdvisoryFields,
+}
+
+#[derive(Deserialize)]
+struct AdvisoryFields {
+    id: String,
+    package: String,
+    #[serde(default)]
+    cvss: Option<String>,
+}
+
+/// The vulnerabilities in `cargo audit --json` output, or `None` if `stdout`
+/// is not an audit report
+pub fn advisories(stdout: &str) -> Option<Vec<Advisory>> {
+    let output: AuditOutput = serde_json::from_str(stdout).ok()?;
+    Some(output.vulnerabilities.list.into_iter()
+        .map(|vulnerability| {
+            let advisory = vulnerability.advisory;
+            let severity = advisory.cvss.as_deref()
+                .and_then(cvss3_base_score)
+                .map(|score| format!("{} {:.1}", severity_name(score), score));
+            Advisory { id: advisory.id, package: advisory.package, severity }
+        })
+        .collect())
+}
+
+fn severity_name(score: f64) -> &'static str {
+    match score {
+        s if s >= 9.0 => "critical",
+        s if s >= 7.0 => "high",
+        s if s >= 4.0 => "medium",
+        s if s > 0.0 => "low",
+        _ => "none",
+    }
+}
+
+/// Base score of a "CVSS:3.x/AV:N/AC:L/..." vector, per the CVSS 3.1 specification
+fn cvss3_base_score(vector: &str) -> Option<f64> {
+    if !vector.starts_with("CVSS:3.") {
+        return None;
+    }
+    let metric = |name: &str| vector.split('/').skip(1).find_map(|m| m.strip_prefix(name)?.strip_prefix(':'));
+
+    let changed = match metric("S")? {
+        "U" => false,
+        "C" => true,
+        _ => return None,
+    };
+    let attack_vector = match metric("AV")? {
+        "N" => 0.85,
+        "A" => 0.62,
+        "L" => 0.55,
+        "P" => 0.2,
+        _ => return None,
+    };
+    let attack_complexity = match metric("AC")? {
+        "L" => 0.77,
+        "H" => 0.44,
+        _ => return None,
+    };
+    let privileges = match (metric("PR")?, changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        _ => return None,
+    };
+    let user_interaction = match metric("UI")? {
+        "N" => 0.85,
+        "R" => 0.62,
+        _ => return None,
+    };
+    let impact_of = |name: &str| match metric(name)? {
+        "H" => Some(0.56),
+        "L" => Some(0.22),
+        "N" => Some(0.0),
+        _ => None,
+    };
+    let base = 1.0 - (1.0 - impact_of("C")?) * (1.0 - impact_of("I")?) * (1.0 - impact_of("A")?);
+
+    let impact = if changed {
+        7.52 * (base - 0.029) - 3.25 * (base - 0.02f64).powi(15)
+    } else {
+        6.42 * base
+    };
+    if impact <= 0.0 {
+        return Some(0.0);
+    }
+    let exploitability = 8.22 * attack_vector * attack_complexity * privileges * user_interaction;
+    let score = if changed { 1.08 * (impact + exploitability) } else { impact + exploitability };
+    Some(round_up(score.min(10.0)))
+}
+
+/// Smallest one-decimal number not below `value`, avoiding float noise
+fn round_up(value: f64) -> f64 {
+    let scaled = (value * 100_000.0).round() as i64;
+    if scaled % 10_000 == 0 {
+        scaled as f64 / 100_000.0
+    } else {
+        (scaled / 10_000 + 1) as f64 / 10.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cvss_severity() {
+        assert_eq!(cvss3_base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"), Some(9.8));
+        assert_eq!(cvss3_base_score("CVSS:3.1/AV:L/AC:H/PR:N/UI:N/S:U/C:H/I:N/A:N"), Some(5.1));
+        assert_eq!(cvss3_base_score("CVSS:3.0/AV:N/AC:L/PR:L/UI:N/S:C/C:L/I:L/A:N"), Some(6.4));
+        assert_eq!(cvss3_base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:N"), Some(0.0));
+        assert_eq!(cvss3_base_score("CVSS:4.0/AV:N/AC:L/AT:N/PR:N/UI:N/VC:H/VI:H/VA:H/SC:N/SI:N/SA:N"), None);
+        assert_eq!(severity_name(5.1), "medium");
+        assert_eq!(severity_name(9.8), "critical");
+    }
+
+    #[test]
+    fn test_advisories_from_audit_json() {
+        let stdout = r#"{"database":{},"lockfile":{"dependency-count":12},"settings":{},
+            "vulnerabilities":{"found":true,"count":2,"list":[
+                {"advisory":{"id":"RUSTSEC-2020-0071","package":"time","title":"Segfault","cvss":"CVSS:3.1/AV:L/AC:H/PR:N/UI:N/S:U/C:N/I:N/A:H"},"versions":{}},
+                {"advisory":{"id":"RUSTSEC-2024-0001","package":"foo","title":"Bad","cvss":null},"versions":{}}
+            ]},
+            "warnings":{"unmaintained":[{"advisory":{"id":"RUSTSEC-2021-0139","package":"ansi_term"}}]}}"#;
+        assert_eq!(advisories(stdout), Some(vec![
+            Advisory { id: "RUSTSEC-2020-0071".to_string(), package: "time".to_string(), severity: Some("medium 5.1".to_string()) },
+            Advisory { id: "RUSTSEC-2024-0001".to_string(), package: "foo".to_string(), severity: None },
+        ]));
+        assert_eq!(advisories(r#"{"vulnerabilities":{"found":false,"count":0,"list":[]}}"#), Some(Vec::new()));
+        assert_eq!(advisories("error: not json"), None);
+    }
+}
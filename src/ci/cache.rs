@@ -0,0 +1,170 @@
+//! On-disk cache of CI check results
+//!
+//! The cargo checks take minutes, so `CI::report` reuses a worktree's last
+//! `CheckResults` while nothing that could change them has changed. The key
+//! combines:
+//!
+//! - the HEAD commit
+//! - a hash of `git status --porcelain` and the contents of every file it lists,
+//!   so editing an already modified file also misses the cache
+//! - a hash of the `CiConfig`, so enabling a check or changing a weight or
+//!   timeout reruns everything
+//!
+//! The cache lives in `.autodebugger/ci_cache.json` in the workspace and is
+//! written atomically like the status cache. A file that cannot be parsed is
+//! discarded with a warning and rebuilt.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use super::CheckResults;
+use crate::config::CiConfig;
+use crate::monitor::cache::{status_paths, write_cache_file, StableHasher, CACHE_DIR};
+use crate::remove_debug::git_output;
+
+/// Default CI cache location for a workspace
+pub fn default_ci_cache_path(workspace: &Path) -> PathBuf {
+    workspace.join(CACHE_DIR).join("ci_cache.json")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    key: String,
+    checks: CheckResults,
+}
+
+/// Cached check results keyed by worktree path
+#[derive(Debug, Default)]
+pub struct CheckCache {
+    path: PathBuf,
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+impl CheckCache {
+    /// Load the cache, starting empty if the file is missing or corrupt
+    pub fn load(path: PathBuf) -> Self {
+        let entries = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|error| {
+                warn!("Discarding corrupt CI cache {}: {}", path.display(), error);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        Self { path, entries }
+    }
+
+    /// Cached results for a worktree if its key still matches
+    pub fn get(&self, worktree: &Path, key: &str) -> Option<CheckResults> {
+        self.entries.get(worktree)
+            .filter(|entry| entry.key == key)
+            .map(|entry| entry.checks.clone())
+    }
+
+    pub fn insert(&mut self, worktree: PathBuf, key: String, checks: CheckResults) {
+        self.entries.insert(worktree, CacheEntry { key, checks });
+    }
+
+    /// Write the cache atomically (temporary file, then rename)
+    pub fn save(&self) -> Result<()> {
+        write_cache_file(&self.path, &serde_json::to_string(&self.entries)?)
+    }
+}
+
+/// Key for a worktree's current state under `config`
+pub fn cache_key(worktree: &Path, config: &CiConfig) -> Result<String> {
+    let head = git_output(worktree, &["rev-parse", "HEAD"])?;
+    let status = git_output(
+        worktree,
+        &["--no-optional-locks", "-c", "core.quotePath=false", "status", "--porcelain", "--untracked-files=all"],
+    )?;
+
+    let mut dirty = StableHasher::default();
+    dirty.update(&status);
+    for path in status_paths(&status) {
+        // Tagged, so a deleted file differs from any content
+        match fs::read(worktree.join(path)) {
+            Ok(content) => {
+                dirty.update([1]);
+                dirty.update(content);
+            }
+            Err(_) => dirty.update([0]),
+        }
+    }
+
+    let mut settings = StableHasher::default();
+    settings.update(serde_json::to_string(config)?);
+
+    Ok(format!("{}:{}:{}", head.trim(), dirty.finish(), settings.finish()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::{git, init_repo};
+    use crate::ci::{Check, CheckStatus};
+    use std::time::Duration;
+
+    fn results() -> CheckResults {
+        CheckResults {
+            cargo_check: CheckStatus::Pass,
+            cargo_test: CheckStatus::Fail("1 test(s) failed: t".to_string()),
+            clippy: CheckStatus::Pass,
+            debug_macros: CheckStatus::Pass,
+            documentation: CheckStatus::Skipped("disabled in ci.enabled_checks".to_string()),
+            fmt: CheckStatus::Pass,
+            audit: CheckStatus::Skipped("cargo-audit is not installed (cargo install cargo-audit)".to_string()),
+            durations: [(Check::CargoTest, Duration::from_millis(1200))].into_iter().collect(),
+            wall_time: Duration::from_millis(1300),
+        }
+    }
+
+    #[test]
+    fn test_key_tracks_head_dirty_files_and_config() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path();
+        init_repo(dir, &[("a.rs", "fn a() {}\n")]);
+
+        let config = CiConfig::default();
+        let clean = cache_key(dir, &config).unwrap();
+        assert_eq!(cache_key(dir, &config).unwrap(), clean);
+
+        fs::write(dir.join("a.rs"), "fn a() { 1; }\n").unwrap();
+        let modified = cache_key(dir, &config).unwrap();
+        assert_ne!(modified, clean);
+        // Same porcelain line (" M a.rs"), different contents
+        fs::write(dir.join("a.rs"), "fn a() { 2; }\n").unwrap();
+        assert_ne!(cache_key(dir, &config).unwrap(), modified);
+
+        git(dir, &["commit", "-qam", "edit"]);
+        let committed = cache_key(dir, &config).unwrap();
+        assert_ne!(committed, clean);
+
+        let mut reweighted = config.clone();
+        reweighted.weights.clippy = 25;
+        assert_ne!(cache_key(dir, &reweighted).unwrap(), committed);
+    }
+
+    #[test]
+    fn test_round_trip_and_corrupt_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = default_ci_cache_path(dir.path());
+        let worktree = PathBuf::from("/work/feature");
+
+        let mut cache = CheckCache::load(path.clone());
+        assert!(cache.get(&worktree, "k1").is_none());
+        cache.insert(worktree.clone(), "k1".to_string(), results());
+        cache.save().unwrap();
+        assert!(dir.path().join(".autodebugger/.gitignore").exists());
+
+        let cache = CheckCache::load(path.clone());
+        assert_eq!(cache.get(&worktree, "k1"), Some(results()));
+        assert!(cache.get(&worktree, "k2").is_none());
+
+        fs::write(&path, "{ not json").unwrap();
+        assert!(CheckCache::load(path).get(&worktree, "k1").is_none());
+    }
+}
@@ -0,0 +1,753 @@
+//! Individual pre-merge checks run against one worktree
+//!
+//! The cargo checks (`cargo check`, `cargo test`, and `cargo clippy` with
+//! warnings denied) run in the worktree root and are skipped when it has no
+//! `Cargo.toml`; a failure lists the compiler errors or failing tests. `fmt`
+//! runs `cargo fmt -- --check` and lists the unformatted files; `audit` runs
+//! `cargo audit --json` and lists advisory IDs with their severities, and is
+//! skipped when cargo-audit is not installed. The other checks take their
+//! paths from the worktree's `config.yaml` (`remove_debug.default_paths`,
+//! `validate_docs.default_paths`), resolved against the worktree root;
+//! configured paths that do not exist are skipped.
+//!
+//! `run_checks` runs each enabled check on its own thread (one after another
+//! when `ci.parallel` is off), with `cargo check` and clippy sharing a thread
+//! when `ci.serialize_check_and_clippy` is set, since they contend for the
+//! same target directory lock. A check that outlives its timeout fails with
+//! "timed out after Ns" (a cargo process is killed; an in-process check is
+//! abandoned), and a panicking check fails without affecting the others.
+//! Results are stored per check, so they do not depend on completion order.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Read;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::audit;
+use crate::config::{CiConfig, Config};
+use crate::remove_debug::DebugRemover;
+use crate::validate_docs::DocValidator;
+
+/// Extra time a cargo check gets to kill its own process after a timeout,
+/// before the check is abandoned with the process still running
+const KILL_GRACE: Duration = Duration::from_secs(5);
+
+/// Locations listed in a failure message before the rest are only counted
+const MAX_LOCATIONS: usize = 5;
+
+/// Outcome of one check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", content = "detail", rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    /// Why the check failed
+    Fail(String),
+    /// Why the check did not run
+    Skipped(String),
+}
+
+impl CheckStatus {
+    pub fn is_pass(&self) -> bool {
+        matches!(self, CheckStatus::Pass)
+    }
+
+    pub fn is_fail(&self) -> bool {
+        matches!(self, CheckStatus::Fail(_))
+    }
+}
+
+/// A pre-merge check, named as in `ci.enabled_checks` and `ci.weights`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    CargoCheck,
+    CargoTest,
+    Clippy,
+    DebugMacros,
+    Documentation,
+    Fmt,
+    Audit,
+}
+
+impl Check {
+    /// Every check, in running order
+    pub const ALL: [Check; 7] = [
+        Check::CargoCheck,
+        Check::CargoTest,
+        Check::Clippy,
+        Check::DebugMacros,
+        Check::Documentation,
+        Check::Fmt,
+        Check::Audit,
+    ];
+
+    /// Name in config.yaml and reports, e.g. "cargo_check"
+    pub fn name(self) -> &'static str {
+        match self {
+            Check::CargoCheck => "cargo_check",
+            Check::CargoTest => "cargo_test",
+            Check::Clippy => "clippy",
+            Check::DebugMacros => "debug_macros",
+            Check::Documentation => "documentation",
+            Check::Fmt => "fmt",
+            Check::Audit => "audit",
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl std::str::FromStr for Check {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Check::ALL.into_iter()
+            .find(|check| check.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Check::ALL.iter().map(|check| check.name()).collect();
+                anyhow::anyhow!("Unknown check '{}' (expected one of {})", s, names.join(", "))
+            })
+    }
+}
+
+/// Outcome of every pre-merge check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckResults {
+    pub cargo_check: CheckStatus,
+    pub cargo_test: CheckStatus,
+    pub clippy: CheckStatus,
+    pub debug_macros: CheckStatus,
+    pub documentation: CheckStatus,
+    pub fmt: CheckStatus,
+    pub audit: CheckStatus,
+    /// How long each check that ran took
+    #[serde(default)]
+    pub durations: BTreeMap<Check, Duration>,
+    /// Wall time of the whole run
+    #[serde(default)]
+    pub wall_time: Duration,
+}
+
+impl CheckResults {
+    /// Each check and its status, in running order
+    pub fn iter(&self) -> impl Iterator<Item = (Check, &CheckStatus)> {
+        Check::ALL.into_iter().map(|check| (check, self.get(check)))
+    }
+
+    pub fn get(&self, check: Check) -> &CheckStatus {
+        match check {
+            Check::CargoCheck => &self.cargo_check,
+            Check::CargoTest => &self.cargo_test,
+            Check::Clippy => &self.clippy,
+            Check::DebugMacros => &self.debug_macros,
+            Check::Documentation => &self.documentation,
+            Check::Fmt => &self.fmt,
+            Check::Audit => &self.audit,
+        }
+    }
+
+    /// The checks that failed
+    pub fn failed(&self) -> Vec<Check> {
+        self.iter().filter(|(_, status)| status.is_fail()).map(|(check, _)| check).collect()
+    }
+}
+
+/// Runs checks in one worktree with that worktree's configuration
+#[derive(Debug, Clone)]
+pub struct CheckRunner {
+    /// Root of the worktree
+    pub worktree: PathBuf,
+    pub config: Config,
+    /// Program run for the cargo checks
+    pub cargo: PathBuf,
+}
+
+/// Exit status and output of a cargo command that ran to completion
+struct CargoOutput {
+    success: bool,
+    stdout: String,
+    stderr: String,
+}
+
+impl CheckRunner {
+    /// Runner for `worktree`, using its `config.yaml` if it has one
+    pub fn new(worktree: &Path) -> Result<Self> {
+        let config_path = worktree.join("config.yaml");
+        let config = if config_path.exists() {
+            Config::load_from(&config_path)?
+        } else {
+            Config::default()
+        };
+        Ok(Self { worktree: worktree.to_path_buf(), config, cargo: PathBuf::from("cargo") })
+    }
+
+    /// Use `config` instead of the worktree's own
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Run `cargo` instead of the `cargo` on PATH, e.g. a wrapper script
+    pub fn with_cargo(mut self, cargo: impl Into<PathBuf>) -> Self {
+        self.cargo = cargo.into();
+        self
+    }
+
+    /// Run every check with the default `CiConfig` (in parallel, with timeouts)
+    pub fn run_all_checks(&self) -> CheckResults {
+        self.run_checks(&CiConfig::default())
+    }
+
+    /// Run the checks in `ci.enabled_checks`; the others are `Skipped`
+    pub fn run_checks(&self, ci: &CiConfig) -> CheckResults {
+        let started = Instant::now();
+        let lanes = lanes(ci);
+        let mut finished: BTreeMap<Check, (CheckStatus, Duration)> = thread::scope(|scope| {
+            let handles: Vec<_> = lanes.iter()
+                .map(|lane| {
+                    scope.spawn(move || {
+                        lane.iter()
+                            .map(|&check| {
+                                let started = Instant::now();
+                                let status = self.run_with_timeout(check, ci.timeout(check));
+                                (check, (status, started.elapsed()))
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles.into_iter()
+                .flat_map(|h| h.join().unwrap_or_default())
+                .collect()
+        });
+
+        let mut durations = BTreeMap::new();
+        let mut status = |check: Check| {
+            if !ci.enabled_checks.contains(&check) {
+                return CheckStatus::Skipped("disabled in ci.enabled_checks".to_string());
+            }
+            match finished.remove(&check) {
+                Some((status, duration)) => {
+                    durations.insert(check, duration);
+                    status
+                }
+                // Only a panic outside the check itself gets here
+                None => CheckStatus::Fail("check panicked".to_string()),
+            }
+        };
+        CheckResults {
+            cargo_check: status(Check::CargoCheck),
+            cargo_test: status(Check::CargoTest),
+            clippy: status(Check::Clippy),
+            debug_macros: status(Check::DebugMacros),
+            documentation: status(Check::Documentation),
+            fmt: status(Check::Fmt),
+            audit: status(Check::Audit),
+            durations,
+            wall_time: started.elapsed(),
+        }
+    }
+
+    /// One check on a thread of its own, so a hang or panic only fails that check
+    fn run_with_timeout(&self, check: Check, timeout: Duration) -> CheckStatus {
+        let runner = self.clone();
+        let grace = match check {
+            Check::CargoCheck | Check::CargoTest | Check::Clippy | Check::Fmt | Check::Audit => KILL_GRACE,
+            Check::DebugMacros | Check::Documentation => Duration::ZERO,
+        };
+        run_isolated(move || runner.run_check(check, Some(timeout)), timeout, grace)
+    }
+
+    fn run_check(&self, check: Check, timeout: Option<Duration>) -> CheckStatus {
+        match check {
+            Check::CargoCheck => self.run_cargo(&["check", "--all-targets"], timeout),
+            Check::CargoTest => self.run_cargo(&["test"], timeout),
+            Check::Clippy => self.run_cargo(&["clippy", "--all-targets", "--", "-D", "warnings"], timeout),
+            Check::DebugMacros => self.check_debug_macros(),
+            Check::Documentation => self.check_documentation(),
+            Check::Fmt => self.run_fmt(timeout),
+            Check::Audit => self.run_audit(timeout),
+        }
+    }
+
+    /// `cargo check --all-targets`
+    pub fn check_cargo_check(&self) -> CheckStatus {
+        self.run_check(Check::CargoCheck, None)
+    }
+
+    /// `cargo test`
+    pub fn check_cargo_test(&self) -> CheckStatus {
+        self.run_check(Check::CargoTest, None)
+    }
+
+    /// `cargo clippy --all-targets -- -D warnings`; skipped without clippy
+    pub fn check_clippy(&self) -> CheckStatus {
+        self.run_check(Check::Clippy, None)
+    }
+
+    /// `cargo fmt -- --check`; fails with the unformatted files
+    pub fn check_fmt(&self) -> CheckStatus {
+        self.run_check(Check::Fmt, None)
+    }
+
+    /// `cargo audit --json`; fails with the advisories, skipped without cargo-audit
+    pub fn check_audit(&self) -> CheckStatus {
+        self.run_check(Check::Audit, None)
+    }
+
+    /// Run cargo in the worktree, killing it after `timeout`
+    fn run_cargo(&self, args: &[&str], timeout: Option<Duration>) -> CheckStatus {
+        match self.cargo_output(args, timeout) {
+            Ok(output) if output.success => CheckStatus::Pass,
+            Ok(output) => CheckStatus::Fail(cargo_failure(&output.stdout, &output.stderr)),
+            Err(status) => status,
+        }
+    }
+
+    fn run_fmt(&self, timeout: Option<Duration>) -> CheckStatus {
+        let output = match self.cargo_output(&["fmt", "--", "--check", "--files-with-diff"], timeout) {
+            Ok(output) if output.success => return CheckStatus::Pass,
+            Ok(output) => output,
+            Err(status) => return status,
+        };
+        let files: Vec<String> = output.stdout.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| self.relative(Path::new(line)))
+            .collect();
+        if files.is_empty() {
+            return CheckStatus::Fail(cargo_failure(&output.stdout, &output.stderr));
+        }
+        CheckStatus::Fail(format!("{} unformatted file(s): {}", files.len(), summarize(&files)))
+    }
+
+    fn run_audit(&self, timeout: Option<Duration>) -> CheckStatus {
+        let output = match self.cargo_output(&["audit", "--json"], timeout) {
+            Err(CheckStatus::Skipped(reason)) if reason.ends_with("is not installed") => {
+                return CheckStatus::Skipped("cargo-audit is not installed (cargo install cargo-audit)".to_string());
+            }
+            Ok(output) => output,
+            Err(status) => return status,
+        };
+        let advisories = match audit::advisories(&output.stdout) {
+            Some(advisories) => advisories,
+            None if output.success => return CheckStatus::Pass,
+            None => return CheckStatus::Fail(cargo_failure(&output.stdout, &output.stderr)),
+        };
+        if advisories.is_empty() {
+            return if output.success {
+                CheckStatus::Pass
+            } else {
+                CheckStatus::Fail(cargo_failure(&output.stdout, &output.stderr))
+            };
+        }
+        let listed: Vec<String> = advisories.iter()
+            .map(|advisory| format!(
+                "{} ({}, {})",
+                advisory.id, advisory.package, advisory.severity.as_deref().unwrap_or("no severity")
+            ))
+            .collect();
+        CheckStatus::Fail(format!("{} advisory(ies): {}", listed.len(), summarize(&listed)))
+    }
+
+    /// Run cargo in the worktree to completion; `Err` is the check's status when
+    /// there is no manifest, the subcommand is missing, or cargo did not finish
+    fn cargo_output(&self, args: &[&str], timeout: Option<Duration>) -> Result<CargoOutput, CheckStatus> {
+        if !self.worktree.join("Cargo.toml").exists() {
+            return Err(CheckStatus::Skipped("no Cargo.toml in the worktree".to_string()));
+        }
+        let mut child = match Command::new(&self.cargo)
+            .current_dir(&self.worktree)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(error) => return Err(CheckStatus::Fail(format!("could not run cargo: {}", error))),
+        };
+        // Drain both pipes so a chatty build cannot block on a full pipe
+        let stdout = child.stdout.take().map(read_to_string);
+        let stderr = child.stderr.take().map(read_to_string);
+
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) => {}
+                Err(error) => return Err(CheckStatus::Fail(format!("could not wait for cargo: {}", error))),
+            }
+            if let (Some(deadline), Some(timeout)) = (deadline, timeout) {
+                if Instant::now() >= deadline {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(CheckStatus::Fail(timed_out(timeout)));
+                }
+            }
+            thread::sleep(Duration::from_millis(50));
+        };
+        let stdout = stdout.and_then(|h| h.join().ok()).unwrap_or_default();
+        let stderr = stderr.and_then(|h| h.join().ok()).unwrap_or_default();
+
+        if !status.success() && stderr.contains("no such command") {
+            return Err(CheckStatus::Skipped(format!("cargo {} is not installed", args[0])));
+        }
+        Ok(CargoOutput { success: status.success(), stdout, stderr })
+    }
+
+    /// Fail if `remove-debug` would remove or rewrite any call
+    pub fn check_debug_macros(&self) -> CheckStatus {
+        let paths = self.existing_paths(&self.config.remove_debug.default_paths);
+        if paths.is_empty() {
+            return CheckStatus::Skipped("no remove_debug.default_paths in the worktree".to_string());
+        }
+
+        let mut locations = Vec::new();
+        for path in paths {
+            let remover = DebugRemover::new(path)
+                .with_dry_run(true)
+                .with_backups(false)
+                .with_macros(self.config.remove_debug.macros.iter().cloned())
+                .with_engine(self.config.remove_debug.engine);
+            let report = match remover.remove_debug_calls() {
+                Ok(report) => report,
+                Err(error) => return CheckStatus::Fail(format!("remove-debug failed: {:#}", error)),
+            };
+            for (file, file_report) in &report.file_reports {
+                let file = self.relative(file);
+                locations.extend(file_report.removals.iter().map(|removal| format!("{}:{}", file, removal.start_line)));
+                locations.extend(file_report.rewrites.iter().map(|rewrite| format!("{}:{}", file, rewrite.line)));
+            }
+        }
+
+        if locations.is_empty() {
+            CheckStatus::Pass
+        } else {
+            CheckStatus::Fail(format!("{} debug call(s) would be removed: {}", locations.len(), summarize(&locations)))
+        }
+    }
+
+    /// Fail unless `validate-docs --strict` would pass
+    pub fn check_documentation(&self) -> CheckStatus {
+        let paths = self.existing_paths(&self.config.validate_docs.default_paths);
+        if paths.is_empty() {
+            return CheckStatus::Skipped("no validate_docs.default_paths in the worktree".to_string());
+        }
+
+        let report = match DocValidator::from_config(self.config.validate_docs.clone())
+            .and_then(|validator| validator.with_strict(true).validate_paths(paths))
+        {
+            Ok(report) => report,
+            Err(error) => return CheckStatus::Fail(format!("validate-docs failed: {:#}", error)),
+        };
+        if report.passed(true) {
+            return CheckStatus::Pass;
+        }
+
+        let locations: Vec<String> = report.file_issues.iter()
+            .flat_map(|(file, issues)| {
+                let path = self.relative(&file.path);
+                issues.iter().map(move |issue| format!("{} ({})", path, issue.rule_id()))
+            })
+            .collect();
+        CheckStatus::Fail(format!(
+            "{} error(s), {} warning(s): {}",
+            report.errors, report.warnings, summarize(&locations)
+        ))
+    }
+
+    fn existing_paths(&self, paths: &[String]) -> Vec<PathBuf> {
+        paths.iter()
+            .map(|path| self.worktree.join(path))
+            .filter(|path| path.exists())
+            .collect()
+    }
+
+    /// `path` relative to the worktree, for messages
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.worktree).unwrap_or(path).display().to_string()
+    }
+}
+
+/// Groups of enabled checks that run one after another; groups run in
+/// parallel unless `ci.parallel` is off
+fn lanes(ci: &CiConfig) -> Vec<Vec<Check>> {
+    let enabled = Check::ALL.into_iter().filter(|check| ci.enabled_checks.contains(check));
+    if !ci.parallel {
+        return vec![enabled.collect()];
+    }
+    let (shared, separate): (Vec<Check>, Vec<Check>) = enabled
+        .partition(|check| ci.serialize_check_and_clippy && matches!(check, Check::CargoCheck | Check::Clippy));
+    let mut lanes: Vec<Vec<Check>> = separate.into_iter().map(|check| vec![check]).collect();
+    if !shared.is_empty() {
+        lanes.insert(0, shared);
+    }
+    lanes
+}
+
+/// Run `check` on a detached thread; a panic or no result within `timeout`
+/// plus `grace` becomes a failure, and a thread that is still running is abandoned
+fn run_isolated<F>(check: F, timeout: Duration, grace: Duration) -> CheckStatus
+where
+    F: FnOnce() -> CheckStatus + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(check));
+        let _ = tx.send(result);
+    });
+    match rx.recv_timeout(timeout + grace) {
+        Ok(Ok(status)) => status,
+        Ok(Err(payload)) => {
+            let message = payload.downcast_ref::<&str>().map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown cause".to_string());
+            CheckStatus::Fail(format!("check panicked: {}", message))
+        }
+        Err(mpsc::RecvTimeoutError::Timeout) => CheckStatus::Fail(timed_out(timeout)),
+        Err(mpsc::RecvTimeoutError::Disconnected) => CheckStatus::Fail("check panicked".to_string()),
+    }
+}
+
+fn timed_out(timeout: Duration) -> String {
+    format!("timed out after {}s", timeout.as_secs_f64())
+}
+
+/// Read a pipe to the end on a thread of its own
+fn read_to_string<R: Read + Send + 'static>(mut pipe: R) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        String::from_utf8_lossy(&buf).into_owned()
+    })
+}
+
+/// Failing tests, else compiler errors, else the last line cargo printed
+fn cargo_failure(stdout: &str, stderr: &str) -> String {
+    let failed_tests: Vec<String> = stdout.lines()
+        .filter_map(|line| line.strip_prefix("test ")?.strip_suffix(" ... FAILED"))
+        .map(str::to_string)
+        .collect();
+    if !failed_tests.is_empty() {
+        return format!("{} test(s) failed: {}", failed_tests.len(), summarize(&failed_tests));
+    }
+    let errors: Vec<String> = stderr.lines()
+        .filter(|line| line.starts_with("error") && !line.starts_with("error: could not compile"))
+        .filter(|line| !line.starts_with("error: aborting"))
+        .map(str::to_string)
+        .collect();
+    if !errors.is_empty() {
+        return format!("{} error(s): {}", errors.len(), summarize(&errors));
+    }
+    stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("cargo failed").trim().to_string()
+}
+
+/// The first few locations, and how many more there are
+fn summarize(locations: &[String]) -> String {
+    let shown = locations[..locations.len().min(MAX_LOCATIONS)].join(", ");
+    match locations.len().saturating_sub(MAX_LOCATIONS) {
+        0 => shown,
+        more => format!("{}, and {} more", shown, more),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A worktree with one `debug!` call and one undocumented complex module
+    fn fixture() -> tempfile::TempDir {
+        let temp = tempfile::tempdir().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("lib.rs"), "//! Small crate\n\nfn f() {\n    debug!(\"x\");\n}\n").unwrap();
+        fs::write(src.join("engine.rs"), "fn step() {}\n".repeat(250)).unwrap();
+        temp
+    }
+
+    #[test]
+    fn test_checks_fail_with_locations() {
+        let worktree = fixture();
+        let runner = CheckRunner::new(worktree.path()).unwrap();
+
+        assert_eq!(runner.check_debug_macros(), CheckStatus::Fail("1 debug call(s) would be removed: src/lib.rs:4".to_string()));
+        assert_eq!(runner.check_documentation(), CheckStatus::Fail("0 error(s), 1 warning(s): src/engine.rs (no-docs)".to_string()));
+        // Dry run: nothing was removed
+        assert!(fs::read_to_string(worktree.path().join("src/lib.rs")).unwrap().contains("debug!"));
+    }
+
+    #[test]
+    fn test_worktree_config_is_respected() {
+        let worktree = fixture();
+        fs::write(
+            worktree.path().join("config.yaml"),
+            "remove_debug:\n  macros: [trace]\nvalidate_docs:\n  complexity_threshold: 500\n",
+        ).unwrap();
+        let runner = CheckRunner::new(worktree.path()).unwrap();
+
+        assert_eq!(runner.check_debug_macros(), CheckStatus::Pass);
+        assert_eq!(runner.check_documentation(), CheckStatus::Pass);
+
+        let runner = runner.with_config(Config::default());
+        assert!(!runner.check_debug_macros().is_pass());
+    }
+
+    #[test]
+    fn test_missing_paths_are_skipped() {
+        let worktree = tempfile::tempdir().unwrap();
+        let runner = CheckRunner::new(worktree.path()).unwrap();
+        assert!(matches!(runner.check_debug_macros(), CheckStatus::Skipped(_)));
+        assert!(matches!(runner.check_documentation(), CheckStatus::Skipped(_)));
+    }
+
+    #[test]
+    fn test_cargo_checks_report_errors_and_failing_tests() {
+        let worktree = tempfile::tempdir().unwrap();
+        fs::write(worktree.path().join("Cargo.toml"), "[package]\nname = \"fixture\"\nversion = \"0.1.0\"\nedition = \"2021\"\n").unwrap();
+        fs::create_dir(worktree.path().join("src")).unwrap();
+        let runner = CheckRunner::new(worktree.path()).unwrap();
+
+        fs::write(worktree.path().join("src/lib.rs"), "pub fn f() -> u32 { \"x\" }\n").unwrap();
+        let CheckStatus::Fail(reason) = runner.check_cargo_check() else { panic!("cargo check passed") };
+        assert!(reason.starts_with("1 error(s): error[E0308]: mismatched types"), "{}", reason);
+
+        fs::write(worktree.path().join("src/lib.rs"), "#[test]\nfn fails() { assert_eq!(1, 2); }\n").unwrap();
+        assert_eq!(runner.check_cargo_check(), CheckStatus::Pass);
+        assert_eq!(runner.check_cargo_test(), CheckStatus::Fail("1 test(s) failed: fails".to_string()));
+    }
+
+    #[test]
+    fn test_cargo_checks_need_a_manifest() {
+        let runner = CheckRunner::new(fixture().path()).unwrap();
+        assert!(matches!(runner.check_cargo_test(), CheckStatus::Skipped(_)));
+    }
+
+    /// A crate with a manifest and an empty `src/lib.rs`
+    fn cargo_fixture() -> tempfile::TempDir {
+        let worktree = tempfile::tempdir().unwrap();
+        fs::write(worktree.path().join("Cargo.toml"), "[package]\nname = \"fixture\"\nversion = \"0.1.0\"\nedition = \"2021\"\n").unwrap();
+        fs::create_dir(worktree.path().join("src")).unwrap();
+        fs::write(worktree.path().join("src/lib.rs"), "").unwrap();
+        worktree
+    }
+
+    #[test]
+    fn test_fmt_lists_unformatted_files() {
+        let worktree = cargo_fixture();
+        let runner = CheckRunner::new(worktree.path()).unwrap();
+        fs::write(worktree.path().join("src/lib.rs"), "pub mod a;\npub fn f()->u32{1}\n").unwrap();
+        fs::write(worktree.path().join("src/a.rs"), "pub fn g() {}\n").unwrap();
+        assert_eq!(runner.check_fmt(), CheckStatus::Fail("1 unformatted file(s): src/lib.rs".to_string()));
+
+        fs::write(worktree.path().join("src/lib.rs"), "pub mod a;\npub fn f() -> u32 {\n    1\n}\n").unwrap();
+        assert_eq!(runner.check_fmt(), CheckStatus::Pass);
+    }
+
+    /// A stand-in for cargo that prints `stdout` and `stderr` and exits with `code`
+    #[cfg(unix)]
+    fn fake_cargo(dir: &Path, stdout: &str, stderr: &str, code: i32) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let (out, err) = (dir.join("stdout"), dir.join("stderr"));
+        fs::write(&out, stdout).unwrap();
+        fs::write(&err, stderr).unwrap();
+        let script = dir.join("cargo");
+        fs::write(&script, format!("#!/bin/sh\ncat '{}'\ncat '{}' >&2\nexit {}\n", out.display(), err.display(), code)).unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        script
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_audit_reports_advisories() {
+        let worktree = cargo_fixture();
+        let bin = tempfile::tempdir().unwrap();
+        let runner = CheckRunner::new(worktree.path()).unwrap();
+
+        let vulnerable = r#"{"vulnerabilities":{"found":true,"count":2,"list":[
+            {"advisory":{"id":"RUSTSEC-2020-0071","package":"time","cvss":"CVSS:3.1/AV:N/AC:H/PR:N/UI:N/S:U/C:N/I:N/A:H"}},
+            {"advisory":{"id":"RUSTSEC-2023-0001","package":"tokio","cvss":null}}]}}"#;
+        let runner = runner.with_cargo(fake_cargo(bin.path(), vulnerable, "error: 2 vulnerabilities found!", 1));
+        assert_eq!(runner.check_audit(), CheckStatus::Fail(
+            "2 advisory(ies): RUSTSEC-2020-0071 (time, medium 5.9), RUSTSEC-2023-0001 (tokio, no severity)".to_string()
+        ));
+
+        let clean = r#"{"vulnerabilities":{"found":false,"count":0,"list":[]}}"#;
+        let runner = runner.with_cargo(fake_cargo(bin.path(), clean, "", 0));
+        assert_eq!(runner.check_audit(), CheckStatus::Pass);
+
+        let runner = runner.with_cargo(fake_cargo(bin.path(), "", "error: failed to fetch advisory database", 1));
+        assert_eq!(runner.check_audit(), CheckStatus::Fail("1 error(s): error: failed to fetch advisory database".to_string()));
+
+        let runner = runner.with_cargo(fake_cargo(bin.path(), "", "error: no such command: `audit`", 101));
+        assert_eq!(runner.check_audit(), CheckStatus::Skipped("cargo-audit is not installed (cargo install cargo-audit)".to_string()));
+    }
+
+    #[test]
+    fn test_summarize_counts_the_rest() {
+        let locations: Vec<String> = (1..=7).map(|line| format!("a.rs:{}", line)).collect();
+        assert_eq!(summarize(&locations), "a.rs:1, a.rs:2, a.rs:3, a.rs:4, a.rs:5, and 2 more");
+    }
+
+    #[test]
+    fn test_hung_and_panicking_checks_fail_alone() {
+        let timeout = Duration::from_millis(200);
+        let started = Instant::now();
+        let hung = run_isolated(|| {
+            thread::sleep(Duration::from_secs(10));
+            CheckStatus::Pass
+        }, timeout, Duration::ZERO);
+        assert_eq!(hung, CheckStatus::Fail("timed out after 0.2s".to_string()));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let panicked = run_isolated(|| panic!("boom"), timeout, Duration::ZERO);
+        assert_eq!(panicked, CheckStatus::Fail("check panicked: boom".to_string()));
+        assert_eq!(run_isolated(|| CheckStatus::Pass, timeout, Duration::ZERO), CheckStatus::Pass);
+    }
+
+    #[test]
+    fn test_lanes() {
+        let mut ci = CiConfig::default();
+        assert_eq!(lanes(&ci).len(), 7);
+
+        ci.serialize_check_and_clippy = true;
+        assert_eq!(lanes(&ci), vec![
+            vec![Check::CargoCheck, Check::Clippy],
+            vec![Check::CargoTest],
+            vec![Check::DebugMacros],
+            vec![Check::Documentation],
+            vec![Check::Fmt],
+            vec![Check::Audit],
+        ]);
+
+        ci.parallel = false;
+        ci.enabled_checks = vec![Check::Documentation, Check::CargoTest];
+        assert_eq!(lanes(&ci), vec![vec![Check::CargoTest, Check::Documentation]]);
+    }
+
+    #[test]
+    fn test_parallel_and_sequential_runs_agree() {
+        let worktree = fixture();
+        let runner = CheckRunner::new(worktree.path()).unwrap();
+        let parallel = runner.run_checks(&CiConfig::default());
+        let sequential = runner.run_checks(&CiConfig { parallel: false, ..CiConfig::default() });
+
+        let statuses = |results: &CheckResults| results.iter().map(|(_, s)| s.clone()).collect::<Vec<_>>();
+        assert_eq!(statuses(&parallel), statuses(&sequential));
+        assert!(parallel.debug_macros.is_fail());
+        assert_eq!(parallel.durations.len(), 7);
+        assert!(parallel.durations.values().all(|duration| *duration <= parallel.wall_time));
+    }
+}
@@ -0,0 +1,261 @@
+//! Merge conflict prediction between a worktree and its base branch
+//!
+//! Both sides are diffed against their merge base with zero context (see
+//! `monitor::overlap`), so their line ranges refer to the same file version and
+//! can be compared exactly. A file changed on both sides is rated by how close
+//! the changes are:
+//!
+//! - **High**: the changed line ranges intersect, or one side added or replaced
+//!   the whole file (untracked or binary files); git will almost certainly stop
+//! - **Medium**: changes within `ADJACENT_LINES` of each other, which git also
+//!   reports as a conflict when the hunks touch
+//! - **Low**: changes elsewhere in the same file; they merge cleanly but are
+//!   worth a look
+//!
+//! Changes that merge cleanly can still break each other when they land in the
+//! same function. For Rust files without a High prediction, a semantic pass
+//! looks up the items (see `items`) enclosing each side's changes in the
+//! merge-base version; if both sides touched the same one, the file's
+//! prediction becomes `ConflictKind::SameItem` with `Medium` severity, scored
+//! with its own `ci.conflict_penalties.same_item` penalty.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::items::{rust_items, touched_items, Item};
+use crate::monitor::overlap::{changed_ranges, parse_hunk_ranges, LineRange};
+use crate::remove_debug::git_output;
+
+/// Changes at most this many lines apart are rated `Medium`
+pub const ADJACENT_LINES: usize = 3;
+
+/// How likely a file is to conflict
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictSeverity {
+    Low,
+    Medium,
+    High,
+}
+
+/// What the prediction is based on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// Line ranges changed on both sides
+    #[default]
+    Textual,
+    /// Both sides changed the same fn, impl, or type, in different lines
+    SameItem,
+}
+
+/// A file changed both in the worktree and on the base branch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConflictPrediction {
+    pub file: String,
+    pub severity: ConflictSeverity,
+    #[serde(default)]
+    pub kind: ConflictKind,
+    /// Lines of the merge-base version changed on both sides
+    pub overlapping_lines: usize,
+    pub description: String,
+}
+
+/// Predicts conflicts for one worktree
+#[derive(Debug, Clone)]
+pub struct ConflictAnalyzer {
+    pub worktree: PathBuf,
+}
+
+impl ConflictAnalyzer {
+    pub fn new(worktree: &Path) -> Self {
+        Self { worktree: worktree.to_path_buf() }
+    }
+
+    /// Files the worktree (committed or not) and `base` both changed since their
+    /// merge base, most severe first
+    pub fn analyze(&self, base: &str) -> Result<Vec<ConflictPrediction>> {
+        let merge_base = git_output(&self.worktree, &["merge-base", "HEAD", base])
+            .with_context(|| format!("No common ancestor with {}", base))?;
+        let ours = changed_ranges(&self.worktree, Some(base))?;
+        let theirs = parse_hunk_ranges(&git_output(
+            &self.worktree,
+            &["-c", "core.quotePath=false", "diff", "-U0", "-M", "--no-color", merge_base.trim(), base],
+        )?);
+
+        let mut predictions: Vec<ConflictPrediction> = ours.iter()
+            .filter_map(|(file, ours)| {
+                let theirs = theirs.get(file)?;
+                let textual = predict(file, ours, theirs);
+                if textual.severity == ConflictSeverity::High || !file.ends_with(".rs") {
+                    return Some(textual);
+                }
+                // Best effort: a file missing from the merge base (e.g. renamed) keeps its textual rating
+                let source = git_output(&self.worktree, &["show", &format!("{}:{}", merge_base.trim(), file)]).ok();
+                Some(source.and_then(|source| same_items(file, &source, ours, theirs)).unwrap_or(textual))
+            })
+            .collect();
+        predictions.sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| a.file.cmp(&b.file)));
+        Ok(predictions)
+    }
+}
+
+/// Rate one file changed on both sides (an empty range list is the whole file)
+fn predict(file: &str, ours: &[LineRange], theirs: &[LineRange]) -> ConflictPrediction {
+    let (severity, overlapping_lines, description) = if ours.is_empty() || theirs.is_empty() {
+        (ConflictSeverity::High, 0, "whole file changed on one side".to_string())
+    } else {
+        let overlapping_lines: usize = ours.iter()
+            .flat_map(|a| theirs.iter().map(move |b| overlap(a, b)))
+            .sum();
+        let gap = ours.iter()
+            .flat_map(|a| theirs.iter().map(move |b| gap(a, b)))
+            .min()
+            .unwrap_or(usize::MAX);
+        if overlapping_lines > 0 {
+            (ConflictSeverity::High, overlapping_lines, format!("{} line(s) changed on both sides", overlapping_lines))
+        } else if gap <= ADJACENT_LINES {
+            (ConflictSeverity::Medium, 0, format!("changes {} line(s) apart", gap))
+        } else {
+            (ConflictSeverity::Low, 0, "both sides changed different parts of the file".to_string())
+        }
+    };
+    ConflictPrediction { file: file.to_string(), severity, kind: ConflictKind::Textual, overlapping_lines, description }
+}
+
+/// A `SameItem` prediction if both sides changed lines of the same item of
+/// `source`, the merge-base version of the file
+fn same_items(file: &str, source: &str, ours: &[LineRange], theirs: &[LineRange]) -> Option<ConflictPrediction> {
+    let items = rust_items(source);
+    let touched = |ranges: &[LineRange]| -> Vec<&Item> {
+        ranges.iter().flat_map(|range| touched_items(&items, range.start, range.end())).collect()
+    };
+    let theirs = touched(theirs);
+    let mut shared: Vec<&Item> = touched(ours).into_iter().filter(|item| theirs.contains(item)).collect();
+    shared.sort_by_key(|item| item.start);
+    shared.dedup();
+    if shared.is_empty() {
+        return None;
+    }
+    let names: Vec<String> = shared.iter().map(|item| format!("`{}`", item.name)).collect();
+    Some(ConflictPrediction {
+        file: file.to_string(),
+        severity: ConflictSeverity::Medium,
+        kind: ConflictKind::SameItem,
+        overlapping_lines: 0,
+        description: format!("both sides changed {}", names.join(", ")),
+    })
+}
+
+/// Lines shared by two ranges; an insertion point inside the other range counts as one
+fn overlap(a: &LineRange, b: &LineRange) -> usize {
+    if !a.intersects(b) {
+        return 0;
+    }
+    a.end().min(b.end()) - a.start.max(b.start)
+}
+
+/// Unchanged lines between two disjoint ranges (0 when they intersect or touch)
+fn gap(a: &LineRange, b: &LineRange) -> usize {
+    if a.start >= b.end() {
+        a.start - b.end()
+    } else {
+        b.start.saturating_sub(a.end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::{git, init_repo};
+    use std::fs;
+
+    fn lines(changed: &[usize]) -> String {
+        (1..=40).map(|i| if changed.contains(&i) { format!("changed {}\n", i) } else { format!("line {}\n", i) }).collect()
+    }
+
+    #[test]
+    fn test_predictions_are_rated_by_distance() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path();
+        git(dir, &["init", "-q", "-b", "main"]);
+        for file in ["same.rs", "near.rs", "far.rs", "untouched.rs"] {
+            fs::write(dir.join(file), lines(&[])).unwrap();
+        }
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-qm", "base"]);
+
+        git(dir, &["checkout", "-qb", "feature"]);
+        fs::write(dir.join("same.rs"), lines(&[10, 11])).unwrap();
+        fs::write(dir.join("far.rs"), lines(&[2])).unwrap();
+        fs::write(dir.join("untouched.rs"), lines(&[5])).unwrap();
+        git(dir, &["commit", "-qam", "feature"]);
+
+        git(dir, &["checkout", "-q", "main"]);
+        fs::write(dir.join("same.rs"), lines(&[11, 12])).unwrap();
+        fs::write(dir.join("near.rs"), lines(&[13])).unwrap();
+        fs::write(dir.join("far.rs"), lines(&[30])).unwrap();
+        git(dir, &["commit", "-qam", "main"]);
+        git(dir, &["checkout", "-q", "feature"]);
+        // Uncommitted work counts too
+        fs::write(dir.join("near.rs"), lines(&[10])).unwrap();
+
+        let predictions = ConflictAnalyzer::new(dir).analyze("main").unwrap();
+        let summary: Vec<(&str, ConflictSeverity, usize)> = predictions.iter()
+            .map(|p| (p.file.as_str(), p.severity, p.overlapping_lines))
+            .collect();
+        assert_eq!(summary, vec![
+            ("same.rs", ConflictSeverity::High, 1),
+            ("near.rs", ConflictSeverity::Medium, 0),
+            ("far.rs", ConflictSeverity::Low, 0),
+        ]);
+        assert_eq!(predictions[1].description, "changes 2 line(s) apart");
+    }
+
+    #[test]
+    fn test_same_function_changed_on_both_sides() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path();
+        let body: String = (1..=12).map(|i| format!("    let v{} = {};\n", i, i)).collect();
+        let source = |a: &str, b: &str| format!(
+            "fn parse() {{\n{}}}\n\nfn other() {{\n    let x = 0;\n}}\n",
+            body.replace("let v2 = 2", a).replace("let v11 = 11", b)
+        );
+        init_repo(
+            dir,
+            &[
+                ("lib.rs", &source("let v2 = 2", "let v11 = 11")),
+                ("notes.txt", &lines(&[])),
+            ],
+        );
+
+        git(dir, &["checkout", "-qb", "feature"]);
+        fs::write(dir.join("lib.rs"), source("let v2 = 20", "let v11 = 11")).unwrap();
+        fs::write(dir.join("notes.txt"), lines(&[2])).unwrap();
+        git(dir, &["commit", "-qam", "feature"]);
+        git(dir, &["checkout", "-q", "main"]);
+        fs::write(dir.join("lib.rs"), source("let v2 = 2", "let v11 = 110")).unwrap();
+        fs::write(dir.join("notes.txt"), lines(&[30])).unwrap();
+        git(dir, &["commit", "-qam", "main"]);
+        git(dir, &["checkout", "-q", "feature"]);
+
+        let predictions = ConflictAnalyzer::new(dir).analyze("main").unwrap();
+        let summary: Vec<(&str, ConflictSeverity, ConflictKind)> = predictions.iter()
+            .map(|p| (p.file.as_str(), p.severity, p.kind))
+            .collect();
+        // Nine lines apart: textually Low, but the same function
+        assert_eq!(summary, vec![
+            ("lib.rs", ConflictSeverity::Medium, ConflictKind::SameItem),
+            ("notes.txt", ConflictSeverity::Low, ConflictKind::Textual),
+        ]);
+        assert_eq!(predictions[0].description, "both sides changed `fn parse`");
+    }
+
+    #[test]
+    fn test_whole_file_changes_are_high() {
+        let prediction = predict("new.rs", &[], &[LineRange { start: 1, len: 3 }]);
+        assert_eq!(prediction.severity, ConflictSeverity::High);
+        assert_eq!(gap(&LineRange { start: 1, len: 2 }, &LineRange { start: 3, len: 1 }), 0);
+    }
+}
@@ -0,0 +1,208 @@
+//! Best-effort Rust item spans for semantic conflict detection
+//!
+//! A line scanner, not a parser: it recognizes `fn`, `struct`, `enum`, `union`,
+//! `trait`, `impl`, and `mod` headers (with visibility and qualifiers such as
+//! `pub(crate) async unsafe`), follows the header to its opening brace, possibly
+//! several lines later, and closes the item at the matching brace. Braces inside
+//! strings, char literals, and comments are ignored. Items without a body
+//! (`fn f();`, `struct Unit;`) are skipped. Macro-generated items and code that
+//! does not compile may be missed or mis-spanned, which only costs a prediction.
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// An item and the lines (1-based, inclusive) from its header to its closing brace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    /// e.g. "fn parse", "impl Display for Token"
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Item {
+    fn contains(&self, other: &Item) -> bool {
+        self != other && self.start <= other.start && other.end <= self.end
+    }
+}
+
+fn header_regex() -> &'static Regex {
+    static HEADER: OnceLock<Regex> = OnceLock::new();
+    HEADER.get_or_init(|| {
+        Regex::new(
+            r#"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:(?:const|async|unsafe|default|extern(?:\s+"[^"]*")?)\s+)*(fn|struct|enum|union|trait|impl|mod)\b(.*)$"#,
+        ).expect("valid item header regex")
+    })
+}
+
+/// Every item with a body in `source`, outer items before the items they contain
+pub fn rust_items(source: &str) -> Vec<Item> {
+    let mut items = Vec::new();
+    // Header seen, body not opened yet: (name, start line, bracket depth)
+    let mut pending: Option<(String, usize, i32)> = None;
+    // Open items: (name, start line, brace depth outside the item)
+    let mut open: Vec<(String, usize, usize)> = Vec::new();
+    let mut depth = 0usize;
+    let mut in_block_comment = false;
+
+    for (index, line) in source.lines().enumerate() {
+        let number = index + 1;
+        let code = strip_non_code(line, &mut in_block_comment);
+        if pending.is_none() {
+            if let Some(captures) = header_regex().captures(&code) {
+                pending = Some((item_name(&captures[1], &captures[2]), number, 0));
+            }
+        }
+        for c in code.chars() {
+            match c {
+                '(' | '[' => if let Some((_, _, brackets)) = &mut pending { *brackets += 1 },
+                ')' | ']' => if let Some((_, _, brackets)) = &mut pending { *brackets -= 1 },
+                ';' if pending.as_ref().is_some_and(|(_, _, brackets)| *brackets <= 0) => pending = None,
+                '{' => {
+                    if let Some((name, start, _)) = pending.take() {
+                        open.push((name, start, depth));
+                    }
+                    depth += 1;
+                }
+                '}' => {
+                    depth = depth.saturating_sub(1);
+                    if open.last().is_some_and(|(_, _, outside)| *outside == depth) {
+                        let (name, start, _) = open.pop().expect("checked above");
+                        items.push(Item { name, start, end: number });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    items.sort_by_key(|item| (item.start, std::cmp::Reverse(item.end)));
+    items
+}
+
+/// Innermost items overlapping lines `start..end` (end exclusive); an empty
+/// range counts as line `start`, like an insertion in `LineRange`
+pub fn touched_items(items: &[Item], start: usize, end: usize) -> Vec<&Item> {
+    let end = end.max(start + 1);
+    let overlapping: Vec<&Item> = items.iter()
+        .filter(|item| item.start < end && start <= item.end)
+        .collect();
+    overlapping.iter()
+        .filter(|item| !overlapping.iter().any(|other| item.contains(other)))
+        .copied()
+        .collect()
+}
+
+/// "fn parse" for named items; the header up to its body for impls
+fn item_name(keyword: &str, rest: &str) -> String {
+    if keyword == "impl" {
+        let header = rest.split(['{', '\n']).next().unwrap_or_default();
+        let header = header.split(" where ").next().unwrap_or(header);
+        let header: Vec<&str> = header.split_whitespace().collect();
+        return format!("impl {}", header.join(" ")).trim_end().to_string();
+    }
+    let name: String = rest.trim_start()
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect();
+    format!("{} {}", keyword, name)
+}
+
+/// The line with string and char literals blanked and comments removed
+fn strip_non_code(line: &str, in_block_comment: &mut bool) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut code = String::with_capacity(line.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if *in_block_comment {
+            if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                *in_block_comment = false;
+                i += 2;
+            } else {
+                i += 1;
+            }
+            continue;
+        }
+        match chars[i] {
+            '/' if chars.get(i + 1) == Some(&'/') => break,
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                *in_block_comment = true;
+                i += 2;
+            }
+            '"' => {
+                code.push_str("\"\"");
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            // Char literals ('{', '\n'); a lifetime ('a) has no closing quote
+            '\'' if chars.get(i + 2) == Some(&'\'') => i += 3,
+            '\'' if chars.get(i + 1) == Some(&'\\') => {
+                i += 2;
+                while i < chars.len() && chars[i] != '\'' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            c => {
+                code.push(c);
+                i += 1;
+            }
+        }
+    }
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"//! Tokens { in docs }
+pub struct Token {
+    text: String,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{{{}}}", self.text) // a } in a comment
+    }
+}
+
+pub(crate) async fn parse(
+    input: [u8; 4],
+) -> Vec<Token> {
+    let open = '{';
+    /* } */
+    Vec::new()
+}
+
+struct Unit;
+fn declared();
+"#;
+
+    #[test]
+    fn test_items_are_spanned_by_their_braces() {
+        let spans: Vec<(String, usize, usize)> = rust_items(SOURCE).into_iter()
+            .map(|item| (item.name, item.start, item.end))
+            .collect();
+        assert_eq!(spans, vec![
+            ("struct Token".to_string(), 2, 4),
+            ("impl std::fmt::Display for Token".to_string(), 6, 10),
+            ("fn fmt".to_string(), 7, 9),
+            ("fn parse".to_string(), 12, 18),
+        ]);
+    }
+
+    #[test]
+    fn test_touched_items_are_innermost() {
+        let items = rust_items(SOURCE);
+        let names = |start, end| touched_items(&items, start, end).into_iter().map(|i| i.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names(8, 9), vec!["fn fmt"]);
+        assert_eq!(names(6, 7), vec!["impl std::fmt::Display for Token"]);
+        // An insertion
+        assert_eq!(names(16, 16), vec!["fn parse"]);
+        assert_eq!(names(3, 14), vec!["struct Token", "fn fmt", "fn parse"]);
+        assert!(names(19, 21).is_empty());
+    }
+}
@@ -0,0 +1,520 @@
+//! Pre-merge checks and merge readiness for a worktree
+//!
+//! `checks::CheckRunner` runs the crate's own tools against a worktree the way
+//! its owner would before merging: `cargo check`, `cargo test`, and clippy must
+//! pass, `remove-debug` in dry-run mode must find nothing to remove, and
+//! `validate-docs` must pass in strict mode. Each check reads the worktree's own
+//! `config.yaml` (defaults when there is none) and reports a `CheckStatus`
+//! instead of an error, so one broken check does not hide the others.
+//!
+//! `CI::report` combines the checks with `conflicts::ConflictAnalyzer`'s
+//! predictions against a base branch into a `CIReport`:
+//!
+//! - the safety score starts at 100 and loses `ci.weights` per failed check
+//!   and `ci.conflict_penalties` per predicted conflict (by severity, or
+//!   `same_item` for semantic ones), saturating at 0
+//! - the recommendation is `Safe` from `ci.thresholds.safe` (80), `Caution`
+//!   from `ci.thresholds.caution` (60), else `Danger`
+//!
+//! Checks missing from `ci.enabled_checks` are skipped and cost nothing. The
+//! report carries the `CiConfig` it was scored with, so scores from machines
+//! with different configurations can be told apart.
+//!
+//! With `CI::with_cache`, check results are reused from `cache::CheckCache`
+//! while the worktree's HEAD, dirty files, and the `CiConfig` are unchanged
+//! (`CIReport::cached`); conflicts are always recomputed, since the base moves.
+//!
+//! The CLI (`autodebugger ci <worktree>`) exits 0 for a `Safe` recommendation
+//! and 1 (`ErrorKind::CheckFailed`) for `Caution` or `Danger`, so scripts can
+//! gate merges on it. 2, 3, and 4 are the codes every subcommand uses for usage
+//! or config errors, missing worktrees or other environment problems, and
+//! internal errors.
+//!
+//! `CI::plan_merge_order` (see `plan`) does the same for every worktree and
+//! suggests the order to merge them in.
+
+mod audit;
+pub mod cache;
+pub mod checks;
+pub mod conflicts;
+pub mod items;
+pub mod plan;
+pub mod report;
+
+pub use checks::{Check, CheckResults, CheckRunner, CheckStatus};
+pub use conflicts::{ConflictAnalyzer, ConflictKind, ConflictPrediction, ConflictSeverity};
+pub use plan::{MergePlan, MergeStep, PairConflicts};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Write as _};
+use std::path::{Path, PathBuf};
+
+use self::cache::{cache_key, CheckCache};
+use crate::config::{CiConfig, MonitorConfig, ScoreThresholds};
+use crate::monitor::overlap::default_branch;
+use crate::monitor::worktree::WorktreeMonitor;
+use crate::remove_debug::git_output;
+
+/// Whether a worktree looks ready to merge, ordered from safe to danger
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Recommendation {
+    Safe,
+    Caution,
+    Danger,
+}
+
+impl fmt::Display for Recommendation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Recommendation::Safe => "SAFE",
+            Recommendation::Caution => "CAUTION",
+            Recommendation::Danger => "DANGER",
+        })
+    }
+}
+
+/// Checks, conflicts, and verdict for one worktree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CIReport {
+    pub worktree: String,
+    pub path: PathBuf,
+    /// Branch or commit the conflicts were predicted against
+    pub base: String,
+    pub checks: CheckResults,
+    /// The check results were reused from the CI cache
+    #[serde(default)]
+    pub cached: bool,
+    pub conflicts: Vec<ConflictPrediction>,
+    /// 0 (do not merge) to 100
+    pub safety_score: u8,
+    pub recommendation: Recommendation,
+    /// What brought the score down, e.g. "cargo_test failed; 1 high conflict"
+    pub reason: String,
+    /// Checks, weights, and thresholds the score was computed with
+    pub config: CiConfig,
+}
+
+impl CIReport {
+    /// Human-readable report: checks table, conflicts, score, and recommendation
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "CI report: {} (against {})", self.worktree, self.base);
+        let cached = if self.cached { ", cached" } else { "" };
+        let _ = writeln!(out, "\nChecks ({:.1}s{}):", self.checks.wall_time.as_secs_f64(), cached);
+        for (check, status) in self.checks.iter() {
+            let took = self.checks.durations.get(&check)
+                .map(|duration| format!(" ({:.1}s)", duration.as_secs_f64()))
+                .unwrap_or_default();
+            let _ = match status {
+                CheckStatus::Pass => writeln!(out, "  ✅ {:<14} pass{}", check, took),
+                CheckStatus::Fail(reason) => writeln!(out, "  ❌ {:<14} fail{}: {}", check, took, reason),
+                CheckStatus::Skipped(reason) => writeln!(out, "  ⏭️  {:<14} skipped: {}", check, reason),
+            };
+        }
+        if self.conflicts.is_empty() {
+            let _ = writeln!(out, "\nNo conflicts predicted with {}", self.base);
+        } else {
+            let _ = writeln!(out, "\nPredicted conflicts ({}):", self.conflicts.len());
+            for conflict in &self.conflicts {
+                let severity = format!("{:?}", conflict.severity).to_uppercase();
+                let _ = writeln!(out, "  {:<6} {}: {}", severity, conflict.file, conflict.description);
+            }
+        }
+        let _ = writeln!(out, "\nSafety score: {}/100", self.safety_score);
+        let _ = writeln!(out, "Recommendation: {} ({})", self.recommendation, self.reason);
+        let _ = writeln!(out, "\n{}", scoring_summary(&self.config));
+        out
+    }
+}
+
+/// One line of weights and thresholds, e.g. "Scoring: cargo_check -30, ..."
+fn scoring_summary(config: &CiConfig) -> String {
+    let checks: Vec<String> = Check::ALL.iter()
+        .map(|&check| if config.enabled_checks.contains(&check) {
+            format!("{} -{}", check, config.weights.get(check))
+        } else {
+            format!("{} off", check)
+        })
+        .collect();
+    let penalties = &config.conflict_penalties;
+    format!(
+        "Scoring: {}; conflicts high -{}, medium -{}, low -{}, same item -{}; safe >= {}, caution >= {}",
+        checks.join(", "), penalties.high, penalties.medium, penalties.low, penalties.same_item,
+        config.thresholds.safe, config.thresholds.caution,
+    )
+}
+
+/// Merge-readiness analysis for the worktrees of a workspace
+#[derive(Debug, Clone)]
+pub struct CI {
+    pub workspace: PathBuf,
+    pub config: CiConfig,
+    /// Where check results are cached; None always reruns them
+    pub cache_path: Option<PathBuf>,
+    /// Worktree discovery settings; None uses `MonitorConfig::default()`
+    pub monitor_config: Option<MonitorConfig>,
+}
+
+impl CI {
+    /// Analysis with the default `CiConfig`
+    pub fn new(workspace: PathBuf) -> Self {
+        Self::with_config(workspace, CiConfig::default())
+    }
+
+    pub fn with_config(workspace: PathBuf, config: CiConfig) -> Self {
+        Self { workspace, config, cache_path: None, monitor_config: None }
+    }
+
+    /// Reuse check results stored in `path` while the worktree is unchanged
+    pub fn with_cache(mut self, path: PathBuf) -> Self {
+        self.cache_path = Some(path);
+        self
+    }
+
+    /// Find worktrees with these settings (usually the config file's `monitor`
+    /// section) instead of the defaults
+    pub fn with_monitor_config(mut self, config: MonitorConfig) -> Self {
+        self.monitor_config = Some(config);
+        self
+    }
+
+    /// Worktree monitor for the workspace
+    pub(crate) fn worktree_monitor(&self) -> Result<WorktreeMonitor> {
+        let config = self.monitor_config.clone().unwrap_or_default();
+        WorktreeMonitor::new(self.workspace.clone(), Some(config))
+    }
+
+    /// A worktree given as a directory, or by name among the workspace's worktrees
+    pub fn resolve_worktree(&self, worktree: &str) -> Result<(String, PathBuf)> {
+        let path = Path::new(worktree);
+        if path.is_dir() {
+            let path = path.canonicalize()
+                .with_context(|| format!("Failed to resolve {}", path.display()))?;
+            let name = path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned());
+            return Ok((name, path));
+        }
+        let monitor = self.worktree_monitor()?;
+        let found = monitor.find_worktree(worktree)?;
+        Ok((found.name, found.path))
+    }
+
+    /// Run the enabled checks in a worktree with its own configuration
+    pub fn pre_merge_checks(&self, worktree: &Path) -> Result<CheckResults> {
+        Ok(CheckRunner::new(worktree)?.run_checks(&self.config))
+    }
+
+    /// Full report for `worktree` (a name or a path) against `base`, a branch or
+    /// another worktree's name (default: the repository's default branch)
+    pub fn report(&self, worktree: &str, base: Option<&str>) -> Result<CIReport> {
+        let (name, path) = self.resolve_worktree(worktree)?;
+        let base = self.base_for(&path, base)?;
+        let (checks, cached) = self.cached_checks(&path)?;
+        self.score(name, path, base, checks, cached)
+    }
+
+    /// `report` from cached check results alone, None when the cache holds none
+    /// for the worktree's current state (or there is no cache); no check runs
+    /// and nothing is written
+    pub fn cached_report(&self, worktree: &str, base: Option<&str>) -> Result<Option<CIReport>> {
+        let Some(cache_path) = &self.cache_path else {
+            return Ok(None);
+        };
+        let (name, path) = self.resolve_worktree(worktree)?;
+        let key = cache_key(&path, &self.config)?;
+        let Some(checks) = CheckCache::load(cache_path.clone()).get(&path, &key) else {
+            return Ok(None);
+        };
+        let base = self.base_for(&path, base)?;
+        self.score(name, path, base, checks, true).map(Some)
+    }
+
+    /// Predict conflicts with `base` and score `checks` with them
+    fn score(&self, name: String, path: PathBuf, base: String, checks: CheckResults, cached: bool) -> Result<CIReport> {
+        let conflicts = ConflictAnalyzer::new(&path).analyze(&base)?;
+        let safety_score = calculate_safety_score(&checks, &conflicts, &self.config);
+        let (recommendation, reason) = generate_recommendation(safety_score, &checks, &conflicts, &self.config.thresholds);
+        Ok(CIReport {
+            worktree: name,
+            path,
+            base,
+            checks,
+            cached,
+            conflicts,
+            safety_score,
+            recommendation,
+            reason,
+            config: self.config.clone(),
+        })
+    }
+
+    /// Check results from the cache when the worktree is unchanged, else a fresh
+    /// run (stored for next time); true when they came from the cache
+    fn cached_checks(&self, worktree: &Path) -> Result<(CheckResults, bool)> {
+        let Some(cache_path) = &self.cache_path else {
+            return Ok((self.pre_merge_checks(worktree)?, false));
+        };
+        let key = match cache_key(worktree, &self.config) {
+            Ok(key) => key,
+            Err(error) => {
+                tracing::warn!("Not caching CI results for {}: {:#}", worktree.display(), error);
+                return Ok((self.pre_merge_checks(worktree)?, false));
+            }
+        };
+        let mut cache = CheckCache::load(cache_path.clone());
+        if let Some(checks) = cache.get(worktree, &key) {
+            return Ok((checks, true));
+        }
+        let checks = self.pre_merge_checks(worktree)?;
+        cache.insert(worktree.to_path_buf(), key, checks.clone());
+        if let Err(error) = cache.save() {
+            tracing::warn!("Failed to save CI cache: {:#}", error);
+        }
+        Ok((checks, false))
+    }
+
+    /// `base` resolved from `worktree`, or its default branch
+    fn base_for(&self, worktree: &Path, base: Option<&str>) -> Result<String> {
+        match base {
+            Some(base) => self.resolve_base(worktree, base),
+            None => default_branch(worktree).context("No default branch found; pass --base"),
+        }
+    }
+
+    /// `base` as a ref in `worktree`'s repository; a worktree name stands for
+    /// that worktree's branch (or commit, when detached)
+    fn resolve_base(&self, worktree: &Path, base: &str) -> Result<String> {
+        let commit = format!("{}^{{commit}}", base);
+        if git_output(worktree, &["rev-parse", "--verify", "--quiet", &commit]).is_ok() {
+            return Ok(base.to_string());
+        }
+        let monitor = self.worktree_monitor()?;
+        let other = monitor.find_worktree(base)
+            .with_context(|| format!("{} is neither a branch nor a worktree", base))?;
+        match other.branch {
+            Some(branch) => Ok(branch),
+            None => Ok(git_output(&other.path, &["rev-parse", "HEAD"])?.trim().to_string()),
+        }
+    }
+}
+
+/// 100 minus the penalties for failed enabled checks and predicted conflicts
+pub fn calculate_safety_score(checks: &CheckResults, conflicts: &[ConflictPrediction], config: &CiConfig) -> u8 {
+    let check_penalty: u32 = checks.failed().into_iter()
+        .filter(|check| config.enabled_checks.contains(check))
+        .map(|check| config.weights.get(check))
+        .sum();
+    let conflict_penalty: u32 = conflicts.iter().map(|c| config.conflict_penalties.penalty(c)).sum();
+    100u32.saturating_sub(check_penalty.saturating_add(conflict_penalty)) as u8
+}
+
+/// Verdict for a score, with the failed checks and conflicts behind it
+pub fn generate_recommendation(
+    score: u8,
+    checks: &CheckResults,
+    conflicts: &[ConflictPrediction],
+    thresholds: &ScoreThresholds,
+) -> (Recommendation, String) {
+    let recommendation = if score >= thresholds.safe {
+        Recommendation::Safe
+    } else if score >= thresholds.caution {
+        Recommendation::Caution
+    } else {
+        Recommendation::Danger
+    };
+
+    let mut reasons: Vec<String> = checks.failed().iter().map(|name| format!("{} failed", name)).collect();
+    for severity in [ConflictSeverity::High, ConflictSeverity::Medium, ConflictSeverity::Low] {
+        let count = conflicts.iter()
+            .filter(|c| c.kind == ConflictKind::Textual && c.severity == severity)
+            .count();
+        if count > 0 {
+            reasons.push(format!("{} {:?} conflict(s)", count, severity).to_lowercase());
+        }
+    }
+    let same_item = conflicts.iter().filter(|c| c.kind == ConflictKind::SameItem).count();
+    if same_item > 0 {
+        reasons.push(format!("{} same-item conflict(s)", same_item));
+    }
+    let reason = if reasons.is_empty() {
+        "all checks passed, no conflicts predicted".to_string()
+    } else {
+        reasons.join("; ")
+    };
+    (recommendation, reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_repo::init_repo;
+    use std::time::Duration;
+
+    fn checks(failed: &[&str]) -> CheckResults {
+        let status = |name: &str| if failed.contains(&name) {
+            CheckStatus::Fail("broken".to_string())
+        } else {
+            CheckStatus::Pass
+        };
+        CheckResults {
+            cargo_check: status("cargo_check"),
+            cargo_test: status("cargo_test"),
+            clippy: status("clippy"),
+            debug_macros: status("debug_macros"),
+            documentation: CheckStatus::Skipped("no src".to_string()),
+            fmt: status("fmt"),
+            audit: status("audit"),
+            durations: Check::ALL.into_iter()
+                .filter(|&check| check != Check::Documentation)
+                .map(|check| (check, Duration::from_millis(1500)))
+                .collect(),
+            wall_time: Duration::from_secs(3),
+        }
+    }
+
+    fn conflict(file: &str, severity: ConflictSeverity) -> ConflictPrediction {
+        ConflictPrediction {
+            file: file.to_string(),
+            severity,
+            kind: ConflictKind::Textual,
+            overlapping_lines: 2,
+            description: "2 line(s) changed on both sides".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_score_and_recommendation() {
+        let config = CiConfig::default();
+        let thresholds = &config.thresholds;
+        let clean = checks(&[]);
+        assert_eq!(calculate_safety_score(&clean, &[], &config), 100);
+        assert_eq!(generate_recommendation(100, &clean, &[], thresholds), (Recommendation::Safe, "all checks passed, no conflicts predicted".to_string()));
+
+        let conflicts = vec![conflict("a.rs", ConflictSeverity::High), conflict("b.rs", ConflictSeverity::Low)];
+        let failing = checks(&["cargo_test"]);
+        let score = calculate_safety_score(&failing, &conflicts, &config);
+        assert_eq!(score, 60);
+        assert_eq!(
+            generate_recommendation(score, &failing, &conflicts, thresholds),
+            (Recommendation::Caution, "cargo_test failed; 1 high conflict(s); 1 low conflict(s)".to_string())
+        );
+
+        let broken = checks(&["cargo_check", "cargo_test", "clippy", "debug_macros"]);
+        assert_eq!(calculate_safety_score(&broken, &conflicts, &config), 10);
+        let insecure = checks(&["fmt", "audit"]);
+        assert_eq!(calculate_safety_score(&insecure, &[], &config), 80);
+        assert_eq!(generate_recommendation(80, &insecure, &[], thresholds).1, "fmt failed; audit failed");
+        assert_eq!(generate_recommendation(10, &broken, &conflicts, thresholds).0, Recommendation::Danger);
+    }
+
+    #[test]
+    fn test_configured_weights_and_thresholds() {
+        let config: Config = serde_yaml::from_str(
+            "ci:\n  enabled_checks: [cargo_check, cargo_test]\n  weights: { cargo_test: 50 }\n  conflict_penalties: { high: 30 }\n  thresholds: { safe: 95, caution: 10 }\n",
+        ).unwrap();
+        let config = config.ci;
+        assert_eq!(config.weights.cargo_check, 30);
+
+        // A failure of a disabled check costs nothing
+        let failing = checks(&["cargo_test", "clippy"]);
+        let conflicts = vec![conflict("a.rs", ConflictSeverity::High)];
+        let score = calculate_safety_score(&failing, &conflicts, &config);
+        assert_eq!(score, 20);
+        assert_eq!(generate_recommendation(score, &failing, &conflicts, &config.thresholds).0, Recommendation::Caution);
+        assert_eq!(generate_recommendation(90, &failing, &[], &config.thresholds).0, Recommendation::Caution);
+
+        assert!(serde_yaml::from_str::<Config>("ci:\n  enabled_checks: [lint]\n").is_err());
+
+        // Semantic conflicts have their own penalty, whatever their severity
+        let mut config = CiConfig::default();
+        config.conflict_penalties.same_item = 25;
+        let same_item = ConflictPrediction {
+            kind: ConflictKind::SameItem,
+            ..conflict("a.rs", ConflictSeverity::Medium)
+        };
+        let clean = checks(&[]);
+        let conflicts = vec![same_item];
+        assert_eq!(calculate_safety_score(&clean, &conflicts, &config), 75);
+        assert_eq!(generate_recommendation(75, &clean, &conflicts, &config.thresholds).1, "1 same-item conflict(s)");
+    }
+
+    #[test]
+    fn test_disabled_checks_are_skipped() {
+        let worktree = tempfile::tempdir().unwrap();
+        let config = CiConfig { enabled_checks: vec![Check::DebugMacros], ..CiConfig::default() };
+        let results = CheckRunner::new(worktree.path()).unwrap().run_checks(&config);
+        assert_eq!(results.cargo_check, CheckStatus::Skipped("disabled in ci.enabled_checks".to_string()));
+        assert_eq!(results.documentation, CheckStatus::Skipped("disabled in ci.enabled_checks".to_string()));
+        // Enabled, but there is no src/ to check
+        assert!(matches!(results.debug_macros, CheckStatus::Skipped(ref reason) if reason != "disabled in ci.enabled_checks"));
+        assert_eq!(results.durations.keys().collect::<Vec<_>>(), vec![&Check::DebugMacros]);
+    }
+
+    #[test]
+    fn test_check_results_are_cached_until_the_worktree_changes() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path();
+        init_repo(dir, &[("src/lib.rs", "//! Small crate\n")]);
+
+        let workspace = tempfile::tempdir().unwrap();
+        let ci = CI::new(workspace.path().to_path_buf())
+            .with_cache(cache::default_ci_cache_path(workspace.path()));
+        let worktree = dir.to_str().unwrap();
+
+        assert!(ci.cached_report(worktree, Some("main")).unwrap().is_none());
+        let first = ci.report(worktree, Some("main")).unwrap();
+        assert!(!first.cached);
+        let second = ci.report(worktree, Some("main")).unwrap();
+        assert!(second.cached);
+        assert_eq!(second.checks, first.checks);
+        let served = ci.cached_report(worktree, Some("main")).unwrap().unwrap();
+        assert!(served.cached);
+        assert_eq!((served.checks, served.safety_score), (first.checks, first.safety_score));
+
+        std::fs::write(dir.join("src/lib.rs"), "//! Small crate\n\nfn f() {\n    debug!(\"x\");\n}\n").unwrap();
+        let edited = ci.report(worktree, Some("main")).unwrap();
+        assert!(!edited.cached);
+        assert!(edited.checks.debug_macros.is_fail());
+        std::fs::write(dir.join("src/lib.rs"), "//! Edited again\n").unwrap();
+        assert!(ci.cached_report(worktree, Some("main")).unwrap().is_none());
+
+        let uncached = CI::new(workspace.path().to_path_buf()).report(worktree, Some("main")).unwrap();
+        assert!(!uncached.cached);
+    }
+
+    #[test]
+    fn test_text_report() {
+        let report = CIReport {
+            worktree: "feature".to_string(),
+            path: PathBuf::from("/work/feature"),
+            base: "main".to_string(),
+            checks: checks(&["clippy"]),
+            cached: false,
+            conflicts: vec![conflict("src/lib.rs", ConflictSeverity::High)],
+            safety_score: 75,
+            recommendation: Recommendation::Caution,
+            reason: "clippy failed; 1 high conflict(s)".to_string(),
+            config: CiConfig {
+                enabled_checks: vec![Check::CargoCheck, Check::CargoTest, Check::Clippy, Check::DebugMacros, Check::Fmt],
+                ..CiConfig::default()
+            },
+        };
+        assert_eq!(report.to_text(), "CI report: feature (against main)\n\
+            \nChecks (3.0s):\n\
+            \x20 ✅ cargo_check    pass (1.5s)\n\
+            \x20 ✅ cargo_test     pass (1.5s)\n\
+            \x20 ❌ clippy         fail (1.5s): broken\n\
+            \x20 ✅ debug_macros   pass (1.5s)\n\
+            \x20 ⏭️  documentation  skipped: no src\n\
+            \x20 ✅ fmt            pass (1.5s)\n\
+            \x20 ✅ audit          pass (1.5s)\n\
+            \nPredicted conflicts (1):\n\
+            \x20 HIGH   src/lib.rs: 2 line(s) changed on both sides\n\
+            \nSafety score: 75/100\n\
+            Recommendation: CAUTION (clippy failed; 1 high conflict(s))\n\
+            \nScoring: cargo_check -30, cargo_test -20, clippy -10, debug_macros -10, documentation off, fmt -5, audit off; \
+            conflicts high -15, medium -10, low -5, same item -10; safe >= 80, caution >= 60\n");
+    }
+}
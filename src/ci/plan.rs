@@ -0,0 +1,315 @@
+//! Merge order for several worktrees that are ready at once
+//!
+//! `CI::plan_merge_order` reports on every worktree of the workspace (reusing
+//! cached check results like `CI::report`), then predicts conflicts between
+//! each pair of worktrees with `ConflictAnalyzer`: the first worktree's changes,
+//! uncommitted ones included, against the second one's branch (or commit, when
+//! detached). Worktrees on the base branch itself are left out.
+//!
+//! The order is greedy. Each step takes, among the worktrees not merged yet:
+//!
+//! 1. the best recommendation (SAFE before CAUTION before DANGER)
+//! 2. the lowest penalty (`ci.conflict_penalties`) for conflicts with the
+//!    other unmerged worktrees, so it leaves the least behind for them
+//! 3. the highest safety score, then the name
+//!
+//! Each step lists the conflicts predicted with the worktrees merged before
+//! it: those are still to be resolved when it merges.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fmt::Write as _;
+
+use super::{CIReport, ConflictAnalyzer, ConflictKind, ConflictPrediction, ConflictSeverity, Recommendation, CI};
+use crate::config::ConflictPenalties;
+use crate::remove_debug::git_output;
+
+/// Predicted conflicts between two worktrees (empty when they merge cleanly)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PairConflicts {
+    pub first: String,
+    pub second: String,
+    pub conflicts: Vec<ConflictPrediction>,
+}
+
+impl PairConflicts {
+    fn involves(&self, worktree: &str) -> bool {
+        self.first == worktree || self.second == worktree
+    }
+
+    /// The worktree paired with `worktree`
+    fn other(&self, worktree: &str) -> &str {
+        if self.first == worktree { &self.second } else { &self.first }
+    }
+}
+
+/// One merge in the plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeStep {
+    pub worktree: String,
+    pub safety_score: u8,
+    pub recommendation: Recommendation,
+    /// Conflicts with the worktrees merged in earlier steps, still to be
+    /// resolved when this one merges
+    pub remaining_conflicts: Vec<PairConflicts>,
+}
+
+/// Suggested merge order for the worktrees of a workspace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergePlan {
+    /// Branch the worktrees merge into
+    pub base: String,
+    /// Each worktree's own report against the base
+    pub reports: Vec<CIReport>,
+    /// Every pair of worktrees, in report order
+    pub matrix: Vec<PairConflicts>,
+    pub steps: Vec<MergeStep>,
+}
+
+impl CI {
+    /// Reports for every worktree in the workspace except those on the base
+    /// branch; a worktree whose report fails is skipped with a warning
+    pub fn report_all(&self, base: Option<&str>) -> Result<Vec<CIReport>> {
+        Ok(self.report_all_with_refs(base)?.into_iter().map(|(report, _)| report).collect())
+    }
+
+    /// Report on every worktree, predict conflicts between each pair, and
+    /// order the merges to leave as few conflicts as possible
+    pub fn plan_merge_order(&self, base: Option<&str>) -> Result<MergePlan> {
+        let worktrees = self.report_all_with_refs(base)?;
+        let base = match worktrees.first() {
+            Some((report, _)) => report.base.clone(),
+            None => base.unwrap_or_default().to_string(),
+        };
+
+        let mut matrix = Vec::new();
+        for (i, (first, _)) in worktrees.iter().enumerate() {
+            for (second, reference) in &worktrees[i + 1..] {
+                let conflicts = ConflictAnalyzer::new(&first.path).analyze(reference).unwrap_or_else(|error| {
+                    tracing::warn!("No conflict prediction for {} and {}: {:#}", first.worktree, second.worktree, error);
+                    Vec::new()
+                });
+                matrix.push(PairConflicts {
+                    first: first.worktree.clone(),
+                    second: second.worktree.clone(),
+                    conflicts,
+                });
+            }
+        }
+
+        let reports: Vec<CIReport> = worktrees.into_iter().map(|(report, _)| report).collect();
+        let steps = order_merges(&reports, &matrix, &self.config.conflict_penalties);
+        Ok(MergePlan { base, reports, matrix, steps })
+    }
+
+    /// Each worktree's report and the ref other worktrees are compared with
+    fn report_all_with_refs(&self, base: Option<&str>) -> Result<Vec<(CIReport, String)>> {
+        let monitor = self.worktree_monitor()?;
+        let worktrees: Vec<_> = monitor.scan_worktrees()?.into_iter().filter(|w| !w.prunable).collect();
+        let Some(first) = worktrees.first() else {
+            return Ok(Vec::new());
+        };
+        // Worktrees share refs, so the base resolves the same from any of them;
+        // knowing it first spares the base branch's worktree its checks
+        let base = self.base_for(&first.path, base)?;
+
+        let mut reports = Vec::new();
+        for worktree in worktrees {
+            if worktree.branch.as_deref() == Some(base.as_str()) {
+                continue;
+            }
+            let mut report = match self.report(&worktree.path.to_string_lossy(), Some(&base)) {
+                Ok(report) => report,
+                Err(error) => {
+                    tracing::warn!("Skipping worktree {}: {:#}", worktree.name, error);
+                    continue;
+                }
+            };
+            let reference = match worktree.branch {
+                Some(branch) => branch,
+                None => git_output(&worktree.path, &["rev-parse", "HEAD"])?.trim().to_string(),
+            };
+            report.worktree = worktree.name;
+            reports.push((report, reference));
+        }
+        Ok(reports)
+    }
+}
+
+/// Greedy merge order over `reports` (see the module docs)
+fn order_merges(reports: &[CIReport], matrix: &[PairConflicts], penalties: &ConflictPenalties) -> Vec<MergeStep> {
+    let pair_penalty = |pair: &PairConflicts| -> u32 { pair.conflicts.iter().map(|c| penalties.penalty(c)).sum() };
+    let mut pending: Vec<&CIReport> = reports.iter().collect();
+    let mut merged: Vec<&str> = Vec::new();
+    let mut steps = Vec::new();
+
+    while !pending.is_empty() {
+        let unmerged_penalty = |report: &CIReport| -> u32 {
+            matrix.iter()
+                .filter(|pair| pair.involves(&report.worktree))
+                .filter(|pair| pending.iter().any(|other| other.worktree == pair.other(&report.worktree)))
+                .map(pair_penalty)
+                .sum()
+        };
+        let (index, _) = pending.iter()
+            .enumerate()
+            .min_by_key(|(_, report)| (
+                report.recommendation,
+                unmerged_penalty(report),
+                Reverse(report.safety_score),
+                report.worktree.as_str(),
+            ))
+            .expect("pending is not empty");
+        let report = pending.remove(index);
+
+        let remaining_conflicts = matrix.iter()
+            .filter(|pair| pair.involves(&report.worktree) && !pair.conflicts.is_empty())
+            .filter(|pair| merged.contains(&pair.other(&report.worktree)))
+            .cloned()
+            .collect();
+        merged.push(&report.worktree);
+        steps.push(MergeStep {
+            worktree: report.worktree.clone(),
+            safety_score: report.safety_score,
+            recommendation: report.recommendation,
+            remaining_conflicts,
+        });
+    }
+    steps
+}
+
+impl MergePlan {
+    /// Conflict matrix and merge order for the terminal
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Merge plan: {} worktree(s) into {}", self.reports.len(), self.base);
+        if self.reports.is_empty() {
+            return out;
+        }
+
+        let names: Vec<&str> = self.reports.iter().map(|report| report.worktree.as_str()).collect();
+        let width = names.iter().map(|name| name.chars().count()).max().unwrap_or(0).max(8);
+        let _ = write!(out, "\nPairwise conflicts:\n  {:width$}", "", width = width);
+        for name in &names {
+            let _ = write!(out, "  {:>width$}", name, width = width);
+        }
+        out.push('\n');
+        for row in &names {
+            let _ = write!(out, "  {:width$}", row, width = width);
+            for column in &names {
+                let cell = if row == column {
+                    "-".to_string()
+                } else {
+                    self.pair(row, column).map_or_else(|| ".".to_string(), |pair| matrix_cell(&pair.conflicts))
+                };
+                let _ = write!(out, "  {:>width$}", cell, width = width);
+            }
+            out.push('\n');
+        }
+
+        let _ = writeln!(out, "\nMerge order:");
+        for (number, step) in self.steps.iter().enumerate() {
+            let _ = writeln!(
+                out, "  {}. {:width$}  {:<7} {:>3}/100",
+                number + 1, step.worktree, step.recommendation.to_string(), step.safety_score, width = width
+            );
+            for pair in &step.remaining_conflicts {
+                let files: Vec<String> = pair.conflicts.iter().map(describe).collect();
+                let _ = writeln!(out, "       conflicts with {}: {}", pair.other(&step.worktree), files.join(", "));
+            }
+        }
+        out
+    }
+
+    /// Conflicts between two worktrees, in either order
+    pub fn pair(&self, a: &str, b: &str) -> Option<&PairConflicts> {
+        self.matrix.iter().find(|pair| pair.involves(a) && pair.other(a) == b && a != b)
+    }
+}
+
+/// "." for a clean pair, else the count and the worst severity, e.g. "2 high"
+fn matrix_cell(conflicts: &[ConflictPrediction]) -> String {
+    match conflicts.iter().map(|c| c.severity).max() {
+        None => ".".to_string(),
+        Some(worst) => format!("{} {}", conflicts.len(), severity_name(worst)),
+    }
+}
+
+/// "src/lib.rs (high)", or "(same item)" for semantic conflicts
+fn describe(conflict: &ConflictPrediction) -> String {
+    match conflict.kind {
+        ConflictKind::SameItem => format!("{} (same item)", conflict.file),
+        ConflictKind::Textual => format!("{} ({})", conflict.file, severity_name(conflict.severity)),
+    }
+}
+
+fn severity_name(severity: ConflictSeverity) -> &'static str {
+    match severity {
+        ConflictSeverity::High => "high",
+        ConflictSeverity::Medium => "medium",
+        ConflictSeverity::Low => "low",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::{git, init_repo};
+    use std::fs;
+
+    /// Three worktrees: `alpha` and `beta` edit the same line of a.txt, `gamma`
+    /// only adds its own file
+    fn workspace() -> tempfile::TempDir {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path();
+        init_repo(repo, &[("a.txt", "one\ntwo\nthree\n")]);
+
+        for (name, file, contents) in [
+            ("alpha", "a.txt", "one\nALPHA\nthree\n"),
+            ("beta", "a.txt", "one\nBETA\nthree\n"),
+            ("gamma", "g.txt", "gamma\n"),
+        ] {
+            let path = repo.join("worktrees").join(name);
+            git(repo, &["worktree", "add", "-q", "-b", name, path.to_str().unwrap()]);
+            fs::write(path.join(file), contents).unwrap();
+            git(&path, &["add", "."]);
+            git(&path, &["commit", "-qm", name]);
+        }
+        temp
+    }
+
+    #[test]
+    fn test_plan_orders_clean_worktrees_first() {
+        let temp = workspace();
+        let config = crate::config::CiConfig { enabled_checks: Vec::new(), ..Default::default() };
+        let cache_path = crate::ci::cache::default_ci_cache_path(temp.path());
+        let plan = CI::with_config(temp.path().to_path_buf(), config)
+            .with_cache(cache_path.clone())
+            .plan_merge_order(Some("main"))
+            .unwrap();
+
+        assert_eq!(plan.base, "main");
+        // The worktree on main is skipped before its checks run
+        let cache: serde_json::Value = serde_json::from_str(&fs::read_to_string(&cache_path).unwrap()).unwrap();
+        assert_eq!(cache.as_object().unwrap().len(), 3);
+        assert!(cache.get(temp.path().to_str().unwrap()).is_none());
+        let names: Vec<&str> = plan.reports.iter().map(|r| r.worktree.as_str()).collect();
+        assert_eq!(names, vec!["alpha", "beta", "gamma"]);
+        assert_eq!(plan.matrix.len(), 3);
+        let clash = plan.pair("beta", "alpha").unwrap();
+        assert_eq!(clash.conflicts.len(), 1);
+        assert_eq!(clash.conflicts[0].severity, ConflictSeverity::High);
+        assert!(plan.pair("alpha", "gamma").unwrap().conflicts.is_empty());
+
+        let order: Vec<&str> = plan.steps.iter().map(|s| s.worktree.as_str()).collect();
+        assert_eq!(order, vec!["gamma", "alpha", "beta"]);
+        assert!(plan.steps[0].remaining_conflicts.is_empty());
+        assert!(plan.steps[1].remaining_conflicts.is_empty());
+        assert_eq!(plan.steps[2].remaining_conflicts, vec![clash.clone()]);
+
+        let text = plan.to_text();
+        assert!(text.contains("\n  alpha            -    1 high         .\n"), "{}", text);
+        assert!(text.contains("  3. beta      SAFE    100/100\n       conflicts with alpha: a.txt (high)\n"), "{}", text);
+    }
+}
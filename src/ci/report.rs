@@ -0,0 +1,180 @@
+//! Markdown renderer for `CIReport`, for PR comments
+//!
+//! The report opens with the score and recommendation as a badge-like heading,
+//! then a table with one row per check and the predicted conflicts in a
+//! collapsible `<details>` section. Durations are left out and a timestamp is
+//! only added on request (`to_markdown_at`), so the same worktree state always
+//! renders the same text and bots can skip posting a duplicate comment.
+//!
+//! Output is checked against golden files in `tests/golden/`; run the tests with
+//! `UPDATE_GOLDEN=1` to regenerate them after an intentional formatting change.
+
+use super::{CIReport, CheckStatus, ConflictKind, ConflictSeverity, Recommendation};
+use crate::monitor::report::escape_markdown;
+
+impl CIReport {
+    /// Render the report as Markdown, e.g. for a PR comment
+    pub fn to_markdown(&self) -> String {
+        self.render_markdown(None)
+    }
+
+    /// `to_markdown` with a "Generated" line for `timestamp`
+    pub fn to_markdown_at(&self, timestamp: &str) -> String {
+        self.render_markdown(Some(timestamp))
+    }
+
+    fn render_markdown(&self, timestamp: Option<&str>) -> String {
+        let badge = match self.recommendation {
+            Recommendation::Safe => "🟢",
+            Recommendation::Caution => "🟡",
+            Recommendation::Danger => "🔴",
+        };
+        let mut out = format!(
+            "## {} {} · {}/100\n\n`{}` against `{}`\n",
+            badge, self.recommendation, self.safety_score, self.worktree, self.base
+        );
+        if let Some(timestamp) = timestamp {
+            out.push_str(&format!("\n_Generated {}_\n", timestamp));
+        }
+        out.push_str(&format!("\n**{}**: {}\n", self.recommendation, escape_markdown(&self.reason)));
+
+        out.push_str("\n| Check | Status | Details |\n|---|---|---|\n");
+        for (check, status) in self.checks.iter() {
+            let (status, details) = match status {
+                CheckStatus::Pass => ("✅ pass", String::new()),
+                CheckStatus::Fail(reason) => ("❌ fail", escape_markdown(reason)),
+                CheckStatus::Skipped(reason) => ("⏭️ skipped", escape_markdown(reason)),
+            };
+            out.push_str(&format!("| {} | {} | {} |\n", check, status, details));
+        }
+
+        if self.conflicts.is_empty() {
+            out.push_str(&format!("\nNo conflicts predicted with `{}`.\n", self.base));
+        } else {
+            out.push_str(&format!(
+                "\n<details>\n<summary>{} predicted conflict(s) with <code>{}</code></summary>\n\n",
+                self.conflicts.len(), self.base
+            ));
+            out.push_str("| File | Severity | Overlapping lines | Description |\n|---|---|---:|---|\n");
+            for conflict in &self.conflicts {
+                let severity = match conflict.severity {
+                    ConflictSeverity::High => "🔴 high",
+                    ConflictSeverity::Medium => "🟡 medium",
+                    ConflictSeverity::Low => "⚪ low",
+                };
+                let kind = match conflict.kind {
+                    ConflictKind::Textual => "",
+                    ConflictKind::SameItem => " (same item)",
+                };
+                out.push_str(&format!(
+                    "| `{}` | {}{} | {} | {} |\n",
+                    escape_markdown(&conflict.file), severity, kind, conflict.overlapping_lines,
+                    escape_markdown(&conflict.description)
+                ));
+            }
+            out.push_str("\n</details>\n");
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ci::{Check, CheckResults, ConflictPrediction};
+    use crate::config::CiConfig;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn report(checks: CheckResults, conflicts: Vec<ConflictPrediction>, score: u8, recommendation: Recommendation, reason: &str) -> CIReport {
+        CIReport {
+            worktree: "feature-x".to_string(),
+            path: PathBuf::from("/work/feature-x"),
+            base: "main".to_string(),
+            checks,
+            cached: false,
+            conflicts,
+            safety_score: score,
+            recommendation,
+            reason: reason.to_string(),
+            config: CiConfig::default(),
+        }
+    }
+
+    fn passing() -> CIReport {
+        let checks = CheckResults {
+            cargo_check: CheckStatus::Pass,
+            cargo_test: CheckStatus::Pass,
+            clippy: CheckStatus::Pass,
+            debug_macros: CheckStatus::Pass,
+            documentation: CheckStatus::Skipped("no src/ in the worktree".to_string()),
+            fmt: CheckStatus::Pass,
+            audit: CheckStatus::Skipped("cargo-audit is not installed (cargo install cargo-audit)".to_string()),
+            durations: Check::ALL[..4].iter().map(|&check| (check, Duration::from_secs(2))).collect(),
+            wall_time: Duration::from_secs(3),
+        };
+        report(checks, Vec::new(), 100, Recommendation::Safe, "all checks passed, no conflicts predicted")
+    }
+
+    fn failing() -> CIReport {
+        let checks = CheckResults {
+            cargo_check: CheckStatus::Pass,
+            cargo_test: CheckStatus::Fail("1 test(s) failed: parser::tests::test_empty".to_string()),
+            clippy: CheckStatus::Fail("1 error(s): error: this `if` has identical blocks | see #[deny]".to_string()),
+            debug_macros: CheckStatus::Pass,
+            documentation: CheckStatus::Pass,
+            fmt: CheckStatus::Fail("1 unformatted file(s): src/parser.rs".to_string()),
+            audit: CheckStatus::Fail("1 advisory(ies): RUSTSEC-2020-0071 (time, medium 6.2)".to_string()),
+            durations: Default::default(),
+            wall_time: Duration::ZERO,
+        };
+        let conflicts = vec![
+            ConflictPrediction {
+                file: "src/parser.rs".to_string(),
+                severity: ConflictSeverity::High,
+                kind: ConflictKind::Textual,
+                overlapping_lines: 4,
+                description: "4 line(s) changed on both sides".to_string(),
+            },
+            ConflictPrediction {
+                file: "src/lib.rs".to_string(),
+                severity: ConflictSeverity::Medium,
+                kind: ConflictKind::SameItem,
+                overlapping_lines: 0,
+                description: "both sides changed `fn parse`".to_string(),
+            },
+        ];
+        report(checks, conflicts, 25, Recommendation::Danger, "cargo_test failed; clippy failed; fmt failed; audit failed; 1 high conflict(s); 1 same-item conflict(s)")
+    }
+
+    /// Compare against a golden file, or rewrite it when `UPDATE_GOLDEN` is set
+    fn assert_golden(name: &str, actual: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(name);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("missing golden file {}", path.display()));
+        assert_eq!(actual, expected, "{} changed; rerun with UPDATE_GOLDEN=1 if intended", name);
+    }
+
+    #[test]
+    fn test_passing_report_matches_golden() {
+        assert_golden("ci_report_passing.md", &passing().to_markdown());
+    }
+
+    #[test]
+    fn test_failing_report_matches_golden() {
+        assert_golden("ci_report_failing.md", &failing().to_markdown());
+    }
+
+    #[test]
+    fn test_markdown_is_deterministic() {
+        let mut rerun = passing();
+        rerun.checks.durations.clear();
+        rerun.checks.wall_time = Duration::from_secs(40);
+        assert_eq!(rerun.to_markdown(), passing().to_markdown());
+        assert!(passing().to_markdown_at("2024-06-01T10:00:00Z").contains("\n_Generated 2024-06-01T10:00:00Z_\n"));
+    }
+}
@@ -0,0 +1,435 @@
+//! Pre-PR sweep (`autodebugger clean`): remove debug calls, validate the docs,
+//! and reformat what changed
+//!
+//! [`Cleaner`] runs the enabled [`CleanStep`]s (`clean.steps`, or `--steps`)
+//! in a fixed order and gathers their results in one [`CleanReport`]:
+//!
+//! 1. `remove_debug`: a `remove-debug` run over the paths with the
+//!    `remove_debug` settings, backups included. It fails when calls are left:
+//!    ambiguous ones, or in dry-run mode any at all.
+//! 2. `validate_docs`: a `validate-docs` run with the docs baseline of the
+//!    working directory. It fails on errors, and with `strict` on warnings too.
+//! 3. `fmt`: `rustfmt` over the files the first step modified, one at a time
+//!    through `Autodebugger` (with the crate's edition, reading the file from
+//!    stdin so its child modules are left alone). In dry-run mode it only
+//!    counts them.
+//!
+//! A step that fails, or cannot run, does not stop the ones after it unless
+//! `fail_fast` is set; the report passes only when every step that ran passed.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::backup::BackupStore;
+use crate::remove_debug::{DebugRemover, RemovalReport};
+use crate::validate_docs::{DocValidator, DocsBaseline, ValidationReport};
+use crate::{Autodebugger, Config, RedactionConfig};
+
+/// A step of the sweep, named as in `clean.steps`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanStep {
+    RemoveDebug,
+    ValidateDocs,
+    Fmt,
+}
+
+impl CleanStep {
+    /// Every step, in running order
+    pub const ALL: [CleanStep; 3] = [CleanStep::RemoveDebug, CleanStep::ValidateDocs, CleanStep::Fmt];
+
+    /// Name in config.yaml and reports, e.g. "remove_debug"
+    pub fn name(self) -> &'static str {
+        match self {
+            CleanStep::RemoveDebug => "remove_debug",
+            CleanStep::ValidateDocs => "validate_docs",
+            CleanStep::Fmt => "fmt",
+        }
+    }
+}
+
+impl fmt::Display for CleanStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl std::str::FromStr for CleanStep {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        CleanStep::ALL.into_iter()
+            .find(|step| step.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = CleanStep::ALL.iter().map(|step| step.name()).collect();
+                anyhow::anyhow!("Unknown step '{}' (expected one of {})", s, names.join(", "))
+            })
+    }
+}
+
+/// How one step ended, with a one-line summary (or the error that stopped it)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepOutcome {
+    pub step: CleanStep,
+    pub passed: bool,
+    pub summary: String,
+}
+
+/// Results of every step of a sweep
+#[derive(Debug, Default)]
+pub struct CleanReport {
+    pub dry_run: bool,
+    /// Steps that ran, in order
+    pub steps: Vec<StepOutcome>,
+    /// Steps not run because an earlier one failed with `fail_fast`
+    pub skipped: Vec<CleanStep>,
+    pub removal: Option<RemovalReport>,
+    pub docs: Option<ValidationReport>,
+    /// Files the `fmt` step reformatted
+    pub formatted: Vec<PathBuf>,
+}
+
+impl CleanReport {
+    /// Every step that ran passed
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(|step| step.passed)
+    }
+
+    /// Debug calls removed and rewritten (or, in dry-run mode, found)
+    pub fn debug_calls(&self) -> usize {
+        self.removal.as_ref().map_or(0, |report| report.total_calls_removed + report.total_calls_rewritten)
+    }
+
+    /// Documentation warnings left after the sweep
+    pub fn doc_warnings(&self) -> usize {
+        self.docs.as_ref().map_or(0, |report| report.warnings)
+    }
+
+    /// The verdict and totals, each step's outcome, and the full reports of
+    /// the remove_debug and validate_docs steps
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "passed": self.passed(),
+            "dry_run": self.dry_run,
+            "debug_calls": self.debug_calls(),
+            "doc_errors": self.docs.as_ref().map_or(0, |report| report.errors),
+            "doc_warnings": self.doc_warnings(),
+            "formatted": self.formatted,
+            "steps": self.steps,
+            "skipped": self.skipped,
+            "remove_debug": self.removal,
+            "validate_docs": self.docs.as_ref().map(ValidationReport::to_json),
+        })
+    }
+
+    /// One line per step, then the verdict
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for outcome in &self.steps {
+            let status = if outcome.passed { "ok" } else { "failed" };
+            out.push_str(&format!("  {:<14} {:<7} {}\n", outcome.step, status, outcome.summary));
+        }
+        for step in &self.skipped {
+            out.push_str(&format!("  {:<14} skipped (--fail-fast)\n", step));
+        }
+        let failed = self.steps.iter().filter(|outcome| !outcome.passed).count();
+        let ran = self.steps.len();
+        if failed == 0 {
+            out.push_str(&format!("Clean passed: {} step(s){}\n", ran, if self.dry_run { " (dry run)" } else { "" }));
+        } else {
+            out.push_str(&format!("Clean failed: {} of {} step(s) failed\n", failed, ran));
+        }
+        out
+    }
+}
+
+/// Runs the sweep over `paths` (each step's `default_paths` when empty)
+pub struct Cleaner {
+    config: Config,
+    paths: Vec<PathBuf>,
+    steps: Vec<CleanStep>,
+    dry_run: bool,
+    fail_fast: bool,
+    strict: bool,
+}
+
+impl Cleaner {
+    /// A sweep of the steps in `config.clean.steps`, with the settings of each
+    /// step's own section
+    pub fn new(config: Config) -> Self {
+        let steps = config.clean.steps.clone();
+        Self { config, paths: Vec::new(), steps, dry_run: false, fail_fast: false, strict: false }
+    }
+
+    pub fn with_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.paths = paths;
+        self
+    }
+
+    /// Steps to run; they still run in `CleanStep::ALL` order
+    pub fn with_steps(mut self, steps: Vec<CleanStep>) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    /// Report what each step would change without writing any file
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Skip the remaining steps once one fails
+    pub fn with_fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    /// Fail the validate_docs step on warnings too
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn run(&self) -> CleanReport {
+        let mut report = CleanReport { dry_run: self.dry_run, ..CleanReport::default() };
+        for step in CleanStep::ALL.into_iter().filter(|step| self.steps.contains(step)) {
+            if self.fail_fast && !report.passed() {
+                report.skipped.push(step);
+                continue;
+            }
+            let outcome = match step {
+                CleanStep::RemoveDebug => self.remove_debug(&mut report),
+                CleanStep::ValidateDocs => self.validate_docs(&mut report),
+                CleanStep::Fmt => self.fmt(&mut report),
+            };
+            let (passed, summary) = outcome.unwrap_or_else(|error| (false, format!("{:#}", error)));
+            report.steps.push(StepOutcome { step, passed, summary });
+        }
+        report
+    }
+
+    /// The given paths, or `defaults`
+    fn paths_or(&self, defaults: &[String]) -> Vec<PathBuf> {
+        if self.paths.is_empty() {
+            defaults.iter().map(PathBuf::from).collect()
+        } else {
+            self.paths.clone()
+        }
+    }
+
+    fn remove_debug(&self, report: &mut CleanReport) -> Result<(bool, String)> {
+        let config = &self.config.remove_debug;
+        let mut paths = self.paths_or(&config.default_paths);
+        if self.paths.is_empty() {
+            // As on the command line, default paths that do not exist here are skipped
+            paths.retain(|path| path.exists());
+        }
+        let backup_run = BackupStore::new_run_id();
+        let mut total = RemovalReport::default();
+        for path in paths {
+            total.merge(DebugRemover::new(path)
+                .with_dry_run(self.dry_run)
+                .with_macros(config.macros.iter().cloned())
+                .with_backups(config.backups)
+                .with_backup_run(backup_run.clone())
+                .with_backup_retention(config.backup_retention)
+                .with_jobs(config.jobs)
+                .with_tidy_whitespace(config.tidy_whitespace)
+                .with_engine(config.engine)
+                .remove_debug_calls()?);
+        }
+
+        let calls = total.total_calls_removed + total.total_calls_rewritten;
+        let files = total.file_reports.iter().filter(|(_, file)| file.is_modified()).count();
+        let mut summary = if self.dry_run {
+            format!("{} debug call(s) to remove in {} file(s)", calls, files)
+        } else {
+            format!("{} debug call(s) removed from {} file(s)", calls, files)
+        };
+        if total.total_warnings > 0 {
+            summary.push_str(&format!(", {} ambiguous call(s) left", total.total_warnings));
+        }
+        if !total.failures.is_empty() {
+            summary.push_str(&format!(", {} file(s) could not be processed", total.failures.len()));
+        }
+        let passed = total.failures.is_empty() && total.total_warnings == 0 && (!self.dry_run || calls == 0);
+        report.removal = Some(total);
+        Ok((passed, summary))
+    }
+
+    fn validate_docs(&self, report: &mut CleanReport) -> Result<(bool, String)> {
+        let config = &self.config.validate_docs;
+        let validator = DocValidator::from_config(config.clone())?
+            .with_require_pub_item_docs(config.require_pub_item_docs)
+            .with_check_references(config.check_references)
+            .with_check_config_snippets(config.check_config_snippets)
+            .with_jobs(config.jobs)
+            .with_strict(self.strict)
+            .with_baseline(DocsBaseline::load(&DocsBaseline::path(&std::env::current_dir()?))?);
+        let docs = validator.validate_paths(self.paths_or(&config.default_paths))?;
+        let summary = format!(
+            "{} error(s) and {} warning(s) remaining in {} file(s)",
+            docs.errors, docs.warnings, docs.files_scanned
+        );
+        let passed = docs.passed(self.strict);
+        report.docs = Some(docs);
+        Ok((passed, summary))
+    }
+
+    fn fmt(&self, report: &mut CleanReport) -> Result<(bool, String)> {
+        let touched: Vec<PathBuf> = report.removal.iter()
+            .flat_map(|removal| &removal.file_reports)
+            .filter(|(path, file)| file.is_modified() && path.extension().is_some_and(|extension| extension == "rs"))
+            .map(|(path, _)| path.clone())
+            .collect();
+        if touched.is_empty() {
+            return Ok((true, "no touched files to format".to_string()));
+        }
+        if self.dry_run {
+            return Ok((true, format!("{} touched file(s) to format", touched.len())));
+        }
+
+        // Formatted code must come back as rustfmt wrote it
+        let redaction = RedactionConfig { redact_output: false, ..self.config.redaction.clone() };
+        // run_command moves the process into each crate; relative paths stay the caller's
+        let cwd = std::env::current_dir()?;
+        let mut failures = Vec::new();
+        for path in &touched {
+            match format_file(path, &redaction) {
+                Ok(true) => report.formatted.push(path.clone()),
+                Ok(false) => {}
+                Err(error) => failures.push(format!("{}: {:#}", path.display(), error)),
+            }
+        }
+        std::env::set_current_dir(&cwd).context("Failed to restore the working directory")?;
+        let mut summary = format!("{} of {} touched file(s) reformatted", report.formatted.len(), touched.len());
+        if !failures.is_empty() {
+            summary.push_str(&format!("; rustfmt failed on {}", failures.join("; ")));
+        }
+        Ok((failures.is_empty(), summary))
+    }
+}
+
+/// Run rustfmt on `path` from its crate's directory, writing the file back if
+/// it changed; true when it did
+fn format_file(path: &Path, redaction: &RedactionConfig) -> Result<bool> {
+    let path = path.canonicalize().with_context(|| format!("Failed to resolve {}", path.display()))?;
+    let original = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let manifest = path.ancestors().skip(1).find(|dir| dir.join("Cargo.toml").is_file());
+    let dir = manifest.or(path.parent()).unwrap_or(Path::new("."));
+    let edition = manifest.and_then(package_edition).unwrap_or_else(|| "2021".to_string());
+
+    let debugger = Autodebugger::with_working_dir(dir.to_path_buf()).with_redaction(redaction)?;
+    let command = format!(
+        "rustfmt --edition {} --emit stdout < {}",
+        edition, crate::hooks::shell_quote(&path.to_string_lossy())
+    );
+    let result = debugger.run_command(&command)?;
+    if !result.success {
+        anyhow::bail!("{}", result.stderr);
+    }
+    // The captured output loses its final newline
+    let formatted = format!("{}\n", result.stdout);
+    if formatted == original {
+        return Ok(false);
+    }
+    fs::write(&path, formatted).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(true)
+}
+
+/// `package.edition` from the Cargo.toml in `dir`, if it sets one literally
+fn package_edition(dir: &Path) -> Option<String> {
+    let manifest = fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+    let mut in_package = false;
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+        } else if let Some(value) = line.strip_prefix("edition").map(str::trim_start) {
+            if in_package {
+                return value.strip_prefix('=')
+                    .map(|value| value.trim().trim_matches('"').to_string())
+                    .filter(|edition| edition.chars().all(|c| c.is_ascii_digit()));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_edition() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "[workspace]\nedition = \"2015\"\n\n[package]\nname = \"x\"\nedition = \"2024\"\n").unwrap();
+        assert_eq!(package_edition(dir.path()).as_deref(), Some("2024"));
+        fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"x\"\nedition.workspace = true\n").unwrap();
+        assert_eq!(package_edition(dir.path()), None);
+    }
+
+    #[test]
+    fn test_sweep_removes_validates_and_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir(&src).unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"swept\"\nedition = \"2021\"\n").unwrap();
+        let touched = src.join("lib.rs");
+        fs::write(&touched, "//! Small crate\n\npub fn f( ) -> u8 {\n    debug!(\"x\");\n    1\n}\n").unwrap();
+        // Not touched by remove_debug, so fmt leaves it alone
+        let untouched = src.join("other.rs");
+        fs::write(&untouched, "pub fn g( ) {}\n").unwrap();
+
+        let mut config = Config::default();
+        config.remove_debug.backups = false;
+        let cleaner = Cleaner::new(config).with_paths(vec![src.clone()]);
+
+        let dry = cleaner.with_dry_run(true).run();
+        assert!(!dry.passed());
+        assert_eq!(dry.debug_calls(), 1);
+        assert_eq!(dry.steps[2].summary, "1 touched file(s) to format");
+        assert!(fs::read_to_string(&touched).unwrap().contains("debug!"));
+
+        let mut config = Config::default();
+        config.remove_debug.backups = false;
+        let report = Cleaner::new(config).with_paths(vec![src.clone()]).run();
+        let names: Vec<CleanStep> = report.steps.iter().map(|outcome| outcome.step).collect();
+        assert_eq!(names, CleanStep::ALL);
+        assert_eq!(report.steps[0].summary, "1 debug call(s) removed from 1 file(s)");
+        assert!(report.steps[1].passed, "{:?}", report.steps[1]);
+        if report.steps[2].passed {
+            assert_eq!(report.formatted, vec![touched.clone()]);
+            assert_eq!(fs::read_to_string(&touched).unwrap(), "//! Small crate\n\npub fn f() -> u8 {\n    1\n}\n");
+        } else {
+            // Without rustfmt the step fails and says why
+            assert!(report.steps[2].summary.contains("rustfmt failed"), "{:?}", report.steps[2]);
+        }
+        assert_eq!(fs::read_to_string(&untouched).unwrap(), "pub fn g( ) {}\n");
+        assert_eq!(report.to_json()["debug_calls"], 1);
+    }
+
+    #[test]
+    fn test_fail_fast_skips_the_rest() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("lib.rs"), "fn f() {\n    debug!(\"x\");\n}\n").unwrap();
+        let report = Cleaner::new(Config::default())
+            .with_paths(vec![dir.path().to_path_buf()])
+            .with_dry_run(true)
+            .with_fail_fast(true)
+            .run();
+        assert_eq!(report.steps.len(), 1);
+        assert_eq!(report.skipped, vec![CleanStep::ValidateDocs, CleanStep::Fmt]);
+        assert!(report.to_text().ends_with("Clean failed: 1 of 1 step(s) failed\n"), "{}", report.to_text());
+
+        let missing = Cleaner::new(Config::default())
+            .with_paths(vec![dir.path().join("missing")])
+            .with_steps(vec![CleanStep::ValidateDocs, CleanStep::RemoveDebug])
+            .run();
+        // A step that cannot run fails without stopping the next
+        assert_eq!(missing.steps.len(), 2);
+        assert!(missing.steps.iter().all(|outcome| !outcome.passed), "{:?}", missing.steps);
+    }
+}
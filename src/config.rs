@@ -1,19 +1,98 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
 
 /// Main configuration structure
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct Config {
     #[serde(default)]
     pub verbosity: VerbosityConfig,
     
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    
     #[serde(default)]
     pub remove_debug: RemoveDebugConfig,
     
     #[serde(default)]
     pub validate_docs: ValidateDocsConfig,
+    
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    
+    #[serde(default)]
+    pub todos: TodosConfig,
+    
+    #[serde(default)]
+    pub monitor: MonitorConfig,
+    
+    #[serde(default)]
+    pub ci: CiConfig,
+    
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    
+    #[serde(default)]
+    pub server: ServerConfig,
+    
+    #[serde(default)]
+    pub clean: CleanConfig,
+    
+    /// Named command sequences run by `autodebugger task <name>`
+    #[serde(default)]
+    pub tasks: BTreeMap<String, Vec<TaskStep>>,
+    
+    /// Fail loading on keys no field reads instead of warning about them
+    /// (the CLI's `--strict-config` does the same)
+    #[serde(default)]
+    pub strict: bool,
+    
+    /// When layering config files, append a file's lists to the ones below it
+    /// instead of replacing them (the topmost file setting this decides)
+    #[serde(default)]
+    pub merge_lists: bool,
+    
+    /// Named overlays, each a partial config merged over the rest when selected
+    /// with `--profile` or `AUTODEBUGGER_PROFILE` (see `with_profile`)
+    #[serde(default)]
+    pub profiles: BTreeMap<String, serde_yaml::Value>,
+    
+    /// Files this configuration was read from, lowest layer first; empty for defaults
+    #[serde(skip)]
+    pub source_paths: Vec<PathBuf>,
+    
+    /// Profile applied by `with_profile`
+    #[serde(skip)]
+    pub profile: Option<String>,
+    
+    /// Keys in those files no field reads, e.g. misspelled ones
+    #[serde(skip)]
+    pub unknown_keys: Vec<UnknownKey>,
+}
+
+/// A key in a config file that no config field reads
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownKey {
+    /// File the key is in
+    pub file: PathBuf,
+    /// Dotted path, e.g. `validate_docs.complexity_treshold`
+    pub key: String,
+    /// Line of the file (1-based)
+    pub line: usize,
+    /// Closest valid key at the same level, if one is close enough to be a typo
+    pub suggestion: Option<String>,
+}
+
+impl std::fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: unknown key `{}`", self.file.display(), self.line, self.key)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " (did you mean `{}`?)", suggestion)?;
+        }
+        Ok(())
+    }
 }
 
 /// Configuration for remove-debug command
@@ -22,6 +101,30 @@ pub struct RemoveDebugConfig {
     /// Default paths to search when no path is specified
     #[serde(default = "default_remove_debug_paths")]
     pub default_paths: Vec<String>,
+    
+    /// Macro names (without `!`) to remove
+    #[serde(default = "crate::remove_debug::default_macros")]
+    pub macros: Vec<String>,
+    
+    /// Back up files to `.autodebugger/backups` before modifying them
+    #[serde(default = "default_true")]
+    pub backups: bool,
+    
+    /// Number of backup runs kept; older ones are pruned
+    #[serde(default = "crate::remove_debug::default_backup_retention")]
+    pub backup_retention: usize,
+    
+    /// Files processed in parallel (0 = one per CPU)
+    #[serde(default)]
+    pub jobs: usize,
+    
+    /// Collapse blank lines left behind by removals
+    #[serde(default = "default_true")]
+    pub tidy_whitespace: bool,
+    
+    /// How calls are located: `regex`, or `ast` when built with the `ast` feature
+    #[serde(default)]
+    pub engine: crate::remove_debug::Engine,
 }
 
 /// Configuration for validate-docs command
@@ -43,11 +146,416 @@ pub struct ValidateDocsConfig {
     #[serde(default = "default_complexity_threshold")]
     pub complexity_threshold: usize,
     
+    /// Compare raw line counts (blanks and comments included) against the threshold
+    #[serde(default)]
+    pub use_raw_line_count: bool,
+    
     /// Glob patterns to ignore (e.g., "**/tests/**")
     #[serde(default = "default_ignore_patterns")]
     pub ignore_patterns: Vec<String>,
+    
+    /// Require `///` docs on public functions, structs, enums, and traits
+    #[serde(default)]
+    pub require_pub_item_docs: bool,
+    
+    /// Glob patterns of item names exempt from `require_pub_item_docs`
+    #[serde(default = "default_pub_item_allowlist")]
+    pub pub_item_allowlist: Vec<String>,
+    
+    /// Thresholds for files matching a glob; the first matching entry wins
+    #[serde(default)]
+    pub overrides: Vec<crate::validate_docs::ThresholdOverride>,
+    
+    /// Starter docs inserted by `validate-docs --fix` (`{module_name}` and `{line_count}` are replaced)
+    #[serde(default = "crate::validate_docs::default_doc_template")]
+    pub doc_template: String,
+    
+    /// Check that symbols named in //! docs exist in the scanned source
+    #[serde(default)]
+    pub check_references: bool,
+    
+    /// Glob patterns of external symbols accepted by `check_references` (e.g. "tokio::*")
+    #[serde(default = "crate::validate_docs::default_reference_allowlist")]
+    pub reference_allowlist: Vec<String>,
+    
+    /// Check that ```yaml blocks in //! docs are valid config.yaml snippets
+    #[serde(default)]
+    pub check_config_snippets: bool,
+    
+    /// Files checked in parallel (0 = one per CPU)
+    #[serde(default)]
+    pub jobs: usize,
+    
+    /// Fail when the percentage of documented complex modules is lower
+    #[serde(default)]
+    pub min_coverage: Option<f64>,
+    
+    /// Words marking a //! line as a placeholder (e.g. "TODO"); empty disables the check
+    #[serde(default = "crate::validate_docs::default_placeholder_markers")]
+    pub placeholder_markers: Vec<String>,
+    
+    /// Count placeholder //! lines toward `min_doc_lines_complex`
+    #[serde(default)]
+    pub count_placeholder_lines: bool,
+    
+    /// Share of the //! lines (0.0 to 1.0) that may be placeholders
+    #[serde(default = "crate::validate_docs::default_max_placeholder_fraction")]
+    pub max_placeholder_fraction: f64,
+    
+    /// Severity per rule (e.g. `no_docs: error`); rules not listed are warnings
+    #[serde(default)]
+    pub severities: HashMap<crate::validate_docs::Rule, crate::validate_docs::Severity>,
 }
 
+/// Secret redaction for command logs and results
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RedactionConfig {
+    /// Regex patterns to mask (replaces the built-in defaults when set)
+    #[serde(default = "default_redaction_patterns")]
+    pub patterns: Vec<String>,
+    
+    /// Also mask matches in the returned command stdout/stderr
+    #[serde(default)]
+    pub redact_output: bool,
+}
+
+/// Configuration for worktree monitoring
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MonitorConfig {
+    /// Directories whose subdirectories are worktrees (relative to the workspace)
+    #[serde(default = "default_worktrees_dirs")]
+    pub worktrees_dirs: Vec<String>,
+    
+    /// Individual worktree paths, e.g. ones living outside the workspace
+    #[serde(default)]
+    pub extra_worktrees: Vec<String>,
+    
+    /// Number of worktrees inspected concurrently when collecting status
+    #[serde(default = "default_monitor_concurrency")]
+    pub concurrency: usize,
+    
+    /// Glob pathspecs hidden from diffs by default (e.g. lockfiles, snapshots)
+    #[serde(default = "default_diff_exclude")]
+    pub diff_exclude: Vec<String>,
+    
+    /// Reuse unchanged worktree statuses between CLI runs of `monitor` and
+    /// `status` (`.autodebugger/status_cache.json`); `--no-cache` skips it once.
+    /// `Monitor` itself caches only with `with_status_cache`
+    #[serde(default = "default_true")]
+    pub status_cache: bool,
+    
+    /// Recurse into submodules when showing diffs (`git diff --submodule=diff`)
+    #[serde(default)]
+    pub include_submodules: bool,
+    
+    /// Section headers in `CLAUDE.local.md` whose list items are tasks
+    #[serde(default = "crate::monitor::tasks::default_task_sections")]
+    pub task_sections: Vec<String>,
+    
+    /// Template file for `CLAUDE.local.md` in worktrees created with `--seed`
+    #[serde(default)]
+    pub task_template: Option<String>,
+    
+    /// Penalties used by the worktree health score
+    #[serde(default)]
+    pub health: HealthWeights,
+}
+
+/// Points subtracted from a worktree's health score (out of 100)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HealthWeights {
+    /// Days without a commit before a worktree counts as stale
+    #[serde(default = "default_stale_after_days")]
+    pub stale_after_days: i64,
+    
+    #[serde(default = "default_stale_penalty")]
+    pub stale: u32,
+    
+    /// Per uncommitted file, capped at `max_uncommitted`
+    #[serde(default = "default_uncommitted_file_penalty")]
+    pub uncommitted_file: u32,
+    
+    #[serde(default = "default_max_uncommitted_penalty")]
+    pub max_uncommitted: u32,
+    
+    /// Per commit behind the upstream, capped at `max_behind`
+    #[serde(default = "default_behind_commit_penalty")]
+    pub behind_commit: u32,
+    
+    #[serde(default = "default_max_behind_penalty")]
+    pub max_behind: u32,
+    
+    #[serde(default = "default_no_upstream_penalty")]
+    pub no_upstream: u32,
+    
+    /// Per file also changed in another worktree
+    #[serde(default = "default_overlap_penalty")]
+    pub overlap: u32,
+    
+    /// Per overlapping file whose changed line ranges intersect (instead of `overlap`)
+    #[serde(default = "default_overlap_conflict_penalty")]
+    pub overlap_conflict: u32,
+    
+    #[serde(default = "default_max_overlap_penalty")]
+    pub max_overlap: u32,
+    
+    /// Unresolved conflicts in the index
+    #[serde(default = "default_conflicts_penalty")]
+    pub conflicts: u32,
+    
+    /// Merge, rebase, cherry-pick, or revert in progress
+    #[serde(default = "default_operation_penalty")]
+    pub operation: u32,
+}
+
+/// Configuration for the `ci` merge-readiness report
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CiConfig {
+    /// Checks to run; the others are reported as skipped and cost nothing
+    #[serde(default = "default_enabled_checks")]
+    pub enabled_checks: Vec<crate::ci::Check>,
+    
+    /// Points subtracted from the safety score per failed check
+    #[serde(default)]
+    pub weights: CheckWeights,
+    
+    /// Points subtracted per predicted conflict
+    #[serde(default)]
+    pub conflict_penalties: ConflictPenalties,
+    
+    /// Minimum scores for the SAFE and CAUTION recommendations
+    #[serde(default)]
+    pub thresholds: ScoreThresholds,
+    
+    /// Run independent checks at the same time
+    #[serde(default = "default_true")]
+    pub parallel: bool,
+    
+    /// Run `cargo check` and clippy one after the other (they share the target dir)
+    #[serde(default)]
+    pub serialize_check_and_clippy: bool,
+    
+    /// Seconds before a check is stopped and fails
+    #[serde(default = "default_check_timeout_secs")]
+    pub timeout_secs: u64,
+    
+    /// Per-check overrides of `timeout_secs` (e.g. `cargo_test: 1800`)
+    #[serde(default)]
+    pub timeouts: BTreeMap<crate::ci::Check, u64>,
+}
+
+impl CiConfig {
+    /// How long `check` may run
+    pub fn timeout(&self, check: crate::ci::Check) -> std::time::Duration {
+        let secs = self.timeouts.get(&check).copied().unwrap_or(self.timeout_secs);
+        std::time::Duration::from_secs(secs)
+    }
+}
+
+/// Checks run by the git hooks that `install-hooks` writes
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct HooksConfig {
+    /// Checks run before each commit
+    #[serde(default = "default_pre_commit_checks")]
+    pub pre_commit: Vec<crate::ci::Check>,
+    
+    /// Checks run before each push
+    #[serde(default = "default_pre_push_checks")]
+    pub pre_push: Vec<crate::ci::Check>,
+    
+    /// autodebugger binary the hooks call (default: the one running `install-hooks`)
+    #[serde(default)]
+    pub program: Option<String>,
+}
+
+/// The pre-PR sweep run by `autodebugger clean`
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CleanConfig {
+    /// Steps to run (`--steps` overrides them); they always run in the order
+    /// remove_debug, validate_docs, fmt
+    #[serde(default = "default_clean_steps")]
+    pub steps: Vec<crate::clean::CleanStep>,
+}
+
+/// Read-only HTTP endpoints of `autodebugger serve` (cargo feature `server`)
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ServerConfig {
+    /// Address to listen on; the default keeps the endpoints on this machine
+    #[serde(default = "default_server_bind")]
+    pub bind: String,
+    
+    /// Port to listen on (`--port` overrides it)
+    #[serde(default = "default_server_port")]
+    pub port: u16,
+    
+    /// Workspace whose worktrees are served (`--path` overrides it; default: the
+    /// current directory)
+    #[serde(default)]
+    pub workspace: Option<String>,
+}
+
+/// One step of a task: a command line run with `bash -c`, written as a string
+/// or as a map with `run` and optional `dir` and `env`
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(from = "TaskStepSpec", into = "TaskStepSpec")]
+pub struct TaskStep {
+    pub run: String,
+    
+    /// Directory to run in, relative to the task's working directory
+    pub dir: Option<String>,
+    
+    /// Environment variables set for this step only
+    pub env: BTreeMap<String, String>,
+}
+
+impl TaskStep {
+    pub fn new(run: impl Into<String>) -> Self {
+        Self { run: run.into(), dir: None, env: BTreeMap::new() }
+    }
+}
+
+/// How a step is written in the config file; plain steps stay strings
+#[derive(Deserialize, Serialize)]
+#[serde(untagged, expecting = "a command line, or a map with run and optional dir and env")]
+enum TaskStepSpec {
+    Command(String),
+    Detailed(DetailedTaskStep),
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct DetailedTaskStep {
+    run: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dir: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    env: BTreeMap<String, String>,
+}
+
+impl From<TaskStepSpec> for TaskStep {
+    fn from(spec: TaskStepSpec) -> Self {
+        match spec {
+            TaskStepSpec::Command(run) => TaskStep::new(run),
+            TaskStepSpec::Detailed(DetailedTaskStep { run, dir, env }) => TaskStep { run, dir, env },
+        }
+    }
+}
+
+impl From<TaskStep> for TaskStepSpec {
+    fn from(step: TaskStep) -> Self {
+        if step.dir.is_none() && step.env.is_empty() {
+            TaskStepSpec::Command(step.run)
+        } else {
+            TaskStepSpec::Detailed(DetailedTaskStep { run: step.run, dir: step.dir, env: step.env })
+        }
+    }
+}
+
+/// Points subtracted from the CI safety score (out of 100) per failed check
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CheckWeights {
+    #[serde(default = "default_cargo_check_weight")]
+    pub cargo_check: u32,
+    
+    #[serde(default = "default_cargo_test_weight")]
+    pub cargo_test: u32,
+    
+    #[serde(default = "default_clippy_weight")]
+    pub clippy: u32,
+    
+    #[serde(default = "default_debug_macros_weight")]
+    pub debug_macros: u32,
+    
+    #[serde(default = "default_documentation_weight")]
+    pub documentation: u32,
+    
+    #[serde(default = "default_fmt_weight")]
+    pub fmt: u32,
+    
+    #[serde(default = "default_audit_weight")]
+    pub audit: u32,
+}
+
+impl CheckWeights {
+    pub fn get(&self, check: crate::ci::Check) -> u32 {
+        use crate::ci::Check;
+        match check {
+            Check::CargoCheck => self.cargo_check,
+            Check::CargoTest => self.cargo_test,
+            Check::Clippy => self.clippy,
+            Check::DebugMacros => self.debug_macros,
+            Check::Documentation => self.documentation,
+            Check::Fmt => self.fmt,
+            Check::Audit => self.audit,
+        }
+    }
+}
+
+/// Points subtracted from the CI safety score per predicted conflict
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ConflictPenalties {
+    #[serde(default = "default_high_conflict_penalty")]
+    pub high: u32,
+    
+    #[serde(default = "default_medium_conflict_penalty")]
+    pub medium: u32,
+    
+    #[serde(default = "default_low_conflict_penalty")]
+    pub low: u32,
+    
+    /// Both sides changed the same fn or type in different lines (instead of the severity's)
+    #[serde(default = "default_same_item_conflict_penalty")]
+    pub same_item: u32,
+}
+
+impl ConflictPenalties {
+    pub fn get(&self, severity: crate::ci::ConflictSeverity) -> u32 {
+        use crate::ci::ConflictSeverity;
+        match severity {
+            ConflictSeverity::High => self.high,
+            ConflictSeverity::Medium => self.medium,
+            ConflictSeverity::Low => self.low,
+        }
+    }
+
+    /// Penalty for one prediction: `same_item` for semantic ones, else by severity
+    pub fn penalty(&self, conflict: &crate::ci::ConflictPrediction) -> u32 {
+        match conflict.kind {
+            crate::ci::ConflictKind::SameItem => self.same_item,
+            crate::ci::ConflictKind::Textual => self.get(conflict.severity),
+        }
+    }
+}
+
+/// Safety score cutoffs: SAFE from `safe`, CAUTION from `caution`, else DANGER
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ScoreThresholds {
+    #[serde(default = "default_safe_threshold")]
+    pub safe: u8,
+    
+    #[serde(default = "default_caution_threshold")]
+    pub caution: u8,
+}
+
+/// Configuration for TODO/FIXME/HACK comment scanning
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TodosConfig {
+    /// File extensions to scan (without the leading dot)
+    #[serde(default = "default_todo_extensions")]
+    pub extensions: Vec<String>,
+    
+    /// Glob patterns to ignore, relative to each worktree root
+    #[serde(default)]
+    pub ignore_patterns: Vec<String>,
+    
+    /// Maximum number of items reported per worktree
+    #[serde(default = "default_max_todos_per_worktree")]
+    pub max_per_worktree: usize,
+    
+    /// Files larger than this are skipped
+    #[serde(default = "default_max_todo_file_size_kb")]
+    pub max_file_size_kb: u64,
+}
 
 /// Log verbosity threshold configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -63,6 +571,119 @@ pub struct VerbosityConfig {
     /// Threshold for TRACE level logging
     #[serde(default = "default_trace_threshold")]
     pub trace_threshold: usize,
+    
+    /// Emit one WARN event as soon as the threshold is exceeded (once per
+    /// crossing, or per window), instead of only on `check_and_report()`
+    #[serde(default)]
+    pub emit_live_warning: bool,
+}
+
+/// Output format of a tracing fmt layer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines from `ConditionalLocationFormatter`
+    #[default]
+    Pretty,
+    /// One JSON object per line from `JsonFormatter`, for log aggregators
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => anyhow::bail!("Unknown log format '{}' (expected pretty or json)", s),
+        }
+    }
+}
+
+/// Logging configuration for the CLI and the init functions
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LoggingConfig {
+    /// Default level or directives when RUST_LOG is unset (CLI); `default_level`
+    /// is accepted as another name
+    #[serde(default = "default_log_level", alias = "default_level")]
+    pub level: String,
+    
+    /// Console destination, "stdout", "stderr", or "mcp" (stderr, reports on
+    /// stderr, JSON log files) (CLI); commands printing machine-readable output
+    /// always log to stderr
+    #[serde(default = "default_log_output")]
+    pub output: String,
+    
+    /// Format of console output
+    #[serde(default)]
+    pub format: LogFormat,
+    
+    /// Format of the rotating log file; defaults to `format`
+    #[serde(default)]
+    pub file_format: Option<LogFormat>,
+    
+    /// Filter directives for noisy crates, e.g. "sqlx=warn"; RUST_LOG overrides them
+    #[serde(default)]
+    pub suppress: Vec<String>,
+    
+    /// Raise the console filter to WARN and skip the startup and shutdown lines
+    /// (CLI `--quiet`)
+    #[serde(default)]
+    pub quiet: bool,
+    
+    /// Rotating file logging for the CLI; unset logs to the console only.
+    /// `console_output: false` here turns console logs off.
+    #[serde(default)]
+    pub file: Option<RotatingFileConfig>,
+    
+    /// Per-span timing, reported at shutdown and optionally warned about live
+    #[serde(default)]
+    pub span_timing: SpanTimingConfig,
+}
+
+/// Span timing configuration (`logging.span_timing`)
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SpanTimingConfig {
+    /// Add a `SpanTimingLayer` in the init functions (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Log a WARN when one span takes longer than this (default: never)
+    #[serde(default)]
+    pub warn_threshold_ms: Option<u64>,
+}
+
+impl LoggingConfig {
+    /// Format of the rotating log file
+    pub fn file_format(&self) -> LogFormat {
+        self.file_format.unwrap_or(self.format)
+    }
+}
+
+/// When a rotating log file is rolled over
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rotation {
+    /// At `max_size_mb` (per `truncate_on_limit`)
+    #[default]
+    Size,
+    /// To a new file at each local midnight
+    Daily,
+    /// To a new file at the start of each hour
+    Hourly,
+    /// At `max_size_mb` and at each local midnight
+    SizeOrDaily,
+}
+
+/// What a non-blocking log write does when the writer thread's buffer is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WhenFull {
+    /// Discard the oldest buffered line; the caller never waits
+    #[default]
+    DropOldest,
+    /// Wait for the writer thread to make room
+    Block,
 }
 
 /// File logging configuration
@@ -78,6 +699,10 @@ pub struct FileLogConfig {
 /// Rotating file logger configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RotatingFileConfig {
+    /// Write the log files at all; false keeps the section but logs to the console
+    /// only (CLI `--no-log-file`, default: true)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
     /// Directory to store log files (created if doesn't exist)
     #[serde(default = "default_log_directory")]
     pub log_directory: String,
@@ -87,6 +712,10 @@ pub struct RotatingFileConfig {
     /// Maximum number of rotating files to keep (default: 10)
     #[serde(default = "default_max_files")]
     pub max_files: usize,
+    /// Cap on the combined size of those files in MB; the oldest are deleted first
+    /// (default: none)
+    #[serde(default)]
+    pub max_total_size_mb: Option<u64>,
     /// Maximum file size in MB before rotation (default: 5)
     #[serde(default = "default_max_size_mb")]
     pub max_size_mb: u64,
@@ -96,22 +725,50 @@ pub struct RotatingFileConfig {
     /// Whether to truncate on size limit vs create numbered backups (default: true)
     #[serde(default = "default_truncate_on_limit")]
     pub truncate_on_limit: bool,
-}
-
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            verbosity: VerbosityConfig::default(),
-            remove_debug: RemoveDebugConfig::default(),
-            validate_docs: ValidateDocsConfig::default(),
-        }
-    }
+    /// Format of the log file, independent of the console (default: `logging.file_format`
+    /// from config.yaml, else the console format)
+    #[serde(default)]
+    pub format: Option<LogFormat>,
+    /// Size-based, time-based, or both (default: size)
+    #[serde(default)]
+    pub rotation: Rotation,
+    /// Gzip each file as it is rotated out, in the background (needs `gzip` on PATH)
+    #[serde(default)]
+    pub compress_rotated: bool,
+    /// Write on a background thread so a slow disk doesn't stall logging (default: false)
+    #[serde(default)]
+    pub non_blocking: bool,
+    /// Lines buffered for the background thread in non-blocking mode (default: 128000)
+    #[serde(default = "default_buffered_lines")]
+    pub buffered_lines: usize,
+    /// What a write does when that buffer is full (default: drop_oldest)
+    #[serde(default)]
+    pub when_full: WhenFull,
+    /// Start each new file with a line naming the crate version, command, pid, host,
+    /// and log filter (default: true)
+    #[serde(default = "default_true")]
+    pub write_header: bool,
+    /// Log panics, with a backtrace, to the file before the previous panic hook runs
+    /// (default: true)
+    #[serde(default = "default_true")]
+    pub log_panics: bool,
+    
+    /// In the pretty format, end each line with the event's fields as one JSON
+    /// object instead of `key=value` pairs (default: false)
+    #[serde(default)]
+    pub json_fields: bool,
 }
 
 impl Default for RemoveDebugConfig {
     fn default() -> Self {
         Self {
             default_paths: default_remove_debug_paths(),
+            macros: crate::remove_debug::default_macros(),
+            backups: true,
+            backup_retention: crate::remove_debug::default_backup_retention(),
+            jobs: 0,
+            tidy_whitespace: true,
+            engine: crate::remove_debug::Engine::default(),
         }
     }
 }
@@ -123,11 +780,169 @@ impl Default for ValidateDocsConfig {
             min_doc_lines_complex: default_min_doc_lines_complex(),
             max_doc_lines: default_max_doc_lines(),
             complexity_threshold: default_complexity_threshold(),
+            use_raw_line_count: false,
             ignore_patterns: default_ignore_patterns(),
+            require_pub_item_docs: false,
+            pub_item_allowlist: default_pub_item_allowlist(),
+            overrides: Vec::new(),
+            doc_template: crate::validate_docs::default_doc_template(),
+            check_references: false,
+            reference_allowlist: crate::validate_docs::default_reference_allowlist(),
+            check_config_snippets: false,
+            jobs: 0,
+            min_coverage: None,
+            placeholder_markers: crate::validate_docs::default_placeholder_markers(),
+            count_placeholder_lines: false,
+            max_placeholder_fraction: crate::validate_docs::default_max_placeholder_fraction(),
+            severities: HashMap::new(),
+        }
+    }
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            patterns: default_redaction_patterns(),
+            redact_output: false,
         }
     }
 }
 
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            worktrees_dirs: default_worktrees_dirs(),
+            extra_worktrees: Vec::new(),
+            concurrency: default_monitor_concurrency(),
+            diff_exclude: default_diff_exclude(),
+            status_cache: true,
+            include_submodules: false,
+            task_sections: crate::monitor::tasks::default_task_sections(),
+            task_template: None,
+            health: HealthWeights::default(),
+        }
+    }
+}
+
+impl Default for HealthWeights {
+    fn default() -> Self {
+        Self {
+            stale_after_days: default_stale_after_days(),
+            stale: default_stale_penalty(),
+            uncommitted_file: default_uncommitted_file_penalty(),
+            max_uncommitted: default_max_uncommitted_penalty(),
+            behind_commit: default_behind_commit_penalty(),
+            max_behind: default_max_behind_penalty(),
+            no_upstream: default_no_upstream_penalty(),
+            overlap: default_overlap_penalty(),
+            overlap_conflict: default_overlap_conflict_penalty(),
+            max_overlap: default_max_overlap_penalty(),
+            conflicts: default_conflicts_penalty(),
+            operation: default_operation_penalty(),
+        }
+    }
+}
+
+impl Default for CiConfig {
+    fn default() -> Self {
+        Self {
+            enabled_checks: default_enabled_checks(),
+            weights: CheckWeights::default(),
+            conflict_penalties: ConflictPenalties::default(),
+            thresholds: ScoreThresholds::default(),
+            parallel: true,
+            serialize_check_and_clippy: false,
+            timeout_secs: default_check_timeout_secs(),
+            timeouts: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        Self {
+            pre_commit: default_pre_commit_checks(),
+            pre_push: default_pre_push_checks(),
+            program: None,
+        }
+    }
+}
+
+impl Default for CleanConfig {
+    fn default() -> Self {
+        Self { steps: default_clean_steps() }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: default_server_bind(),
+            port: default_server_port(),
+            workspace: None,
+        }
+    }
+}
+
+impl Default for CheckWeights {
+    fn default() -> Self {
+        Self {
+            cargo_check: default_cargo_check_weight(),
+            cargo_test: default_cargo_test_weight(),
+            clippy: default_clippy_weight(),
+            debug_macros: default_debug_macros_weight(),
+            documentation: default_documentation_weight(),
+            fmt: default_fmt_weight(),
+            audit: default_audit_weight(),
+        }
+    }
+}
+
+impl Default for ConflictPenalties {
+    fn default() -> Self {
+        Self {
+            high: default_high_conflict_penalty(),
+            medium: default_medium_conflict_penalty(),
+            low: default_low_conflict_penalty(),
+            same_item: default_same_item_conflict_penalty(),
+        }
+    }
+}
+
+impl Default for ScoreThresholds {
+    fn default() -> Self {
+        Self {
+            safe: default_safe_threshold(),
+            caution: default_caution_threshold(),
+        }
+    }
+}
+
+impl Default for TodosConfig {
+    fn default() -> Self {
+        Self {
+            extensions: default_todo_extensions(),
+            ignore_patterns: Vec::new(),
+            max_per_worktree: default_max_todos_per_worktree(),
+            max_file_size_kb: default_max_todo_file_size_kb(),
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: default_log_level(),
+            output: default_log_output(),
+            format: LogFormat::default(),
+            file_format: None,
+            suppress: Vec::new(),
+            quiet: false,
+            file: None,
+            span_timing: SpanTimingConfig::default(),
+        }
+    }
+}
 
 impl Default for VerbosityConfig {
     fn default() -> Self {
@@ -135,6 +950,7 @@ impl Default for VerbosityConfig {
             info_threshold: default_info_threshold(),
             debug_threshold: default_debug_threshold(),
             trace_threshold: default_trace_threshold(),
+            emit_live_warning: false,
         }
     }
 }
@@ -151,12 +967,23 @@ impl Default for FileLogConfig {
 impl Default for RotatingFileConfig {
     fn default() -> Self {
         Self {
+            enabled: true,
             log_directory: default_log_directory(),
             filename: default_log_filename(),
             max_files: default_max_files(),
+            max_total_size_mb: None,
             max_size_mb: default_max_size_mb(),
             console_output: default_console_output(),
             truncate_on_limit: default_truncate_on_limit(),
+            format: None,
+            rotation: Rotation::default(),
+            compress_rotated: false,
+            non_blocking: false,
+            buffered_lines: default_buffered_lines(),
+            when_full: WhenFull::default(),
+            write_header: true,
+            log_panics: true,
+            json_fields: false,
         }
     }
 }
@@ -166,12 +993,16 @@ fn default_info_threshold() -> usize { 50 }
 fn default_debug_threshold() -> usize { 100 }
 fn default_trace_threshold() -> usize { 200 }
 fn default_truncate() -> bool { true }
+fn default_log_level() -> String { "info".to_string() }
+fn default_log_output() -> String { "stdout".to_string() }
+fn default_true() -> bool { true }
 fn default_log_directory() -> String { "logs".to_string() }
 fn default_log_filename() -> String { "app.log".to_string() }
 fn default_max_files() -> usize { 10 }
 fn default_max_size_mb() -> u64 { 5 }
 fn default_console_output() -> bool { true }
 fn default_truncate_on_limit() -> bool { true }
+fn default_buffered_lines() -> usize { 128_000 }
 fn default_remove_debug_paths() -> Vec<String> { 
     vec!["src".to_string(), "tests".to_string()] 
 }
@@ -184,31 +1015,830 @@ fn default_complexity_threshold() -> usize { 200 }
 fn default_ignore_patterns() -> Vec<String> {
     vec!["**/tests/**".to_string(), "**/examples/**".to_string()]
 }
+fn default_pub_item_allowlist() -> Vec<String> {
+    vec!["new".to_string(), "default".to_string()]
+}
+fn default_redaction_patterns() -> Vec<String> {
+    vec![
+        r"gh[pousr]_[A-Za-z0-9]{20,}".to_string(),
+        r"github_pat_[A-Za-z0-9_]{20,}".to_string(),
+        r"AKIA[0-9A-Z]{16}".to_string(),
+        r"xox[abprs]-[A-Za-z0-9-]{10,}".to_string(),
+        r"(?i)\b[A-Z0-9_]*(?:SECRET|TOKEN|PASSWORD|PASSWD|API_KEY)[A-Z0-9_]*=(?P<secret>\S+)".to_string(),
+        r"://[^/\s:@]+:(?P<secret>[^@\s]+)@".to_string(),
+    ]
+}
+fn default_worktrees_dirs() -> Vec<String> {
+    vec!["worktrees".to_string()]
+}
+fn default_monitor_concurrency() -> usize { 8 }
+fn default_diff_exclude() -> Vec<String> {
+    vec!["Cargo.lock".to_string(), "**/*.snap".to_string()]
+}
+fn default_stale_after_days() -> i64 { 7 }
+fn default_stale_penalty() -> u32 { 15 }
+fn default_uncommitted_file_penalty() -> u32 { 2 }
+fn default_max_uncommitted_penalty() -> u32 { 20 }
+fn default_behind_commit_penalty() -> u32 { 3 }
+fn default_max_behind_penalty() -> u32 { 30 }
+fn default_no_upstream_penalty() -> u32 { 5 }
+fn default_overlap_penalty() -> u32 { 5 }
+fn default_overlap_conflict_penalty() -> u32 { 15 }
+fn default_max_overlap_penalty() -> u32 { 30 }
+fn default_conflicts_penalty() -> u32 { 40 }
+fn default_operation_penalty() -> u32 { 25 }
+fn default_enabled_checks() -> Vec<crate::ci::Check> { crate::ci::Check::ALL.to_vec() }
+fn default_cargo_check_weight() -> u32 { 30 }
+fn default_cargo_test_weight() -> u32 { 20 }
+fn default_clippy_weight() -> u32 { 10 }
+fn default_debug_macros_weight() -> u32 { 10 }
+fn default_documentation_weight() -> u32 { 5 }
+fn default_fmt_weight() -> u32 { 5 }
+fn default_audit_weight() -> u32 { 15 }
+fn default_high_conflict_penalty() -> u32 { 15 }
+fn default_medium_conflict_penalty() -> u32 { 10 }
+fn default_low_conflict_penalty() -> u32 { 5 }
+fn default_same_item_conflict_penalty() -> u32 { 10 }
+fn default_pre_commit_checks() -> Vec<crate::ci::Check> { vec![crate::ci::Check::DebugMacros] }
+fn default_pre_push_checks() -> Vec<crate::ci::Check> {
+    use crate::ci::Check;
+    vec![Check::CargoCheck, Check::CargoTest, Check::Clippy]
+}
+fn default_clean_steps() -> Vec<crate::clean::CleanStep> { crate::clean::CleanStep::ALL.to_vec() }
+fn default_server_bind() -> String { "127.0.0.1".to_string() }
+fn default_server_port() -> u16 { 7700 }
+fn default_safe_threshold() -> u8 { 80 }
+fn default_caution_threshold() -> u8 { 60 }
+fn default_check_timeout_secs() -> u64 { 900 }
+fn default_todo_extensions() -> Vec<String> {
+    ["rs", "py", "js", "jsx", "ts", "tsx", "go", "java", "c", "h", "cpp", "hpp", "sh"]
+        .iter()
+        .map(|e| e.to_string())
+        .collect()
+}
+fn default_max_todos_per_worktree() -> usize { 50 }
+fn default_max_todo_file_size_kb() -> u64 { 256 }
 
 impl Config {
-    /// Load configuration from file, or use defaults if not found
+    /// Load the config files found by `find_config_files` from the current
+    /// directory, the project's layered over the user's (see `load_layers`), or
+    /// use defaults if there are none
     pub fn load() -> Result<Self> {
-        // Try to load from config.yaml in current directory
-        let config_path = Path::new("config.yaml");
-        
-        if config_path.exists() {
-            let contents = fs::read_to_string(config_path)
-                .context("Failed to read config.yaml")?;
-            let config: Config = serde_yaml::from_str(&contents)
-                .context("Failed to parse config.yaml")?;
-            Ok(config)
+        Ok(Self::load_layers(&discover_config_files())?.pop().unwrap_or_default())
+    }
+    
+    /// Load configuration from a specific file path, ignoring discovery.
+    ///
+    /// Keys no field reads are kept in `unknown_keys` with their line and the
+    /// nearest valid key, or fail loading when the file sets `strict: true`.
+    /// Values out of range (see `validate`) always fail loading.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        Ok(Self::load_layers(&[path.to_path_buf()])?.remove(0))
+    }
+    
+    /// Load `paths` as layers, lowest first, returning the config after each:
+    /// the last is the result. A file overrides only the keys it sets, section
+    /// by section, so a project file setting `verbosity.info_threshold` keeps
+    /// the user file's other `verbosity` fields and its `logging` section.
+    /// Lists are replaced whole, or appended to (skipping duplicates) when
+    /// `merge_lists: true`; a key set to null leaves the value below it.
+    ///
+    /// Unknown keys are collected as in `load_from`; `strict` and value ranges
+    /// are checked on the merged result.
+    pub fn load_layers(paths: &[PathBuf]) -> Result<Vec<Self>> {
+        let mut files = Vec::new();
+        for path in paths {
+            let contents = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read config from {:?}", path))?;
+            let unknown_keys = unknown_keys(&contents, path)
+                .with_context(|| format!("Failed to parse config from {:?}", path))?;
+            let value: serde_yaml::Value = serde_yaml::from_str(&contents)
+                .with_context(|| format!("Failed to parse config from {:?}", path))?;
+            files.push((path, unknown_keys, value));
+        }
+        
+        let merge_lists = files.iter().rev()
+            .find_map(|(_, _, value)| value.get("merge_lists")?.as_bool())
+            .unwrap_or(false);
+        let mut merged = serde_yaml::Value::Mapping(Default::default());
+        let mut layers: Vec<Config> = Vec::new();
+        for (path, unknown_keys, value) in files {
+            merge_layer(&mut merged, value, merge_lists);
+            let mut config: Config = serde_yaml::from_value(merged.clone())
+                .with_context(|| format!("Failed to merge config from {:?}", path))?;
+            let below = layers.last().cloned().unwrap_or_default();
+            config.source_paths = below.source_paths.into_iter().chain([path.clone()]).collect();
+            config.unknown_keys = below.unknown_keys.into_iter().chain(unknown_keys).collect();
+            layers.push(config);
+        }
+        
+        if let Some(config) = layers.last() {
+            if config.strict {
+                config.deny_unknown_keys()?;
+            }
+            config.validate()
+                .with_context(|| format!("Invalid config in {}", config.source_description()))?;
+        }
+        Ok(layers)
+    }
+    
+    /// Error listing `unknown_keys`, if there are any
+    pub fn deny_unknown_keys(&self) -> Result<()> {
+        if self.unknown_keys.is_empty() {
+            return Ok(());
+        }
+        let keys: Vec<String> = self.unknown_keys.iter().map(|key| format!("  {}", key)).collect();
+        anyhow::bail!("Unknown config keys:\n{}", keys.join("\n"))
+    }
+    
+    /// Check values serde accepts but the commands can't use, naming each
+    /// offending key
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+        let docs = &self.validate_docs;
+        if docs.max_doc_lines < docs.min_doc_lines_complex {
+            problems.push(format!(
+                "validate_docs.max_doc_lines ({}) must be at least validate_docs.min_doc_lines_complex ({})",
+                docs.max_doc_lines, docs.min_doc_lines_complex));
+        }
+        if let Some(file) = &self.logging.file {
+            if file.max_size_mb == 0 {
+                problems.push("logging.file.max_size_mb must be greater than 0".to_string());
+            }
+            if file.max_files == 0 {
+                problems.push("logging.file.max_files must be greater than 0".to_string());
+            }
+            if file.max_total_size_mb == Some(0) {
+                problems.push("logging.file.max_total_size_mb must be greater than 0".to_string());
+            }
+        }
+        if self.monitor.concurrency == 0 {
+            problems.push("monitor.concurrency must be greater than 0".to_string());
+        }
+        let thresholds = &self.ci.thresholds;
+        if thresholds.safe > 100 {
+            problems.push(format!("ci.thresholds.safe ({}) must be at most 100", thresholds.safe));
+        }
+        if thresholds.caution > thresholds.safe {
+            problems.push(format!(
+                "ci.thresholds.caution ({}) must be at most ci.thresholds.safe ({})",
+                thresholds.caution, thresholds.safe));
+        }
+        for (name, steps) in &self.tasks {
+            if steps.is_empty() {
+                problems.push(format!("tasks.{} must have at least one step", name));
+            }
+        }
+        
+        match problems.len() {
+            0 => Ok(()),
+            1 => Err(anyhow::anyhow!(problems.remove(0))),
+            _ => Err(anyhow::anyhow!("{}", problems.join("; "))),
+        }
+    }
+    
+    /// Where the configuration came from, e.g. for `--verbose` output
+    pub fn source_description(&self) -> String {
+        let files = if self.source_paths.is_empty() {
+            "defaults (no config file found)".to_string()
         } else {
-            // Use defaults if no config file exists
-            Ok(Config::default())
+            let paths: Vec<String> = self.source_paths.iter().map(|path| path.display().to_string()).collect();
+            paths.join(" + ")
+        };
+        match &self.profile {
+            Some(profile) => format!("{} (profile {})", files, profile),
+            None => files,
         }
     }
     
-    /// Load configuration from a specific file path
-    pub fn load_from(path: &Path) -> Result<Self> {
-        let contents = fs::read_to_string(path)
-            .with_context(|| format!("Failed to read config from {:?}", path))?;
-        let config: Config = serde_yaml::from_str(&contents)
-            .with_context(|| format!("Failed to parse config from {:?}", path))?;
+    /// Steps of the task `name`; an unknown name is an error listing the defined tasks
+    pub fn task(&self, name: &str) -> Result<&[TaskStep]> {
+        let Some(steps) = self.tasks.get(name) else {
+            if self.tasks.is_empty() {
+                anyhow::bail!("Unknown task {}: {} defines no tasks", name, self.source_description());
+            }
+            let names: Vec<&str> = self.tasks.keys().map(String::as_str).collect();
+            anyhow::bail!("Unknown task {}; available tasks: {}", name, names.join(", "));
+        };
+        Ok(steps)
+    }
+    
+    /// Merge the profile `name` over this config the way a config file is
+    /// merged over the ones below it (see `load_layers`, including
+    /// `merge_lists`), e.g. `profiles: {ci: {validate_docs: {min_coverage: 90}}}`
+    /// for stricter thresholds in CI. An unknown name is an error listing the
+    /// defined profiles
+    pub fn with_profile(self, name: &str) -> Result<Self> {
+        let Some(overlay) = self.profiles.get(name).cloned() else {
+            if self.profiles.is_empty() {
+                anyhow::bail!("Unknown profile {}: {} defines no profiles", name, self.source_description());
+            }
+            let names: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            anyhow::bail!("Unknown profile {}; available profiles: {}", name, names.join(", "));
+        };
+        
+        let mut value = serde_yaml::to_value(&self).context("Failed to serialize config")?;
+        merge_layer(&mut value, overlay, self.merge_lists);
+        let config: Config = serde_yaml::from_value(value)
+            .with_context(|| format!("Invalid profile {}", name))?;
+        let config = Config {
+            source_paths: self.source_paths,
+            unknown_keys: self.unknown_keys,
+            profile: Some(name.to_string()),
+            ..config
+        };
+        config.validate()
+            .with_context(|| format!("Invalid profile {}", name))?;
+        Ok(config)
+    }
+    
+    /// `load()`, then overrides from `AUTODEBUGGER__*` environment variables (see
+    /// `with_env`); the CLI loads its configuration this way
+    pub fn load_with_env() -> Result<Self> {
+        Self::load()?.with_env(std::env::vars())
+    }
+    
+    /// Apply overrides from `vars` named `AUTODEBUGGER__<SECTION>__<FIELD>`, with
+    /// one more `__<FIELD>` per nesting level, e.g.
+    /// `AUTODEBUGGER__VERBOSITY__INFO_THRESHOLD=75` or
+    /// `AUTODEBUGGER__LOGGING__FILE__MAX_FILES=5`. Other variables are ignored.
+    ///
+    /// Names are case-insensitive and applied in sorted order. A value is read as
+    /// the field's type: text fields take it as is, lists split it at commas
+    /// (empty for an empty value), and other fields parse it as a YAML scalar
+    /// (`75`, `true`, `json`). A value that doesn't fit, or a name that is not a
+    /// config field, is an error naming the variable.
+    pub fn with_env<I>(self, vars: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut overrides: Vec<(String, String)> = vars.into_iter()
+            .filter(|(name, _)| name.to_uppercase().starts_with(ENV_PREFIX))
+            .collect();
+        if overrides.is_empty() {
+            return Ok(self);
+        }
+        overrides.sort();
+        
+        let mut value = serde_yaml::to_value(&self).context("Failed to serialize config")?;
+        let source_paths = self.source_paths.clone();
+        let unknown_keys = self.unknown_keys.clone();
+        let profile = self.profile.clone();
+        let mut config = self;
+        for (name, raw) in overrides {
+            let path: Vec<String> = name[ENV_PREFIX.len()..].split("__").map(str::to_lowercase).collect();
+            config = apply_env_override(&mut value, &path, &raw)
+                .with_context(|| format!("Invalid environment override {}", name))?;
+        }
+        let config = Config { source_paths, unknown_keys, profile, ..config };
+        config.validate()?;
         Ok(config)
     }
+}
+
+/// Names of a project config file, in order of preference within a directory
+pub const CONFIG_FILE_NAMES: [&str; 2] = ["autodebugger.yaml", "config.yaml"];
+
+/// Config files for a run started in `start`, lowest layer first: `config.yaml`
+/// in `user_dir`, then the nearest project file (one of `CONFIG_FILE_NAMES`) in
+/// `start` or a parent, searching no higher than the first directory containing
+/// `.git`. Either may be missing
+pub fn find_config_files(start: &Path, user_dir: Option<&Path>) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = user_dir.map(|dir| dir.join("config.yaml")).filter(|path| path.is_file()).into_iter().collect();
+    for dir in start.ancestors() {
+        if let Some(path) = CONFIG_FILE_NAMES.iter().map(|name| dir.join(name)).find(|path| path.is_file()) {
+            files.push(path);
+            break;
+        }
+        // The repository root ends the project search
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+    files
+}
+
+/// `find_config_files` from the current directory with the `user_config_dir`
+pub fn discover_config_files() -> Vec<PathBuf> {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    find_config_files(&cwd, user_config_dir().as_deref())
+}
+
+/// User config directory: `$XDG_CONFIG_HOME/autodebugger`, else `~/.config/autodebugger`
+pub fn user_config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("autodebugger"))
+}
+
+/// Prefix of the environment variables read by `Config::with_env`
+pub const ENV_PREFIX: &str = "AUTODEBUGGER__";
+
+/// Environment variable naming the profile the CLI applies without `--profile`
+pub const PROFILE_ENV: &str = "AUTODEBUGGER_PROFILE";
+
+/// Set the field at `path` in the serialized config `root` from `raw` and
+/// return the config it deserializes to
+fn apply_env_override(root: &mut serde_yaml::Value, path: &[String], raw: &str) -> Result<Config> {
+    use serde_yaml::Value;
+    
+    let unknown = || anyhow::anyhow!("no config field {}", path.join("."));
+    let (field, parents) = path.split_last().ok_or_else(unknown)?;
+    let existing = parent_mapping(root, parents).ok_or_else(unknown)?.get(field.as_str()).cloned();
+    
+    let scalar = |text: &str| serde_yaml::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()));
+    let items = || raw.split(',').map(str::trim).filter(|item| !item.is_empty());
+    let candidates = match existing {
+        Some(Value::String(_)) => vec![Value::String(raw.to_string())],
+        Some(Value::Sequence(_)) => vec![
+            Value::Sequence(items().map(|item| Value::String(item.to_string())).collect()),
+            Value::Sequence(items().map(scalar).collect()),
+            // YAML flow syntax for lists of structs, e.g. [{pattern: "src/**"}]
+            scalar(raw),
+        ],
+        _ => vec![scalar(raw), Value::String(raw.to_string())],
+    };
+    
+    let mut first_error = None;
+    for candidate in candidates {
+        if let Some(parent) = parent_mapping(root, parents) {
+            parent.insert(Value::String(field.clone()), candidate);
+        }
+        match serde_yaml::from_value::<Config>(root.clone()) {
+            Ok(config) => {
+                // serde drops fields it doesn't know instead of rejecting them
+                let written = serde_yaml::to_value(&config).context("Failed to serialize config")?;
+                if path.iter().try_fold(&written, |value, segment| value.get(segment.as_str())).is_none() {
+                    return Err(unknown());
+                }
+                return Ok(config);
+            }
+            Err(e) => { first_error.get_or_insert(e); }
+        }
+    }
+    Err(first_error.map(anyhow::Error::from).unwrap_or_else(unknown))
+}
+
+/// Mapping holding the field below `parents`; an unset optional section, e.g.
+/// `logging.file`, becomes an empty mapping so its defaults apply
+fn parent_mapping<'a>(root: &'a mut serde_yaml::Value, parents: &[String]) -> Option<&'a mut serde_yaml::Mapping> {
+    let mut parent = root;
+    for segment in parents {
+        let child = parent.as_mapping_mut()?.get_mut(segment.as_str())?;
+        if child.is_null() {
+            *child = serde_yaml::Value::Mapping(Default::default());
+        }
+        parent = child;
+    }
+    parent.as_mapping_mut()
+}
+
+/// Keys in the config file `contents` (read from `path`) that no field reads;
+/// an error if a value doesn't fit its field
+fn unknown_keys(contents: &str, path: &Path) -> Result<Vec<UnknownKey>> {
+    let mut unknown = Vec::new();
+    let config: Config = serde_ignored::deserialize(
+        serde_yaml::Deserializer::from_str(contents),
+        |key| unknown.push(key.to_string()),
+    )?;
+    // Profiles are kept as YAML until applied, so their keys are checked here;
+    // the suggestion comes from the key without its `profiles.<name>.` prefix
+    let mut profile_unknown = Vec::new();
+    for (name, overlay) in &config.profiles {
+        let _: Config = serde_ignored::deserialize(overlay.clone(), |key| {
+            profile_unknown.push((format!("profiles.{}.{}", name, key), key.to_string()))
+        }).with_context(|| format!("profiles.{}", name))?;
+    }
+    
+    // Valid keys are read back from the config itself, so optional sections
+    // the file sets (e.g. `logging.file`) offer their fields too
+    let valid = serde_yaml::to_value(&config).context("Failed to serialize config")?;
+    let lines: Vec<&str> = contents.lines().collect();
+    Ok(unknown.into_iter()
+        .map(|key| (key.clone(), key))
+        .chain(profile_unknown)
+        .map(|(key, field)| UnknownKey {
+            file: path.to_path_buf(),
+            line: key_line(&lines, &key) + 1,
+            suggestion: closest_key(&valid, &field),
+            key,
+        })
+        .collect())
+}
+
+/// Fold the config file `layer` into `base`: mappings key by key, lists
+/// replaced or (with `merge_lists`) appended without duplicates, nulls skipped,
+/// anything else replaced
+fn merge_layer(base: &mut serde_yaml::Value, layer: serde_yaml::Value, merge_lists: bool) {
+    use serde_yaml::Value;
+    
+    match (base, layer) {
+        (_, Value::Null) => {}
+        (Value::Mapping(base), Value::Mapping(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_layer(existing, value, merge_lists),
+                    None => { base.insert(key, value); }
+                }
+            }
+        }
+        (Value::Sequence(base), Value::Sequence(layer)) if merge_lists => {
+            for item in layer {
+                if !base.contains(&item) {
+                    base.push(item);
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+/// Index of the line in `lines` that holds the dotted `key`, following its parents
+pub(crate) fn key_line(lines: &[&str], key: &str) -> usize {
+    let mut found = 0;
+    for segment in key.split('.').filter(|s| s.parse::<usize>().is_err()) {
+        let declares = |line: &&str| {
+            let line = line.trim_start();
+            let line = line.strip_prefix("- ").unwrap_or(line);
+            line.strip_prefix(segment).is_some_and(|rest| rest.trim_start().starts_with(':'))
+        };
+        if let Some(offset) = lines[found..].iter().position(declares) {
+            found += offset;
+        }
+    }
+    found
+}
+
+/// The key beside the dotted `key` in `valid` (a serialized config) that is
+/// fewest edits from it, when those are at most a third of its length
+fn closest_key(valid: &serde_yaml::Value, key: &str) -> Option<String> {
+    let segments: Vec<&str> = key.split('.').collect();
+    let (name, parents) = segments.split_last()?;
+    let parent = parents.iter().try_fold(valid, |value, segment| match segment.parse::<usize>() {
+        Ok(index) => value.get(index),
+        Err(_) => value.get(*segment),
+    })?;
+    parent.as_mapping()?.keys()
+        .filter_map(|candidate| candidate.as_str())
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance * 3 <= name.chars().count().max(3))
+        .min()
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+/// Edits (insertions, deletions, substitutions, and swaps of adjacent
+/// characters) turning `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    // d[i][j]: distance between the first i chars of a and the first j of b
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+    
+    #[test]
+    fn test_env_overrides_by_type() {
+        let config = Config::default().with_env(vars(&[
+            ("AUTODEBUGGER__VERBOSITY__INFO_THRESHOLD", "75"),
+            ("AUTODEBUGGER__VERBOSITY__EMIT_LIVE_WARNING", "true"),
+            ("AUTODEBUGGER__VALIDATE_DOCS__COMPLEXITY_THRESHOLD", "150"),
+            ("AUTODEBUGGER__VALIDATE_DOCS__IGNORE_PATTERNS", "target/**, *.generated.rs"),
+            ("AUTODEBUGGER__VALIDATE_DOCS__SEVERITIES__NO_DOCS", "error"),
+            ("AUTODEBUGGER__CI__ENABLED_CHECKS", "clippy,fmt"),
+            ("AUTODEBUGGER__LOGGING__LEVEL", "debug"),
+            ("AUTODEBUGGER__LOGGING__FORMAT", "json"),
+            ("AUTODEBUGGER__LOGGING__FILE__MAX_FILES", "3"),
+            ("AUTODEBUGGER__REMOVE_DEBUG__DEFAULT_PATHS", ""),
+            ("autodebugger__todos__max_per_worktree", "7"),
+            ("PATH", "/usr/bin"),
+        ])).unwrap();
+        
+        assert_eq!(config.verbosity.info_threshold, 75);
+        assert!(config.verbosity.emit_live_warning);
+        assert_eq!(config.validate_docs.complexity_threshold, 150);
+        assert_eq!(config.validate_docs.ignore_patterns, vec!["target/**", "*.generated.rs"]);
+        assert_eq!(config.validate_docs.severities.get(&crate::validate_docs::Rule::NoDocs),
+            Some(&crate::validate_docs::Severity::Error));
+        assert_eq!(config.ci.enabled_checks, vec![crate::ci::Check::Clippy, crate::ci::Check::Fmt]);
+        assert_eq!(config.logging.level, "debug");
+        assert_eq!(config.logging.format, LogFormat::Json);
+        // The unset file section starts from its defaults
+        let file = config.logging.file.unwrap();
+        assert_eq!(file.max_files, 3);
+        assert_eq!(file.max_size_mb, RotatingFileConfig::default().max_size_mb);
+        assert!(config.remove_debug.default_paths.is_empty());
+        assert_eq!(config.todos.max_per_worktree, 7);
+        // Untouched fields keep their values
+        assert_eq!(config.verbosity.debug_threshold, VerbosityConfig::default().debug_threshold);
+    }
+    
+    #[test]
+    fn test_env_override_errors_name_the_variable() {
+        let error = Config::default()
+            .with_env(vars(&[("AUTODEBUGGER__VERBOSITY__INFO_THRESHOLD", "lots")]))
+            .unwrap_err();
+        let message = format!("{:#}", error);
+        assert!(message.starts_with("Invalid environment override AUTODEBUGGER__VERBOSITY__INFO_THRESHOLD: "), "{}", message);
+        
+        for name in ["AUTODEBUGGER__VERBOSITY__INFO_THRESHLD", "AUTODEBUGGER__NOPE__X", "AUTODEBUGGER__VERBOSITY__INFO_THRESHOLD__X"] {
+            let error = Config::default().with_env(vars(&[(name, "1")])).unwrap_err();
+            let message = format!("{:#}", error);
+            assert!(message.contains(name) && message.contains("no config field"), "{}", message);
+        }
+    }
+    
+    #[test]
+    fn test_load_with_env_reads_process_environment() {
+        // Only this test sets the variable, so parallel tests are unaffected
+        std::env::set_var("AUTODEBUGGER__HOOKS__PROGRAM", "/opt/autodebugger");
+        let config = Config::default().with_env(std::env::vars());
+        std::env::remove_var("AUTODEBUGGER__HOOKS__PROGRAM");
+        assert_eq!(config.unwrap().hooks.program.as_deref(), Some("/opt/autodebugger"));
+        
+        // Without overrides the config is returned as loaded
+        let config = Config::default().with_env(Vec::new()).unwrap();
+        assert_eq!(config.logging.level, LoggingConfig::default().level);
+    }
+    
+    #[test]
+    fn test_find_config_files_layers() {
+        let root = tempfile::tempdir().unwrap();
+        let user = root.path().join("user");
+        let repo = root.path().join("repo");
+        let nested = repo.join("crates/core/src");
+        fs::create_dir_all(&user).unwrap();
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir(repo.join(".git")).unwrap();
+        
+        // Defaults: nothing anywhere
+        assert!(find_config_files(&nested, Some(&user)).is_empty());
+        
+        // A file above the repository root is not the project's
+        fs::write(root.path().join("config.yaml"), "").unwrap();
+        assert!(find_config_files(&nested, Some(&user)).is_empty());
+        
+        // User file
+        fs::write(user.join("config.yaml"), "").unwrap();
+        assert_eq!(find_config_files(&nested, Some(&user)), vec![user.join("config.yaml")]);
+        assert!(find_config_files(&nested, None).is_empty());
+        
+        // Project file at the repository root, found from a subdirectory, above the user's
+        fs::write(repo.join("config.yaml"), "").unwrap();
+        assert_eq!(find_config_files(&nested, Some(&user)), vec![user.join("config.yaml"), repo.join("config.yaml")]);
+        fs::write(repo.join("autodebugger.yaml"), "").unwrap();
+        assert_eq!(find_config_files(&nested, Some(&user)), vec![user.join("config.yaml"), repo.join("autodebugger.yaml")]);
+        
+        // The nearest directory wins
+        fs::write(repo.join("crates/config.yaml"), "").unwrap();
+        assert_eq!(find_config_files(&nested, None), vec![repo.join("crates/config.yaml")]);
+    }
+    
+    #[test]
+    fn test_project_layer_overrides_only_its_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.yaml");
+        let project = dir.path().join("project.yaml");
+        fs::write(&user, "verbosity:\n  info_threshold: 10\n  debug_threshold: 20\nlogging:\n  level: debug\n  file:\n    log_directory: /home/me/logs\nvalidate_docs:\n  ignore_patterns: [\"target/**\"]\n").unwrap();
+        fs::write(&project, "verbosity:\n  info_threshold: 30\nlogging:\n  file:\n    max_files: 3\nvalidate_docs:\n  ignore_patterns: [\"gen/**\"]\n").unwrap();
+        
+        let layers = Config::load_layers(&[user.clone(), project.clone()]).unwrap();
+        assert_eq!(layers.len(), 2);
+        assert_eq!(layers[0].verbosity.info_threshold, 10);
+        let config = &layers[1];
+        // Scalars the project sets win; the rest of the section stays the user's
+        assert_eq!(config.verbosity.info_threshold, 30);
+        assert_eq!(config.verbosity.debug_threshold, 20);
+        assert_eq!(config.logging.level, "debug");
+        let file = config.logging.file.as_ref().unwrap();
+        assert_eq!((file.log_directory.as_str(), file.max_files), ("/home/me/logs", 3));
+        // Lists are replaced
+        assert_eq!(config.validate_docs.ignore_patterns, vec!["gen/**"]);
+        // Keys neither sets keep their defaults
+        assert_eq!(config.verbosity.trace_threshold, VerbosityConfig::default().trace_threshold);
+        assert_eq!(file.max_size_mb, RotatingFileConfig::default().max_size_mb);
+        assert_eq!(config.validate_docs.default_paths, ValidateDocsConfig::default().default_paths);
+        assert_eq!(config.source_paths, vec![user.clone(), project.clone()]);
+        assert_eq!(config.source_description(), format!("{} + {}", user.display(), project.display()));
+        
+        // merge_lists appends the project's lists, skipping duplicates
+        fs::write(&project, "merge_lists: true\nvalidate_docs:\n  ignore_patterns: [\"gen/**\", \"target/**\"]\n").unwrap();
+        let config = Config::load_layers(&[user, project]).unwrap().pop().unwrap();
+        assert_eq!(config.validate_docs.ignore_patterns, vec!["target/**", "gen/**"]);
+    }
+    
+    #[test]
+    fn test_load_from_records_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("autodebugger.yaml");
+        fs::write(&path, "verbosity:\n  info_threshold: 12\n").unwrap();
+        
+        // An explicit path is loaded whatever discovery would find
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.verbosity.info_threshold, 12);
+        assert_eq!(config.source_paths, vec![path.clone()]);
+        assert_eq!(config.source_description(), path.display().to_string());
+        
+        // Environment overrides keep the source
+        let config = config.with_env(vars(&[("AUTODEBUGGER__VERBOSITY__INFO_THRESHOLD", "13")])).unwrap();
+        assert_eq!(config.verbosity.info_threshold, 13);
+        assert_eq!(config.source_paths, vec![path.clone()]);
+        assert_eq!(Config::default().source_description(), "defaults (no config file found)");
+    }
+    
+    #[test]
+    fn test_unknown_keys_suggest_the_closest_field() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        fs::write(&path, "verbosity:\n  info_threshold: 60\nvalidate_docs:\n  min_doc_lines_complex: 10\n  complexity_treshold: 80\nlogging:\n  levle: debug\n  mystery: 1\n").unwrap();
+        
+        // Warnings by default: the known keys still load
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.verbosity.info_threshold, 60);
+        assert_eq!(config.validate_docs.min_doc_lines_complex, 10);
+        assert_eq!(config.unknown_keys, vec![
+            UnknownKey { file: path.clone(), key: "validate_docs.complexity_treshold".to_string(), line: 5, suggestion: Some("complexity_threshold".to_string()) },
+            UnknownKey { file: path.clone(), key: "logging.levle".to_string(), line: 7, suggestion: Some("level".to_string()) },
+            UnknownKey { file: path.clone(), key: "logging.mystery".to_string(), line: 8, suggestion: None },
+        ]);
+        assert_eq!(config.unknown_keys[0].to_string(),
+            format!("{}:5: unknown key `validate_docs.complexity_treshold` (did you mean `complexity_threshold`?)", path.display()));
+        
+        // Errors, naming every key, with `strict: true`
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::write(&path, format!("strict: true\n{}", contents)).unwrap();
+        let message = format!("{:#}", Config::load_from(&path).unwrap_err());
+        assert!(message.contains("did you mean `complexity_threshold`?"), "{}", message);
+        assert!(message.contains("did you mean `level`?"), "{}", message);
+        // Lines count the added `strict: true`
+        assert!(message.contains(":9: unknown key `logging.mystery`"), "{}", message);
+    }
+    
+    #[test]
+    fn test_out_of_range_values_fail_loading() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        fs::write(&path, "validate_docs:\n  max_doc_lines: 20\nlogging:\n  file:\n    max_size_mb: 0\n").unwrap();
+        let message = format!("{:#}", Config::load_from(&path).unwrap_err());
+        assert!(message.contains("validate_docs.max_doc_lines (20) must be at least validate_docs.min_doc_lines_complex (50)"), "{}", message);
+        assert!(message.contains("logging.file.max_size_mb must be greater than 0"), "{}", message);
+        
+        // Environment overrides are checked too
+        let error = Config::default().with_env(vars(&[("AUTODEBUGGER__MONITOR__CONCURRENCY", "0")])).unwrap_err();
+        assert!(error.to_string().contains("monitor.concurrency"), "{}", error);
+        assert_eq!(edit_distance("treshold", "threshold"), 1);
+        assert_eq!(edit_distance("levle", "level"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+    
+    #[test]
+    fn test_profile_overlays_base_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        fs::write(&path, "\
+verbosity:
+  info_threshold: 40
+validate_docs:
+  ignore_patterns: [\"target/**\"]
+  severities: {no_docs: warning}
+profiles:
+  ci:
+    validate_docs:
+      min_doc_lines_complex: 10
+      ignore_patterns: [\"gen/**\"]
+      severities: {missing_item_docs: error}
+    logging:
+      file:
+        max_files: 2
+  dev:
+    verbosity:
+      info_treshold: 500
+").unwrap();
+        let base = Config::load_from(&path).unwrap();
+        // Typos inside a profile are found when the file loads
+        assert_eq!(base.unknown_keys.len(), 1);
+        assert_eq!(base.unknown_keys[0].key, "profiles.dev.verbosity.info_treshold");
+        assert_eq!(base.unknown_keys[0].line, 17);
+        assert_eq!(base.unknown_keys[0].suggestion.as_deref(), Some("info_threshold"));
+        
+        let ci = base.clone().with_profile("ci").unwrap();
+        // Nested fields the profile sets win, their siblings stay the base's
+        assert_eq!(ci.validate_docs.min_doc_lines_complex, 10);
+        assert_eq!(ci.verbosity.info_threshold, 40);
+        assert_eq!(ci.validate_docs.max_doc_lines, ValidateDocsConfig::default().max_doc_lines);
+        // Lists are replaced, maps merged per key
+        assert_eq!(ci.validate_docs.ignore_patterns, vec!["gen/**"]);
+        assert_eq!(ci.validate_docs.severities.len(), 2);
+        // An unset optional section comes in with its defaults
+        let file = ci.logging.file.as_ref().unwrap();
+        assert_eq!((file.max_files, file.max_size_mb), (2, RotatingFileConfig::default().max_size_mb));
+        assert_eq!(ci.source_description(), format!("{} (profile ci)", path.display()));
+        // Environment overrides keep the profile
+        let ci = ci.with_env(vars(&[("AUTODEBUGGER__VERBOSITY__INFO_THRESHOLD", "41")])).unwrap();
+        assert_eq!((ci.verbosity.info_threshold, ci.profile.as_deref()), (41, Some("ci")));
+        
+        let error = base.clone().with_profile("release").unwrap_err();
+        assert_eq!(error.to_string(), "Unknown profile release; available profiles: ci, dev");
+        let error = Config::default().with_profile("ci").unwrap_err();
+        assert!(error.to_string().contains("defines no profiles"), "{}", error);
+    }
+    
+    #[test]
+    fn test_logging_and_monitor_sections() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        fs::write(&path, "\
+logging:
+  default_level: autodebugger=debug,info
+  output: stderr
+  quiet: true
+  file:
+    enabled: false
+    log_directory: /var/log/autodebugger
+    filename: cli.log
+    max_files: 4
+    max_size_mb: 8
+    console_output: false
+monitor:
+  worktrees_dirs: [trees, ../more]
+  diff_exclude: [\"*.lock\"]
+  status_cache: false
+").unwrap();
+        let config = Config::load_from(&path).unwrap();
+        assert!(config.unknown_keys.is_empty(), "{:?}", config.unknown_keys);
+        assert_eq!(config.logging.level, "autodebugger=debug,info");
+        assert_eq!(config.logging.output, "stderr");
+        assert!(config.logging.quiet);
+        let file = config.logging.file.unwrap();
+        assert!(!file.enabled);
+        assert_eq!((file.log_directory.as_str(), file.filename.as_str()), ("/var/log/autodebugger", "cli.log"));
+        assert_eq!((file.max_files, file.max_size_mb, file.console_output), (4, 8, false));
+        assert_eq!(config.monitor.worktrees_dirs, vec!["trees", "../more"]);
+        assert_eq!(config.monitor.diff_exclude, vec!["*.lock"]);
+        assert!(!config.monitor.status_cache);
+        
+        // The defaults are what the CLI did before these settings existed
+        let defaults = Config::default();
+        assert_eq!((defaults.logging.level.as_str(), defaults.logging.output.as_str()), ("info", "stdout"));
+        assert!(defaults.logging.file.is_none() && !defaults.logging.quiet);
+        assert!(RotatingFileConfig::default().enabled);
+        assert_eq!(defaults.monitor.worktrees_dirs, vec!["worktrees"]);
+        assert!(defaults.monitor.status_cache);
+    }
+    
+    #[test]
+    fn test_tasks_take_strings_or_maps() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        fs::write(&path, "\
+tasks:
+  check: [cargo fmt --check, cargo clippy]
+  docs:
+    - run: cargo doc
+      dir: crates/core
+      env: {RUSTDOCFLAGS: -D warnings}
+").unwrap();
+        let config = Config::load_from(&path).unwrap();
+        assert!(config.unknown_keys.is_empty(), "{:?}", config.unknown_keys);
+        assert_eq!(config.tasks["check"], vec![TaskStep::new("cargo fmt --check"), TaskStep::new("cargo clippy")]);
+        let docs = &config.tasks["docs"][0];
+        assert_eq!((docs.run.as_str(), docs.dir.as_deref()), ("cargo doc", Some("crates/core")));
+        assert_eq!(docs.env["RUSTDOCFLAGS"], "-D warnings");
+        
+        // Plain steps are written back as strings
+        let written = serde_yaml::to_string(&config.tasks).unwrap();
+        assert!(written.contains("- cargo fmt --check"), "{}", written);
+        assert!(written.contains("dir: crates/core"), "{}", written);
+        
+        fs::write(&path, "tasks:\n  check:\n    - {run: cargo test, cwd: core}\n").unwrap();
+        let error = format!("{:#}", Config::load_from(&path).unwrap_err());
+        assert!(error.contains("a command line, or a map with run"), "{}", error);
+        assert_eq!(config.task("lint").unwrap_err().to_string(), "Unknown task lint; available tasks: check, docs");
+        assert!(Config::default().task("check").unwrap_err().to_string().contains("defines no tasks"));
+        
+        fs::write(&path, "tasks:\n  check: []\n").unwrap();
+        let error = format!("{:#}", Config::load_from(&path).unwrap_err());
+        assert!(error.contains("tasks.check must have at least one step"), "{}", error);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,394 @@
+//! Commented YAML renderings of a `Config`, behind `autodebugger config`
+//!
+//! [`default_template`] is the file `config init` writes: every setting at the
+//! value `Config::default()` serializes to, each under a one-line comment. The
+//! values are never typed out here, so they can't drift from the serde
+//! defaults; the comments come from [`FIELD_DOCS`], and a test fails when a
+//! field is added, renamed, or removed without updating that table.
+//! Optional sections that are off by default (`logging.file`) are written
+//! commented out, with their own defaults, so uncommenting them turns them on.
+//!
+//! [`render_effective`] is `config show`: the configuration a command would
+//! run with, annotating each value that differs from the layer below it with
+//! where it came from (the user or project config file, a profile, an
+//! `AUTODEBUGGER__*` variable, or a command-line option).
+//!
+//! Both walk the serialized config the same way: a mapping is a section (one
+//! comment per field, recursed into) when [`FIELD_DOCS`] has fields under it,
+//! and a value otherwise, so maps such as `validate_docs.severities` and lists
+//! of structs are printed by serde_yaml as a whole.
+
+use anyhow::{Context, Result};
+use serde_yaml::{Mapping, Value};
+
+use crate::config::{Config, RotatingFileConfig, ENV_PREFIX};
+use std::path::PathBuf;
+
+/// What a layer of the effective configuration adds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// A config file (`Config::load_layers`)
+    File(PathBuf),
+    /// A profile from `profiles` (`Config::with_profile`)
+    Profile(String),
+    /// `AUTODEBUGGER__*` variables (`Config::with_env`)
+    Environment,
+    /// Command-line options
+    CommandLine,
+}
+
+impl ConfigSource {
+    /// Comment above a value at the dotted `path` this layer set
+    fn describe(&self, path: &str) -> String {
+        match self {
+            ConfigSource::File(file) => format!("from {}", file.display()),
+            ConfigSource::Profile(name) => format!("from profile {}", name),
+            ConfigSource::Environment => format!("from {}{}", ENV_PREFIX, path.replace('.', "__").to_uppercase()),
+            ConfigSource::CommandLine => "from the command line".to_string(),
+        }
+    }
+}
+
+/// Comment for every config field and section, by dotted path
+pub const FIELD_DOCS: &[(&str, &str)] = &[
+    ("verbosity", "Log verbosity thresholds (INFO, DEBUG, and TRACE only; warnings and errors are never too many)"),
+    ("verbosity.info_threshold", "INFO events before the verbosity check warns"),
+    ("verbosity.debug_threshold", "DEBUG events before the verbosity check warns"),
+    ("verbosity.trace_threshold", "TRACE events before the verbosity check warns"),
+    ("verbosity.emit_live_warning", "Log one WARN as soon as a threshold is crossed, not only on check_and_report()"),
+    ("logging", "CLI logging"),
+    ("logging.level", "Level or filter directives when RUST_LOG is unset (also read as default_level)"),
+    ("logging.output", "Console destination: stdout, stderr, or mcp (stderr, reports on stderr, JSON log files)"),
+    ("logging.format", "Console format: pretty or json"),
+    ("logging.file_format", "Rotating log file format; null uses format"),
+    ("logging.suppress", "Filter directives for noisy crates, e.g. sqlx=warn; RUST_LOG overrides them"),
+    ("logging.quiet", "Console logs at WARN and no startup/shutdown lines (--quiet)"),
+    ("logging.file", "Rotating file logging, off when unset; uncomment to log to files too"),
+    ("logging.file.enabled", "Write the log files; false logs to the console only (--no-log-file)"),
+    ("logging.file.log_directory", "Directory for the log files (created if missing)"),
+    ("logging.file.filename", "Base filename of the logs"),
+    ("logging.file.max_files", "Rotated files kept"),
+    ("logging.file.max_total_size_mb", "Cap on the combined size of the files in MB, oldest deleted first; null for none"),
+    ("logging.file.max_size_mb", "File size in MB that triggers rotation (--max-size-mb overrides it)"),
+    ("logging.file.console_output", "Also log to the console"),
+    ("logging.file.truncate_on_limit", "Truncate at the size limit instead of keeping numbered backups"),
+    ("logging.file.format", "File format; null uses logging.file_format, else the console format"),
+    ("logging.file.rotation", "size, daily, hourly, or size_or_daily"),
+    ("logging.file.compress_rotated", "Gzip each rotated file in the background (needs gzip on PATH)"),
+    ("logging.file.non_blocking", "Write on a background thread so a slow disk doesn't stall logging"),
+    ("logging.file.buffered_lines", "Lines buffered for the background thread in non-blocking mode"),
+    ("logging.file.when_full", "What a write does when that buffer is full: drop_oldest or block"),
+    ("logging.file.write_header", "Start each file with a line naming the version, command, pid, host, and filter"),
+    ("logging.file.log_panics", "Log panics, with a backtrace, to the file"),
+    ("logging.file.json_fields", "In the pretty format, end lines with the fields as a JSON object instead of key=value pairs"),
+    ("logging.span_timing", "Per-span timing, reported at shutdown"),
+    ("logging.span_timing.enabled", "Time spans and print a report at shutdown"),
+    ("logging.span_timing.warn_threshold_ms", "Log a WARN when one span takes longer than this; null never warns"),
+    ("remove_debug", "remove-debug command"),
+    ("remove_debug.default_paths", "Paths searched when none is given"),
+    ("remove_debug.macros", "Macro names (without !) to remove"),
+    ("remove_debug.backups", "Back up files to .autodebugger/backups before modifying them"),
+    ("remove_debug.backup_retention", "Backup runs kept; older ones are pruned"),
+    ("remove_debug.jobs", "Files processed in parallel (0 = one per CPU)"),
+    ("remove_debug.tidy_whitespace", "Collapse blank lines left behind by removals"),
+    ("remove_debug.engine", "How calls are located: regex, or ast when built with the ast feature"),
+    ("validate_docs", "validate-docs command"),
+    ("validate_docs.default_paths", "Paths validated when none is given"),
+    ("validate_docs.min_doc_lines_complex", "Minimum //! lines for a complex module"),
+    ("validate_docs.max_doc_lines", "Maximum //! lines for any module (at least min_doc_lines_complex)"),
+    ("validate_docs.complexity_threshold", "Lines of code that make a module complex (--complexity-threshold overrides it)"),
+    ("validate_docs.use_raw_line_count", "Count blank and comment lines toward the threshold too"),
+    ("validate_docs.ignore_patterns", "Glob patterns of files to skip"),
+    ("validate_docs.require_pub_item_docs", "Require /// docs on public functions, structs, enums, and traits"),
+    ("validate_docs.pub_item_allowlist", "Glob patterns of item names exempt from require_pub_item_docs"),
+    ("validate_docs.overrides", "Thresholds for files matching a glob, e.g. [{pattern: \"src/generated/**\", max_doc_lines: 400}]; the first match wins"),
+    ("validate_docs.doc_template", "Starter docs inserted by --fix ({module_name} and {line_count} are replaced)"),
+    ("validate_docs.check_references", "Check that symbols named in //! docs exist in the scanned source"),
+    ("validate_docs.reference_allowlist", "Glob patterns of external symbols check_references accepts"),
+    ("validate_docs.check_config_snippets", "Check ```yaml blocks in //! docs against this schema"),
+    ("validate_docs.jobs", "Files checked in parallel (0 = one per CPU)"),
+    ("validate_docs.min_coverage", "Fail below this percentage of documented complex modules; null for no minimum"),
+    ("validate_docs.placeholder_markers", "Words marking a //! line as a placeholder; empty disables the check"),
+    ("validate_docs.count_placeholder_lines", "Count placeholder lines toward min_doc_lines_complex"),
+    ("validate_docs.max_placeholder_fraction", "Share of //! lines (0.0 to 1.0) that may be placeholders"),
+    ("validate_docs.severities", "Severity per rule, e.g. {no_docs: error}; rules not listed are warnings"),
+    ("redaction", "Secret redaction for commands run through autodebugger"),
+    ("redaction.patterns", "Regex patterns to mask (replaces the built-in list when set)"),
+    ("redaction.redact_output", "Also mask matches in the returned stdout and stderr"),
+    ("todos", "TODO/FIXME/HACK scanning for the todos context"),
+    ("todos.extensions", "File extensions scanned (without the dot)"),
+    ("todos.ignore_patterns", "Glob patterns to skip, relative to each worktree"),
+    ("todos.max_per_worktree", "Items reported per worktree"),
+    ("todos.max_file_size_kb", "Larger files are skipped"),
+    ("monitor", "Worktree discovery for monitor, status, diff, and context"),
+    ("monitor.worktrees_dirs", "Directories whose subdirectories are worktrees (relative to the workspace)"),
+    ("monitor.extra_worktrees", "Individual worktree paths, e.g. outside the workspace"),
+    ("monitor.concurrency", "Worktrees inspected at once"),
+    ("monitor.diff_exclude", "Glob pathspecs hidden from diffs by default"),
+    ("monitor.status_cache", "Reuse unchanged worktree statuses between runs (.autodebugger/status_cache.json; --no-cache skips it once)"),
+    ("monitor.include_submodules", "Recurse into submodules in diffs"),
+    ("monitor.task_sections", "Headers in CLAUDE.local.md whose list items are tasks"),
+    ("monitor.task_template", "Template for CLAUDE.local.md in worktrees created with --seed; null uses a built-in one"),
+    ("monitor.health", "Worktree health score penalties"),
+    ("monitor.health.stale_after_days", "Days without a commit before a worktree is stale"),
+    ("monitor.health.stale", "Stale worktree"),
+    ("monitor.health.uncommitted_file", "Per uncommitted file"),
+    ("monitor.health.max_uncommitted", "Cap on the uncommitted file penalty"),
+    ("monitor.health.behind_commit", "Per commit behind the upstream"),
+    ("monitor.health.max_behind", "Cap on the behind penalty"),
+    ("monitor.health.no_upstream", "Branch without an upstream"),
+    ("monitor.health.overlap", "Per file also changed in another worktree"),
+    ("monitor.health.overlap_conflict", "Per overlapping file whose changed lines intersect (instead of overlap)"),
+    ("monitor.health.max_overlap", "Cap on the overlap penalties"),
+    ("monitor.health.conflicts", "Unresolved conflicts in the index"),
+    ("monitor.health.operation", "Merge, rebase, cherry-pick, or revert in progress"),
+    ("ci", "Merge readiness scoring for autodebugger ci"),
+    ("ci.enabled_checks", "Checks run; the others are skipped and cost nothing"),
+    ("ci.weights", "Points subtracted from the safety score per failed check"),
+    ("ci.weights.cargo_check", "cargo check"),
+    ("ci.weights.cargo_test", "cargo test"),
+    ("ci.weights.clippy", "cargo clippy"),
+    ("ci.weights.debug_macros", "debug! calls left in the code"),
+    ("ci.weights.documentation", "validate-docs --strict"),
+    ("ci.weights.fmt", "cargo fmt --check"),
+    ("ci.weights.audit", "cargo audit (skipped unless installed)"),
+    ("ci.conflict_penalties", "Points subtracted per predicted conflict"),
+    ("ci.conflict_penalties.high", "High severity conflict"),
+    ("ci.conflict_penalties.medium", "Medium severity conflict"),
+    ("ci.conflict_penalties.low", "Low severity conflict"),
+    ("ci.conflict_penalties.same_item", "Both sides changed the same fn or type in different lines"),
+    ("ci.thresholds", "Minimum scores for the recommendations"),
+    ("ci.thresholds.safe", "SAFE from this score (at most 100)"),
+    ("ci.thresholds.caution", "CAUTION from this score (at most safe), DANGER below"),
+    ("ci.parallel", "Run independent checks at the same time"),
+    ("ci.serialize_check_and_clippy", "Run cargo check and clippy one after the other"),
+    ("ci.timeout_secs", "Seconds before a check is stopped and fails"),
+    ("ci.timeouts", "Per-check timeouts, e.g. {cargo_test: 1800}"),
+    ("hooks", "Git hooks written by install-hooks (check names as in ci.enabled_checks)"),
+    ("hooks.pre_commit", "Checks run before each commit"),
+    ("hooks.pre_push", "Checks run before each push"),
+    ("hooks.program", "autodebugger binary the hooks call; null uses the one running install-hooks"),
+    ("server", "HTTP endpoints of autodebugger serve (built with the server feature)"),
+    ("server.bind", "Address to listen on; 127.0.0.1 keeps the endpoints on this machine"),
+    ("server.port", "Port to listen on (--port overrides it)"),
+    ("server.workspace", "Workspace whose worktrees are served (--path overrides it); null uses the current directory"),
+    ("clean", "Pre-PR sweep of autodebugger clean"),
+    ("clean.steps", "Steps run, always in this order: remove_debug, validate_docs, fmt (rustfmt on the files remove_debug changed)"),
+    ("tasks", "Command sequences for autodebugger task NAME; a step is a command line or {run, dir, env}, e.g. {check: [cargo fmt --check, cargo clippy, cargo test]}"),
+    ("strict", "Fail on unknown (e.g. misspelled) keys instead of warning (--strict-config does the same)"),
+    ("profiles", "Named partial configs merged over these settings with --profile NAME or AUTODEBUGGER_PROFILE, e.g. {ci: {validate_docs: {min_coverage: 90}}}"),
+    ("merge_lists", "Append this file's lists to the user config's instead of replacing them"),
+];
+
+/// Sections that are off by default, written commented out with their defaults
+const OPTIONAL_SECTIONS: &[&str] = &["logging.file"];
+
+/// Header of the file written by `config init`
+const TEMPLATE_HEADER: &str = "\
+# Autodebugger configuration, written by `autodebugger config init` with every
+# setting at its default; delete the ones you don't change. AUTODEBUGGER__*
+# environment variables and command-line options override this file.
+";
+
+/// config.yaml with every setting at its default, commented
+pub fn default_template() -> String {
+    let mut config = Config::default();
+    config.logging.file = Some(RotatingFileConfig::default());
+    let value = serde_yaml::to_value(&config).expect("Config serializes to YAML");
+
+    let mut out = TEMPLATE_HEADER.to_string();
+    if let Value::Mapping(mapping) = &value {
+        write_mapping(&mut out, mapping, "", 0, OPTIONAL_SECTIONS, &|path| field_doc(path).map(str::to_string));
+    }
+    out
+}
+
+/// The last of `layers` as YAML, each value preceded by a comment naming the
+/// topmost layer that changed it. `layers` are lowest first, and each config
+/// includes the ones below it (the defaults are below the first)
+pub fn render_effective(layers: &[(ConfigSource, Config)]) -> Result<String> {
+    let defaults = Config::default();
+    let configs: Vec<&Config> = std::iter::once(&defaults).chain(layers.iter().map(|(_, config)| config)).collect();
+    let values = configs.iter()
+        .map(|config| serde_yaml::to_value(config).context("Failed to serialize config"))
+        .collect::<Result<Vec<Value>>>()?;
+    let source = |path: &str| {
+        // Only values are annotated; a changed section shows in its fields
+        if is_section(path) {
+            return None;
+        }
+        let at: Vec<Option<&Value>> = values.iter().map(|value| lookup(value, path)).collect();
+        (1..at.len()).rev()
+            .find(|&index| at[index] != at[index - 1])
+            .map(|index| layers[index - 1].0.describe(path))
+    };
+
+    let effective = configs[configs.len() - 1];
+    let mut out = format!("# Effective configuration; files: {}\n", effective.source_description());
+    if let Value::Mapping(mapping) = &values[values.len() - 1] {
+        write_mapping(&mut out, mapping, "", 0, &[], &source);
+    }
+    Ok(out)
+}
+
+/// Comment for the field or section at the dotted `path`
+fn field_doc(path: &str) -> Option<&'static str> {
+    FIELD_DOCS.iter().find(|(field, _)| *field == path).map(|(_, doc)| *doc)
+}
+
+/// Whether `path` is a struct whose fields get their own lines and comments
+fn is_section(path: &str) -> bool {
+    let prefix = format!("{}.", path);
+    FIELD_DOCS.iter().any(|(field, _)| field.starts_with(&prefix))
+}
+
+/// Value at the dotted `path`, if the sections above it are set
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, segment| value.get(segment))
+}
+
+/// Append `mapping` at `depth` with `comment` for each key above it; every
+/// line of the sections in `commented_out` goes behind `# `
+fn write_mapping(
+    out: &mut String,
+    mapping: &Mapping,
+    path: &str,
+    depth: usize,
+    commented_out: &[&str],
+    comment: &dyn Fn(&str) -> Option<String>,
+) {
+    let indent = "  ".repeat(depth);
+    for (key, value) in mapping {
+        let Some(name) = key.as_str() else { continue };
+        let field = if path.is_empty() { name.to_string() } else { format!("{}.{}", path, name) };
+        if depth == 0 {
+            out.push('\n');
+        }
+        // Inside a commented-out section comments get a second `# `, so removing
+        // the first from every line of the section turns it on
+        let inside = commented_out.iter().any(|section| field.starts_with(&format!("{}.", section)));
+        let hidden = inside || commented_out.contains(&field.as_str());
+        let prefix = if hidden { "# " } else { "" };
+        if let Some(text) = comment(&field) {
+            out.push_str(&format!("{}{}# {}\n", indent, if inside { "# " } else { "" }, text));
+        }
+
+        match value {
+            Value::Mapping(fields) if is_section(&field) => {
+                out.push_str(&format!("{}{}{}:\n", indent, prefix, name));
+                write_mapping(out, fields, &field, depth + 1, commented_out, comment);
+            }
+            _ => {
+                let mut entry = Mapping::new();
+                entry.insert(key.clone(), value.clone());
+                let text = serde_yaml::to_string(&entry).unwrap_or_default();
+                for line in text.lines() {
+                    if line.is_empty() && !hidden {
+                        out.push('\n');
+                    } else {
+                        out.push_str(&format!("{}{}{}\n", indent, prefix, line));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// Dotted paths of every field and section in `value`
+    fn field_paths(value: &Value, path: &str, paths: &mut BTreeSet<String>) {
+        let Value::Mapping(mapping) = value else { return };
+        for (key, child) in mapping {
+            let field = if path.is_empty() { key.as_str().unwrap().to_string() } else { format!("{}.{}", path, key.as_str().unwrap()) };
+            // Maps keyed by data (severities, timeouts) are empty by default
+            field_paths(child, &field, paths);
+            paths.insert(field);
+        }
+    }
+
+    #[test]
+    fn test_field_docs_match_the_schema() {
+        let mut config = Config::default();
+        config.logging.file = Some(RotatingFileConfig::default());
+        let mut schema = BTreeSet::new();
+        field_paths(&serde_yaml::to_value(&config).unwrap(), "", &mut schema);
+        let documented: BTreeSet<String> = FIELD_DOCS.iter().map(|(field, _)| field.to_string()).collect();
+
+        let undocumented: Vec<_> = schema.difference(&documented).collect();
+        let stale: Vec<_> = documented.difference(&schema).collect();
+        assert!(undocumented.is_empty(), "fields missing from FIELD_DOCS: {:?}", undocumented);
+        assert!(stale.is_empty(), "FIELD_DOCS entries for no field: {:?}", stale);
+    }
+
+    #[test]
+    fn test_template_loads_as_the_defaults() {
+        let template = default_template();
+        assert!(template.contains("# Lines of code that make a module complex (--complexity-threshold overrides it)\n  complexity_threshold: 200\n"), "{}", template);
+        assert!(template.contains("  # file:\n"), "{}", template);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        std::fs::write(&path, &template).unwrap();
+        let loaded = Config::load_from(&path).unwrap();
+        assert!(loaded.unknown_keys.is_empty(), "{:?}", loaded.unknown_keys);
+        assert_eq!(serde_yaml::to_value(&loaded).unwrap(), serde_yaml::to_value(Config::default()).unwrap());
+
+        // Uncommenting the file section turns on file logging at its defaults
+        let mut in_section = false;
+        let uncommented: String = template.lines()
+            .map(|line| {
+                in_section = line == "  # file:" || in_section && line.starts_with("    #");
+                match line.find("# ") {
+                    Some(at) if in_section => format!("{}{}\n", &line[..at], &line[at + 2..]),
+                    _ => format!("{}\n", line),
+                }
+            })
+            .collect();
+        std::fs::write(&path, &uncommented).unwrap();
+        let loaded = Config::load_from(&path).unwrap();
+        assert!(loaded.unknown_keys.is_empty(), "{:?}", loaded.unknown_keys);
+        assert_eq!(serde_yaml::to_value(loaded.logging.file).unwrap(), serde_yaml::to_value(Some(RotatingFileConfig::default())).unwrap());
+    }
+
+    #[test]
+    fn test_render_effective_names_each_source() {
+        let mut user = Config::default();
+        user.verbosity.info_threshold = 60;
+        user.logging.level = "debug".to_string();
+        user.source_paths = vec!["home/config.yaml".into()];
+        let mut project = user.clone();
+        project.verbosity.info_threshold = 70;
+        project.source_paths.push("project/config.yaml".into());
+        project.profiles.insert("ci".to_string(), serde_yaml::from_str("verbosity: {emit_live_warning: true}").unwrap());
+        let ci = project.clone().with_profile("ci").unwrap();
+        let env = ci.clone()
+            .with_env(vec![("AUTODEBUGGER__VERBOSITY__DEBUG_THRESHOLD".to_string(), "150".to_string())])
+            .unwrap();
+        let mut effective = env.clone();
+        effective.validate_docs.complexity_threshold = 10;
+
+        let text = render_effective(&[
+            (ConfigSource::File("home/config.yaml".into()), user),
+            (ConfigSource::File("project/config.yaml".into()), project),
+            (ConfigSource::Profile("ci".to_string()), ci),
+            (ConfigSource::Environment, env),
+            (ConfigSource::CommandLine, effective.clone()),
+        ]).unwrap();
+        assert!(text.starts_with("# Effective configuration; files: home/config.yaml + project/config.yaml (profile ci)\n"), "{}", text);
+        assert!(text.contains("  # from profile ci\n  emit_live_warning: true\n"), "{}", text);
+        assert!(text.contains("  # from project/config.yaml\n  info_threshold: 70\n"), "{}", text);
+        assert!(text.contains("  # from home/config.yaml\n  level: debug\n"), "{}", text);
+        assert!(text.contains("  # from AUTODEBUGGER__VERBOSITY__DEBUG_THRESHOLD\n  debug_threshold: 150\n"), "{}", text);
+        assert!(text.contains("  # from the command line\n  complexity_threshold: 10\n"), "{}", text);
+        // Defaults and sections carry no comment
+        assert!(text.contains("  debug_threshold: 150\n  trace_threshold: 200\n"), "{}", text);
+        assert!(text.contains("\n\nverbosity:\n"), "{}", text);
+
+        let parsed: Config = serde_yaml::from_str(&text).unwrap();
+        assert_eq!(serde_yaml::to_value(parsed).unwrap(), serde_yaml::to_value(&effective).unwrap());
+    }
+}
@@ -0,0 +1,187 @@
+//! Error kinds and exit codes for the CLI
+//!
+//! Every command returns a [`CliError`] on failure, whose [`ErrorKind`] decides
+//! the process exit code, so scripts can tell failed checks from a broken setup:
+//!
+//! | Code | Kind | Meaning |
+//! |------|------|---------|
+//! | 0 | | Success |
+//! | 1 | `check_failed` | A check ran and found problems (validate-docs, remove-debug --check, ci, config validate) |
+//! | 2 | `usage`, `config` | Invalid arguments, or a config file that fails to load or validate |
+//! | 3 | `environment` | Something outside autodebugger is missing or failing: a path, git, a file |
+//! | 4 | `internal` | Anything else, likely a bug |
+//!
+//! Errors converted with `?` are classified by their causes: any
+//! `std::io::Error` in the chain (a missing path, a program that cannot be run)
+//! makes an environment error, everything else is internal. Call sites that know
+//! better tag the error with [`ErrorKindExt::error_kind`]. With `--error-format json`
+//! the CLI prints [`CliError::to_json`] to stderr instead of the text message.
+
+use serde::Serialize;
+use std::fmt;
+
+/// What kind of failure ended a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// A check ran and failed
+    CheckFailed,
+    /// Invalid command-line arguments
+    Usage,
+    /// A config file that fails to load or validate
+    Config,
+    /// A missing path or program, or another I/O failure
+    Environment,
+    /// Anything else
+    Internal,
+}
+
+impl ErrorKind {
+    /// Process exit code: 1 check failures, 2 usage and config errors,
+    /// 3 environment errors, 4 internal errors
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ErrorKind::CheckFailed => 1,
+            ErrorKind::Usage | ErrorKind::Config => 2,
+            ErrorKind::Environment => 3,
+            ErrorKind::Internal => 4,
+        }
+    }
+
+    /// Environment for errors caused by an I/O error, else internal
+    pub fn classify(error: &anyhow::Error) -> Self {
+        if error.chain().any(|cause| cause.is::<std::io::Error>()) {
+            ErrorKind::Environment
+        } else {
+            ErrorKind::Internal
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ErrorKind::CheckFailed => "check_failed",
+            ErrorKind::Usage => "usage",
+            ErrorKind::Config => "config",
+            ErrorKind::Environment => "environment",
+            ErrorKind::Internal => "internal",
+        })
+    }
+}
+
+/// An error ending a CLI command: the underlying error, its kind, and an
+/// optional hint on how to fix it
+///
+/// Like `anyhow::Error` it does not implement `std::error::Error`, so any error
+/// converts into it with `?`.
+#[derive(Debug)]
+pub struct CliError {
+    kind: ErrorKind,
+    error: anyhow::Error,
+    hint: Option<String>,
+}
+
+impl CliError {
+    pub fn new(kind: ErrorKind, error: impl Into<anyhow::Error>) -> Self {
+        Self { kind, error: error.into(), hint: None }
+    }
+
+    /// An error of `kind` with just a message
+    pub fn msg(kind: ErrorKind, message: impl fmt::Display + fmt::Debug + Send + Sync + 'static) -> Self {
+        Self::new(kind, anyhow::Error::msg(message))
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn hint(&self) -> Option<&str> {
+        self.hint.as_deref()
+    }
+
+    pub fn exit_code(&self) -> u8 {
+        self.kind.exit_code()
+    }
+
+    /// The underlying error, with its causes and backtrace
+    pub fn error(&self) -> &anyhow::Error {
+        &self.error
+    }
+
+    /// `{"error": {"kind", "message", "hint"}}`; the message includes the causes
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "error": {
+                "kind": self.kind,
+                "message": format!("{:#}", self.error),
+                "hint": self.hint,
+            }
+        })
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#}", self.error)
+    }
+}
+
+impl<E: Into<anyhow::Error>> From<E> for CliError {
+    fn from(error: E) -> Self {
+        let error = error.into();
+        Self { kind: ErrorKind::classify(&error), error, hint: None }
+    }
+}
+
+/// An I/O "not found" error with `message`, for a missing path; `?` makes it an
+/// environment error
+pub fn not_found(message: impl fmt::Display) -> anyhow::Error {
+    std::io::Error::new(std::io::ErrorKind::NotFound, message.to_string()).into()
+}
+
+/// Tag the error of a `Result` with its kind
+pub trait ErrorKindExt<T> {
+    fn error_kind(self, kind: ErrorKind) -> Result<T, CliError>;
+}
+
+impl<T, E: Into<anyhow::Error>> ErrorKindExt<T> for Result<T, E> {
+    fn error_kind(self, kind: ErrorKind) -> Result<T, CliError> {
+        self.map_err(|error| CliError::new(kind, error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_kinds_from_causes_and_tags() {
+        let missing: anyhow::Result<()> = Err(std::io::Error::from(std::io::ErrorKind::NotFound))
+            .context("Failed to run git");
+        let error = CliError::from(missing.unwrap_err());
+        assert_eq!((error.kind(), error.exit_code()), (ErrorKind::Environment, 3));
+        assert_eq!(error.to_string(), "Failed to run git: entity not found");
+
+        assert_eq!(CliError::from(anyhow::anyhow!("bad state")).exit_code(), 4);
+        let tagged: Result<(), CliError> = Err(anyhow::anyhow!("bad key")).error_kind(ErrorKind::Config);
+        assert_eq!(tagged.unwrap_err().exit_code(), 2);
+    }
+
+    #[test]
+    fn test_json_envelope() {
+        let error = CliError::msg(ErrorKind::Usage, "--format jsonl requires --watch")
+            .with_hint("Add --watch");
+        assert_eq!(error.to_json(), serde_json::json!({
+            "error": { "kind": "usage", "message": "--format jsonl requires --watch", "hint": "Add --watch" }
+        }));
+        let unhinted = CliError::msg(ErrorKind::CheckFailed, "2 warnings");
+        assert_eq!(unhinted.to_json()["error"]["hint"], serde_json::Value::Null);
+    }
+}
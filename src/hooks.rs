@@ -0,0 +1,333 @@
+//! Git hooks that run autodebugger checks before a commit or push
+//!
+//! `HookInstaller` writes a marker-delimited section into `pre-commit` or
+//! `pre-push` in the repository's hooks directory, as reported by
+//! `git rev-parse --git-path hooks` (the common `.git/hooks` for a linked
+//! worktree, or `core.hooksPath`). Each configured check becomes a command that
+//! stops the hook on failure:
+//!
+//! - `debug_macros`: `autodebugger remove-debug --check`
+//! - `documentation`: `autodebugger validate-docs --strict`
+//! - the others: one `autodebugger ci . --checks ...` for all of them
+//!
+//! An existing shell hook keeps its content; the section goes right after its
+//! shebang, so the checks run first and the original script runs when they
+//! pass. A hook in another language is renamed to `<hook>.pre-autodebugger`
+//! and called from the end of the section. Uninstalling removes only the
+//! section, restoring a renamed hook and deleting a hook left empty.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::ci::Check;
+use crate::remove_debug::git_output;
+
+/// First line of the managed section
+pub const BEGIN_MARKER: &str = "# >>> autodebugger >>>";
+/// Last line of the managed section
+pub const END_MARKER: &str = "# <<< autodebugger <<<";
+/// Suffix of a non-shell hook moved aside to be chained
+const CHAINED_SUFFIX: &str = ".pre-autodebugger";
+
+/// A git hook autodebugger can install
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    PreCommit,
+    PrePush,
+}
+
+impl Hook {
+    pub const ALL: [Hook; 2] = [Hook::PreCommit, Hook::PrePush];
+
+    /// File name in the hooks directory
+    pub fn name(self) -> &'static str {
+        match self {
+            Hook::PreCommit => "pre-commit",
+            Hook::PrePush => "pre-push",
+        }
+    }
+}
+
+impl fmt::Display for Hook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// Installs and removes autodebugger sections in a repository's hooks
+#[derive(Debug, Clone)]
+pub struct HookInstaller {
+    /// Any directory in the repository or one of its worktrees
+    repo: PathBuf,
+    /// autodebugger binary the hooks call
+    program: String,
+}
+
+impl HookInstaller {
+    pub fn new(repo: impl Into<PathBuf>) -> Self {
+        Self { repo: repo.into(), program: "autodebugger".to_string() }
+    }
+
+    /// Call `program` from the hooks instead of `autodebugger` on PATH
+    pub fn with_program(mut self, program: impl Into<String>) -> Self {
+        self.program = program.into();
+        self
+    }
+
+    /// The directory git runs hooks from
+    pub fn hooks_dir(&self) -> Result<PathBuf> {
+        let output = git_output(&self.repo, &["rev-parse", "--git-path", "hooks"])
+            .with_context(|| format!("Not a git repository: {}", self.repo.display()))?;
+        Ok(self.repo.join(output.trim()))
+    }
+
+    /// Write (or replace) the section of `hook` that runs `checks`; no checks
+    /// removes the section instead. Returns the hook file.
+    pub fn install(&self, hook: Hook, checks: &[Check]) -> Result<PathBuf> {
+        let path = self.hooks_dir()?.join(hook.name());
+        if checks.is_empty() {
+            self.uninstall(hook)?;
+            return Ok(path);
+        }
+        let existing = match fs::read_to_string(&path) {
+            Ok(content) => Some(content),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+            Err(error) => return Err(error).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        let existing = existing.map(|content| remove_section(&content));
+
+        // Exists when an earlier install moved a non-shell hook aside
+        let chained = chained_path(&path);
+        let content = match existing.as_deref() {
+            Some(content) if is_shell_script(content) => {
+                let (shebang, rest) = split_shebang(content);
+                format!("{}{}{}", shebang, self.section(checks, chained.exists().then_some(chained.as_path())), rest)
+            }
+            Some(_) => {
+                if chained.exists() {
+                    anyhow::bail!("{} is not a shell script and {} already exists", path.display(), chained.display());
+                }
+                fs::rename(&path, &chained)
+                    .with_context(|| format!("Failed to move {} aside", path.display()))?;
+                format!("#!/bin/sh\n{}", self.section(checks, Some(&chained)))
+            }
+            None => format!("#!/bin/sh\n{}", self.section(checks, None)),
+        };
+
+        fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))?;
+        fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+        make_executable(&path)?;
+        Ok(path)
+    }
+
+    /// Remove the section from `hook`; false if it had none
+    pub fn uninstall(&self, hook: Hook) -> Result<bool> {
+        let path = self.hooks_dir()?.join(hook.name());
+        let Ok(content) = fs::read_to_string(&path) else {
+            return Ok(false);
+        };
+        if !content.contains(BEGIN_MARKER) {
+            return Ok(false);
+        }
+
+        let remaining = remove_section(&content);
+        let chained = chained_path(&path);
+        let (_, body) = split_shebang(&remaining);
+        if !body.trim().is_empty() {
+            fs::write(&path, remaining).with_context(|| format!("Failed to write {}", path.display()))?;
+        } else if chained.exists() {
+            fs::rename(&chained, &path)
+                .with_context(|| format!("Failed to restore {}", chained.display()))?;
+        } else {
+            fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+        Ok(true)
+    }
+
+    /// The managed section, ending with a call to a chained hook if there is one
+    fn section(&self, checks: &[Check], chained: Option<&Path>) -> String {
+        let mut lines = vec![
+            BEGIN_MARKER.to_string(),
+            "# Managed by `autodebugger install-hooks`; `autodebugger uninstall-hooks` removes it".to_string(),
+        ];
+        lines.extend(hook_commands(&shell_quote(&self.program), checks).into_iter().map(|command| format!("{} || exit 1", command)));
+        if let Some(chained) = chained {
+            let name = chained.file_name().unwrap_or_default().to_string_lossy();
+            lines.push(format!("\"$(dirname \"$0\")\"/{} \"$@\" || exit $?", shell_quote(&name)));
+        }
+        lines.push(END_MARKER.to_string());
+        lines.join("\n") + "\n"
+    }
+}
+
+/// The commands a hook runs for `checks`, calling `program` (already quoted)
+pub fn hook_commands(program: &str, checks: &[Check]) -> Vec<String> {
+    let mut commands = Vec::new();
+    if checks.contains(&Check::DebugMacros) {
+        commands.push(format!("{} remove-debug --check", program));
+    }
+    if checks.contains(&Check::Documentation) {
+        commands.push(format!("{} validate-docs --strict", program));
+    }
+    let ci: Vec<&str> = Check::ALL.iter()
+        .filter(|check| !matches!(check, Check::DebugMacros | Check::Documentation) && checks.contains(check))
+        .map(|check| check.name())
+        .collect();
+    if !ci.is_empty() {
+        commands.push(format!("{} ci . --checks {}", program, ci.join(",")));
+    }
+    commands
+}
+
+/// `content` without autodebugger sections
+fn remove_section(content: &str) -> String {
+    let mut kept = String::with_capacity(content.len());
+    let mut inside = false;
+    for line in content.split_inclusive('\n') {
+        match line.trim_end() {
+            BEGIN_MARKER => inside = true,
+            END_MARKER if inside => inside = false,
+            _ if !inside => kept.push_str(line),
+            _ => {}
+        }
+    }
+    kept
+}
+
+/// The shebang line (with its newline) and the rest
+fn split_shebang(content: &str) -> (&str, &str) {
+    if !content.starts_with("#!") {
+        return ("", content);
+    }
+    match content.find('\n') {
+        Some(end) => content.split_at(end + 1),
+        None => (content, ""),
+    }
+}
+
+/// Whether a hook can have shell commands inserted: a POSIX-like shell
+/// shebang, or none (git runs such a hook with `sh`)
+fn is_shell_script(content: &str) -> bool {
+    static SHELL: OnceLock<Regex> = OnceLock::new();
+    let shell = SHELL.get_or_init(|| Regex::new(r"^#!.*\b(sh|bash|dash|zsh|ksh)\b").expect("valid shebang regex"));
+    !content.starts_with("#!") || shell.is_match(content.lines().next().unwrap_or_default())
+}
+
+fn chained_path(hook: &Path) -> PathBuf {
+    let mut name = hook.file_name().unwrap_or_default().to_os_string();
+    name.push(CHAINED_SUFFIX);
+    hook.with_file_name(name)
+}
+
+/// `s` in single quotes for sh
+pub(crate) fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() | 0o755);
+    fs::set_permissions(path, permissions).with_context(|| format!("Failed to make {} executable", path.display()))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::git;
+
+    fn repo() -> tempfile::TempDir {
+        let repo = tempfile::tempdir().unwrap();
+        git(repo.path(), &["init", "-q"]);
+        repo
+    }
+
+    #[test]
+    fn test_commands_for_checks() {
+        assert_eq!(
+            hook_commands("'ad'", &[Check::Clippy, Check::Documentation, Check::CargoCheck, Check::DebugMacros]),
+            vec![
+                "'ad' remove-debug --check".to_string(),
+                "'ad' validate-docs --strict".to_string(),
+                "'ad' ci . --checks cargo_check,clippy".to_string(),
+            ]
+        );
+        assert!(hook_commands("'ad'", &[]).is_empty());
+        assert_eq!(shell_quote("/opt/it's/autodebugger"), r"'/opt/it'\''s/autodebugger'");
+    }
+
+    #[test]
+    fn test_install_replace_and_uninstall() {
+        let repo = repo();
+        let installer = HookInstaller::new(repo.path()).with_program("ad");
+        let path = installer.install(Hook::PreCommit, &[Check::DebugMacros]).unwrap();
+        assert_eq!(path, repo.path().join(".git/hooks/pre-commit"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), format!(
+            "#!/bin/sh\n{}\n# Managed by `autodebugger install-hooks`; `autodebugger uninstall-hooks` removes it\n'ad' remove-debug --check || exit 1\n{}\n",
+            BEGIN_MARKER, END_MARKER
+        ));
+
+        // Reinstalling replaces the section instead of adding another
+        installer.install(Hook::PreCommit, &[Check::Fmt]).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content.matches(BEGIN_MARKER).count(), 1);
+        assert!(content.contains("'ad' ci . --checks fmt || exit 1"));
+        assert!(!content.contains("remove-debug"));
+
+        assert!(installer.uninstall(Hook::PreCommit).unwrap());
+        assert!(!path.exists());
+        assert!(!installer.uninstall(Hook::PreCommit).unwrap());
+    }
+
+    #[test]
+    fn test_existing_hooks_are_chained_and_restored() {
+        let repo = repo();
+        let installer = HookInstaller::new(repo.path()).with_program("ad");
+        let hooks = installer.hooks_dir().unwrap();
+        fs::create_dir_all(&hooks).unwrap();
+
+        let shell_hook = "#!/usr/bin/env bash\nset -e\n./scripts/lint.sh\n";
+        fs::write(hooks.join("pre-commit"), shell_hook).unwrap();
+        installer.install(Hook::PreCommit, &[Check::DebugMacros]).unwrap();
+        let content = fs::read_to_string(hooks.join("pre-commit")).unwrap();
+        assert!(content.starts_with(&format!("#!/usr/bin/env bash\n{}\n", BEGIN_MARKER)), "{}", content);
+        assert!(content.ends_with(&format!("{}\nset -e\n./scripts/lint.sh\n", END_MARKER)), "{}", content);
+        installer.uninstall(Hook::PreCommit).unwrap();
+        assert_eq!(fs::read_to_string(hooks.join("pre-commit")).unwrap(), shell_hook);
+
+        let python_hook = "#!/usr/bin/env python3\nprint('push')\n";
+        fs::write(hooks.join("pre-push"), python_hook).unwrap();
+        installer.install(Hook::PrePush, &[Check::CargoCheck]).unwrap();
+        installer.install(Hook::PrePush, &[Check::CargoTest]).unwrap();
+        let content = fs::read_to_string(hooks.join("pre-push")).unwrap();
+        assert!(content.starts_with("#!/bin/sh\n"));
+        assert!(content.contains("\"$(dirname \"$0\")\"/'pre-push.pre-autodebugger' \"$@\" || exit $?\n"), "{}", content);
+        assert_eq!(fs::read_to_string(hooks.join("pre-push.pre-autodebugger")).unwrap(), python_hook);
+        installer.uninstall(Hook::PrePush).unwrap();
+        assert_eq!(fs::read_to_string(hooks.join("pre-push")).unwrap(), python_hook);
+        assert!(!hooks.join("pre-push.pre-autodebugger").exists());
+    }
+
+    #[test]
+    fn test_linked_worktree_uses_the_common_hooks_dir() {
+        let repo = repo();
+        fs::write(repo.path().join("a.txt"), "a\n").unwrap();
+        git(repo.path(), &["add", "."]);
+        git(repo.path(), &["commit", "-qm", "base"]);
+        let worktree = repo.path().join("wt");
+        git(repo.path(), &["worktree", "add", "-q", "-b", "feature", worktree.to_str().unwrap()]);
+
+        let path = HookInstaller::new(&worktree).install(Hook::PreCommit, &[Check::DebugMacros]).unwrap();
+        assert_eq!(path.canonicalize().unwrap(), repo.path().join(".git/hooks/pre-commit").canonicalize().unwrap());
+    }
+}
@@ -0,0 +1,266 @@
+//! Finding and reading autodebugger's own rotating log files (`autodebugger logs`)
+//!
+//! Each run writes a new timestamped file under `<log_directory>/timestamped`, so
+//! the files are listed newest first (`rotating_file_logger::log_files`) and picked
+//! by their index in that list (0 is the newest) or by timestamp. The latest file
+//! is the one the `<base>_latest.log` symlink points to, falling back to the most
+//! recently modified. Compressed (`.gz`) files are read through `gzip -dc`.
+//!
+//! Follow mode polls the latest file for appended bytes, starting over when the
+//! file shrinks (truncated in place) and switching files when a rotation or a new
+//! run moves the latest symlink.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use crate::rotating_file_logger::LogFile;
+
+/// A log file as listed by `logs --list --json`
+#[derive(Debug, Clone, Serialize)]
+pub struct ListedLog {
+    /// Position in the list, for `logs --show`
+    pub index: usize,
+    pub path: PathBuf,
+    /// `YYYYMMDD_HHMMSS` when the file was opened
+    pub timestamp: String,
+    /// Backup number, 0 for a run's live file
+    pub backup: u32,
+    pub size: u64,
+    pub compressed: bool,
+    /// The file the latest symlink points to
+    pub latest: bool,
+}
+
+/// `files` (newest first) with their indexes, marking `latest`
+pub fn listing(files: &[LogFile], latest: Option<&Path>) -> Vec<ListedLog> {
+    files.iter().enumerate()
+        .map(|(index, file)| ListedLog {
+            index,
+            path: file.path.clone(),
+            timestamp: file.timestamp.clone(),
+            backup: file.backup,
+            size: file.size,
+            compressed: file.compressed(),
+            latest: Some(file.path.as_path()) == latest,
+        })
+        .collect()
+}
+
+/// One line per file: index, start time, size, and name, with the latest marked
+pub fn render_listing(listing: &[ListedLog]) -> String {
+    let mut out = String::new();
+    for log in listing {
+        let name = log.path.file_name().unwrap_or_default().to_string_lossy();
+        out.push_str(&format!(
+            "{:>3}  {}  {:>9}  {}{}\n",
+            log.index,
+            display_timestamp(&log.timestamp),
+            display_size(log.size),
+            name,
+            if log.latest { "  (latest)" } else { "" },
+        ));
+    }
+    out
+}
+
+/// `YYYYMMDD_HHMMSS` as `YYYY-MM-DD HH:MM:SS`
+fn display_timestamp(timestamp: &str) -> String {
+    chrono::NaiveDateTime::parse_from_str(timestamp, "%Y%m%d_%H%M%S")
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+/// Bytes as B, KB, or MB with one decimal
+fn display_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0)),
+    }
+}
+
+/// The file `selector` names: an index into `files` (newest first), or a
+/// timestamp or its prefix from the date on (`20250101`, `20250101_1200`),
+/// matching the newest such file; a trailing `.N` picks that numbered backup
+pub fn select<'a>(files: &'a [LogFile], selector: &str) -> Result<&'a LogFile> {
+    if files.is_empty() {
+        bail!("No log files");
+    }
+    if let Ok(index) = selector.parse::<usize>() {
+        if selector.len() < 8 {
+            return files.get(index).with_context(|| {
+                format!("No log file at index {}; there are {} (0 is the newest)", index, files.len())
+            });
+        }
+    }
+    let (timestamp, backup) = match selector.rsplit_once('.') {
+        Some((timestamp, backup)) => (timestamp, Some(backup.parse::<u32>()
+            .with_context(|| format!("Invalid backup number in '{}'", selector))?)),
+        None => (selector, None),
+    };
+    files.iter()
+        .find(|file| file.timestamp.starts_with(timestamp) && backup.is_none_or(|backup| file.backup == backup))
+        .with_context(|| format!("No log file matches '{}'; `autodebugger logs --list` shows them", selector))
+}
+
+/// Whole content of a log file, decompressing `.gz` files with `gzip -dc`
+pub fn read_log(path: &Path) -> Result<Vec<u8>> {
+    if path.extension().is_some_and(|extension| extension == "gz") {
+        let output = Command::new("gzip").arg("-dc").arg("--").arg(path).output()
+            .context("Failed to run gzip")?;
+        if !output.status.success() {
+            bail!("gzip failed on {}: {}", path.display(), String::from_utf8_lossy(&output.stderr).trim());
+        }
+        return Ok(output.stdout);
+    }
+    fs::read(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+/// The last `lines` lines of `content`; a missing final newline still ends a line
+pub fn tail(content: &[u8], lines: usize) -> &[u8] {
+    if lines == 0 {
+        return &content[content.len()..];
+    }
+    let body = content.strip_suffix(b"\n").unwrap_or(content);
+    let start = body.iter().enumerate().rev()
+        .filter(|(_, &byte)| byte == b'\n')
+        .nth(lines - 1)
+        .map_or(0, |(i, _)| i + 1);
+    &content[start..]
+}
+
+/// Bytes appended to `path` since `offset`, advancing it. A file shorter than
+/// `offset` was truncated and is read from the start.
+pub fn read_appended(path: &Path, offset: &mut u64) -> std::io::Result<Vec<u8>> {
+    let mut file = fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    if len < *offset {
+        *offset = 0;
+    }
+    file.seek(SeekFrom::Start(*offset))?;
+    let mut appended = Vec::new();
+    file.take(len - *offset).read_to_end(&mut appended)?;
+    *offset += appended.len() as u64;
+    Ok(appended)
+}
+
+/// Write what is appended to the latest file to `out` every `interval`, from
+/// `offset` in `path`, until `stop` returns true. `latest` is asked for the
+/// current latest file each time; when it changes, the rest of the old file is
+/// written and the new one is followed from its start.
+pub fn follow(
+    mut path: PathBuf,
+    mut offset: u64,
+    mut latest: impl FnMut() -> Option<PathBuf>,
+    out: &mut dyn Write,
+    interval: Duration,
+    mut stop: impl FnMut() -> bool,
+) -> Result<()> {
+    while !stop() {
+        std::thread::sleep(interval);
+        // A pruned or renamed file simply has nothing more to show
+        if let Ok(appended) = read_appended(&path, &mut offset) {
+            out.write_all(&appended)?;
+        }
+        if let Some(next) = latest().filter(|next| *next != path) {
+            path = next;
+            offset = 0;
+            out.write_all(&read_appended(&path, &mut offset).unwrap_or_default())?;
+        }
+        out.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_file(timestamp: &str, backup: u32) -> LogFile {
+        let suffix = if backup == 0 { String::new() } else { format!(".{}", backup) };
+        LogFile {
+            timestamp: timestamp.to_string(),
+            backup,
+            path: PathBuf::from(format!("logs/timestamped/app_{}.log{}", timestamp, suffix)),
+            size: 2048,
+        }
+    }
+
+    #[test]
+    fn test_select_by_index_or_timestamp() {
+        let files = vec![
+            log_file("20250102_090000", 0),
+            log_file("20250101_120000", 0),
+            log_file("20250101_120000", 1),
+            log_file("20250101_080000", 0),
+        ];
+        assert_eq!(select(&files, "0").unwrap().timestamp, "20250102_090000");
+        assert_eq!(select(&files, "3").unwrap().timestamp, "20250101_080000");
+        assert!(select(&files, "4").unwrap_err().to_string().contains("there are 4"));
+        // A prefix picks the newest match, the live file before its backups
+        assert_eq!(select(&files, "20250101").unwrap(), &files[1]);
+        assert_eq!(select(&files, "20250101_120000.1").unwrap(), &files[2]);
+        assert_eq!(select(&files, "20250101_08").unwrap(), &files[3]);
+        assert!(select(&files, "20240101").is_err());
+        assert!(select(&[], "0").is_err());
+    }
+
+    #[test]
+    fn test_listing_marks_latest() {
+        let files = vec![log_file("20250102_090000", 0), log_file("20250101_120000", 1)];
+        let listing = listing(&files, Some(&files[0].path));
+        assert!(listing[0].latest && !listing[1].latest);
+        assert_eq!(render_listing(&listing),
+            "  0  2025-01-02 09:00:00     2.0 KB  app_20250102_090000.log  (latest)\n\
+             \x20 1  2025-01-01 12:00:00     2.0 KB  app_20250101_120000.log.1\n");
+        let json = serde_json::to_value(&listing).unwrap();
+        assert_eq!(json[1]["backup"], 1);
+        assert_eq!(json[0]["latest"], true);
+    }
+
+    #[test]
+    fn test_tail_lines() {
+        assert_eq!(tail(b"a\nb\nc\n", 2), b"b\nc\n");
+        assert_eq!(tail(b"a\nb\nc", 2), b"b\nc");
+        assert_eq!(tail(b"a\nb\n", 5), b"a\nb\n");
+        assert_eq!(tail(b"a\nb\n", 0), b"");
+    }
+
+    #[test]
+    fn test_follow_reads_appends_and_switches_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.log");
+        let second = dir.path().join("second.log");
+        fs::write(&first, "old\n").unwrap();
+
+        let mut offset = 0;
+        assert_eq!(read_appended(&first, &mut offset).unwrap(), b"old\n");
+        fs::write(&first, "old\nnew\n").unwrap();
+        assert_eq!(read_appended(&first, &mut offset).unwrap(), b"new\n");
+        // Truncated in place: read again from the start
+        fs::write(&first, "x\n").unwrap();
+        assert_eq!(read_appended(&first, &mut offset).unwrap(), b"x\n");
+
+        // Each poll appends a line; the second rotates to a new file
+        let polls = std::cell::Cell::new(0);
+        let mut out = Vec::new();
+        let (first_path, second_path) = (first.clone(), second.clone());
+        follow(first.clone(), offset, || {
+            polls.set(polls.get() + 1);
+            if polls.get() == 2 {
+                fs::write(&second_path, "rotated\n").unwrap();
+                return Some(second_path.clone());
+            }
+            Some(first_path.clone())
+        }, &mut out, Duration::from_millis(1), || {
+            fs::OpenOptions::new().append(true).open(&first).unwrap().write_all(b"more\n").unwrap();
+            polls.get() == 2
+        }).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "more\nmore\nrotated\n");
+    }
+}
@@ -27,25 +27,93 @@
 //! ### `status` - Show status of all worktrees
 //! Display the current status of all worktrees in the workspace.
 //!
+//! ### `worktree` - Manage worktrees
+//! Create (`add`), remove, and prune git worktrees, optionally seeding a
+//! `CLAUDE.local.md` task file.
+//!
+//! ### `task` - Named command sequences
+//! Run the steps of a `tasks` entry from the config in order (`--keep-going`
+//! to run them all), print each step's outcome and duration, and exit with the
+//! first failing step's code.
+//!
 //! ### `remove-debug` - Remove debug! macro calls
 //! Automatically remove all debug! macro invocations from Rust source files.
+//! With `--check` it only reports, exiting non-zero when calls remain (for CI);
+//! `--check --watch` keeps re-checking files as they are saved.
 //!
 //! ### `validate-docs` - Validate module documentation
 //! Check that Rust modules have appropriate documentation based on their complexity.
+//! `--watch` keeps running and re-validates each file as it is saved, with the
+//! live totals in the header of each redrawn screen.
+//!
+//! ### `clean` - Pre-PR sweep
+//! Remove debug calls, validate the docs, and rustfmt the files the removal
+//! changed, printing one summary (`--json` for bots); `--dry-run` changes
+//! nothing, `--steps`/`--skip` pick steps, and `--fail-fast` stops at the first failure.
+//!
+//! ### `ci` - Merge readiness of a worktree
+//! Run the pre-merge checks, predict conflicts with a base branch, and score the
+//! result; exits 0 (safe) or 1 (caution or danger). `--all --plan` adds a
+//! pairwise conflict matrix and a suggested merge order for every worktree.
+//!
+//! ### `install-hooks` / `uninstall-hooks` - Git hooks
+//! Add (or remove) marker-delimited pre-commit and pre-push sections that run
+//! the configured checks, keeping any existing hook.
+//!
+//! ### `config` - Configuration
+//! `init` writes a commented config.yaml with every default (`--force` to
+//! overwrite), `show` prints the effective configuration with the source of each
+//! changed value, and `validate` fails on unknown keys or out-of-range values.
+//!
+//! ### `logs` - Autodebugger's own log files
+//! Print the latest rotating log file, `--list` them (`--json` for tools),
+//! `--tail` the latest (`-f` to follow it), or `--show` one by index or
+//! timestamp; `logs clean [--keep N]` prunes old files like the writer does.
+//!
+//! ### `mcp-serve` - Model Context Protocol server
+//! Serve `run_command`, `worktree_status`, `diff`, `remove_debug_check`, and
+//! `validate_docs` as MCP tools over stdin/stdout; logs go to stderr.
+//!
+//! ### `serve` - HTTP endpoints for dashboards
+//! With the `server` feature, serve `GET /healthz`, `/status`, `/diff/{worktree}`,
+//! and `/ci/{worktree}` as JSON on `server.bind:server.port` (`--port`, `--bind`)
+//! until Ctrl-C.
 //!
 //! ## Configuration
 //!
-//! Autodebugger can be configured via a `config.yaml` file in the current directory.
-//! See `config.example.yaml` for available configuration options.
+//! Autodebugger reads `$XDG_CONFIG_HOME/autodebugger/config.yaml` (`~/.config/...`),
+//! then `autodebugger.yaml` or `config.yaml` from the current directory or the
+//! nearest parent, up to the repository root, which overrides the keys it sets;
+//! `--verbose` shows which, and `-c/--config <path>` names a file instead. The config is loaded once
+//! and passed to every command. See `config.example.yaml` for available options.
 //!
 //! ## Logging
 //!
-//! All commands support automatic file logging with rotation. Logs are stored in
-//! the `autodebugger_logs/` directory with automatic rotation based on file size.
+//! Logs go to the console, at the level and destination set by `logging.level` and
+//! `logging.output` in `config.yaml` (or `--log-output`). With a `logging.file` section
+//! they are also written to rotating log files (e.g. `log_directory: autodebugger_logs`),
+//! and `console_output: false` there sends them to the files only. Output `mcp` is the
+//! preset for running under an MCP client: logs and reports on stderr, log files in JSON.
+//! `--quiet` (`logging.quiet`) logs only warnings and errors to the console, and
+//! `--no-log-file` (`logging.file.enabled: false`) skips the log files, for short
+//! runs such as `remove-debug`; `monitor --watch` keeps full logging unless asked.
+//!
+//! ## Exit Codes
+//!
+//! 0 success, 1 a failed check, 2 a usage or config error, 3 an environment error
+//! (a missing path, git not installed), 4 an internal error (`error::ErrorKind`);
+//! `--error-format json` reports failures as a JSON object on stderr.
 //!
 //! ## Environment Variables
 //!
 //! - `RUST_LOG`: Control logging verbosity (e.g., `info`, `debug`, `trace`)
+//! - `AUTODEBUGGER__<SECTION>__<FIELD>`: Override a `config.yaml` setting, e.g.
+//!   `AUTODEBUGGER__VALIDATE_DOCS__COMPLEXITY_THRESHOLD=150` (lists comma-separated)
+//! - `AUTODEBUGGER_PROFILE`: Profile from `profiles` in `config.yaml` to apply when
+//!   `--profile` is not given
+//!
+//! On Unix, `kill -USR1 <pid>` toggles a running autodebugger (e.g. `monitor --watch`)
+//! between debug logging and its starting filter.
 //!
 //! ## Examples
 //!
@@ -60,22 +128,77 @@
 //! autodebugger validate-docs --verbose
 //! ```
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use autodebugger::{
+    error::{CliError, ErrorKind, ErrorKindExt},
     Autodebugger, 
+    Config,
+    RotatingFileConfig,
+    config::{discover_config_files, PROFILE_ENV},
+    config_template::{default_template, render_effective, ConfigSource},
+    log_viewer,
+    rotating_file_logger::{latest_log_file, log_files, prune_log_files},
     monitor::Monitor, 
-    remove_debug::DebugRemover,
+    monitor::worktree::WorktreeMonitor,
+    monitor::cache::default_cache_path,
+    monitor::events::MonitorEventStream,
+    monitor::diff::{format_summary_table, DiffFilter},
+    remove_debug::{Decision, DebugRemover, RemovalCandidate},
+    watch::{FileFindings, WatchOptions},
     init_logging,
+    init_logging_with_file,
+    init_logging_for_mcp,
+    report_output,
 };
 use clap::{Parser, Subcommand};
+use std::io::Write;
 use std::path::PathBuf;
-use tracing::info;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
 
 #[derive(Parser)]
 #[command(author, version, about = "Developer utilities for LLM-assisted coding", long_about = None)]
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+    
+    /// Console log destination, overriding logging.output: stdout, stderr, or mcp
+    /// (stderr, JSON log files, and reports on stderr, keeping stdout for JSON-RPC)
+    #[arg(long, global = true, value_parser = ["stdout", "stderr", "mcp"])]
+    log_output: Option<String>,
+    
+    /// Config file to use instead of searching for autodebugger.yaml/config.yaml
+    #[arg(short, long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+    
+    /// Rotating log file size limit, overriding logging.file.max_size_mb
+    #[arg(long, global = true, value_name = "MB")]
+    max_size_mb: Option<u64>,
+    
+    /// Fail on unknown keys in the config file instead of warning (as `strict: true`)
+    #[arg(long, global = true)]
+    strict_config: bool,
+    
+    /// Config profile merged over the settings (default: $AUTODEBUGGER_PROFILE)
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+    
+    /// Log only warnings and errors to the console, without the startup and
+    /// shutdown lines (as `logging.quiet: true`)
+    #[arg(long, global = true)]
+    quiet: bool,
+    
+    /// Don't create the rotating log file (as `logging.file.enabled: false`)
+    #[arg(long, global = true)]
+    no_log_file: bool,
+    
+    /// How a failure is reported on stderr: text, or json
+    /// (`{"error": {"kind", "message", "hint"}}`)
+    #[arg(long, global = true, value_name = "FORMAT", default_value = "text", value_parser = ["text", "json"])]
+    error_format: String,
 }
 
 #[derive(Subcommand)]
@@ -85,9 +208,21 @@ enum Commands {
         /// Path to workspace containing worktrees
         path: PathBuf,
         
-        /// Output format (json, text)
+        /// Output format (json, text, markdown, html; jsonl with --watch)
         #[arg(short, long, default_value = "text")]
         format: String,
+        
+        /// Recompute every worktree instead of reusing the status cache
+        #[arg(long)]
+        no_cache: bool,
+        
+        /// Keep polling and print an event whenever a worktree changes
+        #[arg(short, long)]
+        watch: bool,
+        
+        /// Seconds between polls in watch mode
+        #[arg(long, default_value = "2")]
+        interval: u64,
     },
     
     /// Show diffs across worktrees
@@ -99,6 +234,18 @@ enum Commands {
         #[arg(short, long)]
         summary: bool,
         
+        /// Output as JSON (structured hunks, or the summary with --summary)
+        #[arg(short, long)]
+        json: bool,
+        
+        /// Only show paths matching this glob (repeatable)
+        #[arg(long)]
+        include: Vec<String>,
+        
+        /// Hide paths matching this glob, in addition to monitor.diff_exclude (repeatable)
+        #[arg(long)]
+        exclude: Vec<String>,
+        
         /// Path to workspace
         #[arg(short, long, default_value = ".")]
         path: PathBuf,
@@ -106,13 +253,21 @@ enum Commands {
     
     /// Get aggregated context
     Context {
-        /// Context type (local-tasks, status)
+        /// Context type (local-tasks, status, commits, todos, all)
         #[arg(default_value = "status")]
         context_type: String,
         
         /// Path to workspace
         #[arg(short, long, default_value = ".")]
         path: PathBuf,
+        
+        /// Number of commits per worktree for the commits context
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+        
+        /// Truncate output to about this many tokens (estimated as chars/4)
+        #[arg(long)]
+        max_tokens: Option<usize>,
     },
     
     /// Show status of all worktrees
@@ -124,6 +279,29 @@ enum Commands {
         /// Output as JSON
         #[arg(short, long)]
         json: bool,
+        
+        /// List changed files under each worktree
+        #[arg(short, long)]
+        files: bool,
+        
+        /// Report files changed in more than one worktree
+        #[arg(short, long)]
+        overlaps: bool,
+        
+        /// Rank worktrees by a 0-100 health score (weights from monitor.health;
+        /// failed checks cached by `ci` cost their ci.weights)
+        #[arg(long)]
+        health: bool,
+        
+        /// Recompute every worktree instead of reusing the status cache
+        #[arg(long)]
+        no_cache: bool,
+    },
+    
+    /// Create, remove, or prune git worktrees
+    Worktree {
+        #[command(subcommand)]
+        action: WorktreeAction,
     },
     
     /// Run a command (legacy mode)
@@ -132,6 +310,17 @@ enum Commands {
         command: Vec<String>,
     },
     
+    /// Run a named command sequence from the config's tasks section, printing a
+    /// per-step summary; exits with the first failing step's code
+    Task {
+        /// Task name, a key of tasks
+        name: String,
+        
+        /// Run the remaining steps after one fails
+        #[arg(short, long)]
+        keep_going: bool,
+    },
+    
     /// Remove all debug! macro calls from Rust source files
     RemoveDebug {
         /// Paths to files or directories (uses config defaults if none specified)
@@ -144,6 +333,59 @@ enum Commands {
         /// Show verbose output
         #[arg(short, long)]
         verbose: bool,
+        
+        /// Macros to remove, comma-separated (default: remove_debug.macros, or debug)
+        #[arg(long, value_delimiter = ',')]
+        macros: Vec<String>,
+        
+        /// Print a unified diff of each changed file (also shown by --dry-run --verbose)
+        #[arg(long)]
+        diff: bool,
+        
+        /// Only remove calls on lines added since branching from this ref (e.g. main)
+        #[arg(long, value_name = "REF")]
+        since: Option<String>,
+        
+        /// Modify files without backing them up to .autodebugger/backups
+        #[arg(long)]
+        no_backup: bool,
+        
+        /// Restore the files of a backup run (the latest if no run is given) and exit
+        #[arg(long, value_name = "RUN")]
+        restore: Option<Option<String>>,
+        
+        /// Files to process in parallel (default: remove_debug.jobs, 0 = one per CPU)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+        
+        /// Output format: text (log summary), json (full report on stdout), or github
+        /// (Actions annotations; the default when GITHUB_ACTIONS=true)
+        #[arg(short, long)]
+        format: Option<String>,
+        
+        /// Dry run that exits with code 1 if any call would be removed or any warning is found
+        #[arg(long)]
+        check: bool,
+        
+        /// Collapse blank lines left behind by removals (default: remove_debug.tidy_whitespace)
+        #[arg(long, overrides_with = "no_tidy_whitespace")]
+        tidy_whitespace: bool,
+        
+        /// Leave blank lines around removed calls as they are
+        #[arg(long, overrides_with = "tidy_whitespace")]
+        no_tidy_whitespace: bool,
+        
+        /// How calls are located: regex, or ast (needs the `ast` feature) (default: remove_debug.engine)
+        #[arg(long)]
+        engine: Option<String>,
+        
+        /// Ask before each removal or rewrite: [y]es, [n]o, [a]ll, [q]uit
+        #[arg(short, long, conflicts_with_all = ["check", "format"])]
+        interactive: bool,
+        
+        /// With --check, keep running and re-check each file as it is saved
+        #[arg(short, long, requires = "check", conflicts_with_all = ["format", "diff"])]
+        watch: bool,
     },
     
     /// Validate module documentation in Rust source files
@@ -158,38 +400,531 @@ enum Commands {
         /// Treat warnings as errors (exit with non-zero code)
         #[arg(short, long)]
         strict: bool,
+        
+        /// Also require /// docs on public items (default: validate_docs.require_pub_item_docs)
+        #[arg(long)]
+        pub_items: bool,
+        
+        /// Output format: text (summary), json (full report), sarif (SARIF 2.1.0 log), or
+        /// github (Actions annotations; the default when GITHUB_ACTIONS=true)
+        #[arg(short, long)]
+        format: Option<String>,
+        
+        /// Record the current issues in .autodebugger/docs-baseline.json and exit
+        #[arg(long)]
+        write_baseline: bool,
+        
+        /// Report issues listed in the baseline too
+        #[arg(long, conflicts_with = "write_baseline")]
+        no_baseline: bool,
+        
+        /// Only validate files changed since branching from REF (HEAD: staged and unstaged changes)
+        #[arg(long, value_name = "REF", num_args = 0..=1, default_missing_value = "HEAD")]
+        changed_since: Option<String>,
+        
+        /// Insert validate_docs.doc_template into complex modules without any //! docs
+        #[arg(long)]
+        fix: bool,
+        
+        /// With --fix, show the templates that would be inserted without writing
+        #[arg(short = 'n', long, requires = "fix")]
+        dry_run: bool,
+        
+        /// Also flag symbols named in //! docs that no longer exist (default: validate_docs.check_references)
+        #[arg(long)]
+        check_references: bool,
+        
+        /// Also check ```yaml blocks in //! docs against the config schema
+        /// (default: validate_docs.check_config_snippets)
+        #[arg(long)]
+        check_config_snippets: bool,
+        
+        /// Files to check in parallel (default: validate_docs.jobs, 0 = one per CPU)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+        
+        /// Exit 1 when less than PERCENT of complex modules are documented, even without --strict
+        /// (default: validate_docs.min_coverage)
+        #[arg(long, value_name = "PERCENT")]
+        min_coverage: Option<f64>,
+        
+        /// List only the N modules furthest from compliance (text output)
+        #[arg(long, value_name = "N")]
+        top: Option<usize>,
+        
+        /// List flagged modules under their directory with subtotals (text output)
+        #[arg(long)]
+        group_by_dir: bool,
+        
+        /// Code lines above which a module is complex (default: validate_docs.complexity_threshold)
+        #[arg(long, value_name = "LINES")]
+        complexity_threshold: Option<usize>,
+        
+        /// Keep running and re-validate each file as it is saved (stale references are not checked)
+        #[arg(short, long, conflicts_with_all = ["format", "write_baseline", "fix", "changed_since"])]
+        watch: bool,
+    },
+    
+    /// Pre-PR sweep: remove debug calls, validate docs, and rustfmt the files the
+    /// removal changed, with one summary (exit 1 if a step fails)
+    Clean {
+        /// Paths to files or directories (default: each step's default_paths)
+        paths: Vec<PathBuf>,
+        
+        /// Report what would change without modifying files
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+        
+        /// Steps to run, comma-separated: remove_debug, validate_docs, fmt (default: clean.steps)
+        #[arg(long, value_delimiter = ',')]
+        steps: Vec<autodebugger::clean::CleanStep>,
+        
+        /// Steps to leave out, comma-separated
+        #[arg(long, value_delimiter = ',')]
+        skip: Vec<autodebugger::clean::CleanStep>,
+        
+        /// Stop at the first step that fails
+        #[arg(long)]
+        fail_fast: bool,
+        
+        /// Fail on documentation warnings, not just errors
+        #[arg(long)]
+        strict: bool,
+        
+        /// Print the combined report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Check whether a worktree is ready to merge (exit 0 safe, 1 caution or danger)
+    Ci {
+        /// Worktree name in the workspace, or a path to a checkout
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        worktree: Option<String>,
+        
+        /// Report on every worktree in the workspace (except those on the base branch)
+        #[arg(short, long)]
+        all: bool,
+        
+        /// With --all, predict conflicts between each pair of worktrees and suggest a merge order
+        #[arg(long, conflicts_with = "worktree")]
+        plan: bool,
+        
+        /// Branch or worktree to predict conflicts against (default: the default branch)
+        #[arg(short, long)]
+        base: Option<String>,
+        
+        /// Output format: text, json, or markdown (for PR comments)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+        
+        /// Shorthand for --format json
+        #[arg(short, long)]
+        json: bool,
+        
+        /// Add a "Generated" timestamp to markdown output (omitted so reruns compare equal)
+        #[arg(long)]
+        timestamp: bool,
+        
+        /// Rerun every check instead of reusing .autodebugger/ci_cache.json
+        #[arg(long)]
+        no_cache: bool,
+        
+        /// Run only these checks, comma-separated, and exit 1 if any fails whatever the score
+        #[arg(long, value_delimiter = ',')]
+        checks: Vec<autodebugger::ci::Check>,
+        
+        /// Path to workspace
+        #[arg(short, long, default_value = ".")]
+        path: PathBuf,
+    },
+    
+    /// Install git hooks that run checks before commits and pushes
+    InstallHooks {
+        /// Checks before each commit, comma-separated (default: hooks.pre_commit)
+        #[arg(long, value_delimiter = ',', num_args = 1..)]
+        pre_commit: Option<Vec<autodebugger::ci::Check>>,
+        
+        /// Checks before each push, comma-separated (default: hooks.pre_push)
+        #[arg(long, value_delimiter = ',', num_args = 1..)]
+        pre_push: Option<Vec<autodebugger::ci::Check>>,
+        
+        /// Repository or worktree path
+        #[arg(short, long, default_value = ".")]
+        path: PathBuf,
+    },
+    
+    /// Remove the autodebugger sections from git hooks
+    UninstallHooks {
+        /// Repository or worktree path
+        #[arg(short, long, default_value = ".")]
+        path: PathBuf,
+    },
+    
+    /// Write, show, or check the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    
+    /// List, print, or follow autodebugger's own log files (logging.file); prints
+    /// the latest file by default
+    #[command(args_conflicts_with_subcommands = true)]
+    Logs {
+        #[command(subcommand)]
+        action: Option<LogsAction>,
+        
+        /// List the log files, newest first, with sizes and start times
+        #[arg(short, long, conflicts_with_all = ["tail", "show"])]
+        list: bool,
+        
+        /// Print the end of the latest log file
+        #[arg(short, long, conflicts_with = "show")]
+        tail: bool,
+        
+        /// Lines printed by --tail
+        #[arg(short = 'n', long, default_value_t = 20, requires = "tail")]
+        lines: usize,
+        
+        /// Keep printing lines as they are appended (with --tail), until interrupted
+        #[arg(short, long, requires = "tail")]
+        follow: bool,
+        
+        /// Print one file: its index in --list (0 is the newest) or its timestamp
+        /// (YYYYMMDD_HHMMSS, a prefix, or with .N for a numbered backup)
+        #[arg(short, long, value_name = "INDEX|TIMESTAMP")]
+        show: Option<String>,
+        
+        /// Print the list as JSON
+        #[arg(long, requires = "list")]
+        json: bool,
+    },
+    
+    /// Serve run_command, worktree_status, diff, remove_debug_check, and validate_docs
+    /// to an MCP client as JSON-RPC over stdin/stdout (logs go to stderr)
+    McpServe,
+    
+    /// Serve read-only JSON endpoints for dashboards (/healthz, /status,
+    /// /diff/{worktree}, /ci/{worktree}) until Ctrl-C; needs the `server` feature
+    Serve {
+        /// Path to workspace (default: server.workspace, else the current directory)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+        
+        /// Port to listen on (default: server.port)
+        #[arg(long)]
+        port: Option<u16>,
+        
+        /// Address to listen on (default: server.bind)
+        #[arg(long)]
+        bind: Option<String>,
     },
 }
 
+#[derive(Subcommand)]
+enum LogsAction {
+    /// Delete old log files as the rotating writer's retention would
+    /// (max_files and max_total_size_mb), keeping the latest
+    Clean {
+        /// Files to keep, instead of logging.file.max_files
+        #[arg(short, long)]
+        keep: Option<usize>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Write a config file with every setting at its default, commented
+    Init {
+        /// File to write
+        #[arg(default_value = "config.yaml")]
+        path: PathBuf,
+        
+        /// Overwrite the file if it exists
+        #[arg(short, long)]
+        force: bool,
+    },
+    
+    /// Print the effective configuration (file, environment, and options) as YAML,
+    /// noting where each changed value came from
+    Show,
+    
+    /// Check the config file strictly; exits 1 on unknown keys or invalid values
+    Validate,
+}
+
+#[derive(Subcommand)]
+enum WorktreeAction {
+    /// Create a worktree under the first configured worktrees directory
+    Add {
+        /// Worktree name (also the directory name)
+        name: String,
+        
+        /// Branch to check out or create (defaults to the name)
+        #[arg(short, long)]
+        branch: Option<String>,
+        
+        /// Ref to create the new branch from (defaults to HEAD)
+        #[arg(long)]
+        base: Option<String>,
+        
+        /// Write a CLAUDE.local.md from monitor.task_template (or a built-in default)
+        #[arg(long)]
+        seed: bool,
+        
+        /// Path to workspace
+        #[arg(short, long, default_value = ".")]
+        path: PathBuf,
+    },
+    
+    /// Remove a worktree
+    Remove {
+        /// Worktree name
+        name: String,
+        
+        /// Remove even if there are uncommitted changes
+        #[arg(short, long)]
+        force: bool,
+        
+        /// Path to workspace
+        #[arg(short, long, default_value = ".")]
+        path: PathBuf,
+    },
+    
+    /// Prune worktrees whose directories no longer exist
+    Prune {
+        /// Path to workspace
+        #[arg(short, long, default_value = ".")]
+        path: PathBuf,
+    },
+}
+
+/// Toggle between debug logging and the starting filter on each SIGUSR1
+#[cfg(unix)]
+fn spawn_level_toggle(logging: autodebugger::LoggingHandle) {
+    use tokio::signal::unix::{signal, SignalKind};
+    
+    let Ok(mut signals) = signal(SignalKind::user_defined1()) else { return };
+    let original = logging.directives().unwrap_or_else(|| "info".to_string());
+    tokio::spawn(async move {
+        let mut raised = false;
+        while signals.recv().await.is_some() {
+            raised = !raised;
+            let result = if raised { logging.set_level("debug") } else { logging.set_directives(&original) };
+            match result {
+                Ok(()) => info!("Log filter set to {}", logging.directives().unwrap_or_default()),
+                Err(e) => tracing::warn!("Failed to change log level: {:#}", e),
+            }
+        }
+    });
+}
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize autodebugger's tracing subscriber (using autodebugger's own config)
-    // Using None for output defaults to stdout (normal behavior for non-MCP usage)
-    let _verbosity_layer = init_logging(Some("info"), None, None);
+async fn main() -> ExitCode {
+    let cli = Cli::try_parse().unwrap_or_else(|e| exit_on_parse_error(e));
+    let json_errors = cli.error_format == "json";
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            report_error(&error, json_errors);
+            ExitCode::from(error.exit_code())
+        }
+    }
+}
+
+/// Print a failure on stderr: anyhow's message with causes (and a backtrace if
+/// enabled) and the hint, or the JSON envelope
+fn report_error(error: &CliError, json: bool) {
+    if json {
+        eprintln!("{}", error.to_json());
+        return;
+    }
+    eprintln!("Error: {:?}", error.error());
+    if let Some(hint) = error.hint() {
+        eprintln!("Hint: {}", hint);
+    }
+}
+
+/// Exit for a command line clap rejects: clap's message and exit code 2 (help
+/// and version print normally), or the JSON envelope if `--error-format json`
+/// is among the arguments
+fn exit_on_parse_error(error: clap::Error) -> ! {
+    let args: Vec<String> = std::env::args().collect();
+    let json = args.iter().any(|arg| arg == "--error-format=json")
+        || args.windows(2).any(|pair| pair[0] == "--error-format" && pair[1] == "json");
+    if !json || !error.use_stderr() {
+        error.exit();
+    }
+    // The message without clap's usage and help lines, on one line
+    let rendered = error.render().to_string();
+    let message = rendered.split("\n\n").next().unwrap_or_default()
+        .trim_start_matches("error: ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let error = CliError::msg(ErrorKind::Usage, message).with_hint("See autodebugger --help");
+    report_error(&error, true);
+    std::process::exit(error.exit_code().into());
+}
+
+/// Check `paths`, then re-check files as they change, until interrupted
+fn watch_files(
+    title: &str,
+    paths: &[PathBuf],
+    accepts: impl Fn(&std::path::Path) -> bool,
+    check: impl FnMut(&std::path::Path) -> FileFindings,
+) -> Result<(), CliError> {
+    use std::io::IsTerminal;
     
-    info!("Autodebugger starting");
+    let options = WatchOptions { clear_screen: std::io::stdout().is_terminal(), ..WatchOptions::default() };
+    autodebugger::watch::run(title, paths, &options, accepts, check, &mut report_output())?;
+    Ok(())
+}
+
+/// The command line's command, with the config and logging set up
+async fn run(cli: Cli) -> Result<(), CliError> {
+    // Initialize autodebugger's tracing subscriber (using autodebugger's own config)
+    // Logs go to stdout, except where stdout carries machine-readable output
+    // (JSON lines event streams, patches from remove-debug --diff, JSON/SARIF reports,
+    // GitHub Actions annotations, the screens of --watch). Level, destination, format, and rotating file
+    // logging come from the logging section of config.yaml
+    let machine_output = matches!(&cli.command, Some(Commands::Monitor { format, .. }) if format == "jsonl")
+        || matches!(&cli.command, Some(Commands::RemoveDebug { diff: true, .. }))
+        || matches!(&cli.command, Some(Commands::RemoveDebug { format, .. }) if matches!(report_format(format), "json" | "github"))
+        || matches!(&cli.command, Some(Commands::ValidateDocs { format, .. }) if matches!(report_format(format), "json" | "sarif" | "github"))
+        || matches!(&cli.command, Some(Commands::Ci { json: true, .. }))
+        || matches!(&cli.command, Some(Commands::Clean { json: true, .. }))
+        || matches!(&cli.command, Some(Commands::Ci { format, .. }) if format != "text")
+        || matches!(&cli.command, Some(Commands::Config { action: ConfigAction::Show }))
+        || matches!(&cli.command, Some(Commands::Logs { action: None, .. }))
+        || matches!(&cli.command, Some(Commands::RemoveDebug { watch: true, .. }) | Some(Commands::ValidateDocs { watch: true, .. }));
+    // The config for every command: --config or the discovered file, then AUTODEBUGGER__*
+    // overrides, then command-line options. A file that fails to load is an error
+    // (e.g. a bad rule name in validate_docs.severities), never silently replaced by
+    // defaults. Discovered files are layered, the project's over the user's, and a
+    // profile goes over both. `config init` reads none, so it can replace a broken file
+    let config_files = match &cli.config {
+        Some(path) => vec![path.clone()],
+        None => discover_config_files(),
+    };
+    let profile = cli.profile.clone()
+        .or_else(|| std::env::var(PROFILE_ENV).ok().filter(|name| !name.is_empty()));
+    let (file_layers, profile_config, env_config) = if matches!(&cli.command, Some(Commands::Config { action: ConfigAction::Init { .. } })) {
+        (Vec::new(), None, Config::default())
+    } else {
+        let file_layers = Config::load_layers(&config_files).error_kind(ErrorKind::Config)
+            .map_err(|e| e.with_hint("Fix the file, or pass --config to use another"))?;
+        let base = file_layers.last().cloned().unwrap_or_default();
+        let profile_config = profile.as_deref().map(|name| base.clone().with_profile(name)).transpose()
+            .error_kind(ErrorKind::Config)?;
+        let env_config = profile_config.clone().unwrap_or(base).with_env(std::env::vars())
+            .error_kind(ErrorKind::Config)?;
+        (file_layers, profile_config, env_config)
+    };
+    let mut config = env_config.clone();
+    if let (Some(max_size_mb), Some(file)) = (cli.max_size_mb, config.logging.file.as_mut()) {
+        file.max_size_mb = max_size_mb;
+    }
+    if cli.quiet {
+        config.logging.quiet = true;
+    }
+    // `logs` reads the log files; a file of its own would become the latest
+    let reading_logs = matches!(&cli.command, Some(Commands::Logs { .. }));
+    if let (true, Some(file)) = (cli.no_log_file || reading_logs, config.logging.file.as_mut()) {
+        file.enabled = false;
+    }
+    if let Some(Commands::ValidateDocs { complexity_threshold: Some(threshold), .. }) = &cli.command {
+        config.validate_docs.complexity_threshold = *threshold;
+    }
+    config.validate().error_kind(ErrorKind::Config)?;
+    if cli.strict_config {
+        config.deny_unknown_keys().error_kind(ErrorKind::Config)?;
+    }
+    let logging_config = config.logging.clone();
+    let quiet = logging_config.quiet;
+    let level = if quiet { "warn" } else { logging_config.level.as_str() };
+    // Serving MCP, stdout carries JSON-RPC whatever the config says
+    let configured_output = match &cli.command {
+        Some(Commands::McpServe) => "mcp",
+        _ => cli.log_output.as_deref().unwrap_or(&logging_config.output),
+    };
+    let output = if machine_output { "stderr" } else { configured_output };
+    let logging = match logging_config.file.clone() {
+        file_config if configured_output == "mcp" => init_logging_for_mcp(Some(level), &config, file_config),
+        Some(file_config) => init_logging_with_file(Some(level), &config, Some(output), None, file_config),
+        None => init_logging(Some(level), &config, Some(output), None),
+    };
+    #[cfg(unix)]
+    spawn_level_toggle(logging.clone());
     
-    let cli = Cli::parse();
+    if !quiet {
+        info!("Autodebugger starting");
+    }
+    // `config validate` reports them itself
+    if !matches!(&cli.command, Some(Commands::Config { action: ConfigAction::Validate })) {
+        for key in &config.unknown_keys {
+            warn!("{}", key);
+        }
+    }
+    let verbose = matches!(&cli.command, Some(Commands::RemoveDebug { verbose: true, .. }) | Some(Commands::ValidateDocs { verbose: true, .. }));
+    if verbose {
+        info!("Config: {}", config.source_description());
+    }
     
     match cli.command {
-        Some(Commands::Monitor { path, format }) => {
+        Some(Commands::Monitor { path, format, no_cache, watch, interval }) => {
             info!("Starting monitor for path: {}", path.display());
-            let monitor = Monitor::new(path)?;
+            let status_cache = config.monitor.status_cache && !no_cache;
+            let mut monitor = Monitor::with_config(path.clone(), config.monitor)?;
+            if status_cache {
+                monitor = monitor.with_status_cache(default_cache_path(&path));
+            }
+            
+            if watch {
+                let mut stdout = std::io::stdout();
+                for event in MonitorEventStream::new(monitor, Duration::from_secs(interval)) {
+                    if format == "jsonl" {
+                        writeln!(stdout, "{}", serde_json::to_string(&event)?)?;
+                    } else {
+                        writeln!(stdout, "{}", event.describe())?;
+                    }
+                    stdout.flush()?;
+                }
+                return Ok(());
+            }
+            if format == "jsonl" {
+                return Err(CliError::msg(ErrorKind::Usage, "--format jsonl requires --watch"));
+            }
+            
             let status = monitor.status()?;
             
             match format.as_str() {
                 "json" => println!("{}", serde_json::to_string_pretty(&status)?),
+                "markdown" | "md" => print!("{}", status.to_markdown()),
+                "html" => print!("{}", status.to_html()),
                 _ => {
                     println!("Worktree Status Report");
                     println!("====================");
-                    for (name, worktree) in &status.worktrees {
-                        println!("\n{}: {} ({})", name, worktree.status, worktree.branch);
+                    for error in &status.errors {
+                        println!("\n{}: error ({})", error.name, error.error);
+                    }
+                    for worktree in status.by_recency() {
+                        println!("\n{}: {} ({})", worktree.name, worktree.status, worktree.branch);
+                        for alert in worktree.alerts().into_iter().chain(worktree.unsaved_work()) {
+                            println!("  ⚠️  {}", alert);
+                        }
                         if let Some(task) = &worktree.current_task {
                             println!("  Current task: {}", task);
                         }
+                        if !worktree.tasks.is_empty() {
+                            let (done, total) = autodebugger::monitor::tasks::completion(&worktree.tasks);
+                            println!("  Tasks: {}/{} done", done, total);
+                        }
                         println!("  Files changed: {}", worktree.files_changed);
-                        if let Some(last) = &worktree.last_change {
+                        if !worktree.submodules_changed.is_empty() {
+                            println!("  Submodules changed: {}", worktree.submodules_changed.join(", "));
+                        }
+                        if let Some(last) = &worktree.last_change_relative {
                             println!("  Last change: {}", last);
                         }
                     }
@@ -197,37 +932,149 @@ async fn main() -> Result<()> {
             }
         }
         
-        Some(Commands::Diff { worktree, summary, path }) => {
-            let monitor = Monitor::new(path)?;
+        Some(Commands::Diff { worktree, summary, json, include, exclude, path }) => {
+            let config = config.monitor;
+            let filter = DiffFilter::new()
+                .with_include(include)
+                .with_exclude(config.diff_exclude.iter().cloned().chain(exclude).collect());
+            let monitor = Monitor::with_config(path, config)?.with_diff_filter(filter);
             
             if summary {
-                // Summary mode implementation pending
-                println!("Summary mode not yet implemented");
+                let summaries = monitor.diff_summary(worktree.as_deref())?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&summaries)?);
+                } else {
+                    println!("{}", format_summary_table(&summaries));
+                }
+            } else if json {
+                let diffs = monitor.structured_diff(worktree.as_deref())?;
+                println!("{}", serde_json::to_string_pretty(&diffs)?);
             } else {
                 let diff = monitor.diff(worktree.as_deref())?;
                 println!("{}", diff);
             }
         }
         
-        Some(Commands::Context { context_type, path }) => {
-            let monitor = Monitor::new(path)?;
-            let context = monitor.context(&context_type)?;
+        Some(Commands::Context { context_type, path, limit, max_tokens }) => {
+            let monitor = Monitor::with_config(path, config.monitor)?
+                .with_commit_limit(limit)
+                .with_todos_config(config.todos);
+            let context = match max_tokens {
+                Some(budget) => monitor.context_with_budget(&context_type, budget)?,
+                None => monitor.context(&context_type)?,
+            };
             println!("{}", context);
         }
         
-        Some(Commands::Status { path, json }) => {
-            let monitor = Monitor::new(path)?;
+        Some(Commands::Status { path, json, files, overlaps, health, no_cache }) => {
+            let status_cache = config.monitor.status_cache && !no_cache;
+            let mut monitor = Monitor::with_config(path.clone(), config.monitor)?;
+            if status_cache {
+                monitor = monitor.with_status_cache(default_cache_path(&path));
+            }
+            if health {
+                monitor = monitor.with_ci_cache(autodebugger::ci::cache::default_ci_cache_path(&path), config.ci);
+            }
             let status = monitor.status()?;
+            let all_overlaps = if overlaps || health { Some(monitor.cross_worktree_overlaps()?) } else { None };
+            let health = all_overlaps.as_ref()
+                .filter(|_| health)
+                .map(|found| monitor.health_from(&status, found));
+            let overlaps = all_overlaps.filter(|_| overlaps);
             
             if json {
-                println!("{}", serde_json::to_string_pretty(&status)?);
+                if overlaps.is_none() && health.is_none() {
+                    println!("{}", serde_json::to_string_pretty(&status)?);
+                } else {
+                    let mut output = serde_json::json!({ "status": status });
+                    if let Some(overlaps) = &overlaps {
+                        output["overlaps"] = serde_json::to_value(overlaps)?;
+                    }
+                    if let Some(health) = &health {
+                        output["health"] = serde_json::to_value(health)?;
+                    }
+                    println!("{}", serde_json::to_string_pretty(&output)?);
+                }
             } else {
-                for (name, worktree) in &status.worktrees {
+                for error in &status.errors {
+                    println!("{}: error ({})", error.name, error.error);
+                }
+                for worktree in status.by_recency() {
                     println!("{}: {} ({} files changed)", 
-                        name, 
+                        worktree.name, 
                         worktree.status,
                         worktree.files_changed
                     );
+                    for alert in worktree.alerts().into_iter().chain(worktree.unsaved_work()) {
+                        println!("  ⚠️  {}", alert);
+                    }
+                    if !worktree.submodules_changed.is_empty() {
+                        println!("  Submodules changed: {}", worktree.submodules_changed.join(", "));
+                    }
+                    if files {
+                        for change in &worktree.changes {
+                            println!("  {} {} (+{} -{})",
+                                change.status.code(),
+                                change.path,
+                                change.insertions,
+                                change.deletions
+                            );
+                        }
+                    }
+                }
+                if let Some(overlaps) = overlaps {
+                    println!("\nOverlapping changes:");
+                    if overlaps.is_empty() {
+                        println!("  none");
+                    }
+                    for overlap in overlaps {
+                        println!("  {} {}: {}",
+                            if overlap.line_ranges_intersect { "⚠️ " } else { "  " },
+                            overlap.file,
+                            overlap.worktrees.join(", ")
+                        );
+                    }
+                }
+                if let Some(health) = health {
+                    println!("\nHealth:");
+                    for (rank, entry) in health.iter().enumerate() {
+                        println!("  {}. {:>3}  {}", rank + 1, entry.score, entry.name);
+                        for reason in &entry.reasons {
+                            println!("          {}", reason);
+                        }
+                    }
+                }
+            }
+        }
+        
+        Some(Commands::Worktree { action }) => {
+            let config = config.monitor;
+            match action {
+                WorktreeAction::Add { name, branch, base, seed, path } => {
+                    let template = match (&config.task_template, seed) {
+                        (Some(file), true) => Some(std::fs::read_to_string(file)
+                            .with_context(|| format!("Failed to read task template: {}", file))?),
+                        _ => None,
+                    };
+                    let monitor = WorktreeMonitor::new(path, Some(config))?;
+                    let worktree = monitor.create_worktree(&name, branch.as_deref(), base.as_deref())?;
+                    println!("Created worktree {} at {}", worktree.name, worktree.path.display());
+                    if seed && monitor.seed_task_file(&worktree, template.as_deref())? {
+                        println!("Seeded {}", worktree.path.join("CLAUDE.local.md").display());
+                    }
+                }
+                WorktreeAction::Remove { name, force, path } => {
+                    WorktreeMonitor::new(path, Some(config))?.remove_worktree(&name, force)?;
+                    println!("Removed worktree {}", name);
+                }
+                WorktreeAction::Prune { path } => {
+                    let pruned = WorktreeMonitor::new(path, Some(config))?.prune()?;
+                    if pruned.is_empty() {
+                        println!("Nothing to prune");
+                    }
+                    for entry in pruned {
+                        println!("{}", entry);
+                    }
                 }
             }
         }
@@ -235,7 +1082,7 @@ async fn main() -> Result<()> {
         Some(Commands::Run { command }) => {
             // Legacy command execution mode
             let command_str = command.join(" ");
-            let debugger = Autodebugger::new();
+            let debugger = Autodebugger::new().with_redaction(&config.redaction)?;
             let result = debugger.run_command(&command_str)?;
             
             if !result.stdout.is_empty() {
@@ -244,17 +1091,55 @@ async fn main() -> Result<()> {
             if !result.stderr.is_empty() {
                 eprint!("{}", result.stderr);
             }
+            logging.flush();
             std::process::exit(result.exit_code);
         }
         
-        Some(Commands::RemoveDebug { paths, dry_run, verbose }) => {
-            use autodebugger::config::Config;
+        Some(Commands::Task { name, keep_going }) => {
+            config.task(&name).error_kind(ErrorKind::Usage)?;
+            let debugger = Autodebugger::new().with_redaction(&config.redaction)?;
+            // A failing step's error is logged; its output is printed as it finishes
+            let report = debugger.run_task_with(&config, &name, keep_going, |step| {
+                if !step.result.stdout.is_empty() {
+                    println!("{}", step.result.stdout);
+                }
+            })?;
+            print!("{}", report.summary());
+            if !report.success() {
+                logging.flush();
+                std::process::exit(report.exit_code());
+            }
+        }
+        
+        Some(Commands::RemoveDebug { paths, dry_run, verbose, macros, diff, since, no_backup, restore, jobs, format, check, tidy_whitespace, no_tidy_whitespace, engine, interactive, watch }) => {
+            let dry_run = dry_run || check;
+            let format = report_format(&format);
+            let config = config.remove_debug;
+            let macros = if macros.is_empty() { config.macros } else { macros };
+            let engine = match engine {
+                Some(engine) => engine.parse().error_kind(ErrorKind::Usage)?,
+                None => config.engine,
+            };
             
-            // Use provided paths or fall back to config defaults
+            if let Some(run) = restore {
+                let remover = DebugRemover::new(paths.first().cloned().unwrap_or_else(|| PathBuf::from(".")));
+                let report = remover.restore_backup(run.as_deref())?;
+                for path in &report.restored {
+                    info!("Restored {}", path.display());
+                }
+                for (path, reason) in &report.skipped {
+                    tracing::warn!("Skipped {}: {}", path.display(), reason);
+                }
+                info!("Restored {} file(s) from backup run {}", report.restored.len(), report.run);
+                return Ok(());
+            }
+            let backup_run = autodebugger::backup::BackupStore::new_run_id();
+            
+            // Use provided paths or fall back to the config defaults that exist here
             let paths_to_process = if paths.is_empty() {
-                let config = Config::load().unwrap_or_default();
-                config.remove_debug.default_paths.into_iter()
+                config.default_paths.into_iter()
                     .map(PathBuf::from)
+                    .filter(|path| path.exists())
                     .collect()
             } else {
                 paths
@@ -264,44 +1149,116 @@ async fn main() -> Result<()> {
                 info!("Running in dry-run mode - no files will be modified");
             }
             
+            let remover_for = |path: PathBuf| {
+                let remover = DebugRemover::new(path)
+                    .with_dry_run(dry_run)
+                    .with_verbose(verbose)
+                    .with_macros(macros.iter().cloned())
+                    .with_backups(config.backups && !no_backup)
+                    .with_backup_run(backup_run.clone())
+                    .with_backup_retention(config.backup_retention)
+                    .with_jobs(jobs.unwrap_or(config.jobs))
+                    .with_tidy_whitespace(tidy_whitespace || (config.tidy_whitespace && !no_tidy_whitespace))
+                    .with_engine(engine);
+                match &since {
+                    Some(base) => remover.with_git_scope(base),
+                    None => remover,
+                }
+            };
+            
+            if watch {
+                if paths_to_process.is_empty() {
+                    return Err(CliError::msg(ErrorKind::Usage, "No paths to watch")
+                        .with_hint("Pass the paths to watch, or list existing ones in remove_debug.default_paths"));
+                }
+                // One session per path, so each keeps its own --since scope
+                let removers: Vec<(PathBuf, DebugRemover)> = paths_to_process.iter()
+                    .map(|path| (autodebugger::watch::clean(path), remover_for(path.clone())))
+                    .collect();
+                let sessions = removers.iter()
+                    .map(|(root, remover)| Ok((root, remover.session()?)))
+                    .collect::<Result<Vec<_>>>()?;
+                return watch_files("remove-debug --check", &paths_to_process,
+                    |path| path.extension().is_some_and(|extension| extension == "rs"),
+                    |path| {
+                        // The watcher only reports files under the watched paths
+                        match sessions.iter().find(|(root, _)| path.starts_with(root)) {
+                            Some((_, session)) => FileFindings::from_removal(&session.process_file(path)),
+                            None => FileFindings::default(),
+                        }
+                    });
+            }
+            
             let mut total_report = autodebugger::remove_debug::RemovalReport::default();
+            // Shared by every path, so `all` and `quit` carry over from one to the next
+            let answers = std::sync::Arc::new(std::sync::Mutex::new(None));
             
             for path in paths_to_process {
                 if verbose {
                     info!("Processing path: {}", path.display());
                 }
                 
-                let remover = DebugRemover::new(path)
-                    .with_dry_run(dry_run)
-                    .with_verbose(verbose);
+                let mut remover = remover_for(path);
+                if interactive {
+                    let answers = answers.clone();
+                    remover = remover.with_decision_fn(move |candidate| {
+                        let mut answers = answers.lock().unwrap_or_else(|e| e.into_inner());
+                        let decision = answers.unwrap_or_else(|| prompt_decision(candidate));
+                        if matches!(decision, Decision::All | Decision::Quit) {
+                            *answers = Some(decision);
+                        }
+                        decision
+                    });
+                }
                 
                 let report = remover.remove_debug_calls()?;
                 
-                // Aggregate reports
-                total_report.files_scanned += report.files_scanned;
-                total_report.files_modified += report.files_modified;
-                total_report.total_lines_removed += report.total_lines_removed;
-                total_report.total_warnings += report.total_warnings;
-                total_report.file_reports.extend(report.file_reports);
+                total_report.merge(report);
+            }
+            
+            if format == "json" {
+                println!("{}", serde_json::to_string_pretty(&total_report)?);
+            } else if format == "github" {
+                for annotation in total_report.github_annotations(check) {
+                    println!("{}", annotation);
+                }
+            } else if diff || (dry_run && verbose) {
+                for (_, file_report) in &total_report.file_reports {
+                    print!("{}", file_report.diff);
+                }
             }
             
             total_report.print_summary(verbose);
             
-            if dry_run && total_report.total_lines_removed > 0 {
+            if check {
+                if total_report.has_findings() {
+                    return Err(CliError::msg(ErrorKind::CheckFailed, format!(
+                        "Check failed: {} removable call(s), {} warning(s)",
+                        total_report.calls_by_macro.values().sum::<usize>(), total_report.total_warnings
+                    )));
+                }
+            } else if dry_run && !total_report.calls_by_macro.is_empty() {
                 info!("Re-run without --dry-run to apply changes");
             }
+            if let Some(run) = &total_report.backup_run {
+                info!("Originals backed up; undo with: autodebugger remove-debug --restore {}", run);
+            }
+            if !total_report.failures.is_empty() {
+                for (path, error) in &total_report.failures {
+                    tracing::error!("{}: {}", path.display(), error);
+                }
+                return Err(CliError::msg(ErrorKind::Environment, format!(
+                    "{} file(s) could not be processed", total_report.failures.len()
+                )));
+            }
         }
         
-        Some(Commands::ValidateDocs { paths, verbose, strict }) => {
-            use autodebugger::config::Config;
-            use autodebugger::validate_docs::DocValidator;
-            
-            // Load configuration
-            let config = Config::load().unwrap_or_default();
+        Some(Commands::ValidateDocs { paths, verbose, strict, pub_items, format, write_baseline, no_baseline, changed_since, fix, dry_run, check_references, check_config_snippets, jobs, min_coverage, top, group_by_dir, complexity_threshold: _, watch }) => {
+            use autodebugger::validate_docs::{DocValidator, DocsBaseline, SummaryOptions};
             
             // Use provided paths or fall back to config defaults
             let paths_to_process = if paths.is_empty() {
-                config.validate_docs.default_paths.into_iter()
+                config.validate_docs.default_paths.iter()
                     .map(PathBuf::from)
                     .collect()
             } else {
@@ -313,26 +1270,343 @@ async fn main() -> Result<()> {
             }
             
             // Create validator with config settings
-            let validator = DocValidator::new()
-                .with_min_doc_lines(config.validate_docs.min_doc_lines_complex)
-                .with_max_doc_lines(config.validate_docs.max_doc_lines)
-                .with_complexity_threshold(config.validate_docs.complexity_threshold)
-                .with_ignore_patterns(config.validate_docs.ignore_patterns)?
+            let mut validator = DocValidator::from_config(config.validate_docs.clone()).error_kind(ErrorKind::Config)?
+                .with_require_pub_item_docs(pub_items || config.validate_docs.require_pub_item_docs)
+                .with_fix(fix)
+                .with_dry_run(dry_run)
+                .with_check_references(check_references || config.validate_docs.check_references)
+                .with_check_config_snippets(check_config_snippets || config.validate_docs.check_config_snippets)
+                .with_jobs(jobs.unwrap_or(config.validate_docs.jobs))
                 .with_verbose(verbose)
                 .with_strict(strict);
             
+            if let Some(base_ref) = &changed_since {
+                validator = validator.with_changed_since(base_ref);
+            }
+            
+            let baseline_path = DocsBaseline::path(&std::env::current_dir()?);
+            let mut baseline = DocsBaseline::load(&baseline_path)?;
+            if write_baseline {
+                let report = validator.validate_paths(paths_to_process)?;
+                baseline.update(&report);
+                baseline.save(&baseline_path)?;
+                info!("Recorded {} issue(s) in {}", baseline.issues.len(), baseline_path.display());
+                return Ok(());
+            }
+            if !no_baseline {
+                validator = validator.with_baseline(baseline);
+            }
+            
+            if watch {
+                return watch_files("validate-docs", &paths_to_process,
+                    |path| validator.accepts(path),
+                    |path| FileFindings::from_docs(&validator.validate_file(path)));
+            }
+            
+            // Ctrl-C stops the run and prints what was validated so far; a second one exits
+            let cancel = Arc::new(AtomicBool::new(false));
+            let flag = cancel.clone();
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    flag.store(true, Ordering::Relaxed);
+                    if tokio::signal::ctrl_c().await.is_ok() {
+                        std::process::exit(130);
+                    }
+                }
+            });
+            
             // Run validation
-            let report = validator.validate_paths(paths_to_process)?;
+            let report = validator.with_cancel_flag(cancel).validate_paths(paths_to_process)?;
             
-            // Print summary
-            report.print_summary(verbose);
+            match report_format(&format) {
+                "json" => println!("{}", serde_json::to_string_pretty(&report.to_json())?),
+                "github" => {
+                    for annotation in report.github_annotations() {
+                        println!("{}", annotation);
+                    }
+                }
+                "sarif" => println!("{}", serde_json::to_string_pretty(&report.to_sarif())?),
+                _ => report.print_summary(&SummaryOptions { verbose, top, group_by_directory: group_by_dir }, &mut report_output())?,
+            }
             
-            // Exit with error code if strict mode and there were warnings
+            if report.cancelled {
+                logging.flush();
+                std::process::exit(130);
+            }
+            if let Some(min) = min_coverage.or(config.validate_docs.min_coverage) {
+                let coverage = report.coverage();
+                if coverage.percent < min {
+                    return Err(CliError::msg(ErrorKind::CheckFailed, format!(
+                        "Documentation coverage {:.1}% is below the minimum of {}%", coverage.percent, min
+                    )));
+                }
+            }
+            // Warnings fail the check in strict mode
             if !report.passed(strict) {
-                std::process::exit(1);
+                let counted = if strict { report.errors + report.warnings } else { report.errors };
+                return Err(CliError::msg(ErrorKind::CheckFailed, format!(
+                    "Documentation validation failed with {} {}", counted,
+                    if strict { "error(s) and warning(s)" } else { "error(s)" }
+                )));
+            }
+        }
+        
+        Some(Commands::Clean { paths, dry_run, steps, skip, fail_fast, strict, json }) => {
+            use autodebugger::clean::Cleaner;
+            
+            let mut steps = if steps.is_empty() { config.clean.steps.clone() } else { steps };
+            steps.retain(|step| !skip.contains(step));
+            let report = Cleaner::new(config)
+                .with_paths(paths)
+                .with_steps(steps)
+                .with_dry_run(dry_run)
+                .with_fail_fast(fail_fast)
+                .with_strict(strict)
+                .run();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report.to_json())?);
+            } else {
+                print!("{}", report.to_text());
+            }
+            if let Some(run) = report.removal.as_ref().and_then(|removal| removal.backup_run.as_ref()) {
+                info!("Originals backed up; undo with: autodebugger remove-debug --restore {}", run);
+            }
+            if !report.passed() {
+                let failed: Vec<&str> = report.steps.iter()
+                    .filter(|outcome| !outcome.passed)
+                    .map(|outcome| outcome.step.name())
+                    .collect();
+                return Err(CliError::msg(ErrorKind::CheckFailed, format!("Clean failed: {}", failed.join(", "))));
+            }
+        }
+        
+        Some(Commands::Ci { worktree, all, plan, base, format, json, timestamp, no_cache, checks, path }) => {
+            use autodebugger::ci::{cache::default_ci_cache_path, Recommendation, CI};
+            
+            // Scoring comes from this run's config; each check uses the worktree's own
+            let mut ci_config = config.ci;
+            if !checks.is_empty() {
+                ci_config.enabled_checks = checks.clone();
+            }
+            let mut ci = CI::with_config(path.clone(), ci_config).with_monitor_config(config.monitor);
+            if !no_cache {
+                ci = ci.with_cache(default_ci_cache_path(&path));
+            }
+            let format = if json { "json" } else { format.as_str() };
+            let reports = if plan {
+                let plan = ci.plan_merge_order(base.as_deref())?;
+                match format {
+                    "json" => println!("{}", serde_json::to_string_pretty(&plan)?),
+                    "text" => print!("{}", plan.to_text()),
+                    other => return Err(CliError::msg(ErrorKind::Usage, format!(
+                        "--plan supports text and json output, not {}", other
+                    ))),
+                }
+                plan.reports
+            } else {
+                let reports = match &worktree {
+                    Some(worktree) => vec![ci.report(worktree, base.as_deref())?],
+                    None => ci.report_all(base.as_deref())?,
+                };
+                let generated = timestamp.then(|| chrono::Utc::now().to_rfc3339());
+                match format {
+                    "json" if all => println!("{}", serde_json::to_string_pretty(&reports)?),
+                    "json" => println!("{}", serde_json::to_string_pretty(&reports[0])?),
+                    "markdown" | "md" => {
+                        let rendered: Vec<String> = reports.iter()
+                            .map(|report| match &generated {
+                                Some(timestamp) => report.to_markdown_at(timestamp),
+                                None => report.to_markdown(),
+                            })
+                            .collect();
+                        print!("{}", rendered.join("\n---\n\n"));
+                    }
+                    _ => {
+                        let rendered: Vec<String> = reports.iter().map(|report| report.to_text()).collect();
+                        print!("{}", rendered.join("\n"));
+                    }
+                }
+                reports
+            };
+            // Hand-picked checks gate on their own results, as in a git hook;
+            // otherwise any worktree short of SAFE fails
+            let failed: Vec<String> = reports.iter()
+                .filter_map(|report| if checks.is_empty() {
+                    (report.recommendation != Recommendation::Safe)
+                        .then(|| format!("{} ({})", report.worktree, report.recommendation))
+                } else {
+                    let failed: Vec<&str> = report.checks.failed().iter().map(|check| check.name()).collect();
+                    (!failed.is_empty()).then(|| format!("{} ({})", report.worktree, failed.join(", ")))
+                })
+                .collect();
+            if !failed.is_empty() {
+                let what = if checks.is_empty() { "Not safe to merge" } else { "Checks failed" };
+                return Err(CliError::msg(ErrorKind::CheckFailed, format!("{}: {}", what, failed.join("; "))));
+            }
+        }
+        
+        Some(Commands::InstallHooks { pre_commit, pre_push, path }) => {
+            use autodebugger::hooks::{Hook, HookInstaller};
+            
+            let config = config.hooks;
+            let program = match config.program {
+                Some(program) => program,
+                None => std::env::current_exe()
+                    .context("Failed to locate the autodebugger binary; set hooks.program")?
+                    .display()
+                    .to_string(),
+            };
+            let installer = HookInstaller::new(path).with_program(program);
+            for (hook, checks) in [
+                (Hook::PreCommit, pre_commit.unwrap_or(config.pre_commit)),
+                (Hook::PrePush, pre_push.unwrap_or(config.pre_push)),
+            ] {
+                let file = installer.install(hook, &checks)?;
+                if checks.is_empty() {
+                    println!("{}: no checks, nothing installed", hook);
+                } else {
+                    let names: Vec<&str> = checks.iter().map(|check| check.name()).collect();
+                    println!("{}: {} ({})", hook, names.join(", "), file.display());
+                }
+            }
+        }
+        
+        Some(Commands::UninstallHooks { path }) => {
+            use autodebugger::hooks::{Hook, HookInstaller};
+            
+            let installer = HookInstaller::new(path);
+            for hook in Hook::ALL {
+                if installer.uninstall(hook)? {
+                    println!("Removed the autodebugger section from {}", hook);
+                }
+            }
+        }
+        
+        Some(Commands::Config { action }) => match action {
+            ConfigAction::Init { path, force } => {
+                if path.exists() && !force {
+                    return Err(CliError::msg(ErrorKind::Usage, format!("{} already exists", path.display()))
+                        .with_hint("Pass --force to overwrite it"));
+                }
+                std::fs::write(&path, default_template())
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                println!("Wrote {}", path.display());
+            }
+            ConfigAction::Show => {
+                let mut layers: Vec<(ConfigSource, Config)> = config_files.into_iter()
+                    .map(ConfigSource::File)
+                    .zip(file_layers)
+                    .collect();
+                if let (Some(name), Some(profile_config)) = (profile, profile_config) {
+                    layers.push((ConfigSource::Profile(name), profile_config));
+                }
+                layers.push((ConfigSource::Environment, env_config));
+                layers.push((ConfigSource::CommandLine, config));
+                print!("{}", render_effective(&layers)?);
+            }
+            ConfigAction::Validate => {
+                if config.unknown_keys.is_empty() {
+                    println!("✓ Valid configuration: {}", config.source_description());
+                } else {
+                    for key in &config.unknown_keys {
+                        println!("{}", key);
+                    }
+                    return Err(CliError::msg(ErrorKind::CheckFailed, format!(
+                        "{} unknown config key(s)", config.unknown_keys.len()
+                    )));
+                }
+            }
+        },
+        
+        Some(Commands::Logs { action, list, tail, lines, follow, show, json }) => {
+            let file_config = config.logging.file.clone().unwrap_or_default();
+            let files = log_files(&file_config);
+            let latest = latest_log_file(&file_config);
+            match action {
+                Some(LogsAction::Clean { keep }) => {
+                    let retention = RotatingFileConfig { max_files: keep.unwrap_or(file_config.max_files), ..file_config };
+                    let pruned = prune_log_files(&retention, latest.as_deref());
+                    if pruned.is_empty() {
+                        println!("Nothing to clean in {}", retention.log_directory);
+                    } else {
+                        println!("Removed {} log file(s) from {}", pruned.len(), retention.log_directory);
+                        for name in pruned {
+                            println!("  {}", name);
+                        }
+                    }
+                }
+                None if list => {
+                    let listing = log_viewer::listing(&files, latest.as_deref());
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&listing)?);
+                    } else if listing.is_empty() {
+                        println!("No log files in {}", file_config.log_directory);
+                    } else {
+                        print!("{}", log_viewer::render_listing(&listing));
+                    }
+                }
+                None => {
+                    let path = match &show {
+                        Some(selector) => log_viewer::select(&files, selector)
+                            .with_context(|| format!("Failed to pick a log file from {}", file_config.log_directory))?
+                            .path.clone(),
+                        None => latest.with_context(|| format!(
+                            "No log files in {} (logging.file.log_directory)", file_config.log_directory))?,
+                    };
+                    let content = log_viewer::read_log(&path)?;
+                    let mut stdout = std::io::stdout().lock();
+                    if tail {
+                        stdout.write_all(log_viewer::tail(&content, lines))?;
+                    } else {
+                        stdout.write_all(&content)?;
+                    }
+                    if follow {
+                        log_viewer::follow(
+                            path,
+                            content.len() as u64,
+                            || latest_log_file(&file_config),
+                            &mut stdout,
+                            Duration::from_millis(500),
+                            || false,
+                        )?;
+                    }
+                }
             }
         }
         
+        Some(Commands::McpServe) => {
+            info!("Serving MCP tools on stdin/stdout");
+            let server = autodebugger::mcp::McpServer::new(config);
+            server.serve(std::io::stdin().lock(), &mut std::io::stdout().lock())?;
+        }
+        
+        #[cfg(feature = "server")]
+        Some(Commands::Serve { path, port, bind }) => {
+            let workspace = path
+                .or_else(|| config.server.workspace.as_ref().map(PathBuf::from))
+                .unwrap_or_else(|| PathBuf::from("."));
+            if !workspace.is_dir() {
+                return Err(autodebugger::error::not_found(format_args!(
+                    "Workspace path does not exist: {}", workspace.display()
+                )).into());
+            }
+            let address = format!("{}:{}", bind.unwrap_or_else(|| config.server.bind.clone()), port.unwrap_or(config.server.port));
+            let listener = tokio::net::TcpListener::bind(&address).await
+                .with_context(|| format!("Failed to listen on {}", address))?;
+            info!("Serving {} on http://{} (Ctrl-C to stop)", workspace.display(), listener.local_addr()?);
+            let router = autodebugger::server::router(workspace, config);
+            autodebugger::server::serve(listener, router, async {
+                tokio::signal::ctrl_c().await.ok();
+            }).await?;
+            info!("Server stopped");
+        }
+        
+        #[cfg(not(feature = "server"))]
+        Some(Commands::Serve { .. }) => {
+            return Err(CliError::msg(ErrorKind::Usage, "serve needs autodebugger built with the `server` feature")
+                .with_hint("Rebuild with --features server"));
+        }
+        
         None => {
             // No command specified, show help
             println!("Autodebugger - Cybernetic Coding Dashboard");
@@ -340,6 +1614,57 @@ async fn main() -> Result<()> {
         }
     }
     
-    info!("Autodebugger shutting down");
+    if !quiet {
+        info!("Autodebugger shutting down");
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Show one remove-debug change on stderr and read the answer from stdin
+///
+/// End of input counts as quit.
+fn prompt_decision(candidate: &RemovalCandidate) -> Decision {
+    let mut stderr = std::io::stderr().lock();
+    let _ = writeln!(stderr, "\n{}:{}", candidate.path.display(), candidate.start_line);
+    let first_context = candidate.start_line - candidate.context_before.len();
+    for (i, line) in candidate.context_before.iter().enumerate() {
+        let _ = writeln!(stderr, "{:>6}   {}", first_context + i, line);
+    }
+    for (i, line) in candidate.text.lines().enumerate() {
+        let _ = writeln!(stderr, "{:>6} - {}", candidate.start_line + i, line);
+    }
+    for line in candidate.replacement.iter().flat_map(|r| r.lines()) {
+        let _ = writeln!(stderr, "       + {}", line);
+    }
+    for (i, line) in candidate.context_after.iter().enumerate() {
+        let _ = writeln!(stderr, "{:>6}   {}", candidate.end_line + 1 + i, line);
+    }
+    
+    let action = if candidate.replacement.is_some() { "Rewrite" } else { "Remove" };
+    loop {
+        let _ = write!(stderr, "{}? [y]es, [n]o, [a]ll, [q]uit: ", action);
+        let _ = stderr.flush();
+        let mut answer = String::new();
+        match std::io::stdin().read_line(&mut answer) {
+            Ok(0) | Err(_) => return Decision::Quit,
+            Ok(_) => {}
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => return Decision::Yes,
+            "n" | "no" => return Decision::No,
+            "a" | "all" => return Decision::All,
+            "q" | "quit" => return Decision::Quit,
+            _ => {}
+        }
+    }
+}
+
+/// `--format` of remove-debug and validate-docs: as given, else `github` inside
+/// GitHub Actions, else `text`
+fn report_format(format: &Option<String>) -> &str {
+    match format {
+        Some(format) => format,
+        None if std::env::var("GITHUB_ACTIONS").is_ok_and(|v| v == "true") => "github",
+        None => "text",
+    }
+}
@@ -0,0 +1,538 @@
+//! Model Context Protocol server over stdio (`autodebugger mcp-serve`)
+//!
+//! [`McpServer`] reads one JSON-RPC 2.0 message per line from its input and
+//! writes one per line to its output, as the MCP stdio transport does. It
+//! answers `initialize`, `ping`, `tools/list`, and `tools/call`, ignores
+//! notifications such as `notifications/initialized`, and reports anything else
+//! with the standard JSON-RPC error codes. Since stdout carries the protocol, the
+//! CLI logs to stderr (`init_logging_for_mcp`) while serving.
+//!
+//! ## Tools
+//!
+//! | Tool | Arguments | Result |
+//! |------|-----------|--------|
+//! | `run_command` | `command`, `working_dir` | `CommandResult` |
+//! | `worktree_status` | `path`, `no_cache` | `MonitorStatus` |
+//! | `diff` | `path`, `worktree`, `summary`, `include`, `exclude` | `{worktrees}`: `WorktreeDiff`s, or `DiffSummary`s with `summary` |
+//! | `remove_debug_check` | `paths`, `macros`, `since` | `{passed, report}` with a `RemovalReport` |
+//! | `validate_docs` | `paths`, `strict`, `pub_items`, `check_references`, `no_baseline` | `{passed, report}` with `ValidationReport::to_json` |
+//!
+//! Each tool's `inputSchema` in `tools/list` describes its arguments; omitted
+//! arguments take the defaults of the config the server was started with, as on
+//! the command line. Results are returned both as `structuredContent` and as
+//! pretty-printed JSON text. A tool that fails (a missing path, a workspace
+//! without worktrees) returns `isError: true` with the error message; a failed
+//! check is a result with `passed: false`, not an error.
+//!
+//! ## Progress
+//!
+//! When a `tools/call` request carries `_meta.progressToken`, the longer tools
+//! (`run_command`, `remove_debug_check`, `validate_docs`) send
+//! `notifications/progress` messages with that token before their response.
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use crate::monitor::cache::default_cache_path;
+use crate::monitor::diff::DiffFilter;
+use crate::monitor::Monitor;
+use crate::remove_debug::{DebugRemover, RemovalReport};
+use crate::validate_docs::{DocValidator, DocsBaseline};
+use crate::{Autodebugger, Config};
+
+/// Protocol revisions the server speaks, newest first; a client asking for
+/// another gets the newest
+pub const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// JSON-RPC error codes
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+
+/// An MCP server exposing autodebugger's tools, configured by `config`
+pub struct McpServer {
+    config: Config,
+}
+
+impl McpServer {
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+
+    /// Answer each line of `input` on `out` until `input` ends
+    pub fn serve(&self, input: impl BufRead, out: &mut dyn Write) -> Result<()> {
+        for line in input.lines() {
+            let line = line.context("Failed to read a message")?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle_message(&line, out) {
+                write_message(out, &response).context("Failed to write a response")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The response to one message, or None for a notification. Progress
+    /// notifications are written to `out` while a tool runs.
+    pub fn handle_message(&self, line: &str, out: &mut dyn Write) -> Option<Value> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, format!("Parse error: {}", e))),
+        };
+        let id = message.get("id").cloned();
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            // The server sends no requests, so a response from the client is stray
+            if message.get("result").is_some() || message.get("error").is_some() {
+                return None;
+            }
+            return Some(error_response(id.unwrap_or(Value::Null), INVALID_REQUEST, "Invalid request: no method".to_string()));
+        };
+        let params = message.get("params").cloned().unwrap_or_else(|| json!({}));
+
+        let result = match method {
+            "initialize" => Ok(initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+            "tools/call" => self.call_tool(&params, out),
+            _ if id.is_none() => return None,
+            other => Err(RpcError { code: METHOD_NOT_FOUND, message: format!("Method not found: {}", other) }),
+        };
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => error_response(id, e.code, e.message),
+        })
+    }
+
+    /// `tools/call`: run the named tool; its failures are tool results with
+    /// `isError`, bad names and arguments are protocol errors
+    fn call_tool(&self, params: &Value, out: &mut dyn Write) -> Result<Value, RpcError> {
+        let name = params.get("name").and_then(Value::as_str)
+            .ok_or_else(|| RpcError::invalid_params("tools/call needs a tool name".to_string()))?;
+        let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+        let mut progress = Progress {
+            token: params.pointer("/_meta/progressToken").cloned(),
+            out,
+        };
+        let outcome = match name {
+            "run_command" => self.run_command(arguments_for(name, arguments)?, &mut progress),
+            "worktree_status" => self.worktree_status(arguments_for(name, arguments)?),
+            "diff" => self.diff(arguments_for(name, arguments)?),
+            "remove_debug_check" => self.remove_debug_check(arguments_for(name, arguments)?, &mut progress),
+            "validate_docs" => self.validate_docs(arguments_for(name, arguments)?, &mut progress),
+            other => return Err(RpcError::invalid_params(format!("Unknown tool: {}", other))),
+        };
+        Ok(match outcome {
+            Ok(value) => json!({
+                "content": [{ "type": "text", "text": serde_json::to_string_pretty(&value).unwrap_or_default() }],
+                "structuredContent": value,
+                "isError": false,
+            }),
+            Err(e) => json!({
+                "content": [{ "type": "text", "text": format!("{:#}", e) }],
+                "isError": true,
+            }),
+        })
+    }
+
+    fn run_command(&self, args: RunCommandArgs, progress: &mut Progress) -> Result<Value> {
+        let working_dir = match args.working_dir {
+            Some(dir) => dir,
+            None => std::env::current_dir()?,
+        };
+        if !working_dir.is_dir() {
+            return Err(crate::error::not_found(format_args!("Directory does not exist: {}", working_dir.display())));
+        }
+        let debugger = Autodebugger::with_working_dir(working_dir).with_redaction(&self.config.redaction)?;
+        progress.report(0, 1, &format!("Running {}", args.command));
+        // run_command moves the process into its working directory; later
+        // relative paths are the client's
+        let cwd = std::env::current_dir()?;
+        let result = debugger.run_command(&args.command);
+        std::env::set_current_dir(&cwd).context("Failed to restore the working directory")?;
+        progress.report(1, 1, "Finished");
+        Ok(serde_json::to_value(result?)?)
+    }
+
+    fn worktree_status(&self, args: StatusArgs) -> Result<Value> {
+        let mut monitor = Monitor::with_config(args.path.clone(), self.config.monitor.clone())?;
+        if self.config.monitor.status_cache && !args.no_cache {
+            monitor = monitor.with_status_cache(default_cache_path(&args.path));
+        }
+        Ok(serde_json::to_value(monitor.status()?)?)
+    }
+
+    fn diff(&self, args: DiffArgs) -> Result<Value> {
+        let config = self.config.monitor.clone();
+        let filter = DiffFilter::new()
+            .with_include(args.include)
+            .with_exclude(config.diff_exclude.iter().cloned().chain(args.exclude).collect());
+        let monitor = Monitor::with_config(args.path, config)?.with_diff_filter(filter);
+        // structuredContent must be an object, so the list is wrapped
+        let worktrees = if args.summary {
+            serde_json::to_value(monitor.diff_summary(args.worktree.as_deref())?)?
+        } else {
+            serde_json::to_value(monitor.structured_diff(args.worktree.as_deref())?)?
+        };
+        Ok(json!({ "worktrees": worktrees }))
+    }
+
+    /// As `remove-debug --check`: a dry run over each path in turn
+    fn remove_debug_check(&self, args: RemoveDebugArgs, progress: &mut Progress) -> Result<Value> {
+        let config = &self.config.remove_debug;
+        let paths = match args.paths {
+            Some(paths) => paths,
+            // As on the command line, default paths that do not exist here are skipped
+            None => config.default_paths.iter().map(PathBuf::from).filter(|path| path.exists()).collect(),
+        };
+        let macros = args.macros.unwrap_or_else(|| config.macros.clone());
+        let mut total = RemovalReport::default();
+        for (done, path) in paths.iter().enumerate() {
+            progress.report(done, paths.len(), &format!("Checking {}", path.display()));
+            let mut remover = DebugRemover::new(path.clone())
+                .with_dry_run(true)
+                .with_macros(macros.iter().cloned())
+                .with_jobs(config.jobs)
+                .with_tidy_whitespace(config.tidy_whitespace)
+                .with_engine(config.engine);
+            if let Some(base) = &args.since {
+                remover = remover.with_git_scope(base);
+            }
+            total.merge(remover.remove_debug_calls()?);
+        }
+        progress.report(paths.len(), paths.len(), "Finished");
+        Ok(json!({ "passed": !total.has_findings(), "report": total }))
+    }
+
+    /// As `validate-docs`, with the baseline in the working directory unless
+    /// `no_baseline`
+    fn validate_docs(&self, args: ValidateDocsArgs, progress: &mut Progress) -> Result<Value> {
+        let config = &self.config.validate_docs;
+        let paths = args.paths.unwrap_or_else(|| config.default_paths.iter().map(PathBuf::from).collect());
+        let mut validator = DocValidator::from_config(config.clone())?
+            .with_require_pub_item_docs(args.pub_items.unwrap_or(config.require_pub_item_docs))
+            .with_check_references(args.check_references.unwrap_or(config.check_references))
+            .with_check_config_snippets(config.check_config_snippets)
+            .with_jobs(config.jobs)
+            .with_strict(args.strict);
+        if !args.no_baseline {
+            validator = validator.with_baseline(DocsBaseline::load(&DocsBaseline::path(&std::env::current_dir()?))?);
+        }
+        let listed: Vec<String> = paths.iter().map(|path| path.display().to_string()).collect();
+        progress.report(0, 1, &format!("Validating {}", listed.join(", ")));
+        let report = validator.validate_paths(paths)?;
+        progress.report(1, 1, &format!("Validated {} files", report.files_scanned));
+        Ok(json!({ "passed": report.passed(args.strict), "report": report.to_json() }))
+    }
+}
+
+/// `initialize`: the client's protocol version if supported, and the tools capability
+fn initialize(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(Value::as_str);
+    let version = requested.filter(|version| PROTOCOL_VERSIONS.contains(version)).unwrap_or(PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": { "listChanged": false } },
+        "serverInfo": { "name": "autodebugger", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+/// `tools/list` entries: name, description, and input schema of each tool
+pub fn tool_definitions() -> Vec<Value> {
+    let paths = json!({
+        "type": "array",
+        "items": { "type": "string" },
+        "description": "Files or directories (default: the config's default_paths)",
+    });
+    vec![
+        json!({
+            "name": "run_command",
+            "description": "Run a shell command with bash and return its stdout, stderr, and exit code (secrets redacted per the config)",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "command": { "type": "string", "description": "Command line passed to bash -c" },
+                    "working_dir": { "type": "string", "description": "Directory to run in (default: the server's)" },
+                },
+                "required": ["command"],
+                "additionalProperties": false,
+            },
+        }),
+        json!({
+            "name": "worktree_status",
+            "description": "Status of every git worktree in a workspace: changed files, alerts, and recent activity",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Workspace containing the worktrees" },
+                    "no_cache": { "type": "boolean", "description": "Recompute every worktree instead of using the status cache" },
+                },
+                "required": ["path"],
+                "additionalProperties": false,
+            },
+        }),
+        json!({
+            "name": "diff",
+            "description": "Uncommitted changes of the worktrees in a workspace, as structured diffs or per-file summaries",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Workspace containing the worktrees" },
+                    "worktree": { "type": "string", "description": "Only this worktree" },
+                    "summary": { "type": "boolean", "description": "Per-file line counts instead of hunks" },
+                    "include": { "type": "array", "items": { "type": "string" }, "description": "Globs of files to include" },
+                    "exclude": { "type": "array", "items": { "type": "string" }, "description": "Globs of files to leave out" },
+                },
+                "required": ["path"],
+                "additionalProperties": false,
+            },
+        }),
+        json!({
+            "name": "remove_debug_check",
+            "description": "Find debug macro calls that remove-debug would remove or rewrite, without changing any file",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "paths": paths,
+                    "macros": { "type": "array", "items": { "type": "string" }, "description": "Macros to look for (default: remove_debug.macros)" },
+                    "since": { "type": "string", "description": "Only lines added since branching from this ref" },
+                },
+                "additionalProperties": false,
+            },
+        }),
+        json!({
+            "name": "validate_docs",
+            "description": "Check that complex Rust modules have //! documentation, and optionally public items /// docs",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "paths": paths,
+                    "strict": { "type": "boolean", "description": "Fail on warnings too" },
+                    "pub_items": { "type": "boolean", "description": "Require /// docs on public items (default: validate_docs.require_pub_item_docs)" },
+                    "check_references": { "type": "boolean", "description": "Flag symbols in //! docs that no longer exist (default: validate_docs.check_references)" },
+                    "no_baseline": { "type": "boolean", "description": "Report issues listed in the docs baseline too" },
+                },
+                "additionalProperties": false,
+            },
+        }),
+    ]
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RunCommandArgs {
+    command: String,
+    #[serde(default)]
+    working_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StatusArgs {
+    path: PathBuf,
+    #[serde(default)]
+    no_cache: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DiffArgs {
+    path: PathBuf,
+    #[serde(default)]
+    worktree: Option<String>,
+    #[serde(default)]
+    summary: bool,
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RemoveDebugArgs {
+    #[serde(default)]
+    paths: Option<Vec<PathBuf>>,
+    #[serde(default)]
+    macros: Option<Vec<String>>,
+    #[serde(default)]
+    since: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ValidateDocsArgs {
+    #[serde(default)]
+    paths: Option<Vec<PathBuf>>,
+    #[serde(default)]
+    strict: bool,
+    #[serde(default)]
+    pub_items: Option<bool>,
+    #[serde(default)]
+    check_references: Option<bool>,
+    #[serde(default)]
+    no_baseline: bool,
+}
+
+fn arguments_for<T: DeserializeOwned>(tool: &str, arguments: Value) -> Result<T, RpcError> {
+    serde_json::from_value(arguments)
+        .map_err(|e| RpcError::invalid_params(format!("Invalid arguments for {}: {}", tool, e)))
+}
+
+/// A JSON-RPC error to answer a request with
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn invalid_params(message: String) -> Self {
+        Self { code: INVALID_PARAMS, message }
+    }
+}
+
+fn error_response(id: Value, code: i64, message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn write_message(out: &mut dyn Write, message: &Value) -> std::io::Result<()> {
+    writeln!(out, "{}", message)?;
+    out.flush()
+}
+
+/// `notifications/progress` for one `tools/call`, sent only if the request
+/// asked for them with a progress token
+struct Progress<'a> {
+    token: Option<Value>,
+    out: &'a mut dyn Write,
+}
+
+impl Progress<'_> {
+    fn report(&mut self, progress: usize, total: usize, message: &str) {
+        let Some(token) = &self.token else {
+            return;
+        };
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/progress",
+            "params": { "progressToken": token, "progress": progress, "total": total, "message": message },
+        });
+        // A closed output also fails the response, which ends the server
+        let _ = write_message(self.out, &notification);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::init_repo;
+    use std::fs;
+
+    /// Responses and notifications written for `lines`
+    fn exchange(server: &McpServer, lines: &[Value]) -> Vec<Value> {
+        let input: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+        let mut out = Vec::new();
+        server.serve(input.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_handshake_and_protocol_errors() {
+        let server = McpServer::new(Config::default());
+        let responses = exchange(&server, &[
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": { "protocolVersion": "2025-03-26", "capabilities": {} } }),
+            json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }),
+            json!({ "jsonrpc": "2.0", "id": 3, "method": "resources/list" }),
+            json!({ "jsonrpc": "2.0", "id": 4, "method": "tools/call", "params": { "name": "nope" } }),
+            json!({ "jsonrpc": "2.0", "id": 5, "method": "tools/call", "params": { "name": "run_command", "arguments": { "cmd": "ls" } } }),
+        ]);
+        // No response to the notification
+        assert_eq!(responses.len(), 5);
+        assert_eq!(responses[0]["result"]["protocolVersion"], "2025-03-26");
+        assert_eq!(responses[0]["result"]["serverInfo"]["name"], "autodebugger");
+        let names: Vec<&str> = responses[1]["result"]["tools"].as_array().unwrap().iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["run_command", "worktree_status", "diff", "remove_debug_check", "validate_docs"]);
+        assert_eq!(responses[2]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(responses[3]["error"]["code"], INVALID_PARAMS);
+        assert!(responses[4]["error"]["message"].as_str().unwrap().contains("unknown field `cmd`"));
+
+        let mut out = Vec::new();
+        let parse_error = server.handle_message("{not json", &mut out).unwrap();
+        assert_eq!((parse_error["id"].clone(), parse_error["error"]["code"].clone()), (Value::Null, json!(PARSE_ERROR)));
+        assert_eq!(initialize(&json!({ "protocolVersion": "1999-01-01" }))["protocolVersion"], PROTOCOL_VERSIONS[0]);
+    }
+
+    #[test]
+    fn test_checks_report_progress_and_results() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("lib.rs");
+        fs::write(&file, "fn f() {\n    debug!(\"x\");\n}\n").unwrap();
+        let server = McpServer::new(Config::default());
+        let responses = exchange(&server, &[
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {
+                "name": "remove_debug_check",
+                "arguments": { "paths": [temp.path()] },
+                "_meta": { "progressToken": "t1" },
+            } }),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {
+                "name": "validate_docs",
+                "arguments": { "paths": [temp.path()], "no_baseline": true },
+            } }),
+            json!({ "jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {
+                "name": "remove_debug_check",
+                "arguments": { "paths": [temp.path().join("missing")] },
+            } }),
+        ]);
+        assert_eq!(responses.len(), 5);
+        assert_eq!(responses[0]["method"], "notifications/progress");
+        assert_eq!(responses[0]["params"]["progressToken"], "t1");
+        assert_eq!(responses[1]["params"]["progress"], responses[1]["params"]["total"]);
+
+        let check = &responses[2]["result"];
+        assert_eq!(check["isError"], false);
+        assert_eq!(check["structuredContent"]["passed"], false);
+        assert_eq!(check["structuredContent"]["report"]["total_calls_removed"], 1);
+        assert!(fs::read_to_string(&file).unwrap().contains("debug!"));
+        let text: Value = serde_json::from_str(check["content"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(text, check["structuredContent"]);
+
+        // Without a progress token, just the response
+        assert_eq!(responses[3]["id"], 2);
+        assert_eq!(responses[3]["result"]["structuredContent"]["passed"], true);
+        assert_eq!(responses[3]["result"]["structuredContent"]["report"]["files_scanned"], 1);
+
+        assert_eq!(responses[4]["result"]["isError"], true);
+        assert!(responses[4]["result"]["content"][0]["text"].as_str().unwrap().contains("Path does not exist"));
+    }
+
+    #[test]
+    fn test_diff_is_an_object() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().join("main");
+        init_repo(&repo, &[("a.txt", "one\n")]);
+        fs::write(repo.join("a.txt"), "two\n").unwrap();
+
+        let server = McpServer::new(Config::default());
+        let call = |id: u32, summary: bool| json!({ "jsonrpc": "2.0", "id": id, "method": "tools/call", "params": {
+            "name": "diff",
+            "arguments": { "path": repo, "worktree": "main", "summary": summary },
+        } });
+        let responses = exchange(&server, &[call(1, false), call(2, true)]);
+
+        let diff = &responses[0]["result"]["structuredContent"];
+        assert_eq!(diff["worktrees"].as_array().unwrap().len(), 1, "{}", responses[0]);
+        assert_eq!(diff["worktrees"][0]["files"][0]["path"], "a.txt");
+        let summary = &responses[1]["result"]["structuredContent"];
+        assert_eq!(summary["worktrees"][0]["modified"], json!(["a.txt"]), "{}", responses[1]);
+    }
+}
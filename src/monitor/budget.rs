@@ -0,0 +1,122 @@
+/// Estimates how many tokens a piece of text will occupy in an LLM context
+///
+/// Implement this to plug in a real tokenizer; `CharEstimator` is the default.
+pub trait TokenEstimator {
+    fn estimate(&self, text: &str) -> usize;
+}
+
+/// Rough estimate of one token per four characters
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CharEstimator;
+
+impl TokenEstimator for CharEstimator {
+    fn estimate(&self, text: &str) -> usize {
+        text.chars().count().div_ceil(4)
+    }
+}
+
+const SECTION_SEPARATOR: &str = "\n\n";
+
+/// Concatenate sections in priority order without exceeding `budget` tokens
+///
+/// Sections that fit are kept whole. The first section that doesn't fit keeps as
+/// many leading lines as possible followed by a `[truncated N lines]` marker, and
+/// every later section is reduced to its marker alone (or dropped if even the
+/// marker no longer fits). The result is deterministic for a given input.
+pub fn fit_to_budget(sections: &[String], budget: usize, estimator: &dyn TokenEstimator) -> String {
+    let mut output = String::new();
+    
+    for section in sections.iter().filter(|s| !s.is_empty()) {
+        let prefix = if output.is_empty() {
+            output.clone()
+        } else {
+            format!("{}{}", output, SECTION_SEPARATOR)
+        };
+        
+        let whole = format!("{}{}", prefix, section);
+        if estimator.estimate(&whole) <= budget {
+            output = whole;
+            continue;
+        }
+        
+        let lines: Vec<&str> = section.lines().collect();
+        let with_lines = |kept: usize| {
+            let marker = format!("[truncated {} lines]", lines.len() - kept);
+            if kept == 0 {
+                format!("{}{}", prefix, marker)
+            } else {
+                format!("{}{}\n{}", prefix, lines[..kept].join("\n"), marker)
+            }
+        };
+        
+        if estimator.estimate(&with_lines(0)) > budget {
+            continue;
+        }
+        
+        // Largest number of leading lines that still fits alongside the marker
+        let (mut low, mut high) = (0, lines.len());
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            if estimator.estimate(&with_lines(mid)) <= budget {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        output = with_lines(low);
+    }
+    
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_sections() -> Vec<String> {
+        let diff: Vec<String> = (0..200).map(|i| format!("+ added line number {}", i)).collect();
+        vec![
+            "# Status\nmain: active (3 files changed)".to_string(),
+            "# Tasks\n1. Fix the parser\n2. Write docs".to_string(),
+            format!("# Diffs\n{}", diff.join("\n")),
+        ]
+    }
+
+    #[test]
+    fn test_output_never_exceeds_budget() {
+        let sections = sample_sections();
+        for budget in [0, 5, 12, 20, 50, 100, 500, 5000] {
+            let output = fit_to_budget(&sections, budget, &CharEstimator);
+            assert!(
+                CharEstimator.estimate(&output) <= budget,
+                "budget {} exceeded: {}",
+                budget,
+                CharEstimator.estimate(&output)
+            );
+        }
+    }
+
+    #[test]
+    fn test_truncation_keeps_priority_order_and_marks_cuts() {
+        let output = fit_to_budget(&sample_sections(), 100, &CharEstimator);
+        assert!(output.starts_with("# Status"));
+        assert!(output.contains("2. Write docs"));
+        assert!(output.contains("+ added line number 0"));
+        assert!(output.contains("[truncated "));
+        assert!(!output.contains("+ added line number 199"));
+    }
+
+    #[test]
+    fn test_later_sections_reduced_to_markers() {
+        let output = fit_to_budget(&sample_sections(), 27, &CharEstimator);
+        assert!(output.starts_with("# Status"));
+        assert!(output.ends_with("[truncated 201 lines]"));
+    }
+
+    #[test]
+    fn test_everything_fits_unchanged() {
+        let sections = sample_sections();
+        let output = fit_to_budget(&sections, usize::MAX, &CharEstimator);
+        assert_eq!(output, sections.join(SECTION_SEPARATOR));
+    }
+}
@@ -0,0 +1,321 @@
+//! On-disk cache of worktree status
+//!
+//! Collecting status runs several git commands per worktree, which adds up on a
+//! large mono-repo. The cache stores each worktree's last `WorktreeStatus` together
+//! with a fingerprint that costs a single `git status`:
+//!
+//! - the HEAD commit, resolved by reading `HEAD`, loose refs, and `packed-refs`
+//!   from the git directory (following `.git` files and `commondir` for linked
+//!   worktrees)
+//! - modification times of the index, the git directory, `FETCH_HEAD`,
+//!   `packed-refs`, the stash reflog, and remote-tracking refs, which cover
+//!   staging, in-progress operations, stashes, and upstream moves
+//! - `git status --porcelain --untracked-files=all`, plus the size and
+//!   modification time of each file it lists, so editing an already modified
+//!   file changes the fingerprint too
+//! - the modification time of `CLAUDE.local.md`, which is usually ignored by git
+//!
+//! If the fingerprint matches, the cached status is reused and only the relative
+//! "last change" text is refreshed. Files git ignores are never looked at. The
+//! cache lives in `.autodebugger/status_cache.json` in the workspace, and that
+//! directory gets a `.gitignore` so it never shows up as a change itself.
+//!
+//! Fingerprints are SHA-256 digests ([`StableHasher`]), so they stay comparable
+//! across Rust releases. Writes go to a temporary file that is then renamed over
+//! the cache, so a crash or a concurrent reader never sees a partial file.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+
+use crate::monitor::WorktreeStatus;
+use crate::remove_debug::git_output;
+
+/// Directory in the workspace holding autodebugger state
+pub const CACHE_DIR: &str = ".autodebugger";
+
+/// Default cache location for a workspace
+pub fn default_cache_path(workspace: &Path) -> PathBuf {
+    workspace.join(CACHE_DIR).join("status_cache.json")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    fingerprint: String,
+    status: WorktreeStatus,
+}
+
+/// Cached statuses keyed by worktree path
+#[derive(Debug, Default)]
+pub struct StatusCache {
+    path: PathBuf,
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+impl StatusCache {
+    /// Load the cache, starting empty if the file is missing or unreadable
+    pub fn load(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { path, entries }
+    }
+
+    /// Cached status for a worktree if its fingerprint still matches
+    pub fn get(&self, worktree: &Path, fingerprint: &str) -> Option<WorktreeStatus> {
+        let entry = self.entries.get(worktree)?;
+        if entry.fingerprint != fingerprint {
+            return None;
+        }
+        let mut status = entry.status.clone();
+        status.last_change_relative = status.last_change.map(|date| relative_time(date, Utc::now()));
+        Some(status)
+    }
+
+    pub fn insert(&mut self, worktree: PathBuf, fingerprint: String, status: WorktreeStatus) {
+        self.entries.insert(worktree, CacheEntry { fingerprint, status });
+    }
+
+    /// Drop entries for worktrees that no longer exist in the workspace
+    pub fn retain(&mut self, worktrees: &[PathBuf]) {
+        self.entries.retain(|path, _| worktrees.contains(path));
+    }
+
+    /// Write the cache atomically (temporary file, then rename)
+    pub fn save(&self) -> Result<()> {
+        write_cache_file(&self.path, &serde_json::to_string(&self.entries)?)
+    }
+}
+
+/// Write a file in the cache directory atomically (temporary file, then rename),
+/// creating the directory and its `.gitignore` first
+pub(crate) fn write_cache_file(path: &Path, contents: &str) -> Result<()> {
+    let dir = path.parent().context("Cache path has no parent directory")?;
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create {}", dir.display()))?;
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() {
+        fs::write(&gitignore, "*\n")
+            .with_context(|| format!("Failed to write {}", gitignore.display()))?;
+    }
+
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, contents)
+        .with_context(|| format!("Failed to write {}", temp.display()))?;
+    fs::rename(&temp, path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+/// Fingerprint a worktree's HEAD, refs, and `git status`
+///
+/// Returns None if the git directory cannot be located or `git status` fails,
+/// in which case the worktree is never served from cache.
+pub fn fingerprint(worktree: &Path) -> Option<String> {
+    let git_dir = resolve_git_dir(worktree)?;
+    let common_dir = fs::read_to_string(git_dir.join("commondir"))
+        .map(|dir| git_dir.join(dir.trim()))
+        .unwrap_or_else(|_| git_dir.clone());
+    // Before the index mtime is read, and without refreshing the index
+    let status = git_output(
+        worktree,
+        &["--no-optional-locks", "-c", "core.quotePath=false", "status", "--porcelain", "--untracked-files=all"],
+    ).ok()?;
+
+    let mut hasher = StableHasher::default();
+    hasher.update(read_head(&git_dir, &common_dir));
+    for path in [
+        git_dir.join("index"),
+        git_dir.clone(),
+        common_dir.join("FETCH_HEAD"),
+        common_dir.join("packed-refs"),
+        common_dir.join("logs/refs/stash"),
+    ] {
+        hasher.update(mtime(&path));
+    }
+    // Pushes and fetches rewrite remote-tracking refs, which moves ahead/behind
+    for entry in WalkDir::new(common_dir.join("refs/remotes")).sort_by_file_name().into_iter().filter_map(|e| e.ok()) {
+        hasher.update(entry.path().to_string_lossy().as_bytes());
+        hasher.update(mtime(entry.path()));
+    }
+
+    hasher.update(&status);
+    for path in status_paths(&status) {
+        let path = worktree.join(path);
+        let size = fs::metadata(&path).map(|metadata| metadata.len()).ok();
+        hasher.update(format!("{:?}", size));
+        hasher.update(mtime(&path));
+    }
+    hasher.update(mtime(&worktree.join("CLAUDE.local.md")));
+
+    Some(hasher.finish())
+}
+
+/// Paths listed by `git status --porcelain`, the new name for renames
+pub(crate) fn status_paths(status: &str) -> impl Iterator<Item = &str> {
+    status.lines().map(|line| {
+        // "XY path", or "XY old -> new" for renames
+        let path = line.get(3..).unwrap_or_default();
+        path.rsplit(" -> ").next().unwrap_or(path)
+    })
+}
+
+/// SHA-256 of everything fed to it, as 16 hex digits
+///
+/// Unlike `DefaultHasher`, whose algorithm may change between Rust releases,
+/// the same input gives the same digest everywhere, so cache files written by
+/// one build stay valid for the next. Each input is length-prefixed, so
+/// `"ab", "c"` and `"a", "bc"` differ.
+#[derive(Default)]
+pub(crate) struct StableHasher(Sha256);
+
+impl StableHasher {
+    pub(crate) fn update(&mut self, bytes: impl AsRef<[u8]>) {
+        let bytes = bytes.as_ref();
+        self.0.update((bytes.len() as u64).to_le_bytes());
+        self.0.update(bytes);
+    }
+
+    pub(crate) fn finish(self) -> String {
+        self.0.finalize()[..8].iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
+
+/// The git directory for a worktree: `.git` itself, or where a `.git` file points
+fn resolve_git_dir(worktree: &Path) -> Option<PathBuf> {
+    let dot_git = worktree.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let content = fs::read_to_string(&dot_git).ok()?;
+    let target = content.trim().strip_prefix("gitdir:")?.trim();
+    Some(worktree.join(target))
+}
+
+/// HEAD as a commit id, or the symbolic ref for an unborn branch
+fn read_head(git_dir: &Path, common_dir: &Path) -> String {
+    let head = fs::read_to_string(git_dir.join("HEAD")).unwrap_or_default();
+    let head = head.trim();
+    let Some(reference) = head.strip_prefix("ref:").map(str::trim) else {
+        return head.to_string();
+    };
+
+    for dir in [git_dir, common_dir] {
+        if let Ok(sha) = fs::read_to_string(dir.join(reference)) {
+            return sha.trim().to_string();
+        }
+    }
+    let packed = fs::read_to_string(common_dir.join("packed-refs")).unwrap_or_default();
+    packed.lines()
+        .find_map(|line| line.strip_suffix(reference)?.strip_suffix(' ').map(str::to_string))
+        .unwrap_or_else(|| format!("unborn:{}", reference))
+}
+
+/// Modification time as "seconds.nanoseconds", or empty if it can't be read
+fn mtime(path: &Path) -> String {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|since| format!("{}.{:09}", since.as_secs(), since.subsec_nanos()))
+        .unwrap_or_default()
+}
+
+/// Approximation of git's relative date format ("3 hours ago")
+pub fn relative_time(then: DateTime<FixedOffset>, now: DateTime<Utc>) -> String {
+    let seconds = (now - then.with_timezone(&Utc)).num_seconds().max(0);
+    let (value, unit) = match seconds {
+        s if s < 90 => (s, "second"),
+        s if s < 90 * 60 => ((s + 30) / 60, "minute"),
+        s if s < 36 * 3600 => ((s + 1800) / 3600, "hour"),
+        s if s < 14 * 86400 => ((s + 43200) / 86400, "day"),
+        s if s < 70 * 86400 => ((s + 3 * 86400) / (7 * 86400), "week"),
+        s if s < 365 * 86400 => ((s + 15 * 86400) / (30 * 86400), "month"),
+        s => ((s + 182 * 86400) / (365 * 86400), "year"),
+    };
+    format!("{} {}{} ago", value, unit, if value == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::init_repo;
+
+    #[test]
+    fn test_relative_time() {
+        let now = DateTime::parse_from_rfc3339("2024-06-01T12:00:00+00:00").unwrap().with_timezone(&Utc);
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap();
+        assert_eq!(relative_time(at("2024-06-01T11:59:59+00:00"), now), "1 second ago");
+        assert_eq!(relative_time(at("2024-06-01T09:00:00+00:00"), now), "3 hours ago");
+        assert_eq!(relative_time(at("2024-06-01T13:00:00+02:00"), now), "60 minutes ago");
+        assert_eq!(relative_time(at("2024-05-29T12:00:00+00:00"), now), "3 days ago");
+    }
+
+    #[test]
+    fn test_stable_hasher_is_fixed_sha256() {
+        let mut hasher = StableHasher::default();
+        hasher.update("abc");
+        // sha256(3u64.to_le_bytes() ++ "abc"), truncated
+        assert_eq!(hasher.finish(), "ce91dc5eec0139ad");
+
+        let digest = |parts: &[&str]| {
+            let mut hasher = StableHasher::default();
+            parts.iter().for_each(|part| hasher.update(part));
+            hasher.finish()
+        };
+        assert_ne!(digest(&["ab", "c"]), digest(&["a", "bc"]));
+    }
+
+    #[test]
+    fn test_fingerprint_follows_git_status() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path();
+        init_repo(repo, &[(".gitignore", "target/\nCLAUDE.local.md\n"), ("file.txt", "base\n")]);
+
+        let clean = fingerprint(repo).unwrap();
+        assert_eq!(fingerprint(repo).unwrap(), clean);
+        // Ignored build output is not looked at
+        fs::create_dir(repo.join("target")).unwrap();
+        fs::write(repo.join("target/out"), "built").unwrap();
+        assert_eq!(fingerprint(repo).unwrap(), clean);
+
+        fs::write(repo.join("file.txt"), "one\n").unwrap();
+        let modified = fingerprint(repo).unwrap();
+        assert_ne!(modified, clean);
+        // Still " M file.txt", but the file changed again
+        fs::write(repo.join("file.txt"), "two longer\n").unwrap();
+        assert_ne!(fingerprint(repo).unwrap(), modified);
+
+        let before_tasks = fingerprint(repo).unwrap();
+        fs::write(repo.join("CLAUDE.local.md"), "## Tasks\n- [ ] one\n").unwrap();
+        assert_ne!(fingerprint(repo).unwrap(), before_tasks);
+    }
+
+    #[test]
+    fn test_save_is_atomic_and_round_trips() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = default_cache_path(temp.path());
+        let mut cache = StatusCache::load(path.clone());
+        assert!(cache.entries.is_empty());
+
+        let status: WorktreeStatus = serde_json::from_str(
+            r#"{"name":"a","path":"a","status":"idle","last_change":null,"files_changed":0,
+            "current_task":null,"branch":"main","changes":[],"operation":null,"has_conflicts":false}"#,
+        ).unwrap();
+        cache.insert(PathBuf::from("a"), "f1".to_string(), status);
+        cache.save().unwrap();
+
+        assert!(!path.with_extension("json.tmp").exists());
+        assert!(temp.path().join(CACHE_DIR).join(".gitignore").exists());
+        let reloaded = StatusCache::load(path);
+        assert!(reloaded.get(Path::new("a"), "f1").is_some());
+        assert!(reloaded.get(Path::new("a"), "f2").is_none());
+    }
+}
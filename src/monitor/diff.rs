@@ -1,24 +1,185 @@
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::process::Command;
+use crate::monitor::worktree::Worktree;
 
-pub struct DiffTracker;
+pub struct DiffTracker {
+    include_submodules: bool,
+}
+
+/// Path filters for diffs, passed to git as glob pathspecs
+///
+/// Patterns are relative to the worktree root; use `**/` to match at any depth
+/// (e.g. `**/*.snap`). An empty filter matches everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiffFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl DiffFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Only show paths matching at least one of these globs
+    pub fn with_include(mut self, patterns: Vec<String>) -> Self {
+        self.include = patterns;
+        self
+    }
+    
+    /// Hide paths matching any of these globs
+    pub fn with_exclude(mut self, patterns: Vec<String>) -> Self {
+        self.exclude = patterns;
+        self
+    }
+    
+    /// Pathspec arguments to append after `--`
+    pub fn pathspecs(&self) -> Vec<String> {
+        let mut specs: Vec<String> = self.include.iter()
+            .map(|p| format!(":(glob){}", p))
+            .collect();
+        if specs.is_empty() && !self.exclude.is_empty() {
+            // Exclusions need something to subtract from
+            specs.push(":(glob)**".to_string());
+        }
+        specs.extend(self.exclude.iter().map(|p| format!(":(exclude,glob){}", p)));
+        specs
+    }
+}
+
+/// File-level summary of a worktree's uncommitted changes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiffSummary {
+    pub worktree: String,
+    pub added: Vec<String>,
+    pub modified: Vec<String>,
+    pub deleted: Vec<String>,
+    pub renamed: Vec<RenamedFile>,
+    /// Lines inserted in tracked files (from `git diff --numstat`)
+    pub insertions: usize,
+    /// Lines deleted in tracked files (from `git diff --numstat`)
+    pub deletions: usize,
+}
+
+/// One file's changes with hunks parsed from a zero-context unified diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileDiff {
+    /// New path, or the old path for a deleted file
+    pub path: String,
+    /// Previous path when the file was renamed
+    pub old_path: Option<String>,
+    /// Binary files carry no hunks
+    pub binary: bool,
+    pub hunks: Vec<Hunk>,
+}
+
+/// A single hunk; `added`/`removed` hold line contents without the +/- prefix
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// Structured diff for one worktree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorktreeDiff {
+    pub worktree: String,
+    pub files: Vec<FileDiff>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenamedFile {
+    pub from: String,
+    pub to: String,
+}
+
+impl DiffSummary {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.modified.is_empty()
+            && self.deleted.is_empty()
+            && self.renamed.is_empty()
+    }
+}
+
+/// Render summaries as a compact table followed by per-worktree file lists
+pub fn format_summary_table(summaries: &[DiffSummary]) -> String {
+    if summaries.is_empty() {
+        return "No worktrees found.".to_string();
+    }
+    
+    let name_width = summaries.iter()
+        .map(|s| s.worktree.len())
+        .chain(std::iter::once("Worktree".len()))
+        .max()
+        .unwrap_or(0);
+    
+    let mut out = format!(
+        "{:<width$}  {:>5}  {:>8}  {:>7}  {:>7}  {:>7}  {:>7}\n",
+        "Worktree", "Added", "Modified", "Deleted", "Renamed", "+Lines", "-Lines",
+        width = name_width
+    );
+    for s in summaries {
+        out.push_str(&format!(
+            "{:<width$}  {:>5}  {:>8}  {:>7}  {:>7}  {:>7}  {:>7}\n",
+            s.worktree, s.added.len(), s.modified.len(), s.deleted.len(), s.renamed.len(),
+            s.insertions, s.deletions,
+            width = name_width
+        ));
+    }
+    
+    for s in summaries.iter().filter(|s| !s.is_empty()) {
+        out.push_str(&format!("\n{}:\n", s.worktree));
+        for file in &s.added {
+            out.push_str(&format!("  A {}\n", file));
+        }
+        for file in &s.modified {
+            out.push_str(&format!("  M {}\n", file));
+        }
+        for file in &s.deleted {
+            out.push_str(&format!("  D {}\n", file));
+        }
+        for rename in &s.renamed {
+            out.push_str(&format!("  R {} -> {}\n", rename.from, rename.to));
+        }
+    }
+    
+    out.trim_end().to_string()
+}
 
 impl DiffTracker {
     pub fn new() -> Self {
-        Self
+        Self { include_submodules: false }
+    }
+    
+    /// Show the content changes inside submodules (`--submodule=diff`) instead of
+    /// just their commit pointers
+    pub fn with_include_submodules(mut self, include: bool) -> Self {
+        self.include_submodules = include;
+        self
+    }
+    
+    fn submodule_arg(&self) -> &'static str {
+        if self.include_submodules { "--submodule=diff" } else { "--submodule=short" }
     }
     
-    pub fn get_diff_for_worktree(&self, workspace_path: &Path, worktree_name: &str) -> Result<String> {
-        let worktree_path = workspace_path.join("worktrees").join(worktree_name);
+    pub fn get_diff_for_worktree(&self, worktree: &Worktree, filter: &DiffFilter) -> Result<String> {
+        let worktree_path = &worktree.path;
+        let worktree_name = &worktree.name;
         
         if !worktree_path.exists() {
             anyhow::bail!("Worktree not found: {}", worktree_name);
         }
         
         // Get both staged and unstaged changes
-        let staged = self.get_staged_diff(&worktree_path)?;
-        let unstaged = self.get_unstaged_diff(&worktree_path)?;
+        let staged = self.get_staged_diff(worktree_path, filter)?;
+        let unstaged = self.get_unstaged_diff(worktree_path, filter)?;
         
         let mut result = format!("# Diff for worktree: {}\n\n", worktree_name);
         
@@ -41,106 +202,377 @@ impl DiffTracker {
         Ok(result)
     }
     
-    pub fn get_all_diffs(&self, workspace_path: &Path) -> Result<String> {
-        let worktrees_dir = workspace_path.join("worktrees");
-        if !worktrees_dir.exists() {
+    pub fn get_all_diffs(&self, worktrees: &[Worktree], filter: &DiffFilter) -> Result<String> {
+        if worktrees.is_empty() {
             return Ok("No worktrees found.".to_string());
         }
         
         let mut all_diffs = Vec::new();
         
-        for entry in std::fs::read_dir(&worktrees_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            
-            if path.is_dir() && path.join(".git").exists() {
-                let name = path.file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("unknown");
-                    
-                match self.get_diff_for_worktree(workspace_path, name) {
-                    Ok(diff) => all_diffs.push(diff),
-                    Err(e) => all_diffs.push(format!("# Error getting diff for {}: {}\n", name, e)),
-                }
+        for worktree in worktrees {
+            match self.get_diff_for_worktree(worktree, filter) {
+                Ok(diff) => all_diffs.push(diff),
+                Err(e) => all_diffs.push(format!("# Error getting diff for {}: {}\n", worktree.name, e)),
             }
         }
         
         Ok(all_diffs.join("\n---\n\n"))
     }
     
-    // Summary mode implementation pending
-    // Should return: Added files, Modified files, Deleted files, Line counts
-    pub fn get_diff_summary(&self, workspace_path: &Path, worktree_name: &str) -> Result<String> {
-        let worktree_path = workspace_path.join("worktrees").join(worktree_name);
+    /// All tracked changes (staged and unstaged) relative to HEAD as structured hunks
+    pub fn get_structured_diff(&self, worktree: &Worktree, filter: &DiffFilter) -> Result<Vec<FileDiff>> {
+        if !worktree.path.exists() {
+            anyhow::bail!("Worktree not found: {}", worktree.name);
+        }
+        
+        let diff_args = |base: &'static [&'static str]| {
+            let mut args = vec!["-c", "core.quotePath=false", "diff"];
+            args.extend(base);
+            args.extend(["--unified=0", "-M", "--no-color", "--no-ext-diff", "--"]);
+            args
+        };
+        let output = Command::new("git")
+            .current_dir(&worktree.path)
+            .args(diff_args(&["HEAD"]))
+            .args(filter.pathspecs())
+            .output()
+            .context("Failed to get diff")?;
+            
+        let output = if output.status.success() {
+            output
+        } else {
+            // No HEAD yet (fresh repository): everything of interest is staged
+            Command::new("git")
+                .current_dir(&worktree.path)
+                .args(diff_args(&["--cached"]))
+                .args(filter.pathspecs())
+                .output()
+                .context("Failed to get diff")?
+        };
+        
+        Ok(parse_unified_diff(&String::from_utf8_lossy(&output.stdout)))
+    }
+    
+    /// Structured diffs for several worktrees, failing on the first error
+    pub fn get_all_structured_diffs(&self, worktrees: &[Worktree], filter: &DiffFilter) -> Result<Vec<WorktreeDiff>> {
+        worktrees.iter()
+            .map(|worktree| {
+                Ok(WorktreeDiff {
+                    worktree: worktree.name.clone(),
+                    files: self.get_structured_diff(worktree, filter)?,
+                })
+            })
+            .collect()
+    }
+    
+    /// Summarize added, modified, deleted, and renamed files plus line counts
+    pub fn get_diff_summary(&self, worktree: &Worktree, filter: &DiffFilter) -> Result<DiffSummary> {
+        let worktree_path = &worktree.path;
         
         if !worktree_path.exists() {
-            anyhow::bail!("Worktree not found: {}", worktree_name);
+            anyhow::bail!("Worktree not found: {}", worktree.name);
         }
         
         // Get file status
         let output = Command::new("git")
-            .current_dir(&worktree_path)
-            .args(&["status", "--porcelain"])
+            .current_dir(worktree_path)
+            .args(["status", "--porcelain", "--"])
+            .args(filter.pathspecs())
             .output()
             .context("Failed to run git status")?;
             
-        let status_lines = String::from_utf8_lossy(&output.stdout);
-        let mut added = Vec::new();
-        let mut modified = Vec::new();
-        let mut deleted = Vec::new();
-        
-        for line in status_lines.lines() {
-            if line.len() < 3 {
-                continue;
-            }
-            
-            let status = &line[0..2];
-            let file = &line[3..];
-            
-            match status {
-                "A " | " A" | "??" => added.push(file),
-                "M " | " M" | "MM" => modified.push(file),
-                "D " | " D" => deleted.push(file),
-                _ => {}
+        let mut summary = DiffSummary {
+            worktree: worktree.name.clone(),
+            ..DiffSummary::default()
+        };
+        
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            match parse_status_line(line) {
+                Some(FileStatus::Added(file)) => summary.added.push(file),
+                Some(FileStatus::Modified(file)) => summary.modified.push(file),
+                Some(FileStatus::Deleted(file)) => summary.deleted.push(file),
+                Some(FileStatus::Renamed(rename)) => summary.renamed.push(rename),
+                None => {}
             }
         }
         
-        let mut summary = Vec::new();
+        let (insertions, deletions) = parse_numstat(&self.get_numstat(worktree_path, filter)?);
+        summary.insertions = insertions;
+        summary.deletions = deletions;
         
-        if !added.is_empty() {
-            summary.push(format!("Added: {}", added.join(", ")));
-        }
-        if !modified.is_empty() {
-            summary.push(format!("Modified: {}", modified.join(", ")));
-        }
-        if !deleted.is_empty() {
-            summary.push(format!("Deleted: {}", deleted.join(", ")));
+        Ok(summary)
+    }
+    
+    /// Line counts for all tracked changes (staged and unstaged) relative to HEAD
+    fn get_numstat(&self, path: &Path, filter: &DiffFilter) -> Result<String> {
+        let output = Command::new("git")
+            .current_dir(path)
+            .args(["diff", "HEAD", "--numstat", "--"])
+            .args(filter.pathspecs())
+            .output()
+            .context("Failed to get diff numstat")?;
+            
+        if output.status.success() {
+            return Ok(String::from_utf8_lossy(&output.stdout).to_string());
         }
         
-        if summary.is_empty() {
-            Ok("No changes".to_string())
-        } else {
-            Ok(summary.join("\n"))
-        }
+        // No HEAD yet (fresh repository): everything of interest is staged
+        let output = Command::new("git")
+            .current_dir(path)
+            .args(["diff", "--cached", "--numstat", "--"])
+            .args(filter.pathspecs())
+            .output()
+            .context("Failed to get diff numstat")?;
+            
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
     
-    fn get_staged_diff(&self, path: &Path) -> Result<String> {
+    fn get_staged_diff(&self, path: &Path, filter: &DiffFilter) -> Result<String> {
         let output = Command::new("git")
             .current_dir(path)
-            .args(&["diff", "--cached"])
+            .args(["diff", "--cached", self.submodule_arg(), "--"])
+            .args(filter.pathspecs())
             .output()
             .context("Failed to get staged diff")?;
             
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
     
-    fn get_unstaged_diff(&self, path: &Path) -> Result<String> {
+    fn get_unstaged_diff(&self, path: &Path, filter: &DiffFilter) -> Result<String> {
         let output = Command::new("git")
             .current_dir(path)
-            .args(&["diff"])
+            .args(["diff", self.submodule_arg(), "--"])
+            .args(filter.pathspecs())
             .output()
             .context("Failed to get unstaged diff")?;
             
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
-}
\ No newline at end of file
+}
+
+/// Parse `git diff` output into per-file hunks
+///
+/// Intended for `--unified=0` output, but context lines (leading space) are
+/// tolerated and ignored.
+pub fn parse_unified_diff(diff: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    let mut in_hunk = false;
+    
+    for line in diff.lines() {
+        if let Some(header) = line.strip_prefix("diff --git ") {
+            // Provisional path; refined by ---/+++ or rename lines below
+            let path = header.rsplit_once(" b/").map(|(_, p)| p).unwrap_or(header);
+            files.push(FileDiff {
+                path: path.to_string(),
+                old_path: None,
+                binary: false,
+                hunks: Vec::new(),
+            });
+            in_hunk = false;
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        
+        if in_hunk {
+            if let Some(header) = line.strip_prefix("@@ -") {
+                if let Some(hunk) = parse_hunk_header(header) {
+                    file.hunks.push(hunk);
+                }
+            } else if let (Some(text), Some(hunk)) = (line.strip_prefix('+'), file.hunks.last_mut()) {
+                hunk.added.push(text.to_string());
+            } else if let (Some(text), Some(hunk)) = (line.strip_prefix('-'), file.hunks.last_mut()) {
+                hunk.removed.push(text.to_string());
+            }
+        } else if let Some(from) = line.strip_prefix("rename from ") {
+            file.old_path = Some(from.to_string());
+        } else if let Some(to) = line.strip_prefix("rename to ") {
+            file.path = to.to_string();
+        } else if let Some(path) = line.strip_prefix("+++ b/") {
+            file.path = path.to_string();
+        } else if let Some(path) = line.strip_prefix("--- a/") {
+            // Deleted files keep their old path ("+++ /dev/null" follows)
+            file.path = path.to_string();
+        } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+            file.binary = true;
+        } else if let Some(header) = line.strip_prefix("@@ -") {
+            in_hunk = true;
+            if let Some(hunk) = parse_hunk_header(header) {
+                file.hunks.push(hunk);
+            }
+        }
+    }
+    
+    files
+}
+
+/// Parse the `-a,b +c,d @@` part of a hunk header (counts default to 1)
+fn parse_hunk_header(header: &str) -> Option<Hunk> {
+    let mut parts = header.split_whitespace();
+    let (old_start, old_lines) = parse_hunk_range(parts.next()?)?;
+    let (new_start, new_lines) = parse_hunk_range(parts.next()?.strip_prefix('+')?)?;
+    Some(Hunk {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        added: Vec::new(),
+        removed: Vec::new(),
+    })
+}
+
+fn parse_hunk_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, lines)) => Some((start.parse().ok()?, lines.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+enum FileStatus {
+    Added(String),
+    Modified(String),
+    Deleted(String),
+    Renamed(RenamedFile),
+}
+
+/// Classify one line of `git status --porcelain` output
+fn parse_status_line(line: &str) -> Option<FileStatus> {
+    if line.len() < 4 {
+        return None;
+    }
+    
+    let status = &line[0..2];
+    let file = line[3..].to_string();
+    
+    if status.contains('R') {
+        let (from, to) = file.split_once(" -> ")?;
+        Some(FileStatus::Renamed(RenamedFile { from: from.to_string(), to: to.to_string() }))
+    } else if status.contains('D') {
+        Some(FileStatus::Deleted(file))
+    } else if status == "??" || status.contains('A') || status.contains('C') {
+        Some(FileStatus::Added(file))
+    } else if status.contains('M') || status.contains('T') || status.contains('U') {
+        Some(FileStatus::Modified(file))
+    } else {
+        None
+    }
+}
+
+/// Sum insertions and deletions from `git diff --numstat` output (binary files count as 0)
+fn parse_numstat(numstat: &str) -> (usize, usize) {
+    numstat.lines().fold((0, 0), |(ins, del), line| {
+        let mut parts = line.split('\t');
+        let added = parts.next().and_then(|n| n.parse::<usize>().ok()).unwrap_or(0);
+        let removed = parts.next().and_then(|n| n.parse::<usize>().ok()).unwrap_or(0);
+        (ins + added, del + removed)
+    })
+}
+
+impl Default for DiffTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::init_repo;
+
+    #[test]
+    fn test_parse_status_line_includes_renames() {
+        match parse_status_line("R  old.rs -> new.rs") {
+            Some(FileStatus::Renamed(rename)) => {
+                assert_eq!(rename.from, "old.rs");
+                assert_eq!(rename.to, "new.rs");
+            }
+            _ => panic!("rename not detected"),
+        }
+        assert!(matches!(parse_status_line("AM src/new.rs"), Some(FileStatus::Added(_))));
+        assert!(matches!(parse_status_line("?? notes.txt"), Some(FileStatus::Added(_))));
+        assert!(matches!(parse_status_line(" D gone.rs"), Some(FileStatus::Deleted(_))));
+        assert!(matches!(parse_status_line("MM lib.rs"), Some(FileStatus::Modified(_))));
+    }
+
+    #[test]
+    fn test_diff_filter_limits_paths() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().to_path_buf();
+        init_repo(&repo, &[("Cargo.lock", "a\n"), ("src/lib.rs", "a\n"), ("src/out.snap", "a\n")]);
+        for file in ["Cargo.lock", "src/lib.rs", "src/out.snap"] {
+            std::fs::write(repo.join(file), "b\n").unwrap();
+        }
+        let worktree = Worktree { name: "repo".to_string(), path: repo, ..Worktree::default() };
+        let tracker = DiffTracker::new();
+        
+        let excluded = DiffFilter::new().with_exclude(vec!["Cargo.lock".to_string(), "**/*.snap".to_string()]);
+        let summary = tracker.get_diff_summary(&worktree, &excluded).unwrap();
+        assert_eq!(summary.modified, vec!["src/lib.rs"]);
+        assert_eq!((summary.insertions, summary.deletions), (1, 1));
+        
+        let included = DiffFilter::new().with_include(vec!["**/*.snap".to_string()]);
+        let diff = tracker.get_diff_for_worktree(&worktree, &included).unwrap();
+        assert!(diff.contains("src/out.snap") && !diff.contains("src/lib.rs"));
+        
+        let nothing = DiffFilter::new().with_exclude(vec!["**".to_string()]);
+        let diff = tracker.get_diff_for_worktree(&worktree, &nothing).unwrap();
+        assert!(diff.contains("No changes detected."));
+    }
+
+    #[test]
+    fn test_parse_unified_diff() {
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs\n\
+                    index 1111111..2222222 100644\n\
+                    --- a/src/lib.rs\n\
+                    +++ b/src/lib.rs\n\
+                    @@ -3 +3,2 @@ fn main() {\n\
+                    -    old();\n\
+                    +    new();\n\
+                    +    --- not a header\n\
+                    @@ -9,0 +11 @@\n\
+                    +// added\n\
+                    diff --git a/before.rs b/after.rs\n\
+                    similarity index 90%\n\
+                    rename from before.rs\n\
+                    rename to after.rs\n\
+                    --- a/before.rs\n\
+                    +++ b/after.rs\n\
+                    @@ -1 +1 @@\n\
+                    -a\n\
+                    +b\n\
+                    diff --git a/gone.rs b/gone.rs\n\
+                    deleted file mode 100644\n\
+                    --- a/gone.rs\n\
+                    +++ /dev/null\n\
+                    @@ -1,2 +0,0 @@\n\
+                    -x\n\
+                    -y\n\
+                    diff --git a/logo.png b/logo.png\n\
+                    index 3333333..4444444 100644\n\
+                    Binary files a/logo.png and b/logo.png differ\n";
+        let files = parse_unified_diff(diff);
+        
+        assert_eq!(files.len(), 4);
+        assert_eq!(files[0].path, "src/lib.rs");
+        assert_eq!(files[0].hunks, vec![
+            Hunk { old_start: 3, old_lines: 1, new_start: 3, new_lines: 2,
+                   added: vec!["    new();".to_string(), "    --- not a header".to_string()],
+                   removed: vec!["    old();".to_string()] },
+            Hunk { old_start: 9, old_lines: 0, new_start: 11, new_lines: 1,
+                   added: vec!["// added".to_string()], removed: vec![] },
+        ]);
+        assert_eq!(files[1].path, "after.rs");
+        assert_eq!(files[1].old_path.as_deref(), Some("before.rs"));
+        assert_eq!(files[2].path, "gone.rs");
+        assert_eq!(files[2].hunks[0].removed, vec!["x", "y"]);
+        assert!(files[3].binary);
+        assert!(files[3].hunks.is_empty());
+    }
+
+    #[test]
+    fn test_parse_numstat_skips_binary() {
+        let numstat = "10\t2\tsrc/lib.rs\n-\t-\tlogo.png\n3\t0\tREADME.md\n";
+        assert_eq!(parse_numstat(numstat), (13, 2));
+    }
+}
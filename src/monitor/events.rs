@@ -0,0 +1,397 @@
+//! Monitor events for dashboards and other long-running consumers
+//!
+//! `MonitorEventStream` polls `Monitor::status()` on an interval and turns the
+//! difference between consecutive snapshots into `MonitorEvent`s. The first poll
+//! reports every worktree as `worktree_added`, so a consumer can build its initial
+//! state from the stream alone. Polls that produce no events are silent; the
+//! iterator blocks until something changes.
+//!
+//! Events serialize as internally tagged JSON objects (`{"type": "files_changed",
+//! ...}`), one per line in `monitor --watch --format jsonl`. The field names are
+//! part of the public interface and are pinned by a schema test.
+//!
+//! With the `async` feature, `MonitorEventStream::into_async` runs the poll loop on
+//! a background thread and exposes the events as a `futures_core::Stream`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::monitor::{GitOperation, Monitor, MonitorStatus};
+
+/// Something that changed in the workspace between two polls
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MonitorEvent {
+    /// A worktree appeared (or was seen for the first time)
+    WorktreeAdded {
+        worktree: String,
+        branch: String,
+        files: usize,
+        timestamp: String,
+    },
+    WorktreeRemoved {
+        worktree: String,
+        timestamp: String,
+    },
+    /// The set of changed files or their line counts moved
+    FilesChanged {
+        worktree: String,
+        files: usize,
+        insertions: usize,
+        deletions: usize,
+        timestamp: String,
+    },
+    BranchChanged {
+        worktree: String,
+        from: String,
+        to: String,
+        timestamp: String,
+    },
+    /// A merge, rebase, cherry-pick, or revert started or finished (`null`)
+    OperationChanged {
+        worktree: String,
+        operation: Option<GitOperation>,
+        timestamp: String,
+    },
+    /// Status collection failed, for one worktree or (with `worktree: null`) the whole scan
+    Error {
+        worktree: Option<String>,
+        error: String,
+        timestamp: String,
+    },
+}
+
+impl MonitorEvent {
+    /// One-line description for text output
+    pub fn describe(&self) -> String {
+        match self {
+            MonitorEvent::WorktreeAdded { worktree, branch, files, .. } => {
+                format!("{}: added ({}, {} files changed)", worktree, branch, files)
+            }
+            MonitorEvent::WorktreeRemoved { worktree, .. } => format!("{}: removed", worktree),
+            MonitorEvent::FilesChanged { worktree, files, insertions, deletions, .. } => {
+                format!("{}: {} files changed (+{} -{})", worktree, files, insertions, deletions)
+            }
+            MonitorEvent::BranchChanged { worktree, from, to, .. } => {
+                format!("{}: branch {} -> {}", worktree, from, to)
+            }
+            MonitorEvent::OperationChanged { worktree, operation: Some(operation), .. } => {
+                format!("{}: {} in progress", worktree, operation)
+            }
+            MonitorEvent::OperationChanged { worktree, operation: None, .. } => {
+                format!("{}: operation finished", worktree)
+            }
+            MonitorEvent::Error { worktree: Some(worktree), error, .. } => format!("{}: error ({})", worktree, error),
+            MonitorEvent::Error { worktree: None, error, .. } => format!("error ({})", error),
+        }
+    }
+}
+
+/// Events describing how `next` differs from `previous`, ordered by worktree name
+///
+/// Without a previous snapshot every worktree is reported as added.
+pub fn diff_statuses(previous: Option<&MonitorStatus>, next: &MonitorStatus) -> Vec<MonitorEvent> {
+    let timestamp = &next.timestamp;
+    let mut events = Vec::new();
+
+    let mut names: Vec<&String> = next.worktrees.keys().collect();
+    names.sort();
+    for name in names {
+        let current = &next.worktrees[name];
+        let insertions = current.changes.iter().map(|c| c.insertions).sum();
+        let deletions = current.changes.iter().map(|c| c.deletions).sum();
+
+        let Some(before) = previous.and_then(|p| p.worktrees.get(name)) else {
+            events.push(MonitorEvent::WorktreeAdded {
+                worktree: name.clone(),
+                branch: current.branch.clone(),
+                files: current.files_changed,
+                timestamp: timestamp.clone(),
+            });
+            continue;
+        };
+
+        if before.branch != current.branch {
+            events.push(MonitorEvent::BranchChanged {
+                worktree: name.clone(),
+                from: before.branch.clone(),
+                to: current.branch.clone(),
+                timestamp: timestamp.clone(),
+            });
+        }
+        if before.operation != current.operation {
+            events.push(MonitorEvent::OperationChanged {
+                worktree: name.clone(),
+                operation: current.operation,
+                timestamp: timestamp.clone(),
+            });
+        }
+        if before.changes != current.changes {
+            events.push(MonitorEvent::FilesChanged {
+                worktree: name.clone(),
+                files: current.files_changed,
+                insertions,
+                deletions,
+                timestamp: timestamp.clone(),
+            });
+        }
+    }
+
+    if let Some(previous) = previous {
+        let mut removed: Vec<&String> = previous.worktrees.keys()
+            .filter(|name| !next.worktrees.contains_key(*name))
+            .collect();
+        removed.sort();
+        events.extend(removed.into_iter().map(|name| MonitorEvent::WorktreeRemoved {
+            worktree: name.clone(),
+            timestamp: timestamp.clone(),
+        }));
+    }
+
+    // Only report an error when it first appears or its message changes
+    for error in &next.errors {
+        let already_reported = previous.is_some_and(|p| p.errors.contains(error));
+        if !already_reported {
+            events.push(MonitorEvent::Error {
+                worktree: Some(error.name.clone()),
+                error: error.error.clone(),
+                timestamp: timestamp.clone(),
+            });
+        }
+    }
+
+    events
+}
+
+/// Blocking iterator over monitor events, polling at a fixed interval
+///
+/// The iterator never ends on its own; stop consuming it (or use `take`) to stop.
+pub struct MonitorEventStream {
+    monitor: Monitor,
+    interval: Duration,
+    previous: Option<MonitorStatus>,
+    pending: VecDeque<MonitorEvent>,
+    polled: bool,
+}
+
+impl MonitorEventStream {
+    pub fn new(monitor: Monitor, interval: Duration) -> Self {
+        Self {
+            monitor,
+            interval,
+            previous: None,
+            pending: VecDeque::new(),
+            polled: false,
+        }
+    }
+
+    fn poll(&mut self) {
+        if self.polled {
+            std::thread::sleep(self.interval);
+        }
+        self.polled = true;
+
+        match self.monitor.status() {
+            Ok(status) => {
+                self.pending.extend(diff_statuses(self.previous.as_ref(), &status));
+                self.previous = Some(status);
+            }
+            Err(e) => self.pending.push_back(MonitorEvent::Error {
+                worktree: None,
+                error: format!("{:#}", e),
+                timestamp: chrono::Utc::now().to_rfc3339(),
+            }),
+        }
+    }
+}
+
+impl Iterator for MonitorEventStream {
+    type Item = MonitorEvent;
+
+    fn next(&mut self) -> Option<MonitorEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+            self.poll();
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+pub use self::async_stream::AsyncMonitorEventStream;
+
+#[cfg(feature = "async")]
+mod async_stream {
+    use super::{MonitorEvent, MonitorEventStream};
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::sync::mpsc;
+
+    /// `futures_core::Stream` of monitor events, fed by a polling thread
+    ///
+    /// The thread exits after its next poll once the stream is dropped.
+    pub struct AsyncMonitorEventStream {
+        receiver: mpsc::Receiver<MonitorEvent>,
+    }
+
+    impl MonitorEventStream {
+        /// Move the poll loop onto a background thread and stream its events
+        pub fn into_async(self) -> AsyncMonitorEventStream {
+            let (sender, receiver) = mpsc::channel(64);
+            std::thread::spawn(move || {
+                for event in self {
+                    if sender.blocking_send(event).is_err() {
+                        break;
+                    }
+                }
+            });
+            AsyncMonitorEventStream { receiver }
+        }
+    }
+
+    impl futures_core::Stream for AsyncMonitorEventStream {
+        type Item = MonitorEvent;
+
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<MonitorEvent>> {
+            self.receiver.poll_recv(cx)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::init_repo;
+    use crate::monitor::{ChangeStatus, FileChange, WorktreeError, WorktreeStatus};
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn worktree(name: &str, branch: &str, changes: Vec<FileChange>) -> WorktreeStatus {
+        WorktreeStatus {
+            name: name.to_string(),
+            path: PathBuf::from(name),
+            status: if changes.is_empty() { "idle" } else { "active" }.to_string(),
+            last_change: None,
+            last_change_relative: None,
+            files_changed: changes.len(),
+            current_task: None,
+            tasks: Vec::new(),
+            branch: branch.to_string(),
+            ahead: None,
+            behind: None,
+            changes,
+            operation: None,
+            has_conflicts: false,
+            stash_count: 0,
+            unpushed_commits: 0,
+            submodules_changed: Vec::new(),
+        }
+    }
+
+    fn snapshot(timestamp: &str, worktrees: Vec<WorktreeStatus>, errors: Vec<WorktreeError>) -> MonitorStatus {
+        MonitorStatus {
+            worktrees: worktrees.into_iter().map(|w| (w.name.clone(), w)).collect(),
+            timestamp: timestamp.to_string(),
+            errors,
+        }
+    }
+
+    #[test]
+    fn test_event_schema() {
+        let t = "2024-06-01T10:00:00+00:00".to_string();
+        let cases = vec![
+            (
+                MonitorEvent::WorktreeAdded { worktree: "feat-x".into(), branch: "feat/x".into(), files: 2, timestamp: t.clone() },
+                json!({"type": "worktree_added", "worktree": "feat-x", "branch": "feat/x", "files": 2, "timestamp": t}),
+            ),
+            (
+                MonitorEvent::WorktreeRemoved { worktree: "feat-x".into(), timestamp: t.clone() },
+                json!({"type": "worktree_removed", "worktree": "feat-x", "timestamp": t}),
+            ),
+            (
+                MonitorEvent::FilesChanged { worktree: "feat-x".into(), files: 3, insertions: 10, deletions: 4, timestamp: t.clone() },
+                json!({"type": "files_changed", "worktree": "feat-x", "files": 3, "insertions": 10, "deletions": 4, "timestamp": t}),
+            ),
+            (
+                MonitorEvent::BranchChanged { worktree: "feat-x".into(), from: "a".into(), to: "b".into(), timestamp: t.clone() },
+                json!({"type": "branch_changed", "worktree": "feat-x", "from": "a", "to": "b", "timestamp": t}),
+            ),
+            (
+                MonitorEvent::OperationChanged { worktree: "feat-x".into(), operation: Some(GitOperation::Rebase), timestamp: t.clone() },
+                json!({"type": "operation_changed", "worktree": "feat-x", "operation": "Rebase", "timestamp": t}),
+            ),
+            (
+                MonitorEvent::Error { worktree: None, error: "boom".into(), timestamp: t.clone() },
+                json!({"type": "error", "worktree": null, "error": "boom", "timestamp": t}),
+            ),
+        ];
+
+        for (event, expected) in cases {
+            assert_eq!(serde_json::to_value(&event).unwrap(), expected);
+            assert_eq!(serde_json::from_value::<MonitorEvent>(expected).unwrap(), event);
+        }
+    }
+
+    #[test]
+    fn test_diff_statuses() {
+        let edit = |insertions| FileChange { path: "a.rs".into(), status: ChangeStatus::Modified, insertions, deletions: 0 };
+        let first = snapshot("t1", vec![worktree("a", "main", vec![]), worktree("gone", "old", vec![])], vec![]);
+        let error = WorktreeError { name: "broken".into(), path: PathBuf::from("broken"), error: "bad".into() };
+
+        let initial = diff_statuses(None, &first);
+        assert_eq!(initial.len(), 2);
+        assert!(matches!(&initial[0], MonitorEvent::WorktreeAdded { worktree, .. } if worktree == "a"));
+
+        let second = snapshot("t2", vec![worktree("a", "feat", vec![edit(3)])], vec![error.clone()]);
+        assert_eq!(diff_statuses(Some(&first), &second), vec![
+            MonitorEvent::BranchChanged { worktree: "a".into(), from: "main".into(), to: "feat".into(), timestamp: "t2".into() },
+            MonitorEvent::FilesChanged { worktree: "a".into(), files: 1, insertions: 3, deletions: 0, timestamp: "t2".into() },
+            MonitorEvent::WorktreeRemoved { worktree: "gone".into(), timestamp: "t2".into() },
+            MonitorEvent::Error { worktree: Some("broken".into()), error: "bad".into(), timestamp: "t2".into() },
+        ]);
+
+        // Same file count but different line counts is still a change; a repeated error is not
+        let third = snapshot("t3", vec![worktree("a", "feat", vec![edit(5)])], vec![error]);
+        assert_eq!(diff_statuses(Some(&second), &third), vec![
+            MonitorEvent::FilesChanged { worktree: "a".into(), files: 1, insertions: 5, deletions: 0, timestamp: "t3".into() },
+        ]);
+        assert!(diff_statuses(Some(&third), &third).is_empty());
+    }
+
+    #[test]
+    fn test_stream_reports_edits_between_polls() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().join("repo");
+        init_repo(&repo, &[("a.txt", "one\n")]);
+
+        let monitor = Monitor::new(repo.clone()).unwrap();
+        let mut stream = MonitorEventStream::new(monitor, Duration::from_millis(10));
+        assert!(matches!(
+            stream.next(),
+            Some(MonitorEvent::WorktreeAdded { worktree, files: 0, .. }) if worktree == "repo"
+        ));
+
+        std::fs::write(repo.join("a.txt"), "one\ntwo\n").unwrap();
+        assert!(matches!(
+            stream.next(),
+            Some(MonitorEvent::FilesChanged { worktree, files: 1, insertions: 1, .. }) if worktree == "repo"
+        ));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_stream_yields_initial_state() {
+        use futures_core::Stream;
+        use std::pin::Pin;
+
+        let temp = tempfile::tempdir().unwrap();
+        init_repo(temp.path(), &[]);
+        let monitor = Monitor::new(temp.path().to_path_buf()).unwrap();
+        let mut stream = MonitorEventStream::new(monitor, Duration::from_millis(10)).into_async();
+
+        let event = std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await;
+        assert!(matches!(event, Some(MonitorEvent::WorktreeAdded { .. })));
+    }
+}
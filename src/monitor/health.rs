@@ -0,0 +1,223 @@
+//! Per-worktree health score: how ready a worktree looks to merge
+//!
+//! Every worktree starts at 100 and loses points for each problem found, with the
+//! penalties taken from `monitor.health` in the config (`HealthWeights`):
+//!
+//! - no commit within `stale_after_days`
+//! - uncommitted files (per file, capped)
+//! - commits behind the upstream (per commit, capped), or no upstream at all
+//! - files also changed in other worktrees, more when line ranges intersect (capped)
+//! - unresolved conflicts, or a merge/rebase/cherry-pick/revert in progress
+//! - each enabled check that failed in the worktree's cached CI results, at its
+//!   `ci.weights` penalty (nothing when no results are cached)
+//!
+//! Each penalty adds a reason string so the score can be explained.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::ci::CheckResults;
+use crate::config::{CiConfig, HealthWeights};
+use crate::monitor::overlap::Overlap;
+use crate::monitor::WorktreeStatus;
+
+/// Health score for one worktree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorktreeHealth {
+    pub name: String,
+    /// 0 (needs attention) to 100 (ready to merge)
+    pub score: u8,
+    /// One entry per penalty applied, e.g. "3 uncommitted files (-6)"
+    pub reasons: Vec<String>,
+}
+
+/// Score one worktree against the overlaps found across the workspace and its
+/// cached CI check results, if any
+pub fn score(
+    status: &WorktreeStatus,
+    overlaps: &[Overlap],
+    checks: Option<&CheckResults>,
+    weights: &HealthWeights,
+    ci: &CiConfig,
+    now: DateTime<Utc>,
+) -> WorktreeHealth {
+    let mut penalties: Vec<(u32, String)> = Vec::new();
+
+    match status.last_change {
+        Some(date) => {
+            let days = (now - date.with_timezone(&Utc)).num_days();
+            if days >= weights.stale_after_days {
+                penalties.push((weights.stale, format!("no commits for {} days", days)));
+            }
+        }
+        None => penalties.push((weights.stale, "no commits".to_string())),
+    }
+
+    if status.files_changed > 0 {
+        let penalty = (status.files_changed as u32).saturating_mul(weights.uncommitted_file).min(weights.max_uncommitted);
+        penalties.push((penalty, plural(status.files_changed, "uncommitted file")));
+    }
+
+    match status.behind {
+        Some(0) => {}
+        Some(behind) => {
+            let penalty = (behind as u32).saturating_mul(weights.behind_commit).min(weights.max_behind);
+            penalties.push((penalty, format!("{} behind upstream", plural(behind, "commit"))));
+        }
+        None => penalties.push((weights.no_upstream, "no upstream".to_string())),
+    }
+
+    let shared: Vec<&Overlap> = overlaps.iter()
+        .filter(|o| o.worktrees.contains(&status.name))
+        .collect();
+    if !shared.is_empty() {
+        let intersecting = shared.iter().filter(|o| o.line_ranges_intersect).count();
+        let penalty = (intersecting as u32).saturating_mul(weights.overlap_conflict)
+            .saturating_add(((shared.len() - intersecting) as u32).saturating_mul(weights.overlap))
+            .min(weights.max_overlap);
+        let reason = if intersecting > 0 {
+            format!("{} shared with other worktrees ({} with overlapping lines)", plural(shared.len(), "file"), intersecting)
+        } else {
+            format!("{} shared with other worktrees", plural(shared.len(), "file"))
+        };
+        penalties.push((penalty, reason));
+    }
+
+    if status.has_conflicts {
+        penalties.push((weights.conflicts, "unresolved conflicts".to_string()));
+    }
+    if let Some(operation) = status.operation {
+        penalties.push((weights.operation, format!("{} in progress", operation)));
+    }
+    for check in checks.map(CheckResults::failed).unwrap_or_default() {
+        if ci.enabled_checks.contains(&check) {
+            penalties.push((ci.weights.get(check), format!("{} failed", check)));
+        }
+    }
+
+    let total: u32 = penalties.iter().map(|(penalty, _)| penalty).sum();
+    WorktreeHealth {
+        name: status.name.clone(),
+        score: 100u32.saturating_sub(total) as u8,
+        reasons: penalties.into_iter()
+            .filter(|(penalty, _)| *penalty > 0)
+            .map(|(penalty, reason)| format!("{} (-{})", reason, penalty))
+            .collect(),
+    }
+}
+
+/// Best score first, ties broken by name
+pub fn sort_leaderboard(health: &mut [WorktreeHealth]) {
+    health.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ci::{Check, CheckStatus};
+    use crate::monitor::{ChangeStatus, FileChange, GitOperation};
+    use std::path::PathBuf;
+
+    fn status(name: &str) -> WorktreeStatus {
+        WorktreeStatus {
+            name: name.to_string(),
+            path: PathBuf::from(name),
+            status: "idle".to_string(),
+            last_change: Some(DateTime::parse_from_rfc3339("2024-06-01T10:00:00+00:00").unwrap()),
+            last_change_relative: None,
+            files_changed: 0,
+            current_task: None,
+            tasks: Vec::new(),
+            branch: name.to_string(),
+            ahead: Some(1),
+            behind: Some(0),
+            changes: Vec::new(),
+            operation: None,
+            has_conflicts: false,
+            stash_count: 0,
+            unpushed_commits: 1,
+            submodules_changed: Vec::new(),
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-06-02T10:00:00+00:00").unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_clean_worktree_scores_full_marks() {
+        let health = score(&status("clean"), &[], None, &HealthWeights::default(), &CiConfig::default(), now());
+        assert_eq!(health, WorktreeHealth { name: "clean".to_string(), score: 100, reasons: Vec::new() });
+    }
+
+    #[test]
+    fn test_penalties_use_configured_weights() {
+        let mut messy = status("messy");
+        messy.files_changed = 3;
+        messy.changes = vec![FileChange { path: "a".into(), status: ChangeStatus::Modified, insertions: 1, deletions: 0 }; 3];
+        messy.behind = Some(20);
+        messy.operation = Some(GitOperation::Rebase);
+        let overlaps = vec![
+            Overlap { file: "a".into(), worktrees: vec!["messy".into(), "other".into()], line_ranges_intersect: true },
+            Overlap { file: "b".into(), worktrees: vec!["messy".into(), "other".into()], line_ranges_intersect: false },
+            Overlap { file: "c".into(), worktrees: vec!["x".into(), "y".into()], line_ranges_intersect: true },
+        ];
+
+        let health = score(&messy, &overlaps, None, &HealthWeights::default(), &CiConfig::default(), now());
+        assert_eq!(health.reasons, vec![
+            "3 uncommitted files (-6)",
+            "20 commits behind upstream (-30)",
+            "2 files shared with other worktrees (1 with overlapping lines) (-20)",
+            "REBASE in progress (-25)",
+        ]);
+        assert_eq!(health.score, 19);
+
+        let lenient = HealthWeights { operation: 0, max_behind: 5, ..HealthWeights::default() };
+        let health = score(&messy, &overlaps, None, &lenient, &CiConfig::default(), now());
+        assert_eq!(health.score, 69);
+        assert_eq!(health.reasons.len(), 3);
+    }
+
+    #[test]
+    fn test_failed_enabled_checks_cost_their_ci_weight() {
+        let checks = CheckResults {
+            cargo_check: CheckStatus::Pass,
+            cargo_test: CheckStatus::Fail("1 test(s) failed: t".to_string()),
+            clippy: CheckStatus::Pass,
+            debug_macros: CheckStatus::Pass,
+            documentation: CheckStatus::Pass,
+            fmt: CheckStatus::Fail("2 file(s) need formatting".to_string()),
+            audit: CheckStatus::Skipped("cargo-audit is not installed".to_string()),
+            durations: Default::default(),
+            wall_time: Default::default(),
+        };
+        let ci = CiConfig { enabled_checks: vec![Check::CargoTest], ..CiConfig::default() };
+
+        let health = score(&status("tested"), &[], Some(&checks), &HealthWeights::default(), &ci, now());
+        assert_eq!(health.reasons, vec!["cargo_test failed (-20)"]);
+        assert_eq!(health.score, 80);
+    }
+
+    #[test]
+    fn test_score_saturates_at_zero_and_sorts() {
+        let mut broken = status("broken");
+        broken.last_change = None;
+        broken.behind = None;
+        broken.has_conflicts = true;
+        broken.operation = Some(GitOperation::Merge);
+        let weights = HealthWeights { conflicts: 90, ..HealthWeights::default() };
+
+        let mut board = vec![
+            score(&broken, &[], None, &weights, &CiConfig::default(), now()),
+            score(&status("b"), &[], None, &weights, &CiConfig::default(), now()),
+            score(&status("a"), &[], None, &weights, &CiConfig::default(), now()),
+        ];
+        sort_leaderboard(&mut board);
+        let order: Vec<(&str, u8)> = board.iter().map(|h| (h.name.as_str(), h.score)).collect();
+        assert_eq!(order, vec![("a", 100), ("b", 100), ("broken", 0)]);
+    }
+}
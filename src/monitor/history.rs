@@ -0,0 +1,121 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+/// Default number of commits shown per worktree
+pub const DEFAULT_COMMIT_LIMIT: usize = 10;
+
+/// A single commit from a worktree's history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitInfo {
+    pub hash: String,
+    pub author: String,
+    pub relative_date: String,
+    pub subject: String,
+}
+
+/// Recent commits for one worktree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorktreeCommits {
+    pub worktree: String,
+    pub branch: Option<String>,
+    /// Newest first; empty for a branch with no commits yet
+    pub commits: Vec<CommitInfo>,
+}
+
+/// Read the last `limit` commits reachable from HEAD
+pub fn get_recent_commits(path: &Path, limit: usize) -> Result<Vec<CommitInfo>> {
+    // Unit separators keep subjects containing tabs or pipes intact
+    let output = Command::new("git")
+        .current_dir(path)
+        .args(["log", &format!("-{}", limit), "--format=%h%x1f%an%x1f%ar%x1f%s"])
+        .output()
+        .context("Failed to run git log")?;
+        
+    if !output.status.success() {
+        // A fresh branch has no HEAD to walk from
+        let head = Command::new("git")
+            .current_dir(path)
+            .args(["rev-parse", "--verify", "--quiet", "HEAD"])
+            .output()
+            .context("Failed to run git rev-parse")?;
+        if !head.status.success() {
+            return Ok(Vec::new());
+        }
+        anyhow::bail!(
+            "git log failed in {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\x1f');
+            Some(CommitInfo {
+                hash: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                relative_date: fields.next()?.to_string(),
+                subject: fields.next()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Render commit history as markdown suitable for an LLM prompt
+pub fn format_commits_markdown(history: &[WorktreeCommits]) -> String {
+    let sections: Vec<String> = history.iter()
+        .map(|entry| {
+            let mut section = match &entry.branch {
+                Some(branch) => format!("## Worktree: {} ({})\n\n", entry.worktree, branch),
+                None => format!("## Worktree: {}\n\n", entry.worktree),
+            };
+            if entry.commits.is_empty() {
+                section.push_str("_No commits yet_\n");
+            }
+            for commit in &entry.commits {
+                section.push_str(&format!(
+                    "- `{}` {} ({}, {})\n",
+                    commit.hash, commit.subject, commit.author, commit.relative_date
+                ));
+            }
+            section
+        })
+        .collect();
+    
+    sections.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::git;
+
+    #[test]
+    fn test_recent_commits_and_fresh_branch() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path();
+        git(repo, &["init", "-q"]);
+        assert!(get_recent_commits(repo, 10).unwrap().is_empty());
+        
+        for subject in ["first", "second | with pipe", "third"] {
+            git(repo, &["commit", "-q", "--allow-empty", "-m", subject]);
+        }
+        
+        let commits = get_recent_commits(repo, 2).unwrap();
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].subject, "third");
+        assert_eq!(commits[1].subject, "second | with pipe");
+        assert_eq!(commits[0].author, "t");
+        
+        let markdown = format_commits_markdown(&[
+            WorktreeCommits { worktree: "main".to_string(), branch: None, commits },
+            WorktreeCommits { worktree: "fresh".to_string(), branch: Some("new".to_string()), commits: vec![] },
+        ]);
+        assert!(markdown.contains("## Worktree: main\n"));
+        assert!(markdown.contains(" third (t, "));
+        assert!(markdown.contains("## Worktree: fresh (new)\n\n_No commits yet_"));
+    }
+}
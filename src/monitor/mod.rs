@@ -1,93 +1,807 @@
 use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::path::{Path, PathBuf};
 use std::collections::HashMap;
 
 pub mod worktree;
 pub mod diff;
+pub mod history;
+pub mod budget;
+pub mod report;
+pub mod overlap;
+pub mod tasks;
+pub mod cache;
+pub mod events;
+pub mod health;
 
-use worktree::WorktreeMonitor;
-use diff::DiffTracker;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use worktree::{Worktree, WorktreeMonitor};
+use diff::{DiffFilter, DiffSummary, DiffTracker, WorktreeDiff};
+use history::{WorktreeCommits, DEFAULT_COMMIT_LIMIT};
+use budget::{CharEstimator, TokenEstimator};
+use overlap::Overlap;
+use health::WorktreeHealth;
+use tasks::Task;
+use cache::StatusCache;
+use crate::ci::cache::{cache_key, CheckCache};
+use crate::config::{CiConfig, HealthWeights, MonitorConfig, TodosConfig};
+use crate::todos::{TodoScan, TodoScanner};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitorStatus {
     pub worktrees: HashMap<String, WorktreeStatus>,
     pub timestamp: String,
+    /// Worktrees whose status could not be collected
+    #[serde(default)]
+    pub errors: Vec<WorktreeError>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl MonitorStatus {
+    /// Worktrees ordered by most recent commit first; ones without commits come last
+    pub fn by_recency(&self) -> Vec<&WorktreeStatus> {
+        let mut worktrees: Vec<&WorktreeStatus> = self.worktrees.values().collect();
+        worktrees.sort_by(|a, b| {
+            b.last_change.cmp(&a.last_change).then_with(|| a.name.cmp(&b.name))
+        });
+        worktrees
+    }
+}
+
+/// A worktree that failed during status collection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorktreeError {
+    pub name: String,
+    pub path: PathBuf,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WorktreeStatus {
     pub name: String,
     pub path: PathBuf,
     pub status: String,
-    pub last_change: Option<String>,
+    /// Committer date of the last commit (RFC3339 when serialized)
+    ///
+    /// Older snapshots stored git's relative text here ("3 hours ago"); such values
+    /// deserialize as `None`.
+    #[serde(default, deserialize_with = "deserialize_last_change")]
+    pub last_change: Option<DateTime<FixedOffset>>,
+    /// Human-readable age of the last commit, e.g. "3 hours ago"
+    #[serde(default)]
+    pub last_change_relative: Option<String>,
+    /// Number of tracked files with changes (untracked files are not counted)
     pub files_changed: usize,
+    /// First incomplete task from `CLAUDE.local.md`
     pub current_task: Option<String>,
+    /// All tasks parsed from `CLAUDE.local.md`
+    #[serde(default)]
+    pub tasks: Vec<Task>,
     pub branch: String,
+    /// Commits not yet on the upstream branch (None without an upstream)
+    #[serde(default)]
+    pub ahead: Option<usize>,
+    /// Upstream commits not yet in this branch (None without an upstream)
+    #[serde(default)]
+    pub behind: Option<usize>,
+    pub changes: Vec<FileChange>,
+    /// Merge, rebase, cherry-pick, or revert currently in progress
+    pub operation: Option<GitOperation>,
+    /// Whether the index has unresolved (unmerged) entries
+    pub has_conflicts: bool,
+    /// Stashes created on this worktree's branch
+    #[serde(default)]
+    pub stash_count: usize,
+    /// Commits on this branch not yet on its upstream (0 without an upstream)
+    #[serde(default)]
+    pub unpushed_commits: usize,
+    /// Submodules with a moved commit or local changes (not counted in `changes`)
+    #[serde(default)]
+    pub submodules_changed: Vec<String>,
+}
+
+/// A multi-step git operation that has been started but not finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GitOperation {
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+}
+
+impl std::fmt::Display for GitOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            GitOperation::Merge => "MERGE",
+            GitOperation::Rebase => "REBASE",
+            GitOperation::CherryPick => "CHERRY-PICK",
+            GitOperation::Revert => "REVERT",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl WorktreeStatus {
+    /// Warning tags for states an agent must not commit through, e.g. `[REBASE IN PROGRESS]`
+    pub fn alerts(&self) -> Vec<String> {
+        let mut alerts = Vec::new();
+        if let Some(operation) = self.operation {
+            alerts.push(format!("[{} IN PROGRESS]", operation));
+        }
+        if self.has_conflicts {
+            alerts.push("[UNRESOLVED CONFLICTS]".to_string());
+        }
+        alerts
+    }
+    
+    /// Work that could be lost by destructive operations, e.g. `2 stashes`
+    pub fn unsaved_work(&self) -> Vec<String> {
+        let mut notices = Vec::new();
+        match self.stash_count {
+            0 => {}
+            1 => notices.push("1 stash".to_string()),
+            n => notices.push(format!("{} stashes", n)),
+        }
+        match self.unpushed_commits {
+            0 => {}
+            1 => notices.push("1 unpushed commit".to_string()),
+            n => notices.push(format!("{} unpushed commits", n)),
+        }
+        notices
+    }
+}
+
+/// Accept RFC3339 timestamps and map anything else (legacy relative strings) to `None`
+fn deserialize_last_change<'de, D>(deserializer: D) -> Result<Option<DateTime<FixedOffset>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Option<String> = Option::deserialize(deserializer)?;
+    Ok(value.and_then(|v| DateTime::parse_from_rfc3339(&v).ok()))
+}
+
+/// A single changed file in a worktree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChange {
+    pub path: String,
+    pub status: ChangeStatus,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangeStatus {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Untracked,
+}
+
+impl ChangeStatus {
+    /// Single-letter code in the style of `git status --short`
+    pub fn code(&self) -> &'static str {
+        match self {
+            ChangeStatus::Added => "A",
+            ChangeStatus::Modified => "M",
+            ChangeStatus::Deleted => "D",
+            ChangeStatus::Renamed => "R",
+            ChangeStatus::Untracked => "?",
+        }
+    }
 }
 
 pub struct Monitor {
     workspace_path: PathBuf,
     worktree_monitor: WorktreeMonitor,
     diff_tracker: DiffTracker,
+    diff_filter: DiffFilter,
+    commit_limit: usize,
+    todos_config: TodosConfig,
+    concurrency: usize,
+    cache_path: Option<PathBuf>,
+    health_weights: HealthWeights,
+    ci_cache_path: Option<PathBuf>,
+    ci_config: CiConfig,
 }
 
 impl Monitor {
     pub fn new(workspace_path: PathBuf) -> Result<Self> {
-        let worktree_monitor = WorktreeMonitor::new(workspace_path.clone())?;
-        let diff_tracker = DiffTracker::new();
+        Self::with_config(workspace_path, MonitorConfig::default())
+    }
+    
+    /// Create a monitor using the `monitor` section of the config
+    pub fn with_config(workspace_path: PathBuf, config: MonitorConfig) -> Result<Self> {
+        let concurrency = config.concurrency.max(1);
+        let diff_filter = DiffFilter::new().with_exclude(config.diff_exclude.clone());
+        let diff_tracker = DiffTracker::new().with_include_submodules(config.include_submodules);
+        let health_weights = config.health.clone();
+        let worktree_monitor = WorktreeMonitor::new(workspace_path.clone(), Some(config))?;
         
         Ok(Self {
             workspace_path,
             worktree_monitor,
             diff_tracker,
+            diff_filter,
+            commit_limit: DEFAULT_COMMIT_LIMIT,
+            todos_config: TodosConfig::default(),
+            concurrency,
+            cache_path: None,
+            health_weights,
+            ci_cache_path: None,
+            ci_config: CiConfig::default(),
         })
     }
     
+    /// Set the scanner settings used by the `todos` context
+    pub fn with_todos_config(mut self, config: TodosConfig) -> Self {
+        self.todos_config = config;
+        self
+    }
+    
+    /// Set the path filter applied to `diff`, `diff_summary`, and diff contexts
+    pub fn with_diff_filter(mut self, filter: DiffFilter) -> Self {
+        self.diff_filter = filter;
+        self
+    }
+    
+    /// Set how many commits per worktree the `commits` context includes
+    pub fn with_commit_limit(mut self, limit: usize) -> Self {
+        self.commit_limit = limit;
+        self
+    }
+    
+    pub fn workspace_path(&self) -> &Path {
+        &self.workspace_path
+    }
+    
+    /// Set how many worktrees are inspected concurrently (1 runs serially)
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+    
+    /// Reuse unchanged worktree statuses from a cache file between `status` calls
+    ///
+    /// See `cache::default_cache_path` for the conventional location.
+    pub fn with_status_cache(mut self, path: PathBuf) -> Self {
+        self.cache_path = Some(path);
+        self
+    }
+    
+    /// Count failed checks from the CI cache at `path` in health scores
+    ///
+    /// Results are used only while they are current under `config` (the same
+    /// key `ci` uses); see `ci::cache::default_ci_cache_path` for the location.
+    pub fn with_ci_cache(mut self, path: PathBuf, config: CiConfig) -> Self {
+        self.ci_cache_path = Some(path);
+        self.ci_config = config;
+        self
+    }
+    
+    /// Collect status for every worktree, running git in parallel up to the
+    /// concurrency cap; a failing worktree is recorded in `errors` instead of
+    /// aborting the scan
+    ///
+    /// With a status cache, worktrees whose fingerprint is unchanged are served
+    /// from the cache after a single `git status` each.
     pub fn status(&self) -> Result<MonitorStatus> {
         let worktrees = self.worktree_monitor.scan_worktrees()?;
+        let mut cache = self.cache_path.clone().map(StatusCache::load);
+        
+        // Fingerprints are taken before running git so edits made while status
+        // is being collected invalidate the entry next time
+        let fingerprints: Vec<Option<String>> = match cache {
+            Some(_) => worktrees.iter().map(|w| cache::fingerprint(&w.path)).collect(),
+            None => vec![None; worktrees.len()],
+        };
+        let cached: Vec<Option<WorktreeStatus>> = worktrees.iter().zip(&fingerprints)
+            .map(|(worktree, fingerprint)| {
+                cache.as_ref()?.get(&worktree.path, fingerprint.as_deref()?)
+            })
+            .collect();
+        
+        let stale: Vec<Worktree> = worktrees.iter().zip(&cached)
+            .filter(|(_, hit)| hit.is_none())
+            .map(|(worktree, _)| worktree.clone())
+            .collect();
+        let mut fresh = self.collect_statuses(&stale).into_iter();
+        let results: Vec<Result<WorktreeStatus>> = cached.into_iter()
+            .map(|hit| match hit {
+                Some(status) => Ok(status),
+                None => fresh.next().expect("one result per stale worktree"),
+            })
+            .collect();
+        
         let mut status_map = HashMap::new();
+        let mut errors = Vec::new();
         
-        for worktree in worktrees {
-            let name = worktree.name.clone();
-            let worktree_status = self.worktree_monitor.get_status(&worktree)?;
-            status_map.insert(name, worktree_status);
+        for ((worktree, result), fingerprint) in worktrees.iter().zip(results).zip(fingerprints) {
+            match result {
+                Ok(worktree_status) => {
+                    if let (Some(cache), Some(fingerprint)) = (&mut cache, fingerprint) {
+                        cache.insert(worktree.path.clone(), fingerprint, worktree_status.clone());
+                    }
+                    status_map.insert(worktree.name.clone(), worktree_status);
+                }
+                Err(e) => errors.push(WorktreeError {
+                    name: worktree.name.clone(),
+                    path: worktree.path.clone(),
+                    error: format!("{:#}", e),
+                }),
+            }
+        }
+        
+        if let Some(cache) = &mut cache {
+            cache.retain(&worktrees.iter().map(|w| w.path.clone()).collect::<Vec<_>>());
+            if let Err(e) = cache.save() {
+                tracing::warn!("Failed to save status cache: {:#}", e);
+            }
         }
         
         Ok(MonitorStatus {
             worktrees: status_map,
             timestamp: chrono::Utc::now().to_rfc3339(),
+            errors,
         })
     }
     
+    /// Status results in the same order as `worktrees`
+    fn collect_statuses(&self, worktrees: &[Worktree]) -> Vec<Result<WorktreeStatus>> {
+        let threads = self.concurrency.min(worktrees.len());
+        if threads <= 1 {
+            return worktrees.iter()
+                .map(|w| self.worktree_monitor.get_status(w))
+                .collect();
+        }
+        
+        // Workers pull indices from a shared counter so slow worktrees don't
+        // hold up a whole batch
+        let next = AtomicUsize::new(0);
+        let mut results: Vec<(usize, Result<WorktreeStatus>)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(worktree) = worktrees.get(index) else {
+                                break;
+                            };
+                            done.push((index, self.worktree_monitor.get_status(worktree)));
+                        }
+                        done
+                    })
+                })
+                .collect();
+            
+            handles.into_iter()
+                .flat_map(|h| h.join().unwrap_or_default())
+                .collect()
+        });
+        
+        results.sort_by_key(|(index, _)| *index);
+        let mut ordered: Vec<Option<Result<WorktreeStatus>>> =
+            worktrees.iter().map(|_| None).collect();
+        for (index, result) in results {
+            ordered[index] = Some(result);
+        }
+        
+        // A panicking worker loses its results; report those worktrees as errors
+        ordered.into_iter()
+            .zip(worktrees)
+            .map(|(result, worktree)| {
+                result.unwrap_or_else(|| {
+                    Err(anyhow::anyhow!("Status collection panicked for {}", worktree.name))
+                })
+            })
+            .collect()
+    }
+    
     pub fn diff(&self, worktree_name: Option<&str>) -> Result<String> {
         match worktree_name {
-            Some(name) => self.diff_tracker.get_diff_for_worktree(&self.workspace_path, name),
-            None => self.diff_tracker.get_all_diffs(&self.workspace_path),
+            Some(name) => {
+                let worktree = self.worktree_monitor.find_worktree(name)?;
+                self.diff_tracker.get_diff_for_worktree(&worktree, &self.diff_filter)
+            }
+            None => self.diff_tracker.get_all_diffs(&self.worktree_monitor.scan_worktrees()?, &self.diff_filter),
         }
     }
     
+    /// Structured hunks per worktree, sorted by worktree name
+    pub fn structured_diff(&self, worktree_name: Option<&str>) -> Result<Vec<WorktreeDiff>> {
+        let mut worktrees = match worktree_name {
+            Some(name) => vec![self.worktree_monitor.find_worktree(name)?],
+            None => self.worktree_monitor.scan_worktrees()?,
+        };
+        worktrees.sort_by(|a, b| a.name.cmp(&b.name));
+        self.diff_tracker.get_all_structured_diffs(&worktrees, &self.diff_filter)
+    }
+    
+    pub fn diff_summary(&self, worktree_name: Option<&str>) -> Result<Vec<DiffSummary>> {
+        match worktree_name {
+            Some(name) => {
+                let worktree = self.worktree_monitor.find_worktree(name)?;
+                Ok(vec![self.diff_tracker.get_diff_summary(&worktree, &self.diff_filter)?])
+            }
+            None => {
+                let mut worktrees = self.worktree_monitor.scan_worktrees()?;
+                worktrees.sort_by(|a, b| a.name.cmp(&b.name));
+                worktrees.iter()
+                    .map(|w| self.diff_tracker.get_diff_summary(w, &self.diff_filter))
+                    .collect()
+            }
+        }
+    }
+    
+    /// Files changed in more than one worktree since each branched from the default branch
+    ///
+    /// Worktrees whose changes cannot be read are skipped with a warning.
+    pub fn cross_worktree_overlaps(&self) -> Result<Vec<Overlap>> {
+        let worktrees = self.worktree_monitor.scan_worktrees()?;
+        let mut changes = Vec::new();
+        
+        for worktree in worktrees {
+            let base = overlap::default_branch(&worktree.path);
+            match overlap::changed_ranges(&worktree.path, base.as_deref()) {
+                Ok(ranges) => changes.push((worktree.name, ranges)),
+                Err(e) => tracing::warn!("Skipping {} in overlap check: {:#}", worktree.name, e),
+            }
+        }
+        
+        Ok(overlap::find_overlaps(&changes))
+    }
+    
+    /// Health score for every worktree, best first
+    ///
+    /// Combines status, cross-worktree overlaps, and cached CI check results
+    /// (with `with_ci_cache`) using the configured weights; see the `health`
+    /// module for the individual penalties.
+    pub fn health(&self) -> Result<Vec<WorktreeHealth>> {
+        let status = self.status()?;
+        let overlaps = self.cross_worktree_overlaps()?;
+        Ok(self.health_from(&status, &overlaps))
+    }
+    
+    /// Health scores from an already collected status and overlap list
+    pub fn health_from(&self, status: &MonitorStatus, overlaps: &[Overlap]) -> Vec<WorktreeHealth> {
+        let now = chrono::Utc::now();
+        let ci_cache = self.ci_cache_path.clone().map(CheckCache::load);
+        let mut health: Vec<WorktreeHealth> = status.worktrees.values()
+            .map(|worktree| {
+                let checks = ci_cache.as_ref().and_then(|cache| {
+                    let key = cache_key(&worktree.path, &self.ci_config).ok()?;
+                    cache.get(&worktree.path, &key)
+                });
+                health::score(worktree, overlaps, checks.as_ref(), &self.health_weights, &self.ci_config, now)
+            })
+            .collect();
+        health::sort_leaderboard(&mut health);
+        health
+    }
+    
+    /// Last `limit` commits of every worktree, sorted by worktree name
+    pub fn recent_commits(&self, limit: usize) -> Result<Vec<WorktreeCommits>> {
+        let mut worktrees = self.worktree_monitor.scan_worktrees()?;
+        worktrees.sort_by(|a, b| a.name.cmp(&b.name));
+        
+        worktrees.into_iter()
+            .map(|worktree| {
+                Ok(WorktreeCommits {
+                    commits: history::get_recent_commits(&worktree.path, limit)?,
+                    worktree: worktree.name,
+                    branch: worktree.branch,
+                })
+            })
+            .collect()
+    }
+    
     pub fn context(&self, context_type: &str) -> Result<String> {
         match context_type {
             "local-tasks" => self.aggregate_local_tasks(),
+            "commits" => Ok(history::format_commits_markdown(&self.recent_commits(self.commit_limit)?)),
+            "todos" => self.aggregate_todos(),
             "status" => Ok(serde_json::to_string_pretty(&self.status()?)?),
             _ => anyhow::bail!("Unknown context type: {}", context_type),
         }
     }
     
+    /// Context limited to roughly `max_tokens` tokens (estimated at four chars each)
+    ///
+    /// Besides the regular context types this accepts `all`, which combines a status
+    /// summary, local tasks, and diffs in that priority order.
+    pub fn context_with_budget(&self, context_type: &str, max_tokens: usize) -> Result<String> {
+        self.context_with_estimator(context_type, max_tokens, &CharEstimator)
+    }
+    
+    /// Like `context_with_budget`, but with a custom token estimator
+    pub fn context_with_estimator(
+        &self,
+        context_type: &str,
+        max_tokens: usize,
+        estimator: &dyn TokenEstimator,
+    ) -> Result<String> {
+        let sections = match context_type {
+            "all" => [
+                ("Status", self.status_summary()?),
+                ("Local Tasks", self.aggregate_local_tasks()?),
+                ("Diffs", self.diff(None)?),
+            ]
+            .into_iter()
+            .filter(|(_, body)| !body.trim().is_empty())
+            .map(|(title, body)| format!("# {}\n\n{}", title, body.trim_end()))
+            .collect(),
+            _ => vec![self.context(context_type)?],
+        };
+        
+        Ok(budget::fit_to_budget(&sections, max_tokens, estimator))
+    }
+    
+    /// One line per worktree, sorted by name
+    fn status_summary(&self) -> Result<String> {
+        let status = self.status()?;
+        let mut names: Vec<&String> = status.worktrees.keys().collect();
+        names.sort();
+        
+        Ok(names.into_iter()
+            .map(|name| {
+                let worktree = &status.worktrees[name];
+                let mut line = format!("- {}: {} on {} ({} files changed)",
+                    name, worktree.status, worktree.branch, worktree.files_changed);
+                for alert in worktree.alerts() {
+                    line.push_str(&format!(" {}", alert));
+                }
+                let unsaved = worktree.unsaved_work();
+                if !unsaved.is_empty() {
+                    line.push_str(&format!(" (there are {} here)", unsaved.join(" and ")));
+                }
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+    
+    /// TODO/FIXME/HACK comments per worktree with file:line references
+    pub fn todos(&self) -> Result<Vec<(String, TodoScan)>> {
+        let scanner = TodoScanner::from_config(&self.todos_config)?;
+        let mut worktrees = self.worktree_monitor.scan_worktrees()?;
+        worktrees.sort_by(|a, b| a.name.cmp(&b.name));
+        
+        worktrees.into_iter()
+            .map(|worktree| Ok((worktree.name, scanner.scan(&worktree.path)?)))
+            .collect()
+    }
+    
+    fn aggregate_todos(&self) -> Result<String> {
+        let sections: Vec<String> = self.todos()?
+            .into_iter()
+            .map(|(name, scan)| {
+                let mut section = format!("## Worktree: {}\n\n", name);
+                if scan.items.is_empty() {
+                    section.push_str("_No TODO/FIXME/HACK comments_\n");
+                }
+                for item in &scan.items {
+                    section.push_str(&format!(
+                        "- `{}:{}` {}: {}\n",
+                        item.path.display(), item.line, item.kind, item.text
+                    ));
+                }
+                if scan.omitted > 0 {
+                    section.push_str(&format!("- _...and {} more_\n", scan.omitted));
+                }
+                section
+            })
+            .collect();
+        
+        Ok(sections.join("\n"))
+    }
+    
     fn aggregate_local_tasks(&self) -> Result<String> {
         let worktrees = self.worktree_monitor.scan_worktrees()?;
-        let mut tasks = Vec::new();
+        let mut sections = Vec::new();
         
         for worktree in worktrees {
             let claude_local_path = worktree.path.join("CLAUDE.local.md");
             if claude_local_path.exists() {
                 let content = std::fs::read_to_string(&claude_local_path)
                     .context("Failed to read CLAUDE.local.md")?;
-                tasks.push(format!("## Worktree: {}\n\n{}", worktree.name, content));
+                let (done, total) = tasks::completion(&self.worktree_monitor.get_tasks(&worktree.path));
+                let progress = if total > 0 { format!(" ({}/{} done)", done, total) } else { String::new() };
+                sections.push(format!("## Worktree: {}{}\n\n{}", worktree.name, progress, content));
             }
         }
         
-        Ok(tasks.join("\n\n---\n\n"))
+        Ok(sections.join("\n\n---\n\n"))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::{git, init_repo};
+
+    #[test]
+    fn test_parallel_status_matches_serial() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().join("repo");
+        init_repo(&repo, &[("shared.txt", "base\n")]);
+        
+        for i in 0..6 {
+            let name = format!("../wt-{}", i);
+            git(&repo, &["worktree", "add", "-q", "-b", &format!("branch-{}", i), &name]);
+            let path = temp.path().join(format!("wt-{}", i));
+            for j in 0..i {
+                std::fs::write(path.join(format!("file-{}.txt", j)), "x\n").unwrap();
+            }
+            if i % 2 == 0 {
+                std::fs::write(path.join("shared.txt"), format!("changed {}\n", i)).unwrap();
+            }
+        }
+        
+        let mut serial = Monitor::new(repo.clone()).unwrap().with_concurrency(1).status().unwrap();
+        let mut parallel = Monitor::new(repo).unwrap().with_concurrency(4).status().unwrap();
+        
+        // Relative timestamps can tick over between the two runs
+        for status in serial.worktrees.values_mut().chain(parallel.worktrees.values_mut()) {
+            status.last_change_relative = None;
+        }
+        
+        assert_eq!(serial.worktrees.len(), 7);
+        assert_eq!(serial.worktrees, parallel.worktrees);
+        assert!(serial.errors.is_empty() && parallel.errors.is_empty());
+    }
+
+    #[test]
+    fn test_status_cache_skips_git_for_unchanged_worktrees() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().join("repo");
+        init_repo(&repo, &[("file.txt", "base\n")]);
+        git(&repo, &["worktree", "add", "-q", "-b", "feature", "../feature"]);
+        
+        let monitor = Monitor::new(repo.clone()).unwrap()
+            .with_status_cache(cache::default_cache_path(&repo));
+        let invocations = || monitor.worktree_monitor.git_invocations();
+        
+        let first = monitor.status().unwrap();
+        assert_eq!(first.worktrees.len(), 2);
+        // The cache directory ignores itself
+        assert_eq!(first.worktrees["repo"].status, "idle");
+        
+        let before = invocations();
+        let second = monitor.status().unwrap();
+        // Only `git worktree list` for discovery; the fingerprints' `git status`
+        // aside, no per-worktree git commands
+        assert_eq!(invocations() - before, 1);
+        assert_eq!(second.worktrees["feature"].branch, "feature");
+        
+        std::fs::write(temp.path().join("feature/file.txt"), "changed\n").unwrap();
+        let before = invocations();
+        let third = monitor.status().unwrap();
+        assert!(invocations() - before > 1);
+        assert_eq!(third.worktrees["feature"].status, "active");
+        assert_eq!(third.worktrees["repo"].status, "idle");
+    }
+
+    #[test]
+    fn test_health_counts_cached_ci_failures() {
+        use crate::ci::{CheckResults, CheckStatus};
+        
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().join("repo");
+        init_repo(&repo, &[("file.txt", "base\n")]);
+        git(&repo, &["worktree", "add", "-q", "-b", "feature", "../feature"]);
+        let feature = temp.path().join("feature").canonicalize().unwrap();
+        
+        let config = CiConfig::default();
+        let ci_cache = temp.path().join("ci_cache.json");
+        let mut cache = CheckCache::load(ci_cache.clone());
+        let checks = CheckResults {
+            cargo_check: CheckStatus::Pass,
+            cargo_test: CheckStatus::Fail("1 test(s) failed: t".to_string()),
+            clippy: CheckStatus::Pass,
+            debug_macros: CheckStatus::Pass,
+            documentation: CheckStatus::Pass,
+            fmt: CheckStatus::Pass,
+            audit: CheckStatus::Pass,
+            durations: Default::default(),
+            wall_time: Default::default(),
+        };
+        cache.insert(feature.clone(), cache_key(&feature, &config).unwrap(), checks);
+        cache.save().unwrap();
+        
+        let monitor = Monitor::new(repo).unwrap().with_ci_cache(ci_cache, config);
+        let health = monitor.health().unwrap();
+        let score = |name: &str| health.iter().find(|h| h.name == name).unwrap().clone();
+        assert!(score("feature").reasons.contains(&"cargo_test failed (-20)".to_string()));
+        assert_eq!(score("feature").score + 20, score("repo").score);
+        
+        // Results for an older state of the worktree no longer count
+        std::fs::write(feature.join("file.txt"), "edited\n").unwrap();
+        let health = monitor.health().unwrap();
+        assert!(!health.iter().flat_map(|h| &h.reasons).any(|reason| reason.contains("cargo_test")));
+    }
+    
+    #[test]
+    fn test_cross_worktree_overlaps() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().join("repo");
+        let base: String = (1..=30).map(|i| format!("line {}\n", i)).collect();
+        init_repo(&repo, &[("shared.txt", &base)]);
+        
+        for (name, line) in [("top", 2), ("bottom", 28), ("also-top", 2)] {
+            git(&repo, &["worktree", "add", "-q", "-b", name, &format!("../{}", name)]);
+            let edited = base.replace(&format!("line {}\n", line), "edited\n");
+            std::fs::write(temp.path().join(name).join("shared.txt"), edited).unwrap();
+        }
+        // Committed work counts too
+        git(&temp.path().join("bottom"), &["commit", "-q", "-am", "edit bottom"]);
+        
+        let overlaps = Monitor::new(repo).unwrap().cross_worktree_overlaps().unwrap();
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(overlaps[0].file, "shared.txt");
+        assert_eq!(overlaps[0].worktrees, vec!["also-top", "bottom", "top"]);
+        assert!(overlaps[0].line_ranges_intersect);
+    }
+
+    #[test]
+    fn test_by_recency_orders_newest_first() {
+        let status_at = |name: &str, date: Option<&str>| WorktreeStatus {
+            name: name.to_string(),
+            path: PathBuf::from(name),
+            status: "idle".to_string(),
+            last_change: date.map(|d| DateTime::parse_from_rfc3339(d).unwrap()),
+            last_change_relative: None,
+            files_changed: 0,
+            current_task: None,
+            tasks: Vec::new(),
+            branch: "main".to_string(),
+            ahead: None,
+            behind: None,
+            changes: Vec::new(),
+            operation: None,
+            has_conflicts: false,
+            stash_count: 0,
+            unpushed_commits: 0,
+            submodules_changed: Vec::new(),
+        };
+        let status = MonitorStatus {
+            worktrees: [
+                status_at("old", Some("2024-01-01T00:00:00+00:00")),
+                status_at("empty", None),
+                status_at("new", Some("2024-06-01T09:00:00+02:00")),
+            ].into_iter().map(|w| (w.name.clone(), w)).collect(),
+            timestamp: String::new(),
+            errors: Vec::new(),
+        };
+        
+        let names: Vec<&str> = status.by_recency().iter().map(|w| w.name.as_str()).collect();
+        assert_eq!(names, vec!["new", "old", "empty"]);
+    }
+
+    #[test]
+    fn test_legacy_relative_last_change_deserializes_as_none() {
+        let json = r#"{"name":"a","path":"a","status":"idle","last_change":"3 hours ago",
+            "files_changed":0,"current_task":null,"branch":"main","changes":[],
+            "operation":null,"has_conflicts":false}"#;
+        let status: WorktreeStatus = serde_json::from_str(json).unwrap();
+        assert_eq!(status.last_change, None);
+        assert_eq!(status.last_change_relative, None);
+    }
+
+    #[test]
+    fn test_failing_worktree_is_recorded_not_fatal() {
+        let temp = tempfile::tempdir().unwrap();
+        let monitor = Monitor::new(temp.path().to_path_buf()).unwrap().with_concurrency(2);
+        let worktrees = vec![
+            Worktree {
+                name: "missing".to_string(),
+                path: temp.path().join("does-not-exist"),
+                ..Worktree::default()
+            },
+        ];
+        
+        let results = monitor.collect_statuses(&worktrees);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+    }
+}
@@ -0,0 +1,218 @@
+//! Detection of files modified in more than one worktree
+//!
+//! Each worktree's changes are taken relative to its merge base with the default
+//! branch, so both committed and uncommitted work count. Line ranges come from the
+//! hunk headers of a zero-context diff (`git diff -U0`) and are compared on the
+//! base-file side; untracked and binary files have no ranges and are treated as
+//! touching the whole file.
+//!
+//! Worktrees branched from different merge bases are compared as if the base file
+//! were the same, so line intersection is a heuristic. The file-level overlap is
+//! exact.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::process::Command;
+
+use crate::monitor::diff::parse_unified_diff;
+
+/// A file changed in two or more worktrees
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Overlap {
+    pub file: String,
+    /// Worktree names, sorted
+    pub worktrees: Vec<String>,
+    /// Whether any two of the worktrees changed intersecting line ranges
+    pub line_ranges_intersect: bool,
+}
+
+/// Lines of the base file touched by a hunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRange {
+    pub start: usize,
+    /// Zero for pure insertions, which still occupy the point at `start`
+    pub len: usize,
+}
+
+impl LineRange {
+    /// First line after the range
+    pub fn end(&self) -> usize {
+        self.start + self.len.max(1)
+    }
+
+    pub fn intersects(&self, other: &LineRange) -> bool {
+        self.start < other.end() && other.start < self.end()
+    }
+}
+
+/// Changed files mapped to the base-side line ranges they touch
+///
+/// An empty range list means the whole file (untracked or binary).
+pub type FileRanges = HashMap<String, Vec<LineRange>>;
+
+/// Branch that worktrees are compared against: `origin/HEAD` if set, else `main` or `master`
+pub fn default_branch(path: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .current_dir(path)
+        .args(["symbolic-ref", "--quiet", "--short", "refs/remotes/origin/HEAD"])
+        .output()
+        .ok()?;
+    if output.status.success() {
+        return Some(String::from_utf8_lossy(&output.stdout).trim().to_string());
+    }
+
+    ["main", "master"].into_iter()
+        .find(|branch| {
+            Command::new("git")
+                .current_dir(path)
+                .args(["rev-parse", "--verify", "--quiet", &format!("refs/heads/{}", branch)])
+                .output()
+                .map(|o| o.status.success())
+                .unwrap_or(false)
+        })
+        .map(str::to_string)
+}
+
+/// Files a worktree has changed since its merge base with `base_branch`
+///
+/// Without a base branch (or a common ancestor) only uncommitted changes against
+/// HEAD are considered.
+pub fn changed_ranges(path: &Path, base_branch: Option<&str>) -> Result<FileRanges> {
+    let merge_base = base_branch.and_then(|base| {
+        let output = Command::new("git")
+            .current_dir(path)
+            .args(["merge-base", "HEAD", base])
+            .output()
+            .ok()?;
+        output.status.success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    });
+    let base = merge_base.as_deref().unwrap_or("HEAD");
+
+    let diff = Command::new("git")
+        .current_dir(path)
+        .args(["-c", "core.quotePath=false", "diff", "-U0", "-M", "--no-color", base])
+        .output()
+        .context("Failed to run git diff")?;
+    let mut ranges = if diff.status.success() {
+        parse_hunk_ranges(&String::from_utf8_lossy(&diff.stdout))
+    } else {
+        // No HEAD yet: nothing committed to compare against
+        FileRanges::new()
+    };
+
+    let untracked = Command::new("git")
+        .current_dir(path)
+        .args(["-c", "core.quotePath=false", "ls-files", "--others", "--exclude-standard"])
+        .output()
+        .context("Failed to list untracked files")?;
+    for file in String::from_utf8_lossy(&untracked.stdout).lines() {
+        ranges.insert(file.to_string(), Vec::new());
+    }
+
+    Ok(ranges)
+}
+
+/// Collect base-side hunk ranges per file from a unified diff
+///
+/// Files are keyed by their new path, or the old path for deletions.
+pub fn parse_hunk_ranges(diff: &str) -> FileRanges {
+    parse_unified_diff(diff)
+        .into_iter()
+        .map(|file| {
+            let ranges = file.hunks.iter()
+                .map(|hunk| LineRange { start: hunk.old_start, len: hunk.old_lines })
+                .collect();
+            (file.path, ranges)
+        })
+        .collect()
+}
+
+/// Find files changed by more than one worktree
+pub fn find_overlaps(changes: &[(String, FileRanges)]) -> Vec<Overlap> {
+    let mut by_file: BTreeMap<&str, Vec<(&str, &[LineRange])>> = BTreeMap::new();
+    for (worktree, files) in changes {
+        for (file, ranges) in files {
+            by_file.entry(file).or_default().push((worktree, ranges));
+        }
+    }
+
+    by_file.into_iter()
+        .filter(|(_, touched)| touched.len() > 1)
+        .map(|(file, mut touched)| {
+            touched.sort_by_key(|(worktree, _)| *worktree);
+            let line_ranges_intersect = touched.iter().enumerate().any(|(i, (_, a))| {
+                touched[i + 1..].iter().any(|(_, b)| ranges_intersect(a, b))
+            });
+            Overlap {
+                file: file.to_string(),
+                worktrees: touched.iter().map(|(w, _)| w.to_string()).collect(),
+                line_ranges_intersect,
+            }
+        })
+        .collect()
+}
+
+fn ranges_intersect(a: &[LineRange], b: &[LineRange]) -> bool {
+    if a.is_empty() || b.is_empty() {
+        return true;
+    }
+    a.iter().any(|x| b.iter().any(|y| x.intersects(y)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hunk_ranges() {
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs\n\
+                    index 1111111..2222222 100644\n\
+                    --- a/src/lib.rs\n\
+                    +++ b/src/lib.rs\n\
+                    @@ -10,3 +10,4 @@ fn main() {\n\
+                    @@ -40 +41 @@\n\
+                    @@ -55,0 +57,2 @@\n\
+                    diff --git a/old.rs b/old.rs\n\
+                    deleted file mode 100644\n\
+                    --- a/old.rs\n\
+                    +++ /dev/null\n\
+                    @@ -1,2 +0,0 @@\n\
+                    diff --git a/logo.png b/logo.png\n\
+                    index 3333333..4444444 100644\n\
+                    Binary files a/logo.png and b/logo.png differ\n";
+        let ranges = parse_hunk_ranges(diff);
+
+        assert_eq!(ranges["src/lib.rs"], vec![
+            LineRange { start: 10, len: 3 },
+            LineRange { start: 40, len: 1 },
+            LineRange { start: 55, len: 0 },
+        ]);
+        assert_eq!(ranges["old.rs"], vec![LineRange { start: 1, len: 2 }]);
+        assert!(ranges["logo.png"].is_empty());
+    }
+
+    #[test]
+    fn test_find_overlaps_checks_every_pair() {
+        let files = |entries: &[(&str, Vec<LineRange>)]| -> FileRanges {
+            entries.iter().map(|(f, r)| (f.to_string(), r.clone())).collect()
+        };
+        let changes = vec![
+            ("a".to_string(), files(&[("shared.rs", vec![LineRange { start: 1, len: 5 }]), ("only-a.rs", vec![])])),
+            ("b".to_string(), files(&[("shared.rs", vec![LineRange { start: 20, len: 2 }]), ("new.rs", vec![])])),
+            ("c".to_string(), files(&[("shared.rs", vec![LineRange { start: 21, len: 0 }]), ("new.rs", vec![])])),
+        ];
+        let overlaps = find_overlaps(&changes);
+
+        assert_eq!(overlaps, vec![
+            Overlap { file: "new.rs".to_string(), worktrees: vec!["b".to_string(), "c".to_string()], line_ranges_intersect: true },
+            Overlap { file: "shared.rs".to_string(), worktrees: vec!["a".to_string(), "b".to_string(), "c".to_string()], line_ranges_intersect: true },
+        ]);
+
+        let disjoint = find_overlaps(&changes[..2]);
+        assert_eq!(disjoint.len(), 1);
+        assert!(!disjoint[0].line_ranges_intersect);
+    }
+}
@@ -0,0 +1,211 @@
+//! Markdown and HTML renderers for `MonitorStatus`
+//!
+//! Both formats list worktrees most recently changed first, with a small table per
+//! worktree (branch, ahead/behind, files changed, current task, last change) followed
+//! by its changed files. The HTML report nests the file list in a collapsible
+//! `<details>` section so a dashboard page stays scannable.
+//!
+//! Output is checked against golden files in `tests/golden/`; run the tests with
+//! `UPDATE_GOLDEN=1` to regenerate them after an intentional formatting change.
+
+use crate::monitor::{MonitorStatus, WorktreeStatus};
+
+impl MonitorStatus {
+    /// Render the status as Markdown, e.g. for a PR description
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# Worktree Status\n\n_Generated {}_\n", self.timestamp);
+
+        for error in &self.errors {
+            out.push_str(&format!("\n> **{}**: error ({})\n", escape_markdown(&error.name), escape_markdown(&error.error)));
+        }
+
+        for worktree in self.by_recency() {
+            out.push_str(&format!("\n## {}\n\n", worktree.name));
+            for alert in worktree.alerts().into_iter().chain(worktree.unsaved_work()) {
+                out.push_str(&format!("> ⚠️ {}\n\n", alert));
+            }
+            out.push_str("| | |\n|---|---|\n");
+            for (label, value) in fields(worktree) {
+                out.push_str(&format!("| {} | {} |\n", label, escape_markdown(&value)));
+            }
+
+            if !worktree.changes.is_empty() {
+                out.push_str("\n| Status | File | + | - |\n|---|---|---:|---:|\n");
+                for change in &worktree.changes {
+                    out.push_str(&format!(
+                        "| {} | `{}` | {} | {} |\n",
+                        change.status.code(), escape_markdown(&change.path), change.insertions, change.deletions
+                    ));
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Render the status as a standalone HTML fragment, e.g. for a dashboard page
+    pub fn to_html(&self) -> String {
+        let mut out = format!(
+            "<div class=\"autodebugger-status\">\n<h1>Worktree Status</h1>\n<p><em>Generated {}</em></p>\n",
+            escape_html(&self.timestamp)
+        );
+
+        for error in &self.errors {
+            out.push_str(&format!(
+                "<p class=\"error\"><strong>{}</strong>: error ({})</p>\n",
+                escape_html(&error.name), escape_html(&error.error)
+            ));
+        }
+
+        for worktree in self.by_recency() {
+            out.push_str(&format!("<section>\n<h2>{}</h2>\n", escape_html(&worktree.name)));
+            for alert in worktree.alerts().into_iter().chain(worktree.unsaved_work()) {
+                out.push_str(&format!("<p class=\"alert\">⚠️ {}</p>\n", escape_html(&alert)));
+            }
+            out.push_str("<table>\n");
+            for (label, value) in fields(worktree) {
+                out.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>\n", label, escape_html(&value)));
+            }
+            out.push_str("</table>\n");
+
+            if !worktree.changes.is_empty() {
+                let insertions: usize = worktree.changes.iter().map(|c| c.insertions).sum();
+                let deletions: usize = worktree.changes.iter().map(|c| c.deletions).sum();
+                out.push_str(&format!(
+                    "<details>\n<summary>{} changed file(s), +{} -{}</summary>\n<table>\n",
+                    worktree.changes.len(), insertions, deletions
+                ));
+                out.push_str("<tr><th>Status</th><th>File</th><th>+</th><th>-</th></tr>\n");
+                for change in &worktree.changes {
+                    out.push_str(&format!(
+                        "<tr><td>{}</td><td><code>{}</code></td><td>{}</td><td>{}</td></tr>\n",
+                        change.status.code(), escape_html(&change.path), change.insertions, change.deletions
+                    ));
+                }
+                out.push_str("</table>\n</details>\n");
+            }
+            out.push_str("</section>\n");
+        }
+
+        out.push_str("</div>\n");
+        out
+    }
+}
+
+/// Label/value rows shared by both renderers
+fn fields(worktree: &WorktreeStatus) -> Vec<(&'static str, String)> {
+    let ahead_behind = match (worktree.ahead, worktree.behind) {
+        (Some(ahead), Some(behind)) => format!("{} ahead, {} behind", ahead, behind),
+        _ => "no upstream".to_string(),
+    };
+    let last_change = match (&worktree.last_change, &worktree.last_change_relative) {
+        (Some(date), Some(relative)) => format!("{} ({})", date.to_rfc3339(), relative),
+        (Some(date), None) => date.to_rfc3339(),
+        _ => "never".to_string(),
+    };
+
+    vec![
+        ("Branch", if worktree.branch.is_empty() { "(detached)".to_string() } else { worktree.branch.clone() }),
+        ("Ahead/behind", ahead_behind),
+        ("Files changed", worktree.files_changed.to_string()),
+        ("Current task", worktree.current_task.clone().unwrap_or_else(|| "none".to_string())),
+        ("Last change", last_change),
+    ]
+}
+
+/// Keep table cells intact when values contain pipes or newlines
+pub(crate) fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::{ChangeStatus, FileChange, GitOperation, WorktreeError};
+    use chrono::DateTime;
+    use std::path::PathBuf;
+
+    fn sample_status() -> MonitorStatus {
+        let feature = WorktreeStatus {
+            name: "feature-auth".to_string(),
+            path: PathBuf::from("/work/feature-auth"),
+            status: "active".to_string(),
+            last_change: Some(DateTime::parse_from_rfc3339("2024-06-01T09:30:00+02:00").unwrap()),
+            last_change_relative: Some("2 hours ago".to_string()),
+            files_changed: 2,
+            current_task: Some("Add <OAuth> | PKCE flow".to_string()),
+            tasks: Vec::new(),
+            branch: "feature/auth".to_string(),
+            ahead: Some(3),
+            behind: Some(1),
+            changes: vec![
+                FileChange { path: "src/auth.rs".to_string(), status: ChangeStatus::Modified, insertions: 40, deletions: 5 },
+                FileChange { path: "src/token.rs".to_string(), status: ChangeStatus::Added, insertions: 12, deletions: 0 },
+                FileChange { path: "notes.txt".to_string(), status: ChangeStatus::Untracked, insertions: 0, deletions: 0 },
+            ],
+            operation: Some(GitOperation::Rebase),
+            has_conflicts: false,
+            stash_count: 0,
+            unpushed_commits: 0,
+            submodules_changed: Vec::new(),
+        };
+        let main = WorktreeStatus {
+            name: "main".to_string(),
+            path: PathBuf::from("/work/main"),
+            status: "idle".to_string(),
+            last_change: Some(DateTime::parse_from_rfc3339("2024-05-30T12:00:00+00:00").unwrap()),
+            last_change_relative: None,
+            files_changed: 0,
+            current_task: None,
+            tasks: Vec::new(),
+            branch: "main".to_string(),
+            ahead: None,
+            behind: None,
+            changes: Vec::new(),
+            operation: None,
+            has_conflicts: false,
+            stash_count: 0,
+            unpushed_commits: 0,
+            submodules_changed: Vec::new(),
+        };
+
+        MonitorStatus {
+            worktrees: [main, feature].into_iter().map(|w| (w.name.clone(), w)).collect(),
+            timestamp: "2024-06-01T10:00:00+00:00".to_string(),
+            errors: vec![WorktreeError {
+                name: "broken".to_string(),
+                path: PathBuf::from("/work/broken"),
+                error: "not a git repository".to_string(),
+            }],
+        }
+    }
+
+    /// Compare against a golden file, or rewrite it when `UPDATE_GOLDEN` is set
+    fn assert_golden(name: &str, actual: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(name);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("missing golden file {}", path.display()));
+        assert_eq!(actual, expected, "{} changed; rerun with UPDATE_GOLDEN=1 if intended", name);
+    }
+
+    #[test]
+    fn test_markdown_report_matches_golden() {
+        assert_golden("status_report.md", &sample_status().to_markdown());
+    }
+
+    #[test]
+    fn test_html_report_matches_golden() {
+        assert_golden("status_report.html", &sample_status().to_html());
+    }
+}
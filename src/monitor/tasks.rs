@@ -0,0 +1,134 @@
+//! Task list parsing for `CLAUDE.local.md`
+//!
+//! Tasks are list items under one of the configured section headers (by default
+//! `## Specific Tasks` and `## Tasks`, matched case-insensitively at any heading
+//! level). A section ends at the next heading of the same or higher level.
+//!
+//! Recognised items:
+//! - checkboxes: `- [ ] open`, `- [x] done` (also `*` and `+` bullets, nested or not)
+//! - numbered items: `1. open` or `1) open`, optionally with a checkbox after the number
+//!
+//! Plain bullets without a checkbox are treated as notes, not tasks.
+
+use serde::{Deserialize, Serialize};
+
+/// A single task from a task list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Task {
+    pub text: String,
+    pub completed: bool,
+    /// 1-based position among all parsed tasks
+    pub index: usize,
+}
+
+/// Default section headers that contain tasks
+pub fn default_task_sections() -> Vec<String> {
+    vec!["Specific Tasks".to_string(), "Tasks".to_string()]
+}
+
+/// Parse every task under the given section headers
+pub fn parse_tasks(content: &str, sections: &[String]) -> Vec<Task> {
+    let mut tasks = Vec::new();
+    // Heading level of the task section we are in, if any
+    let mut in_section: Option<usize> = None;
+
+    for line in content.lines() {
+        if let Some((level, title)) = parse_heading(line) {
+            if in_section.is_some_and(|section_level| level <= section_level) {
+                in_section = None;
+            }
+            if sections.iter().any(|s| s.eq_ignore_ascii_case(title)) {
+                in_section = Some(level);
+            }
+            continue;
+        }
+
+        if in_section.is_none() {
+            continue;
+        }
+        // Empty items are placeholders, e.g. from a freshly seeded template
+        if let Some((text, completed)) = parse_item(line.trim_start()).filter(|(text, _)| !text.is_empty()) {
+            tasks.push(Task {
+                text,
+                completed,
+                index: tasks.len() + 1,
+            });
+        }
+    }
+
+    tasks
+}
+
+/// Count of completed tasks and total tasks
+pub fn completion(tasks: &[Task]) -> (usize, usize) {
+    (tasks.iter().filter(|t| t.completed).count(), tasks.len())
+}
+
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 {
+        return None;
+    }
+    let title = line[level..].strip_prefix(' ')?;
+    Some((level, title.trim().trim_end_matches('#').trim()))
+}
+
+fn parse_item(line: &str) -> Option<(String, bool)> {
+    if let Some(rest) = line.strip_prefix(['-', '*', '+']) {
+        let rest = rest.strip_prefix(' ')?;
+        return parse_checkbox(rest);
+    }
+
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    let rest = line[digits..].strip_prefix(['.', ')'])?.strip_prefix(' ')?;
+    Some(parse_checkbox(rest).unwrap_or_else(|| (rest.trim().to_string(), false)))
+}
+
+fn parse_checkbox(rest: &str) -> Option<(String, bool)> {
+    let (completed, text) = if let Some(text) = rest.strip_prefix("[ ]") {
+        (false, text)
+    } else if let Some(text) = rest.strip_prefix("[x]").or_else(|| rest.strip_prefix("[X]")) {
+        (true, text)
+    } else {
+        return None;
+    };
+    Some((text.trim().to_string(), completed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_checkboxes_and_numbered_items() {
+        let content = "# Worktree\n\n## Context\n\n1. Not a task\n\n\
+                       ## Specific Tasks\n\n1. Write parser\n2) [x] Read config\n\
+                       - [X] Wire up status\n  - [ ] Nested follow-up\n- a note\n\
+                       ### Details\n- [ ] Subsection still counts\n\n\
+                       ## Notes\n- [ ] Outside any task section\n";
+        let tasks = parse_tasks(content, &default_task_sections());
+
+        let found: Vec<(usize, &str, bool)> = tasks.iter()
+            .map(|t| (t.index, t.text.as_str(), t.completed))
+            .collect();
+        assert_eq!(found, vec![
+            (1, "Write parser", false),
+            (2, "Read config", true),
+            (3, "Wire up status", true),
+            (4, "Nested follow-up", false),
+            (5, "Subsection still counts", false),
+        ]);
+        assert_eq!(completion(&tasks), (2, 5));
+    }
+
+    #[test]
+    fn test_custom_sections() {
+        let content = "## TODO\n- [ ] one\n- [ ] \n## Specific Tasks\n1. two\n";
+        let tasks = parse_tasks(content, &["todo".to_string()]);
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].text, "one");
+    }
+}
@@ -1,51 +1,150 @@
 use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset};
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use crate::monitor::WorktreeStatus;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing::warn;
+use crate::config::MonitorConfig;
+use crate::monitor::{ChangeStatus, FileChange, GitOperation, WorktreeStatus};
+use crate::monitor::tasks::{parse_tasks, Task};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Worktree {
     pub name: String,
     pub path: PathBuf,
+    /// Commit checked out in the worktree (None for a fresh branch)
+    pub head: Option<String>,
+    /// Short branch name (None when HEAD is detached)
+    pub branch: Option<String>,
+    pub locked: bool,
+    pub prunable: bool,
 }
 
 pub struct WorktreeMonitor {
     workspace_path: PathBuf,
+    config: MonitorConfig,
+    /// Number of git processes spawned, for verifying cache behaviour
+    git_invocations: AtomicUsize,
 }
 
 impl WorktreeMonitor {
-    pub fn new(workspace_path: PathBuf) -> Result<Self> {
+    pub fn new(workspace_path: PathBuf, config: Option<MonitorConfig>) -> Result<Self> {
         if !workspace_path.exists() {
-            anyhow::bail!("Workspace path does not exist: {}", workspace_path.display());
+            return Err(crate::error::not_found(format_args!("Workspace path does not exist: {}", workspace_path.display())));
         }
-        Ok(Self { workspace_path })
+        Ok(Self {
+            workspace_path,
+            config: config.unwrap_or_default(),
+            git_invocations: AtomicUsize::new(0),
+        })
+    }
+    
+    /// A git command running in `dir`
+    ///
+    /// Optional locks are disabled so read-only commands like `git status` never
+    /// rewrite the index underneath agents working in the worktree.
+    fn git(&self, dir: &Path) -> Command {
+        self.git_invocations.fetch_add(1, Ordering::Relaxed);
+        let mut command = Command::new("git");
+        command.current_dir(dir).env("GIT_OPTIONAL_LOCKS", "0");
+        command
     }
     
+    /// Total git processes spawned by this monitor so far
+    pub fn git_invocations(&self) -> usize {
+        self.git_invocations.load(Ordering::Relaxed)
+    }
+    
+    /// Discover worktrees from every configured source
+    ///
+    /// Sources are `git worktree list` (when the workspace is a git repository), each
+    /// directory in `worktrees_dirs`, and the individual `extra_worktrees`. Results
+    /// are de-duplicated by canonical path, keeping the first occurrence, and entries
+    /// whose path no longer exists are skipped with a warning.
     pub fn scan_worktrees(&self) -> Result<Vec<Worktree>> {
-        let worktrees_dir = self.workspace_path.join("worktrees");
+        let mut candidates = self.git_worktrees()?;
+        for dir in &self.config.worktrees_dirs {
+            candidates.extend(self.scan_worktrees_dir(&self.resolve(dir))?);
+        }
+        for extra in &self.config.extra_worktrees {
+            let path = self.resolve(extra);
+            candidates.push(Worktree {
+                name: worktree_name(&path),
+                path,
+                ..Worktree::default()
+            });
+        }
+        
+        let mut seen = HashSet::new();
+        let mut worktrees = Vec::new();
+        for worktree in candidates {
+            let Ok(canonical) = worktree.path.canonicalize() else {
+                warn!("Skipping worktree {}: {} no longer exists", worktree.name, worktree.path.display());
+                continue;
+            };
+            if seen.insert(canonical) {
+                worktrees.push(worktree);
+            }
+        }
+        
+        Ok(worktrees)
+    }
+    
+    /// Worktrees registered with git, main worktree first (empty outside a repository)
+    fn git_worktrees(&self) -> Result<Vec<Worktree>> {
+        let output = self.git(&self.workspace_path)
+            .args(["worktree", "list", "--porcelain"])
+            .output()
+            .context("Failed to run git worktree list")?;
+            
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+        Ok(parse_worktree_list(&String::from_utf8_lossy(&output.stdout)))
+    }
+    
+    /// Find a worktree by name
+    pub fn find_worktree(&self, name: &str) -> Result<Worktree> {
+        self.scan_worktrees()?
+            .into_iter()
+            .find(|w| w.name == name)
+            .ok_or_else(|| crate::error::not_found(format_args!("Worktree not found: {}", name)))
+    }
+    
+    /// Interpret a configured path relative to the workspace
+    fn resolve(&self, path: &str) -> PathBuf {
+        let path = Path::new(path);
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.workspace_path.join(path)
+        }
+    }
+    
+    fn scan_worktrees_dir(&self, worktrees_dir: &Path) -> Result<Vec<Worktree>> {
         if !worktrees_dir.exists() {
             return Ok(Vec::new());
         }
         
         let mut worktrees = Vec::new();
         
-        for entry in std::fs::read_dir(&worktrees_dir)? {
+        for entry in std::fs::read_dir(worktrees_dir)? {
             let entry = entry?;
             let path = entry.path();
             
             if path.is_dir() {
-                let name = path.file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("unknown")
-                    .to_string();
+                let name = worktree_name(&path);
                     
                 // Check if it's a git worktree
                 if path.join(".git").exists() {
-                    worktrees.push(Worktree { name, path });
+                    worktrees.push(Worktree { name, path, ..Worktree::default() });
                 }
             }
         }
         
+        // read_dir order is platform-dependent
+        worktrees.sort_by(|a, b| a.path.cmp(&b.path));
         Ok(worktrees)
     }
     
@@ -53,76 +152,811 @@ impl WorktreeMonitor {
         // Get git branch
         let branch = self.get_git_branch(&worktree.path)?;
         
-        // Get git status
-        let git_status = Command::new("git")
-            .current_dir(&worktree.path)
-            .args(&["status", "--porcelain"])
-            .output()
-            .context("Failed to run git status")?;
-            
-        let files_changed = String::from_utf8_lossy(&git_status.stdout)
-            .lines()
+        // Get per-file changes
+        let git_status = self.read_status(&worktree.path)?;
+        let changes = self.changes_from_status(&worktree.path, &git_status)?;
+        let submodules_changed = parse_submodules_v2(&git_status);
+        let has_conflicts = git_status.split('\0').any(|entry| entry.starts_with("u "));
+        let operation = self.detect_operation(&worktree.path)?;
+        let files_changed = changes.iter()
+            .filter(|c| c.status != ChangeStatus::Untracked)
             .count();
             
-        // Get last commit time
-        let last_commit = Command::new("git")
-            .current_dir(&worktree.path)
-            .args(&["log", "-1", "--format=%ar"])
+        // Get last commit time, both exact and relative
+        let last_commit = self.git(&worktree.path)
+            .args(["log", "-1", "--format=%cI%x1f%ar"])
             .output()
             .context("Failed to get last commit")?;
             
-        let last_change = if last_commit.status.success() {
-            Some(String::from_utf8_lossy(&last_commit.stdout).trim().to_string())
+        let (last_change, last_change_relative) = if last_commit.status.success() {
+            parse_last_commit(&String::from_utf8_lossy(&last_commit.stdout))
         } else {
-            None
+            (None, None)
         };
         
-        // Try to extract current task from CLAUDE.local.md
-        let current_task = self.extract_current_task(&worktree.path);
+        let (ahead, behind) = match self.get_ahead_behind(&worktree.path)? {
+            Some((ahead, behind)) => (Some(ahead), Some(behind)),
+            None => (None, None),
+        };
+        // `ahead` counts exactly the commits in `@{upstream}..HEAD`
+        let unpushed_commits = ahead.unwrap_or(0);
+        let stash_count = self.get_stash_count(&worktree.path, &branch)?;
+        
+        // The current task is the first incomplete one in CLAUDE.local.md
+        let tasks = self.get_tasks(&worktree.path);
+        let current_task = tasks.iter()
+            .find(|t| !t.completed)
+            .map(|t| t.text.clone());
         
         Ok(WorktreeStatus {
             name: worktree.name.clone(),
             path: worktree.path.clone(),
-            status: if files_changed > 0 { "active".to_string() } else { "idle".to_string() },
+            status: if changes.is_empty() { "idle".to_string() } else { "active".to_string() },
             last_change,
+            last_change_relative,
             files_changed,
             current_task,
+            tasks,
             branch,
+            ahead,
+            behind,
+            changes,
+            operation,
+            has_conflicts,
+            stash_count,
+            unpushed_commits,
+            submodules_changed,
         })
     }
     
+    /// Number of stashes created on `branch`
+    ///
+    /// The stash is shared by all worktrees of a repository, so entries are
+    /// attributed by the branch named in their message (`WIP on <branch>: ...`).
+    /// A detached HEAD (empty `branch`) matches stashes made with no branch.
+    pub fn get_stash_count(&self, path: &Path, branch: &str) -> Result<usize> {
+        let output = self.git(path)
+            .args(["stash", "list", "--format=%gs"])
+            .output()
+            .context("Failed to list stashes")?;
+            
+        if !output.status.success() {
+            return Ok(0);
+        }
+        
+        let branch = if branch.is_empty() { "(no branch)" } else { branch };
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| stash_branch(line) == Some(branch))
+            .count())
+    }
+    
+    /// Commits ahead of and behind the upstream branch, or None without an upstream
+    pub fn get_ahead_behind(&self, path: &Path) -> Result<Option<(usize, usize)>> {
+        let output = self.git(path)
+            .args(["rev-list", "--left-right", "--count", "HEAD...@{upstream}"])
+            .output()
+            .context("Failed to compare with upstream")?;
+            
+        if !output.status.success() {
+            return Ok(None);
+        }
+        
+        let counts = String::from_utf8_lossy(&output.stdout);
+        let mut parts = counts.split_whitespace().map(|n| n.parse::<usize>());
+        match (parts.next(), parts.next()) {
+            (Some(Ok(ahead)), Some(Ok(behind))) => Ok(Some((ahead, behind))),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Detect an in-progress merge, rebase, cherry-pick, or revert
+    ///
+    /// In a linked worktree `.git` is a file pointing at a per-worktree directory
+    /// under the main repository, so the state files are looked up in the directory
+    /// git itself reports rather than in `<worktree>/.git`.
+    pub fn detect_operation(&self, path: &Path) -> Result<Option<GitOperation>> {
+        let output = self.git(path)
+            .args(["rev-parse", "--absolute-git-dir"])
+            .output()
+            .context("Failed to locate git directory")?;
+            
+        if !output.status.success() {
+            return Ok(None);
+        }
+        
+        let git_dir = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+        Ok(operation_in_git_dir(&git_dir))
+    }
+    
+    /// Collect per-file changes from `git status --porcelain=v2` and `git diff --numstat`
+    pub fn get_changes(&self, path: &Path) -> Result<Vec<FileChange>> {
+        let status = self.read_status(path)?;
+        self.changes_from_status(path, &status)
+    }
+    
+    /// Raw `git status --porcelain=v2 -z` output
+    fn read_status(&self, path: &Path) -> Result<String> {
+        // --untracked-files=all expands untracked directories into their files
+        let status = self.git(path)
+            .args(["status", "--porcelain=v2", "-z", "--untracked-files=all"])
+            .output()
+            .context("Failed to run git status")?;
+            
+        Ok(String::from_utf8_lossy(&status.stdout).to_string())
+    }
+    
+    fn changes_from_status(&self, path: &Path, status: &str) -> Result<Vec<FileChange>> {
+        let mut changes = parse_status_v2(status);
+        
+        let mut numstat = self.git(path)
+            .args(["diff", "HEAD", "-M", "--numstat", "-z"])
+            .output()
+            .context("Failed to get diff numstat")?;
+            
+        if !numstat.status.success() {
+            // No HEAD yet (fresh repository): everything tracked is staged
+            numstat = self.git(path)
+                .args(["diff", "--cached", "-M", "--numstat", "-z"])
+                .output()
+                .context("Failed to get diff numstat")?;
+        }
+        
+        let line_counts = parse_numstat_z(&String::from_utf8_lossy(&numstat.stdout));
+        for change in &mut changes {
+            if let Some((insertions, deletions)) = line_counts.get(&change.path) {
+                change.insertions = *insertions;
+                change.deletions = *deletions;
+            }
+        }
+        
+        Ok(changes)
+    }
+    
     fn get_git_branch(&self, path: &Path) -> Result<String> {
-        let output = Command::new("git")
-            .current_dir(path)
-            .args(&["branch", "--show-current"])
+        let output = self.git(path)
+            .args(["branch", "--show-current"])
             .output()
             .context("Failed to get git branch")?;
             
         Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
     }
     
-    fn extract_current_task(&self, path: &Path) -> Option<String> {
-        let claude_local = path.join("CLAUDE.local.md");
-        if !claude_local.exists() {
-            return None;
+    /// Tasks from the worktree's `CLAUDE.local.md` (empty if there is none)
+    pub fn get_tasks(&self, path: &Path) -> Vec<Task> {
+        std::fs::read_to_string(path.join("CLAUDE.local.md"))
+            .map(|content| parse_tasks(&content, &self.config.task_sections))
+            .unwrap_or_default()
+    }
+}
+
+/// Default `CLAUDE.local.md` written by `seed_task_file` when no template is configured
+pub const DEFAULT_TASK_TEMPLATE: &str = "# {name}\n\nBranch: `{branch}`\n\n## Specific Tasks\n\n1. \n";
+
+/// Why a worktree lifecycle operation was refused or failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LifecycleError {
+    /// Name is empty, contains a path separator, or starts with a dot
+    InvalidName(String),
+    /// Something already exists at the target path
+    PathExists(PathBuf),
+    /// A new branch was requested from a base ref but the branch already exists
+    BranchExists(String),
+    /// No worktree with this name was found
+    NotFound(String),
+    /// The main worktree of the repository cannot be removed
+    MainWorktree(String),
+    /// The worktree has uncommitted or untracked files and `force` was not set
+    UncommittedChanges { name: String, files: usize },
+    /// The workspace is not inside a git repository
+    NotARepository(PathBuf),
+    /// The git command itself failed
+    Git { command: String, stderr: String },
+}
+
+impl std::fmt::Display for LifecycleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LifecycleError::InvalidName(name) => write!(f, "Invalid worktree name: {:?}", name),
+            LifecycleError::PathExists(path) => write!(f, "Path already exists: {}", path.display()),
+            LifecycleError::BranchExists(branch) => write!(f, "Branch already exists: {}", branch),
+            LifecycleError::NotFound(name) => write!(f, "Worktree not found: {}", name),
+            LifecycleError::MainWorktree(name) => write!(f, "Refusing to remove main worktree: {}", name),
+            LifecycleError::UncommittedChanges { name, files } => write!(
+                f, "Worktree {} has {} uncommitted file(s); use force to remove anyway", name, files
+            ),
+            LifecycleError::NotARepository(path) => write!(f, "Not a git repository: {}", path.display()),
+            LifecycleError::Git { command, stderr } => write!(f, "{} failed: {}", command, stderr),
+        }
+    }
+}
+
+impl std::error::Error for LifecycleError {}
+
+/// Creating, removing, and pruning worktrees
+///
+/// Errors that callers may want to act on are returned as `LifecycleError` inside the
+/// `anyhow::Error` and can be recovered with `downcast_ref`.
+impl WorktreeMonitor {
+    /// Create a linked worktree under the first configured `worktrees_dirs` entry
+    ///
+    /// The branch defaults to the worktree name. An existing branch is checked out
+    /// as-is; otherwise it is created from `base_ref` (or the current HEAD).
+    pub fn create_worktree(&self, name: &str, branch: Option<&str>, base_ref: Option<&str>) -> Result<Worktree> {
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            return Err(LifecycleError::InvalidName(name.to_string()).into());
+        }
+        if self.git_worktrees()?.is_empty() {
+            return Err(LifecycleError::NotARepository(self.workspace_path.clone()).into());
         }
         
-        // Simple extraction: look for first task in "## Specific Tasks" section
-        if let Ok(content) = std::fs::read_to_string(&claude_local) {
-            let lines: Vec<&str> = content.lines().collect();
-            let mut in_tasks = false;
+        let parent = self.config.worktrees_dirs.first()
+            .map(|dir| self.resolve(dir))
+            .unwrap_or_else(|| self.workspace_path.clone());
+        let path = parent.join(name);
+        if path.exists() {
+            return Err(LifecycleError::PathExists(path).into());
+        }
+        
+        let branch = branch.unwrap_or(name);
+        let branch_exists = self.git(&self.workspace_path)
+            .args(["rev-parse", "--verify", "--quiet", &format!("refs/heads/{}", branch)])
+            .output()
+            .context("Failed to check branch")?
+            .status
+            .success();
             
-            for line in lines {
-                if line.starts_with("## Specific Tasks") {
-                    in_tasks = true;
-                } else if in_tasks && line.starts_with("1.") {
-                    return Some(line.trim_start_matches("1.").trim().to_string());
-                } else if in_tasks && line.starts_with("##") {
-                    break; // End of tasks section
-                }
+        let path_arg = path.to_string_lossy().to_string();
+        let mut args = vec!["worktree", "add"];
+        match (branch_exists, base_ref) {
+            (true, Some(_)) => return Err(LifecycleError::BranchExists(branch.to_string()).into()),
+            (true, None) => args.extend([path_arg.as_str(), branch]),
+            (false, base) => {
+                args.extend(["-b", branch, path_arg.as_str()]);
+                args.extend(base);
+            }
+        }
+        self.run_git(&args)?;
+        
+        Ok(Worktree {
+            name: name.to_string(),
+            path,
+            branch: Some(branch.to_string()),
+            ..Worktree::default()
+        })
+    }
+    
+    /// Write `CLAUDE.local.md` into a worktree so task extraction works immediately
+    ///
+    /// `{name}` and `{branch}` in the template are substituted. An existing file is
+    /// left untouched. Returns whether a file was written.
+    pub fn seed_task_file(&self, worktree: &Worktree, template: Option<&str>) -> Result<bool> {
+        let target = worktree.path.join("CLAUDE.local.md");
+        if target.exists() {
+            return Ok(false);
+        }
+        let content = template.unwrap_or(DEFAULT_TASK_TEMPLATE)
+            .replace("{name}", &worktree.name)
+            .replace("{branch}", worktree.branch.as_deref().unwrap_or(""));
+        std::fs::write(&target, content)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+        Ok(true)
+    }
+    
+    /// Remove a linked worktree, refusing if it has uncommitted changes unless `force`
+    pub fn remove_worktree(&self, name: &str, force: bool) -> Result<()> {
+        let git_worktrees = self.git_worktrees()?;
+        let position = git_worktrees.iter().position(|w| w.name == name);
+        let worktree = match position {
+            Some(0) => return Err(LifecycleError::MainWorktree(name.to_string()).into()),
+            Some(index) => &git_worktrees[index],
+            None => return Err(LifecycleError::NotFound(name.to_string()).into()),
+        };
+        
+        if !force && worktree.path.exists() {
+            let status = self.read_status(&worktree.path)?;
+            let files = parse_status_v2(&status).len() + parse_submodules_v2(&status).len();
+            if files > 0 {
+                return Err(LifecycleError::UncommittedChanges { name: name.to_string(), files }.into());
             }
         }
         
+        let path_arg = worktree.path.to_string_lossy().to_string();
+        let mut args = vec!["worktree", "remove"];
+        if force {
+            args.push("--force");
+        }
+        args.push(&path_arg);
+        self.run_git(&args)?;
+        Ok(())
+    }
+    
+    /// Prune administrative data for worktrees whose directories were deleted
+    ///
+    /// Returns git's description of each pruned entry.
+    pub fn prune(&self) -> Result<Vec<String>> {
+        let output = self.run_git(&["worktree", "prune", "--verbose"])?;
+        Ok(output.lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect())
+    }
+    
+    /// Run git in the workspace, returning combined output or a `LifecycleError::Git`
+    fn run_git(&self, args: &[&str]) -> Result<String> {
+        let output = self.git(&self.workspace_path)
+            .args(args)
+            .output()
+            .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+            
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if !output.status.success() {
+            return Err(LifecycleError::Git {
+                command: format!("git {}", args.join(" ")),
+                stderr,
+            }.into());
+        }
+        // git worktree prune --verbose reports on stderr
+        Ok(format!("{}{}", String::from_utf8_lossy(&output.stdout), stderr))
+    }
+}
+
+fn operation_in_git_dir(git_dir: &Path) -> Option<GitOperation> {
+    if git_dir.join("rebase-merge").is_dir() || git_dir.join("rebase-apply").is_dir() {
+        Some(GitOperation::Rebase)
+    } else if git_dir.join("MERGE_HEAD").exists() {
+        Some(GitOperation::Merge)
+    } else if git_dir.join("CHERRY_PICK_HEAD").exists() {
+        Some(GitOperation::CherryPick)
+    } else if git_dir.join("REVERT_HEAD").exists() {
+        Some(GitOperation::Revert)
+    } else {
         None
     }
-}
\ No newline at end of file
+}
+
+fn worktree_name(path: &Path) -> String {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+/// Parse `git worktree list --porcelain` output into worktrees
+///
+/// Records are separated by blank lines; each starts with `worktree <path>` and is
+/// followed by `HEAD <sha>`, `branch <ref>` or `detached`, and optional `locked`/
+/// `prunable` lines (which may carry a reason after a space).
+fn parse_worktree_list(output: &str) -> Vec<Worktree> {
+    let mut worktrees = Vec::new();
+    let mut current: Option<Worktree> = None;
+    
+    for line in output.lines() {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            worktrees.extend(current.take());
+            let path = PathBuf::from(path);
+            current = Some(Worktree {
+                name: worktree_name(&path),
+                path,
+                ..Worktree::default()
+            });
+            continue;
+        }
+        
+        let Some(worktree) = current.as_mut() else {
+            continue;
+        };
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match key {
+            // An all-zero HEAD means the branch has no commits yet
+            "HEAD" if !value.chars().all(|c| c == '0') => {
+                worktree.head = Some(value.to_string());
+            }
+            "branch" => {
+                worktree.branch = Some(value.trim_start_matches("refs/heads/").to_string());
+            }
+            "locked" => worktree.locked = true,
+            "prunable" => worktree.prunable = true,
+            _ => {}
+        }
+    }
+    worktrees.extend(current);
+    
+    worktrees
+}
+
+/// Parse NUL-separated `git status --porcelain=v2 -z` output
+///
+/// Ordinary entries start with `1`, renames/copies with `2` (followed by a separate
+/// NUL-terminated original path), unmerged entries with `u`, and untracked with `?`.
+/// Submodule entries (a `sub` field starting with `S`) are left out; see
+/// `parse_submodules_v2`.
+fn parse_status_v2(output: &str) -> Vec<FileChange> {
+    let mut changes = Vec::new();
+    let mut fields = output.split('\0');
+    
+    while let Some(entry) = fields.next() {
+        let change = |path: &str, status| FileChange {
+            path: path.to_string(),
+            status,
+            insertions: 0,
+            deletions: 0,
+        };
+        
+        match entry.chars().next() {
+            Some('1') => {
+                // 1 XY sub mH mI mW hH hI path
+                let parts: Vec<&str> = entry.splitn(9, ' ').collect();
+                if parts.len() == 9 && !parts[2].starts_with('S') {
+                    changes.push(change(parts[8], ordinary_status(parts[1])));
+                }
+            }
+            Some('2') => {
+                // 2 XY sub mH mI mW hH hI Xscore path, then origPath as the next field
+                let parts: Vec<&str> = entry.splitn(10, ' ').collect();
+                let _orig_path = fields.next();
+                if parts.len() == 10 && !parts[2].starts_with('S') {
+                    let status = if parts[8].starts_with('C') {
+                        ChangeStatus::Added
+                    } else {
+                        ChangeStatus::Renamed
+                    };
+                    changes.push(change(parts[9], status));
+                }
+            }
+            Some('u') => {
+                // u XY sub m1 m2 m3 mW h1 h2 h3 path
+                let parts: Vec<&str> = entry.splitn(11, ' ').collect();
+                if parts.len() == 11 && !parts[2].starts_with('S') {
+                    changes.push(change(parts[10], ChangeStatus::Modified));
+                }
+            }
+            Some('?') => {
+                if let Some(path) = entry.strip_prefix("? ") {
+                    changes.push(change(path, ChangeStatus::Untracked));
+                }
+            }
+            _ => {}
+        }
+    }
+    
+    changes
+}
+
+/// Paths of submodules with a changed commit, modified content, or untracked files
+fn parse_submodules_v2(output: &str) -> Vec<String> {
+    let mut submodules = Vec::new();
+    let mut fields = output.split('\0');
+    
+    while let Some(entry) = fields.next() {
+        let (field_count, path_index) = match entry.chars().next() {
+            Some('1') => (9, 8),
+            Some('2') => {
+                let _orig_path = fields.next();
+                (10, 9)
+            }
+            Some('u') => (11, 10),
+            _ => continue,
+        };
+        let parts: Vec<&str> = entry.splitn(field_count, ' ').collect();
+        if parts.len() == field_count && parts[2].starts_with('S') {
+            submodules.push(parts[path_index].to_string());
+        }
+    }
+    
+    submodules
+}
+
+fn ordinary_status(xy: &str) -> ChangeStatus {
+    if xy.contains('D') {
+        ChangeStatus::Deleted
+    } else if xy.contains('A') {
+        ChangeStatus::Added
+    } else {
+        ChangeStatus::Modified
+    }
+}
+
+/// Parse `git diff --numstat -z` output into per-path line counts
+///
+/// Renames are emitted as `ins\tdel\t` followed by the old and new paths as separate
+/// NUL-terminated fields; counts are keyed by the new path. Binary files count as 0.
+fn parse_numstat_z(output: &str) -> HashMap<String, (usize, usize)> {
+    let mut counts = HashMap::new();
+    let mut fields = output.split('\0');
+    
+    while let Some(entry) = fields.next() {
+        let mut parts = entry.splitn(3, '\t');
+        let (Some(ins), Some(del), Some(path)) = (parts.next(), parts.next(), parts.next()) else {
+            continue;
+        };
+        let path = if path.is_empty() {
+            let _old = fields.next();
+            fields.next().unwrap_or_default()
+        } else {
+            path
+        };
+        counts.insert(
+            path.to_string(),
+            (ins.parse().unwrap_or(0), del.parse().unwrap_or(0)),
+        );
+    }
+    
+    counts
+}
+
+/// Branch named in a stash reflog subject: `WIP on <branch>: ...` or `On <branch>: ...`
+fn stash_branch(subject: &str) -> Option<&str> {
+    let rest = subject.strip_prefix("WIP on ").or_else(|| subject.strip_prefix("On "))?;
+    rest.split_once(':').map(|(branch, _)| branch)
+}
+
+/// Parse `git log -1 --format=%cI%x1f%ar` into the commit date and its relative form
+fn parse_last_commit(output: &str) -> (Option<DateTime<FixedOffset>>, Option<String>) {
+    let Some((date, relative)) = output.trim().split_once('\x1f') else {
+        return (None, None);
+    };
+    let date = DateTime::parse_from_rfc3339(date).ok();
+    let relative = Some(relative.to_string()).filter(|r| !r.is_empty());
+    (date, relative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::{git, git_command, init_repo};
+    use crate::monitor::diff::DiffTracker;
+
+    #[test]
+    fn test_parse_worktree_list() {
+        let output = "worktree /repo\nHEAD 1111111111111111111111111111111111111111\nbranch refs/heads/main\n\n\
+                      worktree /elsewhere/feature-x\nHEAD 2222222222222222222222222222222222222222\ndetached\nlocked in use\n\n\
+                      worktree /gone\nHEAD 0000000000000000000000000000000000000000\nbranch refs/heads/fresh\nprunable gitdir file points to non-existent location\n";
+        let worktrees = parse_worktree_list(output);
+        
+        assert_eq!(worktrees.len(), 3);
+        assert_eq!(worktrees[0].name, "repo");
+        assert_eq!(worktrees[0].branch.as_deref(), Some("main"));
+        assert_eq!(worktrees[1].name, "feature-x");
+        assert_eq!(worktrees[1].branch, None);
+        assert!(worktrees[1].locked);
+        assert_eq!(worktrees[2].head, None);
+        assert!(worktrees[2].prunable);
+    }
+
+    #[test]
+    fn test_scan_discovers_linked_worktrees() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().join("repo");
+        std::fs::create_dir(&repo).unwrap();
+        git(&repo, &["init", "-q", "-b", "main"]);
+        git(&repo, &["commit", "-q", "--allow-empty", "-m", "init"]);
+        git(&repo, &["worktree", "add", "-q", "../feature-x"]);
+        git(&repo, &["worktree", "add", "-q", "-b", "feature-y", "../nested/feature-y"]);
+        
+        let monitor = WorktreeMonitor::new(repo.clone(), None).unwrap();
+        let mut names: Vec<String> = monitor.scan_worktrees().unwrap()
+            .into_iter()
+            .map(|w| w.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["feature-x", "feature-y", "repo"]);
+        
+        let feature_y = monitor.find_worktree("feature-y").unwrap();
+        assert_eq!(feature_y.branch.as_deref(), Some("feature-y"));
+        assert!(feature_y.path.ends_with("nested/feature-y"));
+        assert!(monitor.find_worktree("missing").is_err());
+    }
+
+    #[test]
+    fn test_scan_merges_configured_sources() {
+        let temp = tempfile::tempdir().unwrap();
+        let workspace = temp.path().join("workspace");
+        for repo in ["workspace/wt/alpha", "workspace/wt/beta", "outside/gamma"] {
+            let path = temp.path().join(repo);
+            std::fs::create_dir_all(&path).unwrap();
+            git(&path, &["init", "-q"]);
+        }
+        
+        let gamma = temp.path().join("outside/gamma");
+        let config = MonitorConfig {
+            worktrees_dirs: vec!["wt".to_string(), "missing-dir".to_string()],
+            extra_worktrees: vec![
+                gamma.to_string_lossy().to_string(),
+                "../outside/gamma".to_string(),
+                "wt/alpha".to_string(),
+                "../outside/deleted".to_string(),
+            ],
+            ..MonitorConfig::default()
+        };
+        
+        let monitor = WorktreeMonitor::new(workspace, Some(config)).unwrap();
+        let names: Vec<String> = monitor.scan_worktrees().unwrap()
+            .into_iter()
+            .map(|w| w.name)
+            .collect();
+        assert_eq!(names, vec!["alpha", "beta", "gamma"]);
+    }
+
+    fn lifecycle_error(result: Result<impl std::fmt::Debug>) -> LifecycleError {
+        result.unwrap_err().downcast::<LifecycleError>().unwrap()
+    }
+
+    #[test]
+    fn test_parse_status_v2_separates_submodules() {
+        let output = "1 .M N... 100644 100644 100644 aaa bbb src/lib.rs\0\
+                      1 .M SC.. 160000 160000 160000 ccc ccc vendor/lib\0\
+                      1 .M S.M. 160000 160000 160000 ddd ddd vendor/other\0";
+        let changes = parse_status_v2(output);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "src/lib.rs");
+        assert_eq!(parse_submodules_v2(output), vec!["vendor/lib", "vendor/other"]);
+    }
+
+    #[test]
+    fn test_submodule_pointer_update_is_reported_separately() {
+        let temp = tempfile::tempdir().unwrap();
+        let sub = temp.path().join("sub");
+        let repo = temp.path().join("repo");
+        for dir in [&sub, &repo] {
+            std::fs::create_dir(dir).unwrap();
+            git(dir, &["init", "-q", "-b", "main"]);
+        }
+        std::fs::write(sub.join("lib.txt"), "v1\n").unwrap();
+        git(&sub, &["add", "."]);
+        git(&sub, &["commit", "-q", "-m", "v1"]);
+        git(&repo, &["-c", "protocol.file.allow=always", "submodule", "add", "-q", sub.to_str().unwrap(), "vendor/sub"]);
+        git(&repo, &["commit", "-q", "-m", "add submodule"]);
+        
+        let nested = repo.join("vendor/sub");
+        std::fs::write(nested.join("lib.txt"), "v2\n").unwrap();
+        git(&nested, &["commit", "-q", "-am", "v2"]);
+        
+        let monitor = WorktreeMonitor::new(repo.clone(), None).unwrap();
+        let status = monitor.get_status(&monitor.find_worktree("repo").unwrap()).unwrap();
+        assert_eq!(status.submodules_changed, vec!["vendor/sub"]);
+        assert!(status.changes.is_empty());
+        assert_eq!(status.status, "idle");
+        
+        let worktree = monitor.find_worktree("repo").unwrap();
+        let filter = crate::monitor::diff::DiffFilter::new();
+        let shallow = DiffTracker::new().get_diff_for_worktree(&worktree, &filter).unwrap();
+        assert!(shallow.contains("Subproject commit") && !shallow.contains("+v2"));
+        let recursive = DiffTracker::new().with_include_submodules(true)
+            .get_diff_for_worktree(&worktree, &filter).unwrap();
+        assert!(recursive.contains("+v2"));
+    }
+
+    #[test]
+    fn test_stash_count_is_per_branch() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().join("repo");
+        init_repo(&repo, &[("file.txt", "base\n")]);
+        git(&repo, &["worktree", "add", "-q", "-b", "feature", "../feature"]);
+        
+        let feature = temp.path().join("feature");
+        for i in 0..2 {
+            std::fs::write(feature.join("file.txt"), format!("change {}\n", i)).unwrap();
+            git(&feature, &["stash", "push", "-q", "-m", "wip: draft"]);
+        }
+        
+        let monitor = WorktreeMonitor::new(repo.clone(), None).unwrap();
+        let feature_status = monitor.get_status(&monitor.find_worktree("feature").unwrap()).unwrap();
+        let main_status = monitor.get_status(&monitor.find_worktree("repo").unwrap()).unwrap();
+        assert_eq!(feature_status.stash_count, 2);
+        assert_eq!(feature_status.unsaved_work(), vec!["2 stashes"]);
+        assert_eq!(main_status.stash_count, 0);
+        assert_eq!(stash_branch("WIP on fix/a: 1234567 msg"), Some("fix/a"));
+        assert_eq!(stash_branch("On main: wip: draft"), Some("main"));
+    }
+
+    #[test]
+    fn test_worktree_lifecycle() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().join("repo");
+        std::fs::create_dir(&repo).unwrap();
+        git(&repo, &["init", "-q", "-b", "main"]);
+        git(&repo, &["commit", "-q", "--allow-empty", "-m", "init"]);
+        let monitor = WorktreeMonitor::new(repo.clone(), None).unwrap();
+        
+        let created = monitor.create_worktree("feature", None, Some("main")).unwrap();
+        assert_eq!(created.path, repo.join("worktrees/feature"));
+        assert!(monitor.seed_task_file(&created, Some("## Specific Tasks\n1. Build {name} on {branch}\n")).unwrap());
+        assert!(!monitor.seed_task_file(&created, None).unwrap());
+        let status = monitor.get_status(&monitor.find_worktree("feature").unwrap()).unwrap();
+        assert_eq!(status.branch, "feature");
+        assert_eq!(status.current_task.as_deref(), Some("Build feature on feature"));
+        
+        assert_eq!(lifecycle_error(monitor.create_worktree("../escape", None, None)),
+            LifecycleError::InvalidName("../escape".to_string()));
+        assert_eq!(lifecycle_error(monitor.create_worktree("other", Some("feature"), Some("main"))),
+            LifecycleError::BranchExists("feature".to_string()));
+        assert_eq!(lifecycle_error(monitor.remove_worktree("repo", true)),
+            LifecycleError::MainWorktree("repo".to_string()));
+        assert_eq!(lifecycle_error(monitor.remove_worktree("feature", false)),
+            LifecycleError::UncommittedChanges { name: "feature".to_string(), files: 1 });
+        
+        monitor.remove_worktree("feature", true).unwrap();
+        assert!(!created.path.exists());
+        assert_eq!(lifecycle_error(monitor.remove_worktree("feature", false)),
+            LifecycleError::NotFound("feature".to_string()));
+        
+        let stale = monitor.create_worktree("stale", None, None).unwrap();
+        std::fs::remove_dir_all(&stale.path).unwrap();
+        assert_eq!(monitor.prune().unwrap().len(), 1);
+        assert!(monitor.git_worktrees().unwrap().iter().all(|w| w.name != "stale"));
+    }
+
+    #[test]
+    fn test_detects_conflicted_merge_in_linked_worktree() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().join("repo");
+        std::fs::create_dir(&repo).unwrap();
+        let commit = ["commit", "-q", "-am"];
+        git(&repo, &["init", "-q", "-b", "main"]);
+        std::fs::write(repo.join("file.txt"), "base\n").unwrap();
+        git(&repo, &["add", "."]);
+        git(&repo, &[&commit[..], &["base"]].concat());
+        git(&repo, &["worktree", "add", "-q", "-b", "feature", "../feature"]);
+        
+        std::fs::write(repo.join("file.txt"), "main side\n").unwrap();
+        git(&repo, &[&commit[..], &["main change"]].concat());
+        
+        let feature = temp.path().join("feature");
+        std::fs::write(feature.join("file.txt"), "feature side\n").unwrap();
+        git(&feature, &[&commit[..], &["feature change"]].concat());
+        
+        let monitor = WorktreeMonitor::new(repo.clone(), None).unwrap();
+        let worktree = monitor.find_worktree("feature").unwrap();
+        assert!(feature.join(".git").is_file());
+        assert_eq!(monitor.get_status(&worktree).unwrap().operation, None);
+        
+        // The merge is expected to stop on the conflict
+        let _ = git_command(&feature).args(["merge", "main"]).output();
+        
+        let status = monitor.get_status(&worktree).unwrap();
+        assert_eq!(status.operation, Some(GitOperation::Merge));
+        assert!(status.has_conflicts);
+    }
+
+    #[test]
+    fn test_changes_expand_untracked_and_count_lines() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path();
+        init_repo(
+            repo,
+            &[
+                ("tracked.txt", "one\ntwo\n"),
+                ("old name.txt", "keep\nthese\nlines\n"),
+            ],
+        );
+        
+        std::fs::write(repo.join("tracked.txt"), "one\nthree\nfour\n").unwrap();
+        git(repo, &["mv", "old name.txt", "new name.txt"]);
+        std::fs::create_dir_all(repo.join("scratch/deep")).unwrap();
+        std::fs::write(repo.join("scratch/a.txt"), "a").unwrap();
+        std::fs::write(repo.join("scratch/deep/b.txt"), "b").unwrap();
+        
+        let monitor = WorktreeMonitor::new(repo.to_path_buf(), None).unwrap();
+        let mut changes = monitor.get_changes(repo).unwrap();
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        
+        let summary: Vec<(&str, ChangeStatus, usize, usize)> = changes.iter()
+            .map(|c| (c.path.as_str(), c.status, c.insertions, c.deletions))
+            .collect();
+        assert_eq!(summary, vec![
+            ("new name.txt", ChangeStatus::Renamed, 0, 0),
+            ("scratch/a.txt", ChangeStatus::Untracked, 0, 0),
+            ("scratch/deep/b.txt", ChangeStatus::Untracked, 0, 0),
+            ("tracked.txt", ChangeStatus::Modified, 2, 1),
+        ]);
+        
+        let status = monitor.get_status(&Worktree {
+            name: "repo".to_string(),
+            path: repo.to_path_buf(),
+            ..Worktree::default()
+        }).unwrap();
+        assert_eq!(status.files_changed, 2);
+        assert_eq!(status.changes.len(), 4);
+    }
+}
@@ -0,0 +1,316 @@
+//! Non-blocking writer that hands log lines to a dedicated writer thread
+//!
+//! Similar to `tracing_appender::non_blocking`: each write pushes the formatted
+//! line onto a bounded queue and returns immediately, and a background thread
+//! drains the queue into the wrapped writer. A slow disk then delays the log
+//! file, not the code that logs.
+//!
+//! When the queue is full, [`WhenFull::DropOldest`] discards the oldest queued
+//! line to make room (the caller never waits) and [`WhenFull::Block`] makes the
+//! caller wait for the writer thread. Dropped lines are counted by
+//! [`NonBlockingWorker::dropped_lines`].
+//!
+//! The [`NonBlockingWorker`] owns the thread: dropping it writes out everything
+//! still queued, flushes, and joins the thread. Keep it alive until shutdown, and
+//! call [`NonBlockingWorker::flush`] before exiting without running destructors.
+//! Lines written after the worker is dropped are themselves dropped and counted.
+
+use crate::config::WhenFull;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::thread::{JoinHandle, ThreadId};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Queue shared by the writers and the writer thread
+struct Shared {
+    state: Mutex<State>,
+    /// Signalled when a line is queued or the queue is closed
+    not_empty: Condvar,
+    /// Signalled when the writer thread takes lines off the queue or finishes a batch
+    not_full: Condvar,
+    capacity: usize,
+    when_full: WhenFull,
+    dropped: AtomicU64,
+    /// Events logged by the wrapped writer itself come from this thread
+    writer_thread: OnceLock<ThreadId>,
+}
+
+struct State {
+    lines: VecDeque<Vec<u8>>,
+    closed: bool,
+    /// The writer thread is writing a batch taken off the queue
+    busy: bool,
+}
+
+/// Cloneable writer and `MakeWriter` feeding the writer thread
+#[derive(Clone)]
+pub struct NonBlocking {
+    shared: Arc<Shared>,
+}
+
+/// Owner of the writer thread; flushes and joins it on drop
+pub struct NonBlockingWorker {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Start a writer thread draining into `writer`, buffering up to `capacity` lines
+pub fn non_blocking<W: Write + Send + 'static>(
+    writer: W,
+    capacity: usize,
+    when_full: WhenFull,
+) -> Result<(NonBlocking, NonBlockingWorker), std::io::Error> {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State { lines: VecDeque::new(), closed: false, busy: false }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+        capacity: capacity.max(1),
+        when_full,
+        dropped: AtomicU64::new(0),
+        writer_thread: OnceLock::new(),
+    });
+    let thread = {
+        let shared = shared.clone();
+        std::thread::Builder::new()
+            .name("autodebugger-log-writer".to_string())
+            .spawn(move || drain(&shared, writer))?
+    };
+    Ok((
+        NonBlocking { shared: shared.clone() },
+        NonBlockingWorker { shared, thread: Some(thread) },
+    ))
+}
+
+/// Writer thread body: write queued lines in batches until closed and empty
+fn drain<W: Write>(shared: &Shared, mut writer: W) {
+    let _ = shared.writer_thread.set(std::thread::current().id());
+    loop {
+        let (batch, closed) = {
+            let mut state = shared.state.lock().unwrap();
+            while state.lines.is_empty() && !state.closed {
+                state = shared.not_empty.wait(state).unwrap();
+            }
+            state.busy = true;
+            (std::mem::take(&mut state.lines), state.closed)
+        };
+        shared.not_full.notify_all();
+        for line in &batch {
+            // Nowhere to report a failed log write; keep going with the next line
+            let _ = writer.write_all(line);
+        }
+        let _ = writer.flush();
+        shared.state.lock().unwrap().busy = false;
+        shared.not_full.notify_all();
+        if closed {
+            return;
+        }
+    }
+}
+
+impl NonBlocking {
+    /// Lines dropped because the queue was full or the worker had stopped
+    pub fn dropped_lines(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    fn push(&self, line: Vec<u8>) {
+        let shared = &self.shared;
+        let mut state = shared.state.lock().unwrap();
+        if state.closed {
+            shared.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        if state.lines.len() >= shared.capacity {
+            // The writer thread waiting for room would wait on itself
+            let on_writer_thread = shared.writer_thread.get() == Some(&std::thread::current().id());
+            if shared.when_full == WhenFull::Block && !on_writer_thread {
+                while state.lines.len() >= shared.capacity && !state.closed {
+                    state = shared.not_full.wait(state).unwrap();
+                }
+                if state.closed {
+                    shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+            } else {
+                state.lines.pop_front();
+                shared.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        state.lines.push_back(line);
+        drop(state);
+        shared.not_empty.notify_one();
+    }
+}
+
+impl Write for NonBlocking {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // The fmt layer writes each formatted event in one call, so one write is one line
+        self.push(buf.to_vec());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        // The writer thread flushes after each batch; waiting here would block the caller
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for NonBlocking {
+    type Writer = NonBlocking;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+impl NonBlockingWorker {
+    /// Lines dropped because the queue was full or the worker had stopped
+    pub fn dropped_lines(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Wait until every line queued so far is written and flushed, e.g. before
+    /// `std::process::exit`, which skips the flush on drop
+    pub fn flush(&self) {
+        // The writer thread cannot wait for itself, e.g. when it panics
+        if self.shared.writer_thread.get() == Some(&std::thread::current().id()) {
+            return;
+        }
+        let mut state = self.shared.state.lock().unwrap();
+        while !state.lines.is_empty() || state.busy {
+            state = self.shared.not_full.wait(state).unwrap();
+        }
+    }
+}
+
+impl Drop for NonBlockingWorker {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().closed = true;
+        self.shared.not_empty.notify_all();
+        self.shared.not_full.notify_all();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    /// Reports each write as it starts, then holds it until the go-ahead
+    struct Gate {
+        started: mpsc::Sender<()>,
+        go: Mutex<mpsc::Receiver<()>>,
+    }
+
+    /// Writer standing in for a slow disk: each write sleeps, then is recorded
+    #[derive(Clone)]
+    struct SlowWriter {
+        delay: Duration,
+        written: Arc<Mutex<Vec<u8>>>,
+        gate: Option<Arc<Gate>>,
+    }
+
+    impl SlowWriter {
+        fn new(delay: Duration) -> Self {
+            Self { delay, written: Arc::new(Mutex::new(Vec::new())), gate: None }
+        }
+
+        fn lines(&self) -> Vec<String> {
+            String::from_utf8(self.written.lock().unwrap().clone()).unwrap()
+                .lines().map(str::to_string).collect()
+        }
+    }
+
+    impl Write for SlowWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if let Some(gate) = &self.gate {
+                let _ = gate.started.send(());
+                let _ = gate.go.lock().unwrap().recv();
+            }
+            std::thread::sleep(self.delay);
+            self.written.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_writes_do_not_wait_for_slow_disk() {
+        let slow = SlowWriter::new(Duration::from_millis(50));
+        let (mut writer, worker) = non_blocking(slow.clone(), 100, WhenFull::Block).unwrap();
+
+        let start = Instant::now();
+        for i in 0..20 {
+            writer.write_all(format!("line {}\n", i).as_bytes()).unwrap();
+        }
+        // Written synchronously this would take 20 * 50ms
+        assert!(start.elapsed() < Duration::from_millis(500), "writes blocked for {:?}", start.elapsed());
+
+        worker.flush();
+        let expected: Vec<String> = (0..20).map(|i| format!("line {}", i)).collect();
+        assert_eq!(slow.lines(), expected);
+
+        // Dropping the worker writes out the rest before returning
+        writer.write_all(b"last\n").unwrap();
+        drop(worker);
+        assert_eq!(slow.lines().last().map(String::as_str), Some("last"));
+        assert_eq!(writer.dropped_lines(), 0);
+    }
+
+    #[test]
+    fn test_drop_oldest_when_full() {
+        let (started_tx, started_rx) = mpsc::channel();
+        let (go_tx, go_rx) = mpsc::channel();
+        let mut slow = SlowWriter::new(Duration::ZERO);
+        slow.gate = Some(Arc::new(Gate { started: started_tx, go: Mutex::new(go_rx) }));
+        let (mut writer, worker) = non_blocking(slow.clone(), 2, WhenFull::DropOldest).unwrap();
+
+        // The writer thread takes "a" and stalls writing it
+        writer.write_all(b"a\n").unwrap();
+        started_rx.recv().unwrap();
+        // "b" and "c" fill the queue; "d", "e", "f" each push out the oldest
+        for line in ["b", "c", "d", "e", "f"] {
+            writer.write_all(format!("{}\n", line).as_bytes()).unwrap();
+        }
+        assert_eq!(worker.dropped_lines(), 3);
+
+        drop(go_tx);
+        drop(worker);
+        assert_eq!(slow.lines(), vec!["a", "e", "f"]);
+        assert_eq!(writer.dropped_lines(), 3);
+    }
+
+    #[test]
+    fn test_block_when_full_keeps_every_line() {
+        let slow = SlowWriter::new(Duration::from_millis(2));
+        let (writer, worker) = non_blocking(slow.clone(), 1, WhenFull::Block).unwrap();
+
+        let threads: Vec<_> = (0..4).map(|t| {
+            let mut writer = writer.clone();
+            std::thread::spawn(move || {
+                for i in 0..10 {
+                    writer.write_all(format!("{}-{}\n", t, i).as_bytes()).unwrap();
+                }
+            })
+        }).collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        drop(worker);
+
+        assert_eq!(slow.lines().len(), 40);
+        assert_eq!(writer.dropped_lines(), 0);
+        // Writes after shutdown are counted, not lost silently
+        writer.clone().write_all(b"late\n").unwrap();
+        assert_eq!(writer.dropped_lines(), 1);
+    }
+}
@@ -6,10 +6,13 @@
 //!
 //! ## Features
 //!
-//! - **Automatic Detection**: Identifies `debug!` macro calls in Rust files
-//! - **Multi-line Support**: Handles debug statements that span multiple lines
+//! - **Automatic Detection**: Identifies `debug!` macro calls in Rust files, or any
+//!   configured set of macros (`trace!`, `info!`, `println!`, `dbg!`, ...)
+//! - **Multi-line Support**: Follows a call to its matching `)` across lines and
+//!   removes the whole span
 //! - **Safe Removal**: Preserves code structure and indentation
-//! - **Dry Run Mode**: Preview changes without modifying files
+//! - **Dry Run Mode**: Preview changes without modifying files, as a unified diff
+//!   per file (`FileReport::diff`) that `patch`/`git apply` accept
 //! - **Detailed Reporting**: Generates comprehensive removal reports
 //! - **Directory Traversal**: Recursively processes entire directory trees
 //!
@@ -33,35 +36,124 @@
 //! ## Algorithm
 //!
 //! The removal process uses a combination of regex patterns and state tracking:
-//! 1. Identifies lines starting with `debug!` macro calls
-//! 2. Tracks parenthesis nesting to handle multi-line statements
-//! 3. Preserves surrounding code structure
-//! 4. Handles edge cases like debug statements in closures
+//! 1. Finds string, raw string, byte string, and char literals and comments in a
+//!    single pass; calls inside literals are ignored and calls in comments are
+//!    only warned about
+//! 2. Identifies lines starting with `debug!` macro calls
+//! 3. Tracks bracket nesting to the matching `)`, skipping over literals, so
+//!    multi-line calls are removed as a unit
+//! 4. Removes the span only if the closing line ends in `;` (optionally followed by
+//!    a comment); code after the call is warned about and left alone
+//! 5. Removes `let _span = debug_span!(...).entered();` statements for each
+//!    configured tracing level, unless the binding is used later in the file
+//! 6. Preserves surrounding code structure
 //!
 //! ## Limitations
 //!
-//! - Only removes `debug!` macros (not `println!`, `eprintln!`, etc.)
+//! - Only `debug!` is removed unless other macros are configured (`with_macros`)
+//! - `dbg!(expr)` whose value is used is rewritten to `expr` only for a single,
+//!   non-compound argument; other uses are skipped with a warning
 //! - May struggle with extremely complex nested macro invocations
 //! - Preserves comments that appear within debug statements
+//! - In a file mixing LF and CRLF, rewritten files use the dominant ending throughout
 //!
 //! ## Configuration
 //!
 //! The module respects configuration from `config.yaml`:
 //! - `remove_debug.default_paths`: Default directories to process
+//! - `remove_debug.macros`: Macros to remove (default: `debug`)
+//! - `remove_debug.jobs`: Files processed in parallel (default: one per CPU)
 //! - Can be overridden via CLI arguments
 //!
+//! ## Review
+//!
+//! `with_decision_fn` asks a callback about every removal and `dbg!` rewrite
+//! (a `RemovalCandidate` with its surrounding lines) before anything is written;
+//! the CLI's `--interactive` prompts on the terminal through it.
+//!
+//! ## Branch Scope
+//!
+//! `with_git_scope("main")` limits removal to lines the current branch added
+//! since its merge base with `main` (see `GitScope`), so calls that already exist
+//! upstream are left alone and reported as warnings.
+//!
+//! `session()` builds that scope and the compiled macro patterns once, and
+//! `RemovalSession::process_file` then handles one file at a time, as
+//! `remove-debug --check --watch` does for each file saved.
+//!
 //! ## Safety
 //!
 //! The removal process is designed to be safe:
-//! - Always creates backups before modifying files
+//! - Backs up every file before modifying it (unless disabled with
+//!   `with_backups(false)`); `restore_backup` puts a run back
 //! - Validates parenthesis matching to avoid breaking code
+//! - Keeps each file's dominant line ending (LF or CRLF), byte order mark, and
+//!   final newline (or lack of one); files that are not valid UTF-8 are never
+//!   rewritten and get a warning instead
 //! - Dry-run mode allows previewing all changes first
 
 use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::Instant;
+use rayon::prelude::*;
 use tracing::{info, warn};
 use regex::Regex;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::backup::{BackupStore, RestoreReport};
+use crate::monitor::diff::parse_unified_diff;
+use crate::text_diff::unified_diff;
+
+#[cfg(feature = "ast")]
+mod ast;
+
+/// tracing levels whose `<level>_span!` is removed along with `<level>!`
+const SPAN_LEVELS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
+
+/// Context lines around each hunk of `FileReport::diff`
+const DIFF_CONTEXT: usize = 3;
+
+/// Files listed in each top-N table of the verbose summary
+const TOP_FILES: usize = 10;
+
+/// Macros removed when none are configured
+pub fn default_macros() -> Vec<String> {
+    vec!["debug".to_string()]
+}
+
+/// Backup runs kept when not configured
+pub fn default_backup_retention() -> usize {
+    10
+}
+
+/// How calls are located in a file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    /// Line-based matching (always available)
+    #[default]
+    Regex,
+    /// Syntax-tree matching with `syn` (feature `ast`); files that fail to parse
+    /// fall back to `Regex`, recorded in `FileReport::ast_fallback`
+    Ast,
+}
+
+impl std::str::FromStr for Engine {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "regex" => Ok(Engine::Regex),
+            "ast" => Ok(Engine::Ast),
+            _ => anyhow::bail!("Unknown engine '{}' (expected regex or ast)", s),
+        }
+    }
+}
 
 pub struct DebugRemover {
     /// Path to search for Rust files
@@ -70,6 +162,125 @@ pub struct DebugRemover {
     pub dry_run: bool,
     /// Whether to show verbose output
     pub verbose: bool,
+    /// Macro names (without `!`) whose calls are removed
+    pub macros: Vec<String>,
+    /// Only remove calls on lines added since the merge base with this ref
+    pub git_scope: Option<String>,
+    /// Back up files before modifying them (see the `backup` module)
+    pub backups: bool,
+    /// Workspace whose `.autodebugger/backups` holds the backups; defaults to the
+    /// git repository containing `path`, or the current directory
+    pub backup_dir: Option<PathBuf>,
+    /// Run id the backups are filed under
+    pub backup_run: String,
+    /// Number of backup runs kept
+    pub backup_retention: usize,
+    /// Worker threads for directories (0 = one per CPU)
+    pub jobs: usize,
+    /// Collapse blank lines left around removed calls (see `tidy_blank_lines`)
+    pub tidy_whitespace: bool,
+    /// How calls are located
+    pub engine: Engine,
+    /// Asked about every removal and rewrite before it is applied (see `with_decision_fn`)
+    pub decision_fn: Option<DecisionFn>,
+}
+
+/// Review callback: whether to apply one change
+pub type DecisionFn = Box<dyn Fn(&RemovalCandidate) -> Decision + Send + Sync>;
+
+/// A removal or `dbg!` rewrite offered for review
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemovalCandidate {
+    pub path: PathBuf,
+    /// 1-based lines the change covers
+    pub start_line: usize,
+    pub end_line: usize,
+    /// The lines as they are now
+    pub text: String,
+    /// The line after a `dbg!` rewrite; None for a removal
+    pub replacement: Option<String>,
+    /// Up to `DIFF_CONTEXT` lines on either side
+    pub context_before: Vec<String>,
+    pub context_after: Vec<String>,
+}
+
+/// Answer to a review prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// Apply this change
+    Yes,
+    /// Keep the code as it is
+    No,
+    /// Apply this and every later change without asking
+    All,
+    /// Apply nothing more: changes approved so far are written, the rest left alone
+    Quit,
+}
+
+/// State shared by every file in one `remove_debug_calls` run
+struct RunContext {
+    scope: Option<GitScope>,
+    /// Compiled once per run; None when no macros are configured
+    patterns: Option<CallPatterns>,
+    /// Locked while backing up, since each backup rewrites the run's manifest
+    backups: Option<Mutex<BackupStore>>,
+    review: Mutex<ReviewState>,
+}
+
+/// Answers that outlast one file in a review
+#[derive(Debug, Default)]
+struct ReviewState {
+    /// `Decision::All` was given
+    all: bool,
+    /// `Decision::Quit` was given
+    quit: bool,
+}
+
+/// Regexes matching calls of the configured macros
+struct CallPatterns {
+    /// A macro call (optionally path-qualified, e.g. tracing::debug!) at the start
+    /// of a line, up to its opening parenthesis
+    call_start_re: Regex,
+    /// A macro call anywhere in a line (for rewriting dbg! and for warnings)
+    any_call_re: Regex,
+    /// What may follow the closing parenthesis: the semicolon and an optional comment
+    call_end_re: Regex,
+    /// `let <binding> = <level>_span!(` for configured tracing levels, if any
+    span_start_re: Option<Regex>,
+    /// What may follow a span's closing parenthesis: an optional `.entered()`, then `;`
+    span_end_re: Regex,
+}
+
+impl CallPatterns {
+    fn new(macros: &[String]) -> Option<Self> {
+        if macros.is_empty() {
+            return None;
+        }
+        let names = macros.iter().map(|m| regex::escape(m)).collect::<Vec<_>>().join("|");
+        let span_names: Vec<String> = macros.iter()
+            .filter(|m| SPAN_LEVELS.contains(&m.as_str()))
+            .map(|m| format!("{}_span", m))
+            .collect();
+        Some(Self {
+            call_start_re: Regex::new(&format!(r"^\s*(?:(?:::)?\w+::)*({})!\s*\(", names)).unwrap(),
+            any_call_re: Regex::new(&format!(r"\b(?:\w+::)*({})!\s*\(", names)).unwrap(),
+            call_end_re: Regex::new(r"^\s*;\s*(?://.*)?$").unwrap(),
+            span_start_re: (!span_names.is_empty()).then(|| {
+                Regex::new(&format!(
+                    r"^\s*let\s+(?:mut\s+)?(\w+)\s*(?::[^=]*)?=\s*(?:(?:::)?\w+::)*({})!\s*\(",
+                    span_names.join("|")
+                )).unwrap()
+            }),
+            span_end_re: Regex::new(r"^(?:\s*\.\s*entered\s*\(\s*\))?\s*;\s*(?://.*)?$").unwrap(),
+        })
+    }
+}
+
+/// What processing one file amounted to
+enum FileOutcome {
+    /// Unchanged since the `GitScope` base, or reached after the review was quit, so not read
+    OutOfScope,
+    Scanned(FileReport),
 }
 
 impl DebugRemover {
@@ -78,8 +289,30 @@ impl DebugRemover {
             path,
             dry_run: false,
             verbose: false,
+            macros: default_macros(),
+            git_scope: None,
+            backups: true,
+            backup_dir: None,
+            backup_run: BackupStore::new_run_id(),
+            backup_retention: default_backup_retention(),
+            jobs: 0,
+            tidy_whitespace: true,
+            engine: Engine::Regex,
+            decision_fn: None,
         }
     }
+    
+    /// Set which macros to remove, e.g. `["debug", "trace", "dbg"]`
+    ///
+    /// `dbg!` used as an expression is rewritten to its argument instead of removed.
+    pub fn with_macros<I, S>(mut self, macros: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.macros = macros.into_iter().map(Into::into).collect();
+        self
+    }
 
     pub fn with_dry_run(mut self, dry_run: bool) -> Self {
         self.dry_run = dry_run;
@@ -91,170 +324,1935 @@ impl DebugRemover {
         self
     }
 
-    /// Remove all debug! macro calls from Rust files in the given path
+    /// Restrict removal to calls the current branch introduced
+    ///
+    /// Only lines added since the merge base of `base_ref` and HEAD (committed or
+    /// not, plus untracked files) are touched; other calls are skipped with a
+    /// warning, and files without changes are not read at all.
+    pub fn with_git_scope(mut self, base_ref: &str) -> Self {
+        self.git_scope = Some(base_ref.to_string());
+        self
+    }
+
+    /// Back up files before modifying them (on by default)
+    pub fn with_backups(mut self, backups: bool) -> Self {
+        self.backups = backups;
+        self
+    }
+    
+    /// Keep backups under `<dir>/.autodebugger/backups`
+    pub fn with_backup_dir(mut self, dir: PathBuf) -> Self {
+        self.backup_dir = Some(dir);
+        self
+    }
+    
+    /// File backups under this run id, e.g. to share one run across several paths
+    pub fn with_backup_run(mut self, run: String) -> Self {
+        self.backup_run = run;
+        self
+    }
+    
+    /// Number of backup runs to keep; older ones are deleted after each run
+    pub fn with_backup_retention(mut self, runs: usize) -> Self {
+        self.backup_retention = runs;
+        self
+    }
+    
+    /// Number of files processed in parallel; 0 (the default) uses one thread per CPU
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs;
+        self
+    }
+    
+    /// Clean up blank lines that removals leave behind (on by default)
+    pub fn with_tidy_whitespace(mut self, tidy: bool) -> Self {
+        self.tidy_whitespace = tidy;
+        self
+    }
+    
+    /// Locate calls with regexes (default) or, with the `ast` feature, a syntax tree
+    pub fn with_engine(mut self, engine: Engine) -> Self {
+        self.engine = engine;
+        self
+    }
+    
+    /// Review each change before it is applied
+    ///
+    /// `decide` sees every removal and `dbg!` rewrite in turn, with the lines
+    /// around it. Declined calls are kept without a warning; a decision covers
+    /// every change starting on the same line. After `Decision::Quit`, the
+    /// current file gets only the changes approved so far and later files are not
+    /// touched. Files are processed one at a time while reviewing.
+    pub fn with_decision_fn<F>(mut self, decide: F) -> Self
+    where
+        F: Fn(&RemovalCandidate) -> Decision + Send + Sync + 'static,
+    {
+        self.decision_fn = Some(Box::new(decide));
+        self
+    }
+    
+    /// Backup store for this remover's workspace
+    pub fn backup_store(&self) -> BackupStore {
+        let base = self.backup_dir.clone().unwrap_or_else(|| {
+            let dir = if self.path.is_dir() { self.path.as_path() } else { self.path.parent().unwrap_or(Path::new("")) };
+            let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+            git_output(dir, &["rev-parse", "--show-toplevel"])
+                .map(|root| PathBuf::from(root.trim()))
+                .unwrap_or_else(|_| PathBuf::from("."))
+        });
+        BackupStore::new(&base)
+    }
+    
+    /// Put back the files modified in a backup run (the latest if None)
+    ///
+    /// Backups are checked against their recorded checksums, and files edited
+    /// since the run are skipped rather than overwritten.
+    pub fn restore_backup(&self, run: Option<&str>) -> Result<RestoreReport> {
+        self.backup_store().restore(run)
+    }
+
+    /// Remove all configured macro calls from Rust files in the given path
+    ///
+    /// Files in a directory are processed in parallel (see `with_jobs`). A file
+    /// that cannot be read or written is recorded in `RemovalReport::failures`
+    /// and the others are still processed.
     pub fn remove_debug_calls(&self) -> Result<RemovalReport> {
+        let started = Instant::now();
         let mut report = RemovalReport::default();
+        let context = self.run_context()?;
         
         if self.path.is_file() {
-            self.process_file(&self.path, &mut report)?;
+            let outcome = self.process_file(&self.path, &context)?;
+            self.record(&mut report, &self.path, outcome);
         } else if self.path.is_dir() {
-            self.process_directory(&self.path, &mut report)?;
+            self.process_directory(&self.path, &context, &mut report)?;
         } else {
-            anyhow::bail!("Path does not exist: {}", self.path.display());
+            return Err(crate::error::not_found(format_args!("Path does not exist: {}", self.path.display())));
         }
         
+        let backups = context.backups.map(|store| store.into_inner().unwrap_or_else(|e| e.into_inner()));
+        if let Some(store) = backups.filter(|_| report.files_modified > 0) {
+            report.backup_run = Some(self.backup_run.clone());
+            store.prune(self.backup_retention)?;
+        }
+        
+        report.elapsed_ms = started.elapsed().as_millis() as u64;
         Ok(report)
     }
 
-    fn process_directory(&self, dir: &Path, report: &mut RemovalReport) -> Result<()> {
-        for entry in walkdir::WalkDir::new(dir)
+    /// Build the per-run state once, for processing files one at a time as they
+    /// change (`remove-debug --check --watch`)
+    ///
+    /// The session compiles the macro patterns and loads the `--since` scope up
+    /// front; unlike `remove_debug_calls` it never prunes old backup runs.
+    pub fn session(&self) -> Result<RemovalSession<'_>> {
+        Ok(RemovalSession { remover: self, context: self.run_context()? })
+    }
+
+    fn run_context(&self) -> Result<RunContext> {
+        if self.engine == Engine::Ast && !cfg!(feature = "ast") {
+            anyhow::bail!("The ast engine needs autodebugger built with the `ast` feature");
+        }
+        Ok(RunContext {
+            scope: match &self.git_scope {
+                Some(base_ref) => Some(GitScope::load(&self.path, base_ref)?),
+                None => None,
+            },
+            patterns: CallPatterns::new(&self.macros),
+            backups: (self.backups && !self.dry_run).then(|| Mutex::new(self.backup_store())),
+            review: Mutex::default(),
+        })
+    }
+
+    fn process_directory(&self, dir: &Path, context: &RunContext, report: &mut RemovalReport) -> Result<()> {
+        let mut files: Vec<PathBuf> = walkdir::WalkDir::new(dir)
             .into_iter()
             .filter_map(|e| e.ok())
-        {
-            let path = entry.path();
-            if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("rs") {
-                self.process_file(path, report)?;
+            .map(|entry| entry.into_path())
+            .filter(|path| path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("rs"))
+            .collect();
+        // Sorted up front so the report does not depend on thread scheduling
+        files.sort();
+        
+        let outcomes: Vec<Result<FileOutcome>> = if self.decision_fn.is_some() {
+            // Prompts come in file order
+            files.iter().map(|path| self.process_file(path, context)).collect()
+        } else {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(self.jobs)
+                .build()
+                .context("Failed to start worker threads")?;
+            pool.install(|| files.par_iter().map(|path| self.process_file(path, context)).collect())
+        };
+        
+        for (path, outcome) in files.iter().zip(outcomes) {
+            match outcome {
+                Ok(outcome) => self.record(report, path, outcome),
+                Err(e) => {
+                    warn!("Skipping {}: {:#}", path.display(), e);
+                    report.failures.push((path.clone(), format!("{:#}", e)));
+                }
             }
         }
         Ok(())
     }
 
-    fn process_file(&self, path: &Path, report: &mut RemovalReport) -> Result<()> {
-        let content = fs::read_to_string(path)
+    /// Add one file's outcome to the report, logging it in verbose mode
+    fn record(&self, report: &mut RemovalReport, path: &Path, outcome: FileOutcome) {
+        let FileOutcome::Scanned(file_report) = outcome else {
+            return;
+        };
+        report.files_scanned += 1;
+        report.note_elapsed(path, file_report.elapsed_us);
+        // Files with neither changes nor warnings are not worth reporting
+        if !file_report.is_modified() && file_report.warnings.is_empty() && file_report.ast_fallback.is_none() {
+            return;
+        }
+        
+        if file_report.is_modified() {
+            report.files_modified += 1;
+        }
+        report.total_calls_removed += file_report.calls_removed;
+        report.total_calls_rewritten += file_report.calls_rewritten;
+        report.total_lines_removed += file_report.lines_removed;
+        report.total_warnings += file_report.warnings.len();
+        for (name, count) in &file_report.calls_by_macro {
+            *report.calls_by_macro.entry(name.clone()).or_default() += count;
+        }
+        
+        if self.verbose {
+            info!("Processing {}", path.display());
+            info!("  Removed {} call(s) ({} lines)", file_report.calls_removed, file_report.lines_removed);
+            if file_report.calls_rewritten > 0 {
+                info!("  Rewrote {} dbg! expression(s) to their argument", file_report.calls_rewritten);
+            }
+            if self.dry_run {
+                for removal in &file_report.removals {
+                    info!("  Lines {}-{}:\n{}", removal.start_line, removal.end_line, removal.text);
+                }
+            }
+            for warning in &file_report.warnings {
+                warn!("  Line {}: {}", warning.line_number, warning.message);
+            }
+            if let Some(reason) = &file_report.ast_fallback {
+                warn!("  Could not parse ({}); used the regex engine", reason);
+            }
+        }
+        
+        report.file_reports.push((path.to_path_buf(), file_report));
+    }
+
+    /// `scan_file`, timed
+    fn process_file(&self, path: &Path, context: &RunContext) -> Result<FileOutcome> {
+        let started = Instant::now();
+        let mut outcome = self.scan_file(path, context)?;
+        if let FileOutcome::Scanned(file_report) = &mut outcome {
+            file_report.elapsed_us = started.elapsed().as_micros() as u64;
+        }
+        Ok(outcome)
+    }
+    
+    fn scan_file(&self, path: &Path, context: &RunContext) -> Result<FileOutcome> {
+        let added = match &context.scope {
+            Some(scope) => match scope.added_lines(path) {
+                Some(added) => Some(added),
+                // Unchanged since the base: nothing of ours to remove
+                None => return Ok(FileOutcome::OutOfScope),
+            },
+            None => None,
+        };
+        if context.review.lock().unwrap_or_else(|e| e.into_inner()).quit {
+            return Ok(FileOutcome::OutOfScope);
+        }
+        
+        let bytes = fs::read(path)
             .with_context(|| format!("Failed to read file: {}", path.display()))?;
+        let content = match String::from_utf8(bytes) {
+            Ok(content) => content,
+            Err(e) => {
+                // Rewriting through a lossy conversion would corrupt the file
+                let valid = &e.as_bytes()[..e.utf8_error().valid_up_to()];
+                let line_number = valid.iter().filter(|&&b| b == b'\n').count() + 1;
+                return Ok(FileOutcome::Scanned(FileReport {
+                    bytes_before: e.as_bytes().len(),
+                    bytes_after: e.as_bytes().len(),
+                    warnings: vec![Warning {
+                        line_number,
+                        message: "file is not valid UTF-8 - not rewritten".to_string(),
+                    }],
+                    ..FileReport::default()
+                }));
+            }
+        };
+        
+        let Some(patterns) = &context.patterns else {
+            return Ok(FileOutcome::Scanned(FileReport {
+                bytes_before: content.len(),
+                bytes_after: content.len(),
+                ..FileReport::default()
+            }));
+        };
+        // Work on LF text without a byte order mark, then restore both on the way out
+        let format = TextFormat::detect(&content);
+        let normalized = format.normalize(&content);
+        let mut allowed = Allowed { added, declined: Vec::new() };
+        let (mut new_text, mut file_report) = self.remove_from_content(&normalized, patterns, &allowed);
+        if let Some(decide) = &self.decision_fn {
+            allowed.declined = self.review(path, &normalized, &file_report, decide, &context.review);
+            if !allowed.declined.is_empty() {
+                (new_text, file_report) = self.remove_from_content(&normalized, patterns, &allowed);
+            }
+        }
+        let new_content = format.restore(&new_text);
+        file_report.bytes_before = content.len();
+        file_report.bytes_after = new_content.len();
+        if !file_report.is_modified() {
+            return Ok(FileOutcome::Scanned(file_report));
+        }
         
-        let (new_content, file_report) = self.remove_debug_from_content(&content);
+        let label = path.display().to_string();
+        file_report.diff = unified_diff(&content, &new_content, &format!("a/{}", label), &format!("b/{}", label), DIFF_CONTEXT);
         
-        if file_report.lines_removed > 0 {
-            report.files_modified += 1;
-            report.total_lines_removed += file_report.lines_removed;
-            report.total_warnings += file_report.warnings.len();
-            
-            if self.verbose {
-                info!("Processing {}", path.display());
-                if file_report.lines_removed > 0 {
-                    info!("  Removed {} debug! call(s)", file_report.lines_removed);
+        if !self.dry_run {
+            if let Some(store) = &context.backups {
+                let store = store.lock().unwrap_or_else(|e| e.into_inner());
+                store.backup(&self.backup_run, path, &content, &new_content)?;
+            }
+            fs::write(path, new_content)
+                .with_context(|| format!("Failed to write file: {}", path.display()))?;
+        }
+        
+        Ok(FileOutcome::Scanned(file_report))
+    }
+
+    /// Ask `decide` about each change in `file_report`; returns the start lines declined
+    fn review(
+        &self,
+        path: &Path,
+        content: &str,
+        file_report: &FileReport,
+        decide: &DecisionFn,
+        state: &Mutex<ReviewState>,
+    ) -> Vec<usize> {
+        let lines: Vec<&str> = content.lines().collect();
+        let candidate = |start_line: usize, end_line: usize, text: String, replacement: Option<String>| RemovalCandidate {
+            path: path.to_path_buf(),
+            start_line,
+            end_line,
+            text,
+            replacement,
+            context_before: lines[start_line.saturating_sub(DIFF_CONTEXT + 1)..start_line - 1].iter().map(|l| l.to_string()).collect(),
+            context_after: lines[end_line.min(lines.len())..(end_line + DIFF_CONTEXT).min(lines.len())].iter().map(|l| l.to_string()).collect(),
+        };
+        let mut candidates: Vec<RemovalCandidate> = file_report.removals.iter()
+            .map(|r| candidate(r.start_line, r.end_line, r.text.clone(), None))
+            .collect();
+        let mut rewrites: Vec<RemovalCandidate> = Vec::new();
+        for rewrite in &file_report.rewrites {
+            let line = lines[rewrite.line - 1];
+            match rewrites.last_mut() {
+                // The ast engine records each call; show whole lines either way
+                Some(last) if last.start_line == rewrite.line => {
+                    last.replacement = last.replacement.take().map(|l| l.replacen(&rewrite.text, &rewrite.replacement, 1));
+                }
+                _ if rewrite.text.contains('\n') => {
+                    let end_line = rewrite.line + rewrite.text.matches('\n').count();
+                    rewrites.push(candidate(rewrite.line, end_line, rewrite.text.clone(), Some(rewrite.replacement.clone())));
                 }
-                for warning in &file_report.warnings {
-                    warn!("  Line {}: {}", warning.line_number, warning.message);
+                _ => {
+                    let replacement = line.replacen(&rewrite.text, &rewrite.replacement, 1);
+                    rewrites.push(candidate(rewrite.line, rewrite.line, line.to_string(), Some(replacement)));
                 }
             }
-            
-            if !self.dry_run {
-                fs::write(path, new_content)
-                    .with_context(|| format!("Failed to write file: {}", path.display()))?;
+        }
+        candidates.extend(rewrites);
+        candidates.sort_by_key(|c| c.start_line);
+        candidates.dedup_by_key(|c| c.start_line);
+        
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        let mut declined = Vec::new();
+        for candidate in candidates {
+            let decision = if state.quit {
+                Decision::No
+            } else if state.all {
+                Decision::Yes
+            } else {
+                decide(&candidate)
+            };
+            match decision {
+                Decision::Yes => {}
+                Decision::All => state.all = true,
+                Decision::No => declined.push(candidate.start_line),
+                Decision::Quit => {
+                    state.quit = true;
+                    declined.push(candidate.start_line);
+                }
             }
-            
-            report.file_reports.push((path.to_path_buf(), file_report));
         }
+        declined
+    }
+    
+    /// Remove calls from `content` with the configured engine
+    fn remove_from_content(
+        &self,
+        content: &str,
+        patterns: &CallPatterns,
+        allowed: &Allowed,
+    ) -> (String, FileReport) {
+        match self.engine {
+            #[cfg(feature = "ast")]
+            Engine::Ast => match self.remove_with_ast(content, allowed) {
+                Ok(result) => result,
+                Err(e) => {
+                    let (output, mut report) = self.remove_debug_from_content(content, patterns, allowed);
+                    let at = e.span().start();
+                    report.ast_fallback = Some(format!("line {}:{}: {}", at.line, at.column + 1, e));
+                    (output, report)
+                }
+            },
+            _ => self.remove_debug_from_content(content, patterns, allowed),
+        }
+    }
+    
+    /// Remove calls located by `ast::find_edits`
+    ///
+    /// Statements alone on their lines are removed with those lines, like the regex
+    /// engine does; others are cut out of their line along with the spaces after them.
+    #[cfg(feature = "ast")]
+    fn remove_with_ast(&self, content: &str, allowed: &Allowed) -> syn::Result<(String, FileReport)> {
+        use ast::AstEdit;
         
-        report.files_scanned += 1;
-        Ok(())
+        let edits = ast::find_edits(content, &self.macros)?;
+        let mut report = FileReport::default();
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let line_of = |offset: usize| line_starts.partition_point(|&start| start <= offset);
+        let line_end = |line: usize| line_starts.get(line).copied().unwrap_or(content.len());
+        let in_scope = |first: usize, last: usize| allowed.in_scope(first, last);
+        
+        let mut output = String::with_capacity(content.len());
+        let mut seams = Vec::new();
+        let mut pos = 0;
+        for edit in edits {
+            if edit.start() < pos {
+                continue;
+            }
+            match edit {
+                AstEdit::Skip { start, message } => {
+                    report.warnings.push(Warning { line_number: line_of(start), message });
+                }
+                AstEdit::Rewrite { start, end, replacement } => {
+                    if allowed.declined(line_of(start)) {
+                        continue;
+                    }
+                    if !in_scope(line_of(start), line_of(end.saturating_sub(1).max(start))) {
+                        report.warnings.push(Warning { line_number: line_of(start), message: "dbg! call predates the branch - skipping".to_string() });
+                        continue;
+                    }
+                    output.push_str(&content[pos..start]);
+                    output.push_str(&replacement);
+                    pos = end;
+                    report.rewrites.push(Rewrite { line: line_of(start), text: content[start..end].to_string(), replacement });
+                    report.calls_rewritten += 1;
+                    *report.calls_by_macro.entry("dbg".to_string()).or_default() += 1;
+                }
+                AstEdit::Remove { start, end, calls, replacement } => {
+                    let (first, last) = (line_of(start), line_of(end.saturating_sub(1).max(start)));
+                    if allowed.declined(first) {
+                        continue;
+                    }
+                    if !in_scope(first, last) {
+                        report.warnings.push(Warning {
+                            line_number: first,
+                            message: format!("{}! call predates the branch - skipping", calls[0]),
+                        });
+                        continue;
+                    }
+                    let (first_start, last_end) = (line_starts[first - 1], line_end(last));
+                    let after = content[end..last_end].trim();
+                    let text = if replacement.is_empty()
+                        && content[first_start..start].trim().is_empty()
+                        && (after.is_empty() || after.starts_with("//"))
+                    {
+                        output.push_str(&content[pos..first_start]);
+                        seams.push(output.matches('\n').count());
+                        pos = last_end;
+                        report.lines_removed += last - first + 1;
+                        content[first_start..last_end].trim_end_matches(['\r', '\n']).to_string()
+                    } else {
+                        output.push_str(&content[pos..start]);
+                        if after.is_empty() && replacement.is_empty() {
+                            while output.ends_with([' ', '\t']) {
+                                output.pop();
+                            }
+                        }
+                        output.push_str(&replacement);
+                        pos = end;
+                        if replacement.is_empty() {
+                            pos += content[end..].len() - content[end..].trim_start_matches([' ', '\t']).len();
+                        }
+                        content[start..end].to_string()
+                    };
+                    report.calls_removed += calls.len();
+                    for name in calls {
+                        *report.calls_by_macro.entry(name).or_default() += 1;
+                    }
+                    report.removals.push(Removal { start_line: first, end_line: last, text });
+                }
+            }
+        }
+        output.push_str(&content[pos..]);
+        
+        let lines: Vec<String> = output.lines().map(str::to_string).collect();
+        Ok((self.finish_lines(lines, seams, content.ends_with('\n'), &mut report), report))
     }
-
-    fn remove_debug_from_content(&self, content: &str) -> (String, FileReport) {
+    
+    /// Tidy blank lines around `seams` (if enabled) and join the lines back up
+    fn finish_lines(&self, mut lines: Vec<String>, mut seams: Vec<usize>, trailing_newline: bool, report: &mut FileReport) -> String {
+        if self.tidy_whitespace {
+            seams.dedup();
+            report.blank_lines_removed = tidy_blank_lines(&mut lines, &seams);
+        }
+        
+        let mut output = lines.join("\n");
+        if trailing_newline && !output.is_empty() {
+            output.push('\n');
+        }
+        output
+    }
+    
+    /// Remove calls from `content`, only those lying entirely on `allowed` lines
+    fn remove_debug_from_content(
+        &self,
+        content: &str,
+        patterns: &CallPatterns,
+        allowed: &Allowed,
+    ) -> (String, FileReport) {
+        let CallPatterns { call_start_re, any_call_re, call_end_re, span_start_re, span_end_re } = patterns;
         let mut new_lines = Vec::new();
         let mut report = FileReport::default();
+        // Positions in `new_lines` where removed lines used to be
+        let mut seams = Vec::new();
         
-        // Regex for simple, standalone debug! calls
-        // Matches lines that contain only whitespace, debug! (with optional tracing:: prefix), and its arguments
-        // Allows trailing comments after the semicolon
-        let simple_debug_re = Regex::new(r"^\s*(?:tracing::)?debug!\s*\([^;]*\)\s*;\s*(?://.*)?$").unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        let mut line_starts = Vec::with_capacity(lines.len());
+        let mut offset = 0;
+        for line in content.split_inclusive('\n') {
+            line_starts.push(offset);
+            offset += line.len();
+        }
         
-        // Regex to detect debug! anywhere in a line (for warning purposes)
-        // Also matches tracing::debug!
-        let any_debug_re = Regex::new(r"(?:tracing::)?debug!\s*\(").unwrap();
+        let in_scope = |first: usize, last: usize| allowed.in_scope(first, last);
         
-        // Track if we're in a multiline comment
-        let mut in_block_comment = false;
+        // Where string/char literals and comments are, so calls inside them are not taken for code
+        let spans = SkippedSpans::new(content);
+        let mut index = 0;
         
-        for (line_num, line) in content.lines().enumerate() {
-            let line_number = line_num + 1;
+        while index < lines.len() {
+            let line = lines[index];
+            let line_start = line_starts[index];
+            let line_number = index + 1;
+            let kind_of = |found: &regex::Captures| spans.kind_at(line_start + found.get(1).unwrap().start());
             
-            // Check for block comment boundaries
-            if line.contains("/*") {
-                in_block_comment = true;
+            // `let _span = debug_span!(...).entered();`: removable unless the binding is used
+            let span_start = span_start_re.as_ref()
+                .and_then(|re| re.captures(line))
+                .filter(|found| spans.kind_at(line_start + found.get(2).unwrap().start()).is_none());
+            if let Some(start) = span_start {
+                let (binding, name) = (start[1].to_string(), start[2].to_string());
+                let open = line_start + start.get(0).unwrap().end() - 1;
+                let end_index = matching_paren(content, open)
+                    .map(|close| (close, line_starts.partition_point(|&s| s < close) - 1))
+                    .filter(|&(close, end_index)| {
+                        span_end_re.is_match(&lines[end_index][(close - line_starts[end_index]).min(lines[end_index].len())..])
+                    })
+                    .map(|(_, end_index)| end_index);
+                
+                // Declined in review: kept quietly
+                let declined = allowed.declined(line_number);
+                let problem = match end_index {
+                    _ if declined => None,
+                    None => Some(format!("{}! span is not a plain `let` statement - skipping", name)),
+                    Some(end_index) if !in_scope(line_number, end_index + 1) => {
+                        Some(format!("{}! span predates the branch - skipping", name))
+                    }
+                    Some(end_index) => {
+                        let after = line_starts.get(end_index + 1).copied().unwrap_or(content.len());
+                        first_use(content, &spans, &binding, after).map(|used| {
+                            format!("{}! span bound to `{}` is used on line {} - skipping", name, binding, used)
+                        })
+                    }
+                };
+                let end_index = end_index.unwrap_or(index);
+                let keep = declined || problem.is_some();
+                
+                if let Some(message) = problem {
+                    report.warnings.push(Warning { line_number, message });
+                }
+                if keep {
+                    new_lines.extend(lines[index..=end_index].iter().map(|l| l.to_string()));
+                } else {
+                    seams.push(new_lines.len());
+                    report.calls_removed += 1;
+                    report.lines_removed += end_index - index + 1;
+                    *report.calls_by_macro.entry(name).or_default() += 1;
+                    report.removals.push(Removal {
+                        start_line: line_number,
+                        end_line: end_index + 1,
+                        text: lines[index..=end_index].join("\n"),
+                    });
+                }
+                index = end_index + 1;
+                continue;
             }
             
-            // Check if line contains debug!
-            if any_debug_re.is_match(line) {
-                // Case 1: Line is entirely a simple debug! call - remove it
-                if simple_debug_re.is_match(line) && !in_block_comment && !line.trim_start().starts_with("//") {
-                    report.lines_removed += 1;
-                    continue; // Skip this line entirely
+            // Calls inside literals are kept silently; calls only in comments get a warning
+            let calls: Vec<regex::Captures> = any_call_re.captures_iter(line).collect();
+            if !calls.iter().any(|found| kind_of(found).is_none()) {
+                if let Some(found) = calls.iter().find(|found| kind_of(found) == Some(SpanKind::Comment)) {
+                    report.warnings.push(Warning {
+                        line_number,
+                        message: format!("{}! found in comment - skipping", &found[1]),
+                    });
                 }
-                
-                // Case 2: debug! in a comment
-                if in_block_comment || line.trim_start().starts_with("//") {
+                new_lines.push(line.to_string());
+                index += 1;
+                continue;
+            }
+            
+            if let Some(start) = call_start_re.captures(line).filter(|found| kind_of(found).is_none()) {
+                // Follow the call to its matching parenthesis, possibly lines later
+                let name = start[1].to_string();
+                let open = line_start + start.get(0).unwrap().end() - 1;
+                let Some(close) = matching_paren(content, open) else {
                     report.warnings.push(Warning {
                         line_number,
-                        message: "debug! or tracing::debug! found in comment - skipping".to_string(),
+                        message: format!("{}! call has no matching ')' - skipping", name),
                     });
                     new_lines.push(line.to_string());
+                    index += 1;
+                    continue;
+                };
+                let end_index = line_starts.partition_point(|&s| s < close) - 1;
+                let end_line = lines[end_index];
+                let rest = &end_line[(close - line_starts[end_index]).min(end_line.len())..];
+                
+                if allowed.declined(line_number) {
+                    new_lines.extend(lines[index..=end_index].iter().map(|l| l.to_string()));
+                    index = end_index + 1;
+                    continue;
                 }
-                // Case 3: debug! with other code on the same line
-                else if !simple_debug_re.is_match(line) {
+                if !in_scope(line_number, end_index + 1) {
                     report.warnings.push(Warning {
                         line_number,
-                        message: "debug! or tracing::debug! found with other code on same line - skipping".to_string(),
+                        message: format!("{}! call predates the branch - skipping", name),
                     });
-                    new_lines.push(line.to_string());
+                    new_lines.extend(lines[index..=end_index].iter().map(|l| l.to_string()));
+                    index = end_index + 1;
+                    continue;
                 }
-            } else {
-                // No debug! on this line, keep it
+                
+                if call_end_re.is_match(rest) {
+                    seams.push(new_lines.len());
+                    report.calls_removed += 1;
+                    report.lines_removed += end_index - index + 1;
+                    *report.calls_by_macro.entry(name).or_default() += 1;
+                    report.removals.push(Removal {
+                        start_line: line_number,
+                        end_line: end_index + 1,
+                        text: lines[index..=end_index].join("\n"),
+                    });
+                    index = end_index + 1;
+                    continue;
+                }
+                
+                if name == "dbg" && end_index == index {
+                    // `dbg!(x).method()` or a tail expression: rewrite rather than remove
+                    new_lines.push(self.rewrite_line(line, line_start, &spans, any_call_re, &mut report));
+                    index += 1;
+                    continue;
+                }
+                
+                report.warnings.push(Warning {
+                    line_number,
+                    message: format!("{}! call followed by other code on line {} - skipping", name, end_index + 1),
+                });
+                // Keep the whole span untouched so the closing line is not re-examined alone
+                new_lines.extend(lines[index..=end_index].iter().map(|l| l.to_string()));
+                index = end_index + 1;
+                continue;
+            }
+            
+            if allowed.declined(line_number) {
+                new_lines.push(line.to_string());
+            } else if !in_scope(line_number, line_number) {
+                let name = calls.iter().find(|found| kind_of(found).is_none()).map_or("", |found| found.get(1).unwrap().as_str());
+                report.warnings.push(Warning {
+                    line_number,
+                    message: format!("{}! call predates the branch - skipping", name),
+                });
                 new_lines.push(line.to_string());
+            } else {
+                new_lines.push(self.rewrite_line(line, line_start, &spans, any_call_re, &mut report));
+            }
+            index += 1;
+        }
+        
+        let output = self.finish_lines(new_lines, seams, content.ends_with('\n'), &mut report);
+        (output, report)
+    }
+    
+    /// Handle calls that share a line with other code
+    ///
+    /// `dbg!(expr)` is replaced by `expr` when that keeps the meaning (a single
+    /// argument that is not a compound expression); everything else is left alone
+    /// with a warning.
+    fn rewrite_line(
+        &self,
+        line: &str,
+        line_start: usize,
+        spans: &SkippedSpans,
+        any_call_re: &Regex,
+        report: &mut FileReport,
+    ) -> String {
+        let line_number = spans.line_of(line_start);
+        let mut output = String::new();
+        let mut pos = 0;
+        
+        while let Some(found) = any_call_re.captures_at(line, pos) {
+            let call = found.get(0).unwrap();
+            let name = &found[1];
+            output.push_str(&line[pos..call.start()]);
+            if spans.kind_at(line_start + found.get(1).unwrap().start()).is_some() {
+                output.push_str(call.as_str());
+                pos = call.end();
+                continue;
             }
             
-            if line.contains("*/") {
-                in_block_comment = false;
+            let open = call.end() - 1;
+            let replacement = match matching_paren(line, open) {
+                Some(close) if name == "dbg" => dbg_argument(&line[open + 1..close - 1]).map(|arg| (arg, close)),
+                _ => None,
+            };
+            match replacement {
+                Some((argument, close)) => {
+                    output.push_str(argument);
+                    report.calls_rewritten += 1;
+                    *report.calls_by_macro.entry(name.to_string()).or_default() += 1;
+                    pos = close;
+                }
+                None => {
+                    let message = if name == "dbg" {
+                        "dbg! value is used but its argument is not a single simple expression - skipping".to_string()
+                    } else {
+                        format!("{}! found with other code on same line - skipping", name)
+                    };
+                    report.warnings.push(Warning { line_number, message });
+                    output.push_str(call.as_str());
+                    pos = call.end();
+                }
             }
         }
         
-        (new_lines.join("\n"), report)
+        output.push_str(&line[pos..]);
+        if output != line {
+            report.rewrites.push(Rewrite { line: line_number, text: line.to_string(), replacement: output.clone() });
+        }
+        output
     }
 }
 
+/// Lines added on the current branch, per file
+///
+/// Built from `git diff -U0 -M <merge-base>` against the working tree, so renamed
+/// files are keyed by their new path and uncommitted additions count too.
+/// Untracked files are entirely in scope.
 #[derive(Debug, Default)]
+pub struct GitScope {
+    files: HashMap<PathBuf, Vec<RangeInclusive<usize>>>,
+}
+
+impl GitScope {
+    /// Collect added lines since the merge base of `base_ref` and HEAD
+    ///
+    /// `path` is any file or directory inside the repository.
+    pub fn load(path: &Path, base_ref: &str) -> Result<Self> {
+        let dir = if path.is_dir() { path } else { path.parent().unwrap_or(Path::new(".")) };
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        let root = PathBuf::from(git_output(dir, &["rev-parse", "--show-toplevel"])?.trim());
+        let root = root.canonicalize().unwrap_or(root);
+        let merge_base = git_output(&root, &["merge-base", base_ref, "HEAD"])
+            .with_context(|| format!("No common ancestor with {}", base_ref))?;
+        
+        let diff = git_output(&root, &["-c", "core.quotePath=false", "diff", "-U0", "-M", "--no-color", merge_base.trim(), "--"])?;
+        let mut files = HashMap::new();
+        for file in parse_unified_diff(&diff) {
+            let spans = file.hunks.iter()
+                .filter(|hunk| hunk.new_lines > 0)
+                .map(|hunk| hunk.new_start..=hunk.new_start + hunk.new_lines - 1)
+                .collect();
+            files.insert(root.join(&file.path), spans);
+        }
+        
+        let untracked = git_output(&root, &["-c", "core.quotePath=false", "ls-files", "--others", "--exclude-standard"])?;
+        for file in untracked.lines() {
+            files.insert(root.join(file), vec![1..=usize::MAX]);
+        }
+        
+        Ok(Self { files })
+    }
+    
+    /// Added line ranges of a file, or None if it is unchanged since the base
+    pub fn added_lines(&self, file: &Path) -> Option<&[RangeInclusive<usize>]> {
+        let file = file.canonicalize().ok()?;
+        self.files.get(&file).map(Vec::as_slice)
+    }
+}
+
+pub(crate) fn git_output(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The expression a `dbg!` call can be replaced with, if unambiguous
+///
+/// Zero or several arguments (which evaluate to `()` or a tuple) and compound
+/// expressions like `a + b` (whose precedence could change) return None.
+fn dbg_argument(args: &str) -> Option<&str> {
+    let args = args.trim();
+    let args = args.strip_suffix(',').map(str::trim_end).unwrap_or(args);
+    if args.is_empty() {
+        return None;
+    }
+    
+    let bytes = args.as_bytes();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        if let Some(end) = skip_literal_or_comment(bytes, i) {
+            i = end;
+            continue;
+        }
+        match bytes[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+            // Leading reference, dereference, or negation is part of a simple expression
+            b'&' | b'*' | b'-' if depth == 0 && i == 0 => {}
+            b if depth == 0 && !(b.is_ascii_alphanumeric() || b"_.:?".contains(&b) || b >= 0x80) => return None,
+            _ => {}
+        }
+        i += 1;
+    }
+    Some(args)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpanKind {
+    Literal,
+    Comment,
+}
+
+/// Byte ranges of literals and comments in a file, found with a single forward scan
+struct SkippedSpans {
+    spans: Vec<(std::ops::Range<usize>, SpanKind)>,
+    line_starts: Vec<usize>,
+}
+
+impl SkippedSpans {
+    fn new(content: &str) -> Self {
+        let bytes = content.as_bytes();
+        let mut spans = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            match skip_literal_or_comment(bytes, i) {
+                Some(end) => {
+                    let kind = if bytes[i] == b'/' { SpanKind::Comment } else { SpanKind::Literal };
+                    spans.push((i..end, kind));
+                    i = end;
+                }
+                None => i += 1,
+            }
+        }
+        let line_starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { spans, line_starts }
+    }
+    
+    /// Whether the byte at `offset` is inside a literal or comment, and which
+    fn kind_at(&self, offset: usize) -> Option<SpanKind> {
+        let index = self.spans.partition_point(|(range, _)| range.end <= offset);
+        self.spans.get(index)
+            .filter(|(range, _)| range.contains(&offset))
+            .map(|(_, kind)| *kind)
+    }
+    
+    /// 1-based line number of a byte offset
+    fn line_of(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset)
+    }
+}
+
+/// Lines the engines may change
+#[derive(Debug, Default)]
+struct Allowed<'a> {
+    /// Only these lines (from `GitScope`), if set
+    added: Option<&'a [RangeInclusive<usize>]>,
+    /// Start lines of removals and rewrites declined in review; kept without a warning
+    declined: Vec<usize>,
+}
+
+impl Allowed<'_> {
+    fn in_scope(&self, first: usize, last: usize) -> bool {
+        self.added.is_none_or(|ranges| (first..=last).all(|line| ranges.iter().any(|r| r.contains(&line))))
+    }
+    
+    fn declined(&self, line: usize) -> bool {
+        self.declined.contains(&line)
+    }
+}
+
+/// Byte order mark and line ending of a file, kept when it is rewritten
+struct TextFormat {
+    bom: bool,
+    /// CRLF is the dominant line ending
+    crlf: bool,
+}
+
+impl TextFormat {
+    fn detect(content: &str) -> Self {
+        let crlf = content.matches("\r\n").count();
+        Self {
+            bom: content.starts_with('\u{feff}'),
+            crlf: crlf > content.matches('\n').count() - crlf,
+        }
+    }
+    
+    /// The text as the engines expect it
+    fn normalize(&self, content: &str) -> String {
+        content.strip_prefix('\u{feff}').unwrap_or(content).replace("\r\n", "\n")
+    }
+    
+    fn restore(&self, text: &str) -> String {
+        let text = if self.crlf { text.replace('\n', "\r\n") } else { text.to_string() };
+        if self.bom { format!("\u{feff}{}", text) } else { text }
+    }
+}
+
+/// Collapse blank lines that removals pushed together
+///
+/// `seams` are the positions in `lines` where removed lines used to be. Each run of
+/// blank lines touching a seam was one or more separate runs before the removal;
+/// it is cut down to the longest of those. A run left directly after a `{` or
+/// before a `}` (or at either end of the file) is dropped entirely when the removal
+/// created it. Runs not touching a seam are never changed, so a second pass over
+/// the result changes nothing. Returns the number of lines dropped.
+fn tidy_blank_lines(lines: &mut Vec<String>, seams: &[usize]) -> usize {
+    let blank = |line: &String| line.trim().is_empty();
+    let mut drop = vec![false; lines.len()];
+    let mut next = 0;
+    
+    while next < seams.len() {
+        let seam = seams[next];
+        let mut start = seam;
+        while start > 0 && blank(&lines[start - 1]) {
+            start -= 1;
+        }
+        let mut end = seam;
+        while end < lines.len() && blank(&lines[end]) {
+            end += 1;
+        }
+        
+        // Split the run at every seam inside it: the pieces are the original runs
+        let mut bounds = vec![start];
+        while next < seams.len() && seams[next] <= end {
+            bounds.push(seams[next]);
+            next += 1;
+        }
+        bounds.push(end);
+        let pieces: Vec<usize> = bounds.windows(2).map(|w| w[1] - w[0]).collect();
+        
+        let opens_block = start == 0 || lines[start - 1].trim_end().ends_with('{');
+        let closes_block = end == lines.len() || lines[end].trim_start().starts_with('}');
+        let keep = if (opens_block && pieces[0] == 0) || (closes_block && pieces[pieces.len() - 1] == 0) {
+            0
+        } else {
+            pieces.iter().copied().max().unwrap_or(0)
+        };
+        drop[start + keep..end].iter_mut().for_each(|d| *d = true);
+    }
+    
+    let mut flags = drop.iter();
+    lines.retain(|_| !flags.next().unwrap());
+    drop.iter().filter(|d| **d).count()
+}
+
+/// Line number of the first use of identifier `name` in code at or after byte `from`
+///
+/// `_` is a wildcard pattern, never a variable, so it is never used. Shadowing is
+/// not tracked, so a later binding with the same name counts as a use.
+fn first_use(content: &str, spans: &SkippedSpans, name: &str, from: usize) -> Option<usize> {
+    if name == "_" {
+        return None;
+    }
+    let re = Regex::new(&format!(r"\b{}\b", regex::escape(name))).unwrap();
+    let offset = re.find_iter(&content[from..])
+        .map(|found| from + found.start())
+        .find(|&offset| spans.kind_at(offset).is_none())?;
+    Some(spans.line_of(offset))
+}
+
+/// Byte offset just past the bracket matching the one at `open`
+///
+/// Brackets inside string, raw string, byte string, and char literals and inside
+/// comments are ignored. Returns None if the end of the content is reached first.
+fn matching_paren(content: &str, open: usize) -> Option<usize> {
+    let bytes = content.as_bytes();
+    let mut depth = 0usize;
+    let mut i = open;
+    
+    while i < bytes.len() {
+        if let Some(end) = skip_literal_or_comment(bytes, i) {
+            i = end;
+            continue;
+        }
+        match bytes[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// If a literal or comment starts at `i`, the offset just past it
+fn skip_literal_or_comment(bytes: &[u8], i: usize) -> Option<usize> {
+    let at = |j: usize| bytes.get(j).copied();
+    let after_ident = i > 0 && (bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_');
+    
+    match bytes[i] {
+        b'/' if at(i + 1) == Some(b'/') => {
+            Some(bytes[i..].iter().position(|&b| b == b'\n').map_or(bytes.len(), |p| i + p))
+        }
+        b'/' if at(i + 1) == Some(b'*') => {
+            Some(find(bytes, i + 2, b"*/").map_or(bytes.len(), |p| p + 2))
+        }
+        b'"' => Some(skip_string(bytes, i + 1)),
+        b'b' if !after_ident && at(i + 1) == Some(b'"') => Some(skip_string(bytes, i + 2)),
+        b'b' if !after_ident && at(i + 1) == Some(b'r') => skip_raw_string(bytes, i + 2),
+        b'r' if !after_ident => skip_raw_string(bytes, i + 1),
+        b'\'' => {
+            // A char literal ('x', '\n', '\u{..}'); anything else is a lifetime
+            if at(i + 1) == Some(b'\\') {
+                find(bytes, i + 2, b"'").map(|p| p + 1)
+            } else {
+                let len = match at(i + 1)? {
+                    0x00..=0x7F => 1,
+                    0xC0..=0xDF => 2,
+                    0xE0..=0xEF => 3,
+                    _ => 4,
+                };
+                (at(i + 1 + len) == Some(b'\'')).then_some(i + len + 2)
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Offset just past the closing quote of a string whose body starts at `i`
+fn skip_string(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Raw string starting after its `r`: `#`* then `"`, ending at `"` and the same number of `#`
+fn skip_raw_string(bytes: &[u8], i: usize) -> Option<usize> {
+    let hashes = bytes[i..].iter().take_while(|&&b| b == b'#').count();
+    if bytes.get(i + hashes) != Some(&b'"') {
+        return None;
+    }
+    let mut terminator = vec![b'"'];
+    terminator.extend(std::iter::repeat_n(b'#', hashes));
+    Some(find(bytes, i + hashes + 1, &terminator).map_or(bytes.len(), |p| p + terminator.len()))
+}
+
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes.get(from..)?
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|p| from + p)
+}
+
+/// A `DebugRemover` with its per-run state built, from `DebugRemover::session`
+pub struct RemovalSession<'a> {
+    remover: &'a DebugRemover,
+    context: RunContext,
+}
+
+impl RemovalSession<'_> {
+    /// Process one file as `remove_debug_calls` would; a file that cannot be read
+    /// is reported in `failures`
+    pub fn process_file(&self, path: &Path) -> RemovalReport {
+        let started = Instant::now();
+        let mut report = RemovalReport::default();
+        match self.remover.process_file(path, &self.context) {
+            Ok(outcome) => self.remover.record(&mut report, path, outcome),
+            Err(e) => report.failures.push((path.to_path_buf(), format!("{:#}", e))),
+        }
+        report.elapsed_ms = started.elapsed().as_millis() as u64;
+        report
+    }
+}
+
+/// Aggregate result of a run; serializes to the `remove-debug --format json` output
+#[derive(Debug, Default, Serialize)]
 pub struct RemovalReport {
     pub files_scanned: usize,
     pub files_modified: usize,
+    pub total_calls_removed: usize,
+    /// `dbg!` expressions replaced by their argument
+    pub total_calls_rewritten: usize,
     pub total_lines_removed: usize,
     pub total_warnings: usize,
+    /// Removed and rewritten calls per macro name
+    pub calls_by_macro: BTreeMap<String, usize>,
+    /// Per-file reports of files with changes or warnings, ordered by path
+    #[serde(rename = "files", serialize_with = "serialize_file_reports")]
     pub file_reports: Vec<(PathBuf, FileReport)>,
+    /// Files that could not be read or written, with the error
+    #[serde(serialize_with = "serialize_failures")]
+    pub failures: Vec<(PathBuf, String)>,
+    /// Backup run holding the originals of modified files
+    pub backup_run: Option<String>,
+    /// Wall-clock time of the run (summed over merged runs)
+    pub elapsed_ms: u64,
+    /// The slowest files scanned, with or without changes, slowest first
+    #[serde(serialize_with = "serialize_slowest_files")]
+    pub slowest_files: Vec<(PathBuf, u64)>,
 }
 
 impl RemovalReport {
+    /// Whether a `--check` run should fail: anything removable or ambiguous was found
+    pub fn has_findings(&self) -> bool {
+        self.total_calls_removed + self.total_calls_rewritten + self.total_warnings > 0
+    }
+    
+    /// Add another report's counts and file reports to this one
+    pub fn merge(&mut self, other: RemovalReport) {
+        self.files_scanned += other.files_scanned;
+        self.files_modified += other.files_modified;
+        self.total_calls_removed += other.total_calls_removed;
+        self.total_calls_rewritten += other.total_calls_rewritten;
+        self.total_lines_removed += other.total_lines_removed;
+        self.total_warnings += other.total_warnings;
+        for (name, count) in other.calls_by_macro {
+            *self.calls_by_macro.entry(name).or_default() += count;
+        }
+        self.file_reports.extend(other.file_reports);
+        self.failures.extend(other.failures);
+        self.backup_run = self.backup_run.take().or(other.backup_run);
+        self.elapsed_ms += other.elapsed_ms;
+        for (path, elapsed_us) in other.slowest_files {
+            self.note_elapsed(&path, elapsed_us);
+        }
+    }
+    
+    /// Keep `path` in `slowest_files` if it is among the `TOP_FILES` slowest
+    fn note_elapsed(&mut self, path: &Path, elapsed_us: u64) {
+        let at = self.slowest_files.partition_point(|(_, us)| *us >= elapsed_us);
+        if at < TOP_FILES {
+            self.slowest_files.insert(at, (path.to_path_buf(), elapsed_us));
+            self.slowest_files.truncate(TOP_FILES);
+        }
+    }
+    
+    /// GitHub Actions `::warning` lines for every warning, plus every removal and
+    /// rewrite when `include_changes` (for `--check` runs), in line order per file
+    pub fn github_annotations(&self, include_changes: bool) -> Vec<String> {
+        let mut annotations = Vec::new();
+        for (path, report) in &self.file_reports {
+            let mut findings: Vec<(usize, String)> = report.warnings.iter()
+                .map(|warning| (warning.line_number, warning.message.clone()))
+                .collect();
+            if include_changes {
+                findings.extend(report.removals.iter().map(|removal| {
+                    let first = removal.text.lines().next().unwrap_or("").trim();
+                    (removal.start_line, format!("Debug call would be removed: {}", first))
+                }));
+                findings.extend(report.rewrites.iter().map(|rewrite| {
+                    (rewrite.line, format!("dbg! would be rewritten: {}", rewrite.replacement.trim()))
+                }));
+            }
+            findings.sort_by_key(|(line, _)| *line);
+            annotations.extend(findings.iter().map(|(line, message)| crate::annotations::warning(path, *line, message)));
+        }
+        annotations
+    }
+    
     pub fn print_summary(&self, verbose: bool) {
         if verbose {
             info!("=== Debug Removal Summary ===");
             info!("Files scanned: {}", self.files_scanned);
             info!("Files modified: {}", self.files_modified);
+            info!("Calls removed: {}", self.total_calls_removed);
+            if self.total_calls_rewritten > 0 {
+                info!("dbg! expressions rewritten: {}", self.total_calls_rewritten);
+            }
+            for (name, count) in &self.calls_by_macro {
+                info!("  {}!: {}", name, count);
+            }
             info!("Lines removed: {}", self.total_lines_removed);
             if self.total_warnings > 0 {
                 info!("Warnings: {}", self.total_warnings);
             }
+            let bytes_removed: usize = self.file_reports.iter()
+                .map(|(_, report)| report.bytes_before.saturating_sub(report.bytes_after))
+                .sum();
+            info!("Bytes removed: {}", bytes_removed);
+            info!("Elapsed: {} ms", self.elapsed_ms);
+            
+            let mut by_changes: Vec<&(PathBuf, FileReport)> = self.file_reports.iter()
+                .filter(|(_, report)| report.is_modified())
+                .collect();
+            // Stable sort keeps path order among ties
+            by_changes.sort_by_key(|(_, report)| std::cmp::Reverse(report.calls_removed + report.calls_rewritten));
+            if !by_changes.is_empty() {
+                info!("Most changed files:");
+                for (path, report) in by_changes.iter().take(TOP_FILES) {
+                    info!("  {}: {} call(s), {} -> {} bytes", path.display(), report.calls_removed + report.calls_rewritten, report.bytes_before, report.bytes_after);
+                }
+            }
+            if !self.slowest_files.is_empty() {
+                info!("Slowest files:");
+                for (path, elapsed_us) in &self.slowest_files {
+                    info!("  {}: {:.1} ms", path.display(), *elapsed_us as f64 / 1000.0);
+                }
+            }
         } else {
             // Quiet mode: single line output
-            if self.total_lines_removed > 0 {
-                info!("Removed {} debug! calls from {} files", self.total_lines_removed, self.files_modified);
+            if self.calls_by_macro.is_empty() {
+                info!("No matching macro calls found");
             } else {
-                info!("No debug! calls found");
+                let breakdown: Vec<String> = self.calls_by_macro.iter()
+                    .map(|(name, count)| format!("{}! {}", name, count))
+                    .collect();
+                info!(
+                    "Removed {} calls from {} files ({})",
+                    self.calls_by_macro.values().sum::<usize>(), self.files_modified, breakdown.join(", ")
+                );
             }
             if self.total_warnings > 0 {
                 info!("Skipped {} ambiguous cases (use --verbose for details)", self.total_warnings);
             }
         }
+        let fallbacks = self.file_reports.iter().filter(|(_, report)| report.ast_fallback.is_some()).count();
+        if fallbacks > 0 {
+            warn!("{} file(s) could not be parsed and used the regex engine", fallbacks);
+        }
+        if !self.failures.is_empty() {
+            warn!("{} file(s) could not be processed", self.failures.len());
+        }
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize)]
 pub struct FileReport {
+    pub calls_removed: usize,
+    /// `dbg!` expressions replaced by their argument
+    pub calls_rewritten: usize,
+    /// Every line removed, including all lines of multi-line calls
     pub lines_removed: usize,
+    /// Blank lines dropped by the whitespace cleanup after removal
+    pub blank_lines_removed: usize,
+    /// Size of the file as read
+    pub bytes_before: usize,
+    /// Size of the file as written (or as it would be, in dry-run mode)
+    pub bytes_after: usize,
+    /// Time spent reading, rewriting, backing up, and writing the file
+    pub elapsed_us: u64,
+    /// Why the ast engine could not parse the file, if it fell back to regexes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ast_fallback: Option<String>,
+    /// Removed and rewritten calls per macro name
+    pub calls_by_macro: BTreeMap<String, usize>,
+    pub removals: Vec<Removal>,
+    pub rewrites: Vec<Rewrite>,
     pub warnings: Vec<Warning>,
+    /// Unified diff of the change (applied, or proposed in dry-run mode)
+    pub diff: String,
+}
+
+impl FileReport {
+    pub fn is_modified(&self) -> bool {
+        self.calls_removed + self.calls_rewritten > 0
+    }
+}
+
+/// `file_reports` as `[{"path": ..., "calls_removed": ..., ...}]`
+fn serialize_file_reports<S: Serializer>(reports: &[(PathBuf, FileReport)], serializer: S) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct Entry<'a> {
+        path: &'a Path,
+        #[serde(flatten)]
+        report: &'a FileReport,
+    }
+    serializer.collect_seq(reports.iter().map(|(path, report)| Entry { path, report }))
+}
+
+/// `failures` as `[{"path": ..., "error": ...}]`
+fn serialize_failures<S: Serializer>(failures: &[(PathBuf, String)], serializer: S) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct Entry<'a> {
+        path: &'a Path,
+        error: &'a str,
+    }
+    serializer.collect_seq(failures.iter().map(|(path, error)| Entry { path, error }))
+}
+
+/// `slowest_files` as `[{"path": ..., "elapsed_us": ...}]`
+fn serialize_slowest_files<S: Serializer>(files: &[(PathBuf, u64)], serializer: S) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct Entry<'a> {
+        path: &'a Path,
+        elapsed_us: u64,
+    }
+    serializer.collect_seq(files.iter().map(|(path, elapsed_us)| Entry { path, elapsed_us: *elapsed_us }))
+}
+
+/// A removed (or, in dry-run mode, removable) call and its full source span
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Removal {
+    /// First line of the call (1-based)
+    pub start_line: usize,
+    /// Last line of the call, inclusive
+    pub end_line: usize,
+    pub text: String,
+}
+
+/// A `dbg!` expression replaced by its argument
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Rewrite {
+    pub line: usize,
+    /// The source before the change: the whole line for the regex engine, the
+    /// `dbg!(...)` expression for the ast engine
+    pub text: String,
+    pub replacement: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Warning {
     pub line_number: usize,
     pub message: String,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::{git, init_repo};
+
+    fn remove(content: &str) -> (String, FileReport) {
+        remove_with(&DebugRemover::new(PathBuf::from(".")), content)
+    }
+
+    fn remove_with(remover: &DebugRemover, content: &str) -> (String, FileReport) {
+        let patterns = CallPatterns::new(&remover.macros).unwrap();
+        remover.remove_debug_from_content(content, &patterns, &Allowed::default())
+    }
+
+    #[test]
+    fn test_removes_single_and_multi_line_calls() {
+        let content = "fn f(x: u32) {\n    debug!(\"x = {}\", x);\n    tracing::debug!(\n        \"value: {}\",\n        x\n    );\n    x + 1\n}\n";
+        let (output, report) = remove(content);
+        assert_eq!(output, "fn f(x: u32) {\n    x + 1\n}\n");
+        assert_eq!(report.calls_removed, 2);
+        assert_eq!(report.lines_removed, 5);
+        assert_eq!(report.removals[1].start_line, 3);
+        assert_eq!(report.removals[1].end_line, 6);
+        assert!(report.removals[1].text.contains("\"value: {}\","));
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_nested_parens_and_literals_inside_call() {
+        let content = "debug!(\n    \"closing ); here\",\n    foo(bar(1), ')'),\n    r#\"raw \")\" \"#,\n    '\\'',\n);\nkeep();";
+        let (output, report) = remove(content);
+        assert_eq!(output, "keep();");
+        assert_eq!(report.lines_removed, 6);
+    }
+
+    #[test]
+    fn test_code_after_closing_line_is_kept_with_warning() {
+        let content = "debug!(\n    \"a\"\n); let y = 2;\nnext();\n";
+        let (output, report) = remove(content);
+        assert_eq!(output, content);
+        assert_eq!(report.calls_removed, 0);
+        assert_eq!(report.warnings.len(), 1);
+        assert!(report.warnings[0].message.contains("line 3"));
+    }
+
+    #[test]
+    fn test_configured_macros_are_counted_per_name() {
+        let content = "fn f() {\n    trace!(\"a\");\n    log::info!(\"b\");\n    println!(\"c\");\n    my_trace!(\"kept\");\n    debug!(\"kept too\");\n}\n";
+        let remover = DebugRemover::new(PathBuf::from(".")).with_macros(["trace", "info", "println"]);
+        let (output, report) = remove_with(&remover, content);
+        assert_eq!(output, "fn f() {\n    my_trace!(\"kept\");\n    debug!(\"kept too\");\n}\n");
+        assert_eq!(report.calls_by_macro, BTreeMap::from([
+            ("info".to_string(), 1),
+            ("println".to_string(), 1),
+            ("trace".to_string(), 1),
+        ]));
+    }
+
+    #[test]
+    fn test_dbg_expressions_are_rewritten_or_warned() {
+        let content = "dbg!(state);\nlet y = dbg!(x.len()) * 2;\nlet z = dbg!(a + b);\nlet t = dbg!(a, b);\nfoo(dbg!(&v[1..]))\n";
+        let remover = DebugRemover::new(PathBuf::from(".")).with_macros(["dbg"]);
+        let (output, report) = remove_with(&remover, content);
+        assert_eq!(output, "let y = x.len() * 2;\nlet z = dbg!(a + b);\nlet t = dbg!(a, b);\nfoo(&v[1..])\n");
+        assert_eq!(report.calls_removed, 1);
+        assert_eq!(report.calls_rewritten, 2);
+        assert_eq!(report.calls_by_macro["dbg"], 3);
+        assert_eq!(report.warnings.iter().map(|w| w.line_number).collect::<Vec<_>>(), vec![3, 4]);
+    }
+
+    #[test]
+    fn test_dry_run_records_diff_without_writing() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("lib.rs");
+        let content = "fn f() {\n    let x = 1;\n    debug!(\n        \"x = {}\",\n        x\n    );\n}\n";
+        std::fs::write(&file, content).unwrap();
+
+        let report = DebugRemover::new(file.clone()).with_dry_run(true).remove_debug_calls().unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), content);
+        let diff = &report.file_reports[0].1.diff;
+        let label = file.display();
+        assert_eq!(*diff, format!(
+            "--- a/{label}\n+++ b/{label}\n@@ -1,7 +1,3 @@\n fn f() {{\n     let x = 1;\n\
+             -    debug!(\n-        \"x = {{}}\",\n-        x\n-    );\n }}\n"
+        ));
+    }
+
+    #[test]
+    fn test_git_scope_only_touches_branch_changes() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path();
+        let old_body: String = (1..=12).map(|i| format!("    step({});\n", i)).collect();
+        init_repo(
+            repo,
+            &[
+                ("src/a.rs", &format!("fn a() {{\n    debug!(\"old\");\n{}}}\n", old_body)),
+                ("src/old_name.rs", &format!("fn b() {{\n{}}}\n", old_body)),
+                ("src/untouched.rs", "fn c() {\n    debug!(\"old\");\n}\n"),
+            ],
+        );
+        
+        git(repo, &["checkout", "-q", "-b", "feature"]);
+        fs::write(repo.join("src/a.rs"), format!("fn a() {{\n    debug!(\"old\");\n    debug!(\n        \"new\"\n    );\n{}}}\n", old_body)).unwrap();
+        git(repo, &["mv", "src/old_name.rs", "src/new_name.rs"]);
+        fs::write(repo.join("src/new_name.rs"), format!("fn b() {{\n    debug!(\"renamed\");\n{}}}\n", old_body)).unwrap();
+        git(repo, &["commit", "-q", "-am", "feature"]);
+        // Uncommitted and untracked additions are in scope too
+        fs::write(repo.join("src/fresh.rs"), "fn d() {\n    debug!(\"fresh\");\n}\n").unwrap();
+        
+        let report = DebugRemover::new(repo.join("src")).with_git_scope("main").remove_debug_calls().unwrap();
+        
+        let a = fs::read_to_string(repo.join("src/a.rs")).unwrap();
+        assert!(a.contains("debug!(\"old\")") && !a.contains("\"new\""));
+        assert!(!fs::read_to_string(repo.join("src/new_name.rs")).unwrap().contains("debug!"));
+        assert!(!fs::read_to_string(repo.join("src/fresh.rs")).unwrap().contains("debug!"));
+        assert!(fs::read_to_string(repo.join("src/untouched.rs")).unwrap().contains("debug!"));
+        assert_eq!(report.total_calls_removed, 3);
+        assert_eq!(report.files_scanned, 3);
+        let (_, a_report) = report.file_reports.iter().find(|(p, _)| p.ends_with("a.rs")).unwrap();
+        assert_eq!(a_report.warnings.len(), 1);
+        assert!(a_report.warnings[0].message.contains("predates the branch"));
+    }
+
+    #[test]
+    fn test_backups_allow_restoring_a_run() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("lib.rs");
+        fs::write(&file, "fn f() {\n    debug!(\"x\");\n}\n").unwrap();
+
+        let remover = DebugRemover::new(file.clone()).with_backup_dir(temp.path().to_path_buf());
+        let report = remover.remove_debug_calls().unwrap();
+        assert_eq!(report.backup_run.as_deref(), Some(remover.backup_run.as_str()));
+        assert_eq!(fs::read_to_string(&file).unwrap(), "fn f() {\n}\n");
+
+        let restored = remover.restore_backup(None).unwrap();
+        assert_eq!(restored.restored.len(), 1);
+        assert!(fs::read_to_string(&file).unwrap().contains("debug!"));
+
+        let unbacked = DebugRemover::new(file.clone())
+            .with_backup_dir(temp.path().to_path_buf())
+            .with_backup_run("second".to_string())
+            .with_backups(false)
+            .remove_debug_calls()
+            .unwrap();
+        assert!(unbacked.backup_run.is_none());
+        assert_eq!(remover.backup_store().runs().unwrap().len(), 1);
+    }
+
+    /// A tree of `count` files in nested directories; every third has no calls
+    fn generate_tree(root: &Path, count: usize) {
+        for i in 0..count {
+            let dir = root.join(format!("mod{}/sub{}", i % 7, i % 3));
+            fs::create_dir_all(&dir).unwrap();
+            let body = match i % 3 {
+                0 => format!("fn f{i}() -> u32 {{\n    {i}\n}}\n"),
+                1 => format!("fn f{i}() {{\n    debug!(\"{i}\");\n}}\n"),
+                _ => format!("fn f{i}(x: u32) {{\n    debug!(\n        \"{{}}\",\n        x\n    );\n    trace!(\"{i}\");\n}}\n"),
+            };
+            fs::write(dir.join(format!("file{}.rs", i)), body).unwrap();
+        }
+    }
+
+    /// Counts and modified files (relative to `root`) of a report, for comparing runs
+    fn summarize(report: &RemovalReport, root: &Path) -> (usize, usize, usize, usize, Vec<PathBuf>) {
+        let files = report.file_reports.iter()
+            .map(|(path, _)| path.strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+        (report.files_scanned, report.files_modified, report.total_calls_removed, report.total_lines_removed, files)
+    }
+
+    #[test]
+    fn test_parallel_run_matches_serial() {
+        let temp = tempfile::tempdir().unwrap();
+        let (serial_root, parallel_root) = (temp.path().join("serial"), temp.path().join("parallel"));
+        generate_tree(&serial_root, 300);
+        generate_tree(&parallel_root, 300);
+        let run = |root: &Path, jobs: usize, dry_run: bool| {
+            DebugRemover::new(root.to_path_buf())
+                .with_jobs(jobs)
+                .with_dry_run(dry_run)
+                .with_backup_dir(temp.path().to_path_buf())
+                .with_backup_run(format!("jobs{}", jobs))
+                .remove_debug_calls()
+                .unwrap()
+        };
+
+        let serial = run(&serial_root, 1, true);
+        let parallel = run(&serial_root, 8, true);
+        let (scanned, modified, removed, lines, files) = summarize(&parallel, &serial_root);
+        assert_eq!((scanned, modified, removed, lines), (300, 200, 200, 500));
+        assert!(files.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(summarize(&serial, &serial_root), summarize(&parallel, &serial_root));
+        let bytes = |report: &RemovalReport| report.file_reports.iter()
+            .map(|(_, file)| (file.bytes_before, file.bytes_after))
+            .collect::<Vec<_>>();
+        assert_eq!(bytes(&serial), bytes(&parallel));
+        // Timings cover every scanned file, not only the reported ones
+        assert_eq!(parallel.slowest_files.len(), TOP_FILES);
+        assert!(parallel.slowest_files.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        assert!(parallel.file_reports.iter().all(|(_, file)| file.elapsed_us <= parallel.slowest_files[0].1));
+
+        let serial = run(&serial_root, 1, false);
+        let parallel = run(&parallel_root, 8, false);
+        assert_eq!(summarize(&serial, &serial_root), summarize(&parallel, &parallel_root));
+        for file in summarize(&serial, &serial_root).4 {
+            assert_eq!(fs::read_to_string(serial_root.join(&file)).unwrap(), fs::read_to_string(parallel_root.join(&file)).unwrap());
+        }
+        let backups = BackupStore::new(temp.path());
+        assert_eq!(backups.manifest("jobs8").unwrap().files.len(), 200);
+    }
+
+    #[test]
+    fn test_file_errors_are_reported_without_aborting() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::create_dir(temp.path().join("src")).unwrap();
+        fs::write(temp.path().join("src/a.rs"), "fn a() {\n    debug!(\"a\");\n}\n").unwrap();
+        fs::write(temp.path().join("c.rs"), "fn c() {\n    debug!(\"c\");\n}\n").unwrap();
+        // A file where the backup of src/a.rs needs a directory
+        let blocker = temp.path().join(".autodebugger/backups/run/src");
+        fs::create_dir_all(blocker.parent().unwrap()).unwrap();
+        fs::write(&blocker, "").unwrap();
+
+        let report = DebugRemover::new(temp.path().to_path_buf())
+            .with_backup_dir(temp.path().to_path_buf())
+            .with_backup_run("run".to_string())
+            .remove_debug_calls()
+            .unwrap();
+        assert_eq!(report.files_modified, 1);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].0, temp.path().join("src/a.rs"));
+        assert!(report.failures[0].1.contains("Failed to create"), "{}", report.failures[0].1);
+        assert!(fs::read_to_string(temp.path().join("src/a.rs")).unwrap().contains("debug!"));
+        assert!(!fs::read_to_string(temp.path().join("c.rs")).unwrap().contains("debug!"));
+    }
+
+    #[test]
+    fn test_session_checks_files_one_at_a_time() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("lib.rs");
+        fs::write(&file, "fn f() {\n    trace!(\"x\");\n}\n").unwrap();
+        let remover = DebugRemover::new(temp.path().to_path_buf())
+            .with_dry_run(true)
+            .with_macros(["trace".to_string()]);
+        let session = remover.session().unwrap();
+
+        let report = session.process_file(&file);
+        assert_eq!((report.files_scanned, report.total_calls_removed), (1, 1));
+        assert!(report.has_findings());
+        assert!(fs::read_to_string(&file).unwrap().contains("trace!"));
+
+        // The same session sees the edit, and a deleted file is a failure, not a panic
+        fs::write(&file, "fn f() {}\n").unwrap();
+        assert!(!session.process_file(&file).has_findings());
+        fs::remove_file(&file).unwrap();
+        let report = session.process_file(&file);
+        assert_eq!(report.failures.len(), 1);
+    }
+
+    #[test]
+    fn test_line_endings_and_final_newline_are_preserved() {
+        let cases = [
+            ("fn f() {\r\n    debug!(\"x\");\r\n    let y = 1;\r\n}\r\n", "fn f() {\r\n    let y = 1;\r\n}\r\n"),
+            ("fn f() {\r\n    let y = 1;\r\n    debug!(\"x\");\r\n}", "fn f() {\r\n    let y = 1;\r\n}"),
+            ("fn f() {\n    debug!(\"x\");\n}", "fn f() {\n}"),
+            ("\u{feff}debug!(\"top\");\r\nfn f() {}\r\n", "\u{feff}fn f() {}\r\n"),
+        ];
+        for (before, after) in cases {
+            let temp = tempfile::tempdir().unwrap();
+            let file = temp.path().join("lib.rs");
+            fs::write(&file, before).unwrap();
+            let report = DebugRemover::new(file.clone()).with_backups(false).remove_debug_calls().unwrap();
+            assert_eq!(report.total_calls_removed, 1, "{:?}", before);
+            assert_eq!(fs::read(&file).unwrap(), after.as_bytes(), "{:?}", before);
+        }
+    }
+
+    #[test]
+    fn test_non_utf8_files_are_left_alone_with_a_warning() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("latin1.rs");
+        let bytes = b"fn f() {\n    debug!(\"caf\xe9\");\n}\n";
+        fs::write(&file, bytes).unwrap();
+
+        let report = DebugRemover::new(temp.path().to_path_buf()).with_backups(false).remove_debug_calls().unwrap();
+        assert_eq!(fs::read(&file).unwrap(), bytes);
+        assert!(report.failures.is_empty());
+        assert_eq!(report.total_warnings, 1);
+        let warning = &report.file_reports[0].1.warnings[0];
+        assert_eq!(warning.line_number, 2);
+        assert!(warning.message.contains("not valid UTF-8"));
+    }
+
+    #[test]
+    fn test_tidy_whitespace_matches_fixture_and_is_idempotent() {
+        let before = include_str!("../tests/golden/tidy_whitespace.before.rs");
+        let after = include_str!("../tests/golden/tidy_whitespace.after.rs");
+        let (output, report) = remove(before);
+        assert_eq!(output, after);
+        assert_eq!(report.blank_lines_removed, 3);
+
+        let (again, report) = remove(&output);
+        assert_eq!(again, after);
+        assert_eq!(report.blank_lines_removed, 0);
+
+        let untidy = DebugRemover::new(PathBuf::from(".")).with_tidy_whitespace(false);
+        let (output, report) = remove_with(&untidy, before);
+        assert!(output.contains("    let x = a + b;\n\n\n    x * 2\n"));
+        assert_eq!(report.blank_lines_removed, 0);
+    }
+
+    #[test]
+    fn test_json_report_lists_removals_and_warning_only_files() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("a.rs"), "fn a() {\n    debug!(\n        \"a\"\n    );\n}\n").unwrap();
+        fs::write(temp.path().join("b.rs"), "// debug!(\"b\");\n").unwrap();
+        fs::write(temp.path().join("c.rs"), "fn c() {}\n").unwrap();
+
+        let report = DebugRemover::new(temp.path().to_path_buf()).with_dry_run(true).remove_debug_calls().unwrap();
+        assert!(report.has_findings());
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["files_scanned"], 3);
+        assert_eq!(json["total_warnings"], 1);
+        assert_eq!(json["files"][0]["path"], temp.path().join("a.rs").display().to_string());
+        assert_eq!(json["files"][0]["removals"][0]["start_line"], 2);
+        assert_eq!(json["files"][0]["removals"][0]["end_line"], 4);
+        assert_eq!(json["files"][0]["bytes_before"], 42);
+        assert_eq!(json["files"][0]["bytes_after"], 11);
+        assert!(json["files"][0]["elapsed_us"].is_u64());
+        assert!(json["elapsed_ms"].is_u64());
+        assert_eq!(json["slowest_files"].as_array().unwrap().len(), 3);
+        assert_eq!(json["files"][1]["calls_removed"], 0);
+        assert_eq!(json["files"][1]["warnings"][0]["line_number"], 1);
+        assert_eq!(json["failures"], serde_json::json!([]));
+
+        fs::remove_file(temp.path().join("a.rs")).unwrap();
+        fs::remove_file(temp.path().join("b.rs")).unwrap();
+        let clean = DebugRemover::new(temp.path().to_path_buf()).with_dry_run(true).remove_debug_calls().unwrap();
+        assert!(!clean.has_findings());
+    }
+
+    #[test]
+    fn test_review_applies_only_approved_changes() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("lib.rs");
+        fs::write(&file, "fn f(x: u32) -> u32 {\n    debug!(\"a\");\n    debug!(\"b\");\n    let y = dbg!(x).pow(2);\n    dbg!(y)\n}\n").unwrap();
+        
+        let seen = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let report = DebugRemover::new(file.clone())
+            .with_macros(["debug", "dbg"])
+            .with_backups(false)
+            .with_decision_fn(move |candidate| {
+                log.lock().unwrap().push(candidate.clone());
+                match candidate.start_line {
+                    3 | 5 => Decision::No,
+                    _ => Decision::Yes,
+                }
+            })
+            .remove_debug_calls()
+            .unwrap();
+        
+        assert_eq!(fs::read_to_string(&file).unwrap(), "fn f(x: u32) -> u32 {\n    debug!(\"b\");\n    let y = x.pow(2);\n    dbg!(y)\n}\n");
+        assert_eq!((report.total_calls_removed, report.total_calls_rewritten, report.total_warnings), (1, 1, 0));
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.iter().map(|c| c.start_line).collect::<Vec<_>>(), vec![2, 3, 4, 5]);
+        assert_eq!(seen[0].context_before, vec!["fn f(x: u32) -> u32 {"]);
+        assert_eq!(seen[0].context_after.len(), DIFF_CONTEXT);
+        assert_eq!(seen[2].replacement.as_deref(), Some("    let y = x.pow(2);"));
+    }
+    
+    #[test]
+    fn test_review_all_and_quit_carry_across_files() {
+        let temp = tempfile::tempdir().unwrap();
+        for name in ["a.rs", "b.rs", "c.rs"] {
+            fs::write(temp.path().join(name), "fn f() {\n    debug!(\"1\");\n    debug!(\"2\");\n}\n").unwrap();
+        }
+        let asked = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        
+        // `all` on the first prompt approves everything without asking again
+        let count = asked.clone();
+        let report = DebugRemover::new(temp.path().to_path_buf())
+            .with_dry_run(true)
+            .with_decision_fn(move |_| {
+                count.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Decision::All
+            })
+            .remove_debug_calls()
+            .unwrap();
+        assert_eq!(asked.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(report.total_calls_removed, 6);
+        
+        // `quit` keeps what was approved in the current file and leaves later files alone
+        let answers = Mutex::new(vec![Decision::Quit, Decision::Yes]);
+        let report = DebugRemover::new(temp.path().to_path_buf())
+            .with_backups(false)
+            .with_decision_fn(move |_| answers.lock().unwrap().pop().unwrap())
+            .remove_debug_calls()
+            .unwrap();
+        assert_eq!(fs::read_to_string(temp.path().join("a.rs")).unwrap(), "fn f() {\n    debug!(\"2\");\n}\n");
+        for name in ["b.rs", "c.rs"] {
+            assert_eq!(fs::read_to_string(temp.path().join(name)).unwrap(), "fn f() {\n    debug!(\"1\");\n    debug!(\"2\");\n}\n");
+        }
+        assert_eq!((report.files_scanned, report.files_modified, report.total_calls_removed), (1, 1, 1));
+    }
+    
+    #[test]
+    fn test_github_annotations_for_check_runs() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("lib.rs");
+        fs::write(&file, "fn f(x: u32) -> u32 {\n    debug!(\"x = {}\", x);\n    let y = dbg!(x);\n    let z = 1; debug!(\"50%\");\n    y + z\n}\n").unwrap();
+        let report = DebugRemover::new(file.clone())
+            .with_macros(["debug", "dbg"])
+            .with_dry_run(true)
+            .remove_debug_calls()
+            .unwrap();
+        
+        let file = file.display();
+        assert_eq!(report.github_annotations(true), vec![
+            format!("::warning file={},line=2::Debug call would be removed: debug!(\"x = {{}}\", x);", file),
+            format!("::warning file={},line=3::dbg! would be rewritten: let y = x;", file),
+            format!("::warning file={},line=4::debug! found with other code on same line - skipping", file),
+        ]);
+        assert_eq!(report.github_annotations(false).len(), 1);
+    }
+    
+    #[test]
+    fn test_calls_inside_literals_are_kept_silently() {
+        let content = concat!(
+            "let s = \"call debug!(x) later\";\n",
+            "assert_eq!(out, \"debug!(\\\"quoted\\\");\");\n",
+            "let raw = r#\"\n",
+            "    debug!(\"inside raw string\");\n",
+            "\"#;\n",
+            "let c = '\"'; debug!(\"after a quote char\");\n",
+            "let bytes = br\"debug!(y)\";\n",
+        );
+        let (output, report) = remove(content);
+        assert_eq!(output, content);
+        assert_eq!(report.calls_removed, 0);
+        assert_eq!(report.warnings.len(), 1, "{:?}", report.warnings);
+        assert_eq!(report.warnings[0].line_number, 6);
+        assert!(report.warnings[0].message.contains("other code"));
+    }
+
+    #[test]
+    fn test_comments_warn_and_code_after_literal_is_found() {
+        let content = "/* start\n   debug!(\"in block\");\n*/\n// debug!(\"line comment\");\nlet s = \"x\"; debug!(\"y\");\n    debug!(\"a \\\" ) ; b\");\n";
+        let (output, report) = remove(content);
+        assert_eq!(output, "/* start\n   debug!(\"in block\");\n*/\n// debug!(\"line comment\");\nlet s = \"x\"; debug!(\"y\");\n");
+        assert_eq!(report.calls_removed, 1);
+        let messages: Vec<(usize, bool)> = report.warnings.iter()
+            .map(|w| (w.line_number, w.message.contains("comment")))
+            .collect();
+        assert_eq!(messages, vec![(2, true), (4, true), (5, false)]);
+    }
+
+    #[test]
+    fn test_trailing_comment_and_unterminated_call() {
+        let (output, report) = remove("debug!(\"a\"); // trace\nkeep();\n");
+        assert_eq!(output, "keep();\n");
+        assert_eq!(report.calls_removed, 1);
+
+        let (output, report) = remove("debug!(\"a\",\n");
+        assert_eq!(output, "debug!(\"a\",\n");
+        assert_eq!(report.warnings.len(), 1);
+    }
+}
+/// tracing's structured call syntax and `<level>_span!` statements
+#[cfg(test)]
+mod tracing_syntax_tests {
+    use super::*;
+
+    /// Run `snippet` with the default macros; returns (removed, warned) start lines
+    fn classify(snippet: &str) -> (Vec<usize>, Vec<usize>) {
+        let remover = DebugRemover::new(PathBuf::from("."));
+        let patterns = CallPatterns::new(&remover.macros).unwrap();
+        let (output, report) = remover.remove_debug_from_content(snippet, &patterns, &Allowed::default());
+
+        let removed_lines: Vec<usize> = report.removals.iter().flat_map(|r| r.start_line..=r.end_line).collect();
+        let expected: String = snippet.split_inclusive('\n')
+            .enumerate()
+            .filter(|(i, _)| !removed_lines.contains(&(i + 1)))
+            .map(|(_, line)| line)
+            .collect();
+        assert_eq!(output, expected, "output of {:?}", snippet);
+
+        (
+            report.removals.iter().map(|r| r.start_line).collect(),
+            report.warnings.iter().map(|w| w.line_number).collect(),
+        )
+    }
+
+    #[test]
+    fn test_representative_snippets() {
+        let cases: &[(&str, &[usize], &[usize])] = &[
+            ("debug!(target: \"net\", \"msg {}\", x);\n", &[1], &[]),
+            ("debug!(?value, \"inspect\");\n", &[1], &[]),
+            ("tracing::debug!(%addr, port = ?p, \"connect\");\n", &[1], &[]),
+            ("debug!(parent: &span, \"child\");\n", &[1], &[]),
+            ("debug!(\n    target: \"net\",\n    peer = %addr,\n    \"handshake\"\n);\nok();\n", &[1], &[]),
+            ("let _e = debug_span!(\"step\").entered();\nwork();\n", &[1], &[]),
+            ("let _span = tracing::debug_span!(\"load\", path = ?p).entered(); // timing\n", &[1], &[]),
+            ("let _ = debug_span!(\"x\").entered();\n", &[1], &[]),
+            ("let _g = debug_span!(\n    \"multi\",\n    id = 3\n).entered();\n", &[1], &[]),
+            ("let span = debug_span!(\"work\");\nlet _g = span.enter();\n", &[], &[1]),
+            ("let span = debug_span!(\"x\");\nspan.in_scope(|| run());\n", &[], &[1]),
+            ("let s = debug_span!(\"x\").in_scope(|| 1);\n", &[], &[1]),
+            ("let span = debug_span!(\"x\");\nlet s = \"span\"; // span\n", &[1], &[]),
+            ("let _span = info_span!(\"other level\").entered();\n", &[], &[]),
+        ];
+
+        for (snippet, removed, warned) in cases {
+            assert_eq!(classify(snippet), (removed.to_vec(), warned.to_vec()), "{:?}", snippet);
+        }
+    }
+
+    #[test]
+    fn test_spans_are_counted_under_their_own_name() {
+        let remover = DebugRemover::new(PathBuf::from(".")).with_macros(["trace", "debug"]);
+        let patterns = CallPatterns::new(&remover.macros).unwrap();
+        let content = "let _a = trace_span!(\"a\").entered();\nlet _b = debug_span!(\"b\").entered();\ndebug!(\"c\");\n";
+        let (output, report) = remover.remove_debug_from_content(content, &patterns, &Allowed::default());
+        assert_eq!(output, "");
+        let counts: Vec<(&str, usize)> = report.calls_by_macro.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+        assert_eq!(counts, vec![("debug", 1), ("debug_span", 1), ("trace_span", 1)]);
+    }
+}
+
+/// Cases the syntax-tree engine removes where the regex engine has to skip
+#[cfg(all(test, feature = "ast"))]
+mod engine_comparison_tests {
+    use super::*;
+
+    /// Output and report of each engine
+    fn both(content: &str, macros: &[&str]) -> [(String, FileReport); 2] {
+        [Engine::Regex, Engine::Ast].map(|engine| {
+            let remover = DebugRemover::new(PathBuf::from(".")).with_macros(macros.iter().copied()).with_engine(engine);
+            let patterns = CallPatterns::new(&remover.macros).unwrap();
+            remover.remove_from_content(content, &patterns, &Allowed::default())
+        })
+    }
+
+    #[test]
+    fn test_ast_engine_removes_what_regex_skips() {
+        let cases = [
+            (
+                "fn f(v: bool) {\n    if v { debug!(\"x\"); }\n    run();\n}\n",
+                "fn f(v: bool) {\n    run();\n}\n",
+            ),
+            (
+                "fn f() {\n    if check() { debug!(\"x\"); }\n}\n",
+                "fn f() {\n    if check() {}\n}\n",
+            ),
+            (
+                "macro_rules! m { ($x:expr) => {{ debug!(\"{}\", $x); $x }}; }\n",
+                "macro_rules! m { ($x:expr) => {{ $x }}; }\n",
+            ),
+            (
+                "fn f(items: &[u32]) {\n    items.iter().for_each(|i| { debug!(\"{}\", i); use_it(i); });\n}\n",
+                "fn f(items: &[u32]) {\n    items.iter().for_each(|i| { use_it(i); });\n}\n",
+            ),
+        ];
+        for (content, expected) in cases {
+            let [(regex_output, regex_report), (ast_output, ast_report)] = both(content, &["debug"]);
+            assert_eq!(regex_output, content, "regex engine changed {:?}", content);
+            assert!(!regex_report.warnings.is_empty(), "regex engine did not warn on {:?}", content);
+            assert_eq!(ast_output, expected);
+            assert_eq!(ast_report.calls_removed, 1);
+            assert!(ast_report.warnings.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_ast_engine_rewrites_compound_dbg_arguments() {
+        let content = "fn f(a: u32, b: u32) -> u32 {\n    dbg!(a);\n    let y = dbg!(a + b) * 2;\n    dbg!(y)\n}\n";
+        let [(regex_output, regex_report), (ast_output, ast_report)] = both(content, &["dbg"]);
+        assert!(regex_output.contains("dbg!(a + b)"));
+        assert_eq!(regex_report.warnings.len(), 1);
+        assert_eq!(ast_output, "fn f(a: u32, b: u32) -> u32 {\n    let y = (a + b) * 2;\n    y\n}\n");
+        assert_eq!((ast_report.calls_removed, ast_report.calls_rewritten), (1, 2));
+    }
+
+    #[test]
+    fn test_unparsable_file_falls_back_to_regex() {
+        let content = "fn f( {\n    debug!(\"x\");\n";
+        let [(regex_output, _), (ast_output, ast_report)] = both(content, &["debug"]);
+        assert_eq!(ast_output, regex_output);
+        assert_eq!(ast_report.calls_removed, 1);
+        assert!(ast_report.ast_fallback.unwrap().starts_with("line "));
+    }
+}
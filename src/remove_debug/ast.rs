@@ -0,0 +1,279 @@
+//! Syntax-tree engine for `remove-debug` (feature `ast`)
+//!
+//! Parses the file with `syn` and uses token spans to find macro calls that the
+//! line-based regex engine has to skip:
+//!
+//! - statements sharing a line with other code, e.g. `if cond { debug!("x"); }`;
+//!   a single-line block left empty becomes `{}`, and an `if` without `else` whose
+//!   body was only removable calls is removed whole when its condition has no side
+//!   effects (paths, literals, field access, and operators on them)
+//! - calls inside `macro_rules!` bodies (`name!(...);` token sequences)
+//! - `dbg!` anywhere in an expression: `dbg!(a + b) * 2` becomes `(a + b) * 2` and
+//!   `dbg!(a, b)` becomes `(a, b)`
+//!
+//! Other configured macros used as values (e.g. a match arm `=> debug!("x"),`)
+//! are reported, not removed. The engine only finds edits; applying them to the
+//! text, the branch scope, and the report are shared with the regex engine.
+
+use proc_macro2::{LineColumn, Span, TokenStream, TokenTree};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Block, Expr, ExprMacro, ItemMacro, Macro, Stmt, Token};
+
+/// A change to the source found in the syntax tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum AstEdit {
+    /// Delete bytes `start..end` (one or more whole statements)
+    Remove {
+        start: usize,
+        end: usize,
+        /// Macro name of every call removed
+        calls: Vec<String>,
+        /// Replacement text, e.g. `{}` for an emptied block
+        replacement: String,
+    },
+    /// Replace a `dbg!` expression with its argument
+    Rewrite { start: usize, end: usize, replacement: String },
+    /// A configured call that cannot be removed safely
+    Skip { start: usize, message: String },
+}
+
+impl AstEdit {
+    pub(super) fn start(&self) -> usize {
+        match self {
+            AstEdit::Remove { start, .. } | AstEdit::Rewrite { start, .. } | AstEdit::Skip { start, .. } => *start,
+        }
+    }
+}
+
+/// Edits for every configured macro call in `content`, ordered by position
+pub(super) fn find_edits(content: &str, macros: &[String]) -> syn::Result<Vec<AstEdit>> {
+    let file = syn::parse_file(content)?;
+    let mut finder = EditFinder {
+        content,
+        macros,
+        line_starts: std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect(),
+        edits: Vec::new(),
+    };
+    finder.visit_file(&file);
+    finder.edits.sort_by_key(AstEdit::start);
+    Ok(finder.edits)
+}
+
+struct EditFinder<'a> {
+    content: &'a str,
+    macros: &'a [String],
+    line_starts: Vec<usize>,
+    edits: Vec<AstEdit>,
+}
+
+impl EditFinder<'_> {
+    fn offset(&self, at: LineColumn) -> usize {
+        let line_start = self.line_starts.get(at.line.saturating_sub(1)).copied().unwrap_or(self.content.len());
+        self.content[line_start..]
+            .char_indices()
+            .nth(at.column)
+            .map_or(self.content.len(), |(i, _)| line_start + i)
+    }
+
+    fn range(&self, span: Span) -> (usize, usize) {
+        (self.offset(span.start()), self.offset(span.end()))
+    }
+
+    /// Configured macro name of `mac`, if it is one
+    fn configured(&self, mac: &Macro) -> Option<String> {
+        let name = mac.path.segments.last()?.ident.to_string();
+        self.macros.contains(&name).then_some(name)
+    }
+
+    /// Macro name of a statement that can be deleted outright: `name!(...);`
+    fn removable(&self, stmt: &Stmt) -> Option<String> {
+        match stmt {
+            Stmt::Macro(stmt) if stmt.semi_token.is_some() => self.configured(&stmt.mac),
+            _ => None,
+        }
+    }
+
+    fn single_line(span: Span) -> bool {
+        span.start().line == span.end().line
+    }
+
+    /// Replace `dbg!(args)` with its value
+    fn rewrite_dbg(&mut self, span: Span, mac: &Macro) {
+        let (start, end) = self.range(span);
+        let replacement = match mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            Ok(args) if args.len() == 1 => {
+                let arg = &args[0];
+                let (arg_start, arg_end) = self.range(arg.span());
+                let text = &self.content[arg_start..arg_end];
+                if binds_tightly(arg) { text.to_string() } else { format!("({})", text) }
+            }
+            Ok(args) => {
+                let texts: Vec<&str> = args.iter()
+                    .map(|arg| self.range(arg.span()))
+                    .map(|(s, e)| &self.content[s..e])
+                    .collect();
+                format!("({})", texts.join(", "))
+            }
+            Err(_) => {
+                self.edits.push(AstEdit::Skip { start, message: "dbg! arguments could not be parsed - skipping".to_string() });
+                return;
+            }
+        };
+        self.edits.push(AstEdit::Rewrite { start, end, replacement });
+    }
+
+    /// Find `name!(...);` sequences in a `macro_rules!` body
+    fn scan_tokens(&mut self, tokens: TokenStream) {
+        let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+        let mut i = 0;
+        while i < tokens.len() {
+            if let [TokenTree::Ident(name), TokenTree::Punct(bang), TokenTree::Group(_), TokenTree::Punct(semi), ..] = &tokens[i..] {
+                let name = name.to_string();
+                if bang.as_char() == '!' && semi.as_char() == ';' && self.macros.contains(&name) {
+                    let start = self.offset(tokens[i].span().start());
+                    let end = self.offset(semi.span().end());
+                    self.edits.push(AstEdit::Remove { start, end, calls: vec![name], replacement: String::new() });
+                    i += 4;
+                    continue;
+                }
+            }
+            if let TokenTree::Group(group) = &tokens[i] {
+                self.scan_tokens(group.stream());
+            }
+            i += 1;
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for EditFinder<'_> {
+    fn visit_block(&mut self, block: &'ast Block) {
+        let calls: Vec<String> = block.stmts.iter().map_while(|stmt| self.removable(stmt)).collect();
+        let span = block.brace_token.span.join();
+        if !block.stmts.is_empty() && calls.len() == block.stmts.len() && Self::single_line(span) {
+            // `{ debug!("x"); }` on one line: leave an empty block behind
+            let (start, end) = self.range(span);
+            self.edits.push(AstEdit::Remove { start, end, calls, replacement: "{}".to_string() });
+            return;
+        }
+
+        for stmt in &block.stmts {
+            if let Some(name) = self.removable(stmt) {
+                let (start, end) = self.range(stmt.span());
+                self.edits.push(AstEdit::Remove { start, end, calls: vec![name], replacement: String::new() });
+                continue;
+            }
+            match stmt {
+                Stmt::Expr(Expr::If(expr_if), _) if expr_if.else_branch.is_none() && is_pure(&expr_if.cond) => {
+                    let calls: Vec<String> = expr_if.then_branch.stmts.iter().map_while(|s| self.removable(s)).collect();
+                    if !calls.is_empty() && calls.len() == expr_if.then_branch.stmts.len() {
+                        let (start, end) = self.range(stmt.span());
+                        self.edits.push(AstEdit::Remove { start, end, calls, replacement: String::new() });
+                        continue;
+                    }
+                }
+                Stmt::Macro(stmt_macro) if stmt_macro.mac.path.is_ident("macro_rules") => {
+                    self.scan_tokens(stmt_macro.mac.tokens.clone());
+                    continue;
+                }
+                Stmt::Macro(stmt_macro) => {
+                    // A configured macro as the block's value
+                    if let Some(name) = self.configured(&stmt_macro.mac) {
+                        if name == "dbg" {
+                            self.rewrite_dbg(stmt.span(), &stmt_macro.mac);
+                        } else {
+                            let start = self.range(stmt.span()).0;
+                            self.edits.push(AstEdit::Skip { start, message: format!("{}! is the value of its block - skipping", name) });
+                        }
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            visit::visit_stmt(self, stmt);
+        }
+    }
+
+    fn visit_expr_macro(&mut self, expr: &'ast ExprMacro) {
+        let Some(name) = self.configured(&expr.mac) else {
+            return;
+        };
+        if name == "dbg" {
+            self.rewrite_dbg(expr.span(), &expr.mac);
+        } else {
+            let start = self.range(expr.span()).0;
+            self.edits.push(AstEdit::Skip { start, message: format!("{}! used as an expression - skipping", name) });
+        }
+    }
+
+    fn visit_item_macro(&mut self, item: &'ast ItemMacro) {
+        if item.mac.path.is_ident("macro_rules") {
+            self.scan_tokens(item.mac.tokens.clone());
+        }
+    }
+}
+
+/// Whether `expr` can take the place of a `dbg!(...)` call without parentheses
+fn binds_tightly(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Path(_) | Expr::Lit(_) | Expr::Field(_) | Expr::MethodCall(_) | Expr::Call(_)
+            | Expr::Paren(_) | Expr::Index(_) | Expr::Macro(_) | Expr::Tuple(_) | Expr::Array(_)
+    )
+}
+
+/// Whether evaluating `expr` has no side effects worth keeping
+fn is_pure(expr: &Expr) -> bool {
+    match expr {
+        Expr::Path(_) | Expr::Lit(_) => true,
+        Expr::Field(field) => is_pure(&field.base),
+        Expr::Paren(paren) => is_pure(&paren.expr),
+        Expr::Unary(unary) => is_pure(&unary.expr),
+        Expr::Binary(binary) => is_pure(&binary.left) && is_pure(&binary.right),
+        Expr::Reference(reference) => is_pure(&reference.expr),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edits(content: &str, macros: &[&str]) -> Vec<AstEdit> {
+        let macros: Vec<String> = macros.iter().map(|m| m.to_string()).collect();
+        find_edits(content, &macros).unwrap()
+    }
+
+    #[test]
+    fn test_single_line_block_is_emptied() {
+        let content = "fn f(x: u32) {\n    if check(x) { debug!(\"x\"); }\n}\n";
+        let start = content.find("{ debug").unwrap();
+        assert_eq!(edits(content, &["debug"]), vec![AstEdit::Remove {
+            start,
+            end: start + "{ debug!(\"x\"); }".len(),
+            calls: vec!["debug".to_string()],
+            replacement: "{}".to_string(),
+        }]);
+    }
+
+    #[test]
+    fn test_dbg_argument_is_parenthesized_when_needed() {
+        let content = "fn f() {\n    let y = dbg!(a + b) * 2;\n    let n = dbg!(v.len());\n    let t = dbg!(a, b);\n}\n";
+        let replacements: Vec<String> = edits(content, &["dbg"]).into_iter()
+            .map(|edit| match edit {
+                AstEdit::Rewrite { replacement, .. } => replacement,
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(replacements, vec!["(a + b)", "v.len()", "(a, b)"]);
+    }
+
+    #[test]
+    fn test_parse_errors_are_returned() {
+        let macros = vec!["debug".to_string()];
+        assert!(find_edits("fn f( {", &macros).is_err());
+    }
+}
@@ -50,18 +50,21 @@
 //!
 //! ### With Tracing Integration
 //! ```rust,no_run
-//! use autodebugger::{init_logging_with_file, RotatingFileConfig};
+//! use autodebugger::{init_logging_with_file, Config, LogFormat, RotatingFileConfig, Rotation};
 //!
 //! let config = RotatingFileConfig {
 //!     log_directory: "logs".to_string(),
 //!     filename: "app.log".to_string(),
 //!     max_files: 5,
 //!     max_size_mb: 10,
-//!     console_output: true,
-//!     truncate_on_limit: true,
+//!     // Pretty console, JSON file
+//!     format: Some(LogFormat::Json),
+//!     // A new file every day, and at 10MB
+//!     rotation: Rotation::SizeOrDaily,
+//!     ..RotatingFileConfig::default()
 //! };
 //!
-//! let _layer = init_logging_with_file(Some("info"), None, None, config);
+//! let _layer = init_logging_with_file(Some("info"), &Config::default(), None, Some(LogFormat::Pretty), config);
 //! ```
 //!
 //! ## File Naming Convention
@@ -70,22 +73,74 @@
 //! - Current log: `timestamped/app_YYYYMMDD_HHMMSS.log` (new file per run)
 //! - Latest symlink: `app_latest.log` (at directory root, points to `timestamped/app_YYYYMMDD_HHMMSS.log`)
 //!
-//! When size limit is exceeded:
-//! - `truncate_on_limit: true` (default): stops logging, preserves history across runs
+//! When the next line would exceed the size limit:
+//! - `truncate_on_limit: true` (default): stops logging for the rest of the run,
+//!   noted once on stderr and by `size_limit_reached()` on the wrapper and guard
 //! - `truncate_on_limit: false`: creates numbered backups within the same run
 //!
+//! Writes are buffered up to the last newline, so the limit is checked per whole
+//! line: a file never ends in a torn line and never exceeds the limit, unless a
+//! single line is larger than the limit (it then gets a file to itself). A trailing
+//! partial line is written out on flush.
+//!
+//! With `rotation: daily` or `hourly` the file is instead rolled over to a new
+//! timestamped file when a local day or hour boundary passes (checked on each
+//! write), whatever its size; `size_or_daily` rotates on both.
+//!
+//! ## Header
+//!
+//! With `write_header: true` (default) every new file, per run and per rotation,
+//! starts with a line naming the crate and version, start time, process id, host,
+//! log filter, and command line; in JSON mode it is a JSON object shaped like the
+//! log lines (target `autodebugger::log_header`). The header is written just
+//! before the file's first line and counts toward its size.
+//!
+//! ## Compression
+//!
+//! With `compress_rotated: true` each file rotated out is gzipped on a background
+//! thread by the `gzip` tool: numbered backups become `.log.1.gz` and time-rotated
+//! files `.log.gz`. gzip only removes the original once the `.gz` is complete, so a
+//! failed compression leaves the plain file. The latest symlink always points at the
+//! live, uncompressed file.
+//!
+//! ## Retention
+//!
+//! When the writer starts and after each rotation, `max_files` caps all timestamped
+//! files of this base name, including earlier runs, numbered backups, and their
+//! compressed forms: the oldest
+//! by timestamp are deleted (never the active file) and listed in a debug log line.
+//!
+//! `max_total_size_mb` additionally caps their combined size, deleting oldest first
+//! until the rest fit; whichever limit is stricter wins. The active file keeps
+//! growing between rotations, so usage can exceed the cap until the next one.
+//! `RotatingFileGuard::directory_usage()` reports the current total.
+//!
+//! ## Non-blocking Mode
+//!
+//! With `non_blocking: true` writes go through [`crate::non_blocking`]: each line is
+//! queued for a dedicated writer thread that owns the file, so a slow disk (or a
+//! rotation) never stalls the logging thread. Up to `buffered_lines` lines are
+//! queued; beyond that `when_full` either drops the oldest line or blocks. The
+//! [`RotatingFileGuard`] returned by [`RotatingWriterWrapper::non_blocking`] counts
+//! dropped lines, and on drop writes out the queue, flushes, and joins the thread.
+//!
 //! ## Performance Considerations
 //!
-//! - File size checks are performed on each write
+//! - File size checks are performed on each complete line
 //! - Rotation is atomic but may cause brief write delays
 //! - Consider rotation size based on write frequency
-//! - Use async logging for high-throughput applications
+//! - Use non-blocking mode for high-throughput applications
 
-use crate::config::RotatingFileConfig;
+use crate::config::{LogFormat, RotatingFileConfig, Rotation};
+use crate::non_blocking::{NonBlocking, NonBlockingWorker};
+use chrono::{DateTime, Local};
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::process::Command;
+use std::cell::Cell;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::JoinHandle;
 use tracing_subscriber::fmt::MakeWriter;
 
 /// Builder for configuring the rotating file logger
@@ -124,6 +179,26 @@ impl RotatingFileLoggerBuilder {
         self
     }
 
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.config.rotation = rotation;
+        self
+    }
+
+    pub fn with_compress_rotated(mut self, compress: bool) -> Self {
+        self.config.compress_rotated = compress;
+        self
+    }
+
+    pub fn with_max_total_size_mb(mut self, size: u64) -> Self {
+        self.config.max_total_size_mb = Some(size);
+        self
+    }
+
+    pub fn with_non_blocking(mut self, non_blocking: bool) -> Self {
+        self.config.non_blocking = non_blocking;
+        self
+    }
+
     /// Build and initialize the rotating file logger
     /// Returns a guard that should be kept alive for the duration of logging
     pub fn build(self) -> Result<RotatingFileGuard, std::io::Error> {
@@ -156,35 +231,114 @@ impl RotatingFileLogger {
         let writer = RotatingWriter::new(config.clone())?;
         let writer = Arc::new(Mutex::new(writer));
 
-        Ok(RotatingFileGuard { _writer: writer })
+        Ok(RotatingFileGuard { writer, worker: None })
     }
 }
 
 /// Guard that keeps the rotating logger alive
 /// Drop this to stop logging to files
 pub struct RotatingFileGuard {
-    _writer: Arc<Mutex<RotatingWriter>>,
+    writer: Arc<Mutex<RotatingWriter>>,
+    /// Writer thread in non-blocking mode; flushed and joined when the guard drops
+    worker: Option<NonBlockingWorker>,
+}
+
+impl RotatingFileGuard {
+    /// Whether `truncate_on_limit` has stopped logging to the current file
+    pub fn size_limit_reached(&self) -> bool {
+        self.writer.lock().unwrap().size_limit_reached
+    }
+
+    /// Number and total size of the logger's files, against `max_total_size_mb`
+    pub fn directory_usage(&self) -> DirectoryUsage {
+        self.writer.lock().unwrap().directory_usage()
+    }
+
+    /// Lines dropped in non-blocking mode because the buffer was full (always 0 otherwise)
+    pub fn dropped_lines(&self) -> u64 {
+        self.worker.as_ref().map_or(0, NonBlockingWorker::dropped_lines)
+    }
+
+    /// Wait for lines queued in non-blocking mode to reach the file
+    pub fn flush(&self) {
+        if let Some(worker) = &self.worker {
+            worker.flush();
+        }
+    }
 }
 
+/// Disk used by a rotating logger's files, from `RotatingFileGuard::directory_usage`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirectoryUsage {
+    /// Timestamped files of this logger: earlier runs, backups, and compressed files
+    pub files: usize,
+    pub bytes: u64,
+    /// `max_total_size_mb` in bytes, if set
+    pub max_bytes: Option<u64>,
+}
+
+/// A timestamped log file found in the log directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFile {
+    /// `YYYYMMDD_HHMMSS` when the file was opened
+    pub timestamp: String,
+    /// Backup number, 0 for a run's live file
+    pub backup: u32,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+impl LogFile {
+    /// Whether the file was gzipped when rotated out (`.gz`)
+    pub fn compressed(&self) -> bool {
+        self.path.extension().is_some_and(|extension| extension == "gz")
+    }
+}
+
+/// Source of the current time, replaceable in tests
+type Clock = Arc<dyn Fn() -> DateTime<Local> + Send + Sync>;
+
+/// Current log filter directives, for the file header
+type FilterSource = Box<dyn Fn() -> Option<String> + Send>;
+
 /// The actual rotating file writer
 struct RotatingWriter {
     config: RotatingFileConfig,
     current_file: fs::File,
     current_size: u64,
     log_path: PathBuf,
+    clock: Clock,
+    /// Day or hour the current file belongs to, for time-based rotation
+    period: Option<String>,
+    /// Files deleted for retention and not yet logged; logged by `RotatingWriterGuard`
+    /// once the writer is unlocked, since a log event here would re-enter the writer
+    pruned: Vec<String>,
+    /// Background gzip of the last rotated-out file
+    compressing: Option<JoinHandle<()>>,
+    /// Bytes after the last newline, held until the rest of the line arrives
+    pending: Vec<u8>,
+    /// `truncate_on_limit` stopped logging to the current file
+    size_limit_reached: bool,
+    /// When the current file was opened, until its header is written before the first line
+    header_pending: Option<DateTime<Local>>,
+    /// Format of the header line
+    format: LogFormat,
+    filter: Option<FilterSource>,
 }
 
 impl RotatingWriter {
     fn new(config: RotatingFileConfig) -> Result<Self, std::io::Error> {
+        Self::with_clock(config, Arc::new(Local::now))
+    }
+
+    fn with_clock(config: RotatingFileConfig, clock: Clock) -> Result<Self, std::io::Error> {
         // Create timestamped subdirectory for actual log files
         let timestamped_dir = PathBuf::from(&config.log_directory).join("timestamped");
         fs::create_dir_all(&timestamped_dir)?;
 
         // Add timestamp to filename to create unique file per run
-        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-        let base_name = config.filename.trim_end_matches(".log");
-        let timestamped_filename = format!("{}_{}.log", base_name, timestamp);
-        let log_path = timestamped_dir.join(&timestamped_filename);
+        let now = clock();
+        let log_path = timestamped_path(&config, now);
         
         // Create new file for this run (not append)
         let current_file = fs::OpenOptions::new()
@@ -196,42 +350,145 @@ impl RotatingWriter {
         // Get current file size
         let current_size = current_file.metadata()?.len();
 
-        let writer = Self {
+        let mut writer = Self {
+            period: period_of(config.rotation, now),
+            header_pending: config.write_header.then_some(now),
+            format: config.format.unwrap_or_default(),
+            filter: None,
             config,
             current_file,
             current_size,
             log_path,
+            clock,
+            pruned: Vec::new(),
+            compressing: None,
+            pending: Vec::new(),
+            size_limit_reached: false,
         };
         
         // Create initial symlink to current log file
         let _ = writer.update_latest_symlink(); // Ignore errors, just log warnings
         
+        // Drop logs from earlier runs beyond max_files
+        writer.prune_old_files();
+        
         Ok(writer)
     }
 
-    /// Rotate log files: app.log -> app.log.1, app.log.1 -> app.log.2, etc.
+    /// Whether a day or hour boundary has passed since the current file was opened
+    fn period_ended(&self, now: DateTime<Local>) -> bool {
+        self.period.is_some() && period_of(self.config.rotation, now) != self.period
+    }
+
+    /// Roll over to a new timestamped file for the period starting at `now`
+    fn rotate_to_new_file(&mut self, now: DateTime<Local>) -> Result<(), std::io::Error> {
+        self.current_file.flush()?;
+        self.wait_for_compression();
+
+        let closed_path = std::mem::replace(&mut self.log_path, timestamped_path(&self.config, now));
+        // Append in case a rotation in the same second reuses the name
+        self.current_file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.log_path)?;
+        self.current_size = self.current_file.metadata()?.len();
+        self.period = period_of(self.config.rotation, now);
+        self.size_limit_reached = false;
+        self.header_pending = self.config.write_header.then_some(now);
+
+        let _ = self.update_latest_symlink(); // Ignore errors, just log warnings
+        if self.config.compress_rotated && closed_path != self.log_path {
+            self.compress_in_background(closed_path);
+        }
+        self.prune_old_files();
+        Ok(())
+    }
+
+    /// Gzip `path` to `<path>.gz` on a background thread. gzip removes the
+    /// original only once the compressed copy is complete.
+    fn compress_in_background(&mut self, path: PathBuf) {
+        self.compressing = Some(std::thread::spawn(move || {
+            if let Err(e) = gzip(&path) {
+                tracing::warn!("Failed to compress rotated log {}: {}", path.display(), e);
+            }
+        }));
+    }
+
+    /// Wait for the previous compression, so backups are not renamed or pruned under it
+    fn wait_for_compression(&mut self) {
+        if let Some(handle) = self.compressing.take() {
+            let _ = handle.join();
+        }
+    }
+
+    /// Timestamped files of this base name, newest first (see `log_files`)
+    fn list_log_files(&self) -> Vec<LogFile> {
+        log_files(&self.config)
+    }
+
+    /// Delete the oldest timestamped files of this base name beyond the retention
+    /// limits (see `prune_files`); the active file, which the latest symlink
+    /// points to, is never deleted
+    fn prune_old_files(&mut self) {
+        let files = self.list_log_files();
+        let (max_files, max_total_size_mb) = (self.config.max_files, self.config.max_total_size_mb);
+        prune_files(files, max_files, max_total_size_mb, |path| self.remove_log_file(path));
+    }
+
+    /// Remove `path` unless it is the active file, noting it for the pruned log line
+    fn remove_log_file(&mut self, path: &Path) -> bool {
+        if path == self.log_path || fs::remove_file(path).is_err() {
+            return false;
+        }
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned());
+        self.pruned.extend(name);
+        true
+    }
+
+    /// Number and total size of this logger's timestamped files
+    fn directory_usage(&self) -> DirectoryUsage {
+        let files = self.list_log_files();
+        DirectoryUsage {
+            files: files.len(),
+            bytes: files.iter().map(|file| file.size).sum(),
+            max_bytes: self.config.max_total_size_mb.map(|mb| mb * 1024 * 1024),
+        }
+    }
+
+    /// Rotate log files: app_<ts>.log -> app_<ts>.log.1, app_<ts>.log.1 -> app_<ts>.log.2, etc.
+    ///
+    /// `max_files` counts the live file, so at most `max_files - 1` backups are kept;
+    /// with `max_files` of 1 the live file is simply started over.
     fn rotate(&mut self) -> Result<(), std::io::Error> {
         // Flush current file
         self.current_file.flush()?;
+        self.wait_for_compression();
 
-        // Rotate existing numbered files
-        for i in (1..self.config.max_files).rev() {
-            let old_path = self.log_path.with_extension(format!("log.{}", i));
-            let new_path = self.log_path.with_extension(format!("log.{}", i + 1));
-            
-            if old_path.exists() {
-                if i + 1 >= self.config.max_files {
-                    // Delete the oldest file if we're at max
+        let max_backups = self.config.max_files.saturating_sub(1);
+        // Shift existing numbered files up one, compressed or not; the oldest
+        // (numbered `max_backups`) would go past the limit and is deleted instead
+        for i in (1..=max_backups).rev() {
+            for compressed in [false, true] {
+                let old_path = backup_path(&self.log_path, i, compressed);
+                if !old_path.exists() {
+                    continue;
+                }
+                if i == max_backups {
                     fs::remove_file(&old_path)?;
                 } else {
-                    fs::rename(&old_path, &new_path)?;
+                    fs::rename(&old_path, backup_path(&self.log_path, i + 1, compressed))?;
                 }
             }
         }
 
         // Move current log to .1
-        let backup_path = self.log_path.with_extension("log.1");
-        fs::rename(&self.log_path, &backup_path)?;
+        if max_backups > 0 {
+            let first_backup = backup_path(&self.log_path, 1, false);
+            fs::rename(&self.log_path, &first_backup)?;
+            if self.config.compress_rotated {
+                self.compress_in_background(first_backup);
+            }
+        }
 
         // Create new empty log file
         self.current_file = fs::OpenOptions::new()
@@ -241,28 +498,106 @@ impl RotatingWriter {
             .open(&self.log_path)?;
         
         self.current_size = 0;
+        self.header_pending = self.config.write_header.then(|| (self.clock)());
         
         // Update symlink to point to the new log file
         let _ = self.update_latest_symlink(); // Ignore errors, just log warnings
         
+        self.prune_old_files();
         Ok(())
     }
 
-    fn should_rotate(&self) -> bool {
-        self.current_size >= self.config.max_size_mb * 1024 * 1024
+    /// Whether writing `len` more bytes would take a non-empty file past `max_size_mb`
+    fn should_rotate(&self, len: usize) -> bool {
+        matches!(self.config.rotation, Rotation::Size | Rotation::SizeOrDaily)
+            && self.current_size > 0
+            && self.current_size + len as u64 > self.config.max_size_mb * 1024 * 1024
+    }
+
+    /// Write whole lines, rotating or stopping beforehand so they are never split
+    /// across files or cut off at the size limit
+    fn write_lines(&mut self, lines: &[u8]) -> std::io::Result<()> {
+        // Check for a day or hour boundary
+        let now = (self.clock)();
+        if self.period_ended(now) {
+            self.rotate_to_new_file(now)?;
+        }
+
+        if self.size_limit_reached {
+            return Ok(());
+        }
+        // Check if these lines would pass the size limit
+        if self.should_rotate(lines.len()) {
+            if self.config.truncate_on_limit {
+                // Truncate mode: stop logging when limit is reached. Reported on stderr,
+                // as a log event here would be written back through this writer.
+                self.size_limit_reached = true;
+                eprintln!("Log size limit reached ({}MB), stopping logging to {} for this run",
+                    self.config.max_size_mb, self.log_path.display());
+                return Ok(());
+            } else {
+                // Backup mode: rotate to numbered files
+                self.rotate()?;
+            }
+        }
+
+        if let Some(opened) = self.header_pending.take() {
+            let header = self.header(opened);
+            self.write_counted(header.as_bytes())?;
+        }
+        self.write_counted(lines)
+    }
+
+    /// Write to the current file, counting the bytes toward its size
+    fn write_counted(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        let result = self.current_file.write_all(bytes);
+        self.current_size = match &result {
+            Ok(()) => self.current_size + bytes.len() as u64,
+            // Part of the bytes may have been written
+            Err(_) => self.current_file.metadata().map_or(self.current_size, |m| m.len()),
+        };
+        result
+    }
+
+    /// First line of a file opened at `opened`: what produced it, in the file's format
+    fn header(&self, opened: DateTime<Local>) -> String {
+        let command = std::env::args().collect::<Vec<_>>().join(" ");
+        let filter = self.filter.as_ref().and_then(|filter| filter()).unwrap_or_default();
+        let pid = std::process::id();
+        let host = hostname();
+        let (name, version) = (env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        match self.format {
+            LogFormat::Pretty => format!(
+                "=== {} {} | started {} | pid {} | host {} | filter \"{}\" | command: {} ===\n",
+                name, version, opened.to_rfc3339_opts(chrono::SecondsFormat::Secs, false), pid, host, filter, command,
+            ),
+            // Shaped like a `JsonFormatter` line, so the file stays one kind of object
+            LogFormat::Json => format!("{}\n", serde_json::json!({
+                "timestamp": opened.with_timezone(&chrono::Utc).to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+                "level": "INFO",
+                "target": "autodebugger::log_header",
+                "message": "Log file started",
+                "fields": {
+                    "crate": name,
+                    "version": version,
+                    "pid": pid,
+                    "hostname": host,
+                    "filter": filter,
+                    "command": command,
+                },
+            })),
+        }
     }
 
     /// Update the "latest" symlink/copy to point to the current log file
     fn update_latest_symlink(&self) -> std::io::Result<()> {
-        // Generate the latest symlink name based on the base filename
-        let base_name = self.config.filename.trim_end_matches(".log");
-        let latest_filename = format!("{}_latest.log", base_name);
-        let latest_path = PathBuf::from(&self.config.log_directory).join(&latest_filename);
+        let latest_path = latest_link_path(&self.config);
+        let latest_filename = latest_path.file_name().unwrap_or_default().to_string_lossy().into_owned();
         
         // Remove existing symlink/file if it exists
         if latest_path.exists() {
             if let Err(e) = fs::remove_file(&latest_path) {
-                tracing::warn!("Failed to remove existing latest symlink: {}", e);
+                eprintln!("Failed to remove existing latest symlink: {}", e);
                 return Ok(()); // Continue without failing
             }
         }
@@ -277,14 +612,14 @@ impl RotatingWriter {
                 .unwrap_or(&self.config.filename);
             let target_path = format!("timestamped/{}", target_filename);
             if let Err(e) = symlink(&target_path, &latest_path) {
-                tracing::warn!("Failed to create symlink {}: {}", latest_filename, e);
+                eprintln!("Failed to create symlink {}: {}", latest_filename, e);
             }
         }
         
         #[cfg(windows)]
         {
             if let Err(e) = fs::copy(&self.log_path, &latest_path) {
-                tracing::warn!("Failed to create latest copy {}: {}", latest_filename, e);
+                eprintln!("Failed to create latest copy {}: {}", latest_filename, e);
             }
         }
         
@@ -292,30 +627,193 @@ impl RotatingWriter {
     }
 }
 
+/// Writes are buffered up to the last newline, so rotation and the size limit are
+/// checked at line (event) boundaries however the caller splits its writes
 impl Write for RotatingWriter {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        // Check if we're at size limit
-        if self.should_rotate() {
-            if self.config.truncate_on_limit {
-                // Truncate mode: stop logging when limit is reached
-                tracing::warn!("Log size limit reached ({}MB), stopping logging for this run", self.config.max_size_mb);
-                return Ok(buf.len()); // Pretend we wrote it to avoid errors
-            } else {
-                // Backup mode: rotate to numbered files
-                self.rotate()?;
-            }
-        }
-
-        let written = self.current_file.write(buf)?;
-        self.current_size += written as u64;
-        Ok(written)
+        let Some(last_newline) = buf.iter().rposition(|&b| b == b'\n') else {
+            self.pending.extend_from_slice(buf);
+            return Ok(buf.len());
+        };
+        let (complete, rest) = buf.split_at(last_newline + 1);
+        let result = if self.pending.is_empty() {
+            self.write_lines(complete)
+        } else {
+            let mut lines = std::mem::take(&mut self.pending);
+            lines.extend_from_slice(complete);
+            self.write_lines(&lines)
+        };
+        self.pending.extend_from_slice(rest);
+        result.map(|()| buf.len())
     }
 
+    /// Also writes out a trailing partial line
     fn flush(&mut self) -> std::io::Result<()> {
+        if !self.pending.is_empty() {
+            let partial = std::mem::take(&mut self.pending);
+            self.write_lines(&partial)?;
+        }
         self.current_file.flush()
     }
 }
 
+impl Drop for RotatingWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Timestamped files of `config`'s base name in its log directory, including
+/// numbered backups, compressed files, and earlier runs, newest first: later
+/// timestamps, then lower backup numbers. Empty if the directory is missing.
+pub fn log_files(config: &RotatingFileConfig) -> Vec<LogFile> {
+    let dir = PathBuf::from(&config.log_directory).join("timestamped");
+    let prefix = format!("{}_", config.filename.trim_end_matches(".log"));
+    let Ok(entries) = fs::read_dir(&dir) else { return Vec::new() };
+    let mut files: Vec<LogFile> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let (timestamp, backup) = parse_log_name(&name, &prefix)?;
+            let size = entry.metadata().map_or(0, |metadata| metadata.len());
+            Some(LogFile { timestamp, backup, path: entry.path(), size })
+        })
+        .collect();
+    files.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then(a.backup.cmp(&b.backup)));
+    files
+}
+
+/// `<log_directory>/<base>_latest.log`, a symlink to the live file (a copy on Windows)
+pub fn latest_link_path(config: &RotatingFileConfig) -> PathBuf {
+    let base_name = config.filename.trim_end_matches(".log");
+    PathBuf::from(&config.log_directory).join(format!("{}_latest.log", base_name))
+}
+
+/// The file the latest symlink points to, else the most recently modified of
+/// `log_files`, else None
+pub fn latest_log_file(config: &RotatingFileConfig) -> Option<PathBuf> {
+    let link = latest_link_path(config);
+    let linked = fs::read_link(&link).ok()
+        .map(|target| link.parent().unwrap_or(Path::new("")).join(target))
+        .filter(|target| target.is_file());
+    // Oldest first, so on equal times the later timestamp wins
+    linked.or_else(|| {
+        log_files(config).into_iter().rev()
+            .filter_map(|file| Some((fs::metadata(&file.path).ok()?.modified().ok()?, file.path)))
+            .max_by_key(|(modified, _)| *modified)
+            .map(|(_, path)| path)
+    })
+}
+
+/// Delete the files the writer's retention would, without a writer: beyond
+/// `max_files`, then beyond `max_total_size_mb`, oldest first. `active` (the
+/// file a running writer may still be appending to) is kept. Returns the names
+/// of the deleted files.
+pub fn prune_log_files(config: &RotatingFileConfig, active: Option<&Path>) -> Vec<String> {
+    let mut pruned = Vec::new();
+    prune_files(log_files(config), config.max_files, config.max_total_size_mb, |path| {
+        if Some(path) == active || fs::remove_file(path).is_err() {
+            return false;
+        }
+        pruned.extend(path.file_name().map(|name| name.to_string_lossy().into_owned()));
+        true
+    });
+    pruned
+}
+
+/// Retention over `files` (newest first): `remove` the oldest beyond `max_files`,
+/// then, with `max_total_size_mb`, oldest first until the rest fit the cap;
+/// whichever limit is stricter wins. Best effort: a file `remove` reports as
+/// kept still counts toward the cap.
+fn prune_files(
+    mut files: Vec<LogFile>,
+    max_files: usize,
+    max_total_size_mb: Option<u64>,
+    mut remove: impl FnMut(&Path) -> bool,
+) {
+    let excess = files.split_off(max_files.max(1).min(files.len()));
+    for file in excess {
+        remove(&file.path);
+    }
+
+    let Some(cap_mb) = max_total_size_mb else { return };
+    let cap = cap_mb * 1024 * 1024;
+    let mut total: u64 = files.iter().map(|file| file.size).sum();
+    for file in files.iter().rev() {
+        if total <= cap {
+            break;
+        }
+        if remove(&file.path) {
+            total -= file.size;
+        }
+    }
+}
+
+/// Host name from the environment or the kernel, else "unknown"
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| fs::read_to_string("/proc/sys/kernel/hostname").ok())
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// `timestamped/<base>_YYYYMMDD_HHMMSS.log` for a file opened at `now`
+fn timestamped_path(config: &RotatingFileConfig, now: DateTime<Local>) -> PathBuf {
+    let base_name = config.filename.trim_end_matches(".log");
+    PathBuf::from(&config.log_directory)
+        .join("timestamped")
+        .join(format!("{}_{}.log", base_name, now.format("%Y%m%d_%H%M%S")))
+}
+
+/// Day or hour containing `now` for time-based rotation, None for size-only rotation
+fn period_of(rotation: Rotation, now: DateTime<Local>) -> Option<String> {
+    match rotation {
+        Rotation::Size => None,
+        Rotation::Daily | Rotation::SizeOrDaily => Some(now.format("%Y%m%d").to_string()),
+        Rotation::Hourly => Some(now.format("%Y%m%d%H").to_string()),
+    }
+}
+
+/// Numbered backup `n` of `log_path`, e.g. `app_20250101_120000.log.2[.gz]`,
+/// appended to the full file name rather than replacing its extension
+fn backup_path(log_path: &Path, n: usize, compressed: bool) -> PathBuf {
+    let mut name = log_path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}{}", n, if compressed { ".gz" } else { "" }));
+    log_path.with_file_name(name)
+}
+
+/// Replace `path` with `<path>.gz` using the `gzip` tool
+fn gzip(path: &Path) -> Result<(), std::io::Error> {
+    let output = Command::new("gzip").arg("-f").arg("--").arg(path).output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()))
+    }
+}
+
+/// Timestamp and backup number (0 for the live file) of
+/// "<prefix>YYYYMMDD_HHMMSS.log[.N][.gz]"; compressed files count like the originals
+fn parse_log_name(name: &str, prefix: &str) -> Option<(String, u32)> {
+    let name = name.strip_suffix(".gz").unwrap_or(name);
+    let rest = name.strip_prefix(prefix)?;
+    let (timestamp, suffix) = rest.split_once(".log")?;
+    let is_timestamp = timestamp.len() == 15
+        && timestamp.char_indices().all(|(i, c)| if i == 8 { c == '_' } else { c.is_ascii_digit() });
+    if !is_timestamp {
+        return None;
+    }
+    let backup = match suffix {
+        "" => 0,
+        _ => suffix.strip_prefix('.')?.parse().ok()?,
+    };
+    Some((timestamp.to_string(), backup))
+}
+
 /// Wrapper to implement MakeWriter that integrates with tracing subscriber
 pub struct RotatingWriterWrapper(Arc<Mutex<RotatingWriter>>);
 
@@ -330,9 +828,57 @@ impl RotatingWriterWrapper {
         Ok(Self(writer))
     }
     
+    /// Format of the file header line (default: the config's `format`, else pretty);
+    /// set this to the format the file is actually written in
+    pub fn with_format(self, format: LogFormat) -> Self {
+        self.0.lock().unwrap().format = format;
+        self
+    }
+
+    /// Where the file header reads the current log filter directives from
+    pub fn with_filter<F>(self, filter: F) -> Self
+    where
+        F: Fn() -> Option<String> + Send + 'static,
+    {
+        self.0.lock().unwrap().filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Whether `truncate_on_limit` has stopped logging to the current file
+    pub fn size_limit_reached(&self) -> bool {
+        self.0.lock().unwrap().size_limit_reached
+    }
+
+    /// Number and total size of the logger's files, against `max_total_size_mb`
+    pub fn directory_usage(&self) -> DirectoryUsage {
+        self.0.lock().unwrap().directory_usage()
+    }
+
+    /// Files deleted for retention since the last call, e.g. at startup; later
+    /// rotations log theirs as they happen
+    pub fn take_pruned(&self) -> Vec<String> {
+        std::mem::take(&mut self.0.lock().unwrap().pruned)
+    }
+
     /// Convert to a guard that keeps the writer alive
     pub fn into_guard(self) -> RotatingFileGuard {
-        RotatingFileGuard { _writer: self.0 }
+        RotatingFileGuard { writer: self.0, worker: None }
+    }
+
+    /// Move writing onto a dedicated thread, per the config's `buffered_lines` and
+    /// `when_full`. Log through the returned writer and keep the guard alive until
+    /// shutdown: dropping it writes out what is still queued and joins the thread.
+    pub fn non_blocking(self) -> Result<(NonBlocking, RotatingFileGuard), std::io::Error> {
+        let (capacity, when_full) = {
+            let writer = self.0.lock().unwrap();
+            (writer.config.buffered_lines, writer.config.when_full)
+        };
+        let file: RotatingWriterGuard<'static> = RotatingWriterGuard {
+            writer: self.0.clone(),
+            _phantom: std::marker::PhantomData,
+        };
+        let (non_blocking, worker) = crate::non_blocking::non_blocking(file, capacity, when_full)?;
+        Ok((non_blocking, RotatingFileGuard { writer: self.0, worker: Some(worker) }))
     }
 }
 
@@ -359,12 +905,509 @@ pub struct RotatingWriterGuard<'a> {
     _phantom: std::marker::PhantomData<&'a ()>,
 }
 
+thread_local! {
+    /// This thread is inside a `RotatingWriterGuard` write and holds the writer
+    static WRITING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Clears `WRITING` once the writer is released, including by unwinding
+struct WritingFlag;
+
+impl Drop for WritingFlag {
+    fn drop(&mut self) {
+        WRITING.with(|writing| writing.set(false));
+    }
+}
+
+impl RotatingWriterGuard<'_> {
+    /// Run `f` on the writer, or return None if this thread already holds it: a
+    /// panic raised mid-write reaches the panic hook before unwinding, and logging
+    /// it would deadlock on the writer. A writer poisoned by such a panic is still used.
+    fn locked<T>(&self, f: impl FnOnce(&mut RotatingWriter) -> T) -> Option<T> {
+        if WRITING.with(|writing| writing.replace(true)) {
+            return None;
+        }
+        let _flag = WritingFlag;
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        Some(f(&mut writer))
+    }
+}
+
 impl<'a> Write for RotatingWriterGuard<'a> {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.writer.lock().unwrap().write(buf)
+        let written = self.locked(|writer| (writer.write(buf), std::mem::take(&mut writer.pruned)));
+        let Some((result, pruned)) = written else {
+            return Ok(buf.len());
+        };
+        // Logged after unlocking, as this event is written through the same writer.
+        // Under a scoped (non-global) dispatcher the nested event is dropped.
+        if !pruned.is_empty() {
+            tracing::debug!("Pruned {} old log file(s): {}", pruned.len(), pruned.join(", "));
+        }
+        result
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
-        self.writer.lock().unwrap().flush()
+        self.locked(|writer| writer.flush()).unwrap_or(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    /// Clock reading a time the test can move forward
+    fn mock_clock(start: DateTime<Local>) -> (Clock, Arc<Mutex<DateTime<Local>>>) {
+        let now = Arc::new(Mutex::new(start));
+        let reader = now.clone();
+        (Arc::new(move || *reader.lock().unwrap()), now)
+    }
+
+    fn config(dir: &Path, rotation: Rotation, max_files: usize) -> RotatingFileConfig {
+        RotatingFileConfig {
+            log_directory: dir.to_string_lossy().to_string(),
+            max_files,
+            truncate_on_limit: false,
+            rotation,
+            // Covered by its own test; the others compare exact contents
+            write_header: false,
+            ..RotatingFileConfig::default()
+        }
+    }
+
+    fn log_files(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir.join("timestamped")).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_daily_rotation_at_midnight() {
+        let dir = tempfile::tempdir().unwrap();
+        let (clock, now) = mock_clock(Local.with_ymd_and_hms(2026, 1, 1, 23, 59, 0).unwrap());
+        let mut writer = RotatingWriter::with_clock(config(dir.path(), Rotation::Daily, 10), clock).unwrap();
+
+        writer.write_all(b"before midnight\n").unwrap();
+        *now.lock().unwrap() += Duration::minutes(30);
+        writer.write_all(b"after midnight\n").unwrap();
+        *now.lock().unwrap() += Duration::hours(12);
+        writer.write_all(b"same day\n").unwrap();
+        writer.flush().unwrap();
+
+        assert_eq!(log_files(dir.path()), vec!["app_20260101_235900.log", "app_20260102_002900.log"]);
+        let first = fs::read_to_string(dir.path().join("timestamped/app_20260101_235900.log")).unwrap();
+        assert_eq!(first, "before midnight\n");
+        let latest = fs::read_to_string(dir.path().join("app_latest.log")).unwrap();
+        assert_eq!(latest, "after midnight\nsame day\n");
+    }
+
+    #[test]
+    fn test_hourly_rotation_keeps_max_files() {
+        let dir = tempfile::tempdir().unwrap();
+        // A file from an earlier run and another logger's file in the same directory
+        fs::create_dir_all(dir.path().join("timestamped")).unwrap();
+        fs::write(dir.path().join("timestamped/app_20251231_080000.log"), "old run\n").unwrap();
+        fs::write(dir.path().join("timestamped/other_20251231_080000.log"), "other\n").unwrap();
+
+        let (clock, now) = mock_clock(Local.with_ymd_and_hms(2026, 1, 1, 9, 15, 0).unwrap());
+        let mut writer = RotatingWriter::with_clock(config(dir.path(), Rotation::Hourly, 3), clock).unwrap();
+        for hour in 0..5 {
+            writer.write_all(format!("hour {}\n", hour).as_bytes()).unwrap();
+            *now.lock().unwrap() += Duration::hours(1);
+        }
+        writer.flush().unwrap();
+
+        assert_eq!(log_files(dir.path()), vec![
+            "app_20260101_111500.log",
+            "app_20260101_121500.log",
+            "app_20260101_131500.log",
+            "other_20251231_080000.log",
+        ]);
+        let latest = fs::read_to_string(dir.path().join("app_latest.log")).unwrap();
+        assert_eq!(latest, "hour 4\n");
+    }
+
+    #[test]
+    fn test_size_or_daily_retention_counts_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = config(dir.path(), Rotation::SizeOrDaily, 2);
+        config.max_size_mb = 1;
+        let (clock, now) = mock_clock(Local.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap());
+        let mut writer = RotatingWriter::with_clock(config, clock).unwrap();
+
+        // At the size limit: the next line moves the file to .log.1
+        let mut full = vec![b'x'; 1024 * 1024 - 1];
+        full.push(b'\n');
+        writer.write_all(&full).unwrap();
+        writer.write_all(b"second file\n").unwrap();
+        assert_eq!(log_files(dir.path()), vec!["app_20260101_120000.log", "app_20260101_120000.log.1"]);
+
+        // The new day's file pushes out the oldest, the size backup
+        *now.lock().unwrap() += Duration::days(1);
+        writer.write_all(b"next day\n").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(log_files(dir.path()), vec!["app_20260101_120000.log", "app_20260102_120000.log"]);
+        let latest = fs::read_to_string(dir.path().join("app_latest.log")).unwrap();
+        assert_eq!(latest, "next day\n");
+    }
+
+    #[test]
+    fn test_parse_log_name() {
+        assert_eq!(parse_log_name("app_20260101_120000.log", "app_"), Some(("20260101_120000".to_string(), 0)));
+        assert_eq!(parse_log_name("app_20260101_120000.log.3", "app_"), Some(("20260101_120000".to_string(), 3)));
+        assert_eq!(parse_log_name("app_20260101_120000.log.2.gz", "app_"), Some(("20260101_120000".to_string(), 2)));
+        assert_eq!(parse_log_name("app_latest.log", "app_"), None);
+        assert_eq!(parse_log_name("app_extra_20260101_120000.log", "app_"), None);
+    }
+
+    #[test]
+    fn test_startup_prunes_old_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("timestamped")).unwrap();
+        for day in 1..=15 {
+            let name = format!("timestamped/app_202512{:02}_080000.log", day);
+            fs::write(dir.path().join(name), "old run\n").unwrap();
+        }
+
+        let (clock, _) = mock_clock(Local.with_ymd_and_hms(2026, 1, 1, 9, 0, 0).unwrap());
+        let writer = RotatingWriter::with_clock(config(dir.path(), Rotation::Size, 10), clock).unwrap();
+
+        let mut expected: Vec<String> = (7..=15).map(|day| format!("app_202512{:02}_080000.log", day)).collect();
+        expected.push("app_20260101_090000.log".to_string());
+        assert_eq!(log_files(dir.path()), expected);
+        assert_eq!(writer.pruned.len(), 6);
+        assert_eq!(writer.pruned.iter().min().unwrap(), "app_20251201_080000.log");
+
+        // The wrapper hands the startup list over once
+        let wrapper = RotatingWriterWrapper(Arc::new(Mutex::new(writer)));
+        assert_eq!(wrapper.take_pruned().len(), 6);
+        assert!(wrapper.take_pruned().is_empty());
+    }
+
+    /// Contents of a gzip file, via `gzip -dc`
+    #[test]
+    fn test_prune_without_writer_keeps_latest() {
+        let dir = tempfile::tempdir().unwrap();
+        let (clock, now) = mock_clock(Local.with_ymd_and_hms(2026, 1, 1, 9, 0, 0).unwrap());
+        let writer = RotatingWriter::with_clock(config(dir.path(), Rotation::Size, 10), clock.clone()).unwrap();
+        drop(writer);
+        *now.lock().unwrap() = Local.with_ymd_and_hms(2026, 1, 2, 9, 0, 0).unwrap();
+        let writer = RotatingWriter::with_clock(config(dir.path(), Rotation::Size, 10), clock).unwrap();
+        fs::write(dir.path().join("timestamped/app_20250101_090000.log"), "oldest\n").unwrap();
+
+        let config = config(dir.path(), Rotation::Size, 10);
+        let latest = latest_log_file(&config).unwrap();
+        assert_eq!(latest, writer.log_path);
+        assert_eq!(super::log_files(&config).len(), 3);
+
+        // With max_files 0 everything but the latest goes
+        let pruned = prune_log_files(&RotatingFileConfig { max_files: 0, ..config.clone() }, Some(&latest));
+        assert_eq!(pruned, vec!["app_20260101_090000.log", "app_20250101_090000.log"]);
+        assert_eq!(log_files(dir.path()), vec!["app_20260102_090000.log"]);
+    }
+
+    fn gunzip(path: &Path) -> String {
+        let output = Command::new("gzip").arg("-dc").arg(path).output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_compress_rotated_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = config(dir.path(), Rotation::SizeOrDaily, 10);
+        config.max_size_mb = 1;
+        config.compress_rotated = true;
+        let (clock, now) = mock_clock(Local.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap());
+        let mut writer = RotatingWriter::with_clock(config, clock).unwrap();
+
+        // Size rotation: the full file becomes .log.1.gz
+        let full = format!("{}\n", "x".repeat(1024 * 1024 - 1));
+        writer.write_all(full.as_bytes()).unwrap();
+        writer.write_all(b"after size rotation\n").unwrap();
+        writer.wait_for_compression();
+        assert_eq!(log_files(dir.path()), vec!["app_20260101_120000.log", "app_20260101_120000.log.1.gz"]);
+        assert_eq!(gunzip(&dir.path().join("timestamped/app_20260101_120000.log.1.gz")), full);
+
+        // Day rotation: the whole timestamped file becomes .log.gz
+        *now.lock().unwrap() += Duration::days(1);
+        writer.write_all(b"next day\n").unwrap();
+        writer.wait_for_compression();
+        writer.flush().unwrap();
+        assert_eq!(log_files(dir.path()), vec![
+            "app_20260101_120000.log.1.gz",
+            "app_20260101_120000.log.gz",
+            "app_20260102_120000.log",
+        ]);
+        assert_eq!(gunzip(&dir.path().join("timestamped/app_20260101_120000.log.gz")), "after size rotation\n");
+        let latest = fs::read_to_string(dir.path().join("app_latest.log")).unwrap();
+        assert_eq!(latest, "next day\n");
+    }
+
+    #[test]
+    fn test_size_rotation_shifts_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = config(dir.path(), Rotation::Size, 3);
+        config.max_size_mb = 1;
+        let (clock, _) = mock_clock(Local.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap());
+        let mut writer = RotatingWriter::with_clock(config, clock).unwrap();
+
+        // Each 1MB line fills the file, so every later line rotates first
+        for chunk in [b'a', b'b', b'c', b'd', b'e'] {
+            let mut line = vec![chunk; 1024 * 1024 - 1];
+            line.push(b'\n');
+            writer.write_all(&line).unwrap();
+        }
+        writer.flush().unwrap();
+
+        assert_eq!(log_files(dir.path()), vec![
+            "app_20250101_120000.log",
+            "app_20250101_120000.log.1",
+            "app_20250101_120000.log.2",
+        ]);
+        let first_byte = |name: &str| fs::read(dir.path().join("timestamped").join(name)).unwrap()[0];
+        assert_eq!(first_byte("app_20250101_120000.log"), b'e');
+        assert_eq!(first_byte("app_20250101_120000.log.1"), b'd');
+        assert_eq!(first_byte("app_20250101_120000.log.2"), b'c');
+        assert_eq!(fs::read(dir.path().join("app_latest.log")).unwrap()[0], b'e');
+    }
+
+    #[test]
+    fn test_backup_path_appends_suffix() {
+        let log_path = Path::new("logs/timestamped/my.app_20250101_120000.log");
+        assert_eq!(backup_path(log_path, 1, false), Path::new("logs/timestamped/my.app_20250101_120000.log.1"));
+        assert_eq!(backup_path(log_path, 12, true), Path::new("logs/timestamped/my.app_20250101_120000.log.12.gz"));
+    }
+
+    #[test]
+    fn test_non_blocking_writes_reach_file_on_guard_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = config(dir.path(), Rotation::Size, 10);
+        config.non_blocking = true;
+        let (mut writer, guard) = RotatingWriterWrapper::new(config).unwrap().non_blocking().unwrap();
+
+        for i in 0..100 {
+            writer.write_all(format!("line {}\n", i).as_bytes()).unwrap();
+        }
+        drop(guard);
+
+        let logged = fs::read_to_string(dir.path().join("app_latest.log")).unwrap();
+        assert_eq!(logged.lines().count(), 100);
+        assert_eq!(logged.lines().last(), Some("line 99"));
+        assert_eq!(writer.dropped_lines(), 0);
+    }
+
+    /// Lines of `len` bytes (newline included) numbered from `start`, each split
+    /// over three writes the way a caller might straddle the limit
+    fn write_split_lines(writer: &mut RotatingWriter, start: usize, count: usize, len: usize) {
+        for i in start..start + count {
+            let mut line = format!("{:06}:", i).into_bytes();
+            line.resize(len - 1, b'x');
+            line.push(b'\n');
+            let (head, tail) = line.split_at(len / 3);
+            let (middle, tail) = tail.split_at(len / 3);
+            for part in [head, middle, tail] {
+                writer.write_all(part).unwrap();
+            }
+        }
+    }
+
+    /// Every line of `content` is a whole line of `len` bytes
+    fn assert_whole_lines(content: &[u8], len: usize) {
+        assert_eq!(content.last(), Some(&b'\n'));
+        for line in content.split_inclusive(|&b| b == b'\n') {
+            assert_eq!(line.len(), len, "torn line {:?}", String::from_utf8_lossy(&line[..7.min(line.len())]));
+        }
+    }
+
+    #[test]
+    fn test_truncate_on_limit_stops_at_line_boundary() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = config(dir.path(), Rotation::Size, 10);
+        config.max_size_mb = 1;
+        config.truncate_on_limit = true;
+        let (clock, _) = mock_clock(Local.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap());
+        let mut writer = RotatingWriter::with_clock(config, clock).unwrap();
+
+        // 1MB is not a multiple of 1000, so one line straddles the limit
+        write_split_lines(&mut writer, 0, 1100, 1000);
+        writer.flush().unwrap();
+        assert!(writer.size_limit_reached);
+
+        let content = fs::read(dir.path().join("app_latest.log")).unwrap();
+        assert_eq!(content.len(), 1048 * 1000);
+        assert_eq!(writer.current_size, content.len() as u64);
+        assert_whole_lines(&content, 1000);
+        // Shorter lines after the limit are dropped too
+        writer.write_all(b"short\n").unwrap();
+        assert_eq!(fs::read(dir.path().join("app_latest.log")).unwrap().len(), content.len());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_backup_rotation_keeps_lines_whole() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = config(dir.path(), Rotation::Size, 3);
+        config.max_size_mb = 1;
+        let (clock, _) = mock_clock(Local.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap());
+        let mut writer = RotatingWriter::with_clock(config, clock).unwrap();
+
+        write_split_lines(&mut writer, 0, 1500, 1000);
+        writer.flush().unwrap();
+
+        let timestamped = dir.path().join("timestamped");
+        let backup = fs::read(timestamped.join("app_20250101_120000.log.1")).unwrap();
+        let live = fs::read(timestamped.join("app_20250101_120000.log")).unwrap();
+        assert_eq!(backup.len(), 1048 * 1000);
+        assert_whole_lines(&backup, 1000);
+        assert_whole_lines(&live, 1000);
+        // The first line after the limit starts the new file
+        assert!(live.starts_with(b"001048:"));
+        assert_eq!(writer.current_size, live.len() as u64);
+    }
+
+    #[test]
+    fn test_partial_line_written_on_flush() {
+        let dir = tempfile::tempdir().unwrap();
+        let (clock, _) = mock_clock(Local.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap());
+        let mut writer = RotatingWriter::with_clock(config(dir.path(), Rotation::Size, 10), clock).unwrap();
+
+        writer.write_all(b"first\nsecond, unfinished").unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("app_latest.log")).unwrap(), "first\n");
+        assert_eq!(writer.current_size, 6);
+
+        writer.flush().unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("app_latest.log")).unwrap(), "first\nsecond, unfinished");
+        assert_eq!(writer.current_size, 24);
+    }
+
+    #[test]
+    fn test_header_starts_each_new_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = config(dir.path(), Rotation::SizeOrDaily, 10);
+        config.write_header = true;
+        config.max_size_mb = 1;
+        let (clock, now) = mock_clock(Local.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap());
+        let wrapper = RotatingWriterWrapper(Arc::new(Mutex::new(RotatingWriter::with_clock(config, clock).unwrap())))
+            .with_filter(|| Some("info,autodebugger=debug".to_string()));
+        let mut writer = wrapper.make_writer();
+
+        writer.write_all(b"first\n").unwrap();
+        let timestamped = dir.path().join("timestamped");
+        let first = fs::read_to_string(timestamped.join("app_20260101_120000.log")).unwrap();
+        let (header, rest) = first.split_once('\n').unwrap();
+        assert!(header.starts_with(&format!("=== autodebugger {} | started 2026-01-01T12:00:00", env!("CARGO_PKG_VERSION"))), "{}", header);
+        assert!(header.contains(&format!("| pid {} |", std::process::id())));
+        assert!(header.contains("| filter \"info,autodebugger=debug\" |"));
+        assert_eq!(rest, "first\n");
+        // The header counts toward the size limit
+        assert_eq!(wrapper.0.lock().unwrap().current_size, first.len() as u64);
+
+        // Size rotation: the live file starts over with a new header
+        let line = format!("{}\n", "x".repeat(1024 * 1024 - first.len() - 1));
+        writer.write_all(line.as_bytes()).unwrap();
+        writer.write_all(b"after size rotation\n").unwrap();
+        let backup = fs::read_to_string(timestamped.join("app_20260101_120000.log.1")).unwrap();
+        assert_eq!(backup.len(), 1024 * 1024);
+        let live = fs::read_to_string(timestamped.join("app_20260101_120000.log")).unwrap();
+        assert!(live.starts_with("=== autodebugger "));
+        assert!(live.ends_with("===\nafter size rotation\n"));
+
+        // Day rotation in JSON: a header object shaped like a log line
+        let _ = wrapper.clone().with_format(LogFormat::Json);
+        *now.lock().unwrap() += Duration::days(1);
+        writer.write_all(b"next day\n").unwrap();
+        let next_day = fs::read_to_string(timestamped.join("app_20260102_120000.log")).unwrap();
+        let (header, rest) = next_day.split_once('\n').unwrap();
+        let header: serde_json::Value = serde_json::from_str(header).unwrap();
+        assert_eq!(header["target"], "autodebugger::log_header");
+        assert_eq!(header["fields"]["crate"], "autodebugger");
+        assert_eq!(header["fields"]["filter"], "info,autodebugger=debug");
+        assert!(header["fields"]["hostname"].as_str().is_some_and(|host| !host.is_empty()));
+        assert_eq!(rest, "next day\n");
+    }
+
+    #[test]
+    fn test_total_size_cap_prunes_oldest() {
+        let dir = tempfile::tempdir().unwrap();
+        let timestamped = dir.path().join("timestamped");
+        fs::create_dir_all(&timestamped).unwrap();
+        // 400KB each; the compressed backup counts like any other file
+        for name in ["app_20251201_080000.log", "app_20251202_080000.log", "app_20251203_080000.log.1.gz", "app_20251203_080000.log"] {
+            fs::write(timestamped.join(name), vec![b'x'; 400 * 1024]).unwrap();
+        }
+
+        let mut config = config(dir.path(), Rotation::Size, 10);
+        config.max_total_size_mb = Some(1);
+        config.max_size_mb = 1;
+        let (clock, _) = mock_clock(Local.with_ymd_and_hms(2026, 1, 1, 9, 0, 0).unwrap());
+        let mut writer = RotatingWriter::with_clock(config, clock).unwrap();
+
+        // 1.6MB over a 1MB cap: the two oldest go, whatever max_files allows
+        assert_eq!(log_files(dir.path()), vec![
+            "app_20251203_080000.log",
+            "app_20251203_080000.log.1.gz",
+            "app_20260101_090000.log",
+        ]);
+        assert_eq!(writer.pruned, vec!["app_20251201_080000.log", "app_20251202_080000.log"]);
+        assert_eq!(writer.directory_usage(), DirectoryUsage { files: 3, bytes: 800 * 1024, max_bytes: Some(1024 * 1024) });
+
+        // After a rotation the active file's backup counts too; the active file is never deleted
+        let line = format!("{}\n", "y".repeat(700 * 1024 - 1));
+        writer.write_all(line.as_bytes()).unwrap();
+        writer.write_all(line.as_bytes()).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(log_files(dir.path()), vec!["app_20260101_090000.log", "app_20260101_090000.log.1"]);
+        // The growing active file can take usage past the cap until the next rotation
+        assert_eq!(writer.directory_usage().bytes, 2 * 700 * 1024);
+    }
+
+    #[test]
+    fn test_stricter_of_max_files_and_total_size_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        let timestamped = dir.path().join("timestamped");
+        fs::create_dir_all(&timestamped).unwrap();
+        for day in 1..=4 {
+            fs::write(timestamped.join(format!("app_2025120{}_080000.log", day)), "small\n").unwrap();
+        }
+
+        let mut config = config(dir.path(), Rotation::Size, 3);
+        config.max_total_size_mb = Some(100);
+        let (clock, _) = mock_clock(Local.with_ymd_and_hms(2026, 1, 1, 9, 0, 0).unwrap());
+        let writer = RotatingWriter::with_clock(config, clock).unwrap();
+
+        assert_eq!(log_files(dir.path()), vec![
+            "app_20251203_080000.log",
+            "app_20251204_080000.log",
+            "app_20260101_090000.log",
+        ]);
+        assert_eq!(writer.directory_usage().files, 3);
+    }
+
+    #[test]
+    fn test_panic_while_holding_writer_does_not_deadlock() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let wrapper = RotatingWriterWrapper::new(config(dir.path(), Rotation::Size, 10)).unwrap();
+        crate::tracing_subscriber::install_panic_hook();
+
+        // The hook's ERROR event goes to the writer this thread holds: it is skipped
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_subscriber::fmt::layer().with_writer(wrapper.clone()).with_ansi(false));
+        let dispatch = tracing::Dispatch::new(subscriber);
+        let doomed = wrapper.clone();
+        let result = std::thread::spawn(move || {
+            tracing::dispatcher::with_default(&dispatch, || {
+                doomed.make_writer().locked(|_| panic!("panic inside the writer"))
+            })
+        }).join();
+        assert!(result.is_err());
+
+        // The poisoned writer keeps working, on this thread and the panicked one's successors
+        wrapper.make_writer().write_all(b"after\n").unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("app_latest.log")).unwrap(), "after\n");
+    }
+}
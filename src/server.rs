@@ -0,0 +1,158 @@
+//! Read-only HTTP JSON endpoints for dashboards (`autodebugger serve`)
+//!
+//! Built with the `server` cargo feature. [`router`] answers a few `GET`
+//! routes about one workspace with the same serde types the CLI prints with
+//! `--json`:
+//!
+//! | Route | Body |
+//! |-------|------|
+//! | `/healthz` | `{"status": "ok", "version"}` |
+//! | `/status` | `MonitorStatus`, reusing the status cache when `monitor.status_cache` is on |
+//! | `/diff/{worktree}` | `WorktreeDiff` of one worktree |
+//! | `/ci/{worktree}` | `CIReport` against the default branch, from cached check results |
+//!
+//! `/ci` never runs the checks, which take minutes and build in the worktree:
+//! it scores the results `autodebugger ci` cached for the worktree's current
+//! state, and answers 404 with a hint to run it when there are none. The only
+//! file written is the status cache, when `monitor.status_cache` is on. Each
+//! request reads the worktrees afresh on a blocking thread, so a slow `git`
+//! does not stall the other connections. Failures are
+//! `{"error": {kind, message, hint}}` as with `--error-format json`: 404 for an
+//! unknown worktree or route or missing CI results, 500 otherwise.
+//! [`serve`] runs until its shutdown future completes; the CLI passes Ctrl-C.
+
+use anyhow::Result;
+use axum::extract::{Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::ci::cache::default_ci_cache_path;
+use crate::ci::CI;
+use crate::error::{CliError, ErrorKind};
+use crate::monitor::cache::default_cache_path;
+use crate::monitor::Monitor;
+use crate::Config;
+
+/// What every request needs: the workspace and the config the server started with
+struct ServerState {
+    workspace: PathBuf,
+    config: Config,
+}
+
+/// Routes for `workspace`, configured by `config`
+pub fn router(workspace: PathBuf, config: Config) -> Router {
+    let state = Arc::new(ServerState { workspace, config });
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/status", get(status))
+        .route("/diff/{worktree}", get(diff))
+        .route("/ci/{worktree}", get(ci))
+        .fallback(not_found)
+        .with_state(state)
+}
+
+/// Serve `router` on `listener` until `shutdown` completes, finishing the
+/// requests in flight
+pub async fn serve(
+    listener: tokio::net::TcpListener,
+    router: Router,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    axum::serve(listener, router).with_graceful_shutdown(shutdown).await?;
+    Ok(())
+}
+
+async fn healthz() -> Json<Value> {
+    Json(json!({ "status": "ok", "version": env!("CARGO_PKG_VERSION") }))
+}
+
+async fn status(State(state): State<Arc<ServerState>>) -> Result<Json<Value>, ApiError> {
+    blocking(move || {
+        let mut monitor = Monitor::with_config(state.workspace.clone(), state.config.monitor.clone())?;
+        if state.config.monitor.status_cache {
+            monitor = monitor.with_status_cache(default_cache_path(&state.workspace));
+        }
+        Ok(serde_json::to_value(monitor.status()?)?)
+    }).await
+}
+
+async fn diff(State(state): State<Arc<ServerState>>, UrlPath(worktree): UrlPath<String>) -> Result<Json<Value>, ApiError> {
+    blocking(move || {
+        let monitor = Monitor::with_config(state.workspace.clone(), state.config.monitor.clone())?;
+        let diffs = monitor.structured_diff(Some(&worktree))?;
+        Ok(serde_json::to_value(&diffs[0])?)
+    }).await
+}
+
+async fn ci(State(state): State<Arc<ServerState>>, UrlPath(worktree): UrlPath<String>) -> Result<Json<Value>, ApiError> {
+    blocking(move || {
+        let ci = CI::with_config(state.workspace.clone(), state.config.ci.clone())
+            .with_monitor_config(state.config.monitor.clone())
+            .with_cache(default_ci_cache_path(&state.workspace));
+        // Only the workspace's worktrees: a name is never read as a directory
+        let found = ci.worktree_monitor()?.find_worktree(&worktree)?;
+        match ci.cached_report(&found.path.to_string_lossy(), None)? {
+            Some(report) => Ok(serde_json::to_value(report)?),
+            None => Err(CliError::from(crate::error::not_found(format_args!("No cached CI results for {}", found.name)))
+                .with_hint(format!("Run `autodebugger ci {}` first; results stay valid until the worktree changes", found.name))),
+        }
+    }).await
+}
+
+async fn not_found() -> ApiError {
+    ApiError(CliError::msg(ErrorKind::Usage, "No such endpoint")
+        .with_hint("GET /healthz, /status, /diff/{worktree}, or /ci/{worktree}"))
+}
+
+/// Run `work` on the blocking pool, as the monitor and CI shell out to git
+async fn blocking(work: impl FnOnce() -> Result<Value, CliError> + Send + 'static) -> Result<Json<Value>, ApiError> {
+    match tokio::task::spawn_blocking(work).await {
+        Ok(result) => result.map(Json).map_err(ApiError),
+        Err(error) => Err(ApiError(CliError::new(ErrorKind::Internal, error))),
+    }
+}
+
+/// A failed request, answered with the `--error-format json` envelope
+struct ApiError(CliError);
+
+impl ApiError {
+    fn status(&self) -> StatusCode {
+        let missing = self.0.error().chain().any(|cause| {
+            cause.downcast_ref::<std::io::Error>().is_some_and(|error| error.kind() == std::io::ErrorKind::NotFound)
+        });
+        if missing || self.0.kind() == ErrorKind::Usage {
+            StatusCode::NOT_FOUND
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status(), Json(self.0.to_json())).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_things_are_not_found() {
+        let missing = ApiError(crate::error::not_found("Worktree not found: nope").into());
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+        assert_eq!(missing.0.to_json()["error"]["message"], "Worktree not found: nope");
+
+        let broken = ApiError(anyhow::anyhow!("git exited with 128").into());
+        assert_eq!(broken.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let denied = ApiError(std::io::Error::from(std::io::ErrorKind::PermissionDenied).into());
+        assert_eq!(denied.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,365 @@
+//! Per-span timing layer that reports slow operations
+//!
+//! `SpanTimingLayer` measures how long each span is busy, from entering it to
+//! exiting it, summed over every time it is entered, and records one sample per
+//! span when it closes. Samples are aggregated by span name into a count, total,
+//! maximum, and an approximate 95th percentile, available from `report()` or as
+//! text from `format_report()`, e.g. at shutdown next to the verbosity report.
+//!
+//! Nested spans are recorded under their own names: an outer span's time
+//! includes its children's, but each span is one sample however often it is
+//! entered, and entering a span that is already entered is not counted twice.
+//!
+//! With `with_warn_threshold` a span busy for longer than the threshold logs one
+//! WARN (target `autodebugger::span_timing`) when it closes. Like the verbosity
+//! layer's live warning, it goes to the layers below this one, so the init
+//! functions add this layer last.
+//!
+//! ## Configuration
+//!
+//! ```yaml
+//! logging:
+//!   span_timing:
+//!     enabled: true
+//!     warn_threshold_ms: 500
+//! ```
+//!
+//! Spans disabled by the log filter are never timed.
+
+use crate::config::SpanTimingConfig;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tracing::callsite::{DefaultCallsite, Identifier};
+use tracing::field::FieldSet;
+use tracing::metadata::Kind;
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Power-of-two microsecond buckets for the percentile: bucket `i` holds
+/// durations below 2^i µs, the last one everything longer (about 6 days)
+const BUCKETS: usize = 40;
+
+/// A tracing Layer that times spans by name
+#[derive(Clone, Default)]
+pub struct SpanTimingLayer {
+    stats: Arc<Mutex<HashMap<&'static str, Stats>>>,
+    warn_threshold: Option<Duration>,
+}
+
+/// Aggregated timings of one span name
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanTiming {
+    pub name: String,
+    /// Spans of this name closed so far
+    pub count: u64,
+    pub total: Duration,
+    pub max: Duration,
+    /// Upper bound of the power-of-two bucket holding the 95th percentile, at most `max`
+    pub p95: Duration,
+}
+
+/// Running aggregate for one span name
+#[derive(Debug, Clone)]
+struct Stats {
+    count: u64,
+    total: Duration,
+    max: Duration,
+    buckets: [u64; BUCKETS],
+}
+
+/// Busy time of an open span, kept in its extensions
+struct Busy {
+    total: Duration,
+    /// When the outermost current entry started
+    entered: Option<Instant>,
+    /// Current entries, as a span can be entered again while entered
+    depth: u32,
+    /// Entered at least once
+    used: bool,
+}
+
+impl SpanTimingLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Layer for `logging.span_timing`
+    pub fn with_config(config: &SpanTimingConfig) -> Self {
+        let layer = Self::new();
+        match config.warn_threshold_ms {
+            Some(ms) => layer.with_warn_threshold(Duration::from_millis(ms)),
+            None => layer,
+        }
+    }
+
+    /// Log a WARN when a single span is busy for longer than `threshold`
+    pub fn with_warn_threshold(mut self, threshold: Duration) -> Self {
+        self.warn_threshold = Some(threshold);
+        self
+    }
+
+    /// Timings per span name, longest total first
+    pub fn report(&self) -> Vec<SpanTiming> {
+        let stats = self.stats.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut timings: Vec<SpanTiming> = stats.iter()
+            .map(|(name, stats)| SpanTiming {
+                name: name.to_string(),
+                count: stats.count,
+                total: stats.total,
+                max: stats.max,
+                p95: stats.p95(),
+            })
+            .collect();
+        timings.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+        timings
+    }
+
+    /// Printable report of `report()`, or None if no span has closed
+    pub fn format_report(&self) -> Option<String> {
+        let timings = self.report();
+        if timings.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "\nSPAN TIMINGS\n\
+            ============\n{}",
+            timings.iter().map(|timing| format!("  {}\n", timing)).collect::<String>(),
+        ))
+    }
+
+    fn record(&self, name: &'static str, busy: Duration) {
+        let mut stats = self.stats.lock().unwrap_or_else(PoisonError::into_inner);
+        stats.entry(name).or_insert_with(Stats::new).record(busy);
+    }
+}
+
+impl Stats {
+    fn new() -> Self {
+        Self { count: 0, total: Duration::ZERO, max: Duration::ZERO, buckets: [0; BUCKETS] }
+    }
+
+    fn record(&mut self, busy: Duration) {
+        self.count += 1;
+        self.total += busy;
+        self.max = self.max.max(busy);
+        self.buckets[bucket_of(busy)] += 1;
+    }
+
+    fn p95(&self) -> Duration {
+        let needed = self.count.saturating_mul(95).div_ceil(100);
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= needed && seen > 0 {
+                return Duration::from_micros(1 << bucket).min(self.max);
+            }
+        }
+        self.max
+    }
+}
+
+/// Bucket whose upper bound, 2^i µs, is the first above `busy`
+fn bucket_of(busy: Duration) -> usize {
+    let micros = u64::try_from(busy.as_micros()).unwrap_or(u64::MAX);
+    ((u64::BITS - micros.leading_zeros()) as usize).min(BUCKETS - 1)
+}
+
+/// "850µs", "12.3ms", or "1.25s"
+fn format_duration(duration: Duration) -> String {
+    if duration < Duration::from_millis(1) {
+        format!("{}µs", duration.as_micros())
+    } else if duration < Duration::from_secs(1) {
+        format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+    } else {
+        format!("{:.2}s", duration.as_secs_f64())
+    }
+}
+
+impl std::fmt::Display for SpanTiming {
+    /// "scan: 12 spans, total 1.25s, mean 104.2ms, p95 ≤ 131.1ms, max 150.0ms"
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mean = self.total / u32::try_from(self.count.max(1)).unwrap_or(u32::MAX);
+        write!(
+            f, "{}: {} span{}, total {}, mean {}, p95 ≤ {}, max {}",
+            self.name, self.count, if self.count == 1 { "" } else { "s" },
+            format_duration(self.total), format_duration(mean),
+            format_duration(self.p95), format_duration(self.max),
+        )
+    }
+}
+
+impl<S> Layer<S> for SpanTimingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(Busy { total: Duration::ZERO, entered: None, depth: 0, used: false });
+        }
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut extensions = span.extensions_mut();
+        if let Some(busy) = extensions.get_mut::<Busy>() {
+            if busy.depth == 0 {
+                busy.entered = Some(Instant::now());
+            }
+            busy.depth += 1;
+            busy.used = true;
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut extensions = span.extensions_mut();
+        if let Some(busy) = extensions.get_mut::<Busy>() {
+            busy.depth = busy.depth.saturating_sub(1);
+            if busy.depth == 0 {
+                if let Some(entered) = busy.entered.take() {
+                    busy.total += entered.elapsed();
+                }
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else { return };
+        let Some(busy) = span.extensions_mut().remove::<Busy>() else { return };
+        // A span that was never entered did no timed work
+        if !busy.used {
+            return;
+        }
+        let name = span.name();
+        self.record(name, busy.total);
+
+        if let Some(threshold) = self.warn_threshold.filter(|threshold| busy.total > *threshold) {
+            let message = format!(
+                "Slow span {}: {} (threshold {})",
+                name, format_duration(busy.total), format_duration(threshold),
+            );
+            SLOW_SPAN_CALLSITE.register();
+            let fields = SLOW_SPAN_METADATA.fields();
+            let Some(field) = fields.field("message") else { return };
+            let values = [(&field, Some(&message as &dyn tracing::Value))];
+            ctx.event(&Event::new(&SLOW_SPAN_METADATA, &fields.value_set(&values)));
+        }
+    }
+}
+
+static SLOW_SPAN_CALLSITE: DefaultCallsite = DefaultCallsite::new(&SLOW_SPAN_METADATA);
+
+static SLOW_SPAN_METADATA: Metadata<'static> = Metadata::new(
+    "slow span",
+    "autodebugger::span_timing",
+    Level::WARN,
+    Some(file!()),
+    Some(line!()),
+    Some(module_path!()),
+    FieldSet::new(&["message"], Identifier(&SLOW_SPAN_CALLSITE)),
+    Kind::EVENT,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn sleep_ms(ms: u64) {
+        std::thread::sleep(Duration::from_millis(ms));
+    }
+
+    #[test]
+    fn test_nested_spans_recorded_once_each() {
+        let layer = SpanTimingLayer::new();
+        let subscriber = tracing_subscriber::registry().with(layer.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            let outer = tracing::info_span!("outer");
+            outer.in_scope(|| {
+                for _ in 0..2 {
+                    tracing::info_span!("inner").in_scope(|| sleep_ms(20));
+                }
+                // Entering the entered span again must not count its time twice
+                outer.in_scope(|| sleep_ms(20));
+            });
+            // Entered a second time later: still one span
+            outer.in_scope(|| sleep_ms(20));
+            // Never entered: not a sample
+            drop(tracing::info_span!("idle"));
+        });
+
+        let report = layer.report();
+        assert_eq!(report.iter().map(|timing| (timing.name.as_str(), timing.count)).collect::<Vec<_>>(),
+            vec![("outer", 1), ("inner", 2)]);
+        let (outer, inner) = (&report[0], &report[1]);
+        assert!(inner.total >= Duration::from_millis(40), "{}", inner);
+        assert!(inner.max >= Duration::from_millis(20) && inner.max < inner.total, "{}", inner);
+        // The outer span covers both inner spans and its own two sleeps, once
+        assert!(outer.total >= Duration::from_millis(80), "{}", outer);
+        assert!(outer.total < Duration::from_millis(80) + inner.total, "{}", outer);
+        assert!(outer.p95 <= outer.max);
+    }
+
+    #[test]
+    fn test_slow_span_warning() {
+        let layer = SpanTimingLayer::new().with_warn_threshold(Duration::from_millis(15));
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let writer = buffer.clone();
+        // The warning goes to the layers below, so the timing layer comes last
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_subscriber::fmt::layer()
+                .with_writer(move || WriteTo(writer.clone()))
+                .with_ansi(false)
+                .without_time())
+            .with(layer.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("quick").in_scope(|| {});
+            tracing::info_span!("scan").in_scope(|| sleep_ms(20));
+        });
+
+        let output = String::from_utf8(buffer.lock().unwrap().clone()).unwrap();
+        assert_eq!(output.lines().count(), 1, "{}", output);
+        assert!(output.contains("WARN autodebugger::span_timing: Slow span scan: "), "{}", output);
+        assert!(output.contains("(threshold 15.0ms)"), "{}", output);
+
+        let report = layer.format_report().unwrap();
+        assert!(report.starts_with("\nSPAN TIMINGS\n============\n  scan: 1 span, total "), "{}", report);
+        assert!(report.contains("\n  quick: 1 span, total "), "{}", report);
+    }
+
+    #[test]
+    fn test_p95_approximation() {
+        let mut stats = Stats::new();
+        for _ in 0..95 {
+            stats.record(Duration::from_micros(100));
+        }
+        for _ in 0..5 {
+            stats.record(Duration::from_millis(50));
+        }
+        // 100µs falls in the bucket up to 128µs
+        assert_eq!(stats.p95(), Duration::from_micros(128));
+        stats.record(Duration::from_millis(50));
+        assert_eq!(stats.p95(), Duration::from_millis(50));
+        assert_eq!(format_duration(Duration::from_micros(850)), "850µs");
+        assert_eq!(format_duration(Duration::from_millis(1250)), "1.25s");
+    }
+
+    /// fmt writer appending to a shared buffer
+    struct WriteTo(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for WriteTo {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Results of running a named task from the config's `tasks` section
+//!
+//! A task is a list of [`TaskStep`](crate::config::TaskStep)s, each a command
+//! line with an optional directory and environment, run one after another by
+//! [`Autodebugger::run_task`](crate::Autodebugger::run_task) (stopping at the
+//! first failure) or [`Autodebugger::run_task_with`](crate::Autodebugger::run_task_with)
+//! (optionally running every step, with a callback as each one finishes).
+//! [`TaskReport`] keeps each step's result and duration; its exit code is the
+//! first failing step's, as `autodebugger task` exits with.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::CommandResult;
+
+/// One step that ran: its command line, where, how long, and its result
+#[derive(Debug, Clone)]
+pub struct StepResult {
+    pub command: String,
+    /// The step's `dir`, if it set one
+    pub dir: Option<String>,
+    pub duration: Duration,
+    pub result: CommandResult,
+}
+
+/// Every step of a task that ran, and the ones skipped after a failure
+#[derive(Debug, Clone, Default)]
+pub struct TaskReport {
+    pub name: String,
+    pub steps: Vec<StepResult>,
+    /// Command lines not run because an earlier step failed
+    pub skipped: Vec<String>,
+}
+
+impl TaskReport {
+    pub fn success(&self) -> bool {
+        self.steps.iter().all(|step| step.result.success)
+    }
+
+    /// The first failing step's exit code, or 0 when every step passed
+    pub fn exit_code(&self) -> i32 {
+        self.steps.iter()
+            .find(|step| !step.result.success)
+            .map_or(0, |step| step.result.exit_code)
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.steps.iter().map(|step| step.duration).sum()
+    }
+
+    /// One line per step with its outcome and duration, under a totals line
+    pub fn summary(&self) -> String {
+        let total = self.steps.len() + self.skipped.len();
+        let passed = self.steps.iter().filter(|step| step.result.success).count();
+        let mut out = format!(
+            "Task {}: {} of {} steps passed in {}\n",
+            self.name, passed, total, format_duration(self.duration())
+        );
+        for step in &self.steps {
+            let command = match &step.dir {
+                Some(dir) => format!("{} (in {})", step.command, dir),
+                None => step.command.clone(),
+            };
+            let outcome = if step.result.success {
+                "ok".to_string()
+            } else {
+                format!("failed ({})", step.result.exit_code)
+            };
+            let _ = writeln!(out, "  {:<12} {:>7}  {}", outcome, format_duration(step.duration), command);
+        }
+        for command in &self.skipped {
+            let _ = writeln!(out, "  {:<12} {:>7}  {}", "skipped", "", command);
+        }
+        out
+    }
+}
+
+/// Seconds with one decimal, or minutes and seconds from a minute up
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs_f64();
+    if seconds < 60.0 {
+        format!("{:.1}s", seconds)
+    } else {
+        format!("{}m{:02}s", duration.as_secs() / 60, duration.as_secs() % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(command: &str, exit_code: i32, millis: u64) -> StepResult {
+        StepResult {
+            command: command.to_string(),
+            dir: None,
+            duration: Duration::from_millis(millis),
+            result: CommandResult { stdout: String::new(), stderr: String::new(), exit_code, success: exit_code == 0 },
+        }
+    }
+
+    #[test]
+    fn test_summary_and_exit_code() {
+        let report = TaskReport {
+            name: "check".to_string(),
+            steps: vec![step("cargo fmt --check", 0, 400), step("cargo clippy", 101, 3200)],
+            skipped: vec!["cargo test".to_string()],
+        };
+        assert!(!report.success());
+        assert_eq!(report.exit_code(), 101);
+        assert_eq!(report.summary(), "\
+Task check: 1 of 3 steps passed in 3.6s
+  ok              0.4s  cargo fmt --check
+  failed (101)    3.2s  cargo clippy
+  skipped               cargo test
+");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m05s");
+        assert_eq!(TaskReport::default().exit_code(), 0);
+    }
+}
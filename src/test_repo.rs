@@ -0,0 +1,39 @@
+//! Git repositories for tests
+//!
+//! Compiled into the crate's unit tests and, through `#[path]`, into the
+//! integration tests, so both build fixtures the same way. Every command runs
+//! with a fixed identity (`t <t@t>`), so commits work without a global git
+//! config.
+
+use std::path::Path;
+use std::process::Command;
+
+/// `git` in `dir` with the test identity, for commands expected to fail
+pub fn git_command(dir: &Path) -> Command {
+    let mut command = Command::new("git");
+    command.current_dir(dir).args(["-c", "user.name=t", "-c", "user.email=t@t"]);
+    command
+}
+
+/// Run `git args` in `dir`, panicking with its stderr if it fails; returns stdout
+pub fn git(dir: &Path, args: &[&str]) -> String {
+    let output = git_command(dir).args(args).output().unwrap();
+    assert!(output.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// A repository on `main` in `dir` (created if missing) with `files`, each a
+/// path and its contents, committed as "base"
+pub fn init_repo(dir: &Path, files: &[(&str, &str)]) {
+    std::fs::create_dir_all(dir).unwrap();
+    git(dir, &["init", "-q", "-b", "main"]);
+    for (path, contents) in files {
+        let path = dir.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        std::fs::write(path, contents).unwrap();
+    }
+    git(dir, &["add", "."]);
+    git(dir, &["commit", "-q", "--allow-empty", "-m", "base"]);
+}
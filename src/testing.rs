@@ -0,0 +1,119 @@
+//! Test helpers for asserting on tracing output (`test-util` feature)
+//!
+//! [`capture_logs`] runs a closure under a subscriber that formats events with
+//! the [`ConditionalLocationFormatter`] into memory and counts them with a fresh
+//! [`VerbosityCheckLayer`], then returns both:
+//!
+//! ```rust
+//! # #[cfg(feature = "test-util")] {
+//! use autodebugger::testing::capture_logs;
+//!
+//! let logs = capture_logs(|| {
+//!     tracing::info!("Scanning");
+//!     tracing::warn!("Slow scan");
+//! });
+//! assert_eq!(logs.counts.warn, 1);
+//! assert!(logs.contains("Slow scan"));
+//! # }
+//! ```
+//!
+//! The subscriber is the thread's default only while the closure runs and the
+//! previous default comes back afterwards; the global subscriber is never set,
+//! so tests running in parallel don't see each other's events. Events from
+//! threads the closure spawns are not captured. Every level is captured,
+//! whatever RUST_LOG says, and without ANSI colors.
+
+use crate::config::Config;
+use crate::tracing_subscriber::{ConditionalLocationFormatter, KeyValueFields, LogCounts, VerbosityCheckLayer};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tracing_subscriber::layer::SubscriberExt;
+
+/// Output of [`capture_logs`]
+#[derive(Debug, Clone, Default)]
+pub struct CapturedLogs {
+    /// Formatted output, one entry per line
+    pub lines: Vec<String>,
+    /// Events counted by level
+    pub counts: LogCounts,
+}
+
+impl CapturedLogs {
+    /// Whether any line contains `text`
+    pub fn contains(&self, text: &str) -> bool {
+        self.lines.iter().any(|line| line.contains(text))
+    }
+
+    /// All output as one string, lines ending in newlines
+    pub fn text(&self) -> String {
+        self.lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+}
+
+/// Run `f` with a thread-local subscriber and return what it logged
+pub fn capture_logs<F: FnOnce()>(f: F) -> CapturedLogs {
+    let buffer = Buffer::default();
+    let writer = buffer.clone();
+    // Default thresholds, without reading a config.yaml from the working directory
+    let verbosity = VerbosityCheckLayer::with_config(Config::default());
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .fmt_fields(KeyValueFields)
+            .event_format(ConditionalLocationFormatter::new()))
+        .with(verbosity.clone());
+
+    tracing::subscriber::with_default(subscriber, f);
+
+    let output = buffer.contents();
+    CapturedLogs {
+        lines: output.lines().map(str::to_string).collect(),
+        counts: verbosity.counts_by_level(),
+    }
+}
+
+/// In-memory writer shared by the fmt layer's writers; the crate's own tests
+/// use it directly for layers `capture_logs` does not build (JSON, file output)
+#[derive(Clone, Default)]
+pub(crate) struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Buffer {
+    /// Everything written so far
+    pub(crate) fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_restores_previous_default() {
+        let outer = capture_logs(|| {
+            tracing::info!("Outer before");
+            let inner = capture_logs(|| tracing::error!("Inner"));
+            assert_eq!(inner.lines.len(), 1);
+            assert_eq!(inner.counts.error, 1);
+            tracing::info!("Outer after");
+        });
+        assert_eq!(outer.lines, vec!["Outer before", "Outer after"]);
+        assert_eq!(outer.counts, LogCounts { info: 2, ..LogCounts::default() });
+
+        // Nothing is left installed for this thread
+        let after = capture_logs(|| {});
+        assert!(after.lines.is_empty());
+        assert_eq!(after.text(), "");
+    }
+}
@@ -0,0 +1,183 @@
+//! Unified diffs between two versions of a text
+//!
+//! Used to preview source rewrites (e.g. `remove-debug --dry-run --diff`). Lines are
+//! matched with Myers' O(ND) algorithm, which is fast when the edit is small
+//! compared to the file, and rendered in the same format as `git diff`: `---`/`+++`
+//! headers, `@@ -a,b +c,d @@` hunk headers, and a configurable number of context
+//! lines. A last line without a trailing newline is marked with
+//! `\ No newline at end of file`, so the output can be applied with `patch` or
+//! `git apply`.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Keep,
+    Delete,
+    Insert,
+}
+
+/// Render a unified diff from `old` to `new`; empty if they are identical
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str, context: usize) -> String {
+    let a: Vec<&str> = old.split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+    let edits = myers(&a, &b);
+    if edits.iter().all(|e| *e == Edit::Keep) {
+        return String::new();
+    }
+
+    // Position in each file before every edit
+    let mut positions = Vec::with_capacity(edits.len() + 1);
+    let (mut x, mut y) = (0, 0);
+    for edit in &edits {
+        positions.push((x, y));
+        match edit {
+            Edit::Keep => { x += 1; y += 1; }
+            Edit::Delete => x += 1,
+            Edit::Insert => y += 1,
+        }
+    }
+    positions.push((x, y));
+
+    let mut out = format!("--- {}\n+++ {}\n", old_label, new_label);
+    for (start, end) in hunk_ranges(&edits, context) {
+        let (old_start, new_start) = positions[start];
+        let old_count = edits[start..end].iter().filter(|e| **e != Edit::Insert).count();
+        let new_count = edits[start..end].iter().filter(|e| **e != Edit::Delete).count();
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(old_start, old_count),
+            range(new_start, new_count)
+        ));
+
+        for (i, edit) in edits.iter().enumerate().take(end).skip(start) {
+            let (x, y) = positions[i];
+            let (prefix, line) = match edit {
+                Edit::Keep => (' ', a[x]),
+                Edit::Delete => ('-', a[x]),
+                Edit::Insert => ('+', b[y]),
+            };
+            out.push(prefix);
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+    out
+}
+
+/// `start,count` as in git: the count is omitted when 1, and an empty range
+/// names the line before it
+fn range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+
+/// Edit index ranges of hunks: each change plus `context` edits on either side,
+/// merging hunks whose context would touch
+fn hunk_ranges(edits: &[Edit], context: usize) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (i, _) in edits.iter().enumerate().filter(|(_, e)| **e != Edit::Keep) {
+        let start = i.saturating_sub(context);
+        let end = (i + 1 + context).min(edits.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+    ranges
+}
+
+/// Shortest edit script from `a` to `b` (Myers, 1986)
+fn myers(a: &[&str], b: &[&str]) -> Vec<Edit> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let offset = n + m + 1;
+    let index = |k: isize| (k + offset) as usize;
+    let mut v = vec![0isize; (2 * offset + 1) as usize];
+    let mut trace = Vec::new();
+
+    'search: for d in 0..=(n + m) {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) { k + 1 } else { k - 1 };
+        let prev_x = v[index(prev_k)];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            edits.push(Edit::Keep);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            edits.push(if x == prev_x { Edit::Insert } else { Edit::Delete });
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    edits.reverse();
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::git;
+
+    #[test]
+    fn test_identical_texts_have_no_diff() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "a/f", "b/f", 3), "");
+    }
+
+    #[test]
+    fn test_hunks_have_context_and_headers() {
+        let old: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+        let new = old.replace("line 2\n", "").replace("line 3\n", "").replace("line 18\n", "changed\n");
+        let diff = unified_diff(&old, &new, "a/src/lib.rs", "b/src/lib.rs", 3);
+        assert_eq!(diff, "--- a/src/lib.rs\n+++ b/src/lib.rs\n\
+            @@ -1,6 +1,4 @@\n line 1\n-line 2\n-line 3\n line 4\n line 5\n line 6\n\
+            @@ -15,6 +13,6 @@\n line 15\n line 16\n line 17\n-line 18\n+changed\n line 19\n line 20\n");
+    }
+
+    #[test]
+    fn test_missing_final_newline_is_marked() {
+        let diff = unified_diff("a\nb", "a\n", "a/f", "b/f", 1);
+        assert_eq!(diff, "--- a/f\n+++ b/f\n@@ -1,2 +1 @@\n a\n-b\n\\ No newline at end of file\n");
+        assert_eq!(unified_diff("", "x\n", "a/f", "b/f", 3), "--- a/f\n+++ b/f\n@@ -0,0 +1 @@\n+x\n");
+    }
+
+    #[test]
+    fn test_diff_applies_with_patch() {
+        let temp = tempfile::tempdir().unwrap();
+        let old: String = (1..=30).map(|i| format!("fn f{}() {{}}\n", i)).collect();
+        let new = old.replace("fn f5() {}\n", "").replace("fn f25() {}\n", "fn g() {}\n");
+        std::fs::write(temp.path().join("file.rs"), &old).unwrap();
+        let diff = unified_diff(&old, &new, "a/file.rs", "b/file.rs", 3);
+        std::fs::write(temp.path().join("change.patch"), diff).unwrap();
+
+        git(temp.path(), &["apply", "change.patch"]);
+        assert_eq!(std::fs::read_to_string(temp.path().join("file.rs")).unwrap(), new);
+    }
+}
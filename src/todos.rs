@@ -0,0 +1,252 @@
+//! TODO/FIXME/HACK comment scanning
+//!
+//! Shared helper for anything that needs to surface outstanding work markers in a
+//! source tree: the `todos` context type in the monitor uses it to give agents a
+//! per-worktree list of open items, and quality checks can use the same scanner to
+//! count them.
+//!
+//! Only markers that open a comment are reported (`// TODO: ...`, `/* FIXME */`, a
+//! leading `*` in block comments, and `#` for scripting and config languages).
+//! Files are filtered by extension and by glob ignore patterns in the same style as
+//! `validate_docs`, and files over a size threshold are skipped to keep scans fast
+//! on large trees.
+//! Hidden directories, `target/`, and `node_modules/` are never descended into.
+//!
+//! ## Configuration
+//!
+//! ```yaml
+//! todos:
+//!   extensions: ["rs", "py", "ts"]
+//!   ignore_patterns: ["**/vendor/**"]
+//!   max_per_worktree: 100
+//!   max_file_size_kb: 512
+//! ```
+
+use anyhow::{Context, Result};
+use glob::Pattern;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::config::TodosConfig;
+
+/// Kind of work marker found in a comment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TodoKind {
+    Todo,
+    Fixme,
+    Hack,
+}
+
+impl std::fmt::Display for TodoKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            TodoKind::Todo => "TODO",
+            TodoKind::Fixme => "FIXME",
+            TodoKind::Hack => "HACK",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A single marker with its location, relative to the scanned root
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodoItem {
+    pub path: PathBuf,
+    pub line: usize,
+    pub kind: TodoKind,
+    pub text: String,
+}
+
+/// Result of scanning one tree
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TodoScan {
+    pub items: Vec<TodoItem>,
+    /// Markers found beyond `max_results` that were not collected
+    pub omitted: usize,
+}
+
+/// Scans source files for TODO, FIXME, and HACK comments
+pub struct TodoScanner {
+    /// File extensions to scan (without the leading dot)
+    pub extensions: Vec<String>,
+    /// Glob patterns to ignore, matched against paths relative to the root
+    pub ignore_patterns: Vec<Pattern>,
+    /// Maximum number of items to collect
+    pub max_results: usize,
+    /// Files larger than this many bytes are skipped
+    pub max_file_size: u64,
+    marker_re: Regex,
+}
+
+impl TodoScanner {
+    /// Create a scanner with default settings
+    pub fn new() -> Self {
+        Self::from_config(&TodosConfig::default())
+            .expect("default todo ignore patterns are valid")
+    }
+
+    /// Create a scanner from the `todos` section of the config
+    pub fn from_config(config: &TodosConfig) -> Result<Self> {
+        Self {
+            extensions: config.extensions.clone(),
+            ignore_patterns: Vec::new(),
+            max_results: config.max_per_worktree,
+            max_file_size: config.max_file_size_kb * 1024,
+            marker_re: Regex::new(r"(?://+!?|/\*+|^\s*\*|#)\s*(TODO|FIXME|HACK)(?:\([^)]*\))?(?::|\s|$)\s*(.*)")
+                .expect("todo marker regex is valid"),
+        }
+        .with_ignore_patterns(config.ignore_patterns.clone())
+    }
+
+    /// Set ignore patterns
+    pub fn with_ignore_patterns(mut self, patterns: Vec<String>) -> Result<Self> {
+        let mut compiled_patterns = Vec::new();
+        for pattern in patterns {
+            let compiled = Pattern::new(&pattern)
+                .with_context(|| format!("Invalid glob pattern: {}", pattern))?;
+            compiled_patterns.push(compiled);
+        }
+        self.ignore_patterns = compiled_patterns;
+        Ok(self)
+    }
+
+    /// Set the maximum number of items to collect
+    pub fn with_max_results(mut self, max: usize) -> Self {
+        self.max_results = max;
+        self
+    }
+
+    /// Scan every matching file under `root`
+    pub fn scan(&self, root: &Path) -> Result<TodoScan> {
+        let mut scan = TodoScan::default();
+
+        let walker = WalkDir::new(root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !is_skipped_dir(e.path(), e.file_type().is_dir()));
+
+        for entry in walker.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            if !entry.file_type().is_file() || !self.should_scan(path, relative) {
+                continue;
+            }
+
+            if entry.metadata().map(|m| m.len() > self.max_file_size).unwrap_or(true) {
+                continue;
+            }
+
+            // Binary or non-UTF-8 files are not source code worth scanning
+            let Ok(content) = fs::read_to_string(path) else {
+                continue;
+            };
+
+            for item in self.scan_content(&content, relative) {
+                if scan.items.len() < self.max_results {
+                    scan.items.push(item);
+                } else {
+                    scan.omitted += 1;
+                }
+            }
+        }
+
+        Ok(scan)
+    }
+
+    /// Find markers in a single file's content
+    pub fn scan_content(&self, content: &str, path: &Path) -> Vec<TodoItem> {
+        content
+            .lines()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let caps = self.marker_re.captures(line)?;
+                let kind = match &caps[1] {
+                    "TODO" => TodoKind::Todo,
+                    "FIXME" => TodoKind::Fixme,
+                    _ => TodoKind::Hack,
+                };
+                let text = caps[2].trim().trim_end_matches("*/").trim().to_string();
+                Some(TodoItem {
+                    path: path.to_path_buf(),
+                    line: index + 1,
+                    kind,
+                    text,
+                })
+            })
+            .collect()
+    }
+
+    fn should_scan(&self, path: &Path, relative: &Path) -> bool {
+        let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+        if !self.extensions.iter().any(|e| e == extension) {
+            return false;
+        }
+
+        let path_str = relative.to_string_lossy();
+        !self.ignore_patterns.iter().any(|p| p.matches(&path_str))
+    }
+}
+
+impl Default for TodoScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_skipped_dir(path: &Path, is_dir: bool) -> bool {
+    if !is_dir {
+        return false;
+    }
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    name.starts_with('.') || name == "target" || name == "node_modules"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_comment_markers_are_reported() {
+        let scanner = TodoScanner::new();
+        let content = "fn main() {\n    // TODO: handle errors\n    // Scans TODO/FIXME markers\n    let s = \"TODO not a comment\";\n    /* FIXME(brandt) leaks */\n    x += 1; // HACK until upstream fix\n}\n";
+        let items = scanner.scan_content(content, Path::new("main.rs"));
+
+        let found: Vec<(usize, TodoKind, &str)> = items.iter()
+            .map(|i| (i.line, i.kind, i.text.as_str()))
+            .collect();
+        assert_eq!(found, vec![
+            (2, TodoKind::Todo, "handle errors"),
+            (5, TodoKind::Fixme, "leaks"),
+            (6, TodoKind::Hack, "until upstream fix"),
+        ]);
+    }
+
+    #[test]
+    fn test_scan_respects_filters_and_limit() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::create_dir_all(root.join("vendor")).unwrap();
+        fs::write(root.join("src/a.rs"), "// TODO one\n// TODO two\n// TODO three\n").unwrap();
+        fs::write(root.join("src/notes.txt"), "# TODO wrong extension\n").unwrap();
+        fs::write(root.join("target/gen.rs"), "// TODO build output\n").unwrap();
+        fs::write(root.join("vendor/lib.rs"), "// TODO vendored\n").unwrap();
+        fs::write(root.join("big.rs"), format!("// TODO big\n{}", "x".repeat(4096))).unwrap();
+
+        let config = TodosConfig {
+            ignore_patterns: vec!["vendor/**".to_string()],
+            max_per_worktree: 2,
+            max_file_size_kb: 1,
+            ..TodosConfig::default()
+        };
+        let scan = TodoScanner::from_config(&config).unwrap().scan(root).unwrap();
+
+        assert_eq!(scan.items.len(), 2);
+        assert_eq!(scan.omitted, 1);
+        assert!(scan.items.iter().all(|i| i.path == Path::new("src/a.rs")));
+    }
+}
@@ -29,30 +29,69 @@
 //! - Next M occurrences are logged at DEBUG level
 //! - Remaining occurrences are logged at TRACE level
 //! - Configurable thresholds via `VerbosityConfig`
+//! - For long-running processes, `reset()`, `snapshot_and_reset()`, or `with_window()`
+//!   restart the counts so the threshold applies per period rather than per lifetime
+//! - With `verbosity.emit_live_warning`, the layer logs one WARN the moment the
+//!   threshold is exceeded (again only after a reset or a new window)
+//! - Counts are also kept per target (module path) for the 256 noisiest targets, and
+//!   the warning from `check_and_report()` lists the top 10 so it names the culprit
 //!
 //! ### Conditional Location Formatting
 //! The `ConditionalLocationFormatter` adds source location information (file:line) to
 //! log messages, but only when the verbosity threshold is exceeded. This helps identify
 //! the source of frequent log messages without cluttering initial output.
 //!
+//! Fields follow the message as `key=value` pairs sorted by name, e.g.
+//! `Signed in user_id=42 zone="eu west"`, with values quoted when empty or
+//! containing whitespace; `KeyValueFields` writes span fields the same way, so
+//! `grep user_id=` finds both. A log file can instead end pretty lines with the
+//! fields as one JSON object (`json_fields` in `RotatingFileConfig`).
+//!
+//! ### JSON Output
+//! With `LogFormat::Json` a fmt layer uses `JsonFormatter` instead, writing one JSON
+//! object per event with `timestamp`, `level`, `target`, `message`, `fields`, `file`,
+//! `line`, and `spans` (name and fields of each enclosing span, root first). Console
+//! and file formats are chosen separately, e.g. pretty console with a JSON file for a
+//! log aggregator (`logging.format` and `logging.file_format` in `config.yaml`).
+//!
+//! ### Runtime Level Changes
+//! The EnvFilter sits in a `reload::Layer`, and the init functions return a
+//! `LoggingHandle` whose `set_level` / `set_directives` swap it while the process
+//! runs, updating the level the verbosity thresholds are chosen by. The handle
+//! derefs to the `VerbosityCheckLayer`.
+//!
+//! ### Panics
+//! `install_panic_hook` logs each panic, with a backtrace, as an ERROR event before
+//! the previous hook runs. `init_logging_with_file` installs it, flushing the log
+//! file, unless `log_panics` is off in its `RotatingFileConfig`.
+//!
+//! ### MCP Servers
+//! An MCP server speaks JSON-RPC on stdout, so nothing else may be written there.
+//! `init_logging_for_mcp` logs to stderr (and a JSON log file, if configured) and
+//! reserves stdout: `report_output()`, which report helpers such as
+//! `ValidationReport::print_summary` are given by the CLI, then returns stderr.
+//! `autodebugger mcp-serve` (the `mcp` module) always starts with this preset.
+//!
 //! ### External Crate Filtering
-//! Automatically suppresses debug-level logs from external crates to reduce noise,
-//! while preserving info-level and above messages from all sources.
+//! Per-crate directives from `logging.suppress` (e.g. `sqlx=warn`) are added to the
+//! filter to quiet noisy dependencies, and kept across runtime level changes.
+//! RUST_LOG can still raise a suppressed crate, since its directives come last.
 //!
 //! ## Architecture
 //!
 //! The module uses tracing-subscriber's layered architecture:
 //! 1. **Base Layer**: EnvFilter for RUST_LOG environment variable support
 //! 2. **Verbosity Layer**: Custom layer for frequency-based filtering
-//! 3. **Format Layer**: Customizable output formatting with conditional locations
+//! 3. **Format Layer**: Customizable output formatting with conditional locations, or JSON
 //!
 //! ## Usage
 //!
 //! ```rust,no_run
-//! use autodebugger::init_logging;
+//! use autodebugger::{init_logging, Config};
 //!
-//! // Use default verbosity config from autodebugger's config.yaml
-//! let verbosity_layer = init_logging(Some("info"), None, None);
+//! // Verbosity thresholds and logging settings from autodebugger's config.yaml
+//! let config = Config::load().unwrap_or_default();
+//! let verbosity_layer = init_logging(Some("info"), &config, None, None);
 //! 
 //! // Or provide custom verbosity thresholds
 //! use autodebugger::VerbosityConfig;
@@ -60,33 +99,84 @@
 //!     info_threshold: 100,
 //!     debug_threshold: 200,
 //!     trace_threshold: 500,
+//!     emit_live_warning: true,
 //! };
-//! let verbosity_layer = init_logging(Some("info"), Some(custom_verbosity), Some("stderr"));
+//! let config = Config { verbosity: custom_verbosity, ..Config::default() };
+//! let verbosity_layer = init_logging(Some("info"), &config, Some("stderr"), None);
+//!
+//! // JSON lines on the console whatever `logging.format` says
+//! use autodebugger::LogFormat;
+//! let verbosity_layer = init_logging(None, &Config::default(), None, Some(LogFormat::Json));
+//!
+//! // Raise verbosity at runtime; the threshold follows the new level
+//! verbosity_layer.set_level("debug").unwrap();
+//! verbosity_layer.set_directives("info,autodebugger::ci=trace").unwrap();
 //! ```
 //!
 //! ## Configuration
 //!
-//! Verbosity thresholds can be configured via `config.yaml`:
+//! The init functions take the `Config` to use, so the CLI's `--config`,
+//! `--profile`, and `AUTODEBUGGER__*` overrides apply; nothing is read from disk
+//! behind the caller's back. The settings involved, as in `config.yaml`:
 //! - `verbosity.info_threshold`: Number of messages before switching to DEBUG
 //! - `verbosity.debug_threshold`: Number of messages before switching to TRACE
 //! - `verbosity.trace_threshold`: Maximum messages to log at TRACE level
+//! - `logging.format`: Console format, `pretty` (default) or `json`
+//! - `logging.file_format`: Rotating file format; defaults to `logging.format`
+//!
+//! The threshold used is the one for the filter's global level: the level of the
+//! directive without a target (`warn,tokio=trace` uses no threshold, being WARN),
+//! taken from RUST_LOG or else the `default_level` given to `init_logging`.
 
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
-use tracing::{Event, Level, Subscriber};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use tracing::field::{Field, Visit};
+use tracing::span::Record;
+use tracing::callsite::{DefaultCallsite, Identifier};
+use tracing::field::FieldSet;
+use tracing::metadata::Kind;
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::field::RecordFields;
 use tracing_subscriber::fmt::format::Writer;
-use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::fmt::writer::{BoxMakeWriter, MakeWriter};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
 use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
 use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::EnvFilter;
-use crate::config::{Config, RotatingFileConfig};
-use crate::rotating_file_logger::RotatingWriterWrapper;
+use tracing_subscriber::filter::{Directive, LevelFilter};
+use tracing_subscriber::{reload, EnvFilter, Registry};
+use anyhow::{Context as _, Result};
+use crate::config::{Config, LogFormat, RotatingFileConfig};
+use crate::rotating_file_logger::{RotatingFileGuard, RotatingWriterWrapper};
+use crate::span_timing::SpanTimingLayer;
 
 
 /// Custom formatter that conditionally shows file:line only for ERROR and WARN levels
 /// and omits the INFO prefix for cleaner default-level output
-pub struct ConditionalLocationFormatter;
+///
+/// The message comes first, then the event's other fields as `key=value` pairs
+/// sorted by name, or with `with_json_fields` as one JSON object. Span fields are
+/// written the same way when the layer uses `KeyValueFields`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConditionalLocationFormatter {
+    json_fields: bool,
+}
+
+impl ConditionalLocationFormatter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Write the fields after the message as one JSON object, e.g. for a log file
+    /// read by tools: `Scanned {"files":3,"worktree":"feature-x"}`
+    pub fn with_json_fields(mut self, json_fields: bool) -> Self {
+        self.json_fields = json_fields;
+        self
+    }
+}
 
 impl<S, N> FormatEvent<S, N> for ConditionalLocationFormatter
 where
@@ -137,13 +227,277 @@ where
             write!(writer, " ")?;
         }
         
-        // Write the event fields
-        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        // Write the message, then the other fields
+        let (message, fields) = if self.json_fields {
+            let mut visitor = JsonVisitor::default();
+            event.record(&mut visitor);
+            let fields = if visitor.fields.is_empty() {
+                String::new()
+            } else {
+                serde_json::to_string(&visitor.fields).map_err(|_| std::fmt::Error)?
+            };
+            (visitor.message, fields)
+        } else {
+            let mut visitor = KeyValueVisitor::default();
+            event.record(&mut visitor);
+            (visitor.message, key_value_pairs(&visitor.fields))
+        };
+        let message = message.unwrap_or_default();
+        let separator = if message.is_empty() || fields.is_empty() { "" } else { " " };
+        writeln!(writer, "{}{}{}", message, separator, fields)
+    }
+}
+
+/// Field formatter writing span fields as sorted `key=value` pairs, for
+/// `ConditionalLocationFormatter`; fields recorded later are merged in by name
+pub struct KeyValueFields;
+
+impl<'writer> FormatFields<'writer> for KeyValueFields {
+    fn format_fields<R: RecordFields>(&self, mut writer: Writer<'writer>, fields: R) -> std::fmt::Result {
+        let mut visitor = KeyValueVisitor::keeping_message();
+        fields.record(&mut visitor);
+        write!(writer, "{}", key_value_pairs(&visitor.fields))
+    }
+    
+    fn add_fields(&self, current: &'writer mut FormattedFields<Self>, fields: &Record<'_>) -> std::fmt::Result {
+        let mut visitor = KeyValueVisitor::keeping_message();
+        visitor.fields = parse_key_value_pairs(&current.fields);
+        fields.record(&mut visitor);
+        current.fields = key_value_pairs(&visitor.fields);
+        Ok(())
+    }
+}
+
+/// Collects fields as escaped `key=value` values, keeping `message` apart for events
+#[derive(Default)]
+struct KeyValueVisitor {
+    message: Option<String>,
+    fields: BTreeMap<String, String>,
+    /// Span fields: `message` is an ordinary pair
+    message_as_field: bool,
+}
+
+impl KeyValueVisitor {
+    fn keeping_message() -> Self {
+        Self { message_as_field: true, ..Self::default() }
+    }
+    
+    fn insert(&mut self, field: &Field, value: String) {
+        if field.name() == "message" && !self.message_as_field {
+            self.message = Some(value);
+        } else {
+            self.fields.insert(field.name().to_string(), escape_value(value));
+        }
+    }
+}
+
+impl Visit for KeyValueVisitor {
+    // `%value` fields arrive here too, wrapped so their Debug is their Display
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.insert(field, format!("{:?}", value));
+    }
+    
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.to_string());
+    }
+}
+
+/// Quote values that would not survive splitting on spaces: empty, containing
+/// whitespace, or starting with a quote (unless already one quoted string, as
+/// `?text` gives)
+fn escape_value(value: String) -> String {
+    if is_quoted(&value) {
+        value
+    } else if value.is_empty() || value.starts_with('"') || value.contains(char::is_whitespace) {
+        format!("{:?}", value)
+    } else {
+        value
+    }
+}
+
+/// Byte length of the quoted string starting `text`, escapes included
+fn quoted_len(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    if chars.next()?.1 != '"' {
+        return None;
+    }
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => { chars.next(); }
+            '"' => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Whether `text` is exactly one quoted string with escaped inner quotes
+fn is_quoted(text: &str) -> bool {
+    quoted_len(text) == Some(text.len())
+}
+
+fn key_value_pairs(fields: &BTreeMap<String, String>) -> String {
+    fields.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<_>>().join(" ")
+}
+
+/// Read back pairs written by `key_value_pairs`
+fn parse_key_value_pairs(mut text: &str) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    loop {
+        text = text.trim_start();
+        let Some((key, rest)) = text.split_once('=') else { return fields };
+        let len = quoted_len(rest).unwrap_or_else(|| rest.find(' ').unwrap_or(rest.len()));
+        fields.insert(key.to_string(), rest[..len].to_string());
+        text = &rest[len..];
+    }
+}
+
+/// Field formatter that stores span fields as a JSON object, for `JsonFormatter`
+pub struct JsonFields;
+
+impl<'writer> FormatFields<'writer> for JsonFields {
+    fn format_fields<R: RecordFields>(&self, mut writer: Writer<'writer>, fields: R) -> std::fmt::Result {
+        let mut visitor = JsonVisitor::default();
+        fields.record(&mut visitor);
+        let json = serde_json::to_string(&visitor.fields).map_err(|_| std::fmt::Error)?;
+        write!(writer, "{}", json)
+    }
+    
+    fn add_fields(&self, current: &'writer mut FormattedFields<Self>, fields: &Record<'_>) -> std::fmt::Result {
+        // Appending would break the object, so merge into the stored fields instead
+        let mut visitor = JsonVisitor::default();
+        if !current.fields.is_empty() {
+            visitor.fields = serde_json::from_str(&current.fields).map_err(|_| std::fmt::Error)?;
+        }
+        fields.record(&mut visitor);
+        current.fields = serde_json::to_string(&visitor.fields).map_err(|_| std::fmt::Error)?;
+        Ok(())
+    }
+}
+
+/// Collects event or span fields as JSON values, keeping `message` apart
+#[derive(Default)]
+struct JsonVisitor {
+    message: Option<String>,
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl JsonVisitor {
+    fn insert(&mut self, field: &Field, value: serde_json::Value) {
+        self.fields.insert(field.name().to_string(), value);
+    }
+}
+
+impl Visit for JsonVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        let value = format!("{:?}", value);
+        if field.name() == "message" {
+            self.message = Some(value);
+        } else {
+            self.insert(field, value.into());
+        }
+    }
+    
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = Some(value.to_string());
+        } else {
+            self.insert(field, value.into());
+        }
+    }
+    
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+    
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+    
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value.into());
+    }
+    
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+}
+
+/// One line of `JsonFormatter` output
+#[derive(Serialize)]
+struct JsonLine<'a> {
+    timestamp: String,
+    level: &'a str,
+    target: &'a str,
+    message: String,
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    fields: serde_json::Map<String, serde_json::Value>,
+    file: Option<&'a str>,
+    line: Option<u32>,
+    /// Span context from the root span inward
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    spans: Vec<JsonSpan>,
+}
+
+#[derive(Serialize)]
+struct JsonSpan {
+    name: &'static str,
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Formatter that writes each event as one JSON object per line, with timestamp,
+/// level, target, message, other fields, file, line, and span context.
+/// Span fields are only available when the layer uses `JsonFields`.
+pub struct JsonFormatter;
+
+impl<S, N> FormatEvent<S, N> for JsonFormatter
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &tracing::Event<'_>,
+    ) -> std::fmt::Result {
+        let metadata = event.metadata();
+        let mut visitor = JsonVisitor::default();
+        event.record(&mut visitor);
+        
+        let mut spans = Vec::new();
+        if let Some(scope) = ctx.event_scope() {
+            for span in scope.from_root() {
+                let ext = span.extensions();
+                let fields = ext.get::<FormattedFields<N>>()
+                    .and_then(|fields| serde_json::from_str(&fields.fields).ok())
+                    .unwrap_or_default();
+                spans.push(JsonSpan { name: span.name(), fields });
+            }
+        }
         
-        writeln!(writer)
+        let line = JsonLine {
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+            level: metadata.level().as_str(),
+            target: metadata.target(),
+            message: visitor.message.unwrap_or_default(),
+            fields: visitor.fields,
+            file: metadata.file(),
+            line: metadata.line(),
+            spans,
+        };
+        let json = serde_json::to_string(&line).map_err(|_| std::fmt::Error)?;
+        writeln!(writer, "{}", json)
     }
 }
 
+/// Most targets tracked by `VerbosityCheckLayer`; past this, the quietest is evicted
+const MAX_TRACKED_TARGETS: usize = 256;
+
+/// Targets listed in the verbosity report
+const REPORTED_TARGETS: usize = 10;
+
 /// A tracing Layer that counts log events by level to detect excessive verbosity
 #[derive(Debug, Clone)]
 pub struct VerbosityCheckLayer {
@@ -152,19 +506,28 @@ pub struct VerbosityCheckLayer {
     info_count: Arc<AtomicUsize>,
     debug_count: Arc<AtomicUsize>,
     trace_count: Arc<AtomicUsize>,
-    configured_level: Level,
+    /// Counts per event target (module path), bounded by `MAX_TRACKED_TARGETS`
+    target_counts: Arc<Mutex<HashMap<String, LogCounts>>>,
+    /// Counting window set by `with_window`, shared by all clones
+    window: Arc<Mutex<Option<Window>>>,
+    /// Whether the live warning was emitted since the last reset
+    live_warning_sent: Arc<AtomicBool>,
+    /// Level that selects the threshold; shared so `LoggingHandle` can update it
+    configured_level: Arc<Mutex<Level>>,
     config: Config,
 }
 
 impl VerbosityCheckLayer {
-    /// Create a new VerbosityCheckLayer with default config
+    /// Create a new VerbosityCheckLayer with the default thresholds
     pub fn new() -> Self {
-        Self::with_config(Config::load().unwrap_or_default())
+        Self::with_config(Config::default())
     }
     
     /// Create a new VerbosityCheckLayer with custom config
+    ///
+    /// The level selecting the threshold comes from RUST_LOG, or INFO; the init
+    /// functions replace it with the level of the filter they install.
     pub fn with_config(config: Config) -> Self {
-        // Detect the configured log level from RUST_LOG or default to INFO
         let configured_level = Self::detect_configured_level();
         
         Self {
@@ -173,35 +536,87 @@ impl VerbosityCheckLayer {
             info_count: Arc::new(AtomicUsize::new(0)),
             debug_count: Arc::new(AtomicUsize::new(0)),
             trace_count: Arc::new(AtomicUsize::new(0)),
-            configured_level,
+            target_counts: Arc::new(Mutex::new(HashMap::new())),
+            window: Arc::new(Mutex::new(None)),
+            live_warning_sent: Arc::new(AtomicBool::new(false)),
+            configured_level: Arc::new(Mutex::new(configured_level)),
             config,
         }
     }
     
-    /// Detect the configured log level from environment
+    /// Set the level that selects the verbosity threshold, for embedders
+    /// that build their own filter
+    pub fn with_level(self, level: Level) -> Self {
+        self.set_configured_level(level);
+        self
+    }
+    
+    /// Detect the configured log level from the RUST_LOG filter
     fn detect_configured_level() -> Level {
-        if let Ok(rust_log) = std::env::var("RUST_LOG") {
-            let lower = rust_log.to_lowercase();
-            if lower.contains("trace") {
-                Level::TRACE
-            } else if lower.contains("debug") {
-                Level::DEBUG
-            } else if lower.contains("info") {
-                Level::INFO
-            } else if lower.contains("warn") {
-                Level::WARN
-            } else if lower.contains("error") {
-                Level::ERROR
-            } else {
-                Level::INFO // Default
+        match EnvFilter::try_from_default_env() {
+            Ok(filter) => level_of_filter(&filter),
+            Err(_) => Level::INFO, // Default when RUST_LOG not set or invalid
+        }
+    }
+    
+    /// Level that selects the verbosity threshold
+    pub fn configured_level(&self) -> Level {
+        *self.configured_level.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    fn set_configured_level(&self, level: Level) {
+        *self.configured_level.lock().unwrap_or_else(|e| e.into_inner()) = level;
+    }
+    
+    /// Count in windows of `length`: counters reset when a window ends, so
+    /// `check_verbosity()` and `check_and_report()` judge the current window only.
+    ///
+    /// The window state is shared with every clone, so this also applies to the
+    /// layer already installed by `init_logging`:
+    /// `init_logging(None, &config, None, None).verbosity.with_window(Duration::from_secs(60))`.
+    pub fn with_window(self, length: Duration) -> Self {
+        // Start from a clean window
+        self.reset();
+        *self.window.lock().unwrap_or_else(|e| e.into_inner()) = Some(Window { length, start: Instant::now() });
+        self
+    }
+    
+    /// Zero all counters, per level and per target
+    pub fn reset(&self) {
+        self.snapshot_and_reset();
+    }
+    
+    /// Return the counts so far and zero all counters, e.g. for periodic reporting
+    /// in a long-running process. Per-target counts are cleared too.
+    pub fn snapshot_and_reset(&self) -> LogCounts {
+        self.target_counts.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        self.live_warning_sent.store(false, Ordering::Relaxed);
+        LogCounts {
+            error: self.error_count.swap(0, Ordering::Relaxed),
+            warn: self.warn_count.swap(0, Ordering::Relaxed),
+            info: self.info_count.swap(0, Ordering::Relaxed),
+            debug: self.debug_count.swap(0, Ordering::Relaxed),
+            trace: self.trace_count.swap(0, Ordering::Relaxed),
+        }
+    }
+    
+    /// Reset the counters if the current window ended by `now`; windows are
+    /// aligned to the start of the first one, skipping any that saw no events
+    fn roll_window(&self, now: Instant) {
+        let mut window = self.window.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(window) = window.as_mut() {
+            let elapsed = now.saturating_duration_since(window.start);
+            if elapsed >= window.length {
+                let windows = elapsed.as_nanos() / window.length.as_nanos().max(1);
+                window.start += window.length.mul_f64(windows as f64);
+                self.reset();
             }
-        } else {
-            Level::INFO // Default when RUST_LOG not set
         }
     }
     
     /// Get the total count of all log events
     pub fn total_count(&self) -> usize {
+        self.roll_window(Instant::now());
         self.error_count.load(Ordering::Relaxed)
             + self.warn_count.load(Ordering::Relaxed)
             + self.info_count.load(Ordering::Relaxed)
@@ -211,6 +626,7 @@ impl VerbosityCheckLayer {
     
     /// Get counts broken down by level
     pub fn counts_by_level(&self) -> LogCounts {
+        self.roll_window(Instant::now());
         LogCounts {
             error: self.error_count.load(Ordering::Relaxed),
             warn: self.warn_count.load(Ordering::Relaxed),
@@ -220,11 +636,26 @@ impl VerbosityCheckLayer {
         }
     }
     
+    /// Get counts per target, noisiest first
+    ///
+    /// Only the `MAX_TRACKED_TARGETS` targets still tracked are listed; when a new
+    /// target arrives with the map full, the target with the fewest events is dropped.
+    pub fn counts_by_target(&self) -> Vec<TargetCounts> {
+        self.roll_window(Instant::now());
+        let map = self.target_counts.lock().unwrap_or_else(|e| e.into_inner());
+        let mut targets: Vec<TargetCounts> = map.iter()
+            .map(|(target, counts)| TargetCounts { target: target.clone(), counts: counts.clone() })
+            .collect();
+        targets.sort_by(|a, b| b.total().cmp(&a.total()).then_with(|| a.target.cmp(&b.target)));
+        targets
+    }
+    
     /// Check if verbosity exceeds recommended thresholds
     pub fn check_verbosity(&self) -> Option<VerbosityWarning> {
         // Only check thresholds for INFO, DEBUG, and TRACE levels
         // WARN and ERROR levels should never trigger verbosity warnings
-        let threshold = match self.configured_level {
+        let configured_level = self.configured_level();
+        let threshold = match configured_level {
             Level::TRACE => Some(self.config.verbosity.trace_threshold),
             Level::DEBUG => Some(self.config.verbosity.debug_threshold),
             Level::INFO => Some(self.config.verbosity.info_threshold),
@@ -237,8 +668,9 @@ impl VerbosityCheckLayer {
                 Some(VerbosityWarning {
                     total_count: total,
                     threshold: threshold_value,
-                    configured_level: self.configured_level,
+                    configured_level,
                     counts: self.counts_by_level(),
+                    top_targets: self.counts_by_target().into_iter().take(REPORTED_TARGETS).collect(),
                 })
             } else {
                 None
@@ -249,6 +681,11 @@ impl VerbosityCheckLayer {
     }
     
     /// Check verbosity and generate a formatted report if threshold exceeded
+    ///
+    /// Counts accumulate for the life of the layer, so in a long-running process the
+    /// report fires once and then keeps repeating stale totals. With `with_window` it
+    /// covers the current window only and can fire again in a later window; otherwise
+    /// call `reset()` after acting on a report to measure from that point.
     pub fn check_and_report(&self) -> Option<String> {
         self.check_verbosity().map(|warning| {
             format!(
@@ -261,6 +698,7 @@ impl VerbosityCheckLayer {
                   INFO:  {}\n\
                   DEBUG: {}\n\
                   TRACE: {}\n\n\
+                Noisiest targets:\n{}\n\
                 Consider reducing log verbosity to improve performance and readability.\n\
                 Recommended thresholds:\n\
                   • INFO:  <50 total logs\n\
@@ -274,6 +712,9 @@ impl VerbosityCheckLayer {
                 warning.counts.info,
                 warning.counts.debug,
                 warning.counts.trace,
+                warning.top_targets.iter()
+                    .map(|target| format!("  {}\n", target))
+                    .collect::<String>(),
             )
         })
     }
@@ -289,8 +730,9 @@ impl<S> Layer<S> for VerbosityCheckLayer
 where
     S: Subscriber,
 {
-    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
         let level = event.metadata().level();
+        self.roll_window(Instant::now());
         
         match *level {
             Level::ERROR => self.error_count.fetch_add(1, Ordering::Relaxed),
@@ -299,11 +741,105 @@ where
             Level::DEBUG => self.debug_count.fetch_add(1, Ordering::Relaxed),
             Level::TRACE => self.trace_count.fetch_add(1, Ordering::Relaxed),
         };
+        
+        {
+            let mut map = self.target_counts.lock().unwrap_or_else(|e| e.into_inner());
+            record_target(&mut map, event.metadata().target(), level, MAX_TRACKED_TARGETS);
+        }
+        
+        if self.config.verbosity.emit_live_warning && !self.live_warning_sent.load(Ordering::Relaxed) {
+            if let Some(warning) = self.check_verbosity() {
+                if !self.live_warning_sent.swap(true, Ordering::Relaxed) {
+                    emit_live_warning(&warning, &ctx);
+                }
+            }
+        }
+    }
+}
+
+/// Log `warning` as a single WARN event
+///
+/// The event goes to the layers below this one through `ctx` rather than through
+/// the dispatcher, so it never reaches `on_event` here: it is not counted and cannot
+/// trigger itself. Layers stacked above the `VerbosityCheckLayer` do not see it,
+/// which is why the init functions add it last.
+fn emit_live_warning<S: Subscriber>(warning: &VerbosityWarning, ctx: &Context<'_, S>) {
+    LIVE_WARNING_CALLSITE.register();
+    let counts = &warning.counts;
+    let mut message = format!(
+        "Log verbosity threshold exceeded: {} events (threshold: {} for {} level); \
+         ERROR {}, WARN {}, INFO {}, DEBUG {}, TRACE {}",
+        warning.total_count, warning.threshold, warning.configured_level,
+        counts.error, counts.warn, counts.info, counts.debug, counts.trace,
+    );
+    if let Some(noisiest) = warning.top_targets.first() {
+        message.push_str(&format!("; noisiest target {}", noisiest));
+    }
+    
+    let fields = LIVE_WARNING_METADATA.fields();
+    let Some(field) = fields.field("message") else { return };
+    let values = [(&field, Some(&message as &dyn tracing::Value))];
+    ctx.event(&Event::new(&LIVE_WARNING_METADATA, &fields.value_set(&values)));
+}
+
+/// Count an event for `target`, evicting the quietest target if `max_targets` are tracked
+fn record_target(map: &mut HashMap<String, LogCounts>, target: &str, level: &Level, max_targets: usize) {
+    if !map.contains_key(target) && map.len() >= max_targets {
+        let quietest = map.iter()
+            .min_by(|a, b| a.1.total().cmp(&b.1.total()).then_with(|| b.0.cmp(a.0)))
+            .map(|(target, _)| target.clone());
+        if let Some(quietest) = quietest {
+            map.remove(&quietest);
+        }
+    }
+    let counts = map.entry(target.to_string()).or_default();
+    match *level {
+        Level::ERROR => counts.error += 1,
+        Level::WARN => counts.warn += 1,
+        Level::INFO => counts.info += 1,
+        Level::DEBUG => counts.debug += 1,
+        Level::TRACE => counts.trace += 1,
     }
 }
 
+static LIVE_WARNING_CALLSITE: DefaultCallsite = DefaultCallsite::new(&LIVE_WARNING_METADATA);
+
+static LIVE_WARNING_METADATA: Metadata<'static> = Metadata::new(
+    "verbosity warning",
+    "autodebugger::verbosity",
+    Level::WARN,
+    Some(file!()),
+    Some(line!()),
+    Some(module_path!()),
+    FieldSet::new(&["message"], Identifier(&LIVE_WARNING_CALLSITE)),
+    Kind::EVENT,
+);
+
+/// Effective level of `filter` for choosing a verbosity threshold
+///
+/// This is the level of the directive without a target, which governs most
+/// events: "warn,tokio=trace" is WARN and "my_crate=trace,info" is INFO. With only
+/// target directives it is the most verbose of them. "off" counts as ERROR, which
+/// has no threshold.
+fn level_of_filter(filter: &EnvFilter) -> Level {
+    let directives = filter.to_string();
+    let global = directives.split(',')
+        .filter_map(|directive| directive.trim().parse::<LevelFilter>().ok())
+        .max();
+    global.or_else(|| filter.max_level_hint())
+        .and_then(LevelFilter::into_level)
+        .unwrap_or(Level::ERROR)
+}
+
+/// Current counting window of a `VerbosityCheckLayer`
+#[derive(Debug, Clone, Copy)]
+struct Window {
+    length: Duration,
+    start: Instant,
+}
+
 /// Breakdown of log counts by level
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct LogCounts {
     pub error: usize,
     pub warn: usize,
@@ -312,6 +848,44 @@ pub struct LogCounts {
     pub trace: usize,
 }
 
+impl LogCounts {
+    fn total(&self) -> usize {
+        self.error + self.warn + self.info + self.debug + self.trace
+    }
+}
+
+/// Log counts for one event target (module path)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetCounts {
+    pub target: String,
+    pub counts: LogCounts,
+}
+
+impl TargetCounts {
+    /// Events from this target at any level
+    pub fn total(&self) -> usize {
+        self.counts.total()
+    }
+}
+
+impl std::fmt::Display for TargetCounts {
+    /// "target: total (INFO n, DEBUG m)", listing only levels with events
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let levels = [
+            ("ERROR", self.counts.error),
+            ("WARN", self.counts.warn),
+            ("INFO", self.counts.info),
+            ("DEBUG", self.counts.debug),
+            ("TRACE", self.counts.trace),
+        ];
+        let breakdown: Vec<String> = levels.iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(level, count)| format!("{} {}", level, count))
+            .collect();
+        write!(f, "{}: {} ({})", self.target, self.total(), breakdown.join(", "))
+    }
+}
+
 /// Warning information when verbosity threshold is exceeded
 #[derive(Debug, Clone)]
 pub struct VerbosityWarning {
@@ -319,188 +893,837 @@ pub struct VerbosityWarning {
     pub threshold: usize,
     pub configured_level: Level,
     pub counts: LogCounts,
+    /// Noisiest targets, at most 10
+    pub top_targets: Vec<TargetCounts>,
+}
+
+/// Handle returned by the init functions for adjusting logging at runtime
+///
+/// Derefs to the installed `VerbosityCheckLayer`, so `check_and_report()` and the
+/// other counters are available directly.
+#[derive(Clone)]
+pub struct LoggingHandle {
+    pub verbosity: VerbosityCheckLayer,
+    /// Installed when `logging.span_timing.enabled` is set
+    pub span_timing: Option<SpanTimingLayer>,
+    filter: reload::Handle<EnvFilter, Registry>,
+    /// `logging.suppress` directives kept in every reloaded filter
+    suppress: Vec<String>,
+    /// Writer thread of a non-blocking log file, stopped when the last handle drops
+    file_guard: Option<Arc<RotatingFileGuard>>,
+}
+
+impl LoggingHandle {
+    /// Replace the filter with a single level ("error" through "trace") for all targets
+    pub fn set_level(&self, level: &str) -> Result<()> {
+        let parsed: Level = level.parse()
+            .map_err(|_| anyhow::anyhow!("Unknown log level '{}' (expected error, warn, info, debug, or trace)", level))?;
+        let filter = filter_with_directives(&parsed.as_str().to_lowercase(), &self.suppress)?;
+        self.filter.reload(filter)
+            .context("Failed to reload log filter")?;
+        self.verbosity.set_configured_level(parsed);
+        Ok(())
+    }
+    
+    /// Replace the filter with `RUST_LOG`-style directives, e.g. "info,autodebugger::ci=debug"
+    pub fn set_directives(&self, directives: &str) -> Result<()> {
+        let filter = filter_with_directives(directives, &self.suppress)?;
+        let level = level_of_filter(&filter);
+        self.filter.reload(filter)
+            .context("Failed to reload log filter")?;
+        self.verbosity.set_configured_level(level);
+        Ok(())
+    }
+    
+    /// Directives of the current filter
+    pub fn directives(&self) -> Option<String> {
+        self.filter.with_current(|filter| filter.to_string()).ok()
+    }
+    
+    /// Span timing report to print at shutdown next to `check_and_report()`, if
+    /// span timing is enabled and any span has closed
+    pub fn span_report(&self) -> Option<String> {
+        self.span_timing.as_ref().and_then(SpanTimingLayer::format_report)
+    }
+    
+    /// Lines the non-blocking log file writer dropped because its buffer was full
+    pub fn dropped_log_lines(&self) -> u64 {
+        self.file_guard.as_ref().map_or(0, |guard| guard.dropped_lines())
+    }
+    
+    /// Wait for lines queued for a non-blocking log file to be written; call before
+    /// `std::process::exit`, which skips the flush when the handle drops
+    pub fn flush(&self) {
+        if let Some(guard) = &self.file_guard {
+            guard.flush();
+        }
+    }
+}
+
+impl std::ops::Deref for LoggingHandle {
+    type Target = VerbosityCheckLayer;
+    
+    fn deref(&self) -> &VerbosityCheckLayer {
+        &self.verbosity
+    }
+}
+
+/// Create a base env filter from RUST_LOG or the default level, plus per-crate
+/// `extra_directives` such as "sqlx=warn" (`logging.suppress` in the init functions)
+///
+/// The extra directives come first, so a directive in RUST_LOG for the same crate wins.
+pub fn create_base_env_filter(default_level: &str, extra_directives: &[String]) -> Result<EnvFilter> {
+    let base = std::env::var(EnvFilter::DEFAULT_ENV).ok()
+        .filter(|rust_log| !rust_log.trim().is_empty() && EnvFilter::try_new(rust_log).is_ok())
+        .unwrap_or_else(|| default_level.to_string());
+    filter_with_directives(&base, extra_directives)
+}
+
+/// Filter for `base` directives preceded by `extra_directives`, naming any invalid entry
+fn filter_with_directives(base: &str, extra_directives: &[String]) -> Result<EnvFilter> {
+    for directive in extra_directives {
+        directive.parse::<Directive>()
+            .with_context(|| format!("Invalid log filter directive '{}'", directive))?;
+    }
+    let directives: Vec<&str> = extra_directives.iter()
+        .map(String::as_str)
+        .chain(std::iter::once(base))
+        .filter(|directive| !directive.trim().is_empty())
+        .collect();
+    EnvFilter::try_new(directives.join(","))
+        .with_context(|| format!("Invalid log filter directives '{}'", base))
+}
+
+/// Filter for the init functions; a bad `suppress` entry is reported and the list ignored
+fn init_filter(default_level: &str, suppress: &[String]) -> EnvFilter {
+    create_base_env_filter(default_level, suppress)
+        .or_else(|e| {
+            eprintln!("Ignoring logging.suppress: {:#}", e);
+            create_base_env_filter(default_level, &[])
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("{:#}", e);
+            EnvFilter::new(default_level)
+        })
+}
+
+/// Boxed layer so console and file layers of either format share one type
+type BoxedLayer<S> = Box<dyn Layer<S> + Send + Sync>;
+
+/// Build a fmt layer writing `format` to `writer`; file layers never use ANSI colors
+/// and may write pretty lines' fields as JSON (`json_fields`)
+fn fmt_layer<S, W>(writer: W, format: LogFormat, file: bool, json_fields: bool) -> BoxedLayer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer().with_writer(writer);
+    let layer = if file { layer.with_ansi(false) } else { layer };
+    match format {
+        LogFormat::Pretty => layer
+            .fmt_fields(KeyValueFields)
+            .event_format(ConditionalLocationFormatter::new().with_json_fields(file && json_fields))
+            .boxed(),
+        LogFormat::Json => layer.fmt_fields(JsonFields).event_format(JsonFormatter).boxed(),
+    }
+}
+
+/// Set by `init_logging_for_mcp`: stdout carries a protocol, not reports
+static STDOUT_RESERVED: AtomicBool = AtomicBool::new(false);
+
+/// Keep reports off stdout (`report_output()` returns stderr), as
+/// `init_logging_for_mcp` does
+pub fn reserve_stdout() {
+    STDOUT_RESERVED.store(true, Ordering::Relaxed);
+}
+
+/// Where human-readable reports go: stdout, or stderr once stdout is reserved
+pub fn report_output() -> Box<dyn std::io::Write + Send> {
+    if STDOUT_RESERVED.load(Ordering::Relaxed) {
+        Box::new(std::io::stderr())
+    } else {
+        Box::new(std::io::stdout())
+    }
+}
+
+/// Console writer for the `output` argument of the init functions
+fn console_writer(output: Option<&str>) -> BoxMakeWriter {
+    // Default to stdout for normal operation, but allow override to stderr
+    // This is critical for MCP servers which must keep stdout clean for JSON-RPC messages
+    match output {
+        Some("stderr") => BoxMakeWriter::new(std::io::stderr),
+        _ => BoxMakeWriter::new(std::io::stdout),
+    }
+}
+
+/// Console and rotating file layers; the console layer is left out unless
+/// `console_output` is set, for file-only logging
+fn file_logging_layers<S>(
+    console: BoxMakeWriter,
+    console_format: LogFormat,
+    file_writer: BoxMakeWriter,
+    file_format: LogFormat,
+    file_json_fields: bool,
+    console_output: bool,
+) -> Vec<BoxedLayer<S>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let mut layers = Vec::new();
+    if console_output {
+        layers.push(fmt_layer(console, console_format, false, false));
+    }
+    layers.push(fmt_layer(file_writer, file_format, true, file_json_fields));
+    layers
+}
+
+/// Log panics as ERROR events before the previous panic hook runs; see
+/// `install_panic_hook_with_flush`
+pub fn install_panic_hook() {
+    install_panic_hook_with_flush(|| {});
+}
+
+/// Log each panic as an ERROR event (target `autodebugger::panic`) with its
+/// message, location, thread, and a backtrace, call `flush` so the event reaches
+/// buffered writers such as a rotating log file, then run the previous panic hook
+///
+/// The hook must not panic itself, as that aborts the process: a panic raised
+/// while the hook is already logging on the same thread skips straight to the
+/// previous hook, and once the subscriber is gone the event is simply dropped.
+pub fn install_panic_hook_with_flush<F>(flush: F)
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    thread_local! {
+        static IN_PANIC_HOOK: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+    }
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if !IN_PANIC_HOOK.with(|in_hook| in_hook.replace(true)) {
+            let message = info.payload().downcast_ref::<&str>().copied()
+                .or_else(|| info.payload().downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("Box<dyn Any>");
+            let location = info.location()
+                .map(|location| format!("{}:{}", location.file(), location.line()))
+                .unwrap_or_default();
+            let thread = std::thread::current();
+            tracing::error!(
+                target: "autodebugger::panic",
+                location = %location,
+                thread = thread.name().unwrap_or("<unnamed>"),
+                backtrace = %std::backtrace::Backtrace::force_capture(),
+                "Panic: {}", message
+            );
+            flush();
+            IN_PANIC_HOOK.with(|in_hook| in_hook.set(false));
+        }
+        previous(info);
+    }));
 }
 
-/// Create a base env filter from environment or default level
-pub fn create_base_env_filter(default_level: &str) -> EnvFilter {
-    EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new(default_level))
+/// Span timing layer for `logging.span_timing`, if enabled
+fn span_timing_layer(logging: &crate::config::LoggingConfig) -> Option<SpanTimingLayer> {
+    logging.span_timing.enabled.then(|| SpanTimingLayer::with_config(&logging.span_timing))
 }
 
 /// Initialize the tracing subscriber with custom formatting and verbosity checking
-/// Returns a handle to the VerbosityCheckLayer for later checking
+/// Returns a `LoggingHandle` for checking verbosity and changing the level at runtime
 /// 
 /// # Arguments
 /// * `default_level` - Optional default log level (e.g., "info", "warn"). If None, defaults to "info"
-/// * `verbosity_config` - Optional custom verbosity thresholds. If None, uses autodebugger's config.yaml
+/// * `config` - Verbosity thresholds, `logging.suppress`, `logging.span_timing`, and format defaults
 /// * `output` - Optional output destination ("stdout" or "stderr"). If None, defaults to stdout.
-///              Note: When using as an MCP server, must be set to "stderr" to keep stdout clean for JSON-RPC.
+///   Note: When using as an MCP server, must be set to "stderr" to keep stdout clean for JSON-RPC.
+/// * `format` - Optional console format. If None, uses `config.logging.format`
 pub fn init_logging(
     default_level: Option<&str>, 
-    verbosity_config: Option<crate::config::VerbosityConfig>,
-    output: Option<&str>
-) -> VerbosityCheckLayer {
+    config: &Config,
+    output: Option<&str>,
+    format: Option<LogFormat>
+) -> LoggingHandle {
     let default = default_level.unwrap_or("info");
-    let env_filter = create_base_env_filter(default);
-    
-    // Create verbosity layer with custom config if provided
-    let verbosity_layer = match verbosity_config {
-        Some(config) => {
-            // Build a Config struct with the provided verbosity
-            let mut full_config = Config::default();
-            full_config.verbosity = config;
-            VerbosityCheckLayer::with_config(full_config)
-        },
-        None => VerbosityCheckLayer::new(),  // Use autodebugger's config
-    };
+    let logging = &config.logging;
+    let env_filter = init_filter(default, &logging.suppress);
+    let level = level_of_filter(&env_filter);
+    let (env_filter, filter) = reload::Layer::new(env_filter);
+    let format = format.unwrap_or(logging.format);
+    
+    let verbosity_layer = VerbosityCheckLayer::with_config(config.clone()).with_level(level);
     let verbosity_clone = verbosity_layer.clone();
+    let span_timing = span_timing_layer(logging);
     
-    // Determine output destination
-    // Default to stdout for normal operation, but allow override to stderr
-    // This is critical for MCP servers which must keep stdout clean for JSON-RPC messages
-    match output {
-        Some("stderr") => {
-            tracing_subscriber::registry()
-                .with(env_filter)
-                .with(tracing_subscriber::fmt::layer()
-                    .with_writer(std::io::stderr)
-                    .event_format(ConditionalLocationFormatter))
-                .with(verbosity_layer)
-                .init();
-        },
-        _ => {
-            tracing_subscriber::registry()
-                .with(env_filter)
-                .with(tracing_subscriber::fmt::layer()
-                    .with_writer(std::io::stdout)
-                    .event_format(ConditionalLocationFormatter))
-                .with(verbosity_layer)
-                .init();
-        }
-    }
+    // Span timing last, so its slow span warnings reach the layers below
+    tracing_subscriber::registry()
+        .with(env_filter)
+        .with(fmt_layer(console_writer(output), format, false, false))
+        .with(verbosity_layer)
+        .with(span_timing.clone())
+        .init();
     
-    verbosity_clone
+    LoggingHandle { verbosity: verbosity_clone, span_timing, filter, suppress: logging.suppress.clone(), file_guard: None }
 }
 
 /// Initialize the tracing subscriber with both console and file output
-/// Returns a handle to the VerbosityCheckLayer for later checking
+/// Returns a `LoggingHandle` for checking verbosity and changing the level at runtime
 /// 
 /// # Arguments
 /// * `default_level` - Optional default log level (e.g., "info", "warn"). If None, defaults to "info"
-/// * `verbosity_config` - Optional custom verbosity thresholds. If None, uses autodebugger's config.yaml
+/// * `config` - Verbosity thresholds, `logging.suppress`, `logging.span_timing`, and format defaults
 /// * `output` - Optional output destination ("stdout" or "stderr"). If None, defaults to stdout.
-///              Note: When using as an MCP server, must be set to "stderr" to keep stdout clean for JSON-RPC.
-/// * `file_config` - Rotating file logging configuration.
+///   Note: When using as an MCP server, must be set to "stderr" to keep stdout clean for JSON-RPC.
+/// * `format` - Optional console format. If None, uses `config.logging.format`
+/// * `file_config` - Rotating file logging configuration. Its `format` sets the file format
+///   independently; if None, `config.logging.file_format`, else the console format.
+///   With `enabled: false` no file is created and this is `init_logging`.
+///   With `non_blocking` the file is written on a background thread owned by the returned
+///   handle: keep a clone alive until shutdown, as dropping the last one stops it.
+///   With `log_panics` (default) `install_panic_hook_with_flush` is called so panics
+///   reach the file.
 pub fn init_logging_with_file(
     default_level: Option<&str>, 
-    verbosity_config: Option<crate::config::VerbosityConfig>,
+    config: &Config,
     output: Option<&str>,
+    format: Option<LogFormat>,
     file_config: RotatingFileConfig
-) -> VerbosityCheckLayer {
+) -> LoggingHandle {
+    if !file_config.enabled {
+        return init_logging(default_level, config, output, format);
+    }
     let default = default_level.unwrap_or("info");
-    let env_filter = create_base_env_filter(default);
-    
-    // Clone verbosity config for potential fallback use
-    let verbosity_config_clone = verbosity_config.clone();
-    
-    // Create verbosity layer with custom config if provided
-    let verbosity_layer = match verbosity_config {
-        Some(config) => {
-            // Build a Config struct with the provided verbosity
-            let mut full_config = Config::default();
-            full_config.verbosity = config;
-            VerbosityCheckLayer::with_config(full_config)
-        },
-        None => VerbosityCheckLayer::new(),  // Use autodebugger's config
-    };
+    let logging = &config.logging;
+    let env_filter = init_filter(default, &logging.suppress);
+    let level = level_of_filter(&env_filter);
+    let (env_filter, filter) = reload::Layer::new(env_filter);
+    
+    let console_format = format.unwrap_or(logging.format);
+    let file_format = file_config.format
+        .or(logging.file_format)
+        .unwrap_or(console_format);
+    
+    let verbosity_layer = VerbosityCheckLayer::with_config(config.clone()).with_level(level);
     let verbosity_clone = verbosity_layer.clone();
+    let span_timing = span_timing_layer(logging);
     
-    // Store console_output flag before moving file_config
+    // Store flags before moving file_config
     let console_output = file_config.console_output;
+    let non_blocking = file_config.non_blocking;
+    let log_panics = file_config.log_panics;
+    let json_fields = file_config.json_fields;
     
-    // Try to create rotating file writer
-    match RotatingWriterWrapper::new(file_config) {
-        Ok(file_writer) => {
-            // Build logging configuration based on console_output flag
-            if console_output {
-                // Dual logging: console + file
-                match output {
-                    Some("stderr") => {
-                        // stderr + file
-                        tracing_subscriber::registry()
-                            .with(env_filter)
-                            .with(tracing_subscriber::fmt::layer()
-                                .with_writer(std::io::stderr)
-                                .event_format(ConditionalLocationFormatter))
-                            .with(tracing_subscriber::fmt::layer()
-                                .with_writer(file_writer)
-                                .with_ansi(false)
-                                .event_format(ConditionalLocationFormatter))
-                            .with(verbosity_layer)
-                            .init();
-                    },
-                    _ => {
-                        // stdout + file  
-                        tracing_subscriber::registry()
-                            .with(env_filter)
-                            .with(tracing_subscriber::fmt::layer()
-                                .with_writer(std::io::stdout)
-                                .event_format(ConditionalLocationFormatter))
-                            .with(tracing_subscriber::fmt::layer()
-                                .with_writer(file_writer)
-                                .with_ansi(false)
-                                .event_format(ConditionalLocationFormatter))
-                            .with(verbosity_layer)
-                            .init();
+    // Try to create rotating file writer, on its own thread in non-blocking mode
+    let header_filter = filter.clone();
+    let file_writer = RotatingWriterWrapper::new(file_config).and_then(|wrapper| {
+        // The file header is written in the file's format and shows the live filter
+        let wrapper = wrapper
+            .with_format(file_format)
+            .with_filter(move || header_filter.with_current(|filter| filter.to_string()).ok());
+        let startup_writer = wrapper.clone();
+        if non_blocking {
+            let (writer, guard) = wrapper.non_blocking()?;
+            Ok((BoxMakeWriter::new(writer), startup_writer, Some(Arc::new(guard))))
+        } else {
+            Ok((BoxMakeWriter::new(wrapper), startup_writer, None))
+        }
+    });
+    let file_guard = match file_writer {
+        Ok((file_writer, startup_writer, file_guard)) => {
+            let layers = file_logging_layers(
+                console_writer(output), console_format, file_writer, file_format, json_fields, console_output,
+            );
+            tracing_subscriber::registry()
+                .with(env_filter)
+                .with(layers)
+                .with(verbosity_layer)
+                .with(span_timing.clone())
+                .init();
+            
+            // Old runs deleted before the subscriber existed
+            let pruned = startup_writer.take_pruned();
+            if !pruned.is_empty() {
+                tracing::debug!("Pruned {} old log file(s): {}", pruned.len(), pruned.join(", "));
+            }
+            
+            if log_panics {
+                // A weak reference, so the hook doesn't keep the writer thread alive
+                let worker = file_guard.as_ref().map(Arc::downgrade);
+                install_panic_hook_with_flush(move || {
+                    if let Some(guard) = worker.as_ref().and_then(std::sync::Weak::upgrade) {
+                        guard.flush();
                     }
-                }
-            } else {
-                // File only - no console output
-                tracing_subscriber::registry()
-                    .with(env_filter)
-                    .with(tracing_subscriber::fmt::layer()
-                        .with_writer(file_writer)
-                        .with_ansi(false)
-                        .event_format(ConditionalLocationFormatter))
-                    .with(verbosity_layer)
-                    .init();
+                    let _ = std::io::Write::flush(&mut startup_writer.make_writer());
+                });
             }
+            file_guard
         },
         Err(e) => {
             eprintln!("Failed to initialize file logging: {}", e);
             // Fall back to console-only logging (reuse original function)
-            return init_logging(default_level, verbosity_config_clone, output);
+            return init_logging(default_level, config, output, Some(console_format));
         }
-    }
+    };
     
-    verbosity_clone
+    LoggingHandle { verbosity: verbosity_clone, span_timing, filter, suppress: logging.suppress.clone(), file_guard }
+}
+
+/// Logging preset for MCP servers, whose stdout carries JSON-RPC
+///
+/// Console logs go to stderr, a rotating log file (if `file_config` is given) is
+/// written as JSON whatever its `format`, and `reserve_stdout()` sends reports
+/// printed through `report_output()` to stderr as well.
+///
+/// # Arguments
+/// * `default_level` - Optional default log level. If None, defaults to "info"
+/// * `config` - As for `init_logging`
+/// * `file_config` - Optional rotating file logging; None logs to stderr only
+pub fn init_logging_for_mcp(default_level: Option<&str>, config: &Config, file_config: Option<RotatingFileConfig>) -> LoggingHandle {
+    reserve_stdout();
+    match file_config {
+        Some(file_config) => {
+            let file_config = RotatingFileConfig { format: Some(LogFormat::Json), ..file_config };
+            init_logging_with_file(default_level, config, Some("stderr"), None, file_config)
+        }
+        None => init_logging(default_level, config, Some("stderr"), None),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::Buffer;
     
     #[test]
     fn test_verbosity_check_layer() {
-        let layer = VerbosityCheckLayer::new();
-        let layer_clone = layer.clone();
+        let logs = crate::testing::capture_logs(|| {
+            tracing::error!("Test error");
+            tracing::warn!("Test warning");
+            tracing::info!("Test info");
+            tracing::trace!("Test trace");
+        });
+        
+        assert_eq!(logs.counts, LogCounts { error: 1, warn: 1, info: 1, debug: 0, trace: 1 });
+        assert_eq!(logs.lines.len(), 4);
+    }
+    
+    #[test]
+    fn test_conditional_location_formatter() {
+        let logs = crate::testing::capture_logs(|| {
+            tracing::info!("Plain info");
+            tracing::debug!(files = 3, "Scanned");
+            tracing::warn!("Disk nearly full");
+            let span = tracing::info_span!("check", worktree = "feature-x");
+            let _enter = span.enter();
+            tracing::error!("Build failed");
+        });
+        
+        // INFO has no prefix; DEBUG and TRACE get the level only
+        assert_eq!(logs.lines[0], "Plain info");
+        assert_eq!(logs.lines[1], "DEBUG: Scanned files=3");
+        // WARN and ERROR add the target and file:line
+        let location = format!("{} src/tracing_subscriber.rs:", module_path!());
+        assert!(logs.lines[2].starts_with(&format!("WARN {}", location)), "{}", logs.lines[2]);
+        assert!(logs.lines[2].ends_with(": Disk nearly full"), "{}", logs.lines[2]);
+        assert!(logs.lines[3].starts_with(&format!("ERROR {}", location)), "{}", logs.lines[3]);
+        assert!(logs.lines[3].ends_with(": check{worktree=feature-x} Build failed"), "{}", logs.lines[3]);
+    }
+    
+    #[test]
+    fn test_key_value_fields() {
+        let logs = crate::testing::capture_logs(|| {
+            tracing::info!("No fields");
+            tracing::info!(zone = "eu west", user_id = 42, path = %"/tmp/a b", point = ?(1, 2),
+                name = ?"bob", ok = true, empty = "", "Signed in");
+            tracing::info!(count = 3);
+            let span = tracing::info_span!("request", id = 7, user = tracing::field::Empty);
+            let _enter = span.enter();
+            span.record("user", "ann lee");
+            span.record("id", 8);
+            tracing::info!("Handled");
+        });
+        
+        assert_eq!(logs.lines, vec![
+            "No fields",
+            // Sorted by name; quoted when empty or containing spaces
+            "Signed in empty=\"\" name=\"bob\" ok=true path=\"/tmp/a b\" point=\"(1, 2)\" user_id=42 zone=\"eu west\"",
+            "count=3",
+            // Recorded span fields replace or join the earlier ones, still sorted
+            "request{id=8 user=\"ann lee\"} Handled",
+        ]);
+        
+        let pairs = parse_key_value_pairs("a=1 b=\"x \\\" y\" c=Some(\"z\")");
+        assert_eq!(key_value_pairs(&pairs), "a=1 b=\"x \\\" y\" c=Some(\"z\")");
+    }
+    
+    #[test]
+    fn test_json_fields_for_file_layer() {
+        let output = Buffer::default();
+        let writer = output.clone();
+        let subscriber = tracing_subscriber::registry()
+            .with(fmt_layer(move || writer.clone(), LogFormat::Pretty, true, true));
+        
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(user_id = 42, zone = "eu west", "Signed in");
+            tracing::info!("No fields");
+            tracing::info_span!("request", id = 7).in_scope(|| tracing::info!(ok = true));
+        });
+        
+        assert_eq!(output.contents(), "Signed in {\"user_id\":42,\"zone\":\"eu west\"}\n\
+            No fields\n\
+            request{id=7} {\"ok\":true}\n");
+    }
+    
+    #[test]
+    fn test_json_format_captures_event_and_spans() {
+        let pretty = Buffer::default();
+        let json = Buffer::default();
+        let (pretty_writer, json_writer) = (pretty.clone(), json.clone());
+        let subscriber = tracing_subscriber::registry()
+            .with(fmt_layer(move || pretty_writer.clone(), LogFormat::Pretty, true, false))
+            .with(fmt_layer(move || json_writer.clone(), LogFormat::Json, true, false));
+        
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("scan", worktree = "feature-x", files = tracing::field::Empty);
+            let _enter = span.enter();
+            span.record("files", 3u64);
+            tracing::warn!(count = 2, cached = true, "Found {} problems", 2);
+        });
+        
+        let line = json.contents();
+        assert_eq!(line.lines().count(), 1);
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["level"], "WARN");
+        assert_eq!(value["target"], module_path!());
+        assert_eq!(value["message"], "Found 2 problems");
+        assert_eq!(value["fields"], serde_json::json!({"count": 2, "cached": true}));
+        assert!(value["file"].as_str().unwrap().ends_with("tracing_subscriber.rs"));
+        assert!(value["line"].as_u64().unwrap() > 0);
+        assert_eq!(value["spans"], serde_json::json!([
+            {"name": "scan", "fields": {"worktree": "feature-x", "files": 3}}
+        ]));
+        assert!(chrono::DateTime::parse_from_rfc3339(value["timestamp"].as_str().unwrap()).is_ok());
+        
+        // The pretty layer alongside is unaffected
+        let pretty = pretty.contents();
+        assert!(pretty.starts_with("WARN "), "{}", pretty);
+        assert!(pretty.contains("scan{files=3 worktree=feature-x} Found 2 problems cached=true count=2"), "{}", pretty);
+    }
+    
+    #[test]
+    fn test_logging_config_formats() {
+        let config: Config = serde_yaml::from_str("logging:\n  file_format: json\n").unwrap();
+        assert_eq!(config.logging.format, LogFormat::Pretty);
+        assert_eq!(config.logging.file_format(), LogFormat::Json);
+        assert_eq!(Config::default().logging.file_format(), LogFormat::Pretty);
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+    
+    #[test]
+    fn test_counts_by_target() {
+        let layer = VerbosityCheckLayer::with_config(Config {
+            verbosity: crate::config::VerbosityConfig { info_threshold: 3, debug_threshold: 3, trace_threshold: 3, emit_live_warning: false },
+            ..Config::default()
+        });
+        let subscriber = tracing_subscriber::registry().with(layer.clone());
+        
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..3 {
+                tracing::info!(target: "app::scan", "Scanning");
+            }
+            tracing::warn!(target: "app::scan", "Slow scan");
+            tracing::error!(target: "app::db", "Lost connection");
+        });
+        
+        let targets = layer.counts_by_target();
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].target, "app::scan");
+        assert_eq!(targets[0].counts, LogCounts { warn: 1, info: 3, ..LogCounts::default() });
+        assert_eq!(targets[0].to_string(), "app::scan: 4 (WARN 1, INFO 3)");
+        assert_eq!(targets[1].to_string(), "app::db: 1 (ERROR 1)");
+        
+        if let Some(report) = layer.check_and_report() {
+            assert!(report.contains("Noisiest targets:\n  app::scan: 4 (WARN 1, INFO 3)\n  app::db: 1 (ERROR 1)\n"), "{}", report);
+        }
+    }
+    
+    #[test]
+    fn test_record_target_evicts_quietest() {
+        let mut map = HashMap::new();
+        record_target(&mut map, "a", &Level::INFO, 2);
+        record_target(&mut map, "a", &Level::INFO, 2);
+        record_target(&mut map, "b", &Level::INFO, 2);
+        record_target(&mut map, "c", &Level::DEBUG, 2);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["a"].info, 2);
+        assert_eq!(map["c"].debug, 1);
+        assert!(!map.contains_key("b"));
+    }
+    
+    #[test]
+    fn test_snapshot_and_reset() {
+        let layer = VerbosityCheckLayer::with_config(Config::default());
+        let subscriber = tracing_subscriber::registry().with(layer.clone());
+        
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("First");
+            tracing::warn!("Second");
+            let snapshot = layer.snapshot_and_reset();
+            assert_eq!(snapshot, LogCounts { warn: 1, info: 1, ..LogCounts::default() });
+            assert_eq!(layer.total_count(), 0);
+            assert!(layer.counts_by_target().is_empty());
+            
+            tracing::error!("Third");
+            assert_eq!(layer.counts_by_level(), LogCounts { error: 1, ..LogCounts::default() });
+            layer.reset();
+            assert_eq!(layer.total_count(), 0);
+        });
+    }
+    
+    #[test]
+    fn test_windowed_counts() {
+        let layer = VerbosityCheckLayer::with_config(Config {
+            verbosity: crate::config::VerbosityConfig { info_threshold: 2, debug_threshold: 2, trace_threshold: 2, emit_live_warning: false },
+            ..Config::default()
+        }).with_window(Duration::from_secs(3600));
+        let subscriber = tracing_subscriber::registry().with(layer.clone());
+        let start = layer.window.lock().unwrap().unwrap().start;
+        
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..3 {
+                tracing::info!("Busy");
+            }
+            assert_eq!(layer.counts_by_level().info, 3);
+            
+            // Two and a half windows later the counts start over, aligned to the first window
+            layer.roll_window(start + Duration::from_secs(9000));
+            assert_eq!(layer.window.lock().unwrap().unwrap().start, start + Duration::from_secs(7200));
+            assert_eq!(layer.total_count(), 0);
+            assert!(layer.check_verbosity().is_none());
+            
+            tracing::info!("Quiet");
+            assert_eq!(layer.counts_by_level().info, 1);
+            
+            // Within the same window nothing resets
+            layer.roll_window(start + Duration::from_secs(9100));
+            assert_eq!(layer.total_count(), 1);
+        });
+    }
+    
+    #[test]
+    fn test_live_warning_emitted_once() {
+        let layer = VerbosityCheckLayer::with_config(Config {
+            verbosity: crate::config::VerbosityConfig { info_threshold: 3, debug_threshold: 3, trace_threshold: 3, emit_live_warning: true },
+            ..Config::default()
+        }).with_level(Level::INFO);
+        let output = Buffer::default();
+        let writer = output.clone();
+        let subscriber = tracing_subscriber::registry()
+            .with(fmt_layer(move || writer.clone(), LogFormat::Pretty, true, false))
+            .with(layer.clone());
         
-        tracing_subscriber::registry()
-            .with(layer)
-            .init();
+        tracing::subscriber::with_default(subscriber, || {
+            for i in 0..3 {
+                tracing::info!(target: "app::scan", "Event {}", i);
+            }
+            assert!(!output.contents().contains("threshold exceeded"));
+            
+            // N+1 events cross the threshold; later events do not warn again
+            for i in 3..10 {
+                tracing::info!(target: "app::scan", "Event {}", i);
+            }
+            let contents = output.contents();
+            let warnings: Vec<&str> = contents.lines().filter(|line| line.contains("threshold exceeded")).collect();
+            assert_eq!(warnings.len(), 1, "{}", contents);
+            assert!(warnings[0].starts_with("WARN autodebugger::verbosity"), "{}", warnings[0]);
+            assert!(warnings[0].contains("Log verbosity threshold exceeded: 4 events (threshold: 3 for INFO level); \
+                ERROR 0, WARN 0, INFO 4, DEBUG 0, TRACE 0; noisiest target app::scan: 4 (INFO 4)"), "{}", warnings[0]);
+            // The warning itself is not counted
+            assert_eq!(layer.total_count(), 10);
+            
+            // A reset allows one more warning at the next crossing
+            layer.reset();
+            for i in 0..5 {
+                tracing::info!(target: "app::scan", "Event {}", i);
+            }
+            assert_eq!(output.contents().matches("threshold exceeded").count(), 2);
+        });
+    }
+    
+    #[test]
+    fn test_logging_handle_changes_level() {
+        let layer = VerbosityCheckLayer::with_config(Config::default());
+        let (env_filter, filter) = reload::Layer::new(EnvFilter::new("info"));
+        let handle = LoggingHandle { verbosity: layer.clone(), span_timing: None, filter, suppress: Vec::new(), file_guard: None };
+        let subscriber = tracing_subscriber::registry()
+            .with(env_filter)
+            .with(layer);
         
-        // Generate some log events
-        tracing::error!("Test error");
-        tracing::warn!("Test warning");
-        tracing::info!("Test info");
-        tracing::trace!("Test trace");
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!("Hidden");
+            assert_eq!(handle.counts_by_level().debug, 0);
+            
+            handle.set_level("debug").unwrap();
+            assert_eq!(handle.configured_level(), Level::DEBUG);
+            assert_eq!(handle.directives().as_deref(), Some("debug"));
+            tracing::debug!("Recorded");
+            assert_eq!(handle.counts_by_level().debug, 1);
+            
+            handle.set_directives("warn").unwrap();
+            assert_eq!(handle.configured_level(), Level::WARN);
+            tracing::info!("Hidden");
+            tracing::debug!("Hidden");
+            assert_eq!(handle.counts_by_level(), LogCounts { debug: 1, ..LogCounts::default() });
+            
+            assert!(handle.set_level("loud").is_err());
+            assert!(handle.set_directives("app=nope").is_err());
+            assert_eq!(handle.directives().as_deref(), Some("warn"));
+        });
+    }
+    
+    #[test]
+    fn test_level_of_mixed_directives() {
+        let level = |directives: &str| level_of_filter(&EnvFilter::new(directives));
+        assert_eq!(level("my_crate=trace,info"), Level::INFO);
+        assert_eq!(level("warn,tokio=trace"), Level::WARN);
+        assert_eq!(level("debug"), Level::DEBUG);
+        assert_eq!(level("info,[scan{worktree=a,b}]=trace"), Level::INFO);
+        assert_eq!(level("my_crate=debug,other=warn"), Level::DEBUG);
+        assert_eq!(level("off"), Level::ERROR);
+    }
+    
+    #[test]
+    fn test_default_level_selects_threshold() {
+        // Without RUST_LOG the default level passed to init_logging decides
+        if std::env::var("RUST_LOG").is_err() {
+            assert_eq!(level_of_filter(&create_base_env_filter("warn", &[]).unwrap()), Level::WARN);
+        }
+        let layer = VerbosityCheckLayer::with_config(Config {
+            verbosity: crate::config::VerbosityConfig { info_threshold: 0, debug_threshold: 0, trace_threshold: 0, emit_live_warning: false },
+            ..Config::default()
+        }).with_level(Level::WARN);
+        let subscriber = tracing_subscriber::registry().with(layer.clone());
+        tracing::subscriber::with_default(subscriber, || tracing::error!("Counted"));
+        assert_eq!(layer.configured_level(), Level::WARN);
+        assert!(layer.check_verbosity().is_none());
+        assert!(layer.with_level(Level::INFO).check_verbosity().is_some());
+    }
+    
+    #[test]
+    fn test_threshold_per_level() {
+        let layer = VerbosityCheckLayer::with_config(Config {
+            verbosity: crate::config::VerbosityConfig { info_threshold: 1, debug_threshold: 2, trace_threshold: 3, emit_live_warning: false },
+            ..Config::default()
+        });
+        let subscriber = tracing_subscriber::registry().with(layer.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..10 {
+                tracing::error!("Counted");
+            }
+        });
+        
+        let threshold = |level| layer.clone().with_level(level).check_verbosity().map(|warning| warning.threshold);
+        assert_eq!(threshold(Level::TRACE), Some(3));
+        assert_eq!(threshold(Level::DEBUG), Some(2));
+        assert_eq!(threshold(Level::INFO), Some(1));
+        // WARN and ERROR have no threshold: there is no such thing as too many warnings
+        assert_eq!(threshold(Level::WARN), None);
+        assert_eq!(threshold(Level::ERROR), None);
+    }
+    
+    #[test]
+    fn test_console_output_disabled_omits_console_layer() {
+        let dir = tempfile::tempdir().unwrap();
+        let yaml = format!(
+            "logging:\n  file:\n    log_directory: {}\n    console_output: false\n  file_format: json\n",
+            dir.path().display()
+        );
+        let config: Config = serde_yaml::from_str(&yaml).unwrap();
+        let file_config = config.logging.file.clone().unwrap();
+        assert!(!file_config.console_output);
+        
+        let console = Buffer::default();
+        let probe = console.clone();
+        let layers = file_logging_layers(
+            BoxMakeWriter::new(move || probe.clone()),
+            config.logging.format,
+            BoxMakeWriter::new(RotatingWriterWrapper::new(file_config.clone()).unwrap()),
+            config.logging.file_format(),
+            file_config.json_fields,
+            file_config.console_output,
+        );
+        assert_eq!(layers.len(), 1);
+        
+        let subscriber = tracing_subscriber::registry().with(layers);
+        tracing::subscriber::with_default(subscriber, || tracing::info!("Only in the file"));
+        
+        assert_eq!(console.contents(), "");
+        let logged = std::fs::read_to_string(dir.path().join("app_latest.log")).unwrap();
+        let line: serde_json::Value = serde_json::from_str(logged.lines().last().unwrap()).unwrap();
+        assert_eq!(line["message"], "Only in the file");
+    }
+    
+    #[test]
+    fn test_suppress_directives() {
+        let suppress = vec!["sqlx=warn".to_string(), "tower=info".to_string()];
+        let env_filter = filter_with_directives("debug", &suppress).unwrap();
+        assert_eq!(level_of_filter(&env_filter), Level::DEBUG);
+        
+        let layer = VerbosityCheckLayer::with_config(Config::default());
+        let (env_filter, filter) = reload::Layer::new(env_filter);
+        let handle = LoggingHandle { verbosity: layer.clone(), span_timing: None, filter, suppress, file_guard: None };
+        let subscriber = tracing_subscriber::registry().with(env_filter).with(layer);
         
-        let counts = layer_clone.counts_by_level();
-        assert_eq!(counts.error, 1);
-        assert_eq!(counts.warn, 1);
-        assert_eq!(counts.info, 1);
-        // Debug and trace might not be captured depending on default filter
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!(target: "sqlx::query", "Suppressed");
+            tracing::warn!(target: "sqlx::query", "Kept");
+            tracing::debug!(target: "app", "Kept");
+            assert_eq!(handle.counts_by_level(), LogCounts { warn: 1, debug: 1, ..LogCounts::default() });
+            
+            // Suppression survives a level change
+            handle.set_level("trace").unwrap();
+            tracing::info!(target: "sqlx::query", "Suppressed");
+            tracing::trace!(target: "tower::buffer", "Suppressed");
+            tracing::trace!(target: "app", "Kept");
+            assert_eq!(handle.counts_by_level(), LogCounts { warn: 1, debug: 1, trace: 1, ..LogCounts::default() });
+        });
         
-        assert!(layer_clone.total_count() >= 3);
+        let err = create_base_env_filter("info", &["sqlx=warn".to_string(), "tower=loud".to_string()]).unwrap_err();
+        assert!(format!("{:#}", err).starts_with("Invalid log filter directive 'tower=loud'"), "{:#}", err);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_panic_hook_logs_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_config = RotatingFileConfig {
+            log_directory: dir.path().display().to_string(),
+            write_header: false,
+            ..RotatingFileConfig::default()
+        };
+        let writer = RotatingWriterWrapper::new(file_config).unwrap();
+        let flushed = Arc::new(AtomicBool::new(false));
+        {
+            let (writer, flushed) = (writer.clone(), flushed.clone());
+            install_panic_hook_with_flush(move || {
+                let _ = std::io::Write::flush(&mut writer.make_writer());
+                flushed.store(true, Ordering::SeqCst);
+            });
+        }
+        
+        let dispatch = tracing::Dispatch::new(
+            tracing_subscriber::registry().with(fmt_layer(writer.clone(), LogFormat::Pretty, true, false)),
+        );
+        let result = std::thread::Builder::new()
+            .name("doomed".to_string())
+            .spawn(move || tracing::dispatcher::with_default(&dispatch, || panic!("boom in worker")))
+            .unwrap()
+            .join();
+        assert!(result.is_err());
+        
+        let logged = std::fs::read_to_string(dir.path().join("app_latest.log")).unwrap();
+        assert!(logged.starts_with("ERROR autodebugger::panic "), "{}", logged);
+        assert!(logged.contains("Panic: boom in worker"), "{}", logged);
+        assert!(logged.contains("thread=doomed"), "{}", logged);
+        assert!(logged.contains("backtrace="), "{}", logged);
+        assert!(flushed.load(Ordering::SeqCst));
+    }
+}
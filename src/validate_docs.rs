@@ -16,12 +16,18 @@
 //!
 //! The validator applies different standards based on module complexity:
 //!
-//! ### Complex Modules (>200 lines by default)
+//! Complexity is measured in code lines: lines with something besides
+//! whitespace, comments (`//`, `/* */`, and doc comments), so a long license header
+//! or generous blank lines do not make a module "complex". Comment markers
+//! inside string and char literals are not taken for comments. Set
+//! `use_raw_line_count: true` to compare the raw line count instead.
+//!
+//! ### Complex Modules (>200 code lines by default)
 //! - **Minimum Documentation**: 50 lines of module-level docs
 //! - **Rationale**: Large modules contain significant logic requiring explanation
 //! - **Expected Content**: Architecture overview, design decisions, usage examples
 //!
-//! ### Simple Modules (<200 lines)
+//! ### Simple Modules (<200 code lines)
 //! - **No Minimum**: Documentation encouraged but not required
 //! - **Rationale**: Simple modules are often self-explanatory
 //! - **Best Practice**: Still add brief module docs explaining purpose
@@ -41,9 +47,54 @@
 //!
 //! Note: This validator does NOT process:
 //! - Regular comments (`//` or `/* */`)
-//! - Item-level documentation (`///`)
 //! - Documentation in other formats
 //!
+//! ### Per-Path Overrides
+//!
+//! `overrides` entries replace any of the three thresholds for files matching a
+//! glob, e.g. a stricter `min_doc_lines_complex` for `src/core/**`. The first
+//! matching entry wins; thresholds it leaves out keep their global values.
+//!
+//! ### Public Item Documentation (opt-in)
+//!
+//! With `require_pub_item_docs`, every `pub fn`, `pub struct`, `pub enum`, and
+//! `pub trait` (at any nesting, including methods in `impl` blocks) needs a `///`
+//! or `/** */` comment or a `#[doc = ...]` attribute above it; attributes and blank
+//! lines may sit in between. `pub(crate)` and other restricted items are exempt,
+//! as are names matching `pub_item_allowlist` globs (default `new`, `default`).
+//! Items are found line by line, so an item whose `pub` keyword is not at the
+//! start of a line is not checked.
+//!
+//! ### Stale References (opt-in)
+//!
+//! With `check_references`, symbols named in `//!` docs must still exist: inline
+//! code spans that look like a symbol (a path, `name()`, `name!`, or a snake case
+//! or camel case name), and calls, macros, and paths in Rust code blocks (fenced
+//! with `rust` or no language), are looked up in an index of every identifier in
+//! the code of the scanned files (doc comments excluded). Method calls, names an example defines
+//! itself, prelude names, and the leading crate or module segment of a path are
+//! not checked. Symbols matching `reference_allowlist` globs (default `std::*`,
+//! `core::*`, `alloc::*`) are external and accepted. Each missing symbol is
+//! reported once per file as `StaleReference` at its first line.
+//!
+//! ### Config Snippets (opt-in)
+//!
+//! With `check_config_snippets`, every ```` ```yaml ```` block in `//!` docs is
+//! deserialized as a `config.yaml` (`crate::config::Config`). Keys serde does not
+//! know, and values of the wrong type, are reported as `InvalidConfigSnippet` with
+//! the dotted key and the line it is on, so documented settings cannot drift from
+//! the real ones. Snippets must start from the top-level section (e.g.
+//! `validate_docs:`).
+//!
+//! ### Placeholder Docs
+//!
+//! A `//!` line containing a placeholder marker as a word (`TODO`, `FIXME`, and
+//! `XXX` by default) is not counted toward the minimum, so "TODO: document this"
+//! does not pass for documentation (`count_placeholder_lines: true` counts it). When
+//! more than `max_placeholder_fraction` of the doc block is placeholders, the
+//! module gets a `PlaceholderDocs` issue listing their lines, which the verbose
+//! summary prints.
+//!
 //! ## Configuration
 //!
 //! All thresholds are configurable via `config.yaml`:
@@ -53,9 +104,88 @@
 //!   min_doc_lines_complex: 50
 //!   max_doc_lines: 200
 //!   complexity_threshold: 200
+//!   use_raw_line_count: false
 //!   ignore_patterns: ["**/tests/**", "**/examples/**"]
+//!   require_pub_item_docs: false
+//!   pub_item_allowlist: ["new", "default"]
+//!   overrides:
+//!     - pattern: "src/core/**"
+//!       min_doc_lines_complex: 80
+//!     - pattern: "src/bin/**"
+//!       complexity_threshold: 400
+//!   check_references: false
+//!   reference_allowlist: ["std::*", "core::*", "alloc::*"]
+//!   check_config_snippets: false
 //! ```
 //!
+//! ## Autofix
+//!
+//! With `with_fix(true)` (CLI `--fix`), a complex module without a single `//!`
+//! line gets a starter doc block from `doc_template`, with `{module_name}` and
+//! `{line_count}` filled in and each line prefixed with `//!`. The block goes
+//! after a byte order mark, a shebang, and leading `//` comments (such as a
+//! license header), and above `#![...]` attributes and code. The file is then
+//! validated as rewritten, so a short template still counts as insufficient.
+//! Files with any `//!` line are never touched. With `with_dry_run(true)` the
+//! insertions are only reported (`ValidationReport::fixes`).
+//!
+//! ## Baseline
+//!
+//! To adopt the validator on an existing codebase, `--write-baseline` records the
+//! current issues (file path, rule id, and item name for item docs) in
+//! `.autodebugger/docs-baseline.json`. Later runs drop baselined issues from the
+//! report and count them in `ValidationReport::suppressed`, so strict mode fails
+//! only on new issues. Rewriting the baseline replaces the entries of every file
+//! validated in that run, which prunes issues that were fixed.
+//!
+//! ## Incremental Validation
+//!
+//! `with_changed_since(base_ref)` (CLI `--changed-since [REF]`, default `HEAD`)
+//! validates only files changed since the merge base of `base_ref` and HEAD:
+//! committed, staged, or unstaged changes plus untracked files. Deleted files and
+//! files outside the given paths are not checked, and ignore patterns still
+//! apply. The report records the ref and how many files were skipped as unchanged.
+//!
+//! ## Severities
+//!
+//! Every rule has a `Severity`, `warning` unless `severities` in the config says
+//! otherwise (keys are the rule names in snake case, e.g. `no_docs`; an unknown
+//! name fails config loading with the list of valid ones). Errors always fail the
+//! run, warnings fail it only in strict mode, and info issues are printed but
+//! never fail it. The summary groups issues by severity, and SARIF and GitHub
+//! output use the matching level.
+//!
+//! ## Coverage
+//!
+//! `ValidationReport::coverage` gives one trend metric: the percentage of complex
+//! modules with at least their minimum `//!` lines (100 when there are none), and,
+//! when item docs are checked, the percentage of public items with docs. The
+//! summary prints it, JSON output includes it as `coverage`, and the CLI's
+//! `--min-coverage` fails below a threshold whether or not strict mode is on.
+//! Issues suppressed by the baseline still count as undocumented.
+//!
+//! ## Summary Layout
+//!
+//! `render_summary` lists flagged modules by severity and, within a severity, by
+//! how far they are from compliance: doc lines missing or over the maximum, plus
+//! one per item-level issue. `SummaryOptions` can cut the list to the `top` N
+//! worst (with a count of the rest) or group it by directory with module and
+//! issue subtotals; neither changes what is validated or the exit code.
+//!
+//! ## Parallel Runs
+//!
+//! Files are checked on `with_jobs(n)` worker threads (CLI `--jobs`, 0 = one per
+//! CPU) and added to the report in path order, so the output does not depend on
+//! scheduling. A file that cannot be read (e.g. not UTF-8) or rewritten by
+//! `--fix` is reported as an `UnreadableFile` issue instead of ending the run.
+//! `with_cancel_flag` takes an `AtomicBool`; once it is set, files not yet started
+//! are skipped and the report is returned with `cancelled: true`. The CLI sets it
+//! on Ctrl-C, prints the partial report, and exits with code 130.
+//!
+//! `validate_file` checks a single file with the validator as built (ignore
+//! patterns, thresholds, baseline), for `validate-docs --watch` to re-check files
+//! as they are saved; it has no symbol index, so references are not checked.
+//!
 //! ## Usage Examples
 //!
 //! ### Command Line
@@ -95,7 +225,9 @@
 //! The validator is designed for CI/CD integration:
 //! - Exit code 0 on success (or warnings in non-strict mode)
 //! - Exit code 1 on failure (warnings in strict mode)
-//! - Machine-readable output for parsing
+//! - Machine-readable output for parsing: the report serializes to JSON, and
+//!   `ValidationReport::to_sarif` gives a SARIF 2.1.0 log that code review tools
+//!   show as annotations (one result per issue, at the item's line or line 1)
 //! - Configurable via environment-specific config files
 //!
 //! ## Best Practices
@@ -107,11 +239,36 @@
 //! 5. **Update Regularly**: Keep docs in sync with code changes
 
 use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
 use std::fs;
-use std::path::{Path, PathBuf};
-use tracing::warn;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::{error, info, warn};
 use walkdir::WalkDir;
 use glob::Pattern;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::config::ValidateDocsConfig;
+use crate::monitor::cache::CACHE_DIR;
+use crate::remove_debug::git_output;
+
+/// Name of the baseline file under `.autodebugger`
+pub const BASELINE_FILE: &str = "docs-baseline.json";
+
+/// Names used without a path or import in doc examples
+const PRELUDE: &[&str] = &[
+    "Some", "None", "Ok", "Err", "Option", "Result", "String", "Vec", "Box", "Self", "self", "super", "crate",
+    "Default", "Clone", "From", "Into", "Iterator", "ToString", "assert", "assert_eq", "assert_ne", "concat",
+    "env", "eprintln", "format", "matches", "panic", "print", "println", "todo", "unimplemented", "vec", "write",
+    "writeln",
+];
+
+/// Keywords that introduce a name an example defines itself
+const DEFINING_KEYWORDS: &[&str] = &["fn", "let", "mut", "struct", "enum", "mod", "trait", "type", "const", "static", "for", "as"];
 
 /// Documentation validator for Rust source files
 pub struct DocValidator {
@@ -121,12 +278,52 @@ pub struct DocValidator {
     pub max_doc_lines: usize,
     /// Line count threshold to consider a module "complex"
     pub complexity_threshold: usize,
+    /// Compare all lines, not just code lines, against `complexity_threshold`
+    pub use_raw_line_count: bool,
     /// Glob patterns to ignore
     pub ignore_patterns: Vec<Pattern>,
     /// Whether to show verbose output
     pub verbose: bool,
     /// Whether to treat warnings as errors
     pub strict: bool,
+    /// Whether public functions, structs, enums, and traits need `///` docs
+    pub require_pub_item_docs: bool,
+    /// Glob patterns of item names exempt from `require_pub_item_docs`
+    pub pub_item_allowlist: Vec<Pattern>,
+    /// Issues accepted as pre-existing; matching issues are suppressed
+    pub baseline: Option<DocsBaseline>,
+    /// Per-path thresholds, first match wins
+    pub overrides: Vec<(Pattern, ThresholdOverride)>,
+    /// Only validate files changed since the merge base with this ref
+    pub changed_since: Option<String>,
+    /// Insert `doc_template` into complex modules without any `//!` docs
+    pub fix: bool,
+    /// Report fixes without writing them
+    pub dry_run: bool,
+    /// Starter docs for `fix`, without the `//!` prefixes
+    pub doc_template: String,
+    /// Whether symbols named in `//!` docs must exist in the scanned source
+    pub check_references: bool,
+    /// Glob patterns of external symbols accepted by `check_references`
+    pub reference_allowlist: Vec<Pattern>,
+    /// Whether YAML blocks in `//!` docs must be valid `config.yaml` snippets
+    pub check_config_snippets: bool,
+    /// Files checked in parallel (0 = one per CPU)
+    pub jobs: usize,
+    /// Raised (e.g. on Ctrl-C) to stop starting new files
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Severity per rule; rules not listed are warnings
+    pub severities: HashMap<Rule, Severity>,
+    /// Count `//!` lines with a placeholder marker (e.g. TODO) as documentation
+    pub count_placeholder_lines: bool,
+    /// Share of the `//!` lines that may be placeholders before `PlaceholderDocs`
+    pub max_placeholder_fraction: f64,
+    /// Any of the placeholder markers as a word; None disables the check
+    placeholder_re: Option<Regex>,
+    /// A public item declaration at the start of a line: kind and name
+    pub_item_re: Regex,
+    /// An identifier or `::` path
+    ident_re: Regex,
 }
 
 impl DocValidator {
@@ -136,12 +333,55 @@ impl DocValidator {
             min_doc_lines_complex: 50,
             max_doc_lines: 200,
             complexity_threshold: 200,
+            use_raw_line_count: false,
             ignore_patterns: vec![],
             verbose: false,
             strict: false,
+            require_pub_item_docs: false,
+            pub_item_allowlist: vec![Pattern::new("new").unwrap(), Pattern::new("default").unwrap()],
+            baseline: None,
+            overrides: Vec::new(),
+            changed_since: None,
+            fix: false,
+            dry_run: false,
+            doc_template: default_doc_template(),
+            check_references: false,
+            check_config_snippets: false,
+            jobs: 0,
+            cancel: None,
+            severities: HashMap::new(),
+            count_placeholder_lines: false,
+            max_placeholder_fraction: default_max_placeholder_fraction(),
+            placeholder_re: placeholder_regex(&default_placeholder_markers()),
+            reference_allowlist: default_reference_allowlist().iter().map(|p| Pattern::new(p).unwrap()).collect(),
+            pub_item_re: Regex::new(r#"^\s*pub\s+(?:(?:const|async|unsafe|default|extern(?:\s+"[^"]*")?)\s+)*(fn|struct|enum|trait)\s+([A-Za-z_]\w*)"#)
+                .unwrap(),
+            ident_re: Regex::new(r"\b(?:[A-Za-z_]\w*::)*[A-Za-z_]\w*").unwrap(),
         }
     }
 
+    /// Create a validator with the settings of a `validate_docs` config section
+    pub fn from_config(config: ValidateDocsConfig) -> Result<Self> {
+        Ok(Self::new()
+            .with_min_doc_lines(config.min_doc_lines_complex)
+            .with_max_doc_lines(config.max_doc_lines)
+            .with_complexity_threshold(config.complexity_threshold)
+            .with_use_raw_line_count(config.use_raw_line_count)
+            .with_ignore_patterns(config.ignore_patterns)?
+            .with_require_pub_item_docs(config.require_pub_item_docs)
+            .with_pub_item_allowlist(config.pub_item_allowlist)?
+            .with_overrides(config.overrides)?
+            .with_doc_template(config.doc_template)
+            .with_check_references(config.check_references)
+            .with_reference_allowlist(config.reference_allowlist)?
+            .with_check_config_snippets(config.check_config_snippets)
+            .with_jobs(config.jobs)
+            .with_placeholder_markers(config.placeholder_markers)
+            .with_count_placeholder_lines(config.count_placeholder_lines)
+            .with_max_placeholder_fraction(config.max_placeholder_fraction)
+            .with_severities(config.severities))
+    }
+
     /// Set minimum documentation lines for complex modules
     pub fn with_min_doc_lines(mut self, lines: usize) -> Self {
         self.min_doc_lines_complex = lines;
@@ -160,6 +400,12 @@ impl DocValidator {
         self
     }
 
+    /// Measure complexity by raw line count, counting blanks and comments too
+    pub fn with_use_raw_line_count(mut self, raw: bool) -> Self {
+        self.use_raw_line_count = raw;
+        self
+    }
+
     /// Set ignore patterns
     pub fn with_ignore_patterns(mut self, patterns: Vec<String>) -> Result<Self> {
         let mut compiled_patterns = Vec::new();
@@ -184,25 +430,223 @@ impl DocValidator {
         self
     }
 
+    /// Require `///` docs on public functions, structs, enums, and traits
+    pub fn with_require_pub_item_docs(mut self, require: bool) -> Self {
+        self.require_pub_item_docs = require;
+        self
+    }
+
+    /// Set item-name patterns exempt from public item docs (e.g. `new`, `with_*`)
+    pub fn with_pub_item_allowlist(mut self, patterns: Vec<String>) -> Result<Self> {
+        self.pub_item_allowlist = patterns.iter()
+            .map(|pattern| Pattern::new(pattern).with_context(|| format!("Invalid item name pattern: {}", pattern)))
+            .collect::<Result<_>>()?;
+        Ok(self)
+    }
+
+    /// Set per-path threshold overrides; the first whose pattern matches a file applies
+    pub fn with_overrides(mut self, overrides: Vec<ThresholdOverride>) -> Result<Self> {
+        self.overrides = overrides.into_iter()
+            .map(|entry| {
+                let pattern = Pattern::new(&entry.pattern)
+                    .with_context(|| format!("Invalid override pattern: {}", entry.pattern))?;
+                Ok((pattern, entry))
+            })
+            .collect::<Result<_>>()?;
+        Ok(self)
+    }
+
+    /// Thresholds for `path`, and the override pattern that set them, if any
+    fn thresholds(&self, path: &Path) -> (usize, usize, usize, Option<&str>) {
+        let defaults = (self.min_doc_lines_complex, self.max_doc_lines, self.complexity_threshold);
+        let path = baseline_path(path);
+        match self.overrides.iter().find(|(pattern, _)| pattern.matches_path(&path)) {
+            Some((_, entry)) => (
+                entry.min_doc_lines_complex.unwrap_or(defaults.0),
+                entry.max_doc_lines.unwrap_or(defaults.1),
+                entry.complexity_threshold.unwrap_or(defaults.2),
+                Some(entry.pattern.as_str()),
+            ),
+            None => (defaults.0, defaults.1, defaults.2, None),
+        }
+    }
+
+    /// Only validate files changed since the merge base of `base_ref` and HEAD
+    ///
+    /// `HEAD` covers staged and unstaged changes; untracked files count as changed.
+    pub fn with_changed_since(mut self, base_ref: &str) -> Self {
+        self.changed_since = Some(base_ref.to_string());
+        self
+    }
+
+    /// Insert a starter doc block into complex modules that have none
+    pub fn with_fix(mut self, fix: bool) -> Self {
+        self.fix = fix;
+        self
+    }
+
+    /// Report fixes instead of writing them
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Set the starter docs used by `with_fix` (`{module_name}` and `{line_count}` are replaced)
+    pub fn with_doc_template(mut self, template: String) -> Self {
+        self.doc_template = template;
+        self
+    }
+
+    /// Check that symbols named in `//!` docs exist in the scanned source
+    pub fn with_check_references(mut self, check: bool) -> Self {
+        self.check_references = check;
+        self
+    }
+
+    /// Set glob patterns of external symbols (e.g. `tokio::*`) accepted by `with_check_references`
+    pub fn with_reference_allowlist(mut self, patterns: Vec<String>) -> Result<Self> {
+        self.reference_allowlist = patterns.iter()
+            .map(|pattern| Pattern::new(pattern).with_context(|| format!("Invalid reference pattern: {}", pattern)))
+            .collect::<Result<_>>()?;
+        Ok(self)
+    }
+
+    /// Check YAML blocks in `//!` docs against the `Config` schema
+    pub fn with_check_config_snippets(mut self, check: bool) -> Self {
+        self.check_config_snippets = check;
+        self
+    }
+
+    /// Set how many files are checked in parallel (0 = one per CPU)
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Stop validating when `cancel` is set and return the partial report
+    pub fn with_cancel_flag(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Set the severity of rules (the others stay warnings)
+    pub fn with_severities(mut self, severities: HashMap<Rule, Severity>) -> Self {
+        self.severities = severities;
+        self
+    }
+
+    /// Set the words that mark a `//!` line as a placeholder (none disables the check)
+    pub fn with_placeholder_markers(mut self, markers: Vec<String>) -> Self {
+        self.placeholder_re = placeholder_regex(&markers);
+        self
+    }
+
+    /// Count placeholder `//!` lines toward the documentation minimum
+    pub fn with_count_placeholder_lines(mut self, count: bool) -> Self {
+        self.count_placeholder_lines = count;
+        self
+    }
+
+    /// Set the share of `//!` lines (0.0 to 1.0) that may be placeholders
+    pub fn with_max_placeholder_fraction(mut self, fraction: f64) -> Self {
+        self.max_placeholder_fraction = fraction;
+        self
+    }
+
+    /// Suppress issues recorded in `baseline`
+    pub fn with_baseline(mut self, baseline: DocsBaseline) -> Self {
+        self.baseline = Some(baseline);
+        self
+    }
+
     /// Validate documentation for all Rust files in the given paths
+    ///
+    /// Files are checked in parallel (see `with_jobs`) and reported in path order.
+    /// A file that cannot be read or rewritten is reported as `UnreadableFile`.
+    /// When the cancel flag is raised, files not yet started are skipped and the
+    /// report covers what was checked so far (`ValidationReport::cancelled`).
     pub fn validate_paths(&self, paths: Vec<PathBuf>) -> Result<ValidationReport> {
-        let mut report = ValidationReport::default();
+        let mut report = ValidationReport { severities: self.severities.clone(), ..ValidationReport::default() };
+        let changed = match &self.changed_since {
+            Some(base_ref) => {
+                let dir = paths.first().map_or(Path::new("."), |path| {
+                    if path.is_dir() { path.as_path() } else { path.parent().unwrap_or(Path::new(".")) }
+                });
+                let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+                report.changed_since = Some(base_ref.clone());
+                Some(changed_files(dir, base_ref)?)
+            }
+            None => None,
+        };
 
-        for path in paths {
+        let mut files = Vec::new();
+        for path in &paths {
             if path.is_file() {
-                if self.should_process_file(&path) {
-                    self.validate_file(&path, &mut report)?;
+                if self.should_process_file(path) {
+                    files.push(path.clone());
                 }
             } else if path.is_dir() {
-                self.validate_directory(&path, &mut report)?;
+                files.extend(self.directory_files(path));
             } else {
-                anyhow::bail!("Path does not exist: {}", path.display());
+                return Err(crate::error::not_found(format_args!("Path does not exist: {}", path.display())));
+            }
+        }
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.jobs)
+            .build()
+            .context("Failed to start worker threads")?;
+        // Indexed before filtering, so unchanged files still define symbols
+        let symbols = self.check_references.then(|| pool.install(|| self.symbol_index(&files)));
+        if let Some(changed) = &changed {
+            let total = files.len();
+            files.retain(|path| changed.contains(&path.canonicalize().unwrap_or_else(|_| path.to_path_buf())));
+            report.unchanged_skipped = total - files.len();
+        }
+
+        let run = RunContext { symbols };
+        let checks: Vec<Option<Result<FileCheck>>> = pool.install(|| {
+            files.par_iter()
+                .map(|path| (!self.is_cancelled()).then(|| self.check_file(path, &run)))
+                .collect()
+        });
+        for (path, check) in files.iter().zip(checks) {
+            match check {
+                Some(Ok(check)) => self.record(&mut report, check),
+                Some(Err(e)) => self.record(&mut report, FileCheck::unreadable(path, &e)),
+                None => report.cancelled = true,
             }
         }
 
         Ok(report)
     }
 
+    /// Validate one file with this validator's ignore patterns, thresholds, and
+    /// baseline, for re-checking a file as it changes (`validate-docs --watch`)
+    ///
+    /// Without the rest of the tree there is no symbol index, so stale references
+    /// are not checked, and `changed_since` does not apply. A file the ignore
+    /// patterns exclude (see `accepts`) gives an empty report; one that cannot be
+    /// read is reported as `UnreadableFile`.
+    pub fn validate_file(&self, path: &Path) -> ValidationReport {
+        let mut report = ValidationReport { severities: self.severities.clone(), ..ValidationReport::default() };
+        if self.should_process_file(path) {
+            let check = self.check_file(path, &RunContext { symbols: None })
+                .unwrap_or_else(|e| FileCheck::unreadable(path, &e));
+            self.record(&mut report, check);
+        }
+        report
+    }
+
+    /// Whether `path` is a Rust file the ignore patterns do not exclude
+    pub fn accepts(&self, path: &Path) -> bool {
+        self.should_process_file(path)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
     /// Check if a file should be processed based on ignore patterns
     fn should_process_file(&self, path: &Path) -> bool {
         // Only process .rs files
@@ -221,39 +665,100 @@ impl DocValidator {
         true
     }
 
-    /// Validate all Rust files in a directory
-    fn validate_directory(&self, dir: &Path, report: &mut ValidationReport) -> Result<()> {
-        for entry in WalkDir::new(dir)
+    /// Rust files to validate under `dir`, sorted so the report does not depend on
+    /// directory order or thread scheduling
+    fn directory_files(&self, dir: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = WalkDir::new(dir)
             .into_iter()
             .filter_map(|e| e.ok())
-        {
-            let path = entry.path();
-            if path.is_file() && self.should_process_file(path) {
-                self.validate_file(path, report)?;
-            }
-        }
-        Ok(())
+            .map(|entry| entry.into_path())
+            .filter(|path| path.is_file() && self.should_process_file(path))
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// Every identifier in the code (not the doc comments) of `files`
+    fn symbol_index(&self, files: &[PathBuf]) -> HashSet<String> {
+        files.par_iter()
+            .map(|path| {
+                let mut symbols = HashSet::new();
+                if self.is_cancelled() {
+                    return symbols;
+                }
+                // Unreadable files are reported when they are validated
+                let Ok(content) = fs::read_to_string(path) else {
+                    return symbols;
+                };
+                for line in content.lines() {
+                    let trimmed = line.trim_start();
+                    if trimmed.starts_with("//!") || trimmed.starts_with("///") {
+                        continue;
+                    }
+                    for path in self.ident_re.find_iter(line) {
+                        symbols.extend(path.as_str().split("::").map(str::to_string));
+                    }
+                }
+                symbols
+            })
+            .reduce(HashSet::new, |mut all, symbols| {
+                all.extend(symbols);
+                all
+            })
     }
 
     /// Validate a single Rust file
-    fn validate_file(&self, path: &Path, report: &mut ValidationReport) -> Result<()> {
-        let content = fs::read_to_string(path)
+    fn check_file(&self, path: &Path, run: &RunContext) -> Result<FileCheck> {
+        let mut content = fs::read_to_string(path)
             .with_context(|| format!("Failed to read file: {}", path.display()))?;
 
-        let total_lines = content.lines().count();
-        let doc_lines = self.count_module_doc_lines(&content);
-
-        report.files_scanned += 1;
+        let mut total_lines = content.lines().count();
+        let code_lines = count_code_lines(&content);
+        let mut docs = self.module_docs(&content);
 
         // Determine if this is a complex module
-        let is_complex = total_lines > self.complexity_threshold;
+        let (min_doc_lines_complex, max_doc_lines, complexity_threshold, override_pattern) = self.thresholds(path);
+        let size = if self.use_raw_line_count { total_lines } else { code_lines };
+        let is_complex = size > complexity_threshold;
+
+        let mut fix = None;
+        if self.fix && is_complex && !content.lines().any(|line| line.trim_start().starts_with("//!")) {
+            let (line, text, fixed) = self.insert_doc_template(path, &content, total_lines);
+            if !self.dry_run {
+                fs::write(path, &fixed)
+                    .with_context(|| format!("Failed to write file: {}", path.display()))?;
+                content = fixed;
+                total_lines = content.lines().count();
+                docs = self.module_docs(&content);
+            }
+            fix = Some(DocFix { path: path.to_path_buf(), line, text, applied: !self.dry_run });
+        }
+
+        let pub_items: Vec<PubItem> = if self.require_pub_item_docs {
+            find_pub_items(&content, &self.pub_item_re).into_iter()
+                .filter(|item| !self.pub_item_allowlist.iter().any(|p| p.matches(&item.name)))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let doc_lines = if self.count_placeholder_lines {
+            docs.lines
+        } else {
+            docs.lines - docs.placeholders.len()
+        };
 
         // Track the file info for reporting
-        let file_info = FileInfo {
+        let info = FileInfo {
             path: path.to_path_buf(),
             doc_lines,
             total_lines,
+            code_lines,
             is_complex,
+            min_doc_lines: min_doc_lines_complex,
+            pub_items: pub_items.len(),
+            pub_items_documented: pub_items.iter().filter(|item| item.documented).count(),
+            override_pattern: override_pattern.map(str::to_string),
         };
 
         // Check for validation issues
@@ -262,78 +767,163 @@ impl DocValidator {
         // Only check complex modules
         if is_complex {
             // Warn if complex module has no header documentation at all
-            if doc_lines == 0 {
+            if docs.lines == 0 {
                 issues.push(ValidationIssue::NoDocs {
                     total_lines,
+                    code_lines,
                 });
-            } else if doc_lines < self.min_doc_lines_complex {
+            } else if doc_lines < min_doc_lines_complex {
                 issues.push(ValidationIssue::InsufficientDocs {
                     lines: doc_lines,
-                    min: self.min_doc_lines_complex,
+                    min: min_doc_lines_complex,
                     total_lines,
+                    code_lines,
                 });
             }
         }
         
         // Check for excessive docs (applies to all modules)
-        if doc_lines > self.max_doc_lines {
+        if doc_lines > max_doc_lines {
             issues.push(ValidationIssue::ExcessiveDocs {
                 lines: doc_lines,
-                max: self.max_doc_lines,
+                max: max_doc_lines,
+            });
+        }
+
+        if !docs.placeholders.is_empty()
+            && docs.placeholders.len() as f64 > self.max_placeholder_fraction * docs.lines as f64
+        {
+            issues.push(ValidationIssue::PlaceholderDocs {
+                count: docs.placeholders.len(),
+                lines: docs.placeholders,
             });
         }
 
+        issues.extend(
+            pub_items.into_iter()
+                .filter(|item| !item.documented)
+                .map(|item| ValidationIssue::MissingItemDocs { item: item.name, kind: item.kind, line: item.line }),
+        );
+
+        if let Some(symbols) = &run.symbols {
+            issues.extend(
+                find_stale_references(&content, symbols, &self.reference_allowlist, &self.ident_re).into_iter()
+                    .map(|(symbol, line)| ValidationIssue::StaleReference { symbol, line }),
+            );
+        }
+
+        if self.check_config_snippets {
+            issues.extend(
+                find_config_snippet_errors(&content).into_iter()
+                    .map(|(key, line, reason)| ValidationIssue::InvalidConfigSnippet { key, line, reason }),
+            );
+        }
+
+        Ok(FileCheck { info, issues, fix })
+    }
+
+    /// Add one file's result to the report, applying the baseline
+    fn record(&self, report: &mut ValidationReport, check: FileCheck) {
+        let FileCheck { info, mut issues, fix } = check;
+        report.files_scanned += 1;
+        report.fixes.extend(fix);
+
+        if let Some(baseline) = &self.baseline {
+            let found = issues.len();
+            issues.retain(|issue| !baseline.contains(&info.path, issue));
+            report.suppressed += found - issues.len();
+        }
+
         if !issues.is_empty() {
-            report.warnings += issues.len();
-            
-            if self.verbose {
-                for issue in &issues {
-                    match issue {
-                        ValidationIssue::NoDocs { total_lines } => {
-                            warn!(
-                                "{}: Complex module ({} lines) has no documentation (use //! format)",
-                                path.display(), total_lines
-                            );
-                        }
-                        ValidationIssue::InsufficientDocs { lines, min, total_lines } => {
-                            warn!(
-                                "{}: Complex module ({} lines) has insufficient documentation ({} lines, minimum {})",
-                                path.display(), total_lines, lines, min
-                            );
-                        }
-                        ValidationIssue::ExcessiveDocs { lines, max } => {
-                            warn!(
-                                "{}: Excessive documentation ({} lines, maximum {})",
-                                path.display(), lines, max
-                            );
-                        }
+            for issue in &issues {
+                let severity = issue.severity(&self.severities);
+                match severity {
+                    Severity::Error => report.errors += 1,
+                    Severity::Warning => report.warnings += 1,
+                    Severity::Info => report.infos += 1,
+                }
+                if self.verbose {
+                    let location = match issue.line() {
+                        Some(line) => format!("{}:{}", info.path.display(), line),
+                        None => info.path.display().to_string(),
+                    };
+                    match severity {
+                        Severity::Error => error!("{}: {}", location, issue.message()),
+                        Severity::Warning => warn!("{}: {}", location, issue.message()),
+                        Severity::Info => info!("{}: {}", location, issue.message()),
                     }
                 }
             }
 
-            report.file_issues.push((file_info, issues));
-        } else if is_complex {
+            report.file_issues.push((info, issues));
+        } else if info.is_complex {
             // Only track complex modules that passed
-            report.complex_files_passed.push(file_info);
+            report.complex_files_passed.push(info);
         } else {
             // Track simple modules separately
-            report.simple_files_skipped.push(file_info);
+            report.simple_files_skipped.push(info);
         }
+    }
+
+    /// `content` with the doc template inserted: the insertion line (1-based), the
+    /// inserted text, and the new content
+    fn insert_doc_template(&self, path: &Path, content: &str, total_lines: usize) -> (usize, String, String) {
+        let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        // `foo/mod.rs` documents module `foo`
+        let module_name = match (stem.as_str(), path.parent().and_then(Path::file_name)) {
+            ("mod", Some(dir)) => dir.to_string_lossy().into_owned(),
+            _ => stem,
+        };
+        let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+        let text: String = self.doc_template
+            .replace("{module_name}", &module_name)
+            .replace("{line_count}", &total_lines.to_string())
+            .lines()
+            .map(|line| if line.is_empty() { format!("//!{}", newline) } else { format!("//! {}{}", line, newline) })
+            .collect();
 
-        Ok(())
+        let (bom, body) = match content.strip_prefix('\u{feff}') {
+            Some(body) => ("\u{feff}", body),
+            None => ("", content),
+        };
+        // Keep a shebang and leading plain comments (e.g. a license header) first
+        let mut offset = 0;
+        let mut line = 1;
+        for (index, raw) in body.split_inclusive('\n').enumerate() {
+            let trimmed = raw.trim();
+            let shebang = index == 0 && trimmed.starts_with("#!") && !trimmed.starts_with("#![");
+            let comment = trimmed.starts_with("//") && !trimmed.starts_with("///");
+            if !(shebang || comment || (trimmed.is_empty() && offset > 0)) {
+                break;
+            }
+            offset += raw.len();
+            line += 1;
+        }
+        let (head, rest) = body.split_at(offset);
+        let separator = if rest.trim().is_empty() || rest.starts_with(['\r', '\n']) { "" } else { newline };
+        let head_break = if head.is_empty() || head.ends_with('\n') { "" } else { newline };
+        let fixed = format!("{}{}{}{}{}{}", bom, head, head_break, text, separator, rest);
+        (line, text, fixed)
     }
 
     /// Count the number of module-level documentation lines (//!) at the start of a file
-    fn count_module_doc_lines(&self, content: &str) -> usize {
-        let mut doc_lines = 0;
+    fn module_docs(&self, content: &str) -> ModuleDocs {
+        let mut docs = ModuleDocs::default();
         let mut in_doc_block = true;
 
-        for line in content.lines() {
+        let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+        for (index, line) in content.lines().enumerate() {
             let trimmed = line.trim();
             
             if in_doc_block {
-                if trimmed.starts_with("//!") {
-                    doc_lines += 1;
+                if index == 0 && trimmed.starts_with("#!") && !trimmed.starts_with("#![") {
+                    // Shebang
+                    continue;
+                } else if trimmed.starts_with("//!") {
+                    docs.lines += 1;
+                    if self.placeholder_re.as_ref().is_some_and(|re| re.is_match(trimmed)) {
+                        docs.placeholders.push(index + 1);
+                    }
                 } else if trimmed.is_empty() {
                     // Allow blank lines within documentation
                     continue;
@@ -354,126 +944,1864 @@ impl DocValidator {
             }
         }
 
-        doc_lines
+        docs
+    }
+}
+
+/// The leading `//!` block of a file
+#[derive(Debug, Default)]
+struct ModuleDocs {
+    lines: usize,
+    /// Line numbers (1-based) of lines with a placeholder marker
+    placeholders: Vec<usize>,
+}
+
+/// `\b(?:TODO|FIXME)\b` for the given markers; None when there are none
+fn placeholder_regex(markers: &[String]) -> Option<Regex> {
+    if markers.is_empty() {
+        return None;
+    }
+    let alternatives: Vec<String> = markers.iter().map(|marker| regex::escape(marker)).collect();
+    Regex::new(&format!(r"\b(?:{})\b", alternatives.join("|"))).ok()
+}
+
+impl Default for DocValidator {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 /// Information about a file that was validated
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct FileInfo {
     pub path: PathBuf,
     pub doc_lines: usize,
     pub total_lines: usize,
+    /// Lines other than blanks and comments (including doc comments)
+    pub code_lines: usize,
     pub is_complex: bool,
+    /// `min_doc_lines_complex` in effect for this file
+    pub min_doc_lines: usize,
+    /// Public items checked by `require_pub_item_docs` (allowlisted names excluded)
+    pub pub_items: usize,
+    pub pub_items_documented: usize,
+    /// Pattern of the `ThresholdOverride` applied to this file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub override_pattern: Option<String>,
+}
+
+/// State shared by every file in one `validate_paths` call
+struct RunContext {
+    /// Identifiers in the scanned source, when checking references
+    symbols: Option<HashSet<String>>,
+}
+
+/// One file's result, before the baseline is applied
+struct FileCheck {
+    info: FileInfo,
+    issues: Vec<ValidationIssue>,
+    fix: Option<DocFix>,
+}
+
+impl FileCheck {
+    /// A file that could not be read or rewritten
+    fn unreadable(path: &Path, error: &anyhow::Error) -> Self {
+        Self {
+            info: FileInfo {
+                path: path.to_path_buf(),
+                doc_lines: 0,
+                total_lines: 0,
+                code_lines: 0,
+                is_complex: false,
+                min_doc_lines: 0,
+                pub_items: 0,
+                pub_items_documented: 0,
+                override_pattern: None,
+            },
+            issues: vec![ValidationIssue::UnreadableFile { error: format!("{:#}", error) }],
+            fix: None,
+        }
+    }
+}
+
+/// Words that mark a `//!` line as a placeholder by default
+pub fn default_placeholder_markers() -> Vec<String> {
+    vec!["TODO".to_string(), "FIXME".to_string(), "XXX".to_string()]
+}
+
+/// Share of `//!` lines that may be placeholders by default
+pub fn default_max_placeholder_fraction() -> f64 {
+    0.25
+}
+
+/// External symbols accepted by `check_references` by default
+pub fn default_reference_allowlist() -> Vec<String> {
+    vec!["std::*".to_string(), "core::*".to_string(), "alloc::*".to_string()]
+}
+
+/// A doc template inserted by `DocValidator::with_fix`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DocFix {
+    pub path: PathBuf,
+    /// Line the template starts on (1-based)
+    pub line: usize,
+    pub text: String,
+    /// False in dry-run mode
+    pub applied: bool,
+}
+
+/// Starter docs inserted by `--fix`
+pub fn default_doc_template() -> String {
+    "{module_name}\n\nTODO: Describe what this module does, its main types, and how they fit\ntogether ({line_count} lines).\n".to_string()
+}
+
+/// Thresholds for files matching `pattern` (`validate_docs.overrides` in the config)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThresholdOverride {
+    /// Glob matched against the file path, e.g. `src/core/**`
+    pub pattern: String,
+    #[serde(default)]
+    pub min_doc_lines_complex: Option<usize>,
+    #[serde(default)]
+    pub max_doc_lines: Option<usize>,
+    #[serde(default)]
+    pub complexity_threshold: Option<usize>,
 }
 
 /// Report from documentation validation
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize)]
 pub struct ValidationReport {
     pub files_scanned: usize,
+    /// Issues by severity
+    pub errors: usize,
     pub warnings: usize,
+    pub infos: usize,
+    /// Severities the issues were counted with
+    #[serde(skip)]
+    pub severities: HashMap<Rule, Severity>,
+    /// Issues left out because the baseline lists them
+    pub suppressed: usize,
+    /// Ref of an incremental run (`DocValidator::with_changed_since`)
+    pub changed_since: Option<String>,
+    /// Files skipped in an incremental run because they did not change
+    pub unchanged_skipped: usize,
+    /// Doc templates inserted (or, in dry-run mode, proposed) by `--fix`
+    pub fixes: Vec<DocFix>,
+    /// The run was cancelled before every file was checked
+    pub cancelled: bool,
     pub complex_files_passed: Vec<FileInfo>,
     pub simple_files_skipped: Vec<FileInfo>,
+    /// Serialized as `[{"path": ..., ..., "issues": [...]}]`
+    #[serde(serialize_with = "serialize_file_issues")]
     pub file_issues: Vec<(FileInfo, Vec<ValidationIssue>)>,
 }
 
 impl ValidationReport {
-    /// Print a summary of the validation results
-    pub fn print_summary(&self, verbose: bool) {
+    /// Print a summary of the validation results to `out`, e.g. stdout or
+    /// `report_output()`
+    pub fn print_summary(&self, options: &SummaryOptions, out: &mut dyn std::io::Write) -> std::io::Result<()> {
+        write!(out, "{}", self.render_summary(options))?;
+        out.flush()
+    }
+
+    /// The summary printed by `print_summary`
+    ///
+    /// Flagged modules are listed by severity, then by how far they are from
+    /// compliance, worst first.
+    pub fn render_summary(&self, options: &SummaryOptions) -> String {
+        let mut out = String::new();
+        let verbose = options.verbose;
         let complex_count = self.complex_files_passed.len() + self.file_issues.len();
         let simple_count = self.simple_files_skipped.len();
-        
+
+        let issue_count = self.errors + self.warnings + self.infos;
+
         // Non-verbose: Just show the essential summary
-        if !verbose && self.warnings == 0 {
-            println!("Validated {} files: {} complex, {} simple (skipped)", 
+        if !verbose && issue_count == 0 {
+            let _ = writeln!(out, "Validated {} files: {} complex, {} simple (skipped)",
                     self.files_scanned, complex_count, simple_count);
-            println!("✓ All complex modules have appropriate documentation!");
-            return;
+            if !self.cancelled {
+                let _ = writeln!(out, "✓ All complex modules have appropriate documentation!");
+            }
+            self.write_footnotes(&mut out);
+            return out;
         }
-        
+
         // Verbose mode or there are warnings - show detailed output
-        println!("\nDocumentation Validation Report");
-        println!("===============================");
-        println!("{} files scanned: {} complex (validated), {} simple (skipped)",
+        let _ = writeln!(out, "\nDocumentation Validation Report");
+        let _ = writeln!(out, "===============================");
+        let _ = writeln!(out, "{} files scanned: {} complex (validated), {} simple (skipped)",
                 self.files_scanned, complex_count, simple_count);
-        
+
         // Group results by status
-        if !self.complex_files_passed.is_empty() && (verbose || self.warnings > 0) {
-            println!("\n✅ Passed ({} complex modules):", self.complex_files_passed.len());
+        if !self.complex_files_passed.is_empty() && (verbose || issue_count > 0) {
+            let _ = writeln!(out, "\n✅ Passed ({} complex modules):", self.complex_files_passed.len());
             if verbose {
                 for file in &self.complex_files_passed {
-                    println!("  {} ({} lines, {} doc lines)",
-                            file.path.display(), file.total_lines, file.doc_lines);
+                    let _ = writeln!(out, "  {} ({} lines, {} code, {} doc lines)",
+                            file.path.display(), file.total_lines, file.code_lines, file.doc_lines);
                 }
             } else {
                 for file in &self.complex_files_passed {
-                    println!("  {}", file.path.display());
+                    let _ = writeln!(out, "  {}", file.path.display());
                 }
             }
         }
-        
-        if !self.file_issues.is_empty() {
-            println!("\n⚠️  Warnings ({} modules):", self.file_issues.len());
-            for (file_info, issues) in &self.file_issues {
-                for issue in issues {
-                    match issue {
-                        ValidationIssue::NoDocs { total_lines } => {
-                            println!("  {}: Complex module ({} lines) has no documentation (use //! format)",
-                                    file_info.path.display(), total_lines);
-                        }
-                        ValidationIssue::InsufficientDocs { lines, min, total_lines } => {
-                            println!("  {}: Complex module ({} lines) has insufficient documentation ({} lines, minimum {})",
-                                    file_info.path.display(), total_lines, lines, min);
-                        }
-                        ValidationIssue::ExcessiveDocs { lines, max } => {
-                            println!("  {}: Excessive documentation ({} lines, maximum {})",
-                                    file_info.path.display(), lines, max);
-                        }
-                    }
+
+        let mut offenders = self.offenders();
+        let total = offenders.len();
+        offenders.truncate(options.top.unwrap_or(total));
+        for (severity, heading) in [
+            (Severity::Error, "❌ Errors"),
+            (Severity::Warning, "⚠️  Warnings"),
+            (Severity::Info, "ℹ️  Info"),
+        ] {
+            let section: Vec<&Offender> = offenders.iter().filter(|o| o.severity == severity).collect();
+            if section.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "\n{} ({} modules):", heading, section.len());
+            if !options.group_by_directory {
+                for offender in section {
+                    write_file_issues(&mut out, offender.file, &offender.issues, verbose, "");
+                }
+                continue;
+            }
+            // Directories in the order of their worst module
+            let mut directories: Vec<(&Path, Vec<&Offender>)> = Vec::new();
+            for offender in section {
+                let dir = offender.file.path.parent().unwrap_or(Path::new(""));
+                match directories.iter_mut().find(|(d, _)| *d == dir) {
+                    Some((_, members)) => members.push(offender),
+                    None => directories.push((dir, vec![offender])),
+                }
+            }
+            for (dir, members) in directories {
+                let issues: usize = members.iter().map(|o| o.issues.len()).sum();
+                let dir = if dir.as_os_str().is_empty() { ".".to_string() } else { dir.display().to_string() };
+                let _ = writeln!(out, "  {}/ ({} modules, {} issues)", dir, members.len(), issues);
+                for offender in members {
+                    write_file_issues(&mut out, offender.file, &offender.issues, verbose, "  ");
                 }
             }
         }
-        
+        if offenders.len() < total {
+            let _ = writeln!(out, "\n... and {} more (showing the {} worst)", total - offenders.len(), offenders.len());
+        }
+
         if verbose && !self.simple_files_skipped.is_empty() {
-            println!("\n⏭️  Skipped ({} simple modules under {} lines):", 
-                    self.simple_files_skipped.len(), 
-                    200); // TODO: get this from config
+            let _ = writeln!(out, "\n⏭️  Skipped ({} simple modules under their complexity threshold):",
+                    self.simple_files_skipped.len());
             for file in &self.simple_files_skipped {
-                println!("  {} ({} lines)", file.path.display(), file.total_lines);
+                let _ = writeln!(out, "  {} ({} lines, {} code)", file.path.display(), file.total_lines, file.code_lines);
             }
         }
-        
+
         // Final status for verbose or warning cases
-        if verbose || self.warnings > 0 {
-            if self.warnings == 0 && complex_count > 0 {
-                println!("\n✓ All complex modules have appropriate documentation!");
-            } else if self.warnings == 0 && complex_count == 0 {
-                println!("\n✓ No complex modules found requiring validation.");
+        if verbose || issue_count > 0 {
+            let _ = if self.errors + self.warnings > 0 {
+                writeln!(out, "\n❌ {} error(s), {} warning(s) found.", self.errors, self.warnings)
+            } else if complex_count > 0 && issue_count == 0 {
+                writeln!(out, "\n✓ All complex modules have appropriate documentation!")
+            } else if complex_count == 0 && issue_count == 0 {
+                writeln!(out, "\n✓ No complex modules found requiring validation.")
+            } else {
+                writeln!(out, "\n✓ No errors or warnings ({} info).", self.infos)
+            };
+        }
+        self.write_footnotes(&mut out);
+        out
+    }
+
+    /// Each flagged module once per severity among its issues; most severe
+    /// first, then furthest from compliance, then by path
+    fn offenders(&self) -> Vec<Offender<'_>> {
+        let mut offenders = Vec::new();
+        for (file, issues) in &self.file_issues {
+            for severity in [Severity::Error, Severity::Warning, Severity::Info] {
+                let issues: Vec<&ValidationIssue> = issues.iter()
+                    .filter(|issue| issue.severity(&self.severities) == severity)
+                    .collect();
+                if !issues.is_empty() {
+                    let shortfall = issues.iter().map(|issue| shortfall(issue, file)).sum();
+                    offenders.push(Offender { file, severity, issues, shortfall });
+                }
+            }
+        }
+        offenders.sort_by(|a, b| {
+            a.severity.cmp(&b.severity)
+                .then(b.shortfall.cmp(&a.shortfall))
+                .then_with(|| a.file.path.cmp(&b.file.path))
+        });
+        offenders
+    }
+
+    /// Coverage, fixes, and why some issues or files may be missing from the report
+    fn write_footnotes(&self, out: &mut String) {
+        let coverage = self.coverage();
+        let _ = match coverage.pub_items_percent {
+            Some(items) => writeln!(out, "Coverage: {:.1}% of complex modules documented ({}/{}), {:.1}% of public items ({}/{})",
+                    coverage.percent, coverage.complex_documented, coverage.complex_total,
+                    items, coverage.pub_items_documented, coverage.pub_items_total),
+            None => writeln!(out, "Coverage: {:.1}% of complex modules documented ({}/{})",
+                    coverage.percent, coverage.complex_documented, coverage.complex_total),
+        };
+        if self.cancelled {
+            let _ = writeln!(out, "⏹️  Interrupted: partial report of the {} file(s) validated before cancellation", self.files_scanned);
+        }
+        for fix in &self.fixes {
+            if fix.applied {
+                let _ = writeln!(out, "📝 Inserted doc template in {} at line {}", fix.path.display(), fix.line);
             } else {
-                println!("\n❌ {} warning(s) found.", self.warnings);
+                let _ = writeln!(out, "📝 Would insert at {}:{}:", fix.path.display(), fix.line);
+                for line in fix.text.lines() {
+                    let _ = writeln!(out, "  + {}", line);
+                }
             }
         }
+        if self.suppressed > 0 {
+            let _ = writeln!(out, "{} pre-existing issue(s) suppressed by baseline", self.suppressed);
+        }
+        if let Some(base_ref) = &self.changed_since {
+            let _ = writeln!(out, "Incremental run: only files changed since {} were validated ({} unchanged file(s) skipped)",
+                    base_ref, self.unchanged_skipped);
+        }
+    }
+
+    /// One GitHub Actions annotation per issue, `::error`, `::warning`, or `::notice`
+    /// by severity (see the `annotations` module)
+    pub fn github_annotations(&self) -> Vec<String> {
+        self.file_issues.iter()
+            .flat_map(|(file, issues)| issues.iter().map(move |issue| {
+                let command = match issue.severity(&self.severities) {
+                    Severity::Error => crate::annotations::error,
+                    Severity::Warning => crate::annotations::warning,
+                    Severity::Info => crate::annotations::notice,
+                };
+                command(&file.path, issue.line().unwrap_or(1), &issue.message())
+            }))
+            .collect()
     }
 
-    /// Check if validation passed (no warnings in strict mode, otherwise always true)
+    /// Every file validated, with or without issues
+    pub fn files(&self) -> impl Iterator<Item = &FileInfo> {
+        self.complex_files_passed.iter()
+            .chain(&self.simple_files_skipped)
+            .chain(self.file_issues.iter().map(|(file, _)| file))
+    }
+
+    /// Check if validation passed: no errors, and in strict mode no warnings either
     pub fn passed(&self, strict: bool) -> bool {
-        !strict || self.warnings == 0
+        self.errors == 0 && (!strict || self.warnings == 0)
+    }
+
+    /// Share of complex modules meeting their minimum docs, and of checked public
+    /// items with docs; baselined issues do not count as documented
+    pub fn coverage(&self) -> CoverageStats {
+        let files = self.complex_files_passed.iter()
+            .chain(&self.simple_files_skipped)
+            .chain(self.file_issues.iter().map(|(file, _)| file));
+        let mut stats = CoverageStats::default();
+        for file in files {
+            if file.is_complex {
+                stats.complex_total += 1;
+                if file.doc_lines > 0 && file.doc_lines >= file.min_doc_lines {
+                    stats.complex_documented += 1;
+                }
+            }
+            stats.pub_items_total += file.pub_items;
+            stats.pub_items_documented += file.pub_items_documented;
+        }
+        stats.percent = percent(stats.complex_documented, stats.complex_total);
+        stats.pub_items_percent = (stats.pub_items_total > 0)
+            .then(|| percent(stats.pub_items_documented, stats.pub_items_total));
+        stats
+    }
+
+    /// The full report as JSON, with `coverage` and each issue's `severity` added
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut json = serde_json::to_value(self).unwrap_or_default();
+        json["coverage"] = serde_json::to_value(self.coverage()).unwrap_or_default();
+        for (index, (_, issues)) in self.file_issues.iter().enumerate() {
+            for (issue_index, issue) in issues.iter().enumerate() {
+                json["file_issues"][index]["issues"][issue_index]["severity"] =
+                    serde_json::to_value(issue.severity(&self.severities)).unwrap_or_default();
+            }
+        }
+        json
+    }
+
+    /// The issues as a SARIF 2.1.0 log, one result per issue at its severity's level
+    pub fn to_sarif(&self) -> serde_json::Value {
+        let rules: Vec<serde_json::Value> = SARIF_RULES.iter()
+            .map(|(id, description)| serde_json::json!({
+                "id": id,
+                "shortDescription": { "text": description },
+            }))
+            .collect();
+        let results: Vec<serde_json::Value> = self.file_issues.iter()
+            .flat_map(|(file, issues)| issues.iter().map(move |issue| (file, issue)))
+            .map(|(file, issue)| serde_json::json!({
+                "ruleId": issue.rule_id(),
+                "level": issue.severity(&self.severities).sarif_level(),
+                "message": { "text": issue.message() },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": file.path.to_string_lossy().replace('\\', "/") },
+                        "region": { "startLine": issue.line().unwrap_or(1) },
+                    },
+                }],
+            }))
+            .collect();
+        serde_json::json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "autodebugger validate-docs",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules,
+                    },
+                },
+                "results": results,
+            }],
+        })
     }
 }
 
-/// Types of validation issues
-#[derive(Debug)]
-pub enum ValidationIssue {
-    NoDocs {
-        total_lines: usize,
-    },
-    InsufficientDocs { 
-        lines: usize, 
-        min: usize,
-        total_lines: usize,
-    },
-    ExcessiveDocs { 
-        lines: usize, 
-        max: usize,
-    },
-}
\ No newline at end of file
+/// One file's issues in the summary, indented by `indent`; item and reference
+/// issues are listed together under their file
+fn write_file_issues(out: &mut String, file_info: &FileInfo, issues: &[&ValidationIssue], verbose: bool, indent: &str) {
+    let path = file_info.path.display();
+    let mut items = Vec::new();
+    let mut references = Vec::new();
+    let mut config_keys = Vec::new();
+    for issue in issues {
+        match issue {
+            ValidationIssue::MissingItemDocs { item, kind, line } => {
+                items.push((item, kind, line));
+            }
+            ValidationIssue::StaleReference { symbol, line } => references.push((symbol, line)),
+            ValidationIssue::InvalidConfigSnippet { key, line, reason } => config_keys.push((key, line, reason)),
+            ValidationIssue::PlaceholderDocs { lines, .. } if verbose => {
+                let lines: Vec<String> = lines.iter().map(usize::to_string).collect();
+                let _ = writeln!(out, "{}  {}: {}", indent, path, issue.message());
+                let _ = writeln!(out, "{}    line(s) {}", indent, lines.join(", "));
+            }
+            _ => {
+                let _ = writeln!(out, "{}  {}: {}", indent, path, issue.message());
+            }
+        }
+    }
+    if !items.is_empty() {
+        let _ = writeln!(out, "{}  {}: {} public item(s) without documentation", indent, path, items.len());
+        for (item, kind, line) in items {
+            let _ = writeln!(out, "{}    line {}: {} {}", indent, line, kind, item);
+        }
+    }
+    if !references.is_empty() {
+        let _ = writeln!(out, "{}  {}: {} doc reference(s) not found in the source", indent, path, references.len());
+        for (symbol, line) in references {
+            let _ = writeln!(out, "{}    line {}: `{}`", indent, line, symbol);
+        }
+    }
+    if !config_keys.is_empty() {
+        let _ = writeln!(out, "{}  {}: {} invalid key(s) in config snippets", indent, path, config_keys.len());
+        for (key, line, reason) in config_keys {
+            let _ = writeln!(out, "{}    line {}: `{}`: {}", indent, line, key, reason);
+        }
+    }
+    if verbose {
+        let _ = match &file_info.override_pattern {
+            Some(pattern) => writeln!(out, "{}    (thresholds from override `{}`)", indent, pattern),
+            None => writeln!(out, "{}    (global thresholds)", indent),
+        };
+    }
+}
+
+/// A flagged module's issues of one severity, as listed in the summary
+struct Offender<'a> {
+    file: &'a FileInfo,
+    severity: Severity,
+    issues: Vec<&'a ValidationIssue>,
+    /// Sum of `shortfall` over `issues`
+    shortfall: usize,
+}
+
+/// How far an issue is from compliance: doc lines missing or over the limit,
+/// and 1 for each item-level issue
+fn shortfall(issue: &ValidationIssue, file: &FileInfo) -> usize {
+    match issue {
+        ValidationIssue::NoDocs { .. } => file.min_doc_lines.max(1),
+        ValidationIssue::InsufficientDocs { lines, min, .. } => min.saturating_sub(*lines),
+        ValidationIssue::ExcessiveDocs { lines, max } => lines.saturating_sub(*max),
+        ValidationIssue::PlaceholderDocs { count, .. } => *count,
+        _ => 1,
+    }
+}
+
+/// Layout of `ValidationReport::render_summary`
+#[derive(Debug, Clone, Default)]
+pub struct SummaryOptions {
+    /// Also list passed and skipped modules, and the thresholds of flagged ones
+    pub verbose: bool,
+    /// List only the N worst flagged modules
+    pub top: Option<usize>,
+    /// List flagged modules under their directory, with per-directory subtotals
+    pub group_by_directory: bool,
+}
+
+/// Documentation coverage of one run (`ValidationReport::coverage`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CoverageStats {
+    pub complex_total: usize,
+    /// Complex modules with at least their minimum `//!` lines
+    pub complex_documented: usize,
+    /// 100 when there are no complex modules
+    pub percent: f64,
+    pub pub_items_total: usize,
+    pub pub_items_documented: usize,
+    /// None when no public items were checked
+    pub pub_items_percent: Option<f64>,
+}
+
+fn percent(part: usize, total: usize) -> f64 {
+    if total == 0 { 100.0 } else { part as f64 * 100.0 / total as f64 }
+}
+
+/// SARIF rule ids (`ValidationIssue::rule_id`) and what they check
+const SARIF_RULES: [(&str, &str); 8] = [
+    ("no-docs", "Complex module without //! documentation"),
+    ("insufficient-docs", "Complex module with fewer //! lines than the minimum"),
+    ("excessive-docs", "Module with more //! lines than the maximum"),
+    ("missing-item-docs", "Public item without /// documentation"),
+    ("stale-reference", "Symbol named in //! docs that does not exist in the source"),
+    ("unreadable-file", "File that could not be read"),
+    ("invalid-config-snippet", "YAML block in //! docs that config.yaml would not accept"),
+    ("placeholder-docs", "Module docs that are mostly TODO/FIXME placeholders"),
+];
+
+/// `file_issues` as a list of file infos, each with its `issues`
+fn serialize_file_issues<S: Serializer>(
+    file_issues: &[(FileInfo, Vec<ValidationIssue>)],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct Entry<'a> {
+        #[serde(flatten)]
+        file: &'a FileInfo,
+        issues: &'a [ValidationIssue],
+    }
+    serializer.collect_seq(file_issues.iter().map(|(file, issues)| Entry { file, issues }))
+}
+
+/// Types of validation issues
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ValidationIssue {
+    NoDocs {
+        total_lines: usize,
+        code_lines: usize,
+    },
+    InsufficientDocs { 
+        lines: usize, 
+        min: usize,
+        total_lines: usize,
+        code_lines: usize,
+    },
+    ExcessiveDocs { 
+        lines: usize, 
+        max: usize,
+    },
+    /// A public item without `///` docs (only with `require_pub_item_docs`)
+    MissingItemDocs {
+        item: String,
+        kind: ItemKind,
+        /// Line of the declaration (1-based)
+        line: usize,
+    },
+    /// A symbol named in `//!` docs that is not in the source (only with `check_references`)
+    StaleReference {
+        symbol: String,
+        /// First line naming it (1-based)
+        line: usize,
+    },
+    /// A file that could not be read (or, with `fix`, rewritten)
+    UnreadableFile {
+        error: String,
+    },
+    /// A key in a YAML block of `//!` docs that `config.yaml` does not accept
+    /// (only with `check_config_snippets`)
+    InvalidConfigSnippet {
+        /// Dotted path, e.g. `validate_docs.min_doc_lines`
+        key: String,
+        line: usize,
+        reason: String,
+    },
+    /// More than `max_placeholder_fraction` of the `//!` lines are TODO/FIXME
+    /// placeholders
+    PlaceholderDocs {
+        count: usize,
+        /// Line of each placeholder (1-based)
+        lines: Vec<usize>,
+    },
+}
+
+/// A validation rule, named in snake case in the config (`severities`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    NoDocs,
+    InsufficientDocs,
+    ExcessiveDocs,
+    MissingItemDocs,
+    StaleReference,
+    UnreadableFile,
+    InvalidConfigSnippet,
+    PlaceholderDocs,
+}
+
+impl Rule {
+    /// Stable id for SARIF output and baselines
+    pub fn id(self) -> &'static str {
+        match self {
+            Rule::NoDocs => "no-docs",
+            Rule::InsufficientDocs => "insufficient-docs",
+            Rule::ExcessiveDocs => "excessive-docs",
+            Rule::MissingItemDocs => "missing-item-docs",
+            Rule::StaleReference => "stale-reference",
+            Rule::UnreadableFile => "unreadable-file",
+            Rule::InvalidConfigSnippet => "invalid-config-snippet",
+            Rule::PlaceholderDocs => "placeholder-docs",
+        }
+    }
+}
+
+/// How much an issue matters: errors always fail a run, warnings only in strict
+/// mode, and info never does
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    #[default]
+    Warning,
+    Info,
+}
+
+impl Severity {
+    /// SARIF result level
+    pub fn sarif_level(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "note",
+        }
+    }
+}
+
+impl ValidationIssue {
+    /// Description without the file path
+    pub fn message(&self) -> String {
+        match self {
+            ValidationIssue::NoDocs { total_lines, code_lines } => {
+                format!("Complex module ({} lines, {} code) has no documentation (use //! format)", total_lines, code_lines)
+            }
+            ValidationIssue::InsufficientDocs { lines, min, total_lines, code_lines } => {
+                format!(
+                    "Complex module ({} lines, {} code) has insufficient documentation ({} lines, minimum {})",
+                    total_lines, code_lines, lines, min
+                )
+            }
+            ValidationIssue::ExcessiveDocs { lines, max } => {
+                format!("Excessive documentation ({} lines, maximum {})", lines, max)
+            }
+            ValidationIssue::MissingItemDocs { item, kind, .. } => {
+                format!("Public {} `{}` has no documentation (use /// format)", kind, item)
+            }
+            ValidationIssue::StaleReference { symbol, .. } => {
+                format!("Doc reference `{}` not found in the source", symbol)
+            }
+            ValidationIssue::UnreadableFile { error } => format!("Could not be validated: {}", error),
+            ValidationIssue::InvalidConfigSnippet { key, reason, .. } => {
+                format!("Config snippet key `{}` is not valid: {}", key, reason)
+            }
+            ValidationIssue::PlaceholderDocs { count, .. } => {
+                format!("Documentation is mostly placeholders ({} TODO/FIXME line(s))", count)
+            }
+        }
+    }
+
+    /// Line the issue points at; module-level issues have none
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ValidationIssue::MissingItemDocs { line, .. }
+            | ValidationIssue::StaleReference { line, .. }
+            | ValidationIssue::InvalidConfigSnippet { line, .. } => Some(*line),
+            ValidationIssue::PlaceholderDocs { lines, .. } => lines.first().copied(),
+            _ => None,
+        }
+    }
+
+    /// Name of the item the issue is about, for item-level issues
+    pub fn item(&self) -> Option<&str> {
+        match self {
+            ValidationIssue::MissingItemDocs { item, .. } => Some(item),
+            ValidationIssue::StaleReference { symbol, .. } => Some(symbol),
+            ValidationIssue::InvalidConfigSnippet { key, .. } => Some(key),
+            _ => None,
+        }
+    }
+
+    /// Rule that raised the issue
+    pub fn rule(&self) -> Rule {
+        match self {
+            ValidationIssue::NoDocs { .. } => Rule::NoDocs,
+            ValidationIssue::InsufficientDocs { .. } => Rule::InsufficientDocs,
+            ValidationIssue::ExcessiveDocs { .. } => Rule::ExcessiveDocs,
+            ValidationIssue::MissingItemDocs { .. } => Rule::MissingItemDocs,
+            ValidationIssue::StaleReference { .. } => Rule::StaleReference,
+            ValidationIssue::UnreadableFile { .. } => Rule::UnreadableFile,
+            ValidationIssue::InvalidConfigSnippet { .. } => Rule::InvalidConfigSnippet,
+            ValidationIssue::PlaceholderDocs { .. } => Rule::PlaceholderDocs,
+        }
+    }
+
+    /// Stable id for SARIF output and baselines
+    pub fn rule_id(&self) -> &'static str {
+        self.rule().id()
+    }
+
+    /// Effective severity under the configured `severities` (warning by default)
+    pub fn severity(&self, severities: &HashMap<Rule, Severity>) -> Severity {
+        severities.get(&self.rule()).copied().unwrap_or_default()
+    }
+}
+
+/// One accepted issue in a `DocsBaseline`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub path: PathBuf,
+    /// `ValidationIssue::rule_id`
+    pub issue: String,
+    /// Item name, for item-level issues
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+}
+
+/// Pre-existing issues that should not fail validation (see the module docs)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocsBaseline {
+    pub issues: Vec<BaselineEntry>,
+}
+
+impl DocsBaseline {
+    /// Baseline location for a workspace
+    pub fn path(base: &Path) -> PathBuf {
+        base.join(CACHE_DIR).join(BASELINE_FILE)
+    }
+
+    /// Read a baseline; a missing file is an empty baseline
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read baseline {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid baseline {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("Failed to write baseline {}", path.display()))
+    }
+
+    /// Whether `issue` in `path` is accepted
+    pub fn contains(&self, path: &Path, issue: &ValidationIssue) -> bool {
+        let path = baseline_path(path);
+        self.issues.iter().any(|entry| {
+            entry.path == path && entry.issue == issue.rule_id() && entry.item.as_deref() == issue.item()
+        })
+    }
+
+    /// Replace the entries of every file in `report` with its current issues
+    ///
+    /// Entries for files the report did not cover are kept. `report` must come
+    /// from a validator without a baseline, or suppressed issues would be pruned.
+    pub fn update(&mut self, report: &ValidationReport) {
+        let scanned: Vec<PathBuf> = report.files().map(|file| baseline_path(&file.path)).collect();
+        self.issues.retain(|entry| !scanned.contains(&entry.path));
+        for (file, issues) in &report.file_issues {
+            self.issues.extend(issues.iter().map(|issue| BaselineEntry {
+                path: baseline_path(&file.path),
+                issue: issue.rule_id().to_string(),
+                item: issue.item().map(str::to_string),
+            }));
+        }
+        self.issues.sort();
+        self.issues.dedup();
+    }
+}
+
+/// Existing files changed since the merge base of `base_ref` and HEAD, canonicalized
+///
+/// Includes uncommitted changes and untracked files; deletions are left out.
+fn changed_files(dir: &Path, base_ref: &str) -> Result<HashSet<PathBuf>> {
+    let root = PathBuf::from(git_output(dir, &["rev-parse", "--show-toplevel"])?.trim());
+    let merge_base = git_output(&root, &["merge-base", base_ref, "HEAD"])
+        .with_context(|| format!("No common ancestor with {}", base_ref))?;
+    let diff = git_output(&root, &["-c", "core.quotePath=false", "diff", "--name-only", "--diff-filter=d", merge_base.trim(), "--"])?;
+    let untracked = git_output(&root, &["-c", "core.quotePath=false", "ls-files", "--others", "--exclude-standard"])?;
+    Ok(diff.lines()
+        .chain(untracked.lines())
+        .map(|file| root.join(file))
+        .map(|path| path.canonicalize().unwrap_or(path))
+        .collect())
+}
+
+/// `path` without `./` components, so `./src/a.rs` and `src/a.rs` match
+fn baseline_path(path: &Path) -> PathBuf {
+    path.components().filter(|c| !matches!(c, Component::CurDir)).collect()
+}
+
+/// Kinds of public items checked by `require_pub_item_docs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemKind {
+    Function,
+    Struct,
+    Enum,
+    Trait,
+}
+
+impl std::fmt::Display for ItemKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ItemKind::Function => "function",
+            ItemKind::Struct => "struct",
+            ItemKind::Enum => "enum",
+            ItemKind::Trait => "trait",
+        })
+    }
+}
+
+/// Symbols named in `//!` code spans and Rust examples that are not in `symbols`,
+/// each with the first line naming it
+fn find_stale_references(content: &str, symbols: &HashSet<String>, allowlist: &[Pattern], ident_re: &Regex) -> Vec<(String, usize)> {
+    let mut candidates = Vec::new();
+    // Lines of the open fence when it holds Rust
+    let mut fence: Option<Vec<(usize, &str)>> = None;
+    let mut in_other_fence = false;
+
+    for (index, line) in content.lines().enumerate() {
+        let Some(doc) = line.trim_start().strip_prefix("//!") else {
+            continue;
+        };
+        let doc = doc.strip_prefix(' ').unwrap_or(doc);
+        if let Some(lang) = doc.trim_start().strip_prefix("```") {
+            if let Some(lines) = fence.take() {
+                candidates.extend(example_symbols(&lines, ident_re));
+            } else if in_other_fence {
+                in_other_fence = false;
+            } else if is_rust_fence(lang) {
+                fence = Some(Vec::new());
+            } else {
+                in_other_fence = true;
+            }
+            continue;
+        }
+        match &mut fence {
+            Some(lines) => lines.push((index + 1, doc)),
+            None if !in_other_fence => {
+                candidates.extend(span_symbols(doc, ident_re).into_iter().map(|symbol| (symbol, index + 1)));
+            }
+            None => {}
+        }
+    }
+
+    let mut seen = HashSet::new();
+    candidates.into_iter()
+        .filter(|(symbol, _)| is_stale(symbol, symbols, allowlist))
+        .filter(|(symbol, _)| seen.insert(symbol.clone()))
+        .collect()
+}
+
+/// Whether a fence info string (after the backticks) marks a Rust example
+fn is_rust_fence(lang: &str) -> bool {
+    lang.split(',').map(str::trim).all(|attr| {
+        matches!(attr, "" | "rust" | "no_run" | "ignore" | "should_panic" | "compile_fail") || attr.starts_with("edition")
+    })
+}
+
+/// Symbols in the inline code spans of one doc line
+fn span_symbols(doc: &str, ident_re: &Regex) -> Vec<String> {
+    let parts: Vec<&str> = doc.split('`').collect();
+    // Odd parts are inside backticks; a trailing one is unclosed
+    parts.iter().enumerate()
+        .filter(|(i, _)| i % 2 == 1 && i + 1 < parts.len())
+        .filter_map(|(_, span)| {
+            let span = span.trim();
+            let symbol = span.strip_suffix("()").or_else(|| span.strip_suffix('!')).unwrap_or(span);
+            let whole = ident_re.find(symbol).is_some_and(|m| m.start() == 0 && m.end() == symbol.len());
+            (whole && looks_like_symbol(symbol, symbol.len() < span.len())).then(|| symbol.to_string())
+        })
+        .collect()
+}
+
+/// Calls, macros, and paths in a Rust example, skipping names it defines
+fn example_symbols(lines: &[(usize, &str)], ident_re: &Regex) -> Vec<(String, usize)> {
+    let code: Vec<(usize, String)> = lines.iter()
+        .map(|(line, text)| {
+            // `# ` hides a line of the example from rendered docs
+            let text = text.trim_start();
+            (*line, strip_literals(text.strip_prefix("# ").unwrap_or(text)))
+        })
+        .collect();
+
+    let mut locals = HashSet::new();
+    for (_, text) in &code {
+        let tokens: Vec<&str> = ident_re.find_iter(text).map(|m| m.as_str()).collect();
+        for pair in tokens.windows(2) {
+            if DEFINING_KEYWORDS.contains(&pair[0]) {
+                locals.insert(pair[1]);
+            }
+        }
+    }
+
+    let mut symbols = Vec::new();
+    for (line, text) in &code {
+        for found in ident_re.find_iter(text) {
+            let symbol = found.as_str();
+            let before = text[..found.start()].chars().next_back();
+            let after = text[found.end()..].chars().next();
+            if matches!(before, Some('.' | '\'')) || locals.contains(symbol) {
+                continue;
+            }
+            if looks_like_symbol(symbol, matches!(after, Some('(' | '!'))) {
+                symbols.push((symbol.to_string(), *line));
+            }
+        }
+    }
+    symbols
+}
+
+/// `line` with string literal contents blanked and any trailing `//` comment removed
+fn strip_literals(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut in_string = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_string => {
+                chars.next();
+            }
+            '"' => {
+                in_string = !in_string;
+                out.push(' ');
+            }
+            '/' if !in_string && chars.peek() == Some(&'/') => break,
+            _ if in_string => out.push(' '),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Whether a token reads as a code symbol rather than a plain word
+fn looks_like_symbol(token: &str, called: bool) -> bool {
+    let mut chars = token.chars();
+    let camel_case = chars.next().is_some_and(|c| c.is_ascii_uppercase()) && chars.any(|c| c.is_ascii_lowercase());
+    called || token.contains("::") || camel_case || (token.contains('_') && !token.starts_with('_'))
+}
+
+/// Whether `symbol` is neither allowlisted nor found in the index
+fn is_stale(symbol: &str, symbols: &HashSet<String>, allowlist: &[Pattern]) -> bool {
+    if allowlist.iter().any(|pattern| pattern.matches(symbol)) {
+        return false;
+    }
+    let segments: Vec<&str> = symbol.split("::").collect();
+    // The leading crate or module name of a path is not checked
+    let skip = usize::from(segments.len() > 1 && segments[0].starts_with(|c: char| c.is_ascii_lowercase()));
+    segments[skip..].iter()
+        .any(|segment| !PRELUDE.contains(segment) && !symbols.contains(*segment))
+}
+
+/// Keys rejected in the ```` ```yaml ```` blocks of `//!` docs: dotted key, line,
+/// and reason
+fn find_config_snippet_errors(content: &str) -> Vec<(String, usize, String)> {
+    let mut errors = Vec::new();
+    // First line of the open YAML block, and its lines
+    let mut snippet: Option<(usize, Vec<&str>)> = None;
+    let mut in_other_fence = false;
+
+    for (index, line) in content.lines().enumerate() {
+        let Some(doc) = line.trim_start().strip_prefix("//!") else {
+            continue;
+        };
+        let doc = doc.strip_prefix(' ').unwrap_or(doc);
+        let Some(lang) = doc.trim_start().strip_prefix("```") else {
+            if let Some((_, lines)) = &mut snippet {
+                lines.push(doc);
+            }
+            continue;
+        };
+        if let Some((start, lines)) = snippet.take() {
+            errors.extend(check_config_snippet(start, &lines));
+        } else if in_other_fence {
+            in_other_fence = false;
+        } else if matches!(lang.trim(), "yaml" | "yml") {
+            snippet = Some((index + 2, Vec::new()));
+        } else {
+            in_other_fence = true;
+        }
+    }
+    errors
+}
+
+/// Deserialize one snippet (starting at line `start`) as a `Config`
+fn check_config_snippet(start: usize, lines: &[&str]) -> Vec<(String, usize, String)> {
+    let text = lines.join("\n");
+    let mut unknown = Vec::new();
+    let result: std::result::Result<crate::config::Config, _> = serde_ignored::deserialize(
+        serde_yaml::Deserializer::from_str(&text),
+        |path| unknown.push(path.to_string()),
+    );
+
+    let mut errors: Vec<(String, usize, String)> = unknown.into_iter()
+        .map(|key| {
+            let line = start + crate::config::key_line(lines, &key);
+            (key, line, "unknown key".to_string())
+        })
+        .collect();
+    if let Err(e) = result {
+        let line = e.location().map_or(start, |location| start + location.line().saturating_sub(1));
+        // serde_yaml prefixes the message with the path of the bad value
+        let message = e.to_string();
+        let message = message.split(" at line ").next().unwrap_or(&message);
+        let (key, reason) = match message.split_once(": ") {
+            Some((key, reason)) if !key.contains(' ') => (key.to_string(), reason.to_string()),
+            _ => ("(snippet)".to_string(), message.to_string()),
+        };
+        errors.push((key, line, reason));
+    }
+    errors
+}
+
+/// A public item declaration found by `find_pub_items`
+struct PubItem {
+    kind: ItemKind,
+    name: String,
+    line: usize,
+    documented: bool,
+}
+
+/// Public items in `content`, and whether a doc comment or `#[doc]` attribute
+/// is above them
+///
+/// Returns each item's kind, name, and line. Attribute lines (including
+/// multi-line ones), plain comments, and blank lines between the docs and the
+/// item are allowed.
+fn find_pub_items(content: &str, pub_item_re: &Regex) -> Vec<PubItem> {
+    let mut items = Vec::new();
+    let mut documented = false;
+    // Open brackets of a multi-line attribute, or inside a `/* */` comment
+    let mut attribute_depth = 0i32;
+    let mut in_block_comment = false;
+
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if in_block_comment {
+            in_block_comment = !trimmed.contains("*/");
+            continue;
+        }
+        if attribute_depth > 0 {
+            attribute_depth += bracket_balance(trimmed);
+            continue;
+        }
+
+        if trimmed.starts_with("///") || trimmed.starts_with("#[doc") {
+            documented = true;
+            attribute_depth = if trimmed.starts_with('#') { bracket_balance(trimmed) } else { 0 };
+        } else if let Some(rest) = trimmed.strip_prefix("/**") {
+            documented = true;
+            in_block_comment = !rest.contains("*/");
+        } else if let Some(rest) = trimmed.strip_prefix("/*") {
+            in_block_comment = !rest.contains("*/");
+        } else if trimmed.starts_with("#[") {
+            attribute_depth = bracket_balance(trimmed);
+        } else if trimmed.is_empty() || trimmed.starts_with("//") {
+            // Blank lines and plain comments keep the docs above them attached
+        } else {
+            if let Some(found) = pub_item_re.captures(line) {
+                let kind = match &found[1] {
+                    "fn" => ItemKind::Function,
+                    "struct" => ItemKind::Struct,
+                    "enum" => ItemKind::Enum,
+                    _ => ItemKind::Trait,
+                };
+                items.push(PubItem { kind, name: found[2].to_string(), line: index + 1, documented });
+            }
+            documented = false;
+        }
+    }
+    items
+}
+
+/// Lines with code: anything but whitespace and comments
+///
+/// Tracks nested `/* */` comments and string, raw string, and char literals
+/// across lines, so `"// not a comment"` is code and a line inside a multi-line
+/// string counts as code.
+fn count_code_lines(content: &str) -> usize {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Code,
+        BlockComment(usize),
+        Str,
+        RawStr(usize),
+    }
+
+    let mut state = State::Code;
+    let mut count = 0;
+    for line in content.lines() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut has_code = matches!(state, State::Str | State::RawStr(_)) && !line.trim().is_empty();
+        let mut i = 0;
+        while i < chars.len() {
+            let (c, next) = (chars[i], chars.get(i + 1).copied());
+            match state {
+                State::BlockComment(depth) => {
+                    if c == '*' && next == Some('/') {
+                        state = if depth == 1 { State::Code } else { State::BlockComment(depth - 1) };
+                        i += 1;
+                    } else if c == '/' && next == Some('*') {
+                        state = State::BlockComment(depth + 1);
+                        i += 1;
+                    }
+                }
+                State::Str => {
+                    if c == '\\' {
+                        i += 1;
+                    } else if c == '"' {
+                        state = State::Code;
+                    }
+                }
+                State::RawStr(hashes) => {
+                    if c == '"' && chars[i + 1..].iter().take(hashes).filter(|&&h| h == '#').count() == hashes {
+                        state = State::Code;
+                        i += hashes;
+                    }
+                }
+                State::Code => {
+                    if c == '/' && next == Some('/') {
+                        break;
+                    }
+                    if c == '/' && next == Some('*') {
+                        state = State::BlockComment(1);
+                        i += 2;
+                        continue;
+                    }
+                    if !c.is_whitespace() {
+                        has_code = true;
+                    }
+                    if c == '"' {
+                        state = State::Str;
+                    } else if c == 'r' && (next == Some('"') || next == Some('#')) && (i == 0 || !is_ident_char(chars[i - 1])) {
+                        let hashes = chars[i + 1..].iter().take_while(|&&h| h == '#').count();
+                        if chars.get(i + 1 + hashes) == Some(&'"') {
+                            state = State::RawStr(hashes);
+                            i += 1 + hashes;
+                        }
+                    } else if c == '\'' {
+                        // Char literal ('x', '\n', '\'') rather than a lifetime
+                        let len = match (next, chars.get(i + 2), chars.get(i + 3)) {
+                            (Some('\\'), Some(_), _) => chars[i + 2..].iter().position(|&q| q == '\'').map(|p| p + 2),
+                            (Some(_), Some('\''), _) => Some(2),
+                            _ => None,
+                        };
+                        i += len.unwrap_or(0);
+                    }
+                }
+            }
+            i += 1;
+        }
+        if has_code {
+            count += 1;
+        }
+    }
+    count
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// `[` minus `]` on a line
+fn bracket_balance(line: &str) -> i32 {
+    line.chars().map(|c| match c {
+        '[' => 1,
+        ']' => -1,
+        _ => 0,
+    }).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::{git, init_repo};
+
+    #[test]
+    fn test_undocumented_pub_items_are_found() {
+        let content = concat!(
+            "/// Documented\n",
+            "#[derive(\n",
+            "    Debug,\n",
+            ")]\n",
+            "pub struct Documented;\n",
+            "\n",
+            "pub struct Bare;\n",
+            "pub(crate) fn internal() {}\n",
+            "fn private() {}\n",
+            "impl Bare {\n",
+            "    pub fn new() -> Self { Bare }\n",
+            "    /** Block docs */\n",
+            "    pub async fn run(&self) {}\n",
+            "    pub const unsafe fn raw() {}\n",
+            "}\n",
+            "#[doc = \"Attribute docs\"]\n",
+            "pub enum Mode { A }\n",
+            "// not a doc comment\n",
+            "pub trait Plugin {}\n",
+        );
+        let items = find_pub_items(content, &DocValidator::new().pub_item_re);
+        let undocumented: Vec<(ItemKind, String, usize)> = items.iter()
+            .filter(|item| !item.documented)
+            .map(|item| (item.kind, item.name.clone(), item.line))
+            .collect();
+        assert_eq!(undocumented, vec![
+            (ItemKind::Struct, "Bare".to_string(), 7),
+            (ItemKind::Function, "new".to_string(), 11),
+            (ItemKind::Function, "raw".to_string(), 14),
+            (ItemKind::Trait, "Plugin".to_string(), 19),
+        ]);
+    }
+
+    #[test]
+    fn test_item_docs_are_opt_in_and_respect_allowlist() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("lib.rs");
+        fs::write(&file, "//! Short module\n\npub fn new() {}\npub fn with_x() {}\npub fn run() {}\n").unwrap();
+
+        let report = DocValidator::new().validate_paths(vec![file.clone()]).unwrap();
+        assert!(report.passed(true));
+
+        let validator = DocValidator::new()
+            .with_require_pub_item_docs(true)
+            .with_pub_item_allowlist(vec!["new".to_string(), "with_*".to_string()])
+            .unwrap();
+        let report = validator.validate_paths(vec![file]).unwrap();
+        assert_eq!(report.warnings, 1);
+        assert!(!report.passed(true));
+        assert!(matches!(
+            report.file_issues[0].1.as_slice(),
+            [ValidationIssue::MissingItemDocs { item, kind: ItemKind::Function, line: 5 }] if item == "run"
+        ));
+    }
+
+    #[test]
+    fn test_baseline_suppresses_known_issues_and_prunes_fixed_ones() {
+        let temp = tempfile::tempdir().unwrap();
+        let (old, fixed) = (temp.path().join("old.rs"), temp.path().join("fixed.rs"));
+        fs::write(&old, "pub fn a() {}\n").unwrap();
+        fs::write(&fixed, "pub fn b() {}\n").unwrap();
+        let validator = || DocValidator::new().with_require_pub_item_docs(true);
+
+        let mut baseline = DocsBaseline::default();
+        baseline.update(&validator().validate_paths(vec![temp.path().to_path_buf()]).unwrap());
+        assert_eq!(baseline.issues.len(), 2);
+        let baseline_file = DocsBaseline::path(temp.path());
+        baseline.save(&baseline_file).unwrap();
+        let baseline = DocsBaseline::load(&baseline_file).unwrap();
+
+        // Known issues pass strict mode; a new item in a baselined file does not
+        fs::write(&old, "pub fn a() {}\npub fn c() {}\n").unwrap();
+        fs::write(&fixed, "/// Documented\npub fn b() {}\n").unwrap();
+        let report = validator().with_baseline(baseline.clone()).validate_paths(vec![temp.path().to_path_buf()]).unwrap();
+        assert_eq!((report.suppressed, report.warnings), (1, 1));
+        assert!(!report.passed(true));
+
+        let mut rewritten = baseline.clone();
+        rewritten.update(&validator().validate_paths(vec![old.clone(), fixed.clone()]).unwrap());
+        let items: Vec<Option<&str>> = rewritten.issues.iter().map(|entry| entry.item.as_deref()).collect();
+        assert_eq!(items, vec![Some("a"), Some("c")]);
+
+        // Files outside a partial run keep their entries
+        let mut partial = baseline;
+        partial.update(&validator().validate_paths(vec![old]).unwrap());
+        assert_eq!(partial.issues.len(), 3);
+    }
+
+    #[test]
+    fn test_first_matching_override_sets_thresholds() {
+        let temp = tempfile::tempdir().unwrap();
+        let core = temp.path().join("src/core");
+        fs::create_dir_all(&core).unwrap();
+        let module = format!("//! One line of docs\n{}", "fn f() {}\n".repeat(20));
+        fs::write(core.join("engine.rs"), &module).unwrap();
+        fs::write(temp.path().join("src/lib.rs"), &module).unwrap();
+
+        let strict_core = |pattern: &str| ThresholdOverride {
+            pattern: pattern.to_string(),
+            min_doc_lines_complex: Some(5),
+            max_doc_lines: None,
+            complexity_threshold: Some(10),
+        };
+        let validator = DocValidator::new()
+            .with_overrides(vec![
+                strict_core(&format!("{}/src/core/**", temp.path().display())),
+                ThresholdOverride { min_doc_lines_complex: Some(1), ..strict_core("**/engine.rs") },
+            ])
+            .unwrap();
+        let report = validator.validate_paths(vec![temp.path().join("src")]).unwrap();
+        assert_eq!(report.warnings, 1);
+        let (file, issues) = &report.file_issues[0];
+        assert!(file.path.ends_with("src/core/engine.rs"));
+        assert!(file.override_pattern.as_deref().unwrap().ends_with("src/core/**"));
+        assert!(matches!(issues[..], [ValidationIssue::InsufficientDocs { lines: 1, min: 5, total_lines: 21, code_lines: 20 }]));
+        assert_eq!(report.simple_files_skipped[0].override_pattern, None);
+
+        let error = DocValidator::new().with_overrides(vec![strict_core("src/[core")]).err().unwrap();
+        assert!(error.to_string().contains("src/[core"));
+    }
+
+    #[test]
+    fn test_code_lines_skip_blanks_and_comments() {
+        let content = concat!(
+            "//! Module docs\n",
+            "// License header\n",
+            "\n",
+            "/* block\n",
+            "   /* nested */ still a comment\n",
+            "*/\n",
+            "/// Item docs\n",
+            "fn f() -> &'static str { // trailing comment\n",
+            "    let c = '\"';\n",
+            "    let s = \"// not a comment\n",
+            "\n",
+            "    /* inside the string */\";\n",
+            "    let r = r#\"\"/*\"#; /* done */\n",
+            "    s\n",
+            "}\n",
+        );
+        assert_eq!(count_code_lines(content), 7);
+    }
+
+    #[test]
+    fn test_complexity_uses_code_lines_unless_raw_count_is_set() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("lib.rs");
+        let header = "// Licensed under the Apache License\n".repeat(60);
+        fs::write(&file, format!("{}//! Short docs\n\n{}", header, "fn f() {}\n\n".repeat(80))).unwrap();
+
+        let report = DocValidator::new().validate_paths(vec![file.clone()]).unwrap();
+        assert_eq!(report.warnings, 0);
+        assert_eq!((report.simple_files_skipped[0].total_lines, report.simple_files_skipped[0].code_lines), (222, 80));
+
+        let report = DocValidator::new().with_use_raw_line_count(true).validate_paths(vec![file]).unwrap();
+        assert_eq!(report.warnings, 1);
+        assert!(report.file_issues[0].1[0].message().starts_with("Complex module (222 lines, 80 code)"));
+    }
+
+    #[test]
+    fn test_changed_since_validates_only_changed_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path();
+        let files = ["src/same.rs", "src/edited.rs", "src/deleted.rs", "other/outside.rs"];
+        init_repo(repo, &files.map(|file| (file, "pub fn f() {}\n")));
+
+        fs::write(repo.join("src/edited.rs"), "pub fn g() {}\n").unwrap();
+        fs::write(repo.join("src/new.rs"), "pub fn h() {}\n").unwrap();
+        fs::write(repo.join("src/notes.txt"), "not rust\n").unwrap();
+        fs::write(repo.join("other/outside.rs"), "pub fn g() {}\n").unwrap();
+        fs::remove_file(repo.join("src/deleted.rs")).unwrap();
+
+        let report = DocValidator::new()
+            .with_require_pub_item_docs(true)
+            .with_changed_since("HEAD")
+            .validate_paths(vec![repo.join("src")])
+            .unwrap();
+        let mut validated: Vec<String> = report.files()
+            .map(|file| file.path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        validated.sort();
+        assert_eq!(validated, vec!["edited.rs", "new.rs"]);
+        assert_eq!((report.files_scanned, report.unchanged_skipped, report.warnings), (2, 1, 2));
+        assert_eq!(report.changed_since.as_deref(), Some("HEAD"));
+
+        // Against a commit: only what changed since it
+        git(repo, &["add", "."]);
+        git(repo, &["commit", "-q", "-m", "next"]);
+        let report = DocValidator::new().with_changed_since("HEAD").validate_paths(vec![repo.join("src")]).unwrap();
+        assert_eq!((report.files_scanned, report.unchanged_skipped), (0, 3));
+        let report = DocValidator::new().with_changed_since("HEAD~1").validate_paths(vec![repo.join("src")]).unwrap();
+        assert_eq!((report.files_scanned, report.unchanged_skipped), (2, 1));
+    }
+
+    #[test]
+    fn test_fix_inserts_template_after_comments_and_above_attributes() {
+        let temp = tempfile::tempdir().unwrap();
+        let body = "fn f() {}\n".repeat(5);
+        let cases = [
+            ("attrs.rs", format!("#![allow(dead_code)]\n{}", body),
+             format!("//! attrs\n//!\n//! TODO: 6 lines\n\n#![allow(dead_code)]\n{}", body)),
+            ("licensed.rs", format!("// Copyright\n// Apache-2.0\n\nuse std::fs;\n{}", body),
+             format!("// Copyright\n// Apache-2.0\n\n//! licensed\n//!\n//! TODO: 9 lines\n\nuse std::fs;\n{}", body)),
+            ("script.rs", format!("\u{feff}#!/usr/bin/env rust-script\r\nfn main() {{}}\r\n{}", body.replace('\n', "\r\n")),
+             format!("\u{feff}#!/usr/bin/env rust-script\r\n//! script\r\n//!\r\n//! TODO: 7 lines\r\n\r\nfn main() {{}}\r\n{}", body.replace('\n', "\r\n"))),
+            ("later.rs", format!("{}//! docs after code are left alone\n", body), format!("{}//! docs after code are left alone\n", body)),
+        ];
+        for (name, before, _) in &cases {
+            fs::write(temp.path().join(name), before).unwrap();
+        }
+        let validator = || DocValidator::new()
+            .with_complexity_threshold(2)
+            .with_fix(true)
+            .with_doc_template("{module_name}\n\nTODO: {line_count} lines".to_string());
+
+        let report = validator().with_dry_run(true).validate_paths(vec![temp.path().to_path_buf()]).unwrap();
+        assert_eq!(report.fixes.len(), 3);
+        assert!(report.fixes.iter().all(|fix| !fix.applied));
+        for (name, before, _) in &cases {
+            assert_eq!(&fs::read_to_string(temp.path().join(name)).unwrap(), before);
+        }
+
+        let report = validator().validate_paths(vec![temp.path().to_path_buf()]).unwrap();
+        let mut fixed: Vec<(String, usize)> = report.fixes.iter()
+            .map(|fix| (fix.path.file_name().unwrap().to_string_lossy().into_owned(), fix.line))
+            .collect();
+        fixed.sort();
+        assert_eq!(fixed, vec![("attrs.rs".to_string(), 1), ("licensed.rs".to_string(), 4), ("script.rs".to_string(), 2)]);
+        for (name, _, after) in &cases {
+            assert_eq!(&fs::read_to_string(temp.path().join(name)).unwrap(), after, "{}", name);
+        }
+        // Rewritten files are validated as they are now: three lines is not enough
+        let first_issues: Vec<(String, &str)> = report.file_issues.iter()
+            .map(|(info, issues)| (info.path.file_name().unwrap().to_string_lossy().into_owned(), issues[0].rule_id()))
+            .collect();
+        for name in ["attrs.rs", "licensed.rs", "script.rs"] {
+            assert!(first_issues.contains(&(name.to_string(), "insufficient-docs")), "{:?}", first_issues);
+        }
+    }
+
+    #[test]
+    fn test_stale_references_in_spans_and_examples() {
+        let content = r#"//! Logging setup
+//!
+//! Call `init_logging_with_rotating_file()` or `init_logging`; `tokio::spawn` and
+//! `std::fs::read` are external, and `json` is just a word.
+//!
+//! ```rust
+//! use mycrate::logger::{init_logging, LogGuard};
+//! # fn setup() -> LogGuard { init_logging(Some("info")) }
+//! let guard = setup();
+//! guard.flush_all();
+//! let parsed = Config::load_from("missing_fn(x)");
+//! ```
+//!
+//! ```text
+//! removed_helper()
+//! ```
+"#;
+        let symbols: HashSet<String> = ["logger", "init_logging", "LogGuard", "Config"].iter().map(|s| s.to_string()).collect();
+        let allowlist = vec![Pattern::new("tokio::*").unwrap(), Pattern::new("std::*").unwrap()];
+        let stale = find_stale_references(content, &symbols, &allowlist, &DocValidator::new().ident_re);
+        assert_eq!(stale, vec![
+            ("init_logging_with_rotating_file".to_string(), 3),
+            ("Config::load_from".to_string(), 11),
+        ]);
+    }
+
+    #[test]
+    fn test_check_references_indexes_every_scanned_file() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("lib.rs"), "//! See `helpers::format_report` and `render_old()`.\npub mod helpers;\n").unwrap();
+        fs::write(temp.path().join("helpers.rs"), "/// Not counted: `render_old`\npub fn format_report() {}\n").unwrap();
+
+        let report = DocValidator::new()
+            .with_check_references(true)
+            .validate_paths(vec![temp.path().to_path_buf()])
+            .unwrap();
+        let issues: Vec<(&str, Option<usize>)> = report.file_issues.iter()
+            .flat_map(|(_, issues)| issues)
+            .map(|issue| (issue.item().unwrap(), issue.line()))
+            .collect();
+        assert_eq!(issues, vec![("render_old", Some(1))]);
+        assert_eq!(report.file_issues[0].1[0].rule_id(), "stale-reference");
+    }
+
+    #[test]
+    fn test_parallel_validation_matches_serial() {
+        let temp = tempfile::tempdir().unwrap();
+        for i in 0..500 {
+            let dir = temp.path().join(format!("m{}", i % 7));
+            fs::create_dir_all(&dir).unwrap();
+            let docs = "//! docs\n".repeat(i % 4);
+            let body = format!("pub fn f{}() {{}}\n", i).repeat(i % 30);
+            fs::write(dir.join(format!("f{}.rs", i)), format!("{}{}", docs, body)).unwrap();
+        }
+        // Not UTF-8: reported, not fatal
+        fs::write(temp.path().join("m0/binary.rs"), [0xff, 0xfe, 0x00]).unwrap();
+
+        let run = |jobs| {
+            let report = DocValidator::new()
+                .with_complexity_threshold(10)
+                .with_min_doc_lines(2)
+                .with_require_pub_item_docs(true)
+                .with_jobs(jobs)
+                .validate_paths(vec![temp.path().to_path_buf()])
+                .unwrap();
+            serde_json::to_string(&report).unwrap()
+        };
+        let serial = run(1);
+        assert_eq!(run(8), serial);
+
+        let report: serde_json::Value = serde_json::from_str(&serial).unwrap();
+        assert_eq!(report["files_scanned"], 501);
+        let unreadable: Vec<&serde_json::Value> = report["file_issues"].as_array().unwrap().iter()
+            .filter(|file| file["issues"][0]["type"] == "unreadable_file")
+            .collect();
+        assert_eq!(unreadable.len(), 1);
+        assert!(unreadable[0]["path"].as_str().unwrap().ends_with("binary.rs"));
+    }
+
+    #[test]
+    fn test_cancelled_run_returns_partial_report() {
+        let temp = tempfile::tempdir().unwrap();
+        for i in 0..20 {
+            fs::write(temp.path().join(format!("f{}.rs", i)), "fn f() {}\n").unwrap();
+        }
+        let cancel = Arc::new(AtomicBool::new(true));
+        let report = DocValidator::new()
+            .with_cancel_flag(cancel.clone())
+            .validate_paths(vec![temp.path().to_path_buf()])
+            .unwrap();
+        assert!(report.cancelled);
+        assert_eq!(report.files_scanned, 0);
+
+        cancel.store(false, Ordering::Relaxed);
+        let report = DocValidator::new()
+            .with_cancel_flag(cancel)
+            .validate_paths(vec![temp.path().to_path_buf()])
+            .unwrap();
+        assert!(!report.cancelled);
+        assert_eq!(report.files_scanned, 20);
+    }
+
+    #[test]
+    fn test_validate_file_matches_a_full_run() {
+        let temp = tempfile::tempdir().unwrap();
+        let body = "fn f() {}\n".repeat(5);
+        let bare = temp.path().join("bare.rs");
+        fs::write(&bare, &body).unwrap();
+        fs::write(temp.path().join("bare_test.rs"), &body).unwrap();
+        let validator = DocValidator::new()
+            .with_complexity_threshold(3)
+            .with_ignore_patterns(vec!["**/*_test.rs".to_string()])
+            .unwrap();
+
+        let full = validator.validate_paths(vec![temp.path().to_path_buf()]).unwrap();
+        let single = validator.validate_file(&bare);
+        assert_eq!(serde_json::to_value(&single).unwrap(), serde_json::to_value(&full).unwrap());
+        assert_eq!(single.warnings, 1);
+
+        // Ignored, then gone: neither is an error
+        assert!(!validator.accepts(&temp.path().join("bare_test.rs")));
+        assert_eq!(validator.validate_file(&temp.path().join("bare_test.rs")).files_scanned, 0);
+        fs::remove_file(&bare).unwrap();
+        let gone = validator.validate_file(&bare);
+        assert_eq!(gone.file_issues[0].1[0].rule_id(), "unreadable-file");
+    }
+
+    #[test]
+    fn test_coverage_counts_documented_modules_and_items() {
+        let temp = tempfile::tempdir().unwrap();
+        let body = "fn f() {}\n".repeat(5);
+        fs::write(temp.path().join("good.rs"), format!("//! a\n//! b\n/// Documented\npub fn run() {{}}\n{}", body)).unwrap();
+        fs::write(temp.path().join("short.rs"), format!("//! a\npub fn bare() {{}}\n{}", body)).unwrap();
+        fs::write(temp.path().join("none.rs"), format!("pub fn new() {{}}\n{}", body)).unwrap();
+        fs::write(temp.path().join("simple.rs"), "/// Documented\npub fn tiny() {}\n").unwrap();
+
+        let validator = DocValidator::new().with_complexity_threshold(3).with_min_doc_lines(2);
+        let coverage = validator.validate_paths(vec![temp.path().to_path_buf()]).unwrap().coverage();
+        assert_eq!((coverage.complex_documented, coverage.complex_total), (1, 3));
+        assert!((coverage.percent - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(coverage.pub_items_percent, None);
+
+        // `new` is allowlisted, so 2 of 3 checked items are documented
+        let coverage = validator.with_require_pub_item_docs(true)
+            .validate_paths(vec![temp.path().to_path_buf()]).unwrap().coverage();
+        assert_eq!((coverage.pub_items_documented, coverage.pub_items_total), (2, 3));
+
+        // No complex modules is full coverage
+        let coverage = DocValidator::new().validate_paths(vec![temp.path().join("simple.rs")]).unwrap().coverage();
+        assert_eq!((coverage.complex_total, coverage.percent), (0, 100.0));
+    }
+
+    #[test]
+    fn test_severities_decide_whether_a_run_passes() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("big.rs"), "fn f() {}\n".repeat(5)).unwrap();
+        fs::write(temp.path().join("wordy.rs"), "//! docs\n".repeat(5)).unwrap();
+        let run = |severities: &[(Rule, Severity)]| DocValidator::new()
+            .with_complexity_threshold(2)
+            .with_max_doc_lines(2)
+            .with_severities(severities.iter().copied().collect())
+            .validate_paths(vec![temp.path().to_path_buf()])
+            .unwrap();
+
+        let report = run(&[]);
+        assert_eq!((report.errors, report.warnings, report.infos), (0, 2, 0));
+        assert!(report.passed(false) && !report.passed(true));
+
+        let report = run(&[(Rule::NoDocs, Severity::Error), (Rule::ExcessiveDocs, Severity::Info)]);
+        assert_eq!((report.errors, report.warnings, report.infos), (1, 0, 1));
+        assert!(!report.passed(false));
+        assert_eq!(report.to_sarif()["runs"][0]["results"][0]["level"], "error");
+        assert!(report.github_annotations()[1].starts_with("::notice "));
+        assert_eq!(report.to_json()["file_issues"][1]["issues"][0]["severity"], "info");
+
+        let report = run(&[(Rule::NoDocs, Severity::Info), (Rule::ExcessiveDocs, Severity::Info)]);
+        assert!(report.passed(true));
+    }
+
+    #[test]
+    fn test_unknown_rule_in_severities_lists_valid_names() {
+        let config: crate::config::Config = serde_yaml::from_str("validate_docs:\n  severities:\n    no_docs: error\n").unwrap();
+        assert_eq!(config.validate_docs.severities.get(&Rule::NoDocs), Some(&Severity::Error));
+
+        let error = serde_yaml::from_str::<crate::config::Config>("validate_docs:\n  severities:\n    no_doc: error\n")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("no_doc") && error.contains("insufficient_docs") && error.contains("unreadable_file"), "{}", error);
+    }
+
+    #[test]
+    fn test_config_snippets_are_checked_against_the_schema() {
+        let content = concat!(
+            "//! Settings:\n",
+            "//!\n",
+            "//! ```yaml\n",
+            "//! validate_docs:\n",
+            "//!   min_doc_lines_complex: 50\n",
+            "//!   min_doc_lines: 10\n",
+            "//!   overrides:\n",
+            "//!     - pattern: \"src/**\"\n",
+            "//!       max_lines: 5\n",
+            "//! todo:\n",
+            "//!   extensions: [rs]\n",
+            "//! ```\n",
+            "//!\n",
+            "//! ```yaml\n",
+            "//! remove_debug:\n",
+            "//!   jobs: many\n",
+            "//! ```\n",
+            "//!\n",
+            "//! ```text\n",
+            "//! not_config: true\n",
+            "//! ```\n",
+        );
+        let errors = find_config_snippet_errors(content);
+        let keys: Vec<(&str, usize)> = errors.iter().map(|(key, line, _)| (key.as_str(), *line)).collect();
+        assert_eq!(keys, vec![
+            ("validate_docs.min_doc_lines", 6),
+            ("validate_docs.overrides.0.max_lines", 9),
+            ("todo", 10),
+            ("remove_debug.jobs", 16),
+        ]);
+        assert_eq!(errors[0].2, "unknown key");
+        assert!(errors[3].2.contains("invalid"), "{}", errors[3].2);
+    }
+
+    #[test]
+    fn test_placeholder_docs_do_not_count_toward_the_minimum() {
+        let temp = tempfile::tempdir().unwrap();
+        let placeholders = "//! TODO: document this properly\n//! FIXME: describe the types\n";
+        fs::write(temp.path().join("todo.rs"), format!("{}{}", placeholders, "fn f() {}\n".repeat(20))).unwrap();
+        let validator = || DocValidator::new().with_min_doc_lines(2).with_complexity_threshold(10);
+
+        let report = validator().validate_paths(vec![temp.path().to_path_buf()]).unwrap();
+        let (file, issues) = &report.file_issues[0];
+        assert_eq!(file.doc_lines, 0);
+        assert!(matches!(issues[0], ValidationIssue::InsufficientDocs { lines: 0, min: 2, .. }));
+        assert!(matches!(&issues[1], ValidationIssue::PlaceholderDocs { count: 2, lines } if *lines == [1, 2]));
+        let summary = report.render_summary(&SummaryOptions { verbose: true, ..SummaryOptions::default() });
+        assert!(summary.contains("Documentation is mostly placeholders (2 TODO/FIXME line(s))\n    line(s) 1, 2\n"), "{}", summary);
+
+        let report = validator().with_count_placeholder_lines(true).validate_paths(vec![temp.path().to_path_buf()]).unwrap();
+        assert!(matches!(report.file_issues[0].1[..], [ValidationIssue::PlaceholderDocs { count: 2, .. }]));
+
+        let report = validator().with_placeholder_markers(Vec::new()).validate_paths(vec![temp.path().to_path_buf()]).unwrap();
+        assert!(report.file_issues.is_empty());
+    }
+
+    #[test]
+    fn test_few_placeholders_are_tolerated() {
+        let validator = DocValidator::new();
+        let docs = validator.module_docs("//! Parser\n//!\n//! Reads TODO.md files.\n//! XXXL sizes are not markers\nfn f() {}\n");
+        assert_eq!((docs.lines, docs.placeholders), (4, vec![3]));
+
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("lib.rs"), "//! Parser\n//!\n//! Reads TODO.md files.\n//! More docs.\nfn f() {}\n").unwrap();
+        let report = validator.validate_paths(vec![temp.path().to_path_buf()]).unwrap();
+        assert!(report.file_issues.is_empty());
+        let report = DocValidator::new().with_max_placeholder_fraction(0.2).validate_paths(vec![temp.path().to_path_buf()]).unwrap();
+        assert_eq!(report.warnings, 1);
+    }
+
+    fn report_with_issues() -> ValidationReport {
+        let file = |path: &str| FileInfo {
+            path: PathBuf::from(path),
+            doc_lines: 0,
+            total_lines: 300,
+            code_lines: 250,
+            is_complex: true,
+            min_doc_lines: 50,
+            pub_items: 1,
+            pub_items_documented: 0,
+            override_pattern: None,
+        };
+        ValidationReport {
+            files_scanned: 2,
+            errors: 0,
+            warnings: 3,
+            infos: 0,
+            severities: HashMap::new(),
+            suppressed: 0,
+            changed_since: None,
+            unchanged_skipped: 0,
+            fixes: Vec::new(),
+            cancelled: false,
+            complex_files_passed: Vec::new(),
+            simple_files_skipped: Vec::new(),
+            file_issues: vec![
+                (file("src/a.rs"), vec![
+                    ValidationIssue::NoDocs { total_lines: 300, code_lines: 250 },
+                    ValidationIssue::MissingItemDocs { item: "run".to_string(), kind: ItemKind::Function, line: 12 },
+                ]),
+                (file("src/b.rs"), vec![ValidationIssue::ExcessiveDocs { lines: 250, max: 200 }]),
+            ],
+        }
+    }
+
+    /// `report_with_issues` plus a module in a subdirectory, with item docs as errors
+    fn summary_report() -> ValidationReport {
+        let mut report = report_with_issues();
+        let nested = FileInfo { path: PathBuf::from("src/monitor/c.rs"), doc_lines: 10, ..report.file_issues[1].0.clone() };
+        report.file_issues.push((nested, vec![
+            ValidationIssue::InsufficientDocs { lines: 10, min: 70, total_lines: 300, code_lines: 250 },
+        ]));
+        report.files_scanned = 3;
+        report.errors = 1;
+        report.severities = HashMap::from([(Rule::MissingItemDocs, Severity::Error)]);
+        report
+    }
+
+    #[test]
+    fn test_summary_lists_worst_offenders_first() {
+        let summary = summary_report().render_summary(&SummaryOptions::default());
+        assert_eq!(summary, "\nDocumentation Validation Report\n\
+            ===============================\n\
+            3 files scanned: 3 complex (validated), 0 simple (skipped)\n\
+            \n❌ Errors (1 modules):\n\
+            \x20 src/a.rs: 1 public item(s) without documentation\n\
+            \x20   line 12: function run\n\
+            \n⚠️  Warnings (3 modules):\n\
+            \x20 src/monitor/c.rs: Complex module (300 lines, 250 code) has insufficient documentation (10 lines, minimum 70)\n\
+            \x20 src/a.rs: Complex module (300 lines, 250 code) has no documentation (use //! format)\n\
+            \x20 src/b.rs: Excessive documentation (250 lines, maximum 200)\n\
+            \n❌ 1 error(s), 3 warning(s) found.\n\
+            Coverage: 0.0% of complex modules documented (0/3), 0.0% of public items (0/3)\n");
+    }
+
+    #[test]
+    fn test_summary_top_and_group_by_directory() {
+        let options = SummaryOptions { top: Some(3), group_by_directory: true, ..SummaryOptions::default() };
+        let summary = summary_report().render_summary(&options);
+        assert_eq!(summary, "\nDocumentation Validation Report\n\
+            ===============================\n\
+            3 files scanned: 3 complex (validated), 0 simple (skipped)\n\
+            \n❌ Errors (1 modules):\n\
+            \x20 src/ (1 modules, 1 issues)\n\
+            \x20   src/a.rs: 1 public item(s) without documentation\n\
+            \x20     line 12: function run\n\
+            \n⚠️  Warnings (2 modules):\n\
+            \x20 src/monitor/ (1 modules, 1 issues)\n\
+            \x20   src/monitor/c.rs: Complex module (300 lines, 250 code) has insufficient documentation (10 lines, minimum 70)\n\
+            \x20 src/ (1 modules, 1 issues)\n\
+            \x20   src/a.rs: Complex module (300 lines, 250 code) has no documentation (use //! format)\n\
+            \n... and 1 more (showing the 3 worst)\n\
+            \n❌ 1 error(s), 3 warning(s) found.\n\
+            Coverage: 0.0% of complex modules documented (0/3), 0.0% of public items (0/3)\n");
+    }
+
+    #[test]
+    fn test_json_report_nests_issues_under_files() {
+        let json = report_with_issues().to_json();
+        assert_eq!(json["warnings"], 3);
+        assert_eq!(json["coverage"]["complex_total"], 2);
+        assert_eq!(json["coverage"]["percent"], 0.0);
+        assert_eq!(json["file_issues"][0]["path"], "src/a.rs");
+        assert_eq!(json["file_issues"][0]["total_lines"], 300);
+        assert_eq!(json["file_issues"][0]["issues"][0], serde_json::json!({ "type": "no_docs", "total_lines": 300, "code_lines": 250, "severity": "warning" }));
+        assert_eq!(json["file_issues"][0]["issues"][1]["kind"], "function");
+        assert_eq!(json["file_issues"][1]["issues"][0]["type"], "excessive_docs");
+    }
+
+    #[test]
+    fn test_github_annotations() {
+        assert_eq!(report_with_issues().github_annotations(), vec![
+            "::warning file=src/a.rs,line=1::Complex module (300 lines, 250 code) has no documentation (use //! format)",
+            "::warning file=src/a.rs,line=12::Public function `run` has no documentation (use /// format)",
+            "::warning file=src/b.rs,line=1::Excessive documentation (250 lines, maximum 200)",
+        ]);
+    }
+
+    #[test]
+    fn test_sarif_log_is_schema_shaped() {
+        let sarif = report_with_issues().to_sarif();
+        assert_eq!(sarif["version"], "2.1.0");
+        assert!(sarif["$schema"].as_str().unwrap().ends_with("sarif-2.1.0.json"));
+        let runs = sarif["runs"].as_array().unwrap();
+        assert_eq!(runs.len(), 1);
+
+        let driver = &runs[0]["tool"]["driver"];
+        assert!(driver["name"].is_string());
+        let rule_ids: Vec<&str> = driver["rules"].as_array().unwrap().iter()
+            .map(|rule| {
+                assert!(rule["shortDescription"]["text"].is_string());
+                rule["id"].as_str().unwrap()
+            })
+            .collect();
+
+        let results = runs[0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        for result in results {
+            assert!(rule_ids.contains(&result["ruleId"].as_str().unwrap()));
+            assert!(["none", "note", "warning", "error"].contains(&result["level"].as_str().unwrap()));
+            assert!(result["message"]["text"].is_string());
+            let location = &result["locations"][0]["physicalLocation"];
+            assert!(location["artifactLocation"]["uri"].is_string());
+            assert!(location["region"]["startLine"].as_u64().unwrap() >= 1);
+        }
+        let lines: Vec<u64> = results.iter()
+            .map(|r| r["locations"][0]["physicalLocation"]["region"]["startLine"].as_u64().unwrap())
+            .collect();
+        assert_eq!(lines, vec![1, 12, 1]);
+        assert_eq!(results[1]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "src/a.rs");
+    }
+}
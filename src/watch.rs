@@ -0,0 +1,487 @@
+//! Edit-time feedback: re-checking files as they change (`--watch`)
+//!
+//! [`FileWatcher`] watches the checked paths with `notify` and hands out
+//! [`Changes`]: events are collected until `debounce` passes without another, so
+//! the burst of writes, renames, and chmods an editor makes for one save is
+//! checked once. Each path in a batch is sorted by whether it exists when the
+//! batch is handed out, so a file deleted or renamed away is dropped from the
+//! results and the new name of a renamed file arrives as a change. Reads (ours
+//! included) are not changes.
+//!
+//! [`Tally`] keeps the latest [`FileFindings`] of every checked file, so the
+//! header of each screen shows the live totals for the whole tree rather than
+//! just the files that changed. [`run`] ties the two together for
+//! `validate-docs --watch` and `remove-debug --check --watch`: it checks every
+//! file once, then re-checks only what changes, redrawing the screen each time
+//! (cleared, like `cargo watch -c`, when `clear_screen` is set).
+//!
+//! Paths are kept relative to the working directory, as the checks are given
+//! them on the command line, so ignore patterns match the same way.
+
+use anyhow::{Context, Result};
+use notify::event::{AccessKind, AccessMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+use walkdir::WalkDir;
+
+use crate::remove_debug::RemovalReport;
+use crate::validate_docs::{Severity, ValidationReport};
+
+/// Quiet period that ends a batch of events by default
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Moves the cursor home and clears the terminal
+pub const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// One debounced batch of file system changes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Changes {
+    /// Files written or created (including renamed to), sorted
+    pub changed: Vec<PathBuf>,
+    /// Files and directories that no longer exist, sorted
+    pub removed: Vec<PathBuf>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+
+    /// Sort `paths` by whether they exist now; a directory that appeared (e.g.
+    /// renamed into place) contributes the files under it
+    fn classify(paths: BTreeSet<PathBuf>) -> Self {
+        let mut changes = Changes::default();
+        for path in paths {
+            if path.is_dir() {
+                changes.changed.extend(
+                    WalkDir::new(&path).into_iter()
+                        .filter_map(|entry| entry.ok())
+                        .filter(|entry| entry.file_type().is_file())
+                        .map(|entry| entry.into_path()),
+                );
+            } else if path.exists() {
+                changes.changed.push(path);
+            } else {
+                changes.removed.push(path);
+            }
+        }
+        changes.changed.sort();
+        changes.changed.dedup();
+        // A removed directory stands for the files under it
+        let mut removed: Vec<PathBuf> = Vec::new();
+        for path in changes.removed {
+            if !removed.last().is_some_and(|dir| path.starts_with(dir)) {
+                removed.push(path);
+            }
+        }
+        changes.removed = removed;
+        changes
+    }
+}
+
+/// A recursive watch on some paths that yields debounced [`Changes`]
+pub struct FileWatcher {
+    // Dropping the watcher stops the events
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    /// Working directory when the watch started; changes are relative to it
+    root: PathBuf,
+    debounce: Duration,
+}
+
+impl FileWatcher {
+    /// Watch `paths` (files or directories, recursively)
+    pub fn new(paths: &[PathBuf], debounce: Duration) -> Result<Self> {
+        let root = std::env::current_dir().context("Failed to get the current directory")?;
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).context("Failed to start the file watcher")?;
+        for path in paths {
+            if !path.exists() {
+                return Err(crate::error::not_found(format_args!("Path does not exist: {}", path.display())));
+            }
+            watcher.watch(&root.join(clean(path)), RecursiveMode::Recursive)
+                .with_context(|| format!("Failed to watch {}", path.display()))?;
+        }
+        Ok(Self { _watcher: watcher, events, root, debounce })
+    }
+
+    /// Block until something changes and the changes settle; None once the
+    /// watcher has stopped
+    pub fn next_changes(&self) -> Option<Changes> {
+        self.next_batch(None)
+    }
+
+    /// As `next_changes`, giving up after `wait` without any event
+    fn next_batch(&self, wait: Option<Duration>) -> Option<Changes> {
+        let paths = collect_batch(&self.events, wait, self.debounce)?;
+        let canonical_root = self.root.canonicalize().ok();
+        let paths = paths.into_iter()
+            .map(|path| {
+                let relative = path.strip_prefix(&self.root).ok()
+                    .or_else(|| canonical_root.as_ref().and_then(|root| path.strip_prefix(root).ok()));
+                clean(relative.unwrap_or(&path))
+            })
+            .collect();
+        Some(Changes::classify(paths))
+    }
+}
+
+/// Paths of the events received until `debounce` passes without one, after
+/// waiting up to `wait` (forever if None) for the first; None if the channel
+/// closed or nothing came
+fn collect_batch(
+    events: &Receiver<notify::Result<Event>>,
+    wait: Option<Duration>,
+    debounce: Duration,
+) -> Option<BTreeSet<PathBuf>> {
+    let mut paths = BTreeSet::new();
+    let mut next = match wait {
+        Some(wait) => events.recv_timeout(wait).ok()?,
+        None => events.recv().ok()?,
+    };
+    loop {
+        match next {
+            Ok(event) if is_change(&event) => paths.extend(event.paths),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("File watcher error: {}", e),
+        }
+        // Keep waiting while only reads come in, so a batch is never empty
+        let timeout = if paths.is_empty() { wait.unwrap_or(Duration::MAX) } else { debounce };
+        next = match events.recv_timeout(timeout) {
+            Ok(event) => event,
+            Err(RecvTimeoutError::Timeout) if !paths.is_empty() => return Some(paths),
+            Err(RecvTimeoutError::Timeout) => return None,
+            Err(RecvTimeoutError::Disconnected) => return (!paths.is_empty()).then_some(paths),
+        };
+    }
+}
+
+/// Anything but a read, or closing a file without writing it
+fn is_change(event: &Event) -> bool {
+    match event.kind {
+        EventKind::Access(AccessKind::Close(AccessMode::Write)) => true,
+        EventKind::Access(_) => false,
+        _ => true,
+    }
+}
+
+/// `path` without `.` components, so `./src/lib.rs` and `src/lib.rs` are one file
+pub fn clean(path: &Path) -> PathBuf {
+    path.components().filter(|component| *component != Component::CurDir).collect()
+}
+
+/// What a check found in one file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileFindings {
+    pub errors: usize,
+    pub warnings: usize,
+    /// One line per finding, e.g. `12: warning: ...`
+    pub lines: Vec<String>,
+}
+
+impl FileFindings {
+    pub fn is_clean(&self) -> bool {
+        self.errors + self.warnings == 0 && self.lines.is_empty()
+    }
+
+    /// Findings of a one-file `DocValidator::validate_file` report; info issues
+    /// are listed but not counted
+    pub fn from_docs(report: &ValidationReport) -> Self {
+        let mut findings = FileFindings::default();
+        for issue in report.file_issues.iter().flat_map(|(_, issues)| issues) {
+            let severity = issue.severity(&report.severities);
+            let label = match severity {
+                Severity::Error => {
+                    findings.errors += 1;
+                    "error"
+                }
+                Severity::Warning => {
+                    findings.warnings += 1;
+                    "warning"
+                }
+                Severity::Info => "info",
+            };
+            findings.lines.push(finding_line(issue.line(), &format!("{}: {}", label, issue.message())));
+        }
+        findings
+    }
+
+    /// Findings of a one-file `RemovalSession::process_file` report: every
+    /// removable call, rewrite, and ambiguity counts as a warning, as each fails
+    /// `--check`; a file that could not be read is an error
+    pub fn from_removal(report: &RemovalReport) -> Self {
+        let mut findings = FileFindings::default();
+        for (_, file_report) in &report.file_reports {
+            for removal in &file_report.removals {
+                let call = removal.text.lines().next().unwrap_or_default().trim();
+                findings.lines.push(finding_line(Some(removal.start_line), &format!("would remove `{}`", call)));
+            }
+            for rewrite in &file_report.rewrites {
+                findings.lines.push(finding_line(Some(rewrite.line), &format!("would rewrite `{}`", rewrite.text.trim())));
+            }
+            for warning in &file_report.warnings {
+                findings.lines.push(finding_line(Some(warning.line_number), &format!("warning: {}", warning.message)));
+            }
+            findings.warnings += file_report.removals.len() + file_report.rewrites.len() + file_report.warnings.len();
+        }
+        for (_, error) in &report.failures {
+            findings.errors += 1;
+            findings.lines.push(format!("error: could not be checked: {}", error));
+        }
+        findings
+    }
+
+    /// `2 errors, 1 warning`, or `ok`
+    fn summary(&self) -> String {
+        if self.errors + self.warnings == 0 {
+            return "ok".to_string();
+        }
+        counts(self.errors, self.warnings)
+    }
+}
+
+fn finding_line(line: Option<usize>, text: &str) -> String {
+    match line {
+        Some(line) => format!("{}: {}", line, text),
+        None => text.to_string(),
+    }
+}
+
+fn counts(errors: usize, warnings: usize) -> String {
+    format!(
+        "{} error{}, {} warning{}",
+        errors, if errors == 1 { "" } else { "s" },
+        warnings, if warnings == 1 { "" } else { "s" },
+    )
+}
+
+/// Latest findings of every file checked in a watch session
+#[derive(Debug, Default)]
+pub struct Tally {
+    files: BTreeMap<PathBuf, FileFindings>,
+}
+
+impl Tally {
+    /// Record the findings of a fresh check of `path`
+    pub fn update(&mut self, path: PathBuf, findings: FileFindings) {
+        self.files.insert(path, findings);
+    }
+
+    /// Forget `path` and, if it was a directory, every file under it
+    pub fn remove(&mut self, path: &Path) {
+        self.files.retain(|file, _| !file.starts_with(path));
+    }
+
+    pub fn files(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn errors(&self) -> usize {
+        self.files.values().map(|findings| findings.errors).sum()
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.files.values().map(|findings| findings.warnings).sum()
+    }
+
+    /// The screen shown after a pass: a header with the live totals, what this
+    /// pass checked (`changes`, or every file on the first pass), then the
+    /// findings of every file that has any
+    pub fn render(&self, title: &str, time: &str, changes: Option<&Changes>) -> String {
+        let flagged: Vec<(&PathBuf, &FileFindings)> = self.files.iter()
+            .filter(|(_, findings)| !findings.is_clean())
+            .collect();
+        let mut out = format!(
+            "[{}] {}: {} in {} of {} file{}\n",
+            time, title, counts(self.errors(), self.warnings()), flagged.len(), self.files(),
+            if self.files() == 1 { "" } else { "s" },
+        );
+        match changes {
+            None => out.push_str(&format!("Checked {} file(s)\n", self.files())),
+            Some(changes) => {
+                for path in &changes.changed {
+                    let summary = self.files.get(path).map_or_else(|| "ok".to_string(), FileFindings::summary);
+                    out.push_str(&format!("Checked {}: {}\n", path.display(), summary));
+                }
+                for path in &changes.removed {
+                    out.push_str(&format!("Removed {}\n", path.display()));
+                }
+            }
+        }
+        for (path, findings) in flagged {
+            out.push_str(&format!("\n{}\n", path.display()));
+            for line in &findings.lines {
+                out.push_str(&format!("  {}\n", line));
+            }
+        }
+        out.push_str("\nWatching for changes (Ctrl-C to stop)\n");
+        out
+    }
+}
+
+/// How `run` waits for and shows changes
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    pub debounce: Duration,
+    /// Clear the screen before each redraw (for a terminal)
+    pub clear_screen: bool,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self { debounce: DEFAULT_DEBOUNCE, clear_screen: false }
+    }
+}
+
+/// Check every file under `paths` that `accepts` takes, then re-check each one
+/// that changes, writing a screen to `out` after every pass; returns when the
+/// watcher stops
+pub fn run(
+    title: &str,
+    paths: &[PathBuf],
+    options: &WatchOptions,
+    accepts: impl Fn(&Path) -> bool,
+    mut check: impl FnMut(&Path) -> FileFindings,
+    out: &mut dyn Write,
+) -> Result<()> {
+    // Started first, so nothing saved during the first pass is missed
+    let watcher = FileWatcher::new(paths, options.debounce)?;
+    let mut tally = Tally::default();
+    for path in paths {
+        let files: Vec<PathBuf> = WalkDir::new(path).sort_by_file_name().into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| clean(entry.path()))
+            .filter(|file| accepts(file))
+            .collect();
+        for file in files {
+            let findings = check(&file);
+            tally.update(file, findings);
+        }
+    }
+    show(out, options, &tally.render(title, &now(), None))?;
+
+    while let Some(mut changes) = watcher.next_changes() {
+        changes.changed.retain(|path| accepts(path));
+        changes.removed.retain(|path| tally.files.keys().any(|file| file.starts_with(path)));
+        if changes.is_empty() {
+            continue;
+        }
+        for path in &changes.removed {
+            tally.remove(path);
+        }
+        for path in &changes.changed {
+            let findings = check(path);
+            tally.update(path.clone(), findings);
+        }
+        show(out, options, &tally.render(title, &now(), Some(&changes)))?;
+    }
+    Ok(())
+}
+
+fn now() -> String {
+    chrono::Local::now().format("%H:%M:%S").to_string()
+}
+
+fn show(out: &mut dyn Write, options: &WatchOptions, screen: &str) -> Result<()> {
+    if options.clear_screen {
+        write!(out, "{}", CLEAR_SCREEN)?;
+    } else {
+        writeln!(out)?;
+    }
+    write!(out, "{}", screen)?;
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, ModifyKind, RemoveKind, RenameMode};
+    use std::fs;
+
+    fn event(kind: EventKind, path: &str) -> notify::Result<Event> {
+        Ok(Event::new(kind).add_path(PathBuf::from(path)))
+    }
+
+    #[test]
+    fn test_batches_end_after_a_quiet_period() {
+        let (sender, events) = mpsc::channel();
+        // An editor's save: temp file, rename over the original, then reads
+        sender.send(event(EventKind::Create(CreateKind::File), "src/.lib.rs.swp")).unwrap();
+        sender.send(event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), "src/lib.rs")).unwrap();
+        sender.send(event(EventKind::Access(AccessKind::Open(AccessMode::Read)), "src/main.rs")).unwrap();
+        sender.send(event(EventKind::Access(AccessKind::Close(AccessMode::Write)), "src/lib.rs")).unwrap();
+        let batch = collect_batch(&events, Some(Duration::from_millis(10)), Duration::from_millis(10)).unwrap();
+        assert_eq!(batch.into_iter().collect::<Vec<_>>(), [PathBuf::from("src/.lib.rs.swp"), PathBuf::from("src/lib.rs")]);
+
+        // Reads alone are not a batch
+        sender.send(event(EventKind::Access(AccessKind::Read), "src/lib.rs")).unwrap();
+        assert_eq!(collect_batch(&events, Some(Duration::from_millis(10)), Duration::from_millis(10)), None);
+
+        sender.send(event(EventKind::Remove(RemoveKind::File), "src/old.rs")).unwrap();
+        drop(sender);
+        let batch = collect_batch(&events, None, Duration::from_millis(10)).unwrap();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(collect_batch(&events, None, Duration::from_millis(10)), None);
+    }
+
+    #[test]
+    fn test_watcher_reports_writes_renames_and_deletions() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().canonicalize().unwrap();
+        fs::write(dir.join("a.rs"), "fn a() {}\n").unwrap();
+        let watcher = FileWatcher::new(std::slice::from_ref(&dir), Duration::from_millis(100)).unwrap();
+        let wait = Some(Duration::from_secs(5));
+        // Relative to the working directory when it is a parent (other tests move it)
+        let absolute = |paths: Vec<PathBuf>| paths.iter().map(|path| watcher.root.join(path)).collect::<Vec<_>>();
+
+        fs::write(dir.join("a.rs"), "fn a() { }\n").unwrap();
+        let changes = watcher.next_batch(wait).unwrap();
+        assert_eq!(absolute(changes.changed), [dir.join("a.rs")]);
+
+        fs::rename(dir.join("a.rs"), dir.join("b.rs")).unwrap();
+        let changes = watcher.next_batch(wait).unwrap();
+        assert_eq!((absolute(changes.changed), absolute(changes.removed)), (vec![dir.join("b.rs")], vec![dir.join("a.rs")]));
+
+        fs::remove_file(dir.join("b.rs")).unwrap();
+        assert_eq!(absolute(watcher.next_batch(wait).unwrap().removed), [dir.join("b.rs")]);
+    }
+
+    #[test]
+    fn test_tally_keeps_live_totals() {
+        let mut tally = Tally::default();
+        let warned = FileFindings { errors: 0, warnings: 2, lines: vec!["3: warning: a".into(), "9: warning: b".into()] };
+        tally.update(PathBuf::from("src/a.rs"), warned);
+        tally.update(PathBuf::from("src/b.rs"), FileFindings::default());
+        tally.update(PathBuf::from("src/sub/c.rs"), FileFindings { errors: 1, warnings: 0, lines: vec!["error: c".into()] });
+        assert_eq!((tally.errors(), tally.warnings()), (1, 2));
+
+        // Fixing a file and deleting a directory both lower the totals
+        tally.update(PathBuf::from("src/a.rs"), FileFindings::default());
+        tally.remove(Path::new("src/sub"));
+        assert_eq!((tally.files(), tally.errors(), tally.warnings()), (2, 0, 0));
+
+        tally.update(PathBuf::from("src/b.rs"), FileFindings { errors: 0, warnings: 1, lines: vec!["1: warning: b".into()] });
+        let changes = Changes { changed: vec![PathBuf::from("src/b.rs")], removed: vec![PathBuf::from("src/sub")] };
+        assert_eq!(tally.render("validate-docs", "12:00:00", Some(&changes)),
+            "[12:00:00] validate-docs: 0 errors, 1 warning in 1 of 2 files\n\
+             Checked src/b.rs: 0 errors, 1 warning\n\
+             Removed src/sub\n\
+             \n\
+             src/b.rs\n\
+             \x20 1: warning: b\n\
+             \n\
+             Watching for changes (Ctrl-C to stop)\n");
+    }
+
+    #[test]
+    fn test_clean_drops_current_dir_components() {
+        assert_eq!(clean(Path::new("./src/./lib.rs")), PathBuf::from("src/lib.rs"));
+        assert_eq!(clean(Path::new(".")), PathBuf::new());
+    }
+}
@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+fn load(path: &str) -> String {
+    let content = read(path);
+
+    parse(&content)
+}
+
+fn between(a: u32, b: u32) -> u32 {
+    let x = a + b;
+
+    x * 2
+}
+
+fn tail(items: &[u32]) -> usize {
+    let n = items.len();
+}
+
+fn untouched() {
+
+
+    let spaced = 1;
+
+
+
+    spaced
+}
+
+fn kept_runs() {
+    let a = 1;
+
+
+    let b = 2;
+}
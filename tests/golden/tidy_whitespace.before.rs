@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+
+fn load(path: &str) -> String {
+    debug!("loading {}", path);
+
+    let content = read(path);
+    debug!("read {} bytes", content.len());
+
+    parse(&content)
+}
+
+fn between(a: u32, b: u32) -> u32 {
+    let x = a + b;
+
+    debug!("x = {}", x);
+
+    x * 2
+}
+
+fn tail(items: &[u32]) -> usize {
+    let n = items.len();
+
+    debug!(
+        "n = {}",
+        n
+    );
+}
+
+fn untouched() {
+
+
+    let spaced = 1;
+
+
+
+    spaced
+}
+
+fn kept_runs() {
+    let a = 1;
+
+
+    debug!("a");
+    let b = 2;
+}
@@ -1,5 +1,10 @@
 use autodebugger::AutoDebugger;
 
+// Not every feature set runs every test that uses these
+#[allow(dead_code)]
+#[path = "../src/test_repo.rs"]
+mod test_repo;
+
 #[test]
 fn test_basic_commands() -> anyhow::Result<()> {
     let debugger = AutoDebugger::new();
@@ -19,4 +24,515 @@ fn test_basic_commands() -> anyhow::Result<()> {
     assert!(result.success);
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(unix)]
+#[test]
+fn test_pre_commit_hook_blocks_debug_calls() -> anyhow::Result<()> {
+    use std::process::Command;
+    use test_repo::{git, git_command};
+
+    let repo = tempfile::tempdir()?;
+    git(repo.path(), &["init", "-q"]);
+
+    let install = Command::new(env!("CARGO_BIN_EXE_autodebugger"))
+        .args(["install-hooks", "--pre-commit", "debug_macros", "--pre-push", "fmt", "--path"])
+        .arg(repo.path())
+        .output()?;
+    assert!(install.status.success(), "{}", String::from_utf8_lossy(&install.stderr));
+    assert!(repo.path().join(".git/hooks/pre-commit").exists());
+
+    std::fs::create_dir(repo.path().join("src"))?;
+    std::fs::write(repo.path().join("src/lib.rs"), "fn f() {\n    debug!(\"x\");\n}\n")?;
+    git(repo.path(), &["add", "."]);
+    let blocked = git_command(repo.path()).args(["commit", "-qm", "debug"]).output()?;
+    assert!(!blocked.status.success(), "commit with debug! went through");
+    assert!(git_command(repo.path()).args(["rev-parse", "--verify", "-q", "HEAD"]).output()?.stdout.is_empty());
+
+    std::fs::write(repo.path().join("src/lib.rs"), "fn f() {}\n")?;
+    git(repo.path(), &["add", "."]);
+    git(repo.path(), &["commit", "-qm", "clean"]);
+
+    let uninstall = Command::new(env!("CARGO_BIN_EXE_autodebugger"))
+        .args(["uninstall-hooks", "--path"])
+        .arg(repo.path())
+        .output()?;
+    assert!(uninstall.status.success());
+    assert!(!repo.path().join(".git/hooks/pre-commit").exists());
+    Ok(())
+}
+
+#[test]
+fn test_mcp_output_keeps_stdout_clean() -> anyhow::Result<()> {
+    use std::process::Command;
+
+    let project = tempfile::tempdir()?;
+    std::fs::create_dir(project.path().join("src"))?;
+    std::fs::write(project.path().join("src/lib.rs"), "//! Small module\n\npub fn f() {}\n")?;
+
+    let run = |output: &str| {
+        Command::new(env!("CARGO_BIN_EXE_autodebugger"))
+            .current_dir(project.path())
+            .args(["validate-docs", "src", "--log-output", output])
+            .output()
+            .unwrap()
+    };
+
+    let normal = run("stdout");
+    assert!(normal.status.success(), "{}", String::from_utf8_lossy(&normal.stderr));
+    assert!(String::from_utf8_lossy(&normal.stdout).contains("Validated 1 files"));
+
+    // Logs and the summary both move to stderr
+    let mcp = run("mcp");
+    assert!(mcp.status.success(), "{}", String::from_utf8_lossy(&mcp.stderr));
+    assert_eq!(String::from_utf8_lossy(&mcp.stdout), "");
+    let stderr = String::from_utf8_lossy(&mcp.stderr);
+    assert!(stderr.contains("Autodebugger starting"), "{}", stderr);
+    assert!(stderr.contains("Validated 1 files"), "{}", stderr);
+    Ok(())
+}
+
+#[test]
+fn test_mcp_serve_answers_canned_requests() -> anyhow::Result<()> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let project = tempfile::tempdir()?;
+    std::fs::create_dir(project.path().join("src"))?;
+    std::fs::write(project.path().join("src/lib.rs"), "//! Small module\n\npub fn f() {\n    debug!(\"x\");\n}\n")?;
+
+    let requests = [
+        r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-06-18","capabilities":{},"clientInfo":{"name":"test","version":"0"}}}"#,
+        r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#,
+        r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"run_command","arguments":{"command":"echo hi"}}}"#,
+        r#"{"jsonrpc":"2.0","id":4,"method":"tools/call","params":{"name":"remove_debug_check","arguments":{"paths":["src"]},"_meta":{"progressToken":7}}}"#,
+        r#"{"jsonrpc":"2.0","id":5,"method":"tools/call","params":{"name":"validate_docs","arguments":{"paths":["src"]}}}"#,
+        r#"{"jsonrpc":"2.0","id":6,"method":"tools/call","params":{"name":"worktree_status","arguments":{"path":"missing"}}}"#,
+    ];
+    let mut child = Command::new(env!("CARGO_BIN_EXE_autodebugger"))
+        .current_dir(project.path())
+        .arg("mcp-serve")
+        .env_remove("XDG_CONFIG_HOME")
+        .env("HOME", project.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    for request in requests {
+        writeln!(stdin, "{}", request)?;
+    }
+    // End of input ends the server
+    drop(stdin);
+    let output = child.wait_with_output()?;
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Autodebugger starting"));
+
+    // Every stdout line is a JSON-RPC message; no logs
+    let messages: Vec<serde_json::Value> = String::from_utf8(output.stdout)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    let response = |id: u64| messages.iter().find(|message| message["id"] == id).unwrap();
+    assert_eq!(response(1)["result"]["protocolVersion"], "2025-06-18");
+    assert_eq!(response(2)["result"]["tools"].as_array().unwrap().len(), 5);
+    assert_eq!(response(3)["result"]["structuredContent"]["stdout"].as_str().unwrap().trim(), "hi");
+
+    let progress: Vec<&serde_json::Value> = messages.iter()
+        .filter(|message| message["method"] == "notifications/progress")
+        .collect();
+    assert!(progress.len() >= 2);
+    assert!(progress.iter().all(|message| message["params"]["progressToken"] == 7));
+    let check = &response(4)["result"]["structuredContent"];
+    assert_eq!(check["passed"], false);
+    assert_eq!(check["report"]["files"][0]["removals"][0]["start_line"], 4);
+
+    assert_eq!(response(5)["result"]["structuredContent"]["passed"], true);
+    assert_eq!(response(6)["result"]["isError"], true);
+    assert_eq!(messages.len(), 6 + progress.len());
+    Ok(())
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn test_serve_answers_status_and_diff() -> anyhow::Result<()> {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::process::{Command, Stdio};
+    use std::time::{Duration, Instant};
+
+    let workspace = tempfile::tempdir()?;
+    let repo = workspace.path().join("main");
+    test_repo::init_repo(&repo, &[("a.txt", "one\n")]);
+    std::fs::write(repo.join("a.txt"), "two\n")?;
+    // No cargo checks, so `ci` is quick
+    std::fs::write(workspace.path().join("config.yaml"), "ci:\n  enabled_checks: []\n")?;
+    let autodebugger = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_autodebugger"))
+            .current_dir(workspace.path())
+            .args(["--quiet", "--no-log-file"])
+            .args(args)
+            .env_remove("XDG_CONFIG_HOME")
+            .env("HOME", workspace.path())
+            .output()
+            .unwrap()
+    };
+
+    // A port free a moment ago
+    let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_autodebugger"))
+        .current_dir(workspace.path())
+        .args(["serve", "--port", &port.to_string(), "--path", "main"])
+        .env_remove("XDG_CONFIG_HOME")
+        .env("HOME", workspace.path())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let get = |route: &str| -> anyhow::Result<(String, serde_json::Value)> {
+        let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", route)?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        let (head, body) = response.split_once("\r\n\r\n").unwrap_or_default();
+        let status = head.lines().next().unwrap_or_default().to_string();
+        Ok((status, serde_json::from_str(body)?))
+    };
+    let fetched = (|| -> anyhow::Result<_> {
+        let started = Instant::now();
+        let health = loop {
+            match get("/healthz") {
+                Ok(health) => break health,
+                Err(_) if started.elapsed() < Duration::from_secs(20) => std::thread::sleep(Duration::from_millis(50)),
+                Err(error) => return Err(error),
+            }
+        };
+        // `/ci` only serves what `autodebugger ci` cached
+        let uncached = get("/ci/main")?;
+        let ci = autodebugger(&["ci", "main", "--path", "main"]);
+        assert!(ci.status.code().is_some_and(|code| code <= 1), "{}", String::from_utf8_lossy(&ci.stderr));
+        let cached = get("/ci/main")?;
+        Ok((health, get("/status")?, get("/diff/main")?, get("/diff/nope")?, uncached, cached))
+    })();
+    child.kill()?;
+    child.wait()?;
+    let (health, status, diff, missing, uncached, cached) = fetched?;
+
+    assert_eq!(health.1["status"], "ok");
+    assert!(status.0.contains("200"), "{}", status.0);
+    assert_eq!(status.1["worktrees"]["main"]["files_changed"], 1);
+    assert_eq!(diff.1["worktree"], "main");
+    assert_eq!(diff.1["files"][0]["path"], "a.txt");
+    assert!(missing.0.contains("404"), "{}", missing.0);
+    assert_eq!(missing.1["error"]["message"], "Worktree not found: nope");
+    assert!(uncached.0.contains("404"), "{}", uncached.0);
+    assert_eq!(uncached.1["error"]["message"], "No cached CI results for main");
+    assert!(uncached.1["error"]["hint"].as_str().unwrap().contains("autodebugger ci main"));
+    assert!(cached.0.contains("200"), "{} {}", cached.0, cached.1);
+    assert_eq!(cached.1["cached"], true);
+    assert_eq!(cached.1["worktree"], "main");
+    Ok(())
+}
+
+#[test]
+fn test_task_runs_steps_and_exits_with_the_failing_code() -> anyhow::Result<()> {
+    use std::process::Command;
+
+    let project = tempfile::tempdir()?;
+    std::fs::create_dir(project.path().join("sub"))?;
+    std::fs::write(project.path().join("config.yaml"), "\
+tasks:
+  check:
+    - echo first
+    - {run: 'echo \"$NAME\" > out.txt', dir: sub, env: {NAME: second}}
+    - exit 3
+    - echo never
+")?;
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_autodebugger"))
+            .current_dir(project.path())
+            .args(["--quiet", "--no-log-file", "task"])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&["check"]);
+    assert_eq!(output.status.code(), Some(3));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Task check: 2 of 4 steps passed"), "{}", stdout);
+    assert!(stdout.contains("skipped") && !stdout.contains("\nnever"), "{}", stdout);
+    assert_eq!(std::fs::read_to_string(project.path().join("sub/out.txt"))?, "second\n");
+
+    let kept_going = run(&["check", "--keep-going"]);
+    assert_eq!(kept_going.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&kept_going.stdout).contains("3 of 4 steps passed"));
+
+    let unknown = run(&["lint"]);
+    assert_eq!(unknown.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&unknown.stderr).contains("available tasks: check"));
+    Ok(())
+}
+
+#[test]
+fn test_clean_reports_every_step() -> anyhow::Result<()> {
+    use std::process::Command;
+
+    let project = tempfile::tempdir()?;
+    std::fs::create_dir(project.path().join("src"))?;
+    std::fs::write(project.path().join("src/lib.rs"), "//! Small module\n\npub fn f() {\n    debug!(\"x\");\n}\n")?;
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_autodebugger"))
+            .current_dir(project.path())
+            .args(["--quiet", "--no-log-file", "clean", "src", "--skip", "fmt"])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    // A dry run finds the call, fails, and keeps going to validate the docs
+    let dry = run(&["--dry-run", "--json"]);
+    assert_eq!(dry.status.code(), Some(1));
+    let report: serde_json::Value = serde_json::from_slice(&dry.stdout)?;
+    assert_eq!((report["passed"].clone(), report["debug_calls"].clone()), (false.into(), 1.into()));
+    assert_eq!(report["steps"][1]["step"], "validate_docs");
+    assert!(std::fs::read_to_string(project.path().join("src/lib.rs"))?.contains("debug!"));
+
+    let applied = run(&[]);
+    assert!(applied.status.success(), "{}", String::from_utf8_lossy(&applied.stderr));
+    assert!(String::from_utf8_lossy(&applied.stdout).contains("Clean passed: 2 step(s)"));
+    assert!(!std::fs::read_to_string(project.path().join("src/lib.rs"))?.contains("debug!"));
+    Ok(())
+}
+
+#[test]
+fn test_quiet_and_no_log_file() -> anyhow::Result<()> {
+    use std::process::Command;
+
+    let project = tempfile::tempdir()?;
+    std::fs::create_dir(project.path().join("src"))?;
+    std::fs::write(project.path().join("src/lib.rs"), "//! Small module\n\npub fn f() {}\n")?;
+    std::fs::write(project.path().join("config.yaml"), "logging:\n  file:\n    log_directory: logs\n")?;
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_autodebugger"))
+            .current_dir(project.path())
+            .args(["validate-docs", "src"])
+            .args(args)
+            .env_remove("RUST_LOG")
+            .output()
+            .unwrap()
+    };
+
+    // Reports still print; the info lines and the log directory are gone
+    let quiet = run(&["--quiet", "--no-log-file"]);
+    assert!(quiet.status.success(), "{}", String::from_utf8_lossy(&quiet.stderr));
+    let stdout = String::from_utf8_lossy(&quiet.stdout);
+    assert!(stdout.contains("Validated 1 files"), "{}", stdout);
+    assert!(!stdout.contains("Autodebugger starting"), "{}", stdout);
+    assert!(!project.path().join("logs").exists());
+
+    let normal = run(&[]);
+    assert!(normal.status.success(), "{}", String::from_utf8_lossy(&normal.stderr));
+    assert!(String::from_utf8_lossy(&normal.stdout).contains("Autodebugger starting"));
+    assert!(project.path().join("logs").exists());
+    Ok(())
+}
+
+#[test]
+fn test_logs_lists_prints_and_cleans() -> anyhow::Result<()> {
+    use std::process::Command;
+
+    let project = tempfile::tempdir()?;
+    std::fs::write(project.path().join("config.yaml"), "logging:\n  file:\n    log_directory: logs\n")?;
+    std::fs::create_dir_all(project.path().join("logs/timestamped"))?;
+    std::fs::write(project.path().join("logs/timestamped/app_20250101_080000.log"), "first run\n")?;
+    std::fs::write(project.path().join("logs/timestamped/app_20250102_080000.log"), "second run\nlast line\n")?;
+
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_autodebugger"))
+            .current_dir(project.path())
+            .arg("logs")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // Reading the logs doesn't add a file of its own
+    let listing: serde_json::Value = serde_json::from_str(&run(&["--list", "--json"]))?;
+    assert_eq!(listing.as_array().map(Vec::len), Some(2));
+    assert_eq!(listing[0]["timestamp"], "20250102_080000");
+    assert_eq!(run(&[]), "second run\nlast line\n");
+    assert_eq!(run(&["--tail", "-n", "1"]), "last line\n");
+    assert_eq!(run(&["--show", "1"]), "first run\n");
+    assert_eq!(run(&["--show", "20250101"]), "first run\n");
+
+    assert!(run(&["clean", "--keep", "1"]).contains("app_20250101_080000.log"));
+    assert!(!project.path().join("logs/timestamped/app_20250101_080000.log").exists());
+    assert!(project.path().join("logs/timestamped/app_20250102_080000.log").exists());
+    Ok(())
+}
+
+#[test]
+fn test_exit_codes_and_error_envelope() -> anyhow::Result<()> {
+    use std::process::Command;
+
+    let project = tempfile::tempdir()?;
+    std::fs::create_dir(project.path().join("src"))?;
+    // Complex enough to need docs, and without any
+    let module: String = (0..30).map(|i| format!("pub fn f{}() {{}}\n", i)).collect();
+    std::fs::write(project.path().join("src/lib.rs"), module)?;
+    std::fs::write(project.path().join("strict.yaml"), "validate_docs:\n  complexity_threshold: 10\n")?;
+    std::fs::write(project.path().join("bad.yaml"), "monitor:\n  concurrency: 0\n")?;
+    std::fs::write(project.path().join("no-paths.yaml"), "remove_debug:\n  default_paths: [missing]\n")?;
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_autodebugger"))
+            .current_dir(project.path())
+            .args(["--error-format", "json"])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    let envelope = |output: &std::process::Output| -> serde_json::Value {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let line = stderr.lines().last().unwrap_or_default();
+        serde_json::from_str(line).unwrap_or_else(|e| panic!("{}: {}", e, stderr))
+    };
+
+    let failed_check = run(&["-c", "strict.yaml", "validate-docs", "src", "--strict"]);
+    assert_eq!(failed_check.status.code(), Some(1));
+    assert_eq!(envelope(&failed_check)["error"]["kind"], "check_failed");
+
+    let bad_config = run(&["-c", "bad.yaml", "validate-docs", "src"]);
+    assert_eq!(bad_config.status.code(), Some(2));
+    let error = envelope(&bad_config);
+    assert_eq!(error["error"]["kind"], "config");
+    assert!(error["error"]["message"].as_str().unwrap().contains("concurrency"), "{}", error);
+
+    let missing_path = run(&["status", "--path", "no-such-workspace"]);
+    assert_eq!(missing_path.status.code(), Some(3));
+    let error = envelope(&missing_path);
+    assert_eq!(error["error"]["kind"], "environment");
+    assert!(error["error"]["message"].as_str().unwrap().contains("no-such-workspace"), "{}", error);
+
+    let usage = run(&["status", "--no-such-flag"]);
+    assert_eq!(usage.status.code(), Some(2));
+    assert_eq!(envelope(&usage)["error"]["kind"], "usage");
+
+    // None of the default paths exist, so there is nothing to watch
+    let nothing = run(&["-c", "no-paths.yaml", "remove-debug", "--check", "--watch"]);
+    assert_eq!(nothing.status.code(), Some(2));
+    let error = envelope(&nothing);
+    assert_eq!(error["error"]["kind"], "usage");
+    assert_eq!(error["error"]["message"], "No paths to watch");
+    Ok(())
+}
+
+#[test]
+fn test_explicit_config_and_overrides() -> anyhow::Result<()> {
+    use std::process::Command;
+
+    let project = tempfile::tempdir()?;
+    std::fs::create_dir(project.path().join("src"))?;
+    let module: String = (0..30).map(|i| format!("pub fn f{}() {{}}\n", i)).collect();
+    std::fs::write(project.path().join("src/lib.rs"), module)?;
+    let config = project.path().join("ci-config.yaml");
+    std::fs::write(&config, "validate_docs:\n  complexity_threshold: 10\n")?;
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_autodebugger"))
+            .current_dir(project.path())
+            .args(["validate-docs", "src", "--strict"])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    // Without a config the module is simple; with it, complex and undocumented
+    assert!(run(&[]).status.success());
+    let strict = run(&["--config", "ci-config.yaml"]);
+    assert!(!strict.status.success(), "{}", String::from_utf8_lossy(&strict.stdout));
+    // The command-line option wins over the file
+    let overridden = run(&["-c", "ci-config.yaml", "--complexity-threshold", "100"]);
+    assert!(overridden.status.success(), "{}", String::from_utf8_lossy(&overridden.stdout));
+
+    // A missing explicit file is an error naming it, not a silent fallback to defaults
+    let missing = run(&["--config", "missing.yaml"]);
+    assert!(!missing.status.success());
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("missing.yaml"));
+    Ok(())
+}
+
+#[test]
+fn test_env_overrides_reach_the_verbosity_layer() -> anyhow::Result<()> {
+    use std::process::Command;
+
+    let project = tempfile::tempdir()?;
+    let run = |env: &[(&str, &str)]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_autodebugger"))
+            .current_dir(project.path())
+            .args(["--no-log-file", "run", "echo", "hi"])
+            .env_remove("RUST_LOG")
+            .env_remove("XDG_CONFIG_HOME")
+            .env("HOME", project.path())
+            .envs(env.iter().copied())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr))
+    };
+
+    assert!(!run(&[]).contains("threshold exceeded"));
+    // "Autodebugger starting" and "Running command" cross a threshold of 1
+    let output = run(&[
+        ("AUTODEBUGGER__VERBOSITY__INFO_THRESHOLD", "1"),
+        ("AUTODEBUGGER__VERBOSITY__EMIT_LIVE_WARNING", "true"),
+    ]);
+    assert!(output.contains("Log verbosity threshold exceeded: 2 events (threshold: 1 for INFO level)"), "{}", output);
+    Ok(())
+}
+
+#[test]
+fn test_config_init_show_and_validate() -> anyhow::Result<()> {
+    use std::process::Command;
+
+    let project = tempfile::tempdir()?;
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_autodebugger"))
+            .current_dir(project.path())
+            .arg("config")
+            .args(args)
+            .env_remove("XDG_CONFIG_HOME")
+            .env("HOME", project.path())
+            .output()
+            .unwrap()
+    };
+
+    // init writes a file that validates, and won't overwrite it without --force
+    assert!(run(&["init"]).status.success());
+    assert!(project.path().join("config.yaml").exists());
+    assert!(run(&["validate"]).status.success());
+    let again = run(&["init"]);
+    assert!(!again.status.success());
+    assert!(String::from_utf8_lossy(&again.stderr).contains("--force"));
+    assert!(run(&["init", "--force"]).status.success());
+
+    // A typo fails validation with a suggestion
+    std::fs::write(project.path().join("config.yaml"), "validate_docs:\n  complexity_treshold: 80\n")?;
+    let invalid = run(&["validate"]);
+    assert!(!invalid.status.success());
+    assert!(String::from_utf8_lossy(&invalid.stdout).contains("did you mean `complexity_threshold`?"));
+
+    // show prints only YAML on stdout, naming the source of changed values
+    std::fs::write(project.path().join("config.yaml"), "verbosity:\n  info_threshold: 75\n")?;
+    let show = run(&["show"]);
+    assert!(show.status.success());
+    let yaml = String::from_utf8_lossy(&show.stdout);
+    assert!(yaml.contains("config.yaml\n  info_threshold: 75\n"), "{}", yaml);
+    let config: autodebugger::Config = serde_yaml::from_str(&yaml)?;
+    assert_eq!(config.verbosity.info_threshold, 75);
+    Ok(())
+}